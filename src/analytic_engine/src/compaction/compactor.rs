@@ -28,7 +28,8 @@ use crate::{
         },
         CompactionInputFiles, CompactionTask, ExpiredFiles,
     },
-    instance::flush_compaction::{AllocFileId, Other, Result, StoreVersionEdit},
+    hook::{EngineHooks, SstChangeKind},
+    instance::flush_compaction::{AllocFileId, Other, RejectedByHook, Result, StoreVersionEdit},
     manifest::{
         meta_edit::{MetaEdit, MetaEditRequest, MetaUpdate, VersionEditMeta},
         ManifestRef,
@@ -46,11 +47,17 @@ pub(crate) struct Compactor {
 
     /// Manifest (or meta) stores meta data of the engine instance.
     manifest: ManifestRef,
+
+    hooks: EngineHooks,
 }
 
 impl Compactor {
-    pub fn new(runner: CompactionRunnerPtr, manifest: ManifestRef) -> Self {
-        Self { runner, manifest }
+    pub fn new(runner: CompactionRunnerPtr, manifest: ManifestRef, hooks: EngineHooks) -> Self {
+        Self {
+            runner,
+            manifest,
+            hooks,
+        }
     }
 
     pub async fn compact_table(
@@ -75,6 +82,13 @@ impl Compactor {
             return Ok(());
         }
 
+        self.hooks
+            .before_compaction(table_data)
+            .await
+            .context(RejectedByHook {
+                table: &table_data.name,
+            })?;
+
         let inputs = task.inputs();
         let mut edit_meta = VersionEditMeta {
             space_id: table_data.space_id,
@@ -85,6 +99,7 @@ impl Compactor {
             files_to_delete: vec![],
             mems_to_remove: vec![],
             max_file_id: 0,
+            delete_before: task.delete_before(),
         };
 
         for files in task.expired() {
@@ -124,6 +139,9 @@ impl Compactor {
             .apply_edit(edit_req)
             .await
             .context(StoreVersionEdit)?;
+        self.hooks
+            .after_sst_change(table_data, SstChangeKind::Compaction, &edit_meta)
+            .await;
 
         Ok(())
     }
@@ -19,7 +19,7 @@
 
 use std::{collections::HashMap, fmt, str::FromStr, sync::Arc};
 
-use common_types::COMPACTION_STRATEGY;
+use common_types::{time::Timestamp, COMPACTION_STRATEGIES, COMPACTION_STRATEGY};
 use serde::{Deserialize, Serialize};
 use size_ext::ReadableSize;
 use snafu::{ensure, Backtrace, GenerateBacktrace, ResultExt, Snafu};
@@ -137,9 +137,9 @@ const MIN_THRESHOLD_KEY: &str = "compaction_min_threshold";
 const MAX_THRESHOLD_KEY: &str = "compaction_max_threshold";
 const MIN_SSTABLE_SIZE_KEY: &str = "compaction_min_sstable_size";
 const TIMESTAMP_RESOLUTION_KEY: &str = "compaction_timestamp_resolution";
-const DEFAULT_STRATEGY: &str = "default";
-const STC_STRATEGY: &str = "size_tiered";
-const TWC_STRATEGY: &str = "time_window";
+const DEFAULT_STRATEGY: &str = COMPACTION_STRATEGIES[0];
+const STC_STRATEGY: &str = COMPACTION_STRATEGIES[1];
+const TWC_STRATEGY: &str = COMPACTION_STRATEGIES[2];
 
 impl CompactionStrategy {
     pub(crate) fn parse_from(
@@ -338,6 +338,11 @@ pub struct ExpiredFiles {
 pub struct CompactionTask {
     inputs: Vec<CompactionInputFiles>,
     expired: Vec<ExpiredFiles>,
+    /// The delete bound to record into the version, see
+    /// [VersionEdit::delete_before].
+    ///
+    /// [VersionEdit::delete_before]: crate::table::version_edit::VersionEdit::delete_before
+    delete_before: Option<Timestamp>,
 }
 
 impl Drop for CompactionTask {
@@ -386,7 +391,7 @@ impl CompactionTask {
 
     #[inline]
     pub fn is_empty(&self) -> bool {
-        self.is_input_empty() && self.expired.is_empty()
+        self.is_input_empty() && self.expired.is_empty() && self.delete_before.is_none()
     }
 
     #[inline]
@@ -404,6 +409,11 @@ impl CompactionTask {
         &self.inputs
     }
 
+    #[inline]
+    pub fn delete_before(&self) -> Option<Timestamp> {
+        self.delete_before
+    }
+
     #[inline]
     pub fn contains_min_level(&self) -> bool {
         for input in &self.inputs {
@@ -419,6 +429,7 @@ impl CompactionTask {
 pub struct CompactionTaskBuilder {
     expired: Vec<ExpiredFiles>,
     inputs: Vec<CompactionInputFiles>,
+    delete_before: Option<Timestamp>,
 }
 
 impl CompactionTaskBuilder {
@@ -426,6 +437,7 @@ impl CompactionTaskBuilder {
         Self {
             expired,
             inputs: Vec::new(),
+            delete_before: None,
         }
     }
 
@@ -433,10 +445,15 @@ impl CompactionTaskBuilder {
         self.inputs.push(files);
     }
 
+    pub fn delete_before(&mut self, before: Timestamp) {
+        self.delete_before = Some(before);
+    }
+
     pub fn build(self) -> CompactionTask {
         let task = CompactionTask {
             expired: self.expired,
             inputs: self.inputs,
+            delete_before: self.delete_before,
         };
 
         task.mark_files_being_compacted(true);
@@ -465,6 +482,7 @@ impl fmt::Debug for CompactionTask {
                     .take(10)
                     .collect::<Vec<_>>(),
             )
+            .field("delete_before", &self.delete_before)
             .finish()
     }
 }
@@ -522,6 +540,8 @@ impl Drop for WaiterNotifier {
 pub struct TableCompactionRequest {
     pub table_data: TableDataRef,
     pub waiter: Option<oneshot::Sender<WaitResult<()>>>,
+    /// Delete the data before it instead of the normal compaction if set.
+    pub delete_before: Option<Timestamp>,
 }
 
 impl TableCompactionRequest {
@@ -530,6 +550,7 @@ impl TableCompactionRequest {
         let req = Self {
             table_data,
             waiter: Some(tx),
+            delete_before: None,
         };
 
         (req, rx)
@@ -539,8 +560,21 @@ impl TableCompactionRequest {
         TableCompactionRequest {
             table_data,
             waiter: None,
+            delete_before: None,
         }
     }
+
+    /// Request to delete the data before `before` (in the timestamp precision
+    /// of the table).
+    pub fn delete_before(
+        table_data: TableDataRef,
+        before: Timestamp,
+    ) -> (Self, oneshot::Receiver<WaitResult<()>>) {
+        let (mut req, rx) = Self::new(table_data);
+        req.delete_before = Some(before);
+
+        (req, rx)
+    }
 }
 
 #[cfg(test)]
@@ -23,7 +23,7 @@ use std::{
     time::Duration,
 };
 
-use common_types::time::Timestamp;
+use common_types::time::{Timestamp, TimestampPrecision};
 use logger::{debug, info};
 use macros::define_result;
 use snafu::Snafu;
@@ -47,10 +47,12 @@ define_result!(Error);
 
 #[derive(Clone)]
 pub struct PickerContext {
+    /// The segment duration scaled to the timestamp precision.
     pub segment_duration: Duration,
     /// The ttl of the data in sst.
     pub ttl: Option<Duration>,
     pub strategy: CompactionStrategy,
+    pub timestamp_precision: TimestampPrecision,
 }
 
 impl PickerContext {
@@ -141,7 +143,7 @@ impl CompactionPicker for CommonCompactionPicker {
         ctx: PickerContext,
         levels_controller: &mut LevelsController,
     ) -> Result<CompactionTask> {
-        let expire_time = ctx.ttl.map(Timestamp::expire_time);
+        let expire_time = ctx.ttl.map(|ttl| ctx.timestamp_precision.expire_time(ttl));
         let mut builder =
             CompactionTaskBuilder::with_expired(levels_controller.expired_ssts(expire_time));
 
@@ -823,6 +825,7 @@ mod tests {
             segment_duration: Duration::from_millis(1000),
             ttl: Some(Duration::from_secs(100000)),
             strategy: CompactionStrategy::Default,
+            timestamp_precision: TimestampPrecision::Millisecond,
         };
         let now = Timestamp::now();
         {
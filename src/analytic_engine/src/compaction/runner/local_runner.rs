@@ -18,8 +18,15 @@
 use std::{collections::HashMap, sync::Arc};
 
 use async_trait::async_trait;
-use common_types::projected_schema::{ProjectedSchema, RowProjectorBuilder};
+use common_types::{
+    projected_schema::{ProjectedSchema, RowProjectorBuilder},
+    time::{TimeRange, Timestamp},
+};
 use generic_error::BoxError;
+use object_store::{
+    rate_limit::{RateLimiter, StoreWithRateLimit},
+    ObjectStoreRef,
+};
 use runtime::Runtime;
 use snafu::ResultExt;
 use table_engine::predicate::Predicate;
@@ -31,11 +38,17 @@ use crate::{
     },
     row_iter::{
         self,
+        column_expire::ColumnExpireIterator,
         dedup::DedupIterator,
+        downsample::DownsampleIterator,
+        expire::ExpireFilterIterator,
         merge::{MergeBuilder, MergeConfig},
     },
     sst::{
-        factory::{ColumnStats, FactoryRef, ObjectStorePickerRef, ScanOptions, SstWriteOptions},
+        factory::{
+            ColumnStats, FactoryRef, ObjectStorePicker, ObjectStorePickerRef, ReadFrequency,
+            ScanOptions, SstWriteOptions,
+        },
         meta_data::{cache::MetaCacheRef, SstMetaData, SstMetaReader},
         writer::MetaData,
     },
@@ -50,7 +63,7 @@ pub struct LocalCompactionRunner {
     scan_options: ScanOptions,
     /// Sst factory
     sst_factory: FactoryRef,
-    /// Store picker for persisting sst
+    /// Store picker for persisting sst, limited by the compaction rate limits.
     store_picker: ObjectStorePickerRef,
     // TODO: maybe not needed in compaction
     sst_meta_cache: Option<MetaCacheRef>,
@@ -68,6 +81,21 @@ impl LocalCompactionRunner {
             background_read_parallelism: 1,
             max_record_batches_in_flight: MAX_RECORD_BATCHES_IN_FLIGHT_WHEN_COMPACTION_READ,
             num_streams_to_prefetch: config.num_streams_to_prefetch,
+            get_range_coalesce_gap: config
+                .sst_get_range_coalesce_gap
+                .map(|v| v.as_byte() as usize),
+        };
+
+        let read_limit = config.compaction.read_rate_limit;
+        let write_limit = config.compaction.write_rate_limit;
+        let store_picker: ObjectStorePickerRef = if read_limit.is_some() || write_limit.is_some() {
+            Arc::new(RateLimitedStorePicker::new(
+                &store_picker,
+                read_limit.map(|v| Arc::new(RateLimiter::new(v.as_byte()))),
+                write_limit.map(|v| Arc::new(RateLimiter::new(v.as_byte()))),
+            ))
+        } else {
+            store_picker
         };
 
         Self {
@@ -80,6 +108,50 @@ impl LocalCompactionRunner {
     }
 }
 
+/// Store picker sharing the rate limiters among the stores of the wrapped
+/// picker.
+#[derive(Debug)]
+struct RateLimitedStorePicker {
+    default_store: ObjectStoreRef,
+    once_store: ObjectStoreRef,
+    frequent_store: ObjectStoreRef,
+}
+
+impl RateLimitedStorePicker {
+    fn new(
+        picker: &ObjectStorePickerRef,
+        read_limiter: Option<Arc<RateLimiter>>,
+        write_limiter: Option<Arc<RateLimiter>>,
+    ) -> Self {
+        let wrap = |store: &ObjectStoreRef| -> ObjectStoreRef {
+            Arc::new(StoreWithRateLimit::new(
+                store.clone(),
+                read_limiter.clone(),
+                write_limiter.clone(),
+            ))
+        };
+
+        Self {
+            default_store: wrap(picker.default_store()),
+            once_store: wrap(picker.pick_by_freq(ReadFrequency::Once)),
+            frequent_store: wrap(picker.pick_by_freq(ReadFrequency::Frequent)),
+        }
+    }
+}
+
+impl ObjectStorePicker for RateLimitedStorePicker {
+    fn default_store(&self) -> &ObjectStoreRef {
+        &self.default_store
+    }
+
+    fn pick_by_freq(&self, freq: ReadFrequency) -> &ObjectStoreRef {
+        match freq {
+            ReadFrequency::Once => &self.once_store,
+            ReadFrequency::Frequent => &self.frequent_store,
+        }
+    }
+}
+
 #[async_trait]
 impl CompactionRunner for LocalCompactionRunner {
     async fn run(&self, task: CompactionRunnerTask) -> Result<CompactionRunnerResult> {
@@ -129,14 +201,61 @@ impl CompactionRunner for LocalCompactionRunner {
             })?
         };
 
+        let expire_at_column = task.input_ctx.expire_at_column.as_deref();
+        let downsample = task.input_ctx.downsample.as_ref();
+        let resolution_column = task.input_ctx.downsample_resolution_column.as_deref();
+        let column_ttls = &task.input_ctx.column_ttls;
+        let timestamp_column = task.schema.timestamp_name().to_string();
+        let now = task.input_ctx.timestamp_precision.now();
+        // The deleted rows are exactly the rows "expired" at `delete_before - 1`
+        // according to the timestamp column.
+        let delete_before = task.input_ctx.delete_before;
+        let (delete_column, delete_expire_time) = match delete_before {
+            Some(before) => (
+                Some(timestamp_column.as_str()),
+                Timestamp::new(before.as_i64().saturating_sub(1)),
+            ),
+            None => (None, Timestamp::MIN),
+        };
         let record_batch_stream = if task.input_ctx.need_dedup {
-            row_iter::record_batch_with_key_iter_to_stream(DedupIterator::new(
+            let dedup_iter = DedupIterator::new(
                 request_id.clone(),
                 merge_iter,
                 task.input_ctx.merge_iter_options,
+            );
+            row_iter::record_batch_with_key_iter_to_stream(DownsampleIterator::new(
+                ColumnExpireIterator::new(
+                    ExpireFilterIterator::new(
+                        ExpireFilterIterator::new(dedup_iter, expire_at_column, now),
+                        delete_column,
+                        delete_expire_time,
+                    ),
+                    column_ttls,
+                    &timestamp_column,
+                    now,
+                ),
+                downsample,
+                &timestamp_column,
+                resolution_column,
+                now,
             ))
         } else {
-            row_iter::record_batch_with_key_iter_to_stream(merge_iter)
+            row_iter::record_batch_with_key_iter_to_stream(DownsampleIterator::new(
+                ColumnExpireIterator::new(
+                    ExpireFilterIterator::new(
+                        ExpireFilterIterator::new(merge_iter, expire_at_column, now),
+                        delete_column,
+                        delete_expire_time,
+                    ),
+                    column_ttls,
+                    &timestamp_column,
+                    now,
+                ),
+                downsample,
+                &timestamp_column,
+                resolution_column,
+                now,
+            ))
         };
 
         // TODO: eliminate the duplicated building of `SstReadOptions`.
@@ -155,8 +274,18 @@ impl CompactionRunner for LocalCompactionRunner {
                 .context(ReadSstMeta)?;
 
             let column_stats = collect_column_stats_from_meta_datas(&sst_metas);
-            let merged_meta =
+            let mut merged_meta =
                 MetaData::merge(sst_metas.into_iter().map(MetaData::from), task.schema);
+            // Exclude the deleted part from the time range of the output sst.
+            if let Some(before) = delete_before {
+                let time_range = merged_meta.time_range;
+                if let Some(remaining) = TimeRange::new(
+                    time_range.inclusive_start().max(before),
+                    time_range.exclusive_end(),
+                ) {
+                    merged_meta.time_range = remaining;
+                }
+            }
             (merged_meta, column_stats)
         };
 
@@ -166,6 +295,7 @@ impl CompactionRunner for LocalCompactionRunner {
             compression: task.output_ctx.write_options.compression,
             max_buffer_size: task.output_ctx.write_options.max_buffer_size,
             column_stats,
+            field_groups: task.output_ctx.write_options.field_groups.clone(),
         };
 
         let mut sst_writer = self
@@ -20,7 +20,12 @@ pub mod local_runner;
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use common_types::{request_id::RequestId, schema::Schema, SequenceNumber};
+use common_types::{
+    request_id::RequestId,
+    schema::Schema,
+    time::{Timestamp, TimestampPrecision},
+    SequenceNumber,
+};
 use object_store::Path;
 use table_engine::table::TableId;
 
@@ -34,6 +39,7 @@ use crate::{
         writer::{MetaData, SstInfo},
     },
     table::data::TableData,
+    table_options::{ColumnTtl, DownsamplePolicy},
 };
 
 /// Compaction runner
@@ -83,12 +89,43 @@ impl CompactionRunnerTask {
             let iter_options = IterOptions {
                 batch_size: table_options.num_rows_per_row_group,
             };
+            // Purging the expired rows needs all the versions of the rows, otherwise an
+            // older version left in other ssts shows up again on read.
+            let version = table_data.current_version();
+            let contains_all_versions = version.contains_all_overlapping_ssts(&input_files.files);
+            // Downsampling needs all the rows of the intervals, otherwise the raw rows
+            // left are read along with the downsampled ones, or an interval
+            // downsampled before is aggregated again into a duplicated row.
+            let downsample = table_options
+                .downsample
+                .as_ref()
+                .map(|policy| policy.scale_to(table_options.timestamp_precision))
+                .filter(|policy| {
+                    let interval = policy.interval.as_millis() as i64;
+                    interval > 0
+                        && version.contains_all_ssts_of_intervals(&input_files.files, interval)
+                });
 
             InputContext {
                 files: input_files,
                 num_rows_per_row_group: table_options.num_rows_per_row_group,
                 merge_iter_options: iter_options,
                 need_dedup: table_options.need_dedup(),
+                expire_at_column: table_options
+                    .expire_at_column
+                    .clone()
+                    .filter(|_| contains_all_versions),
+                downsample,
+                downsample_resolution_column: table_options.downsample_resolution_column.clone(),
+                column_ttls: table_options
+                    .column_ttls
+                    .iter()
+                    .map(|column_ttl| column_ttl.scale_to(table_options.timestamp_precision))
+                    .collect(),
+                timestamp_precision: table_options.timestamp_precision,
+                // The deleted rows are hidden from the reads of all the versions, so
+                // they are dropped by any compaction.
+                delete_before: version.delete_before(),
             }
         };
 
@@ -126,6 +163,20 @@ pub struct InputContext {
     pub num_rows_per_row_group: usize,
     pub merge_iter_options: IterOptions,
     pub need_dedup: bool,
+    /// Column whose values are the expire time of the rows, None if the input
+    /// doesn't contain all the overlapping ssts.
+    pub expire_at_column: Option<String>,
+    /// Policy of downsampling the aged data, scaled to the timestamp
+    /// precision, None if the input doesn't contain all the overlapping ssts.
+    pub downsample: Option<DownsamplePolicy>,
+    /// Column filled with the resolution of the downsampled rows.
+    pub downsample_resolution_column: Option<String>,
+    /// Ttls of the column values, scaled to the timestamp precision.
+    pub column_ttls: Vec<ColumnTtl>,
+    pub timestamp_precision: TimestampPrecision,
+    /// The rows whose timestamp is before it are deleted, in the timestamp
+    /// precision.
+    pub delete_before: Option<Timestamp>,
 }
 
 #[derive(Debug, Clone)]
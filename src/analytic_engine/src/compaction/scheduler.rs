@@ -18,7 +18,7 @@
 // Compaction scheduler.
 
 use std::{
-    collections::{HashMap, VecDeque},
+    collections::{HashMap, HashSet, VecDeque},
     hash::Hash,
     sync::{
         atomic::{AtomicBool, AtomicUsize, Ordering},
@@ -28,7 +28,7 @@ use std::{
 };
 
 use async_trait::async_trait;
-use common_types::request_id::RequestId;
+use common_types::{request_id::RequestId, table::ShardId};
 use futures::{stream::FuturesUnordered, StreamExt};
 use logger::{debug, error, info, warn};
 use macros::define_result;
@@ -57,7 +57,7 @@ use crate::{
         SpaceStore,
     },
     sst::factory::SstWriteOptions,
-    table::data::TableDataRef,
+    table::{data::TableDataRef, metrics::UnflushedWal},
     TableOptions,
 };
 
@@ -78,6 +78,14 @@ pub struct SchedulerConfig {
     pub max_unflushed_duration: ReadableDuration,
     pub memory_limit: ReadableSize,
     pub max_pending_compaction_tasks: usize,
+    /// Max bytes per second read from the object store by the compactions,
+    /// unlimited if not set.
+    pub read_rate_limit: Option<ReadableSize>,
+    /// Max bytes per second written to the object store by the compactions,
+    /// unlimited if not set.
+    pub write_rate_limit: Option<ReadableSize>,
+    /// Policy to flush the tables proactively to truncate the wal.
+    pub wal_truncation: WalTruncationConfig,
 }
 
 impl Default for SchedulerConfig {
@@ -91,10 +99,102 @@ impl Default for SchedulerConfig {
             max_unflushed_duration: ReadableDuration(Duration::from_secs(60 * 60 * 5)),
             memory_limit: ReadableSize::gb(4),
             max_pending_compaction_tasks: 1024,
+            read_rate_limit: None,
+            write_rate_limit: None,
+            wal_truncation: WalTruncationConfig::default(),
         }
     }
 }
 
+/// Limits of the unflushed wal, exceeding which the tables will be flushed so
+/// that the wal can be truncated.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct WalTruncationPolicy {
+    /// Max size of the unflushed wal of all the tables in a shard, unlimited if
+    /// not set.
+    pub max_region_size: Option<ReadableSize>,
+    /// Max age of the oldest unflushed wal entry of a table, unlimited if not
+    /// set.
+    pub max_age: Option<ReadableDuration>,
+}
+
+impl WalTruncationPolicy {
+    fn is_unlimited(&self) -> bool {
+        self.max_region_size.is_none() && self.max_age.is_none()
+    }
+
+    /// Pick the tables to flush from the `tables` of a shard, returning their
+    /// indexes.
+    ///
+    /// The tables whose oldest unflushed entry is too old are picked, and then
+    /// the tables with the most unflushed bytes are picked until the rest of
+    /// the shard fits in the `max_region_size`.
+    fn pick_tables_to_flush(&self, tables: &[UnflushedWal], now_ms: u64) -> Vec<usize> {
+        let mut picked = vec![false; tables.len()];
+        if let Some(max_age) = &self.max_age {
+            let max_age_ms = max_age.as_millis_u64();
+            for (idx, table) in tables.iter().enumerate() {
+                if let Some(first_write_time_ms) = table.first_write_time_ms {
+                    picked[idx] = now_ms.saturating_sub(first_write_time_ms) > max_age_ms;
+                }
+            }
+        }
+
+        if let Some(max_region_size) = &self.max_region_size {
+            let mut remaining_bytes: u64 = tables
+                .iter()
+                .zip(&picked)
+                .filter(|(_, picked)| !**picked)
+                .map(|(table, _)| table.bytes)
+                .sum();
+            let mut candidates: Vec<_> = (0..tables.len()).filter(|idx| !picked[*idx]).collect();
+            candidates.sort_unstable_by_key(|idx| std::cmp::Reverse(tables[*idx].bytes));
+            for idx in candidates {
+                if remaining_bytes <= max_region_size.as_byte() {
+                    break;
+                }
+                remaining_bytes -= tables[idx].bytes;
+                picked[idx] = true;
+            }
+        }
+
+        picked
+            .into_iter()
+            .enumerate()
+            .filter_map(|(idx, picked)| picked.then_some(idx))
+            .collect()
+    }
+}
+
+/// The wal truncation policy of a specific shard.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ShardWalTruncationPolicy {
+    pub shard_id: ShardId,
+    #[serde(flatten)]
+    pub policy: WalTruncationPolicy,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct WalTruncationConfig {
+    /// Policy of the shards not specified in `shards`.
+    pub default: WalTruncationPolicy,
+    /// Policies of the specific shards, overriding the default one.
+    pub shards: Vec<ShardWalTruncationPolicy>,
+}
+
+impl WalTruncationConfig {
+    fn policy_of(&self, shard_id: ShardId) -> &WalTruncationPolicy {
+        self.shards
+            .iter()
+            .find(|v| v.shard_id == shard_id)
+            .map(|v| &v.policy)
+            .unwrap_or(&self.default)
+    }
+}
+
 enum ScheduleTask {
     Request(TableCompactionRequest),
     Schedule,
@@ -316,7 +416,11 @@ impl SchedulerImpl {
         let (tx, rx) = mpsc::channel(config.schedule_channel_len);
         let running = Arc::new(AtomicBool::new(true));
 
-        let compactor = Arc::new(Compactor::new(runner, space_store.manifest.clone()));
+        let compactor = Arc::new(Compactor::new(
+            runner,
+            space_store.manifest.clone(),
+            space_store.hooks.clone(),
+        ));
         let mut worker = ScheduleWorker {
             sender: tx.clone(),
             receiver: rx,
@@ -327,6 +431,7 @@ impl SchedulerImpl {
             picker_manager: PickerManager,
             max_ongoing_tasks: config.max_ongoing_tasks,
             max_unflushed_duration: config.max_unflushed_duration.0,
+            wal_truncation: config.wal_truncation,
             write_sst_max_buffer_size,
             min_flush_interval_ms,
             limit: Arc::new(OngoingTaskLimit {
@@ -404,6 +509,7 @@ struct ScheduleWorker {
     runtime: Arc<Runtime>,
     schedule_interval: Duration,
     max_unflushed_duration: Duration,
+    wal_truncation: WalTruncationConfig,
     picker_manager: PickerManager,
     max_ongoing_tasks: usize,
     write_sst_max_buffer_size: usize,
@@ -506,13 +612,16 @@ impl ScheduleWorker {
 
         let sender = self.sender.clone();
         let request_id = RequestId::next_id();
-        let storage_format_hint = table_data.table_options().storage_format_hint;
+        // Load the options once to avoid mixing the old and new options altered
+        // concurrently.
+        let table_options = table_data.table_options();
         let sst_write_options = SstWriteOptions {
-            storage_format_hint,
-            num_rows_per_row_group: table_data.table_options().num_rows_per_row_group,
-            compression: table_data.table_options().compression,
+            storage_format_hint: table_options.storage_format_hint,
+            num_rows_per_row_group: table_options.num_rows_per_row_group,
+            compression: table_options.compression,
             max_buffer_size: self.write_sst_max_buffer_size,
             column_stats: Default::default(),
+            field_groups: table_options.field_groups.clone(),
         };
 
         // Do actual costly compact job in background.
@@ -579,16 +688,7 @@ impl ScheduleWorker {
         token
     }
 
-    async fn handle_table_compaction_request(&self, compact_req: TableCompactionRequest) {
-        let table_data = compact_req.table_data.clone();
-        if !table_data.allow_compaction() {
-            error!(
-                "Table status is not ok, unable to compact further, table:{}, table_id:{}",
-                table_data.name, table_data.id
-            );
-            return;
-        }
-
+    fn pick_compaction_task(&self, table_data: &TableDataRef) -> Option<CompactionTask> {
         let table_options = table_data.table_options();
         let compaction_strategy = table_options.compaction_strategy;
         let picker = self.picker_manager.get_picker(compaction_strategy);
@@ -597,15 +697,15 @@ impl ScheduleWorker {
             None => {
                 warn!("No valid context can be created, compaction request will be ignored, table_id:{}, table_name:{}",
                     table_data.id, table_data.name);
-                return;
+                return None;
             }
         };
         let version = table_data.current_version();
 
         // Pick compaction task.
         let compaction_task = version.pick_for_compaction(picker_ctx, &picker);
-        let compaction_task = match compaction_task {
-            Ok(v) => v,
+        match compaction_task {
+            Ok(v) => Some(v),
             Err(e) => {
                 error!(
                     "Compaction scheduler failed to pick compaction, table:{}, table_id:{}, err:{}",
@@ -613,8 +713,27 @@ impl ScheduleWorker {
                 );
                 // Now the error of picking compaction is considered not fatal and not sent to
                 // compaction notifier.
-                return;
+                None
             }
+        }
+    }
+
+    async fn handle_table_compaction_request(&self, compact_req: TableCompactionRequest) {
+        let table_data = compact_req.table_data.clone();
+        if !table_data.allow_compaction() {
+            error!(
+                "Table status is not ok, unable to compact further, table:{}, table_id:{}",
+                table_data.name, table_data.id
+            );
+            return;
+        }
+
+        let compaction_task = match compact_req.delete_before {
+            Some(before) => table_data.current_version().pick_for_delete(before),
+            None => match self.pick_compaction_task(&table_data) {
+                Some(v) => v,
+                None => return,
+            },
         };
 
         let token = match self.try_apply_memory_usage_token_for_task(&compaction_task) {
@@ -672,13 +791,15 @@ impl ScheduleWorker {
             min_flush_interval_ms: Some(self.min_flush_interval_ms),
         };
 
+        let now_ms = time_ext::current_time_millis();
+        let mut tables_to_truncate_wal = self.pick_tables_to_truncate_wal(&tables_buf, now_ms);
         for table_data in &tables_buf {
             let last_flush_time = table_data.last_flush_time();
             let flush_deadline_ms = last_flush_time + self.max_unflushed_duration.as_millis_u64();
-            let now_ms = time_ext::current_time_millis();
-            if now_ms > flush_deadline_ms {
+            let truncate_wal = tables_to_truncate_wal.remove(&table_data.id);
+            if now_ms > flush_deadline_ms || truncate_wal {
                 info!(
-                    "Scheduled flush is triggered, table:{}, last_flush_time:{last_flush_time}ms, max_unflushed_duration:{:?}",
+                    "Scheduled flush is triggered, table:{}, last_flush_time:{last_flush_time}ms, max_unflushed_duration:{:?}, truncate_wal:{truncate_wal}",
                     table_data.name,
                     self.max_unflushed_duration,
                 );
@@ -696,6 +817,44 @@ impl ScheduleWorker {
         }
     }
 
+    /// Pick the tables whose wal exceeds the limits of the truncation policy of
+    /// their shards, the wal will be truncated after they are flushed.
+    fn pick_tables_to_truncate_wal(
+        &self,
+        tables: &[TableDataRef],
+        now_ms: u64,
+    ) -> HashSet<TableId> {
+        let mut shard_tables: HashMap<ShardId, Vec<&TableDataRef>> = HashMap::new();
+        for table_data in tables {
+            shard_tables
+                .entry(table_data.shard_info.shard_id)
+                .or_default()
+                .push(table_data);
+        }
+
+        let mut picked = HashSet::new();
+        for (shard_id, tables) in shard_tables {
+            let policy = self.wal_truncation.policy_of(shard_id);
+            if policy.is_unlimited() {
+                continue;
+            }
+
+            let unflushed: Vec<_> = tables
+                .iter()
+                .map(|table_data| table_data.metrics.unflushed_wal())
+                .collect();
+            for idx in policy.pick_tables_to_flush(&unflushed, now_ms) {
+                debug!(
+                    "Pick table to truncate wal, shard_id:{shard_id}, table:{}, unflushed_wal:{:?}",
+                    tables[idx].name, unflushed[idx]
+                );
+                picked.insert(tables[idx].id);
+            }
+        }
+
+        picked
+    }
+
     fn is_pending_queue_hungry(&self) -> bool {
         // TODO: Currently we consider pending queue is hungry when number of pending
         // tasks is less than `max_ongoing_tasks`, maybe we can add a new option
@@ -713,6 +872,7 @@ fn new_picker_context(table_opts: &TableOptions) -> Option<PickerContext> {
             segment_duration,
             ttl: table_opts.ttl().map(|ttl| ttl.0),
             strategy: table_opts.compaction_strategy,
+            timestamp_precision: table_opts.timestamp_precision,
         })
 }
 
@@ -819,4 +979,51 @@ mod tests {
         assert!(q.is_empty());
         assert_eq!(0, q.len());
     }
+
+    #[test]
+    fn test_wal_truncation_policy() {
+        let unflushed = |bytes, first_write_time_ms| UnflushedWal {
+            entries: bytes,
+            bytes,
+            first_write_time_ms,
+        };
+        let tables = vec![
+            unflushed(100, Some(1000)),
+            unflushed(300, Some(5000)),
+            unflushed(200, Some(9000)),
+            unflushed(0, None),
+        ];
+
+        let cases = vec![
+            // One case is (max_region_size, max_age_ms, picked tables).
+            (None, None, vec![]),
+            (Some(600), None, vec![]),
+            (Some(500), None, vec![1]),
+            (Some(200), None, vec![1, 2]),
+            (Some(0), None, vec![0, 1, 2]),
+            (None, Some(2000), vec![0, 1]),
+            (None, Some(8000), vec![0]),
+            (Some(250), Some(8000), vec![0, 1]),
+        ];
+        for (max_region_size, max_age_ms, expect) in cases {
+            let policy = WalTruncationPolicy {
+                max_region_size: max_region_size.map(ReadableSize),
+                max_age: max_age_ms.map(|v| ReadableDuration(Duration::from_millis(v))),
+            };
+            assert_eq!(policy.pick_tables_to_flush(&tables, 10000), expect);
+        }
+
+        let config = WalTruncationConfig {
+            default: WalTruncationPolicy::default(),
+            shards: vec![ShardWalTruncationPolicy {
+                shard_id: 1,
+                policy: WalTruncationPolicy {
+                    max_region_size: Some(ReadableSize::mb(1)),
+                    max_age: None,
+                },
+            }],
+        };
+        assert!(config.policy_of(0).is_unlimited());
+        assert!(!config.policy_of(1).is_unlimited());
+    }
 }
@@ -21,7 +21,7 @@ use std::{fmt, sync::Arc};
 
 use table_engine::engine::EngineRuntimes;
 
-use crate::{sst::meta_data::cache::MetaCacheRef, Config};
+use crate::{hook::EngineHooks, sst::meta_data::cache::MetaCacheRef, Config};
 
 /// Context for instance open
 pub struct OpenContext {
@@ -33,6 +33,9 @@ pub struct OpenContext {
 
     /// Sst meta data cache.
     pub meta_cache: Option<MetaCacheRef>,
+
+    /// Hooks registered to the engine.
+    pub hooks: EngineHooks,
 }
 
 impl fmt::Debug for OpenContext {
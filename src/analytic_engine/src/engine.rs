@@ -27,10 +27,11 @@ use prometheus::{core::Collector, HistogramVec, IntCounterVec};
 use snafu::{OptionExt, ResultExt};
 use table_engine::{
     engine::{
-        Close, CloseShardRequest, CloseTableRequest, CreateTableParams, CreateTableRequest,
-        DropTableRequest, OpenShard, OpenShardRequest, OpenShardResult, OpenTableNoCause,
-        OpenTableRequest, OpenTableWithCause, Result, ShardStats, TableDef, TableEngine,
-        TableEngineStats, Unexpected,
+        Close, CloseShardRequest, CloseTableRequest, ClosedTable, CreateTableParams,
+        CreateTableRequest, DropTableRequest, FindTableRequest, OpenShard, OpenShardCheckReport,
+        OpenShardRequest, OpenShardResult, OpenTableNoCause, OpenTableRequest, OpenTableWithCause,
+        Result, ScanWalRequest, ScanWalResult, ShardStats, TableDef, TableEngine, TableEngineStats,
+        Unexpected, WriteTablesRequest,
     },
     table::{SchemaId, TableRef},
     ANALYTIC_ENGINE_TYPE,
@@ -65,21 +66,22 @@ impl TableEngineImpl {
     async fn close_tables_of_shard(
         &self,
         close_requests: Vec<table_engine::engine::CloseTableRequest>,
-    ) -> Vec<table_engine::engine::Result<String>> {
+    ) -> Vec<table_engine::engine::Result<ClosedTable>> {
         if close_requests.is_empty() {
             return Vec::new();
         }
 
         let mut close_results = Vec::with_capacity(close_requests.len());
         for request in close_requests {
+            let space_id = build_space_id(request.schema_id);
             let result = self
-                .close_table(request.clone())
+                .instance
+                .close_table(space_id, request.clone())
                 .await
                 .map_err(|e| {
                     error!("Failed to close table, close_request:{request:?}, err:{e}");
-                    e
-                })
-                .map(|_| request.table_name);
+                    table_engine::engine::Error::from(e)
+                });
 
             close_results.push(result);
         }
@@ -169,7 +171,7 @@ impl TableEngine for TableEngineImpl {
         };
 
         let mut shard_result = self.instance.open_tables_of_shard(shard_request).await?;
-        let table_opt = shard_result.remove(&table_id).with_context(|| OpenTableNoCause {
+        let table_opt = shard_result.tables.remove(&table_id).with_context(|| OpenTableNoCause {
             msg: Some(format!("table not exist, table_id:{table_id}, space_id:{space_id}, shard_id:{shard_id}")),
         })?
         .box_err()
@@ -183,6 +185,49 @@ impl TableEngine for TableEngineImpl {
         Ok(table_opt)
     }
 
+    async fn write_tables(&self, request: WriteTablesRequest) -> Result<Vec<usize>> {
+        let num_rows = self.instance.write_tables(request.requests).await?;
+
+        Ok(num_rows)
+    }
+
+    fn install_shard_write_epoch(&self, shard_id: ShardId, epoch: u64) {
+        self.instance.shard_write_epochs.install(shard_id, epoch);
+    }
+
+    fn fence_shard_writes(&self, shard_id: ShardId) {
+        self.instance.shard_write_epochs.fence(shard_id);
+    }
+
+    fn write_pressure(&self) -> f64 {
+        self.instance.write_pressure()
+    }
+
+    async fn find_table(&self, request: FindTableRequest) -> Result<Option<TableRef>> {
+        let space_id = build_space_id(request.schema_id);
+        let space_table = match self
+            .instance
+            .find_table(space_id, &request.table_name)
+            .await?
+        {
+            Some(space_table) => Some(space_table),
+            // The table closed by the idle table reaper is reopened on finding.
+            None => match self
+                .instance
+                .idle_closed_tables
+                .find_by_name(space_id, &request.table_name)
+            {
+                Some(table_id) => Some(self.instance.reopen_idle_table(space_id, table_id).await?),
+                None => None,
+            },
+        };
+
+        let table_opt = space_table
+            .map(|space_table| Arc::new(TableImpl::new(self.instance.clone(), space_table)) as _);
+
+        Ok(table_opt)
+    }
+
     async fn close_table(&self, request: CloseTableRequest) -> Result<()> {
         let space_id = build_space_id(request.schema_id);
 
@@ -204,8 +249,8 @@ impl TableEngine for TableEngineImpl {
             .box_err()
             .context(OpenShard)?;
 
-        let mut engine_shard_result = OpenShardResult::with_capacity(shard_result.len());
-        for (table_id, table_res) in shard_result {
+        let mut engine_shard_result = OpenShardResult::with_capacity(shard_result.tables.len());
+        for (table_id, table_res) in shard_result.tables {
             match table_res.box_err() {
                 Ok(Some(space_table)) => {
                     let table_impl = Arc::new(TableImpl::new(self.instance.clone(), space_table));
@@ -226,7 +271,7 @@ impl TableEngine for TableEngineImpl {
     async fn close_shard(
         &self,
         request: CloseShardRequest,
-    ) -> Vec<table_engine::engine::Result<String>> {
+    ) -> Vec<table_engine::engine::Result<ClosedTable>> {
         let table_defs = request.table_defs;
         let close_requests = table_defs
             .into_iter()
@@ -249,6 +294,15 @@ impl TableEngine for TableEngineImpl {
 
         Ok(Some(table_engine_stats))
     }
+
+    async fn check_open_shard(&self, request: OpenShardRequest) -> Result<OpenShardCheckReport> {
+        Ok(self.instance.check_open_shard(request).await)
+    }
+
+    async fn scan_wal(&self, request: ScanWalRequest) -> Result<ScanWalResult> {
+        let result = self.instance.scan_wal(request).await?;
+        Ok(result)
+    }
 }
 
 /// Collect the table engine stats from the two provided metric.
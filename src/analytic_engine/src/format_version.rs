@@ -0,0 +1,376 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Format versions of the records persisted by the engine.
+//!
+//! The versioned record is prefixed by a stamp: `| magic(u8) | version(u8) |`,
+//! and the records written before the stamp was introduced are regarded as
+//! [`LEGACY_VERSION`]. The records of older versions are upgraded to the
+//! current version through the registered migrations on reading, and the
+//! records newer than the binary supports are refused.
+//!
+//! The versions are also stamped into the object store when the engine is
+//! opened, so a binary older than the persisted records fails at startup
+//! rather than in the middle of the replay.
+
+use std::borrow::Cow;
+
+use generic_error::GenericError;
+use logger::info;
+use macros::define_result;
+use object_store::{ObjectStoreError, ObjectStoreRef, Path};
+use serde::{Deserialize, Serialize};
+use snafu::{ensure, Backtrace, ResultExt, Snafu};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display(
+        "Unsupported format version of {}, version:{}, min_supported:{}, current:{}.\nBacktrace:\n{}",
+        name,
+        version,
+        min_supported,
+        current,
+        backtrace
+    ))]
+    UnsupportedVersion {
+        name: &'static str,
+        version: u8,
+        min_supported: u8,
+        current: u8,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Truncated format stamp of {}.\nBacktrace:\n{}", name, backtrace))]
+    TruncatedStamp {
+        name: &'static str,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display(
+        "Failed to migrate {} from version:{}, err:{}",
+        name,
+        from_version,
+        source
+    ))]
+    Migrate {
+        name: &'static str,
+        from_version: u8,
+        source: GenericError,
+    },
+
+    #[snafu(display("Failed to load stamped format versions, err:{}", source))]
+    LoadStampedVersions { source: ObjectStoreError },
+
+    #[snafu(display("Failed to decode stamped format versions, err:{}", source))]
+    DecodeStampedVersions { source: serde_json::Error },
+
+    #[snafu(display("Failed to encode stamped format versions, err:{}", source))]
+    EncodeStampedVersions { source: serde_json::Error },
+
+    #[snafu(display("Failed to store stamped format versions, err:{}", source))]
+    StoreStampedVersions { source: ObjectStoreError },
+}
+
+define_result!(Error);
+
+/// The first byte of the stamp.
+///
+/// The legacy records start with a protobuf tag of small field number or a
+/// wal payload header, so this value never appears at their beginning.
+const STAMP_MAGIC: u8 = 0xFE;
+pub const STAMP_SIZE: usize = 2;
+/// Version of the records without stamp.
+pub const LEGACY_VERSION: u8 = 0;
+
+/// Upgrade the body encoded in the version `v` to `v + 1`.
+pub type Migration = fn(&[u8]) -> std::result::Result<Vec<u8>, GenericError>;
+
+/// Keep the body unchanged, used when only the stamp is introduced.
+fn identity_migration(body: &[u8]) -> std::result::Result<Vec<u8>, GenericError> {
+    Ok(body.to_vec())
+}
+
+/// Size of the write batch id following the header of the wal payload.
+pub const WRITE_BATCH_ID_SIZE: usize = 16;
+
+/// Insert the nil write batch id after the header of the wal payload, and the
+/// payloads with nil batch id are never deduplicated.
+fn insert_nil_write_batch_id(body: &[u8]) -> std::result::Result<Vec<u8>, GenericError> {
+    let Some((header, rest)) = body.split_first() else {
+        return Err("wal payload without header".into());
+    };
+
+    let mut upgraded = Vec::with_capacity(body.len() + WRITE_BATCH_ID_SIZE);
+    upgraded.push(*header);
+    upgraded.extend_from_slice(&[0; WRITE_BATCH_ID_SIZE]);
+    upgraded.extend_from_slice(rest);
+    Ok(upgraded)
+}
+
+/// Format version of one kind of persisted records.
+#[derive(Debug)]
+pub struct FormatVersion {
+    pub name: &'static str,
+    pub current: u8,
+    /// The oldest version still readable, that is `current - 1` in general.
+    pub min_supported: u8,
+    /// `migrations[i]` upgrades the version `min_supported + i` to the next.
+    migrations: &'static [Migration],
+}
+
+/// Version of the manifest records carrying the extended table options.
+///
+/// The extension is an unknown field to the binaries before it, which would
+/// silently drop the options instead of refusing the records.
+pub const MANIFEST_EXT_VERSION: u8 = 2;
+
+/// Format of the manifest logs and snapshots.
+///
+/// Version 2 appends the table options missing in the manifest proto to the
+/// encoded records, see [`MANIFEST_EXT_VERSION`].
+pub const MANIFEST_FORMAT: FormatVersion = FormatVersion {
+    name: "manifest",
+    current: MANIFEST_EXT_VERSION,
+    min_supported: LEGACY_VERSION,
+    migrations: &[identity_migration, identity_migration],
+};
+
+/// Format of the payloads written into the data wal.
+///
+/// Version 2 carries the write batch id to deduplicate the retried appends.
+pub const WAL_PAYLOAD_FORMAT: FormatVersion = FormatVersion {
+    name: "wal payload",
+    current: 2,
+    min_supported: LEGACY_VERSION,
+    migrations: &[identity_migration, insert_nil_write_batch_id],
+};
+
+impl FormatVersion {
+    /// Stamp of the current version.
+    #[inline]
+    pub fn stamp(&self) -> [u8; STAMP_SIZE] {
+        [STAMP_MAGIC, self.current]
+    }
+
+    /// Check whether the records of `version` can be read.
+    pub fn check(&self, version: u8) -> Result<()> {
+        ensure!(
+            version >= self.min_supported && version <= self.current,
+            UnsupportedVersion {
+                name: self.name,
+                version,
+                min_supported: self.min_supported,
+                current: self.current,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Split the stamped record into the version and the body.
+    pub fn split<'a>(&self, buf: &'a [u8]) -> Result<(u8, &'a [u8])> {
+        match buf.first() {
+            Some(&STAMP_MAGIC) => {
+                ensure!(buf.len() >= STAMP_SIZE, TruncatedStamp { name: self.name });
+                Ok((buf[1], &buf[STAMP_SIZE..]))
+            }
+            _ => Ok((LEGACY_VERSION, buf)),
+        }
+    }
+
+    /// Decode the stamped record and upgrade its body to the current version.
+    pub fn decode<'a>(&self, buf: &'a [u8]) -> Result<Cow<'a, [u8]>> {
+        let (version, body) = self.split(buf)?;
+        self.check(version)?;
+
+        let mut body = Cow::Borrowed(body);
+        for from_version in version..self.current {
+            let migration = self.migrations[(from_version - self.min_supported) as usize];
+            body = Cow::Owned(migration(&body).context(Migrate {
+                name: self.name,
+                from_version,
+            })?);
+        }
+
+        Ok(body)
+    }
+}
+
+/// Path of the stamped format versions in the default object store.
+const STAMPED_VERSIONS_PATH: &str = "format_versions.json";
+
+/// Format versions of the records persisted by the last opened engine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct StampedVersions {
+    manifest: u8,
+    wal_payload: u8,
+}
+
+impl StampedVersions {
+    fn current() -> Self {
+        Self {
+            manifest: MANIFEST_FORMAT.current,
+            wal_payload: WAL_PAYLOAD_FORMAT.current,
+        }
+    }
+}
+
+/// Check the format versions stamped in the `store` can be read by this
+/// binary, and stamp the current versions then.
+///
+/// It should be called before the manifest and wal are replayed.
+pub async fn check_stamped_versions(store: &ObjectStoreRef) -> Result<()> {
+    let path = Path::from(STAMPED_VERSIONS_PATH);
+    let current = StampedVersions::current();
+
+    if let Some(stamped) = load_stamped_versions(store, &path).await? {
+        MANIFEST_FORMAT.check(stamped.manifest)?;
+        WAL_PAYLOAD_FORMAT.check(stamped.wal_payload)?;
+        if stamped == current {
+            return Ok(());
+        }
+
+        info!("Upgrade stamped format versions, from:{stamped:?}, to:{current:?}");
+    }
+
+    let payload = serde_json::to_vec(&current).context(EncodeStampedVersions)?;
+    store
+        .put(&path, payload.into())
+        .await
+        .context(StoreStampedVersions)?;
+
+    Ok(())
+}
+
+async fn load_stamped_versions(
+    store: &ObjectStoreRef,
+    path: &Path,
+) -> Result<Option<StampedVersions>> {
+    let payload = match store.get(path).await {
+        Ok(res) => res.bytes().await.context(LoadStampedVersions)?,
+        Err(ObjectStoreError::NotFound { .. }) => return Ok(None),
+        Err(err) => return Err(err).context(LoadStampedVersions),
+    };
+
+    serde_json::from_slice(&payload)
+        .map(Some)
+        .context(DecodeStampedVersions)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use object_store::local_file;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::{
+        manifest::meta_edit::{AlterOptionsMeta, MetaUpdate, MetaUpdatePayload},
+        table_options::TableOptions,
+    };
+
+    #[test]
+    fn test_decode_stamped_and_legacy() {
+        let body = [1u8, 2, 3];
+        let mut stamped = MANIFEST_FORMAT.stamp().to_vec();
+        stamped.extend_from_slice(&body);
+
+        assert_eq!(MANIFEST_FORMAT.decode(&stamped).unwrap().as_ref(), &body);
+        assert_eq!(MANIFEST_FORMAT.decode(&body).unwrap().as_ref(), &body);
+    }
+
+    #[test]
+    fn test_insert_nil_write_batch_id() {
+        let body = [1u8, 2, 3];
+        let upgraded = WAL_PAYLOAD_FORMAT.decode(&body).unwrap();
+
+        assert_eq!(upgraded.len(), body.len() + WRITE_BATCH_ID_SIZE);
+        assert_eq!(upgraded[0], 1);
+        assert!(upgraded[1..=WRITE_BATCH_ID_SIZE].iter().all(|v| *v == 0));
+        assert_eq!(&upgraded[WRITE_BATCH_ID_SIZE + 1..], &[2, 3]);
+    }
+
+    #[test]
+    fn test_refuse_newer_version() {
+        let newer = [STAMP_MAGIC, MANIFEST_FORMAT.current + 1, 1];
+        assert!(matches!(
+            MANIFEST_FORMAT.decode(&newer),
+            Err(Error::UnsupportedVersion { .. })
+        ));
+        assert!(matches!(
+            MANIFEST_FORMAT.decode(&[STAMP_MAGIC]),
+            Err(Error::TruncatedStamp { .. })
+        ));
+    }
+
+    #[test]
+    fn test_manifest_ext_refused_by_older_version() {
+        let options = TableOptions {
+            expire_at_column: Some("expire_at".to_string()),
+            ..Default::default()
+        };
+        let payload = MetaUpdatePayload::from(MetaUpdate::AlterOptions(AlterOptionsMeta {
+            space_id: 0,
+            table_id: 0.into(),
+            options,
+        }));
+        let mut buf = Vec::new();
+        wal::log_batch::Payload::encode_to(&payload, &mut buf).unwrap();
+
+        let (version, _) = MANIFEST_FORMAT.split(&buf).unwrap();
+        assert_eq!(version, MANIFEST_EXT_VERSION);
+
+        let older = FormatVersion {
+            current: MANIFEST_EXT_VERSION - 1,
+            ..MANIFEST_FORMAT
+        };
+        assert!(matches!(
+            older.decode(&buf),
+            Err(Error::UnsupportedVersion { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_check_stamped_versions() {
+        let dir = TempDir::new().unwrap();
+        let store: ObjectStoreRef = Arc::new(
+            local_file::try_new_with_default(dir.path().to_string_lossy().to_string()).unwrap(),
+        );
+        let path = Path::from(STAMPED_VERSIONS_PATH);
+
+        check_stamped_versions(&store).await.unwrap();
+        assert_eq!(
+            load_stamped_versions(&store, &path).await.unwrap(),
+            Some(StampedVersions::current())
+        );
+        check_stamped_versions(&store).await.unwrap();
+
+        let newer = StampedVersions {
+            manifest: MANIFEST_FORMAT.current + 1,
+            ..StampedVersions::current()
+        };
+        store
+            .put(&path, serde_json::to_vec(&newer).unwrap().into())
+            .await
+            .unwrap();
+        assert!(matches!(
+            check_stamped_versions(&store).await,
+            Err(Error::UnsupportedVersion { .. })
+        ));
+    }
+}
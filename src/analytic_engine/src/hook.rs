@@ -0,0 +1,267 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Hooks to intercept the writes, flushes and compactions of the engine.
+//!
+//! The hooks receive the table meta and the rows, making it possible to build
+//! external indexing, alerting or custom replication without forking the
+//! engine. Any hook returning an error vetoes the operation.
+
+use std::{fmt, sync::Arc};
+
+use async_trait::async_trait;
+use common_types::{row::RowGroup, SequenceNumber};
+use disk_quota::DiskGovernor;
+use generic_error::{BoxError, GenericResult};
+use object_store::Path;
+use table_engine::table::TableId;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::{
+    manifest::meta_edit::VersionEditMeta,
+    space::SpaceId,
+    sst::manager::FileId,
+    table::{
+        data::TableData,
+        sst_util,
+        version_edit::{AddFile, DeleteFile},
+    },
+};
+
+/// The operation changing the ssts of a table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SstChangeKind {
+    Flush,
+    Compaction,
+}
+
+/// The ssts added and deleted by a flush or compaction, which have been
+/// persisted into the manifest.
+#[derive(Debug, Clone)]
+pub struct SstChangeEvent {
+    pub kind: SstChangeKind,
+    pub space_id: SpaceId,
+    pub table_id: TableId,
+    pub table_name: String,
+    pub added: Vec<AddFile>,
+    pub deleted: Vec<DeleteFile>,
+    /// Max sequence of the data in the added ssts.
+    pub max_sequence: SequenceNumber,
+}
+
+impl SstChangeEvent {
+    pub(crate) fn new(kind: SstChangeKind, table: &TableData, edit_meta: &VersionEditMeta) -> Self {
+        let max_sequence = edit_meta
+            .files_to_add
+            .iter()
+            .map(|add| add.file.max_seq)
+            .max()
+            .unwrap_or(edit_meta.flushed_sequence);
+
+        Self {
+            kind,
+            space_id: table.space_id,
+            table_id: table.id,
+            table_name: table.name.clone(),
+            added: edit_meta.files_to_add.clone(),
+            deleted: edit_meta.files_to_delete.clone(),
+            max_sequence,
+        }
+    }
+
+    /// Path of the sst in the object store.
+    pub fn sst_path(&self, file_id: FileId) -> Path {
+        sst_util::new_sst_file_path(self.space_id, self.table_id, file_id)
+    }
+}
+
+#[async_trait]
+pub trait EngineHook: fmt::Debug + Send + Sync {
+    /// Called before the rows are written into the wal and memtable.
+    async fn before_write(&self, _table: &TableData, _rows: &RowGroup) -> GenericResult<()> {
+        Ok(())
+    }
+
+    /// Called before the memtables of the table are dumped into ssts.
+    async fn before_flush(&self, _table: &TableData) -> GenericResult<()> {
+        Ok(())
+    }
+
+    /// Called before the ssts of the table are compacted.
+    async fn before_compaction(&self, _table: &TableData) -> GenericResult<()> {
+        Ok(())
+    }
+
+    /// Called after the sst changes of a flush or compaction are persisted,
+    /// which can't be vetoed any more.
+    async fn after_sst_change(&self, _table: &TableData, _event: &SstChangeEvent) {}
+}
+
+pub type EngineHookRef = Arc<dyn EngineHook>;
+
+/// The writes are rejected once the node is degraded by the disk quota.
+#[async_trait]
+impl EngineHook for DiskGovernor {
+    async fn before_write(&self, _table: &TableData, _rows: &RowGroup) -> GenericResult<()> {
+        self.ensure_writable().box_err()
+    }
+}
+
+/// The hooks registered to the engine, called in the order of registration.
+#[derive(Debug, Clone, Default)]
+pub struct EngineHooks {
+    hooks: Vec<EngineHookRef>,
+}
+
+impl EngineHooks {
+    pub fn register(&mut self, hook: EngineHookRef) {
+        self.hooks.push(hook);
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    pub(crate) async fn before_write(
+        &self,
+        table: &TableData,
+        rows: &RowGroup,
+    ) -> GenericResult<()> {
+        for hook in &self.hooks {
+            hook.before_write(table, rows).await?;
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn before_flush(&self, table: &TableData) -> GenericResult<()> {
+        for hook in &self.hooks {
+            hook.before_flush(table).await?;
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn before_compaction(&self, table: &TableData) -> GenericResult<()> {
+        for hook in &self.hooks {
+            hook.before_compaction(table).await?;
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn after_sst_change(
+        &self,
+        table: &TableData,
+        kind: SstChangeKind,
+        edit_meta: &VersionEditMeta,
+    ) {
+        if self.hooks.is_empty() {
+            return;
+        }
+
+        let event = SstChangeEvent::new(kind, table, edit_meta);
+        for hook in &self.hooks {
+            hook.after_sst_change(table, &event).await;
+        }
+    }
+}
+
+/// Broadcast the sst changes to the subscribers, e.g. cache invalidators and
+/// index builders.
+///
+/// Slow subscribers lagging more than `capacity` events miss the oldest
+/// events, which is reported by [SstChangeSubscriber::recv].
+#[derive(Debug)]
+pub struct SstChangeNotifier {
+    sender: broadcast::Sender<Arc<SstChangeEvent>>,
+}
+
+impl SstChangeNotifier {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self { sender }
+    }
+
+    /// Subscribe the sst changes of the table, or of all the tables if
+    /// `table_id` is `None`.
+    pub fn subscribe(&self, table_id: Option<TableId>) -> SstChangeSubscriber {
+        SstChangeSubscriber {
+            receiver: self.sender.subscribe(),
+            table_id,
+        }
+    }
+}
+
+#[async_trait]
+impl EngineHook for SstChangeNotifier {
+    async fn after_sst_change(&self, _table: &TableData, event: &SstChangeEvent) {
+        // No subscriber is not an error.
+        let _ = self.sender.send(Arc::new(event.clone()));
+    }
+}
+
+pub struct SstChangeSubscriber {
+    receiver: broadcast::Receiver<Arc<SstChangeEvent>>,
+    table_id: Option<TableId>,
+}
+
+impl SstChangeSubscriber {
+    /// Receive the next sst change of the subscribed table.
+    pub async fn recv(&mut self) -> Result<Arc<SstChangeEvent>, RecvError> {
+        loop {
+            let event = self.receiver.recv().await?;
+            match self.table_id {
+                Some(table_id) if table_id != event.table_id => continue,
+                _ => return Ok(event),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_event(table_id: u64) -> Arc<SstChangeEvent> {
+        Arc::new(SstChangeEvent {
+            kind: SstChangeKind::Flush,
+            space_id: 0,
+            table_id: TableId::new(table_id),
+            table_name: format!("table_{table_id}"),
+            added: vec![],
+            deleted: vec![],
+            max_sequence: 0,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_sst_change_subscriber() {
+        let notifier = SstChangeNotifier::new(8);
+        let mut all = notifier.subscribe(None);
+        let mut table2 = notifier.subscribe(Some(TableId::new(2)));
+
+        for table_id in [1, 2, 3] {
+            notifier.sender.send(new_event(table_id)).unwrap();
+        }
+
+        for table_id in [1, 2, 3] {
+            assert_eq!(TableId::new(table_id), all.recv().await.unwrap().table_id);
+        }
+        assert_eq!(TableId::new(2), table2.recv().await.unwrap().table_id);
+        drop(notifier);
+        assert!(matches!(table2.recv().await, Err(RecvError::Closed)));
+    }
+}
@@ -20,12 +20,13 @@
 use std::collections::HashMap;
 
 use generic_error::BoxError;
-use logger::info;
+use logger::{info, warn};
 use snafu::{ensure, ResultExt};
 use table_engine::table::AlterSchemaRequest;
 use wal::{kv_encoder::LogBatchEncoder, manager::WriteContext};
 
 use crate::{
+    compaction::TableCompactionRequest,
     instance::{
         self,
         engine::{
@@ -37,11 +38,11 @@ use crate::{
         InstanceRef,
     },
     manifest::meta_edit::{
-        AlterOptionsMeta, AlterSchemaMeta, MetaEdit, MetaEditRequest, MetaUpdate,
+        AlterOptionsMeta, AlterSchemaMeta, ManifestExt, MetaEdit, MetaEditRequest, MetaUpdate,
     },
     payload::WritePayload,
     table::data::TableDataRef,
-    table_options,
+    table_options::{self, OptionsChange},
 };
 
 pub struct Alterer<'a> {
@@ -244,6 +245,10 @@ impl<'a> Alterer<'a> {
         if let Some(reason) = table_opts.check_validity() {
             return InvalidTableOptions { reason }.fail();
         }
+        if let Some(reason) = table_opts.check_schema_validity(&self.table_data.schema()) {
+            return InvalidTableOptions { reason }.fail();
+        }
+        let change = current_table_options.diff(&table_opts);
 
         let manifest_update = AlterOptionsMeta {
             space_id: self.table_data.space_id,
@@ -257,7 +262,11 @@ impl<'a> Alterer<'a> {
 
         // Write AlterOptions to Data Wal
         let alter_options_pb = manifest_update.clone().into();
-        let payload = WritePayload::AlterOption(&alter_options_pb);
+        let ext = ManifestExt::new(Some(&manifest_update.options));
+        let payload = WritePayload::AlterOption {
+            meta: &alter_options_pb,
+            ext: &ext,
+        };
 
         // Encode payload
         let table_location = self.table_data.table_location();
@@ -303,6 +312,38 @@ impl<'a> Alterer<'a> {
                 table_id: self.table_data.id,
             })?;
 
+        // The new options have been swapped into the table data by the manifest,
+        // and the readers, flush and compaction will load them on their next
+        // run, so only the changes needing extra actions are handled here.
+        self.apply_options_change(change).await;
+
         Ok(())
     }
+
+    async fn apply_options_change(&self, change: OptionsChange) {
+        if change.need_compaction() {
+            // Trigger a compaction to make the new compaction strategy, ttl or field groups
+            // take effect at once rather than in the next round of the
+            // scheduling.
+            let request = TableCompactionRequest::no_waiter(self.table_data.clone());
+            let succeed = self
+                .instance
+                .compaction_scheduler
+                .schedule_table_compaction(request)
+                .await;
+            if !succeed {
+                warn!(
+                    "Failed to schedule compaction after altering options, table:{}, change:{:?}",
+                    self.table_data.name, change
+                );
+            }
+        }
+
+        if change.memtable {
+            info!(
+                "Memtable options of table are altered and will take effect on the new memtables, table:{}",
+                self.table_data.name
+            );
+        }
+    }
 }
@@ -19,7 +19,7 @@
 
 use logger::{info, warn};
 use snafu::ResultExt;
-use table_engine::engine::CloseTableRequest;
+use table_engine::engine::{CloseTableRequest, ClosedTable};
 
 use crate::{
     instance::{
@@ -40,14 +40,17 @@ pub(crate) struct Closer {
 
 impl Closer {
     /// Close table need to be handled by write worker.
-    pub async fn close(&self, request: CloseTableRequest) -> Result<()> {
+    ///
+    /// Return the final sequences of the table, and the entries not flushed
+    /// are left in the wal to be replayed on next opening.
+    pub async fn close(&self, request: CloseTableRequest) -> Result<ClosedTable> {
         info!("Try to close table, request:{:?}", request);
 
         let table_data = match self.space.find_table_by_id(request.table_id) {
             Some(v) => v,
             None => {
                 warn!("try to close a closed table, request:{:?}", request);
-                return Ok(());
+                return Ok(ClosedTable::new(request.table_name));
             }
         };
 
@@ -68,11 +71,20 @@ impl Closer {
         // jobs.
         table_data.set_closed();
 
+        let closed_table = ClosedTable {
+            table_name: request.table_name,
+            last_sequence: table_data.last_sequence(),
+            flushed_sequence: table_data.current_version().flushed_sequence(),
+        };
         info!(
-            "table:{}-{} has been removed from the space_id:{}",
-            table_data.name, table_data.id, self.space.id
+            "table:{}-{} has been removed from the space_id:{}, last_sequence:{}, flushed_sequence:{}",
+            table_data.name,
+            table_data.id,
+            self.space.id,
+            closed_table.last_sequence,
+            closed_table.flushed_sequence
         );
-        Ok(())
+        Ok(closed_table)
     }
 
     async fn flush(&self, table_data: &TableDataRef) -> Result<()> {
@@ -17,6 +17,7 @@
 
 //! Create table logic of instance
 
+use common_types::datum::DatumKind;
 use generic_error::BoxError;
 use logger::info;
 use snafu::{ensure, OptionExt, ResultExt};
@@ -53,6 +54,24 @@ impl Instance {
             return InvalidTableOptions { reason }.fail();
         }
 
+        if let Some(column) = &table_opts.expire_at_column {
+            let schema = &params.table_schema;
+            let is_timestamp = schema
+                .index_of(column)
+                .map(|idx| schema.column(idx).data_type == DatumKind::Timestamp)
+                .unwrap_or(false);
+            if !is_timestamp {
+                return InvalidTableOptions {
+                    reason: format!("expire_at_column must be a timestamp column, column:{column}"),
+                }
+                .fail();
+            }
+        }
+
+        if let Some(reason) = table_opts.check_schema_validity(&params.table_schema) {
+            return InvalidTableOptions { reason }.fail();
+        }
+
         if let Some(partition_info) = &params.partition_info {
             let dedup_on_random_partition =
                 table_opts.need_dedup() && matches!(partition_info, PartitionInfo::Random(_));
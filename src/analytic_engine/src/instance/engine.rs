@@ -22,9 +22,12 @@ use std::sync::Arc;
 use common_types::{schema::Version, SequenceNumber};
 use generic_error::GenericError;
 use macros::define_result;
-use snafu::{Backtrace, OptionExt, Snafu};
+use snafu::{Backtrace, OptionExt, ResultExt, Snafu};
 use table_engine::{
-    engine::{CloseTableRequest, CreateTableRequest, DropTableRequest, OpenShardRequest},
+    engine::{
+        CloseTableRequest, ClosedTable, CreateTableRequest, DropTableRequest, OpenShardRequest,
+        TableWriteRequest,
+    },
     table::TableId,
 };
 use wal::manager::WalLocation;
@@ -259,6 +262,11 @@ pub enum Error {
         sequence: SequenceNumber,
         source: wal::manager::Error,
     },
+
+    #[snafu(display("Failed to write tables atomically, err:{}", source))]
+    WriteTables {
+        source: crate::instance::write::Error,
+    },
 }
 
 define_result!(Error);
@@ -297,7 +305,8 @@ impl From<Error> for table_engine::engine::Error {
             | Error::ReplayWalNoCause { .. }
             | Error::PurgeWal { .. }
             | Error::ReplayWalWithCause { .. }
-            | Error::InvalidTableOptions { .. } => Self::Unexpected {
+            | Error::InvalidTableOptions { .. }
+            | Error::WriteTables { .. } => Self::Unexpected {
                 source: Box::new(err),
             },
         }
@@ -379,12 +388,55 @@ impl Instance {
         Ok(space_table)
     }
 
+    /// Write to multiple tables of the same shard atomically.
+    pub async fn write_tables(
+        self: &Arc<Self>,
+        requests: Vec<TableWriteRequest>,
+    ) -> Result<Vec<usize>> {
+        let mut tables = Vec::with_capacity(requests.len());
+        for TableWriteRequest {
+            schema_id,
+            table_id,
+            request,
+        } in requests
+        {
+            let space_id = build_space_id(schema_id);
+            let space = self.find_space(space_id).context(SpaceNotExist {
+                space_id,
+                table: table_id.to_string(),
+            })?;
+            let space_table = match space.find_table_by_id(table_id) {
+                Some(table_data) => SpaceAndTable::new(space, table_data),
+                // The table may be closed by the idle table reaper.
+                None => self.reopen_idle_table(space_id, table_id).await?,
+            };
+            tables.push((space_table, request));
+        }
+
+        // All the tables should be in the same shard, which is validated later.
+        let write_epoch = tables.first().and_then(|(table, _)| {
+            let shard_id = table.table_data().shard_info.shard_id;
+            self.shard_write_epochs.current(shard_id)
+        });
+        self.write_tables_atomically(tables, write_epoch)
+            .await
+            .context(WriteTables)
+    }
+
     /// Drop a table under given space
     pub async fn drop_table(
         self: &Arc<Self>,
         space_id: SpaceId,
         request: DropTableRequest,
     ) -> Result<bool> {
+        // The table closed by the idle table reaper should be reopened before dropped.
+        if let Some(table_id) = self
+            .idle_closed_tables
+            .find_by_name(space_id, &request.table_name)
+        {
+            self.reopen_idle_table(space_id, table_id).await?;
+        }
+
         let space = self.find_space(space_id).context(SpaceNotExist {
             space_id,
             table: &request.table_name,
@@ -402,7 +454,10 @@ impl Instance {
         self: &Arc<Self>,
         space_id: SpaceId,
         request: CloseTableRequest,
-    ) -> Result<()> {
+    ) -> Result<ClosedTable> {
+        // The table closed by the idle table reaper shouldn't be reopened any more.
+        self.idle_closed_tables.remove(request.table_id);
+
         let space = self.find_space(space_id).context(SpaceNotExist {
             space_id,
             table: &request.table_name,
@@ -447,14 +502,16 @@ impl Instance {
 
         // Insert opened tables to spaces.
         for ((table_name, table_id), space) in spaces_of_tables {
-            let table_result = shard_result
-                .get(&table_id)
-                .with_context(|| OpenTablesOfShard {
-                    msg: format!(
+            let table_result =
+                shard_result
+                    .tables
+                    .get(&table_id)
+                    .with_context(|| OpenTablesOfShard {
+                        msg: format!(
                         "table not exist in result, table_id:{}, space_id:{shard_id}, shard_id:{}",
                         table_id, space.id
                     ),
-                })?;
+                    })?;
 
             // TODO: should not modify space here, maybe should place it into manifest.
             if table_result.is_err() {
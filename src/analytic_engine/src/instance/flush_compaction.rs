@@ -41,6 +41,7 @@ use tokio::{sync::oneshot, time::Instant};
 use wal::manager::WalLocation;
 
 use crate::{
+    hook::SstChangeKind,
     instance::{
         self, reorder_memtable::Reorder, serial_executor::TableFlushScheduler, SpaceStoreRef,
     },
@@ -158,6 +159,9 @@ pub enum Error {
 
     #[snafu(display("Failed to alloc file id, err:{}", source))]
     AllocFileId { source: data::Error },
+
+    #[snafu(display("Operation is rejected by hook, table:{}, err:{}", table, source))]
+    RejectedByHook { table: String, source: GenericError },
 }
 
 define_result!(Error);
@@ -309,6 +313,14 @@ impl FlushTask {
             return Ok(());
         }
 
+        self.space_store
+            .hooks
+            .before_flush(&self.table_data)
+            .await
+            .context(RejectedByHook {
+                table: &self.table_data.name,
+            })?;
+
         let request_id = RequestId::next_id();
 
         // Start flush duration timer.
@@ -355,7 +367,7 @@ impl FlushTask {
         let mut last_sequence = table_data.last_sequence();
         // Switch (freeze) all mutable memtables. And update segment duration if
         // suggestion is returned.
-        let mut need_reorder = table_data.enable_layered_memtable;
+        let mut need_reorder = table_data.enable_layered_memtable();
         if let Some(suggest_segment_duration) = current_version.suggest_duration() {
             info!(
                 "Update segment duration, table:{}, table_id:{}, segment_duration:{:?}",
@@ -504,17 +516,18 @@ impl FlushTask {
         );
 
         // Persist the flush result to manifest.
+        let edit_meta = VersionEditMeta {
+            space_id: self.table_data.space_id,
+            table_id: self.table_data.id,
+            flushed_sequence,
+            files_to_add: files_to_level0.clone(),
+            files_to_delete: vec![],
+            mems_to_remove: mems_to_flush.ids(),
+            max_file_id: 0,
+            delete_before: None,
+        };
         let edit_req = {
-            let edit_meta = VersionEditMeta {
-                space_id: self.table_data.space_id,
-                table_id: self.table_data.id,
-                flushed_sequence,
-                files_to_add: files_to_level0.clone(),
-                files_to_delete: vec![],
-                mems_to_remove: mems_to_flush.ids(),
-                max_file_id: 0,
-            };
-            let meta_update = MetaUpdate::VersionEdit(edit_meta);
+            let meta_update = MetaUpdate::VersionEdit(edit_meta.clone());
             MetaEditRequest {
                 shard_info: self.table_data.shard_info,
                 meta_edit: MetaEdit::Update(meta_update),
@@ -527,6 +540,10 @@ impl FlushTask {
             .apply_edit(edit_req)
             .await
             .context(StoreVersionEdit)?;
+        self.space_store
+            .hooks
+            .after_sst_change(&self.table_data, SstChangeKind::Flush, &edit_meta)
+            .await;
 
         // Mark sequence <= flushed_sequence to be deleted.
         let table_location = self.table_data.table_location();
@@ -540,6 +557,9 @@ impl FlushTask {
                 wal_location,
                 sequence: flushed_sequence,
             })?;
+        self.table_data
+            .metrics
+            .on_wal_flushed(self.table_data.last_sequence(), flushed_sequence);
 
         Ok(())
     }
@@ -573,12 +593,14 @@ impl FlushTask {
         let mut sst_handlers = Vec::with_capacity(time_ranges.len());
         let mut file_ids = Vec::with_capacity(time_ranges.len());
 
+        let table_options = self.table_data.table_options();
         let sst_write_options = SstWriteOptions {
-            storage_format_hint: self.table_data.table_options().storage_format_hint,
-            num_rows_per_row_group: self.table_data.table_options().num_rows_per_row_group,
-            compression: self.table_data.table_options().compression,
+            storage_format_hint: table_options.storage_format_hint,
+            num_rows_per_row_group: table_options.num_rows_per_row_group,
+            compression: table_options.compression,
             max_buffer_size: self.write_sst_max_buffer_size,
             column_stats: Default::default(),
+            field_groups: table_options.field_groups.clone(),
         };
 
         for time_range in &time_ranges {
@@ -601,7 +623,7 @@ impl FlushTask {
             };
 
             let store = self.space_store.clone();
-            let storage_format_hint = self.table_data.table_options().storage_format_hint;
+            let storage_format_hint = table_options.storage_format_hint;
             let sst_write_options = sst_write_options.clone();
             let request_id = request_id.clone();
 
@@ -745,13 +767,15 @@ impl FlushTask {
             .context(AllocFileId)?;
 
         let sst_file_path = self.table_data.sst_file_path(file_id);
-        let storage_format_hint = self.table_data.table_options().storage_format_hint;
+        let table_options = self.table_data.table_options();
+        let storage_format_hint = table_options.storage_format_hint;
         let sst_write_options = SstWriteOptions {
             storage_format_hint,
-            num_rows_per_row_group: self.table_data.table_options().num_rows_per_row_group,
-            compression: self.table_data.table_options().compression,
+            num_rows_per_row_group: table_options.num_rows_per_row_group,
+            compression: table_options.compression,
             max_buffer_size: self.write_sst_max_buffer_size,
             column_stats: Default::default(),
+            field_groups: table_options.field_groups.clone(),
         };
         let mut writer = self
             .space_store
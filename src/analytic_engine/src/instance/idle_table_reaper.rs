@@ -0,0 +1,420 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Idle table reaper closes the tables idle for a long time to reclaim the
+//! memory, and the closed tables will be reopened lazily on access.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, Weak},
+};
+
+use common_types::table::ShardId;
+use lazy_static::lazy_static;
+use logger::{error, info, warn};
+use prometheus::{register_int_counter, register_int_gauge, IntCounter, IntGauge};
+use runtime::{JoinHandle, Runtime};
+use serde::{Deserialize, Serialize};
+use snafu::{OptionExt, ResultExt};
+use table_engine::{
+    engine::{OpenShardRequest, TableDef},
+    table::TableId,
+    ANALYTIC_ENGINE_TYPE,
+};
+use time_ext::ReadableDuration;
+use tokio::{
+    sync::oneshot::{self, Receiver, Sender},
+    time,
+};
+
+use crate::{
+    engine::build_space_id,
+    instance::{
+        engine::{DoManifestSnapshot, FlushTable, OpenTablesOfShard, Result, TableNotExist},
+        flush_compaction::TableFlushOptions,
+        Instance,
+    },
+    manifest::SnapshotRequest,
+    space::{SpaceAndTable, SpaceId},
+    table::data::TableDataRef,
+};
+
+lazy_static! {
+    static ref IDLE_TABLE_CLOSED_COUNTER: IntCounter = register_int_counter!(
+        "idle_table_closed_counter",
+        "Counter of tables closed by the idle table reaper"
+    )
+    .unwrap();
+    static ref IDLE_TABLE_REOPENED_COUNTER: IntCounter = register_int_counter!(
+        "idle_table_reopened_counter",
+        "Counter of idle closed tables reopened on access"
+    )
+    .unwrap();
+    static ref IDLE_CLOSED_TABLES_GAUGE: IntGauge = register_int_gauge!(
+        "idle_closed_tables",
+        "Number of tables closed by the idle table reaper"
+    )
+    .unwrap();
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct IdleTableReaperConfig {
+    /// Whether to close the idle tables
+    pub enable: bool,
+    /// The interval to check the idle tables
+    pub check_interval: ReadableDuration,
+    /// The table is considered idle if it is not read or written for this
+    /// duration
+    pub idle_threshold: ReadableDuration,
+    /// The table won't be closed within this duration after it's (re)opened,
+    /// which avoids the table being closed and reopened back and forth.
+    pub min_open_duration: ReadableDuration,
+    /// Max number of tables to close in one check
+    pub max_tables_per_check: usize,
+}
+
+impl Default for IdleTableReaperConfig {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            check_interval: ReadableDuration::minutes(5),
+            idle_threshold: ReadableDuration::hours(2),
+            min_open_duration: ReadableDuration::hours(1),
+            max_tables_per_check: 100,
+        }
+    }
+}
+
+impl IdleTableReaperConfig {
+    /// Check whether the table is idle at `now`, all the times are in
+    /// milliseconds.
+    fn is_idle(&self, last_access_time: u64, opened_time: u64, now: u64) -> bool {
+        now.saturating_sub(last_access_time) >= self.idle_threshold.as_millis()
+            && now.saturating_sub(opened_time) >= self.min_open_duration.as_millis()
+    }
+}
+
+/// The information to reopen the table closed by the idle table reaper.
+#[derive(Debug, Clone)]
+struct IdleClosedTable {
+    shard_id: ShardId,
+    table_def: TableDef,
+}
+
+/// Tables closed by the idle table reaper.
+#[derive(Default)]
+pub(crate) struct IdleClosedTables {
+    tables: Mutex<HashMap<TableId, IdleClosedTable>>,
+    /// Serialize the reopening of tables to avoid opening a table concurrently.
+    reopen_lock: tokio::sync::Mutex<()>,
+}
+
+impl IdleClosedTables {
+    fn insert(&self, table_data: &TableDataRef) {
+        let catalog_info = &table_data.table_catalog_info;
+        let table = IdleClosedTable {
+            shard_id: table_data.shard_info.shard_id,
+            table_def: TableDef {
+                catalog_name: catalog_info.catalog_name.clone(),
+                schema_name: catalog_info.schema_name.clone(),
+                schema_id: catalog_info.schema_id,
+                id: table_data.id,
+                name: table_data.name.clone(),
+            },
+        };
+
+        let mut tables = self.tables.lock().unwrap();
+        tables.insert(table_data.id, table);
+        IDLE_CLOSED_TABLES_GAUGE.set(tables.len() as i64);
+    }
+
+    /// Remove the table which is closed or reopened.
+    pub fn remove(&self, table_id: TableId) -> bool {
+        let mut tables = self.tables.lock().unwrap();
+        let removed = tables.remove(&table_id).is_some();
+        IDLE_CLOSED_TABLES_GAUGE.set(tables.len() as i64);
+
+        removed
+    }
+
+    fn get(&self, table_id: TableId) -> Option<IdleClosedTable> {
+        self.tables.lock().unwrap().get(&table_id).cloned()
+    }
+
+    /// Find the idle closed table by its name in the space.
+    pub fn find_by_name(&self, space_id: SpaceId, table_name: &str) -> Option<TableId> {
+        let tables = self.tables.lock().unwrap();
+        tables
+            .values()
+            .find(|v| {
+                build_space_id(v.table_def.schema_id) == space_id && v.table_def.name == table_name
+            })
+            .map(|v| v.table_def.id)
+    }
+}
+
+/// Background job to close the idle tables periodically.
+pub(crate) struct IdleTableReaper {
+    stop_sender: Mutex<Option<Sender<()>>>,
+    handle: tokio::sync::Mutex<Option<JoinHandle<()>>>,
+}
+
+impl IdleTableReaper {
+    /// Start the reaper if it's enabled.
+    ///
+    /// Only weak reference of the instance is held to avoid the reference
+    /// cycle.
+    pub fn start(
+        runtime: &Runtime,
+        instance: Weak<Instance>,
+        config: IdleTableReaperConfig,
+    ) -> Option<Self> {
+        if !config.enable {
+            return None;
+        }
+
+        let (tx, rx) = oneshot::channel();
+        let handle = runtime.spawn(async move {
+            Self::reap_loop(instance, config, rx).await;
+        });
+
+        Some(Self {
+            stop_sender: Mutex::new(Some(tx)),
+            handle: tokio::sync::Mutex::new(Some(handle)),
+        })
+    }
+
+    pub async fn stop(&self) -> std::result::Result<(), runtime::Error> {
+        info!("Try to stop idle table reaper");
+
+        if let Some(tx) = self.stop_sender.lock().unwrap().take() {
+            if tx.send(()).is_err() {
+                error!("Idle table reaper already exited");
+            }
+        }
+
+        let mut handle = self.handle.lock().await;
+        // Also clear the handle to avoid await a ready future.
+        if let Some(h) = handle.take() {
+            h.await?;
+        }
+
+        Ok(())
+    }
+
+    async fn reap_loop(
+        instance: Weak<Instance>,
+        config: IdleTableReaperConfig,
+        mut stop_receiver: Receiver<()>,
+    ) {
+        info!("Idle table reaper start, config:{config:?}");
+
+        loop {
+            // Stop if the stopping message is received or the sender is dropped.
+            if time::timeout(config.check_interval.0, &mut stop_receiver)
+                .await
+                .is_ok()
+            {
+                break;
+            }
+
+            let Some(instance) = instance.upgrade() else {
+                break;
+            };
+            instance.reap_idle_tables(&config).await;
+        }
+
+        info!("Idle table reaper exit");
+    }
+}
+
+impl Instance {
+    /// Close the tables which are idle for a long time.
+    async fn reap_idle_tables(&self, config: &IdleTableReaperConfig) {
+        let mut tables = Vec::new();
+        self.space_store.list_all_tables(&mut tables);
+
+        let now = time_ext::current_time_millis();
+        let idle_tables = tables
+            .into_iter()
+            .filter(|v| config.is_idle(v.last_access_time(), v.opened_time(), now))
+            .take(config.max_tables_per_check);
+        for table_data in idle_tables {
+            match self.close_idle_table(&table_data, config).await {
+                Ok(true) => {
+                    IDLE_TABLE_CLOSED_COUNTER.inc();
+                    info!(
+                        "Idle table is closed, table:{}, table_id:{}",
+                        table_data.name, table_data.id
+                    );
+                }
+                Ok(false) => {}
+                Err(e) => {
+                    warn!(
+                        "Failed to close idle table, table:{}, table_id:{}, err:{e}",
+                        table_data.name, table_data.id
+                    );
+                }
+            }
+        }
+    }
+
+    /// Flush and close the table if it's still idle, returns true if the table
+    /// is closed.
+    pub(crate) async fn close_idle_table(
+        &self,
+        table_data: &TableDataRef,
+        config: &IdleTableReaperConfig,
+    ) -> Result<bool> {
+        let Some(space) = self.find_space(table_data.space_id) else {
+            return Ok(false);
+        };
+
+        let mut serial_exec = table_data.serial_exec.lock().await;
+        // The table may be accessed or closed during waiting for the lock.
+        let now = time_ext::current_time_millis();
+        if !table_data.allow_compaction()
+            || !config.is_idle(table_data.last_access_time(), table_data.opened_time(), now)
+        {
+            return Ok(false);
+        }
+
+        // Flush the memtables to reclaim the memory and speed up the reopening.
+        let flush_scheduler = serial_exec.flush_scheduler();
+        self.make_flusher()
+            .do_flush(flush_scheduler, table_data, TableFlushOptions::default())
+            .await
+            .context(FlushTable {
+                space_id: space.id,
+                table: &table_data.name,
+                table_id: table_data.id,
+            })?;
+        let snapshot_request = SnapshotRequest {
+            space_id: space.id,
+            table_id: table_data.id,
+            shard_id: table_data.shard_info.shard_id,
+            table_catalog_info: table_data.table_catalog_info.clone(),
+        };
+        self.space_store
+            .manifest
+            .do_snapshot(snapshot_request)
+            .await
+            .context(DoManifestSnapshot {
+                space_id: space.id,
+                table: &table_data.name,
+            })?;
+
+        // Record the table before removing it from the space, so that it can always be
+        // found by the reopening.
+        self.idle_closed_tables.insert(table_data);
+        space.remove_table(&table_data.name);
+        table_data.set_idle_closed();
+
+        Ok(true)
+    }
+
+    /// Close the table whose memtables are inconsistent with the wal, without
+    /// flushing them, so the memtables are rebuilt from the wal when it's
+    /// reopened on the next access.
+    ///
+    /// The caller should hold the serial executor of the table.
+    pub(crate) fn close_table_for_recovery(&self, table_data: &TableDataRef) {
+        let Some(space) = self.find_space(table_data.space_id) else {
+            return;
+        };
+
+        self.idle_closed_tables.insert(table_data);
+        space.remove_table(&table_data.name);
+        table_data.set_idle_closed();
+    }
+
+    /// Reopen the table closed by the idle table reaper.
+    ///
+    /// The opened table is returned directly if it has been reopened.
+    pub(crate) async fn reopen_idle_table(
+        self: &Arc<Self>,
+        space_id: SpaceId,
+        table_id: TableId,
+    ) -> Result<SpaceAndTable> {
+        let _reopen_guard = self.idle_closed_tables.reopen_lock.lock().await;
+
+        let opened = self
+            .find_space(space_id)
+            .and_then(|space| space.find_table_by_id(table_id).map(|v| (space, v)));
+        if let Some((space, table_data)) = opened {
+            return Ok(SpaceAndTable::new(space, table_data));
+        }
+
+        let IdleClosedTable {
+            shard_id,
+            table_def,
+        } = self
+            .idle_closed_tables
+            .get(table_id)
+            .with_context(|| TableNotExist {
+                msg: format!("table is not idle closed, space_id:{space_id}, table_id:{table_id}"),
+            })?;
+        info!("Try to reopen idle closed table, shard_id:{shard_id}, table_def:{table_def:?}");
+
+        let request = OpenShardRequest {
+            shard_id,
+            table_defs: vec![table_def],
+            engine: ANALYTIC_ENGINE_TYPE.to_string(),
+        };
+        let mut shard_result = self.open_tables_of_shard(request).await?;
+        let space_table = shard_result
+            .tables
+            .remove(&table_id)
+            .with_context(|| OpenTablesOfShard {
+                msg: format!("table not exist in result, table_id:{table_id}"),
+            })??
+            .with_context(|| TableNotExist {
+                msg: format!("table not found when reopening, table_id:{table_id}"),
+            })?;
+
+        self.idle_closed_tables.remove(table_id);
+        IDLE_TABLE_REOPENED_COUNTER.inc();
+
+        Ok(space_table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_idle_table_with_hysteresis() {
+        let config = IdleTableReaperConfig {
+            enable: true,
+            check_interval: ReadableDuration::secs(1),
+            idle_threshold: ReadableDuration::secs(10),
+            min_open_duration: ReadableDuration::secs(60),
+            max_tables_per_check: 10,
+        };
+
+        // Not idle long enough.
+        assert!(!config.is_idle(100_000, 0, 105_000));
+        assert!(config.is_idle(100_000, 0, 110_000));
+        // Reopened recently.
+        assert!(!config.is_idle(100_000, 80_000, 110_000));
+        assert!(config.is_idle(100_000, 80_000, 140_000));
+        // Time goes back.
+        assert!(!config.is_idle(100_000, 0, 90_000));
+    }
+}
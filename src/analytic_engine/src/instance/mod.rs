@@ -26,31 +26,54 @@ mod create;
 mod drop;
 pub mod engine;
 pub mod flush_compaction;
+pub(crate) mod idle_table_reaper;
 pub(crate) mod mem_collector;
 pub mod open;
+mod open_check;
 mod read;
 mod reorder_memtable;
+pub(crate) mod replay_throttle;
 pub(crate) mod serial_executor;
+pub(crate) mod sst_reconciler;
+pub(crate) mod summary_refresher;
+pub mod tiering_manifest;
 pub mod wal_replayer;
+mod wal_scan;
 pub(crate) mod write;
+pub(crate) mod write_epoch;
+pub(crate) mod write_presort;
 
 use std::sync::Arc;
 
-use common_types::{projected_schema::RowProjectorBuilder, table::TableId};
+use common_types::{projected_schema::RowProjectorBuilder, table::TableId, time::Timestamp};
 use generic_error::{BoxError, GenericError};
 use logger::{error, info};
 use macros::define_result;
 use mem_collector::MemUsageCollector;
-use runtime::{PriorityRuntime, Runtime};
+use runtime::{Priority, PriorityRuntime, Runtime};
 use snafu::{ResultExt, Snafu};
-use table_engine::{engine::EngineRuntimes, predicate::PredicateRef, table::FlushRequest};
+use table_engine::{
+    engine::EngineRuntimes,
+    predicate::PredicateRef,
+    table::{FlushRequest, QueryStatsRef},
+};
 use time_ext::ReadableDuration;
 use tokio::sync::oneshot::{self, error::RecvError};
 use wal::manager::{WalLocation, WalManagerRef};
 
-use self::flush_compaction::{Flusher, TableFlushOptions};
+use self::{
+    flush_compaction::{Flusher, TableFlushOptions},
+    idle_table_reaper::{IdleClosedTables, IdleTableReaper},
+    replay_throttle::ReplayThrottleRef,
+    sst_reconciler::SstReconcilerConfig,
+    summary_refresher::{SummaryRefresher, TableSummaryConfig},
+    tiering_manifest::TieringManifestEmitter,
+    write_epoch::ShardWriteEpochs,
+    write_presort::WritePresortConfig,
+};
 use crate::{
     compaction::{scheduler::CompactionSchedulerRef, TableCompactionRequest},
+    hook::EngineHooks,
     manifest::ManifestRef,
     row_iter::IterOptions,
     space::{SpaceId, SpaceRef, SpacesRef},
@@ -64,7 +87,7 @@ use crate::{
         metrics::MaybeTableLevelMetrics,
     },
     table::data::{TableDataRef, TableShardInfo},
-    RecoverMode, TableOptions, WalEncodeConfig,
+    AutoRecoverConfig, RecoverMode, TableOptions, WalEncodeConfig, WalReplayCorruptionPolicy,
 };
 
 #[allow(clippy::enum_variant_names)]
@@ -73,6 +96,15 @@ pub enum Error {
     #[snafu(display("Failed to stop file purger, err:{}", source))]
     StopFilePurger { source: crate::sst::file::Error },
 
+    #[snafu(display("Failed to stop idle table reaper, err:{}", source))]
+    StopIdleTableReaper { source: runtime::Error },
+
+    #[snafu(display("Failed to stop tiering manifest emitter, err:{}", source))]
+    StopTieringManifestEmitter { source: runtime::Error },
+
+    #[snafu(display("Failed to stop table summary refresher, err:{}", source))]
+    StopSummaryRefresher { source: runtime::Error },
+
     #[snafu(display("Failed to stop compaction scheduler, err:{}", source))]
     StopScheduler {
         source: crate::compaction::scheduler::Error,
@@ -107,6 +139,8 @@ pub struct SpaceStore {
     store_picker: ObjectStorePickerRef,
     /// Sst factory.
     sst_factory: SstFactoryRef,
+    /// Hooks intercepting writes, flushes and compactions.
+    pub(crate) hooks: EngineHooks,
 }
 
 pub type SpaceStoreRef = Arc<SpaceStore>;
@@ -176,12 +210,25 @@ pub struct Instance {
     pub(crate) space_write_buffer_size: usize,
     /// Replay wal batch size
     pub(crate) replay_batch_size: usize,
+    /// Throttle of the wal replay shared with the foreground writes
+    pub(crate) replay_throttle: ReplayThrottleRef,
+    /// Max memtable memory of the tables replayed together, zero means no
+    /// limit
+    pub(crate) replay_memory_budget: usize,
+    /// Max number of tables replayed concurrently
+    pub(crate) replay_parallelism: usize,
+    pub(crate) wal_replay_corruption_policy: WalReplayCorruptionPolicy,
+    /// Max number of the latest write batch ids of a table kept to dedup the
+    /// wal replay
+    pub(crate) replay_dedup_window: usize,
     /// Write sst max buffer size
     pub(crate) write_sst_max_buffer_size: usize,
     /// The min interval between flushes
     pub(crate) min_flush_interval: ReadableDuration,
     /// Max retry limit to flush memtables
     pub(crate) max_retry_flush_limit: usize,
+    /// Max retry limit to write the log batch into wal
+    pub(crate) max_retry_wal_write_limit: usize,
     /// Max bytes per write batch
     pub(crate) max_bytes_per_write_batch: Option<usize>,
     /// The interval for sampling the mem size
@@ -190,13 +237,41 @@ pub struct Instance {
     pub(crate) scan_options: ScanOptions,
     pub(crate) iter_options: Option<IterOptions>,
     pub(crate) recover_mode: RecoverMode,
+    pub(crate) auto_recover: AutoRecoverConfig,
+    pub(crate) sst_reconciler: SstReconcilerConfig,
     pub(crate) wal_encode: WalEncodeConfig,
     pub(crate) disable_wal: bool,
+    /// Options of sorting the rows before inserting into the memtable
+    pub(crate) write_presort: WritePresortConfig,
+    /// Epochs of the shard locks to fence the stale writes
+    pub(crate) shard_write_epochs: ShardWriteEpochs,
+    /// Background job to close the idle tables, None if it's disabled
+    idle_table_reaper: Option<IdleTableReaper>,
+    /// Tables closed by the idle table reaper
+    pub(crate) idle_closed_tables: IdleClosedTables,
+    /// Background job to emit the tiering manifests, None if it's disabled
+    tiering_manifest_emitter: Option<TieringManifestEmitter>,
+    /// Options of the table summaries used as the estimated statistics
+    pub(crate) table_summary: TableSummaryConfig,
+    /// Background job to refresh the table summaries, None if it's disabled
+    summary_refresher: Option<SummaryRefresher>,
 }
 
 impl Instance {
     /// Close the instance gracefully.
     pub async fn close(&self) -> Result<()> {
+        if let Some(reaper) = &self.idle_table_reaper {
+            reaper.stop().await.context(StopIdleTableReaper)?;
+        }
+
+        if let Some(emitter) = &self.tiering_manifest_emitter {
+            emitter.stop().await.context(StopTieringManifestEmitter)?;
+        }
+
+        if let Some(refresher) = &self.summary_refresher {
+            refresher.stop().await.context(StopSummaryRefresher)?;
+        }
+
         self.file_purger.stop().await.context(StopFilePurger)?;
 
         self.space_store.close().await?;
@@ -274,6 +349,99 @@ impl Instance {
                 table: &table_data.name,
             })
     }
+
+    /// Delete the data whose timestamp is before `before` (in the timestamp
+    /// precision of the table).
+    ///
+    /// The bound is persisted in the manifest along with dropping the ssts
+    /// lying entirely before it. The rows before it left in the memtables and
+    /// other ssts are filtered out by the reads and dropped by the later
+    /// compactions.
+    pub async fn manual_delete_table(
+        &self,
+        table_data: &TableDataRef,
+        before: Timestamp,
+    ) -> Result<()> {
+        let (request, rx) = TableCompactionRequest::delete_before(table_data.clone(), before);
+        let succeed = self
+            .compaction_scheduler
+            .schedule_table_compaction(request)
+            .await;
+        if !succeed {
+            error!("Failed to schedule deletion, table:{}", table_data.name);
+        }
+
+        rx.await
+            .context(RecvManualOpResult {
+                op: "delete",
+                table: &table_data.name,
+            })?
+            .box_err()
+            .context(ManualOp {
+                op: "delete",
+                table: &table_data.name,
+            })
+    }
+
+    /// Compact the given ssts of the table at the `level` into one, bypassing
+    /// the compaction picker.
+    #[cfg(test)]
+    pub(crate) async fn compact_ssts_for_test(
+        &self,
+        table_data: &TableDataRef,
+        level: crate::sst::file::Level,
+        files: Vec<crate::sst::file::FileHandle>,
+    ) -> Result<()> {
+        use crate::{
+            compaction::{
+                compactor::Compactor, runner::local_runner::LocalCompactionRunner,
+                CompactionInputFiles, CompactionTaskBuilder,
+            },
+            sst::factory::SstWriteOptions,
+        };
+
+        let runner = LocalCompactionRunner::new(
+            self.runtimes.compact_runtime.clone(),
+            &crate::Config::default(),
+            self.space_store.sst_factory.clone(),
+            self.space_store.store_picker.clone(),
+            self.meta_cache.clone(),
+        );
+        let compactor = Compactor::new(
+            Box::new(runner),
+            self.space_store.manifest.clone(),
+            self.space_store.hooks.clone(),
+        );
+        let mut builder = CompactionTaskBuilder::with_expired(vec![]);
+        builder.add_inputs(CompactionInputFiles {
+            level,
+            files,
+            output_level: level,
+        });
+        let table_options = table_data.table_options();
+        let sst_write_options = SstWriteOptions {
+            storage_format_hint: table_options.storage_format_hint,
+            num_rows_per_row_group: table_options.num_rows_per_row_group,
+            compression: table_options.compression,
+            max_buffer_size: self.write_sst_max_buffer_size,
+            column_stats: Default::default(),
+            field_groups: table_options.field_groups.clone(),
+        };
+
+        compactor
+            .compact_table(
+                common_types::request_id::RequestId::next_id(),
+                table_data,
+                &builder.build(),
+                &sst_write_options,
+            )
+            .await
+            .box_err()
+            .context(ManualOp {
+                op: "compact",
+                table: &table_data.name,
+            })
+    }
 }
 
 // TODO(yingwen): Instance builder
@@ -292,6 +460,16 @@ impl Instance {
             && self.space_store.total_memory_usage_space() >= self.db_write_buffer_size
     }
 
+    /// Ratio of the total memtable memory usage to the db_write_buffer_size,
+    /// zero if the db_write_buffer_size is unlimited.
+    pub fn write_pressure(&self) -> f64 {
+        if self.db_write_buffer_size == 0 {
+            return 0.0;
+        }
+
+        self.space_store.total_memory_usage_space() as f64 / self.db_write_buffer_size as f64
+    }
+
     #[inline]
     fn read_runtime(&self) -> &PriorityRuntime {
         &self.runtimes.read_runtime
@@ -338,6 +516,7 @@ pub struct SstReadOptionsBuilder {
     num_rows_per_row_group: usize,
     predicate: PredicateRef,
     meta_cache: Option<MetaCacheRef>,
+    query_stats: Option<QueryStatsRef>,
     runtime: Arc<Runtime>,
 }
 
@@ -358,10 +537,17 @@ impl SstReadOptionsBuilder {
             num_rows_per_row_group,
             predicate,
             meta_cache,
+            query_stats: None,
             runtime,
         }
     }
 
+    /// Collect the bytes fetched by the sst reads into the `query_stats`.
+    pub fn query_stats(mut self, query_stats: Option<QueryStatsRef>) -> Self {
+        self.query_stats = query_stats;
+        self
+    }
+
     pub fn build(self, row_projector_builder: RowProjectorBuilder) -> SstReadOptions {
         SstReadOptions {
             maybe_table_level_metrics: self.maybe_table_level_metrics.clone(),
@@ -371,6 +557,7 @@ impl SstReadOptionsBuilder {
             predicate: self.predicate,
             meta_cache: self.meta_cache,
             scan_options: self.scan_options,
+            query_stats: self.query_stats,
             runtime: self.runtime,
         }
     }
@@ -380,14 +567,28 @@ impl SstReadOptionsBuilder {
 #[derive(Debug, Clone, Copy)]
 pub enum ScanType {
     Query,
+    /// Query decided to be expensive, e.g. an export-style scan over a wide
+    /// time range, whose data is unlikely to be read again soon.
+    ExpensiveQuery,
     Compaction,
 }
 
+impl ScanType {
+    /// Expensive queries are scheduled with the low priority, and their data
+    /// shouldn't be filled into the cache for the frequent queries.
+    pub fn from_query_priority(priority: Priority) -> Self {
+        match priority {
+            Priority::High => ScanType::Query,
+            Priority::Low => ScanType::ExpensiveQuery,
+        }
+    }
+}
+
 impl From<ScanType> for ReadFrequency {
     fn from(value: ScanType) -> Self {
         match value {
             ScanType::Query => ReadFrequency::Frequent,
-            ScanType::Compaction => ReadFrequency::Once,
+            ScanType::ExpensiveQuery | ScanType::Compaction => ReadFrequency::Once,
         }
     }
 }
@@ -23,7 +23,8 @@ use std::{
 };
 
 use common_types::table::ShardId;
-use logger::{error, info};
+use futures::{stream, StreamExt};
+use logger::{error, info, warn};
 use object_store::ObjectStoreRef;
 use snafu::ResultExt;
 use table_engine::{engine::TableDef, table::TableId};
@@ -39,8 +40,14 @@ use crate::{
     instance::{
         engine::{OpenManifest, OpenTablesOfShard, ReadMetaUpdate, Result},
         flush_compaction::Flusher,
+        idle_table_reaper::{IdleClosedTables, IdleTableReaper},
         mem_collector::MemUsageCollector,
-        wal_replayer::{ReplayMode, WalReplayer},
+        replay_throttle::{ReplayThrottle, ReplayThrottleRef},
+        sst_reconciler::{self, SstReconcilerConfig},
+        summary_refresher::SummaryRefresher,
+        tiering_manifest::TieringManifestEmitter,
+        wal_replayer::{ReplayMode, SkippedLogs, WalReplayer},
+        write_epoch::ShardWriteEpochs,
         Instance, InstanceRef, SpaceStore,
     },
     manifest::{details::ManifestImpl, LoadRequest, Manifest, ManifestRef},
@@ -52,9 +59,12 @@ use crate::{
     },
     table::data::{TableCatalogInfo, TableDataRef},
     table_meta_set_impl::TableMetaSetImpl,
-    RecoverMode,
+    AutoRecoverConfig, RecoverMode, WalReplayCorruptionPolicy,
 };
 
+/// Max number of tables to reconcile the ssts concurrently.
+const RECONCILE_SSTS_CONCURRENCY: usize = 16;
+
 pub(crate) struct InstanceContext {
     pub instance: InstanceRef,
     // TODO: unused now, will be used in remote compaction.
@@ -140,6 +150,7 @@ impl Instance {
             wal_manager: wal_manager.clone(),
             store_picker: store_picker.clone(),
             sst_factory,
+            hooks: ctx.hooks.clone(),
         });
 
         let scheduler_config = ctx.config.compaction.clone();
@@ -157,13 +168,20 @@ impl Instance {
             background_read_parallelism: ctx.config.sst_background_read_parallelism,
             max_record_batches_in_flight: ctx.config.scan_max_record_batches_in_flight,
             num_streams_to_prefetch: ctx.config.num_streams_to_prefetch,
+            get_range_coalesce_gap: ctx
+                .config
+                .sst_get_range_coalesce_gap
+                .map(|v| v.as_byte() as usize),
         };
 
         let iter_options = ctx
             .config
             .scan_batch_size
             .map(|batch_size| IterOptions { batch_size });
-        let instance = Arc::new(Instance {
+        let idle_table_reaper_config = ctx.config.idle_table_reaper.clone();
+        let tiering_manifest_config = ctx.config.tiering_manifest.clone();
+        let table_summary_config = ctx.config.table_summary.clone();
+        let instance = Arc::new_cyclic(|weak_instance| Instance {
             space_store,
             runtimes: ctx.runtimes.clone(),
             table_opts: ctx.config.table_opts.clone(),
@@ -176,9 +194,15 @@ impl Instance {
             db_write_buffer_size: ctx.config.db_write_buffer_size,
             space_write_buffer_size: ctx.config.space_write_buffer_size,
             replay_batch_size: ctx.config.replay_batch_size,
+            replay_throttle: Arc::new(ReplayThrottle::new(ctx.config.replay_throttle.clone())),
+            replay_memory_budget: ctx.config.replay_memory_budget.as_byte() as usize,
+            replay_parallelism: ctx.config.replay_parallelism.max(1),
+            wal_replay_corruption_policy: ctx.config.wal_replay_corruption_policy,
+            replay_dedup_window: ctx.config.replay_dedup_window,
             write_sst_max_buffer_size: ctx.config.write_sst_max_buffer_size.as_byte() as usize,
             min_flush_interval: ctx.config.min_flush_interval,
             max_retry_flush_limit: ctx.config.max_retry_flush_limit,
+            max_retry_wal_write_limit: ctx.config.max_retry_wal_write_limit,
             mem_usage_sampling_interval: ctx.config.mem_usage_sampling_interval,
             max_bytes_per_write_batch: ctx
                 .config
@@ -187,8 +211,29 @@ impl Instance {
             iter_options,
             scan_options,
             recover_mode: ctx.config.recover_mode,
+            auto_recover: ctx.config.auto_recover,
+            sst_reconciler: ctx.config.sst_reconciler.clone(),
             wal_encode: ctx.config.wal_encode,
             disable_wal: ctx.config.wal.disable_data,
+            write_presort: ctx.config.write_presort.clone(),
+            shard_write_epochs: ShardWriteEpochs::default(),
+            idle_table_reaper: IdleTableReaper::start(
+                &default_runtime,
+                weak_instance.clone(),
+                idle_table_reaper_config,
+            ),
+            idle_closed_tables: IdleClosedTables::default(),
+            tiering_manifest_emitter: TieringManifestEmitter::start(
+                &default_runtime,
+                weak_instance.clone(),
+                tiering_manifest_config,
+            ),
+            table_summary: table_summary_config.clone(),
+            summary_refresher: SummaryRefresher::start(
+                &default_runtime,
+                weak_instance.clone(),
+                table_summary_config,
+            ),
         });
 
         Ok(instance)
@@ -204,9 +249,21 @@ impl Instance {
             self.space_store.manifest.clone(),
             self.space_store.wal_manager.clone(),
             self.replay_batch_size,
+            self.replay_throttle.clone(),
+            self.replay_memory_budget,
+            self.replay_parallelism,
+            self.wal_replay_corruption_policy,
+            self.replay_dedup_window,
             self.make_flusher(),
             self.max_retry_flush_limit,
             self.recover_mode,
+            self.auto_recover,
+            self.sst_reconciler.enable.then(|| {
+                (
+                    self.space_store.store_picker().default_store().clone(),
+                    self.sst_reconciler.clone(),
+                )
+            }),
         )?;
 
         shard_opener.open().await
@@ -247,7 +304,15 @@ struct RecoverTableDataContext {
     space: SpaceRef,
 }
 
-pub type OpenTablesOfShardResult = HashMap<TableId, Result<Option<SpaceAndTable>>>;
+/// Result of opening the tables of a shard.
+#[derive(Debug, Default)]
+pub struct OpenTablesOfShardResult {
+    /// Open result of each table, and None if the table has been dropped
+    pub tables: HashMap<TableId, Result<Option<SpaceAndTable>>>,
+    /// Sequences of the corrupted logs skipped by the corruption policy in the
+    /// wal replay, keyed by the table
+    pub skipped_logs: SkippedLogs,
+}
 
 /// Opener for tables of the same shard
 struct ShardOpener {
@@ -255,21 +320,39 @@ struct ShardOpener {
     manifest: ManifestRef,
     wal_manager: WalManagerRef,
     stages: HashMap<TableId, TableOpenStage>,
+    skipped_logs: SkippedLogs,
     wal_replay_batch_size: usize,
+    replay_throttle: ReplayThrottleRef,
+    replay_memory_budget: usize,
+    replay_parallelism: usize,
+    corruption_policy: WalReplayCorruptionPolicy,
+    dedup_window: usize,
     flusher: Flusher,
     max_retry_flush_limit: usize,
     recover_mode: RecoverMode,
+    auto_recover: AutoRecoverConfig,
+    /// The store and the config to reconcile the ssts of the recovered
+    /// tables, no reconciliation if not set.
+    sst_reconciler: Option<(ObjectStoreRef, SstReconcilerConfig)>,
 }
 
 impl ShardOpener {
+    #[allow(clippy::too_many_arguments)]
     fn init(
         shard_context: TablesOfShardContext,
         manifest: ManifestRef,
         wal_manager: WalManagerRef,
         wal_replay_batch_size: usize,
+        replay_throttle: ReplayThrottleRef,
+        replay_memory_budget: usize,
+        replay_parallelism: usize,
+        corruption_policy: WalReplayCorruptionPolicy,
+        dedup_window: usize,
         flusher: Flusher,
         max_retry_flush_limit: usize,
         recover_mode: RecoverMode,
+        auto_recover: AutoRecoverConfig,
+        sst_reconciler: Option<(ObjectStoreRef, SstReconcilerConfig)>,
     ) -> Result<Self> {
         let mut stages = HashMap::with_capacity(shard_context.table_ctxs.len());
         for table_ctx in shard_context.table_ctxs {
@@ -293,10 +376,18 @@ impl ShardOpener {
             manifest,
             wal_manager,
             stages,
+            skipped_logs: SkippedLogs::new(),
             wal_replay_batch_size,
+            replay_throttle,
+            replay_memory_budget,
+            replay_parallelism,
+            corruption_policy,
+            dedup_window,
             flusher,
             max_retry_flush_limit,
             recover_mode,
+            auto_recover,
+            sst_reconciler,
         })
     }
 
@@ -329,7 +420,10 @@ impl ShardOpener {
             }
         }
 
-        Ok(table_results)
+        Ok(OpenTablesOfShardResult {
+            tables: table_results,
+            skipped_logs: std::mem::take(&mut self.skipped_logs),
+        })
     }
 
     /// Recover table meta data from manifest based on shard.
@@ -423,19 +517,33 @@ impl ShardOpener {
         let replay_mode = match self.recover_mode {
             RecoverMode::TableBased => ReplayMode::TableBased,
             RecoverMode::ShardBased => ReplayMode::RegionBased,
+            RecoverMode::Auto => ReplayMode::Auto(self.auto_recover),
         };
         let mut wal_replayer = WalReplayer::new(
             &replay_table_datas,
             self.shard_id,
             self.wal_manager.clone(),
             self.wal_replay_batch_size,
+            self.replay_throttle.clone(),
+            self.replay_memory_budget,
+            self.replay_parallelism,
+            self.corruption_policy,
+            self.dedup_window,
             self.flusher.clone(),
             self.max_retry_flush_limit,
             replay_mode,
         );
         let mut table_results = wal_replayer.replay().await?;
+        self.skipped_logs = wal_replayer.take_skipped_logs();
+        for (table_id, sequences) in &self.skipped_logs {
+            warn!(
+                "ShardOpener skipped corrupted logs of table, table_id:{table_id}, shard_id:{}, sequences:{sequences:?}",
+                self.shard_id
+            );
+        }
 
         // Process the replay results.
+        let mut recovered_table_datas = Vec::with_capacity(replay_table_datas.len());
         for table_data in replay_table_datas {
             let table_id = table_data.id;
             // Each `table_data` has its related `stage` in `stages`, impossible to panic
@@ -445,6 +553,11 @@ impl ShardOpener {
 
             match (&stage, failed_table_opt) {
                 (TableOpenStage::RecoverTableData(ctx), None) => {
+                    ctx.table_data.metrics.on_wal_flushed(
+                        ctx.table_data.last_sequence(),
+                        ctx.table_data.current_version().flushed_sequence(),
+                    );
+                    recovered_table_datas.push(ctx.table_data.clone());
                     let space_table = SpaceAndTable::new(ctx.space.clone(), ctx.table_data.clone());
                     *stage = TableOpenStage::Success(Some(space_table));
                 }
@@ -462,6 +575,11 @@ impl ShardOpener {
             }
         }
 
+        if let Some((store, config)) = &self.sst_reconciler {
+            self.reconcile_ssts(store, config, &recovered_table_datas)
+                .await;
+        }
+
         info!(
             "ShardOpener recover table datas finish, shard_id:{}",
             self.shard_id
@@ -469,6 +587,30 @@ impl ShardOpener {
         Ok(())
     }
 
+    /// Reconcile the ssts of the recovered tables, and the failure won't fail
+    /// the opening of the tables.
+    async fn reconcile_ssts(
+        &self,
+        store: &ObjectStoreRef,
+        config: &SstReconcilerConfig,
+        table_datas: &[TableDataRef],
+    ) {
+        let results: Vec<_> = stream::iter(table_datas)
+            .map(|table_data| sst_reconciler::reconcile_ssts(store, config, table_data))
+            .buffer_unordered(RECONCILE_SSTS_CONCURRENCY)
+            .collect()
+            .await;
+        let num_orphan_files: usize = results.iter().map(|v| v.orphan_files.len()).sum();
+        let num_deleted_files: usize = results.iter().map(|v| v.num_deleted_files).sum();
+        let num_missing_ssts: usize = results.iter().map(|v| v.missing_ssts.len()).sum();
+
+        info!(
+            "ShardOpener reconcile ssts finish, shard_id:{}, tables:{}, orphan_files:{num_orphan_files}, deleted_orphan_files:{num_deleted_files}, missing_ssts:{num_missing_ssts}",
+            self.shard_id,
+            table_datas.len(),
+        );
+    }
+
     /// Recover meta data from manifest.
     ///
     /// Return None if no meta data is found for the table.
@@ -0,0 +1,172 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+//! Dry run of opening the tables of a shard, which checks whether this node
+//! could open them before the shard is moved here.
+
+use logger::info;
+use table_engine::engine::{OpenShardCheck, OpenShardCheckReport, OpenShardRequest, TableDef};
+
+use crate::{
+    engine::build_space_id,
+    instance::{self, Instance},
+    manifest::LoadRequest,
+    table::{
+        data::{TableCatalogInfo, TableShardInfo},
+        sst_util,
+    },
+};
+
+const CHECK_MEMORY: &str = "memory";
+const CHECK_WAL: &str = "wal";
+const CHECK_MANIFEST: &str = "manifest";
+const CHECK_SCHEMA: &str = "schema";
+const CHECK_OBJECT_STORE: &str = "object_store";
+
+/// Max number of the latest ssts of a table to check.
+const MAX_SSTS_TO_CHECK: usize = 3;
+
+impl Instance {
+    /// Check whether the tables of the shard could be opened by this node,
+    /// nothing is opened or changed by the check.
+    ///
+    /// Checked items:
+    /// + The memtables of this node have room for the tables.
+    /// + The wal regions of the tables are accessible.
+    /// + The table metas in the manifest can be loaded and decoded.
+    /// + The latest ssts of the tables are reachable in the object store.
+    pub async fn check_open_shard(&self, request: OpenShardRequest) -> OpenShardCheckReport {
+        let shard_id = request.shard_id;
+        let mut checks = vec![self.check_memory()];
+        for table_def in &request.table_defs {
+            self.check_open_table(shard_id, table_def, &mut checks)
+                .await;
+        }
+
+        let report = OpenShardCheckReport::new(shard_id, checks);
+        info!(
+            "Check opening shard, shard_id:{shard_id}, table_num:{}, passed:{}",
+            request.table_defs.len(),
+            report.passed
+        );
+        report
+    }
+
+    fn check_memory(&self) -> OpenShardCheck {
+        let used = self.space_store.total_memory_usage_space();
+        let limit = self.db_write_buffer_size;
+        OpenShardCheck {
+            item: CHECK_MEMORY.to_string(),
+            table: None,
+            passed: limit == 0 || used < limit,
+            msg: format!("memtable_usage:{used}, db_write_buffer_size:{limit}"),
+        }
+    }
+
+    async fn check_open_table(
+        &self,
+        shard_id: common_types::table::ShardId,
+        table_def: &TableDef,
+        checks: &mut Vec<OpenShardCheck>,
+    ) {
+        let new_check = |item: &str, result: std::result::Result<String, String>| {
+            let (passed, msg) = match result {
+                Ok(msg) => (true, msg),
+                Err(msg) => (false, msg),
+            };
+            OpenShardCheck {
+                item: item.to_string(),
+                table: Some(table_def.name.clone()),
+                passed,
+                msg,
+            }
+        };
+
+        let wal_location =
+            instance::create_wal_location(table_def.id.as_u64(), TableShardInfo::new(shard_id));
+        let wal_result = self
+            .space_store
+            .wal_manager
+            .sequence_num(wal_location)
+            .await
+            .map(|seq| format!("last_sequence:{seq}"))
+            .map_err(|e| format!("failed to access wal, err:{e}"));
+        checks.push(new_check(CHECK_WAL, wal_result));
+
+        let space_id = build_space_id(table_def.schema_id);
+        let load_req = LoadRequest {
+            space_id,
+            table_id: table_def.id,
+            shard_id,
+            table_catalog_info: TableCatalogInfo {
+                schema_id: table_def.schema_id,
+                schema_name: table_def.schema_name.clone(),
+                catalog_name: table_def.catalog_name.clone(),
+            },
+        };
+        let snapshot = match self.space_store.manifest.load(&load_req).await {
+            Ok(Some(snapshot)) => {
+                checks.push(new_check(CHECK_MANIFEST, Ok("loaded".to_string())));
+                snapshot
+            }
+            Ok(None) => {
+                // The table is dropped, and it's ignored by the opening.
+                checks.push(new_check(CHECK_MANIFEST, Ok("no meta found".to_string())));
+                return;
+            }
+            Err(e) => {
+                checks.push(new_check(
+                    CHECK_MANIFEST,
+                    Err(format!("failed to load meta, err:{e}")),
+                ));
+                return;
+            }
+        };
+
+        let table_meta = &snapshot.table_meta;
+        let schema = &table_meta.schema;
+        let schema_result = if table_meta.table_name == table_def.name {
+            Ok(format!(
+                "schema_version:{}, num_columns:{}",
+                schema.version(),
+                schema.num_columns()
+            ))
+        } else {
+            Err(format!(
+                "table name mismatch, name_in_meta:{}",
+                table_meta.table_name
+            ))
+        };
+        checks.push(new_check(CHECK_SCHEMA, schema_result));
+
+        let mut file_ids: Vec<_> = snapshot
+            .version_meta
+            .map(|v| v.files.into_keys().collect())
+            .unwrap_or_default();
+        // Check the latest ssts which are most likely to be read after opening.
+        file_ids.sort_unstable_by(|a, b| b.cmp(a));
+        let store = self.space_store.store_picker().default_store();
+        let mut store_result = Ok(format!("num_ssts:{}", file_ids.len()));
+        for file_id in file_ids.into_iter().take(MAX_SSTS_TO_CHECK) {
+            let path = sst_util::new_sst_file_path(space_id, table_def.id, file_id);
+            if let Err(e) = store.head(&path).await {
+                store_result = Err(format!("failed to access sst, path:{path}, err:{e}"));
+                break;
+            }
+        }
+        checks.push(new_check(CHECK_OBJECT_STORE, store_result));
+    }
+}
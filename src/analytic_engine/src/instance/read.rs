@@ -18,6 +18,7 @@
 //! Read logic of instance
 
 use std::{
+    cmp::Reverse,
     collections::BTreeMap,
     pin::Pin,
     task::{Context, Poll},
@@ -25,38 +26,41 @@ use std::{
 
 use async_stream::try_stream;
 use common_types::{
-    projected_schema::ProjectedSchema,
+    projected_schema::{ProjectedSchema, RowProjectorBuilder},
     record_batch::{FetchedRecordBatch, RecordBatch},
     schema::RecordSchema,
-    time::TimeRange,
+    time::{TimeRange, Timestamp},
+    SequenceNumber,
 };
 use futures::stream::Stream;
-use generic_error::BoxError;
+use generic_error::{BoxError, GenericError};
 use logger::debug;
 use macros::define_result;
+use runtime::Priority;
 use snafu::{ResultExt, Snafu};
 use table_engine::{
     stream::{
         self, ErrWithSource, PartitionedStreams, RecordBatchStream, SendableRecordBatchStream,
     },
-    table::ReadRequest,
+    table::{ExistsRequest, ReadOrder, ReadRequest},
 };
 use time_ext::current_time_millis;
 use trace_metric::Metric;
 
 use crate::{
     instance::{Instance, ScanType, SstReadOptionsBuilder},
+    prefetchable_stream::PrefetchableStream,
     row_iter::{
         chain,
         chain::{ChainConfig, ChainIterator},
         dedup::DedupIterator,
+        expire::ExpireFilterIterator,
         merge::{MergeBuilder, MergeConfig, MergeIterator},
+        record_batch_stream::{self, MemtableStreamContext},
         FetchedRecordBatchIterator, IterOptions,
     },
-    table::{
-        data::TableData,
-        version::{ReadView, TableVersion},
-    },
+    sst::{factory::SstReadHint, manager::FileId},
+    table::{data::TableData, sst_util, version::ReadView},
     table_options::TableOptions,
 };
 
@@ -79,6 +83,37 @@ pub enum Error {
         table: String,
         source: crate::row_iter::chain::Error,
     },
+
+    #[snafu(display(
+        "Failed to project expire_at column, table:{}, column:{}, err:{}",
+        table,
+        column,
+        source
+    ))]
+    ProjectExpireAtColumn {
+        table: String,
+        column: String,
+        source: common_types::projected_schema::Error,
+    },
+
+    #[snafu(display(
+        "Failed to check existence in memtable, table:{}, err:{}",
+        table,
+        source
+    ))]
+    CheckMemTable { table: String, source: GenericError },
+
+    #[snafu(display(
+        "Failed to check existence in sst, table:{}, file_id:{}, err:{}",
+        table,
+        file_id,
+        source
+    ))]
+    CheckSst {
+        table: String,
+        file_id: FileId,
+        source: GenericError,
+    },
 }
 
 define_result!(Error);
@@ -94,7 +129,7 @@ impl Instance {
     pub async fn partitioned_read_from_table(
         &self,
         table_data: &TableData,
-        request: ReadRequest,
+        mut request: ReadRequest,
     ) -> Result<PartitionedStreams> {
         debug!(
             "Instance read from table, space_id:{}, table:{}, table_id:{:?}, request:{:?}",
@@ -119,6 +154,21 @@ impl Instance {
         let table_options = table_data.table_options();
         table_data.metrics.on_read_request_begin();
         let need_merge_sort = table_options.need_dedup();
+
+        // The `expire_at` column is fetched additionally to filter out the expired
+        // rows, and it is removed by the projection of the outputs.
+        let output_schema = request.projected_schema.clone();
+        let expire_at_column = table_options.expire_at_column.as_deref();
+        let now = table_options.timestamp_precision.now();
+        let delete_before = table_data.current_version().delete_before();
+        if let Some(column) = expire_at_column {
+            request.projected_schema = projected_schema_with_column(&output_schema, column)
+                .context(ProjectExpireAtColumn {
+                    table: &table_data.name,
+                    column,
+                })?;
+        }
+
         request.metrics_collector.collect(Metric::boolean(
             MERGE_SORT_METRIC_NAME.to_string(),
             need_merge_sort,
@@ -129,15 +179,23 @@ impl Instance {
             .read_runtime()
             .choose_runtime(&request.priority)
             .clone();
+        // The data read by the query hinted with no cache is treated as the one
+        // read by the expensive query.
+        let scan_type = if request.opts.no_cache {
+            ScanType::ExpensiveQuery
+        } else {
+            ScanType::from_query_priority(request.priority)
+        };
         let sst_read_options_builder = SstReadOptionsBuilder::new(
-            ScanType::Query,
+            scan_type,
             self.scan_options.clone(),
             Some(table_metrics.sst_metrics.clone()),
             table_options.num_rows_per_row_group,
             request.predicate.clone(),
             self.meta_cache.clone(),
             runtime,
-        );
+        )
+        .query_stats(request.opts.stats.clone());
 
         if need_merge_sort {
             let merge_iters = self
@@ -148,7 +206,14 @@ impl Instance {
                     sst_read_options_builder,
                 )
                 .await?;
-            self.build_partitioned_streams(&request, merge_iters)
+            self.build_partitioned_streams(
+                &request,
+                merge_iters,
+                output_schema,
+                expire_at_column,
+                now,
+                delete_before,
+            )
         } else {
             let chain_iters = self
                 .build_chain_iters(
@@ -158,30 +223,199 @@ impl Instance {
                     sst_read_options_builder,
                 )
                 .await?;
-            self.build_partitioned_streams(&request, chain_iters)
+            self.build_partitioned_streams(
+                &request,
+                chain_iters,
+                output_schema,
+                expire_at_column,
+                now,
+                delete_before,
+            )
         }
     }
 
-    fn build_partitioned_streams(
+    /// Check whether the rows matching the predicate may exist in the table.
+    ///
+    /// The memtables are scanned and filtered in memory, while only the meta
+    /// data and the filters of the ssts are checked, so false positives are
+    /// possible.
+    pub async fn maybe_exists_in_table(
         &self,
-        request: &ReadRequest,
-        partitioned_iters: Vec<impl FetchedRecordBatchIterator + 'static>,
-    ) -> Result<PartitionedStreams> {
-        let read_parallelism = request.opts.read_parallelism;
+        table_data: &TableData,
+        request: ExistsRequest,
+    ) -> Result<bool> {
+        debug!(
+            "Instance check existence in table, table:{}, table_id:{:?}, request:{:?}",
+            table_data.name, table_data.id, request
+        );
+
+        let time_range = request.predicate.time_range();
+        let sequence = table_data.last_sequence();
+        let read_view = table_data.current_version().pick_read_view(time_range);
+
+        // Fetch all the columns, which are required by the filters.
+        let projected_schema = ProjectedSchema::no_projection(table_data.schema());
+        let fetched_schema = projected_schema.to_record_schema_with_key();
+        let primary_key_indexes = fetched_schema.primary_key_idx().to_vec();
+        let fetched_schema = fetched_schema.into_record_schema();
+        let row_projector_builder = RowProjectorBuilder::new(
+            fetched_schema.clone(),
+            projected_schema.table_schema().clone(),
+            Some(primary_key_indexes),
+        );
 
-        // Split iterators into `read_parallelism` groups.
-        let mut splitted_iters: Vec<_> = std::iter::repeat_with(Vec::new)
-            .take(read_parallelism)
-            .collect();
+        let memtable_stream_ctx = MemtableStreamContext {
+            row_projector_builder: row_projector_builder.clone(),
+            fetched_schema,
+            predicate: request.predicate.clone(),
+            need_dedup: false,
+            reverse: false,
+            deadline: None,
+            max_visible_sequence: Some(sequence),
+        };
+        let memtables = read_view
+            .sampling_mem
+            .iter()
+            .map(|v| &v.mem)
+            .chain(read_view.memtables.iter().map(|v| &v.mem));
+        for memtable in memtables {
+            let mut stream = record_batch_stream::filtered_stream_from_memtable(
+                memtable,
+                &memtable_stream_ctx,
+                None,
+            )
+            .box_err()
+            .context(CheckMemTable {
+                table: &table_data.name,
+            })?;
+            // The empty batches are filtered out by the stream.
+            if let Some(batch) = stream.fetch_next().await {
+                batch.context(CheckMemTable {
+                    table: &table_data.name,
+                })?;
+                return Ok(true);
+            }
+        }
 
-        for (i, time_aligned_iter) in partitioned_iters.into_iter().enumerate() {
-            splitted_iters[i % read_parallelism].push(time_aligned_iter);
+        let table_options = table_data.table_options();
+        let runtime = self.read_runtime().choose_runtime(&Priority::High).clone();
+        let sst_read_options = SstReadOptionsBuilder::new(
+            ScanType::Query,
+            self.scan_options.clone(),
+            Some(
+                table_data
+                    .metrics
+                    .maybe_table_level_metrics()
+                    .sst_metrics
+                    .clone(),
+            ),
+            table_options.num_rows_per_row_group,
+            request.predicate,
+            self.meta_cache.clone(),
+            runtime,
+        )
+        .build(row_projector_builder);
+        let store_picker = self.space_store.store_picker();
+        for sst_file in read_view.leveled_ssts.iter().flatten() {
+            let path =
+                sst_util::new_sst_file_path(table_data.space_id, table_data.id, sst_file.id());
+            let read_hint = SstReadHint {
+                file_size: Some(sst_file.size() as usize),
+                file_format: Some(sst_file.storage_format()),
+            };
+            let mut sst_reader = self
+                .space_store
+                .sst_factory
+                .create_reader(&path, &sst_read_options, read_hint, store_picker, None)
+                .await
+                .box_err()
+                .context(CheckSst {
+                    table: &table_data.name,
+                    file_id: sst_file.id(),
+                })?;
+            let maybe_contains = sst_reader
+                .maybe_contains()
+                .await
+                .box_err()
+                .context(CheckSst {
+                    table: &table_data.name,
+                    file_id: sst_file.id(),
+                })?;
+            if maybe_contains {
+                return Ok(true);
+            }
         }
 
+        Ok(false)
+    }
+
+    /// Build `read_parallelism` streams from the iterators of the time
+    /// segments.
+    ///
+    /// If the query needs the first `limit` rows in the time order, all the
+    /// segments are read in the time order by the first stream, which stops
+    /// once the rows read are enough, and the other streams are empty.
+    fn build_partitioned_streams<I: FetchedRecordBatchIterator + 'static>(
+        &self,
+        request: &ReadRequest,
+        partitioned_iters: Vec<(TimeRange, I)>,
+        output_schema: ProjectedSchema,
+        expire_at_column: Option<&str>,
+        now: Timestamp,
+        delete_before: Option<Timestamp>,
+    ) -> Result<PartitionedStreams> {
+        let read_parallelism = request.opts.read_parallelism;
+        // The deleted rows are exactly the rows "expired" at `delete_before - 1`
+        // according to the timestamp column.
+        let timestamp_column = request.projected_schema.table_schema().timestamp_name();
+        let (delete_column, delete_expire_time) = match delete_before {
+            Some(before) => (
+                Some(timestamp_column),
+                Timestamp::new(before.as_i64().saturating_sub(1)),
+            ),
+            None => (None, Timestamp::MIN),
+        };
+        let partitioned_iters = partitioned_iters.into_iter().map(|(time_range, iter)| {
+            (
+                time_range,
+                ExpireFilterIterator::new(
+                    ExpireFilterIterator::new(iter, expire_at_column, now),
+                    delete_column,
+                    delete_expire_time,
+                ),
+            )
+        });
+
         let mut streams = Vec::with_capacity(read_parallelism);
-        for iters in splitted_iters {
-            let stream = iters_to_stream(iters, request.projected_schema.clone());
-            streams.push(stream);
+        match (request.opts.order, request.opts.limit) {
+            (ReadOrder::Asc | ReadOrder::Desc, Some(limit)) => {
+                let stream = time_ordered_iters_to_stream(
+                    partitioned_iters.collect(),
+                    output_schema.clone(),
+                    limit,
+                    request.opts.order,
+                );
+                streams.push(stream);
+                for _ in 1..read_parallelism {
+                    let empty_iters: Vec<ExpireFilterIterator<ExpireFilterIterator<I>>> =
+                        Vec::new();
+                    streams.push(iters_to_stream(empty_iters, output_schema.clone(), None));
+                }
+            }
+            _ => {
+                // Split iterators into `read_parallelism` groups.
+                let mut splitted_iters: Vec<_> = std::iter::repeat_with(Vec::new)
+                    .take(read_parallelism)
+                    .collect();
+                for (i, (_, iter)) in partitioned_iters.enumerate() {
+                    splitted_iters[i % read_parallelism].push(iter);
+                }
+
+                for iters in splitted_iters {
+                    let stream = iters_to_stream(iters, output_schema.clone(), request.opts.limit);
+                    streams.push(stream);
+                }
+            }
         }
 
         assert_eq!(read_parallelism, streams.len());
@@ -195,16 +429,15 @@ impl Instance {
         request: &ReadRequest,
         table_options: &TableOptions,
         sst_read_options_builder: SstReadOptionsBuilder,
-    ) -> Result<Vec<DedupIterator<MergeIterator>>> {
-        // Current visible sequence
-        let sequence = table_data.last_sequence();
+    ) -> Result<Vec<(TimeRange, DedupIterator<MergeIterator>)>> {
         let time_range = request.predicate.time_range();
-        let version = table_data.current_version();
-        let read_views = self.partition_ssts_and_memtables(time_range, version, table_options);
+        let (sequence, read_view) = pick_read_view(table_data, request, time_range);
+        let read_views = self.partition_ssts_and_memtables(read_view, table_options);
         let iter_options = self.make_iter_options(table_options.num_rows_per_row_group);
 
         let mut iters = Vec::with_capacity(read_views.len());
         for (idx, read_view) in read_views.into_iter().enumerate() {
+            let time_range = read_view.time_range();
             let metrics_collector = request
                 .metrics_collector
                 .span(format!("{MERGE_ITER_METRICS_COLLECTOR_NAME_PREFIX}_{idx}"));
@@ -237,7 +470,7 @@ impl Instance {
             let dedup_iter =
                 DedupIterator::new(request.request_id.clone(), merge_iter, iter_options.clone());
 
-            iters.push(dedup_iter);
+            iters.push((time_range, dedup_iter));
         }
 
         request.metrics_collector.collect(Metric::number(
@@ -255,15 +488,19 @@ impl Instance {
         request: &ReadRequest,
         table_options: &TableOptions,
         sst_read_options_builder: SstReadOptionsBuilder,
-    ) -> Result<Vec<ChainIterator>> {
+    ) -> Result<Vec<(TimeRange, ChainIterator)>> {
         let projected_schema = request.projected_schema.clone();
 
         let time_range = request.predicate.time_range();
-        let version = table_data.current_version();
-        let read_views = self.partition_ssts_and_memtables(time_range, version, table_options);
+        let (sequence, read_view) = pick_read_view(table_data, request, time_range);
+        // Only the rows in the memtables need to be filtered by the sequence when
+        // reading a snapshot.
+        let max_visible_sequence = request.opts.snapshot.as_ref().map(|_| sequence);
+        let read_views = self.partition_ssts_and_memtables(read_view, table_options);
 
         let mut iters = Vec::with_capacity(read_views.len());
         for (idx, read_view) in read_views.into_iter().enumerate() {
+            let time_range = read_view.time_range();
             let metrics_collector = request
                 .metrics_collector
                 .span(format!("{CHAIN_ITER_METRICS_COLLECTOR_NAME_PREFIX}_{idx}"));
@@ -272,6 +509,7 @@ impl Instance {
                 metrics_collector: Some(metrics_collector),
                 deadline: request.opts.deadline,
                 num_streams_to_prefetch: self.scan_options.num_streams_to_prefetch,
+                max_visible_sequence,
                 space_id: table_data.space_id,
                 table_id: table_data.id,
                 projected_schema: projected_schema.clone(),
@@ -291,7 +529,7 @@ impl Instance {
                     table: &table_data.name,
                 })?;
 
-            iters.push(chain_iter);
+            iters.push((time_range, chain_iter));
         }
 
         Ok(iters)
@@ -299,14 +537,11 @@ impl Instance {
 
     fn partition_ssts_and_memtables(
         &self,
-        time_range: TimeRange,
-        version: &TableVersion,
+        read_view: ReadView,
         table_options: &TableOptions,
     ) -> Vec<ReadView> {
-        let read_view = version.pick_read_view(time_range);
-
-        let segment_duration = match table_options.segment_duration {
-            Some(v) => v.0,
+        let segment_duration = match table_options.segment_duration() {
+            Some(v) => v,
             None => {
                 // Segment duration is unknown, the table maybe still in sampling phase
                 // or the segment duration is still not applied to the table options,
@@ -356,6 +591,45 @@ impl Instance {
     }
 }
 
+/// Version of the table pinned by the [ReadSnapshot] of the request.
+///
+/// [ReadSnapshot]: table_engine::table::ReadSnapshot
+struct PinnedVersion {
+    sequence: SequenceNumber,
+    read_view: ReadView,
+}
+
+/// Pick the memtables and ssts to read and the max visible sequence of them.
+///
+/// The whole version of the table is pinned at the first read if the request
+/// shares a snapshot with other requests, and the later reads of the same
+/// table will see exactly the same data.
+fn pick_read_view(
+    table_data: &TableData,
+    request: &ReadRequest,
+    time_range: TimeRange,
+) -> (SequenceNumber, ReadView) {
+    let Some(snapshot) = &request.opts.snapshot else {
+        // Current visible sequence
+        let sequence = table_data.last_sequence();
+        let read_view = table_data.current_version().pick_read_view(time_range);
+        return (sequence, read_view);
+    };
+
+    let pinned = snapshot
+        .get_or_pin(table_data.id, || PinnedVersion {
+            sequence: table_data.last_sequence(),
+            read_view: table_data
+                .current_version()
+                .pick_read_view(TimeRange::min_to_max()),
+        })
+        .expect("the pinned version of the table must be a PinnedVersion");
+    (
+        pinned.sequence,
+        pinned.read_view.filter_by_time_range(time_range),
+    )
+}
+
 struct StreamStateOnMultiIters<I> {
     iters: Vec<I>,
     curr_iter_idx: usize,
@@ -393,24 +667,55 @@ impl<I: FetchedRecordBatchIterator + 'static> StreamStateOnMultiIters<I> {
     }
 }
 
+/// Add the `column` into the projection if it is not projected.
+fn projected_schema_with_column(
+    projected_schema: &ProjectedSchema,
+    column: &str,
+) -> common_types::projected_schema::Result<ProjectedSchema> {
+    let table_schema = projected_schema.table_schema();
+    let (Some(mut projection), Some(column_idx)) =
+        (projected_schema.projection(), table_schema.index_of(column))
+    else {
+        return Ok(projected_schema.clone());
+    };
+    if projection.contains(&column_idx) {
+        return Ok(projected_schema.clone());
+    }
+
+    projection.push(column_idx);
+    ProjectedSchema::new(table_schema.clone(), Some(projection))
+}
+
+/// Build a stream reading the `iters` in order, which stops reading once
+/// `limit` rows are produced.
 fn iters_to_stream(
     iters: Vec<impl FetchedRecordBatchIterator + 'static>,
     projected_schema: ProjectedSchema,
+    limit: Option<usize>,
 ) -> SendableRecordBatchStream {
     let mut state = StreamStateOnMultiIters {
         projected_schema: projected_schema.clone(),
         iters,
         curr_iter_idx: 0,
     };
+    let mut remaining = limit.unwrap_or(usize::MAX);
 
     let record_batch_stream = try_stream! {
-        while let Some(value) = state.fetch_next_batch().await {
+        while remaining > 0 {
+            let Some(value) = state.fetch_next_batch().await else {
+                break;
+            };
             let record_batch = value
                 .box_err()
                 .context(ErrWithSource {
                     msg: "Read record batch",
                 })
                 .and_then(|batch_with_key| {
+                    let batch_with_key = if batch_with_key.num_rows() > remaining {
+                        batch_with_key.slice(0, remaining)
+                    } else {
+                        batch_with_key
+                    };
                     // TODO(yingwen): Try to use projector to do this, which pre-compute row
                     // indexes to project.
                     batch_with_key
@@ -419,8 +724,9 @@ fn iters_to_stream(
                         .context(ErrWithSource {
                             msg: "Project record batch",
                         })
-                });
-            yield record_batch?;
+                })?;
+            remaining -= record_batch.num_rows();
+            yield record_batch;
         }
     };
 
@@ -432,6 +738,70 @@ fn iters_to_stream(
     Box::pin(stream_with_schema)
 }
 
+/// Build a stream reading the iterators of the time segments in the `order`,
+/// which stops once `limit` rows are produced and all the remaining segments
+/// are behind the segments read in the `order`.
+///
+/// The rows of a segment aren't sorted by the timestamp, so a segment is
+/// always read entirely.
+fn time_ordered_iters_to_stream<I: FetchedRecordBatchIterator + 'static>(
+    mut iters: Vec<(TimeRange, I)>,
+    projected_schema: ProjectedSchema,
+    limit: usize,
+    order: ReadOrder,
+) -> SendableRecordBatchStream {
+    let is_desc = order == ReadOrder::Desc;
+    if is_desc {
+        iters.sort_by_key(|(time_range, _)| Reverse(time_range.exclusive_end()));
+    } else {
+        iters.sort_by_key(|(time_range, _)| time_range.inclusive_start());
+    }
+    let record_schema = projected_schema.to_record_schema();
+
+    let record_batch_stream = try_stream! {
+        let mut num_rows = 0;
+        // Time range covering the segments read so far.
+        let mut read_range: Option<TimeRange> = None;
+        for (time_range, mut iter) in iters {
+            if let Some(read_range) = read_range.filter(|_| num_rows >= limit) {
+                let is_behind = if is_desc {
+                    time_range.exclusive_end() <= read_range.inclusive_start()
+                } else {
+                    time_range.inclusive_start() >= read_range.exclusive_end()
+                };
+                if is_behind {
+                    break;
+                }
+            }
+
+            while let Some(batch_with_key) = iter
+                .next_batch()
+                .await
+                .box_err()
+                .context(ErrWithSource {
+                    msg: "Read record batch",
+                })?
+            {
+                let record_batch = batch_with_key
+                    .try_project(&projected_schema)
+                    .box_err()
+                    .context(ErrWithSource {
+                        msg: "Project record batch",
+                    })?;
+                num_rows += record_batch.num_rows();
+                yield record_batch;
+            }
+            read_range = Some(read_range.map_or(time_range, |v| v.merge_range(time_range)));
+        }
+    };
+
+    let stream_with_schema = RecordBatchStreamWithSchema {
+        schema: record_schema,
+        inner_stream: Box::pin(Box::pin(record_batch_stream)),
+    };
+    Box::pin(stream_with_schema)
+}
+
 pub struct RecordBatchStreamWithSchema {
     schema: RecordSchema,
     inner_stream: Pin<Box<dyn Stream<Item = stream::Result<RecordBatch>> + Send + Unpin>>,
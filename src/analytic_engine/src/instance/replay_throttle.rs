@@ -0,0 +1,204 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+//! Throttle the wal replay of the recovering shards to leave room for the
+//! foreground writes of the serving shards on the same node.
+//!
+//! The replay only yields when there are foreground writes: after replaying a
+//! batch of logs, it pauses for a while so that it takes at most `max_share` of
+//! the time, e.g. a batch costing 100ms is followed by a pause of 300ms if the
+//! share is 0.25.
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use lazy_static::lazy_static;
+use logger::{debug, warn};
+use prometheus::{exponential_buckets, register_histogram, Histogram};
+use serde::{Deserialize, Serialize};
+use time_ext::ReadableDuration;
+
+lazy_static! {
+    static ref WAL_REPLAY_THROTTLE_DURATION_HISTOGRAM: Histogram = register_histogram!(
+        "wal_replay_throttle_duration",
+        "Histogram for the pause of the wal replay yielding to the foreground writes in seconds",
+        exponential_buckets(0.01, 2.0, 13).unwrap()
+    )
+    .unwrap();
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ReplayThrottleConfig {
+    /// Whether to throttle the wal replay when there are foreground writes
+    pub enable: bool,
+    /// The max share of the time taken by the wal replay when there are
+    /// foreground writes, in the range (0, 1]
+    pub max_share: f64,
+    /// The max pause after replaying a batch of logs
+    pub max_pause: ReadableDuration,
+}
+
+impl Default for ReplayThrottleConfig {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            max_share: 0.5,
+            max_pause: ReadableDuration::secs(1),
+        }
+    }
+}
+
+pub type ReplayThrottleRef = Arc<ReplayThrottle>;
+
+/// Throttle shared by the wal replay of all shards and the foreground writes.
+#[derive(Debug)]
+pub struct ReplayThrottle {
+    config: ReplayThrottleConfig,
+    /// Number of the foreground writes so far
+    foreground_writes: AtomicU64,
+}
+
+impl ReplayThrottle {
+    pub fn new(mut config: ReplayThrottleConfig) -> Self {
+        if config.enable && !(config.max_share > 0.0 && config.max_share <= 1.0) {
+            warn!(
+                "Invalid max share of wal replay throttle, disable it, max_share:{}",
+                config.max_share
+            );
+            config.enable = false;
+        }
+
+        Self {
+            config,
+            foreground_writes: AtomicU64::new(0),
+        }
+    }
+
+    /// Record a foreground write.
+    #[inline]
+    pub fn on_foreground_write(&self) {
+        if self.config.enable {
+            self.foreground_writes.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Create the tracker for a replay.
+    pub fn tracker(&self) -> ReplayTracker<'_> {
+        ReplayTracker {
+            throttle: self,
+            last_writes: self.foreground_writes.load(Ordering::Relaxed),
+        }
+    }
+
+    /// The pause after replaying a batch costing `cost`.
+    fn pause_of(&self, cost: Duration) -> Duration {
+        if self.config.max_share >= 1.0 {
+            return Duration::ZERO;
+        }
+
+        let ratio = (1.0 - self.config.max_share) / self.config.max_share;
+        cost.mul_f64(ratio).min(self.config.max_pause.0)
+    }
+}
+
+/// Tracker of the foreground writes during a replay.
+pub struct ReplayTracker<'a> {
+    throttle: &'a ReplayThrottle,
+    last_writes: u64,
+}
+
+impl<'a> ReplayTracker<'a> {
+    /// Pause the replay after replaying a batch costing `cost` if there are
+    /// foreground writes during it.
+    pub async fn maybe_pause(&mut self, cost: Duration) {
+        if !self.throttle.config.enable {
+            return;
+        }
+
+        let writes = self.throttle.foreground_writes.load(Ordering::Relaxed);
+        let has_foreground_writes = writes != self.last_writes;
+        self.last_writes = writes;
+        if !has_foreground_writes {
+            return;
+        }
+
+        let pause = self.throttle.pause_of(cost);
+        if pause.is_zero() {
+            return;
+        }
+
+        debug!("Pause wal replay for foreground writes, cost:{cost:?}, pause:{pause:?}");
+        WAL_REPLAY_THROTTLE_DURATION_HISTOGRAM.observe(pause.as_secs_f64());
+        tokio::time::sleep(pause).await;
+        // The writes during the pause shouldn't make the next batch pause.
+        self.last_writes = self.throttle.foreground_writes.load(Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_throttle(max_share: f64) -> ReplayThrottle {
+        ReplayThrottle::new(ReplayThrottleConfig {
+            enable: true,
+            max_share,
+            max_pause: ReadableDuration::secs(1),
+        })
+    }
+
+    #[test]
+    fn test_pause_of() {
+        let throttle = new_throttle(0.25);
+        assert_eq!(
+            throttle.pause_of(Duration::from_millis(100)),
+            Duration::from_millis(300)
+        );
+        // The pause is bounded.
+        assert_eq!(
+            throttle.pause_of(Duration::from_secs(10)),
+            Duration::from_secs(1)
+        );
+
+        let throttle = new_throttle(1.0);
+        assert_eq!(
+            throttle.pause_of(Duration::from_millis(100)),
+            Duration::ZERO
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pause_only_with_foreground_writes() {
+        let throttle = new_throttle(0.5);
+        let mut tracker = throttle.tracker();
+
+        let cost = Duration::from_millis(20);
+        let begin = std::time::Instant::now();
+        tracker.maybe_pause(cost).await;
+        assert!(begin.elapsed() < cost);
+
+        throttle.on_foreground_write();
+        let begin = std::time::Instant::now();
+        tracker.maybe_pause(cost).await;
+        assert!(begin.elapsed() >= cost);
+    }
+}
@@ -0,0 +1,161 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Reconcile the sst files in the object store with the version of the table
+//! when it's opened.
+//!
+//! TODO: abort the multipart uploads left by the interrupted writes, which are
+//! not listed as objects so they can't be found by the reconciliation.
+
+use std::{collections::HashSet, time::SystemTime};
+
+use common_types::time::TimeRange;
+use futures::TryStreamExt;
+use logger::{error, info, warn};
+use object_store::{ObjectStoreRef, Path};
+use serde::{Deserialize, Serialize};
+use time_ext::ReadableDuration;
+
+use crate::{
+    sst::manager::FileId,
+    table::{data::TableData, sst_util},
+};
+
+/// Config of reconciling the ssts when the tables are opened.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SstReconcilerConfig {
+    /// Whether to reconcile the ssts when the tables are opened
+    pub enable: bool,
+    /// Delete the orphan files found, otherwise they are only reported
+    pub delete_orphan_files: bool,
+    /// The files modified within this duration are never considered as
+    /// orphans, as they may be written by the previous owner of the table
+    /// not aware of losing it yet
+    pub orphan_grace_period: ReadableDuration,
+}
+
+impl Default for SstReconcilerConfig {
+    fn default() -> Self {
+        Self {
+            enable: true,
+            delete_orphan_files: false,
+            orphan_grace_period: ReadableDuration::hours(1),
+        }
+    }
+}
+
+/// Result of reconciling the sst files of a table.
+#[derive(Debug, Default)]
+pub(crate) struct ReconcileResult {
+    /// The files not referenced by the table.
+    pub orphan_files: Vec<Path>,
+    /// Number of the orphan files deleted.
+    pub num_deleted_files: usize,
+    /// The ssts referenced by the table but missing in the object store.
+    pub missing_ssts: Vec<FileId>,
+}
+
+/// Find the files left by the flushes, compactions and purges interrupted in
+/// the previous runs, and report the ssts referenced by the table but missing.
+///
+/// The file ids are persisted to the manifest before being allocated, so only
+/// the files whose ids are not greater than the recovered max file id are
+/// considered, and the files being written since the table is opened are
+/// never touched.
+pub(crate) async fn reconcile_ssts(
+    store: &ObjectStoreRef,
+    config: &SstReconcilerConfig,
+    table_data: &TableData,
+) -> ReconcileResult {
+    let mut result = ReconcileResult::default();
+    let prefix = Path::from_iter([table_data.space_id.to_string(), table_data.id.to_string()]);
+    let objects = match store.list(Some(&prefix)).try_collect::<Vec<_>>().await {
+        Ok(v) => v,
+        Err(e) => {
+            warn!(
+                "Failed to list objects to reconcile ssts, table:{}, table_id:{}, err:{e}",
+                table_data.name, table_data.id
+            );
+            return result;
+        }
+    };
+
+    let referenced_ssts: HashSet<_> = table_data
+        .current_version()
+        .pick_read_view(TimeRange::min_to_max())
+        .leveled_ssts
+        .iter()
+        .flatten()
+        .map(|sst| sst.id())
+        .collect();
+    let max_file_id = table_data.recovered_max_file_id();
+    let now = SystemTime::now();
+    let mut existing_ssts = HashSet::with_capacity(referenced_ssts.len());
+    for object in objects {
+        let Some(file_name) = object.location.filename() else {
+            continue;
+        };
+        let Some(file_id) = sst_util::parse_sst_file_id(file_name) else {
+            continue;
+        };
+        if file_name == sst_util::sst_file_name(file_id) {
+            existing_ssts.insert(file_id);
+        }
+        if referenced_ssts.contains(&file_id) || file_id > max_file_id {
+            continue;
+        }
+        let modified = SystemTime::from(object.last_modified);
+        let age = now.duration_since(modified).unwrap_or_default();
+        if age < config.orphan_grace_period.0 {
+            continue;
+        }
+
+        if !config.delete_orphan_files {
+            warn!(
+                "Found orphan sst file, table:{}, table_id:{}, path:{}",
+                table_data.name, table_data.id, object.location
+            );
+            result.orphan_files.push(object.location);
+            continue;
+        }
+
+        info!(
+            "Delete orphan sst file, table:{}, table_id:{}, path:{}",
+            table_data.name, table_data.id, object.location
+        );
+        if let Err(e) = store.delete(&object.location).await {
+            warn!(
+                "Failed to delete orphan sst file, table:{}, table_id:{}, path:{}, err:{e}",
+                table_data.name, table_data.id, object.location
+            );
+        } else {
+            result.num_deleted_files += 1;
+        }
+        result.orphan_files.push(object.location);
+    }
+
+    for file_id in referenced_ssts.difference(&existing_ssts) {
+        error!(
+            "Sst referenced by the table is missing, table:{}, table_id:{}, file_id:{file_id}",
+            table_data.name, table_data.id
+        );
+        result.missing_ssts.push(*file_id);
+    }
+
+    result
+}
@@ -0,0 +1,176 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+//! Summary refresher refreshes the summaries of the ssts of the tables
+//! periodically, which are used as the estimated statistics by the query
+//! optimizer.
+//!
+//! The tables refreshed least recently are refreshed first, so the summaries of
+//! the tables rarely flushed or compacted don't get too stale.
+
+use std::sync::{Mutex, Weak};
+
+use lazy_static::lazy_static;
+use logger::{debug, error, info};
+use prometheus::{register_int_counter, IntCounter};
+use runtime::{JoinHandle, Runtime};
+use serde::{Deserialize, Serialize};
+use time_ext::ReadableDuration;
+use tokio::{
+    sync::oneshot::{self, Receiver, Sender},
+    time,
+};
+
+use crate::instance::Instance;
+
+lazy_static! {
+    static ref TABLE_SUMMARY_REFRESHED_COUNTER: IntCounter = register_int_counter!(
+        "table_summary_refreshed_counter",
+        "Counter of table summaries refreshed"
+    )
+    .unwrap();
+    static ref TABLE_SUMMARY_MERGED_SST_COUNTER: IntCounter = register_int_counter!(
+        "table_summary_merged_sst_counter",
+        "Counter of ssts added or removed by refreshing the table summaries"
+    )
+    .unwrap();
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TableSummaryConfig {
+    /// Whether to refresh the table summaries, no estimated statistics are
+    /// provided to the query optimizer if it's disabled
+    pub enable: bool,
+    /// The interval to refresh the summaries
+    pub refresh_interval: ReadableDuration,
+    /// Max number of tables to refresh in one round
+    pub max_tables_per_refresh: usize,
+    /// The confidence of the summary halves every `half_life` since it's
+    /// refreshed
+    pub half_life: ReadableDuration,
+    /// The summary whose confidence is below this is too stale to be provided
+    /// to the query optimizer, in the range [0, 1]
+    pub min_confidence: f64,
+}
+
+impl Default for TableSummaryConfig {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            refresh_interval: ReadableDuration::minutes(1),
+            max_tables_per_refresh: 1000,
+            half_life: ReadableDuration::minutes(30),
+            min_confidence: 0.25,
+        }
+    }
+}
+
+/// Background job to refresh the table summaries periodically.
+pub(crate) struct SummaryRefresher {
+    stop_sender: Mutex<Option<Sender<()>>>,
+    handle: tokio::sync::Mutex<Option<JoinHandle<()>>>,
+}
+
+impl SummaryRefresher {
+    /// Start the refresher if it's enabled.
+    ///
+    /// Only weak reference of the instance is held to avoid the reference
+    /// cycle.
+    pub fn start(
+        runtime: &Runtime,
+        instance: Weak<Instance>,
+        config: TableSummaryConfig,
+    ) -> Option<Self> {
+        if !config.enable {
+            return None;
+        }
+
+        let (tx, rx) = oneshot::channel();
+        let handle = runtime.spawn(async move {
+            Self::refresh_loop(instance, config, rx).await;
+        });
+
+        Some(Self {
+            stop_sender: Mutex::new(Some(tx)),
+            handle: tokio::sync::Mutex::new(Some(handle)),
+        })
+    }
+
+    pub async fn stop(&self) -> std::result::Result<(), runtime::Error> {
+        info!("Try to stop table summary refresher");
+
+        if let Some(tx) = self.stop_sender.lock().unwrap().take() {
+            if tx.send(()).is_err() {
+                error!("Table summary refresher already exited");
+            }
+        }
+
+        let mut handle = self.handle.lock().await;
+        // Also clear the handle to avoid await a ready future.
+        if let Some(h) = handle.take() {
+            h.await?;
+        }
+
+        Ok(())
+    }
+
+    async fn refresh_loop(
+        instance: Weak<Instance>,
+        config: TableSummaryConfig,
+        mut stop_receiver: Receiver<()>,
+    ) {
+        info!("Table summary refresher start, config:{config:?}");
+
+        loop {
+            // Stop if the stopping message is received or the sender is dropped.
+            if time::timeout(config.refresh_interval.0, &mut stop_receiver)
+                .await
+                .is_ok()
+            {
+                break;
+            }
+
+            let Some(instance) = instance.upgrade() else {
+                break;
+            };
+            instance.refresh_table_summaries(&config);
+        }
+
+        info!("Table summary refresher exit");
+    }
+}
+
+impl Instance {
+    /// Refresh the summaries of the tables refreshed least recently.
+    fn refresh_table_summaries(&self, config: &TableSummaryConfig) {
+        let mut tables = Vec::new();
+        self.space_store.list_all_tables(&mut tables);
+        tables.sort_by_cached_key(|v| v.summary_refreshed_at());
+
+        let now = time_ext::current_time_millis();
+        for table_data in tables.iter().take(config.max_tables_per_refresh) {
+            let changed = table_data.refresh_summary(now);
+            debug!(
+                "Table summary is refreshed, table:{}, table_id:{}, changed_ssts:{changed}",
+                table_data.name, table_data.id
+            );
+
+            TABLE_SUMMARY_REFRESHED_COUNTER.inc();
+            TABLE_SUMMARY_MERGED_SST_COUNTER.inc_by(changed as u64);
+        }
+    }
+}
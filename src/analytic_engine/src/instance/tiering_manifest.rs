@@ -0,0 +1,437 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Tiering manifest emitter writes a manifest per table describing the ages
+//! and the access frequency of its ssts, so that the external lifecycle
+//! policies of the object storage can transition the cold ssts to the
+//! infrequent-access storage classes safely.
+//!
+//! The manifest is written to `{space_id}/{table_id}/tiering_manifest.json`,
+//! next to the ssts of the table.
+
+use std::{
+    collections::HashMap,
+    sync::{Mutex, Weak},
+};
+
+use common_types::time::TimeRange;
+use futures::TryStreamExt;
+use lazy_static::lazy_static;
+use logger::{error, info, warn};
+use object_store::{ObjectStoreRef, Path};
+use prometheus::{register_int_counter, IntCounter};
+use runtime::{JoinHandle, Runtime};
+use serde::{Deserialize, Serialize};
+use time_ext::ReadableDuration;
+use tokio::{
+    sync::oneshot::{self, Receiver, Sender},
+    time,
+};
+
+use crate::{instance::Instance, sst::file::FileHandle, table::data::TableDataRef};
+
+const TIERING_MANIFEST_FILE: &str = "tiering_manifest.json";
+const TIERING_MANIFEST_VERSION: u32 = 1;
+
+lazy_static! {
+    static ref TIERING_MANIFEST_EMITTED_COUNTER: IntCounter = register_int_counter!(
+        "tiering_manifest_emitted_counter",
+        "Counter of the emitted tiering manifests"
+    )
+    .unwrap();
+    static ref TIERING_MANIFEST_FAILED_COUNTER: IntCounter = register_int_counter!(
+        "tiering_manifest_failed_counter",
+        "Counter of the failures to emit tiering manifests"
+    )
+    .unwrap();
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TieringManifestConfig {
+    /// Whether to emit the tiering manifests
+    pub enable: bool,
+    /// The interval to emit the manifests of all the tables
+    pub emit_interval: ReadableDuration,
+    /// The sst is considered cold only if the newest data in it is older than
+    /// this duration
+    pub cold_data_age: ReadableDuration,
+    /// The sst is considered cold only if it is not modified for this
+    /// duration, which avoids transitioning the ssts about to be compacted
+    pub min_object_age: ReadableDuration,
+    /// The sst is considered cold only if its read rate (reads per second over
+    /// the last two hours) is not greater than this threshold
+    pub max_cold_read_rate: f64,
+    /// The read rates are meaningless until the table has been opened for
+    /// this duration, and no sst is considered cold before that
+    pub min_observe_duration: ReadableDuration,
+}
+
+impl Default for TieringManifestConfig {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            emit_interval: ReadableDuration::hours(1),
+            cold_data_age: ReadableDuration::days(7),
+            min_object_age: ReadableDuration::days(1),
+            max_cold_read_rate: 0.0,
+            min_observe_duration: ReadableDuration::hours(2),
+        }
+    }
+}
+
+/// Suggested storage tier of the sst.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Tier {
+    /// Keep the sst in the standard storage class.
+    Hot,
+    /// The sst can be transitioned to the infrequent-access storage class.
+    Cold,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TieringManifest {
+    pub version: u32,
+    pub space_id: u32,
+    pub table_id: u64,
+    pub table: String,
+    pub generated_at_ms: u64,
+    /// Since when the read statistics of the ssts are collected
+    pub observed_since_ms: u64,
+    pub files: Vec<TieringFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TieringFile {
+    /// Path of the sst in the object store
+    pub path: String,
+    /// Files read together with the sst, they should be kept in the same tier
+    pub associated_files: Vec<String>,
+    pub level: u16,
+    pub size: u64,
+    pub row_num: u64,
+    /// The inclusive start of the time range of the data in the sst, in the
+    /// timestamp precision of the table
+    pub start_time: i64,
+    /// The exclusive end of the time range of the data in the sst
+    pub end_time: i64,
+    /// How long the newest data in the sst has been written, None if the
+    /// timestamp can't be converted to milliseconds
+    pub data_age_ms: Option<u64>,
+    /// How long the sst has been created, None if it's unknown
+    pub object_age_ms: Option<u64>,
+    /// Number of reads since the table is opened
+    pub read_count: u64,
+    /// Reads per second over the last two hours
+    pub read_rate: f64,
+    pub being_compacted: bool,
+    pub tier: Tier,
+}
+
+impl TieringManifestConfig {
+    fn suggest_tier(&self, file: &TieringFile, observed_ms: u64) -> Tier {
+        let is_old = |age: Option<u64>, threshold: &ReadableDuration| {
+            age.map(|v| v >= threshold.as_millis()).unwrap_or(false)
+        };
+
+        if !file.being_compacted
+            && observed_ms >= self.min_observe_duration.as_millis()
+            && is_old(file.data_age_ms, &self.cold_data_age)
+            && is_old(file.object_age_ms, &self.min_object_age)
+            && file.read_rate <= self.max_cold_read_rate
+        {
+            Tier::Cold
+        } else {
+            Tier::Hot
+        }
+    }
+}
+
+/// Background job to emit the tiering manifests periodically.
+pub(crate) struct TieringManifestEmitter {
+    stop_sender: Mutex<Option<Sender<()>>>,
+    handle: tokio::sync::Mutex<Option<JoinHandle<()>>>,
+}
+
+impl TieringManifestEmitter {
+    /// Start the emitter if it's enabled.
+    ///
+    /// Only weak reference of the instance is held to avoid the reference
+    /// cycle.
+    pub fn start(
+        runtime: &Runtime,
+        instance: Weak<Instance>,
+        config: TieringManifestConfig,
+    ) -> Option<Self> {
+        if !config.enable {
+            return None;
+        }
+
+        let (tx, rx) = oneshot::channel();
+        let handle = runtime.spawn(async move {
+            Self::emit_loop(instance, config, rx).await;
+        });
+
+        Some(Self {
+            stop_sender: Mutex::new(Some(tx)),
+            handle: tokio::sync::Mutex::new(Some(handle)),
+        })
+    }
+
+    pub async fn stop(&self) -> std::result::Result<(), runtime::Error> {
+        info!("Try to stop tiering manifest emitter");
+
+        if let Some(tx) = self.stop_sender.lock().unwrap().take() {
+            if tx.send(()).is_err() {
+                error!("Tiering manifest emitter already exited");
+            }
+        }
+
+        let mut handle = self.handle.lock().await;
+        // Also clear the handle to avoid await a ready future.
+        if let Some(h) = handle.take() {
+            h.await?;
+        }
+
+        Ok(())
+    }
+
+    async fn emit_loop(
+        instance: Weak<Instance>,
+        config: TieringManifestConfig,
+        mut stop_receiver: Receiver<()>,
+    ) {
+        info!("Tiering manifest emitter start, config:{config:?}");
+
+        loop {
+            // Stop if the stopping message is received or the sender is dropped.
+            if time::timeout(config.emit_interval.0, &mut stop_receiver)
+                .await
+                .is_ok()
+            {
+                break;
+            }
+
+            let Some(instance) = instance.upgrade() else {
+                break;
+            };
+            instance.emit_tiering_manifests(&config).await;
+        }
+
+        info!("Tiering manifest emitter exit");
+    }
+}
+
+impl Instance {
+    /// Emit the tiering manifests of all the opened tables.
+    async fn emit_tiering_manifests(&self, config: &TieringManifestConfig) {
+        let mut tables = Vec::new();
+        self.space_store.list_all_tables(&mut tables);
+
+        let store = self.space_store.store_picker().default_store();
+        for table_data in tables {
+            if table_data.is_dropped() {
+                continue;
+            }
+
+            let manifest = build_tiering_manifest(store, &table_data, config).await;
+            match write_tiering_manifest(store, &manifest).await {
+                Ok(()) => TIERING_MANIFEST_EMITTED_COUNTER.inc(),
+                Err(e) => {
+                    TIERING_MANIFEST_FAILED_COUNTER.inc();
+                    warn!(
+                        "Failed to emit tiering manifest, table:{}, table_id:{}, err:{e}",
+                        table_data.name, table_data.id
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Build the tiering manifest of the ssts in the current version of the table.
+pub async fn build_tiering_manifest(
+    store: &ObjectStoreRef,
+    table_data: &TableDataRef,
+    config: &TieringManifestConfig,
+) -> TieringManifest {
+    let now = time_ext::current_time_millis();
+    let observed_since_ms = table_data.opened_time();
+    let observed_ms = now.saturating_sub(observed_since_ms);
+    let object_modified_times = list_object_modified_times(store, table_data).await;
+    let precision = table_data.table_options().timestamp_precision;
+
+    let read_view = table_data
+        .current_version()
+        .pick_read_view(TimeRange::min_to_max());
+    let mut files = Vec::new();
+    for (level, ssts) in read_view.leveled_ssts.iter().enumerate() {
+        for sst in ssts {
+            let path = table_data.sst_file_path(sst.id());
+            let data_age_ms = precision
+                .checked_to_millis(sst.time_range().exclusive_end().as_i64())
+                .map(|end| now.saturating_sub(end.max(0) as u64));
+            let object_age_ms = object_modified_times
+                .get(&path)
+                .map(|modified| now.saturating_sub(*modified));
+
+            let mut file = new_tiering_file(sst, level as u16, path, data_age_ms, object_age_ms);
+            file.tier = config.suggest_tier(&file, observed_ms);
+            files.push(file);
+        }
+    }
+
+    TieringManifest {
+        version: TIERING_MANIFEST_VERSION,
+        space_id: table_data.space_id,
+        table_id: table_data.id.as_u64(),
+        table: table_data.name.clone(),
+        generated_at_ms: now,
+        observed_since_ms,
+        files,
+    }
+}
+
+fn new_tiering_file(
+    sst: &FileHandle,
+    level: u16,
+    path: Path,
+    data_age_ms: Option<u64>,
+    object_age_ms: Option<u64>,
+) -> TieringFile {
+    let meta = sst.meta();
+    let read_meter = sst.read_meter();
+    TieringFile {
+        path: path.to_string(),
+        associated_files: meta.associated_files,
+        level,
+        size: meta.size,
+        row_num: meta.row_num,
+        start_time: meta.time_range.inclusive_start().as_i64(),
+        end_time: meta.time_range.exclusive_end().as_i64(),
+        data_age_ms,
+        object_age_ms,
+        read_count: read_meter.count(),
+        read_rate: read_meter.h2_rate(),
+        being_compacted: sst.being_compacted(),
+        tier: Tier::Hot,
+    }
+}
+
+/// List the last modified times (in milliseconds) of the objects of the table.
+///
+/// The failure is tolerated and the ages of the objects will be unknown.
+async fn list_object_modified_times(
+    store: &ObjectStoreRef,
+    table_data: &TableDataRef,
+) -> HashMap<Path, u64> {
+    let prefix = Path::from_iter([table_data.space_id.to_string(), table_data.id.to_string()]);
+    match store.list(Some(&prefix)).try_collect::<Vec<_>>().await {
+        Ok(objects) => objects
+            .into_iter()
+            .map(|v| (v.location, v.last_modified.timestamp_millis().max(0) as u64))
+            .collect(),
+        Err(e) => {
+            warn!(
+                "Failed to list objects of table, table:{}, table_id:{}, err:{e}",
+                table_data.name, table_data.id
+            );
+            HashMap::new()
+        }
+    }
+}
+
+async fn write_tiering_manifest(
+    store: &ObjectStoreRef,
+    manifest: &TieringManifest,
+) -> std::result::Result<(), String> {
+    let path = tiering_manifest_path(manifest.space_id, manifest.table_id);
+    let payload = serde_json::to_vec(manifest).map_err(|e| e.to_string())?;
+    store
+        .put(&path, payload.into())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[inline]
+pub fn tiering_manifest_path(space_id: u32, table_id: u64) -> Path {
+    Path::from_iter([
+        space_id.to_string(),
+        table_id.to_string(),
+        TIERING_MANIFEST_FILE.to_string(),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_file(data_age_ms: u64, object_age_ms: u64, read_rate: f64) -> TieringFile {
+        TieringFile {
+            path: "1/2/3.sst".to_string(),
+            associated_files: vec![],
+            level: 1,
+            size: 1024,
+            row_num: 10,
+            start_time: 0,
+            end_time: 100,
+            data_age_ms: Some(data_age_ms),
+            object_age_ms: Some(object_age_ms),
+            read_count: 0,
+            read_rate,
+            being_compacted: false,
+            tier: Tier::Hot,
+        }
+    }
+
+    #[test]
+    fn test_suggest_tier() {
+        let config = TieringManifestConfig {
+            enable: true,
+            emit_interval: ReadableDuration::secs(1),
+            cold_data_age: ReadableDuration::secs(100),
+            min_object_age: ReadableDuration::secs(10),
+            max_cold_read_rate: 0.01,
+            min_observe_duration: ReadableDuration::secs(50),
+        };
+
+        let cold = new_file(200_000, 20_000, 0.0);
+        assert_eq!(Tier::Cold, config.suggest_tier(&cold, 60_000));
+        // Not observed long enough.
+        assert_eq!(Tier::Hot, config.suggest_tier(&cold, 10_000));
+        // Data is new.
+        let file = new_file(50_000, 20_000, 0.0);
+        assert_eq!(Tier::Hot, config.suggest_tier(&file, 60_000));
+        // Object is new.
+        let file = new_file(200_000, 5_000, 0.0);
+        assert_eq!(Tier::Hot, config.suggest_tier(&file, 60_000));
+        // Read frequently.
+        let file = new_file(200_000, 20_000, 1.0);
+        assert_eq!(Tier::Hot, config.suggest_tier(&file, 60_000));
+        // Being compacted.
+        let mut file = new_file(200_000, 20_000, 0.0);
+        file.being_compacted = true;
+        assert_eq!(Tier::Hot, config.suggest_tier(&file, 60_000));
+        // Unknown object age.
+        let mut file = new_file(200_000, 20_000, 0.0);
+        file.object_age_ms = None;
+        assert_eq!(Tier::Hot, config.suggest_tier(&file, 60_000));
+    }
+}
@@ -18,28 +18,33 @@
 //! Wal replayer
 
 use std::{
-    collections::{HashMap, VecDeque},
+    collections::{HashMap, HashSet, VecDeque},
     fmt::Display,
     ops::Range,
-    sync::Arc,
+    sync::{Arc, Mutex as StdMutex},
     time::Instant,
 };
 
 use async_trait::async_trait;
+use bytes_ext::Buf;
 use common_types::{
     schema::{IndexInWriterSchema, Schema},
     table::ShardId,
+    SequenceNumber,
 };
 use futures::StreamExt;
 use generic_error::BoxError;
 use lazy_static::lazy_static;
 use logger::{debug, error, info, trace, warn};
-use prometheus::{exponential_buckets, register_histogram, Histogram};
+use prometheus::{
+    exponential_buckets, register_histogram, register_int_counter, Histogram, IntCounter,
+};
 use snafu::ResultExt;
 use table_engine::table::TableId;
 use tokio::sync::{Mutex, MutexGuard};
+use uuid::Uuid;
 use wal::{
-    log_batch::LogEntry,
+    log_batch::{LogEntry, PayloadDecodeContext, PayloadDecoder},
     manager::{
         ReadBoundary, ReadContext, ReadRequest, RegionId, ScanContext, ScanRequest, WalManagerRef,
     },
@@ -50,12 +55,13 @@ use crate::{
         self,
         engine::{Error, ReplayWalWithCause, Result},
         flush_compaction::{Flusher, TableFlushOptions},
+        replay_throttle::ReplayThrottleRef,
         serial_executor::TableOpSerialExecutor,
         write::{Error as WriteError, MemTableWriter},
     },
-    payload::{ReadPayload, SingleSchemaProviderAdapter, TableSchemaProvider, WalDecoder},
+    payload::{self, ReadPayload, SingleSchemaProviderAdapter, TableSchemaProvider, WalDecoder},
     table::data::TableDataRef,
-    ErrorKind,
+    AutoRecoverConfig, ErrorKind, WalReplayCorruptionPolicy,
 };
 
 // Metrics of wal replayer
@@ -72,22 +78,32 @@ lazy_static! {
         exponential_buckets(0.01, 2.0, 13).unwrap()
     )
     .unwrap();
+    static ref SKIPPED_LOGS_COUNTER: IntCounter = register_int_counter!(
+        "wal_replay_skipped_logs",
+        "Counter of the corrupted logs skipped in wal replay"
+    )
+    .unwrap();
 }
 
 /// Wal replayer supporting both table based and region based
-// TODO: limit the memory usage in `RegionBased` mode.
 pub struct WalReplayer<'a> {
     context: ReplayContext,
-    replay: Box<dyn Replay>,
+    replay_mode: ReplayMode,
     table_datas: &'a [TableDataRef],
 }
 
 impl<'a> WalReplayer<'a> {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         table_datas: &'a [TableDataRef],
         shard_id: ShardId,
         wal_manager: WalManagerRef,
         wal_replay_batch_size: usize,
+        replay_throttle: ReplayThrottleRef,
+        replay_memory_budget: usize,
+        replay_parallelism: usize,
+        corruption_policy: WalReplayCorruptionPolicy,
+        dedup_window: usize,
         flusher: Flusher,
         max_retry_flush_limit: usize,
         replay_mode: ReplayMode,
@@ -96,26 +112,75 @@ impl<'a> WalReplayer<'a> {
             shard_id,
             wal_manager,
             wal_replay_batch_size,
+            replay_throttle,
+            replay_memory_budget,
+            replay_parallelism,
+            corruption_policy,
+            dedup_window,
+            skipped_logs: StdMutex::new(SkippedLogs::new()),
             flusher,
             max_retry_flush_limit,
         };
 
-        let replay = Self::build_replay(replay_mode);
-
         Self {
-            replay,
             context,
+            replay_mode,
             table_datas,
         }
     }
 
-    fn build_replay(mode: ReplayMode) -> Box<dyn Replay> {
-        info!("Replay wal in mode:{mode:?}");
+    async fn build_replay(&self) -> Box<dyn Replay> {
+        let region_based = match self.replay_mode {
+            ReplayMode::RegionBased => true,
+            ReplayMode::TableBased => false,
+            ReplayMode::Auto(config) => self.prefer_region_based(&config).await,
+        };
+        info!(
+            "Replay wal in mode:{:?}, shard_id:{}, region_based:{region_based}",
+            self.replay_mode, self.context.shard_id
+        );
+
+        if region_based {
+            Box::new(RegionBasedReplay)
+        } else {
+            Box::new(TableBasedReplay)
+        }
+    }
+
+    /// Replaying tables together scans the logs of the shard only once, which
+    /// pays off when there are many tables, but all the tables are locked and
+    /// their memtables grow together until the end of the replay.
+    async fn prefer_region_based(&self, config: &AutoRecoverConfig) -> bool {
+        if self.table_datas.len() < config.min_tables_for_shard_based {
+            return false;
+        }
 
-        match mode {
-            ReplayMode::RegionBased => Box::new(RegionBasedReplay),
-            ReplayMode::TableBased => Box::new(TableBasedReplay),
+        // The difference between the last and the flushed sequence is an upper
+        // bound of the logs to replay, as the sequences may be shared by the
+        // tables of the same region.
+        let mut num_logs = 0;
+        for table_data in self.table_datas {
+            let table_location = table_data.table_location();
+            let wal_location =
+                instance::create_wal_location(table_location.id, table_location.shard_info);
+            let last_sequence = match self.context.wal_manager.sequence_num(wal_location).await {
+                Ok(v) => v,
+                Err(e) => {
+                    warn!(
+                        "Failed to estimate logs to replay, table:{}, err:{e}",
+                        table_data.name
+                    );
+                    continue;
+                }
+            };
+            let flushed_sequence = table_data.current_version().flushed_sequence();
+            num_logs += last_sequence.saturating_sub(flushed_sequence);
+            if num_logs > config.max_logs_for_shard_based {
+                return false;
+            }
         }
+
+        true
     }
 
     /// Replay tables and return the failed tables and the causes.
@@ -127,18 +192,35 @@ impl<'a> WalReplayer<'a> {
             self.context, self.table_datas
         );
         let begin = Instant::now();
-        let result = self.replay.run(&self.context, self.table_datas).await;
+        let replay = self.build_replay().await;
+        let result = replay.run(&self.context, self.table_datas).await;
         let cost = Instant::now().duration_since(begin);
         info!("Replay wal logs finish, table_num:{table_num}, cost:{cost:?}");
 
         result
     }
+
+    /// Take the corrupted logs skipped in the replay.
+    pub fn take_skipped_logs(&mut self) -> SkippedLogs {
+        std::mem::take(&mut *self.context.skipped_logs.lock().unwrap())
+    }
 }
 
 pub struct ReplayContext {
     pub shard_id: ShardId,
     pub wal_manager: WalManagerRef,
     pub wal_replay_batch_size: usize,
+    pub replay_throttle: ReplayThrottleRef,
+    /// Max memtable memory of the replayed tables, zero means no limit
+    pub replay_memory_budget: usize,
+    /// Max number of tables replayed concurrently
+    pub replay_parallelism: usize,
+    pub corruption_policy: WalReplayCorruptionPolicy,
+    /// Max number of the latest write batch ids of a table kept to skip the
+    /// duplicates appended by the retried wal writes
+    pub dedup_window: usize,
+    /// Sequences of the corrupted logs skipped by the `corruption_policy`
+    pub skipped_logs: StdMutex<SkippedLogs>,
     pub flusher: Flusher,
     pub max_retry_flush_limit: usize,
 }
@@ -148,6 +230,10 @@ impl Display for ReplayContext {
         f.debug_struct("ReplayContext")
             .field("shard_id", &self.shard_id)
             .field("replay_batch_size", &self.wal_replay_batch_size)
+            .field("replay_memory_budget", &self.replay_memory_budget)
+            .field("replay_parallelism", &self.replay_parallelism)
+            .field("corruption_policy", &self.corruption_policy)
+            .field("dedup_window", &self.dedup_window)
             .field("max_retry_flush_limit", &self.max_retry_flush_limit)
             .finish()
     }
@@ -157,10 +243,155 @@ impl Display for ReplayContext {
 pub enum ReplayMode {
     RegionBased,
     TableBased,
+    /// Choose one of the above by the tables and logs to replay
+    Auto(AutoRecoverConfig),
 }
 
 pub type FailedTables = HashMap<TableId, Error>;
 
+pub type SkippedLogs = HashMap<TableId, Vec<SequenceNumber>>;
+
+/// Flushed sequences of the replayed tables, keyed by the table id.
+type FlushedSequences = Arc<HashMap<common_types::table::TableId, SequenceNumber>>;
+
+fn flushed_sequences(table_datas: &[TableDataRef]) -> FlushedSequences {
+    let flushed_sequences = table_datas
+        .iter()
+        .map(|table_data| {
+            let flushed_sequence = table_data.current_version().flushed_sequence();
+            (table_data.id.as_u64(), flushed_sequence)
+        })
+        .collect();
+
+    Arc::new(flushed_sequences)
+}
+
+/// Payload decoded in the wal replay.
+#[derive(Debug)]
+enum ReplayPayload {
+    /// The log isn't flushed yet, and is decoded fully.
+    Unflushed(ReadPayload),
+    /// Only the write batch id of the flushed log is decoded, which is still
+    /// needed to skip its duplicates.
+    Flushed { batch_id: Option<Uuid> },
+    /// The corrupted log skipped by the corruption policy.
+    Corrupted,
+}
+
+/// Decoder of the wal replay, which decodes just the write batch ids of the
+/// flushed logs, and applies the corruption policy to the corrupted logs,
+/// including the records corrupted in the wal storage and the payloads failed
+/// to decode. Other errors, e.g. the missing schema, always fail the replay.
+struct ReplayDecoder<P> {
+    inner: WalDecoder<P>,
+    flushed_sequences: FlushedSequences,
+    corruption_policy: WalReplayCorruptionPolicy,
+}
+
+impl<P> ReplayDecoder<P> {
+    fn new(
+        inner: WalDecoder<P>,
+        flushed_sequences: FlushedSequences,
+        corruption_policy: WalReplayCorruptionPolicy,
+    ) -> Self {
+        Self {
+            inner,
+            flushed_sequences,
+            corruption_policy,
+        }
+    }
+}
+
+impl<P: TableSchemaProvider + Send + Sync> PayloadDecoder for ReplayDecoder<P> {
+    type Error = payload::Error;
+    type Target = ReplayPayload;
+
+    fn decode<B: Buf>(
+        &self,
+        ctx: &PayloadDecodeContext,
+        buf: &mut B,
+    ) -> std::result::Result<Self::Target, Self::Error> {
+        let flushed = self
+            .flushed_sequences
+            .get(&ctx.table_id)
+            .is_some_and(|flushed_sequence| ctx.sequence <= *flushed_sequence);
+        let result = if flushed {
+            payload::decode_write_batch_id(buf).map(|batch_id| ReplayPayload::Flushed { batch_id })
+        } else {
+            self.inner.decode(ctx, buf).map(ReplayPayload::Unflushed)
+        };
+
+        match result {
+            Ok(v) => Ok(v),
+            Err(e) if !e.is_corrupted() => Err(e),
+            Err(e) => match self.corruption_policy {
+                WalReplayCorruptionPolicy::Fail => Err(e),
+                WalReplayCorruptionPolicy::SkipAndReport => {
+                    warn!(
+                        "Failed to decode log in wal replay, table_id:{}, sequence:{}, err:{e}",
+                        ctx.table_id, ctx.sequence
+                    );
+                    Ok(ReplayPayload::Corrupted)
+                }
+            },
+        }
+    }
+
+    fn decode_corrupted(&self, ctx: &PayloadDecodeContext) -> Option<Self::Target> {
+        match self.corruption_policy {
+            WalReplayCorruptionPolicy::Fail => None,
+            WalReplayCorruptionPolicy::SkipAndReport => {
+                warn!(
+                    "Corrupted log record in wal replay, table_id:{}, sequence:{}",
+                    ctx.table_id, ctx.sequence
+                );
+                Some(ReplayPayload::Corrupted)
+            }
+        }
+    }
+}
+
+/// The latest write batch ids of a table, the oldest one is evicted once
+/// there are more than `capacity` ids.
+///
+/// The duplicates of a write batch are appended by the retries of the same
+/// log batch, while the table is locked by the write, so only the latest ids
+/// are needed to skip them.
+struct ReplayedBatches {
+    ids: HashSet<Uuid>,
+    order: VecDeque<Uuid>,
+    capacity: usize,
+}
+
+impl ReplayedBatches {
+    fn new(capacity: usize) -> Self {
+        Self {
+            ids: HashSet::with_capacity(capacity),
+            order: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Returns false if the batch is in the window already.
+    fn insert(&mut self, batch_id: Uuid) -> bool {
+        if self.capacity == 0 {
+            return true;
+        }
+        if !self.ids.insert(batch_id) {
+            return false;
+        }
+
+        self.order.push_back(batch_id);
+        if self.order.len() > self.capacity {
+            if let Some(evicted) = self.order.pop_front() {
+                self.ids.remove(&evicted);
+            }
+        }
+
+        true
+    }
+}
+
 /// Replay action, the abstract of different replay strategies
 #[async_trait]
 trait Replay: Send + Sync + 'static {
@@ -202,7 +433,7 @@ impl Replay for TableBasedReplay {
                 })
                 .collect::<Vec<_>>(),
         )
-        .buffer_unordered(20);
+        .buffer_unordered(context.replay_parallelism);
         while let Some((table_id, ret)) = tasks.next().await {
             if let Err(e) = ret {
                 // If occur error, mark this table as failed and store the cause.
@@ -223,9 +454,12 @@ impl TableBasedReplay {
         let table_location = table_data.table_location();
         let wal_location =
             instance::create_wal_location(table_location.id, table_location.shard_info);
+        // The retries of a wal write finish before its rows can be flushed, so the
+        // duplicates of the flushed write batches aren't after the flushed sequence.
+        let flushed_sequence = table_data.current_version().flushed_sequence();
         let read_req = ReadRequest {
             location: wal_location,
-            start: ReadBoundary::Excluded(table_data.current_version().flushed_sequence()),
+            start: ReadBoundary::Excluded(flushed_sequence),
             end: ReadBoundary::Max,
         };
 
@@ -238,14 +472,22 @@ impl TableBasedReplay {
             .context(ReplayWalWithCause { msg: None })?;
 
         let mut serial_exec = table_data.serial_exec.lock().await;
+        let mut replayed_batches = ReplayedBatches::new(context.dedup_window);
+        let flushed_sequences = flushed_sequences(std::slice::from_ref(table_data));
         let mut log_entry_buf = VecDeque::with_capacity(context.wal_replay_batch_size);
+        let mut throttle_tracker = context.replay_throttle.tracker();
         loop {
+            let begin = Instant::now();
             // fetch entries to log_entry_buf
             let _timer = PULL_LOGS_DURATION_HISTOGRAM.start_timer();
             let adapter = SingleSchemaProviderAdapter {
                 schema: table_data.schema(),
             };
-            let decoder = WalDecoder::new(adapter);
+            let decoder = ReplayDecoder::new(
+                WalDecoder::new(adapter),
+                flushed_sequences.clone(),
+                context.corruption_policy,
+            );
             // All the logs should belong the table, so no need to check again.
             let filter = |_| true;
             log_entry_buf = log_iter
@@ -265,9 +507,13 @@ impl TableBasedReplay {
                 context.max_retry_flush_limit,
                 &mut serial_exec,
                 table_data,
+                &mut replayed_batches,
+                &context.skipped_logs,
                 log_entry_buf.iter(),
             )
             .await?;
+
+            throttle_tracker.maybe_pause(begin.elapsed()).await;
         }
 
         Ok(())
@@ -344,20 +590,27 @@ impl RegionBasedReplay {
             let serial_exec_ctx = SerialExecContext {
                 table_data: table_data.clone(),
                 serial_exec,
+                replayed_batches: ReplayedBatches::new(context.dedup_window),
             };
-            serial_exec_ctxs.insert(table_data.id, serial_exec_ctx);
+            serial_exec_ctxs.insert(table_data.id, Mutex::new(serial_exec_ctx));
             table_datas_by_id.insert(table_data.id.as_u64(), table_data.clone());
         }
 
         let table_datas_by_id = Arc::new(table_datas_by_id);
+        let flushed_sequences = flushed_sequences(table_datas);
         let schema_provider = TableSchemaProviderAdapter {
             table_datas: table_datas_by_id.clone(),
         };
-        let serial_exec_ctxs = Arc::new(Mutex::new(serial_exec_ctxs));
         // Split and replay logs.
+        let mut throttle_tracker = context.replay_throttle.tracker();
         loop {
+            let begin = Instant::now();
             let _timer = PULL_LOGS_DURATION_HISTOGRAM.start_timer();
-            let decoder = WalDecoder::new(schema_provider.clone());
+            let decoder = ReplayDecoder::new(
+                WalDecoder::new(schema_provider.clone()),
+                flushed_sequences.clone(),
+                context.corruption_policy,
+            );
             let table_datas_for_filter = table_datas_by_id.clone();
             let log_filter = move |log_table_id| table_datas_for_filter.contains_key(&log_table_id);
             log_entry_buf = log_iter
@@ -373,22 +626,91 @@ impl RegionBasedReplay {
             let _timer = APPLY_LOGS_DURATION_HISTOGRAM.start_timer();
             Self::replay_single_batch(context, &log_entry_buf, &serial_exec_ctxs, failed_tables)
                 .await?;
+            // Don't pull more logs until the memtables fit in the budget again.
+            Self::flush_over_memory_budget(context, &serial_exec_ctxs, failed_tables).await;
+
+            throttle_tracker.maybe_pause(begin.elapsed()).await;
         }
 
         Ok(())
     }
 
+    /// Flush the tables with the largest memtables and wait for the flushes
+    /// to finish, until the memory usage of the replayed tables drops below
+    /// the budget.
+    async fn flush_over_memory_budget(
+        context: &ReplayContext,
+        serial_exec_ctxs: &SerialExecContexts<'_>,
+        failed_tables: &mut FailedTables,
+    ) {
+        let budget = context.replay_memory_budget;
+        if budget == 0 {
+            return;
+        }
+
+        let mut memory_usage = 0;
+        let mut ctxs = Vec::with_capacity(serial_exec_ctxs.len());
+        for (table_id, ctx) in serial_exec_ctxs {
+            let ctx = ctx.lock().await;
+            let table_memory_usage = ctx.table_data.memtable_memory_usage();
+            memory_usage += table_memory_usage;
+            if !failed_tables.contains_key(table_id) {
+                ctxs.push((*table_id, table_memory_usage, ctx));
+            }
+        }
+        if memory_usage <= budget {
+            return;
+        }
+        ctxs.sort_unstable_by(|a, b| b.1.cmp(&a.1));
+
+        info!(
+            "Replay wal exceeds memory budget, shard_id:{}, memory_usage:{memory_usage}, budget:{budget}",
+            context.shard_id
+        );
+        for (table_id, table_memory_usage, mut ctx) in ctxs {
+            if memory_usage <= budget {
+                break;
+            }
+
+            let ctx = &mut *ctx;
+            let opts = TableFlushOptions {
+                res_sender: None,
+                max_retry_flush_limit: context.max_retry_flush_limit,
+            };
+            let flush_scheduler = ctx.serial_exec.flush_scheduler();
+            let result = context
+                .flusher
+                .do_flush(flush_scheduler, &ctx.table_data, opts)
+                .await;
+            if let Err(e) = result {
+                // The table can't be replayed without exceeding the budget.
+                failed_tables.insert(
+                    table_id,
+                    Error::ReplayWalWithCause {
+                        msg: Some(format!(
+                            "flush for memory budget, table_id:{}, table_name:{}",
+                            ctx.table_data.id, ctx.table_data.name
+                        )),
+                        source: Box::new(e),
+                    },
+                );
+            }
+            memory_usage = memory_usage.saturating_sub(table_memory_usage);
+        }
+    }
+
     async fn replay_single_batch(
         context: &ReplayContext,
-        log_batch: &VecDeque<LogEntry<ReadPayload>>,
-        serial_exec_ctxs: &Arc<Mutex<HashMap<TableId, SerialExecContext<'_>>>>,
+        log_batch: &VecDeque<LogEntry<ReplayPayload>>,
+        serial_exec_ctxs: &SerialExecContexts<'_>,
         failed_tables: &mut FailedTables,
     ) -> Result<()> {
         let mut table_batches = Vec::new();
         // TODO: No `group_by` method in `VecDeque`, so implement it manually here...
         Self::split_log_batch_by_table(log_batch, &mut table_batches);
 
-        // TODO: Replay logs of different tables in parallel.
+        // Every table has its own lock, so the logs of different tables are
+        // replayed concurrently.
         let mut replay_tasks = Vec::with_capacity(table_batches.len());
         for table_batch in table_batches {
             // Some tables may have failed in previous replay, ignore them.
@@ -401,15 +723,18 @@ impl RegionBasedReplay {
                 .flat_map(|range| log_batch.range(range.clone()))
                 .collect();
 
-            let serial_exec_ctxs = serial_exec_ctxs.clone();
             replay_tasks.push(async move {
                 // Some tables may have been moved to other shards or dropped, ignore such logs.
-                if let Some(ctx) = serial_exec_ctxs.lock().await.get_mut(&table_batch.table_id) {
+                if let Some(ctx) = serial_exec_ctxs.get(&table_batch.table_id) {
+                    let mut ctx = ctx.lock().await;
+                    let ctx = &mut *ctx;
                     let result = replay_table_log_entries(
                         &context.flusher,
                         context.max_retry_flush_limit,
                         &mut ctx.serial_exec,
                         &ctx.table_data,
+                        &mut ctx.replayed_batches,
+                        &context.skipped_logs,
                         log_entries.into_iter(),
                     )
                     .await;
@@ -420,8 +745,8 @@ impl RegionBasedReplay {
             });
         }
 
-        // Run at most 20 tasks in parallel
-        let mut replay_tasks = futures::stream::iter(replay_tasks).buffer_unordered(20);
+        let mut replay_tasks =
+            futures::stream::iter(replay_tasks).buffer_unordered(context.replay_parallelism);
         while let Some((table_id, ret)) = replay_tasks.next().await {
             if let Some(Err(e)) = ret {
                 // If occur error, mark this table as failed and store the cause.
@@ -497,15 +822,24 @@ struct TableBatch {
 struct SerialExecContext<'a> {
     table_data: TableDataRef,
     serial_exec: MutexGuard<'a, TableOpSerialExecutor>,
+    replayed_batches: ReplayedBatches,
 }
 
+type SerialExecContexts<'a> = HashMap<TableId, Mutex<SerialExecContext<'a>>>;
+
 /// Replay all log entries into memtable and flush if necessary
+///
+/// The write batches already in `replayed_batches` are appended more than once
+/// by the retries, and they are skipped. The batches of the flushed logs read
+/// by the region based replay are added to it too.
 async fn replay_table_log_entries(
     flusher: &Flusher,
     max_retry_flush_limit: usize,
     serial_exec: &mut TableOpSerialExecutor,
     table_data: &TableDataRef,
-    log_entries: impl Iterator<Item = &LogEntry<ReadPayload>>,
+    replayed_batches: &mut ReplayedBatches,
+    skipped_logs: &StdMutex<SkippedLogs>,
+    log_entries: impl Iterator<Item = &LogEntry<ReplayPayload>>,
 ) -> Result<()> {
     let flushed_sequence = table_data.current_version().flushed_sequence();
     debug!(
@@ -517,13 +851,52 @@ async fn replay_table_log_entries(
         let (sequence, payload) = (log_entry.sequence, &log_entry.payload);
 
         // Ignore too old logs(sequence <= `flushed_sequence`).
-        if sequence <= flushed_sequence {
+        if sequence <= flushed_sequence || matches!(payload, ReplayPayload::Flushed { .. }) {
+            let batch_id = match payload {
+                ReplayPayload::Flushed { batch_id }
+                | ReplayPayload::Unflushed(ReadPayload::Write { batch_id, .. }) => *batch_id,
+                _ => None,
+            };
+            if let Some(batch_id) = batch_id {
+                replayed_batches.insert(batch_id);
+            }
             continue;
         }
 
+        let ReplayPayload::Unflushed(payload) = payload else {
+            warn!(
+                "Skip corrupted log during replaying, table:{}, table_id:{:?}, sequence:{sequence}",
+                table_data.name, table_data.id
+            );
+            SKIPPED_LOGS_COUNTER.inc();
+            skipped_logs
+                .lock()
+                .unwrap()
+                .entry(table_data.id)
+                .or_default()
+                .push(sequence);
+            table_data.set_last_sequence(sequence);
+            continue;
+        };
+
+        if let ReadPayload::Write {
+            batch_id: Some(batch_id),
+            ..
+        } = payload
+        {
+            if !replayed_batches.insert(*batch_id) {
+                debug!(
+                    "Skip duplicate write batch during replaying, table:{}, batch_id:{batch_id}, sequence:{sequence}",
+                    table_data.name
+                );
+                table_data.set_last_sequence(sequence);
+                continue;
+            }
+        }
+
         // Apply logs to memtable.
         match payload {
-            ReadPayload::Write { row_group } => {
+            ReadPayload::Write { row_group, .. } => {
                 trace!(
                     "Instance replay row_group, table:{}, row_group:{:?}",
                     table_data.name,
@@ -616,12 +989,25 @@ async fn replay_table_log_entries(
 
 #[cfg(test)]
 mod tests {
-    use std::collections::VecDeque;
+    use std::{
+        collections::{HashMap, VecDeque},
+        sync::Arc,
+    };
 
+    use common_types::{tests::build_schema, SequenceNumber};
+    use horaedbproto::table_requests::WriteRequest;
     use table_engine::table::TableId;
-    use wal::log_batch::LogEntry;
-
-    use crate::instance::wal_replayer::{RegionBasedReplay, TableBatch};
+    use uuid::Uuid;
+    use wal::log_batch::{LogEntry, Payload, PayloadDecodeContext, PayloadDecoder};
+
+    use crate::{
+        instance::wal_replayer::{
+            RegionBasedReplay, ReplayDecoder, ReplayPayload, ReplayedBatches, TableBatch,
+            TableSchemaProviderAdapter,
+        },
+        payload::{self, SingleSchemaProviderAdapter, WalDecoder, WritePayload},
+        WalReplayCorruptionPolicy,
+    };
 
     #[test]
     fn test_split_log_batch_by_table() {
@@ -631,6 +1017,109 @@ mod tests {
         }
     }
 
+    fn new_decoder(
+        flushed_sequence: SequenceNumber,
+        policy: WalReplayCorruptionPolicy,
+    ) -> ReplayDecoder<SingleSchemaProviderAdapter> {
+        let adapter = SingleSchemaProviderAdapter {
+            schema: build_schema(),
+        };
+        let flushed_sequences = Arc::new(HashMap::from([(0, flushed_sequence)]));
+        ReplayDecoder::new(WalDecoder::new(adapter), flushed_sequences, policy)
+    }
+
+    #[test]
+    fn test_replay_decoder_corruption_policy() {
+        let ctx = PayloadDecodeContext {
+            table_id: 0,
+            sequence: 1,
+        };
+        let corrupted = [0xff; 8];
+
+        let decoder = new_decoder(0, WalReplayCorruptionPolicy::Fail);
+        assert!(decoder.decode(&ctx, &mut corrupted.as_slice()).is_err());
+        assert!(decoder.decode_corrupted(&ctx).is_none());
+
+        let decoder = new_decoder(0, WalReplayCorruptionPolicy::SkipAndReport);
+        let payload = decoder.decode(&ctx, &mut corrupted.as_slice()).unwrap();
+        assert!(matches!(payload, ReplayPayload::Corrupted));
+        assert!(matches!(
+            decoder.decode_corrupted(&ctx),
+            Some(ReplayPayload::Corrupted)
+        ));
+
+        // The log of the table without schema isn't corrupted, so it's never skipped.
+        let adapter = TableSchemaProviderAdapter {
+            table_datas: Arc::new(HashMap::new()),
+        };
+        let decoder = ReplayDecoder::new(
+            WalDecoder::new(adapter),
+            Arc::new(HashMap::new()),
+            WalReplayCorruptionPolicy::SkipAndReport,
+        );
+        let request = WriteRequest::default();
+        let write = WritePayload::from(&request);
+        let mut buf = Vec::with_capacity(write.encode_size());
+        write.encode_to(&mut buf).unwrap();
+        let err = decoder.decode(&ctx, &mut buf.as_slice()).unwrap_err();
+        assert!(matches!(err, payload::Error::TableSchemaNotFound { .. }));
+    }
+
+    #[test]
+    fn test_replay_decoder_flushed_logs() {
+        let batch_id = Uuid::new_v4();
+        let request = WriteRequest::default();
+        let write = WritePayload::Write {
+            request: &request,
+            batch_id,
+        };
+        let mut buf = Vec::with_capacity(write.encode_size());
+        write.encode_to(&mut buf).unwrap();
+
+        // The body of the flushed log isn't decoded.
+        let decoder = new_decoder(2, WalReplayCorruptionPolicy::Fail);
+        for sequence in [1, 2] {
+            let ctx = PayloadDecodeContext {
+                table_id: 0,
+                sequence,
+            };
+            let payload = decoder.decode(&ctx, &mut buf.as_slice()).unwrap();
+            assert!(
+                matches!(payload, ReplayPayload::Flushed { batch_id: Some(id) } if id == batch_id)
+            );
+        }
+
+        // The logs of other tables are decoded fully, which fails without the schema
+        // in the request.
+        let ctx = PayloadDecodeContext {
+            table_id: 1,
+            sequence: 1,
+        };
+        assert!(decoder.decode(&ctx, &mut buf.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_replayed_batches_window() {
+        let ids: Vec<_> = (0..4).map(|_| Uuid::new_v4()).collect();
+        let mut replayed_batches = ReplayedBatches::new(2);
+        assert!(replayed_batches.insert(ids[0]));
+        assert!(replayed_batches.insert(ids[1]));
+        assert!(!replayed_batches.insert(ids[0]));
+        assert!(!replayed_batches.insert(ids[1]));
+
+        // The oldest id is evicted from the window.
+        assert!(replayed_batches.insert(ids[2]));
+        assert!(replayed_batches.insert(ids[0]));
+        assert!(!replayed_batches.insert(ids[2]));
+        assert_eq!(2, replayed_batches.ids.len());
+        assert_eq!(2, replayed_batches.order.len());
+
+        // No dedup without the window.
+        let mut replayed_batches = ReplayedBatches::new(0);
+        assert!(replayed_batches.insert(ids[3]));
+        assert!(replayed_batches.insert(ids[3]));
+    }
+
     #[allow(clippy::single_range_in_vec_init)]
     fn test_set() -> Vec<(VecDeque<LogEntry<u32>>, Vec<TableBatch>)> {
         let test_log_batch1: VecDeque<LogEntry<u32>> = VecDeque::from([
@@ -0,0 +1,127 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Scan of the wal of a table, which is used for debugging and auditing.
+
+use std::collections::VecDeque;
+
+use common_types::row::Row;
+use snafu::{OptionExt, ResultExt};
+use table_engine::engine::{ScanWalRequest, ScanWalResult, WalEntryView};
+use wal::manager::{ReadBoundary, ReadContext, ReadRequest};
+
+use crate::{
+    engine::build_space_id,
+    instance::{
+        self,
+        engine::{ReadWal, Result, TableNotExist},
+        Instance,
+    },
+    payload::{ReadPayload, SingleSchemaProviderAdapter, WalDecoder},
+};
+
+const KIND_WRITE: &str = "write";
+const KIND_ALTER_SCHEMA: &str = "alter_schema";
+const KIND_ALTER_OPTIONS: &str = "alter_options";
+
+impl Instance {
+    /// Scan the wal entries of the opened table in the sequence range, the
+    /// entries are decoded with the current schema of the table.
+    ///
+    /// The scan stops after `limit` entries are collected, and the sequence of
+    /// the next entry is returned for the caller to continue.
+    pub async fn scan_wal(&self, request: ScanWalRequest) -> Result<ScanWalResult> {
+        let table_data = self
+            .find_space(build_space_id(request.schema_id))
+            .and_then(|space| space.find_table(&request.table_name))
+            .with_context(|| TableNotExist {
+                msg: format!("table:{} is not opened", request.table_name),
+            })?;
+
+        let table_location = table_data.table_location();
+        let wal_location =
+            instance::create_wal_location(table_location.id, table_location.shard_info);
+        let read_req = ReadRequest {
+            location: wal_location,
+            start: ReadBoundary::Included(request.start_sequence),
+            end: request
+                .end_sequence
+                .map(ReadBoundary::Included)
+                .unwrap_or(ReadBoundary::Max),
+        };
+        let mut log_iter = self
+            .space_store
+            .wal_manager
+            .read_batch(&ReadContext::default(), &read_req)
+            .await
+            .context(ReadWal)?;
+
+        let schema = table_data.schema();
+        let mut result = ScanWalResult::default();
+        let mut log_entry_buf = VecDeque::new();
+        loop {
+            let decoder = WalDecoder::new(SingleSchemaProviderAdapter {
+                schema: schema.clone(),
+            });
+            log_entry_buf = log_iter
+                .next_log_entries(decoder, |_| true, log_entry_buf)
+                .await
+                .context(ReadWal)?;
+            if log_entry_buf.is_empty() {
+                return Ok(result);
+            }
+
+            for log_entry in log_entry_buf.drain(..) {
+                let (kind, rows) = match &log_entry.payload {
+                    ReadPayload::Write { row_group, .. } => {
+                        let rows: Vec<&Row> = row_group
+                            .iter()
+                            .filter(|row| match (&request.time_range, row.timestamp(&schema)) {
+                                (Some(time_range), Some(ts)) => time_range.contains(ts),
+                                _ => true,
+                            })
+                            .collect();
+                        // Skip the writes with no rows in the time range.
+                        if rows.is_empty() {
+                            continue;
+                        }
+                        (KIND_WRITE, rows)
+                    }
+                    ReadPayload::AlterSchema { .. } => (KIND_ALTER_SCHEMA, Vec::new()),
+                    ReadPayload::AlterOptions { .. } => (KIND_ALTER_OPTIONS, Vec::new()),
+                };
+
+                if result.entries.len() >= request.limit {
+                    result.next_sequence = Some(log_entry.sequence);
+                    return Ok(result);
+                }
+
+                result.entries.push(WalEntryView {
+                    sequence: log_entry.sequence,
+                    kind: kind.to_string(),
+                    num_rows: rows.len(),
+                    size: rows.iter().map(|row| row.size()).sum(),
+                    preview: rows
+                        .iter()
+                        .take(request.max_preview_rows)
+                        .map(|row| format!("{:?}", row.iter().collect::<Vec<_>>()))
+                        .collect(),
+                });
+            }
+        }
+    }
+}
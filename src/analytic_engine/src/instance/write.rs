@@ -17,7 +17,7 @@
 
 //! Write logic of instance
 
-use std::iter;
+use std::{iter, sync::Arc, time::Duration};
 
 use bytes_ext::ByteVec;
 use codec::{
@@ -27,7 +27,11 @@ use codec::{
 use common_types::{
     row::RowGroup,
     schema::{IndexInWriterSchema, Schema},
+    table::ShardId,
+    time::TimestampPrecision,
 };
+use future_ext::{Backoff, BackoffConfig};
+use generic_error::GenericError;
 use horaedbproto::{schema as schema_pb, table_requests};
 use itertools::Itertools;
 use logger::{debug, error, info, trace, warn};
@@ -37,22 +41,30 @@ use snafu::{ensure, Backtrace, ResultExt, Snafu};
 use table_engine::table::WriteRequest;
 use wal::{
     kv_encoder::LogBatchEncoder,
-    log_batch::Payload,
+    log_batch::{LogWriteBatch, Payload},
     manager::{SequenceNumber, WalLocation, WriteContext},
 };
 
 use crate::{
     instance,
     instance::{
-        flush_compaction::TableFlushOptions, serial_executor::TableOpSerialExecutor, InstanceRef,
+        flush_compaction::TableFlushOptions,
+        serial_executor::TableOpSerialExecutor,
+        write_epoch::WriteEpoch,
+        write_presort::{self, RowGroupSorter},
+        Instance, InstanceRef,
     },
     memtable::{key::KeySequence, PutContext},
     payload::WritePayload,
-    space::SpaceRef,
+    space::{SpaceAndTable, SpaceRef},
     table::{data::TableDataRef, version::MemTableForWrite},
     WalEncodeConfig, WalEncodeFormat,
 };
 
+/// Backoff between the retries of the failed wal writes.
+const WAL_WRITE_INIT_BACKOFF_MS: u64 = 10;
+const WAL_WRITE_MAX_BACKOFF_MS: u64 = 1000;
+
 #[derive(Debug, Snafu)]
 pub enum Error {
     #[snafu(display(
@@ -82,6 +94,9 @@ pub enum Error {
     #[snafu(display("Try to write to a dropped table, table:{}", table))]
     WriteDroppedTable { table: String },
 
+    #[snafu(display("Try to write to a table closed for idle, table:{}", table))]
+    WriteIdleClosedTable { table: String },
+
     #[snafu(display(
         "Too many rows to write (more than {}), table:{}, rows:{}.\nBacktrace:\n{}",
         MAX_ROWS_TO_WRITE,
@@ -95,6 +110,20 @@ pub enum Error {
         backtrace: Backtrace,
     },
 
+    #[snafu(display(
+        "Timestamp is out of range of the precision, table:{}, timestamp:{}, precision:{}.\nBacktrace:\n{}",
+        table,
+        timestamp,
+        precision,
+        backtrace,
+    ))]
+    TimestampOutOfRange {
+        table: String,
+        timestamp: i64,
+        precision: TimestampPrecision,
+        backtrace: Backtrace,
+    },
+
     #[snafu(display("Failed to find mutable memtable, table:{}, err:{}", table, source))]
     FindMutableMemTable {
         table: String,
@@ -124,6 +153,44 @@ pub enum Error {
 
     #[snafu(display("Failed to update sequence of memtable, err:{}", source))]
     UpdateMemTableSequence { source: crate::memtable::Error },
+
+    #[snafu(display("Write is rejected by hook, table:{}, err:{}", table, source))]
+    RejectedByHook { table: String, source: GenericError },
+
+    #[snafu(display(
+        "Invalid atomic write of tables, msg:{}.\nBacktrace:\n{}",
+        msg,
+        backtrace
+    ))]
+    InvalidAtomicWrite { msg: String, backtrace: Backtrace },
+
+    #[snafu(display(
+        "Failed to write to wal atomically, tables:{:?}, err:{}",
+        tables,
+        source
+    ))]
+    WriteLogBatches {
+        tables: Vec<String>,
+        source: wal::manager::Error,
+    },
+
+    #[snafu(display(
+        "Write with stale epoch is refused, table:{}, shard_id:{}, epoch:{}, current:{:?}, fenced:{}.\nBacktrace:\n{}",
+        table,
+        shard_id,
+        epoch,
+        current,
+        fenced,
+        backtrace
+    ))]
+    StaleWriteEpoch {
+        table: String,
+        shard_id: ShardId,
+        epoch: WriteEpoch,
+        current: Option<WriteEpoch>,
+        fenced: bool,
+        backtrace: Backtrace,
+    },
 }
 
 define_result!(Error);
@@ -303,11 +370,22 @@ impl WriteRowGroupSplitter {
     }
 }
 
+/// A write request which is validated and encoded but not written yet.
+struct PreparedWrite {
+    encode_ctx: EncodeContext,
+    /// The log batch to write to the wal, it is `None` if wal is disabled.
+    log_batch: Option<LogWriteBatch>,
+    /// Number of entries and bytes of the log batch.
+    log_batch_size: (usize, usize),
+}
+
 pub struct Writer<'a> {
     instance: InstanceRef,
     space: SpaceRef,
     table_data: TableDataRef,
     serial_exec: &'a mut TableOpSerialExecutor,
+    /// The epoch of the shard when the write is accepted
+    write_epoch: Option<WriteEpoch>,
 }
 
 impl<'a> Writer<'a> {
@@ -325,6 +403,128 @@ impl<'a> Writer<'a> {
             space,
             table_data,
             serial_exec,
+            write_epoch: None,
+        }
+    }
+
+    /// Set the epoch of the shard when the write is accepted, and the write
+    /// will be refused if the epoch is stale when appending to the wal.
+    pub fn with_write_epoch(mut self, write_epoch: Option<WriteEpoch>) -> Self {
+        self.write_epoch = write_epoch;
+        self
+    }
+}
+
+impl Instance {
+    /// Write to multiple tables of the same shard atomically.
+    ///
+    /// The log batches of all the tables are persisted by one wal write, so
+    /// either all or none of the writes will be recovered after crash.
+    /// Returns the number of rows written to each table.
+    pub(crate) async fn write_tables_atomically(
+        self: &Arc<Self>,
+        requests: Vec<(SpaceAndTable, WriteRequest)>,
+        write_epoch: Option<WriteEpoch>,
+    ) -> Result<Vec<usize>> {
+        let num_tables = requests.len();
+        let mut requests = requests.into_iter().enumerate().collect_vec();
+        // Acquire the serial executors in the order of table id to avoid deadlock.
+        requests.sort_unstable_by_key(|(_, (table, _))| table.table_data().id);
+        let (tables, write_reqs): (Vec<_>, Vec<_>) = requests
+            .into_iter()
+            .map(|(idx, (table, request))| ((idx, table), request))
+            .unzip();
+
+        let table_names = tables
+            .iter()
+            .map(|(_, table)| table.table_data().name.clone())
+            .collect_vec();
+        ensure!(
+            tables
+                .iter()
+                .map(|(_, table)| table.table_data().shard_info.shard_id)
+                .all_equal(),
+            InvalidAtomicWrite {
+                msg: format!("tables are not in the same shard, tables:{table_names:?}"),
+            }
+        );
+        ensure!(
+            tables
+                .windows(2)
+                .all(|v| v[0].1.table_data().id != v[1].1.table_data().id),
+            InvalidAtomicWrite {
+                msg: format!("table is written more than once, tables:{table_names:?}"),
+            }
+        );
+
+        let mut serial_execs = Vec::with_capacity(num_tables);
+        for (_, table) in &tables {
+            serial_execs.push(table.table_data().serial_exec.lock().await);
+        }
+        let mut writers = tables
+            .iter()
+            .zip(serial_execs.iter_mut())
+            .map(|((_, table), serial_exec)| {
+                Writer::new(
+                    self.clone(),
+                    table.space().clone(),
+                    table.table_data().clone(),
+                    serial_exec,
+                )
+                .with_write_epoch(write_epoch)
+            })
+            .collect_vec();
+
+        let mut prepared_writes = Vec::with_capacity(num_tables);
+        for (writer, request) in writers.iter_mut().zip(write_reqs) {
+            prepared_writes.push(writer.prepare(request).await?);
+        }
+        for writer in &writers {
+            writer.ensure_write_epoch()?;
+        }
+
+        let sequences = if self.disable_wal {
+            tables
+                .iter()
+                .map(|(_, table)| table.table_data().next_sequence())
+                .collect_vec()
+        } else {
+            let log_batches = prepared_writes
+                .iter_mut()
+                .filter_map(|prepared| prepared.log_batch.take())
+                .collect_vec();
+            self.space_store
+                .wal_manager
+                .write_atomically(&WriteContext::default(), &log_batches)
+                .await
+                .context(WriteLogBatches {
+                    tables: table_names,
+                })?
+        };
+
+        // The wal is written, so the remaining tables are still applied after one
+        // fails, and the failed ones are closed to rebuild their memtables from the
+        // wal on the next access.
+        let mut num_rows = vec![0; num_tables];
+        let mut first_err = None;
+        for (i, (writer, prepared)) in writers.iter_mut().zip(prepared_writes).enumerate() {
+            match writer.apply(prepared, sequences[i]).await {
+                Ok(v) => num_rows[tables[i].0] = v,
+                Err(e) => {
+                    let table_data = tables[i].1.table_data();
+                    error!(
+                        "Failed to apply the atomic write, close the table to recover from the wal, table:{}, table_id:{}, err:{e}",
+                        table_data.name, table_data.id
+                    );
+                    self.close_table_for_recovery(table_data);
+                    first_err.get_or_insert(e);
+                }
+            }
+        }
+
+        match first_err {
+            Some(e) => Err(e),
+            None => Ok(num_rows),
         }
     }
 }
@@ -417,17 +617,40 @@ impl<'a> MemTableWriter<'a> {
 impl<'a> Writer<'a> {
     pub(crate) async fn write(&mut self, request: WriteRequest) -> Result<usize> {
         let _timer = self.table_data.metrics.start_table_write_execute_timer();
+
+        let prepared = self.prepare(request).await?;
+        self.ensure_write_epoch()?;
+        let seq = match &prepared.log_batch {
+            Some(log_batch) => self.write_log_batch(log_batch).await?,
+            // When wal is disabled, just update the last_seq one by one.
+            None => self.table_data.next_sequence(),
+        };
+
+        self.apply(prepared, seq).await
+    }
+
+    /// Validate, preprocess and encode the request, nothing is written to the
+    /// wal or the memtable in this step.
+    async fn prepare(&mut self, request: WriteRequest) -> Result<PreparedWrite> {
         self.table_data.metrics.on_write_request_begin();
+        self.instance.replay_throttle.on_foreground_write();
 
         self.validate_before_write(&request)?;
+        self.instance
+            .space_store
+            .hooks
+            .before_write(&self.table_data, &request.row_group)
+            .await
+            .context(RejectedByHook {
+                table: &self.table_data.name,
+            })?;
         let mut encode_ctx = EncodeContext::new(request.row_group);
 
         self.preprocess_write(&mut encode_ctx).await?;
+        self.presort_rows(&mut encode_ctx);
 
-        let table_data = self.table_data.clone();
-        let seq = if self.instance.disable_wal {
-            // When wal is disabled, just update the last_seq one by one.
-            table_data.next_sequence()
+        let log_batch = if self.instance.disable_wal {
+            None
         } else {
             let encoded_payload = {
                 let _timer = self.table_data.metrics.start_table_write_encode_timer();
@@ -435,34 +658,53 @@ impl<'a> Writer<'a> {
                 encode_ctx.encode(&self.instance.wal_encode, &schema)?
             };
 
-            match encoded_payload {
+            let log_batch = match encoded_payload {
                 EncodedPayload::Rows(encoded_rows) => {
-                    self.write_to_wal_in_rows(encoded_rows).await?
+                    self.encode_rows_to_log_batch(encoded_rows)?
                 }
                 EncodedPayload::Cols(encoded_cols) => {
-                    self.write_to_wal_in_cols(encoded_cols).await?
+                    self.encode_cols_to_log_batch(encoded_cols)?
                 }
-            }
+            };
+            Some(log_batch)
         };
+        let log_batch_size = log_batch
+            .as_ref()
+            .map(|batch| {
+                let num_bytes = batch.entries.iter().map(|entry| entry.payload.len()).sum();
+                (batch.len(), num_bytes)
+            })
+            .unwrap_or_default();
+
+        Ok(PreparedWrite {
+            encode_ctx,
+            log_batch,
+            log_batch_size,
+        })
+    }
 
-        // Write the row group to the memtable and update the state in the mem.
+    /// Write the prepared request to the memtable and update the state in the
+    /// mem, the `sequence` should be the one allocated for its log batch.
+    async fn apply(&mut self, prepared: PreparedWrite, sequence: SequenceNumber) -> Result<usize> {
         let EncodeContext {
             row_group,
             index_in_writer,
-        } = encode_ctx;
-        self.write_to_mem(&table_data, &row_group, index_in_writer, seq)
+        } = prepared.encode_ctx;
+        let table_data = self.table_data.clone();
+        self.write_to_mem(&table_data, &row_group, index_in_writer, sequence)
             .await?;
+        let (num_entries, num_bytes) = prepared.log_batch_size;
+        table_data.metrics.on_wal_write(num_entries, num_bytes);
 
         Ok(row_group.num_rows())
     }
 
-    async fn write_to_wal_in_rows(&self, encoded_rows: Vec<ByteVec>) -> Result<SequenceNumber> {
+    fn encode_rows_to_log_batch(&self, encoded_rows: Vec<ByteVec>) -> Result<LogWriteBatch> {
         let split_res = self.maybe_split_write_request(encoded_rows);
         match split_res {
             SplitResult::Integrate { encoded_rows } => {
                 let write_req = self.make_rowwise_write_request(encoded_rows);
-                let payload = WritePayload::Write(&write_req);
-                self.write_to_wal(iter::once(payload)).await
+                self.encode_log_batch(iter::once(WritePayload::from(&write_req)))
             }
             SplitResult::Splitted { encoded_batches } => {
                 let write_reqs = encoded_batches
@@ -470,22 +712,20 @@ impl<'a> Writer<'a> {
                     .map(|v| self.make_rowwise_write_request(v))
                     .collect_vec();
 
-                let payload = write_reqs.iter().map(WritePayload::Write);
-                self.write_to_wal(payload).await
+                let payload = write_reqs.iter().map(WritePayload::from);
+                self.encode_log_batch(payload)
             }
         }
     }
 
-    async fn write_to_wal_in_cols(&self, encoded_cols: Vec<ByteVec>) -> Result<SequenceNumber> {
+    fn encode_cols_to_log_batch(&self, encoded_cols: Vec<ByteVec>) -> Result<LogWriteBatch> {
         let write_req = table_requests::WriteRequest {
             version: WalEncodeVersion::Columnar.as_u32(),
             schema: None,
             rows: vec![],
             cols: encoded_cols,
         };
-        let payload = WritePayload::Write(&write_req);
-
-        self.write_to_wal(iter::once(payload)).await
+        self.encode_log_batch(iter::once(WritePayload::from(&write_req)))
     }
 
     fn make_rowwise_write_request(
@@ -546,6 +786,7 @@ impl<'a> Writer<'a> {
             num_columns,
             num_written_bytes,
         );
+        table_data.collect_series(row_group);
 
         Ok(())
     }
@@ -561,11 +802,30 @@ impl<'a> Writer<'a> {
             }
         );
 
+        // The timestamps in precision other than millis must be convertible to
+        // millis, in which they are read by the queries.
+        let precision = self.table_data.table_options().timestamp_precision;
+        if precision != TimestampPrecision::Millisecond {
+            let schema = request.row_group.schema();
+            for row in request.row_group.iter() {
+                if let Some(timestamp) = row.timestamp(schema) {
+                    ensure!(
+                        precision.checked_to_millis(timestamp.as_i64()).is_some(),
+                        TimestampOutOfRange {
+                            table: &self.table_data.name,
+                            timestamp: timestamp.as_i64(),
+                            precision,
+                        }
+                    );
+                }
+            }
+        }
+
         Ok(())
     }
 
     /// Preprocess before write, check:
-    ///  - whether table is dropped
+    ///  - whether table is dropped or closed for idle
     ///  - memtable capacity and maybe trigger flush
     ///
     /// Fills [common_types::schema::IndexInWriterSchema] in [EncodeContext]
@@ -577,14 +837,18 @@ impl<'a> Writer<'a> {
                 table: &self.table_data.name,
             }
         );
+        ensure!(
+            !self.table_data.is_idle_closed(),
+            WriteIdleClosedTable {
+                table: &self.table_data.name,
+            }
+        );
 
         // Checks schema compatibility.
-        self.table_data
-            .schema()
-            .compatible_for_write(
-                encode_ctx.row_group.schema(),
-                &mut encode_ctx.index_in_writer,
-            )
+        encode_ctx.index_in_writer = self
+            .table_data
+            .write_schema_cache
+            .compatible_for_write(&self.table_data.schema(), encode_ctx.row_group.schema())
             .context(IncompatSchema)?;
 
         if self.instance.should_flush_instance() {
@@ -631,37 +895,116 @@ impl<'a> Writer<'a> {
         Ok(())
     }
 
-    /// Write log_batch into wal, return the sequence number of log_batch.
-    async fn write_to_wal<I, P>(&self, payloads: I) -> Result<SequenceNumber>
+    /// Sort the rows by the primary key and detect the disorder with the
+    /// previous batch, nothing is done if the presort is disabled.
+    fn presort_rows(&self, encode_ctx: &mut EncodeContext) {
+        let config = &self.instance.write_presort;
+        if !config.enable {
+            return;
+        }
+
+        let sorter = RowGroupSorter::new(&self.table_data.schema(), &encode_ctx.index_in_writer);
+        sorter.sort(&mut encode_ctx.row_group);
+
+        if let Some((min_timestamp, max_timestamp)) =
+            write_presort::timestamp_range(&encode_ctx.row_group)
+        {
+            let prev_max_timestamp = self
+                .table_data
+                .swap_last_write_max_timestamp(max_timestamp.as_i64());
+            if write_presort::check_disorder(
+                prev_max_timestamp,
+                min_timestamp,
+                config.tolerance_window,
+            ) {
+                debug!(
+                    "Write batch is out of order with the previous one, table:{}, min_timestamp:{:?}, prev_max_timestamp:{}",
+                    self.table_data.name, min_timestamp, prev_max_timestamp
+                );
+            }
+        }
+    }
+
+    /// Encode the payloads into the log batch of this table.
+    fn encode_log_batch<I, P>(&self, payloads: I) -> Result<LogWriteBatch>
     where
         I: Iterator<Item = P>,
         P: Payload,
     {
-        let _timer = self.table_data.metrics.start_table_write_wal_timer();
         let table_location = self.table_data.table_location();
         let wal_location =
             instance::create_wal_location(table_location.id, table_location.shard_info);
         let log_batch_encoder = LogBatchEncoder::create(wal_location);
-        let log_batch = log_batch_encoder
+        log_batch_encoder
             .encode_batch(payloads)
             .context(EncodePayloads {
                 table: &self.table_data.name,
                 wal_location,
-            })?;
+            })
+    }
+
+    /// Refuse the write if the shard lock is lost (and maybe taken over by
+    /// others) since the write is accepted.
+    fn ensure_write_epoch(&self) -> Result<()> {
+        let shard_id = self.table_data.shard_info.shard_id;
+        let epochs = &self.instance.shard_write_epochs;
+        if let Some(epoch) = self.write_epoch {
+            ensure!(
+                !epochs.is_stale(shard_id, Some(epoch)),
+                StaleWriteEpoch {
+                    table: &self.table_data.name,
+                    shard_id,
+                    epoch,
+                    current: epochs.current(shard_id),
+                    fenced: epochs.is_fenced(shard_id),
+                }
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Write log_batch into wal, return the sequence number of log_batch.
+    ///
+    /// The retryable failed write is retried with the same log batch after a
+    /// backoff, which may be appended more than once and is deduplicated by
+    /// its write batch ids on replay.
+    async fn write_log_batch(&self, log_batch: &LogWriteBatch) -> Result<SequenceNumber> {
+        let _timer = self.table_data.metrics.start_table_write_wal_timer();
 
         // Write to wal manager
         let write_ctx = WriteContext::default();
-        let sequence = self
-            .instance
-            .space_store
-            .wal_manager
-            .write(&write_ctx, &log_batch)
-            .await
-            .context(WriteLogBatch {
-                table: &self.table_data.name,
-            })?;
-
-        Ok(sequence)
+        let mut retries = 0;
+        let mut backoff = Backoff::new(&BackoffConfig {
+            init_backoff: Duration::from_millis(WAL_WRITE_INIT_BACKOFF_MS),
+            max_backoff: Duration::from_millis(WAL_WRITE_MAX_BACKOFF_MS),
+            base: 2.,
+        });
+        loop {
+            let res = self
+                .instance
+                .space_store
+                .wal_manager
+                .write(&write_ctx, log_batch)
+                .await;
+            match res {
+                Ok(sequence) => return Ok(sequence),
+                Err(e) if e.is_retryable() && retries < self.instance.max_retry_wal_write_limit => {
+                    retries += 1;
+                    let wait = backoff.next();
+                    warn!(
+                        "Failed to write wal and retry, table:{}, retries:{retries}, wait:{wait:?}, err:{e}",
+                        self.table_data.name
+                    );
+                    tokio::time::sleep(wait).await;
+                }
+                Err(e) => {
+                    return Err(e).context(WriteLogBatch {
+                        table: &self.table_data.name,
+                    })
+                }
+            }
+        }
     }
 
     /// Flush memtables of table in background.
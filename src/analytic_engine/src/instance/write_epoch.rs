@@ -0,0 +1,177 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Epochs of the shard locks to fence the stale writes.
+//!
+//! A write is stamped with the epoch installed when it is accepted, and it
+//! will be refused before appended to the wal if a newer epoch is installed
+//! since then, which means the shard lock has been lost (and maybe taken over
+//! by another node) during the write.
+//!
+//! The shard is fenced once its lock is lost, and all the writes are refused
+//! until a newer epoch is installed.
+
+use std::{collections::HashMap, sync::RwLock};
+
+use common_types::table::ShardId;
+use logger::info;
+
+/// Epoch of the shard lock, which increases whenever the lock is re-acquired.
+pub type WriteEpoch = u64;
+
+#[derive(Debug, Clone, Copy)]
+struct ShardEpoch {
+    epoch: WriteEpoch,
+    /// The lock of the epoch is lost.
+    fenced: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct ShardWriteEpochs {
+    epochs: RwLock<HashMap<ShardId, ShardEpoch>>,
+}
+
+impl ShardWriteEpochs {
+    /// Install the epoch of the shard, and the epoch never goes back.
+    ///
+    /// The fenced shard is only unfenced by a newer epoch.
+    pub fn install(&self, shard_id: ShardId, epoch: WriteEpoch) {
+        let mut epochs = self.epochs.write().unwrap();
+        let current = epochs.entry(shard_id).or_insert(ShardEpoch {
+            epoch,
+            fenced: false,
+        });
+        if current.epoch < epoch {
+            *current = ShardEpoch {
+                epoch,
+                fenced: false,
+            };
+        }
+
+        info!(
+            "Shard write epoch is installed, shard_id:{shard_id}, epoch:{}, fenced:{}",
+            current.epoch, current.fenced
+        );
+    }
+
+    /// Fence the shard to refuse all the writes until a newer epoch is
+    /// installed.
+    ///
+    /// Nothing is done if no epoch is installed for the shard.
+    pub fn fence(&self, shard_id: ShardId) {
+        let mut epochs = self.epochs.write().unwrap();
+        if let Some(current) = epochs.get_mut(&shard_id) {
+            current.fenced = true;
+
+            info!(
+                "Shard writes are fenced, shard_id:{shard_id}, epoch:{}",
+                current.epoch
+            );
+        }
+    }
+
+    /// Get the current epoch of the shard, which may be fenced.
+    pub fn current(&self, shard_id: ShardId) -> Option<WriteEpoch> {
+        self.epochs
+            .read()
+            .unwrap()
+            .get(&shard_id)
+            .map(|current| current.epoch)
+    }
+
+    /// Whether the writes of the shard are fenced.
+    pub fn is_fenced(&self, shard_id: ShardId) -> bool {
+        self.epochs
+            .read()
+            .unwrap()
+            .get(&shard_id)
+            .is_some_and(|current| current.fenced)
+    }
+
+    /// Check whether the write stamped with `epoch` is stale, that is the
+    /// shard is fenced or a newer epoch is installed.
+    ///
+    /// The write without epoch is never considered stale, e.g. in standalone
+    /// mode.
+    pub fn is_stale(&self, shard_id: ShardId, epoch: Option<WriteEpoch>) -> bool {
+        let Some(epoch) = epoch else {
+            return false;
+        };
+
+        self.epochs
+            .read()
+            .unwrap()
+            .get(&shard_id)
+            .is_some_and(|current| current.fenced || epoch < current.epoch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_install_epoch() {
+        let epochs = ShardWriteEpochs::default();
+        assert_eq!(epochs.current(0), None);
+
+        epochs.install(0, 10);
+        assert_eq!(epochs.current(0), Some(10));
+
+        // The epoch never goes back.
+        epochs.install(0, 5);
+        assert_eq!(epochs.current(0), Some(10));
+
+        epochs.install(0, 20);
+        assert_eq!(epochs.current(0), Some(20));
+        assert_eq!(epochs.current(1), None);
+    }
+
+    #[test]
+    fn test_fence_stale_writes() {
+        let epochs = ShardWriteEpochs::default();
+        assert!(!epochs.is_stale(0, None));
+        assert!(!epochs.is_stale(0, Some(1)));
+
+        // Fencing a shard without epoch does nothing.
+        epochs.fence(0);
+        assert_eq!(epochs.current(0), None);
+
+        epochs.install(0, 10);
+        let accepted = epochs.current(0);
+        assert!(!epochs.is_stale(0, accepted));
+
+        epochs.fence(0);
+        assert!(epochs.is_fenced(0));
+        assert!(epochs.is_stale(0, accepted));
+        assert!(!epochs.is_stale(0, None));
+
+        // The writes accepted after fencing are refused too.
+        assert!(epochs.is_stale(0, epochs.current(0)));
+
+        // The epoch of the lost lock doesn't unfence the shard.
+        epochs.install(0, 10);
+        assert!(epochs.is_fenced(0));
+        assert!(epochs.is_stale(0, epochs.current(0)));
+
+        // The lock is re-acquired with a newer epoch.
+        epochs.install(0, 30);
+        assert!(!epochs.is_fenced(0));
+        assert!(epochs.is_stale(0, accepted));
+        assert!(!epochs.is_stale(0, epochs.current(0)));
+    }
+}
@@ -0,0 +1,200 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Presort the rows of the write batch by the primary key before inserting
+//! them into the memtable, and detect the disorder between the batches.
+
+use std::cmp::Ordering;
+
+use common_types::{
+    row::{Row, RowGroup},
+    schema::{IndexInWriterSchema, Schema},
+    time::Timestamp,
+};
+use lazy_static::lazy_static;
+use prometheus::{register_int_counter, IntCounter};
+use serde::{Deserialize, Serialize};
+use time_ext::ReadableDuration;
+
+lazy_static! {
+    static ref WRITE_PRESORTED_BATCH_COUNTER: IntCounter = register_int_counter!(
+        "write_presorted_batch_counter",
+        "Counter of write batches sorted before inserting into memtable"
+    )
+    .unwrap();
+    static ref WRITE_DISORDERED_BATCH_COUNTER: IntCounter = register_int_counter!(
+        "write_disordered_batch_counter",
+        "Counter of write batches out of order with the previous batch beyond the tolerance"
+    )
+    .unwrap();
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct WritePresortConfig {
+    /// Whether to sort the rows of the write batch by the primary key
+    pub enable: bool,
+    /// The batch whose min timestamp is earlier than the max timestamp of the
+    /// previous batch by more than this window is flagged as disordered
+    pub tolerance_window: ReadableDuration,
+}
+
+impl Default for WritePresortConfig {
+    fn default() -> Self {
+        Self {
+            enable: true,
+            tolerance_window: ReadableDuration::minutes(1),
+        }
+    }
+}
+
+/// Sorter of the rows in the write batch.
+pub(crate) struct RowGroupSorter {
+    /// Indexes of the primary key columns in the writer schema
+    primary_key_indexes: Vec<usize>,
+}
+
+impl RowGroupSorter {
+    pub fn new(table_schema: &Schema, index_in_writer: &IndexInWriterSchema) -> Self {
+        // The missing primary key columns are filled with the same default value,
+        // so they are ignored in the comparison.
+        let primary_key_indexes = table_schema
+            .primary_key_indexes()
+            .iter()
+            .filter_map(|idx| index_in_writer.column_index_in_writer(*idx))
+            .collect();
+
+        Self {
+            primary_key_indexes,
+        }
+    }
+
+    fn compare(&self, lhs: &Row, rhs: &Row) -> Ordering {
+        for idx in &self.primary_key_indexes {
+            match lhs[*idx].partial_cmp(&rhs[*idx]) {
+                Some(Ordering::Equal) | None => continue,
+                Some(v) => return v,
+            }
+        }
+
+        Ordering::Equal
+    }
+
+    /// Sort the rows if they are not sorted yet, and return whether the rows
+    /// are reordered.
+    ///
+    /// The relative order of the rows with the same primary key is kept, so
+    /// the later one still overwrites the former one in the memtable.
+    pub fn sort(&self, row_group: &mut RowGroup) -> bool {
+        let is_sorted = (1..row_group.num_rows()).all(|i| {
+            let prev = row_group.get_row(i - 1).unwrap();
+            let curr = row_group.get_row(i).unwrap();
+            self.compare(prev, curr) != Ordering::Greater
+        });
+        if is_sorted {
+            return false;
+        }
+
+        row_group.sort_rows_by(|lhs, rhs| self.compare(lhs, rhs));
+        WRITE_PRESORTED_BATCH_COUNTER.inc();
+
+        true
+    }
+}
+
+/// Returns the min and max timestamp of the rows, None if the row group is
+/// empty.
+pub(crate) fn timestamp_range(row_group: &RowGroup) -> Option<(Timestamp, Timestamp)> {
+    let schema = row_group.schema();
+    row_group
+        .iter()
+        .filter_map(|row| row.timestamp(schema))
+        .fold(None, |range, ts| match range {
+            None => Some((ts, ts)),
+            Some((min, max)) => Some((min.min(ts), max.max(ts))),
+        })
+}
+
+/// Returns whether the batch starting at `min_timestamp` is out of order with
+/// the previous batch ended at `prev_max_timestamp` beyond the tolerance.
+pub(crate) fn check_disorder(
+    prev_max_timestamp: i64,
+    min_timestamp: Timestamp,
+    tolerance_window: ReadableDuration,
+) -> bool {
+    let tolerance_ms = tolerance_window.as_millis() as i64;
+    let disordered = min_timestamp.as_i64().saturating_add(tolerance_ms) < prev_max_timestamp;
+    if disordered {
+        WRITE_DISORDERED_BATCH_COUNTER.inc();
+    }
+
+    disordered
+}
+
+#[cfg(test)]
+mod tests {
+    use common_types::{
+        datum::Datum,
+        tests::{build_row, build_schema},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_sort_row_group() {
+        let schema = build_schema();
+        let index_in_writer = IndexInWriterSchema::for_same_schema(schema.num_columns());
+        let sorter = RowGroupSorter::new(&schema, &index_in_writer);
+
+        let rows = vec![
+            build_row(b"b", 1000, 1.0, "v1", 0, 0),
+            build_row(b"a", 2000, 2.0, "v2", 0, 0),
+            build_row(b"b", 1000, 3.0, "v3", 0, 0),
+            build_row(b"a", 1000, 4.0, "v4", 0, 0),
+        ];
+        let mut row_group = RowGroup::new_unchecked(schema, rows);
+        assert!(sorter.sort(&mut row_group));
+
+        let values: Vec<_> = row_group.iter().map(|row| row[2].clone()).collect();
+        assert_eq!(
+            vec![
+                Datum::Double(4.0),
+                Datum::Double(2.0),
+                Datum::Double(1.0),
+                Datum::Double(3.0)
+            ],
+            values
+        );
+
+        // Already sorted.
+        assert!(!sorter.sort(&mut row_group));
+        assert_eq!(
+            Some((Timestamp::new(1000), Timestamp::new(2000))),
+            timestamp_range(&row_group)
+        );
+    }
+
+    #[test]
+    fn test_check_disorder() {
+        let window = ReadableDuration::secs(1);
+
+        assert!(!check_disorder(i64::MIN, Timestamp::new(0), window));
+        assert!(!check_disorder(1500, Timestamp::new(1000), window));
+        assert!(!check_disorder(2000, Timestamp::new(1000), window));
+        assert!(check_disorder(2001, Timestamp::new(1000), window));
+    }
+}
@@ -23,6 +23,8 @@ mod compaction;
 mod context;
 mod engine;
 pub mod error;
+mod format_version;
+pub mod hook;
 mod instance;
 mod manifest;
 pub mod memtable;
@@ -50,7 +52,12 @@ use wal::config::Config as WalConfig;
 
 pub use crate::{
     compaction::scheduler::SchedulerConfig,
-    instance::{ScanType, SstReadOptionsBuilder},
+    instance::{
+        idle_table_reaper::IdleTableReaperConfig, replay_throttle::ReplayThrottleConfig,
+        sst_reconciler::SstReconcilerConfig, summary_refresher::TableSummaryConfig,
+        tiering_manifest::TieringManifestConfig, write_presort::WritePresortConfig, ScanType,
+        SstReadOptionsBuilder,
+    },
     table_options::TableOptions,
 };
 
@@ -65,6 +72,20 @@ pub struct Config {
     pub replay_batch_size: usize,
     /// Batch size to replay tables
     pub max_replay_tables_per_batch: usize,
+    /// Throttle the wal replay to leave room for the foreground writes
+    pub replay_throttle: ReplayThrottleConfig,
+    /// Max memtable memory of the tables replayed together in the shard based
+    /// recovery, the largest tables are flushed before reading more logs once
+    /// it's exceeded. Zero means no limit.
+    pub replay_memory_budget: ReadableSize,
+    /// Max number of tables whose logs are replayed concurrently
+    pub replay_parallelism: usize,
+    /// How to handle the logs failed to decode in the wal replay
+    pub wal_replay_corruption_policy: WalReplayCorruptionPolicy,
+    /// Max number of the latest write batch ids of a table kept in the wal
+    /// replay to skip the duplicates appended by the retried wal writes. It
+    /// should be larger than the number of the payloads in one log batch.
+    pub replay_dedup_window: usize,
 
     /// Default options for table
     pub table_opts: TableOptions,
@@ -109,10 +130,21 @@ pub struct Config {
     pub sst_background_read_parallelism: usize,
     /// Number of streams to prefetch
     pub num_streams_to_prefetch: usize,
+    /// The byte ranges of sst whose gap is within this size are fetched by one
+    /// request, and `None` means disabling coalescing.
+    ///
+    /// Larger gap means fewer requests to the object store but more useless
+    /// bytes fetched.
+    pub sst_get_range_coalesce_gap: Option<ReadableSize>,
     /// Max buffer size for writing sst
     pub write_sst_max_buffer_size: ReadableSize,
     /// Max retry limit After flush failed
     pub max_retry_flush_limit: usize,
+    /// Max retry limit after writing wal failed.
+    ///
+    /// The log batch may be appended more than once if the failure is
+    /// ambiguous, and the duplicates are skipped on replay.
+    pub max_retry_wal_write_limit: usize,
     /// The min interval between two consecutive flushes
     pub min_flush_interval: ReadableDuration,
     /// Max bytes per write batch.
@@ -137,7 +169,27 @@ pub struct Config {
     ///
     /// + TableBased, tables on same shard will be recovered table by table.
     /// + ShardBased, tables on same shard will be recovered together.
+    /// + Auto, one of the above is chosen for every shard by `auto_recover`.
     pub recover_mode: RecoverMode,
+    /// Thresholds to choose the recover mode of a shard in the `Auto` mode
+    pub auto_recover: AutoRecoverConfig,
+    /// Find the sst files left by the interrupted flushes and compactions
+    /// when the tables are opened
+    pub sst_reconciler: SstReconcilerConfig,
+
+    /// Close the tables idle for a long time to reclaim memory
+    pub idle_table_reaper: IdleTableReaperConfig,
+
+    /// Emit the manifests describing the ages and access frequency of the
+    /// ssts for the lifecycle policies of the object storage
+    pub tiering_manifest: TieringManifestConfig,
+
+    /// Refresh the summaries of the tables used as the estimated statistics
+    /// by the query optimizer
+    pub table_summary: TableSummaryConfig,
+
+    /// Sort the rows of the write batch before inserting into memtable
+    pub write_presort: WritePresortConfig,
 
     pub remote_engine_client: remote_engine_client::config::Config,
 
@@ -154,6 +206,37 @@ pub struct MetricsOptions {
 pub enum RecoverMode {
     TableBased,
     ShardBased,
+    Auto,
+}
+
+/// Policy to handle the logs failed to decode in the wal replay.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+pub enum WalReplayCorruptionPolicy {
+    /// Fail the recovery of the table
+    #[default]
+    Fail,
+    /// Skip the log and report its sequence
+    SkipAndReport,
+}
+
+/// A shard is recovered table by table if it has few tables, or too many logs
+/// to replay all its tables together, otherwise it's recovered as a whole.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AutoRecoverConfig {
+    /// Min number of tables of a shard to recover them together
+    pub min_tables_for_shard_based: usize,
+    /// Max estimated number of logs of a shard to recover its tables together
+    pub max_logs_for_shard_based: u64,
+}
+
+impl Default for AutoRecoverConfig {
+    fn default() -> Self {
+        Self {
+            min_tables_for_shard_based: 8,
+            max_logs_for_shard_based: 10_000_000,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, Deserialize, Serialize)]
@@ -183,6 +266,11 @@ impl Default for Config {
         Self {
             storage: Default::default(),
             replay_batch_size: 500,
+            replay_throttle: ReplayThrottleConfig::default(),
+            replay_memory_budget: ReadableSize(0),
+            replay_parallelism: 20,
+            wal_replay_corruption_policy: WalReplayCorruptionPolicy::default(),
+            replay_dedup_window: 1024,
             max_replay_tables_per_batch: 64,
             table_opts: TableOptions::default(),
             try_compat_old_layered_memtable_opts: false,
@@ -202,9 +290,12 @@ impl Default for Config {
             scan_batch_size: None,
             sst_background_read_parallelism: 8,
             num_streams_to_prefetch: 2,
+            // Only the adjacent ranges are coalesced by default.
+            sst_get_range_coalesce_gap: Some(ReadableSize(0)),
             scan_max_record_batches_in_flight: 1024,
             write_sst_max_buffer_size: ReadableSize::mb(10),
             max_retry_flush_limit: 0,
+            max_retry_wal_write_limit: 0,
             min_flush_interval: ReadableDuration::minutes(1),
             max_bytes_per_write_batch: None,
             mem_usage_sampling_interval: ReadableDuration::secs(0),
@@ -212,6 +303,12 @@ impl Default for Config {
             wal: WalConfig::default(),
             remote_engine_client: remote_engine_client::config::Config::default(),
             recover_mode: RecoverMode::ShardBased,
+            auto_recover: AutoRecoverConfig::default(),
+            sst_reconciler: SstReconcilerConfig::default(),
+            idle_table_reaper: IdleTableReaperConfig::default(),
+            tiering_manifest: TieringManifestConfig::default(),
+            write_presort: WritePresortConfig::default(),
+            table_summary: TableSummaryConfig::default(),
             metrics: MetricsOptions::default(),
         }
     }
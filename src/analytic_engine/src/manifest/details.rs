@@ -29,13 +29,11 @@ use std::{
 
 use async_trait::async_trait;
 use generic_error::{BoxError, GenericResult};
-use horaedbproto::manifest as manifest_pb;
 use lazy_static::lazy_static;
 use logger::{debug, info, warn};
 use object_store::{ObjectStoreRef, Path};
 use parquet::data_type::AsBytes;
 use prometheus::{exponential_buckets, register_histogram, Histogram};
-use prost::Message;
 use serde::{Deserialize, Serialize};
 use table_engine::table::TableId;
 use time_ext::ReadableDuration;
@@ -429,6 +427,25 @@ impl Manifest for ManifestImpl {
         info!("Manifest recover begin, request:{load_req:?}");
 
         // Load table meta snapshot from storage.
+        let meta_snapshot_opt = self.load(load_req).await?;
+        let meta_snapshot_exists = meta_snapshot_opt.is_some();
+        // Apply it to table.
+        if let Some(snapshot) = meta_snapshot_opt {
+            let meta_edit = MetaEdit::Snapshot(snapshot);
+            let request = MetaEditRequest {
+                shard_info: TableShardInfo::new(load_req.shard_id),
+                meta_edit,
+                table_catalog_info: load_req.table_catalog_info.clone(),
+            };
+            self.table_meta_set.apply_edit_to_table(request)?;
+        }
+
+        info!("Manifest recover finish, request:{load_req:?}, meta_snapshot_exist:{meta_snapshot_exists}");
+
+        Ok(())
+    }
+
+    async fn load(&self, load_req: &LoadRequest) -> GenericResult<Option<MetaSnapshot>> {
         let location = WalLocation::new(load_req.shard_id as u64, load_req.table_id.as_u64());
 
         let log_store = WalBasedLogStore {
@@ -447,22 +464,8 @@ impl Manifest for ManifestImpl {
             log_store,
             snapshot_store,
         };
-        let meta_snapshot_opt = recover.recover().await?.and_then(|v| v.data);
-        let meta_snapshot_exists = meta_snapshot_opt.is_some();
-        // Apply it to table.
-        if let Some(snapshot) = meta_snapshot_opt {
-            let meta_edit = MetaEdit::Snapshot(snapshot);
-            let request = MetaEditRequest {
-                shard_info: TableShardInfo::new(load_req.shard_id),
-                meta_edit,
-                table_catalog_info: load_req.table_catalog_info.clone(),
-            };
-            self.table_meta_set.apply_edit_to_table(request)?;
-        }
-
-        info!("Manifest recover finish, request:{load_req:?}, meta_snapshot_exist:{meta_snapshot_exists}");
 
-        Ok(())
+        Ok(recover.recover().await?.and_then(|v| v.data))
     }
 
     async fn do_snapshot(&self, request: SnapshotRequest) -> GenericResult<()> {
@@ -533,8 +536,7 @@ impl MetaUpdateSnapshotStore for ObjectStoreBasedSnapshotStore {
     /// Store the latest snapshot to the underlying store by overwriting the old
     /// snapshot.
     async fn store(&self, snapshot: &Snapshot) -> Result<()> {
-        let snapshot_pb = manifest_pb::Snapshot::from(snapshot.clone());
-        let payload = snapshot_pb.encode_to_vec();
+        let payload = snapshot.encode_record()?;
         // The atomic write is ensured by the [`ObjectStore`] implementation.
         self.store
             .put(&self.snapshot_path, payload.into())
@@ -571,9 +573,7 @@ impl MetaUpdateSnapshotStore for ObjectStoreBasedSnapshotStore {
             .bytes()
             .await
             .map_err(anyhow::Error::new)?;
-        let snapshot_pb =
-            manifest_pb::Snapshot::decode(payload.as_bytes()).map_err(anyhow::Error::new)?;
-        let snapshot = Snapshot::try_from(snapshot_pb).map_err(anyhow::Error::new)?;
+        let snapshot = Snapshot::decode_record(payload.as_bytes())?;
 
         Ok(Some(snapshot))
     }
@@ -650,7 +650,12 @@ mod tests {
 
     use arena::NoopCollector;
     use common_types::{
-        column_schema, datum::DatumKind, schema, schema::Schema, table::DEFAULT_SHARD_ID,
+        column_schema,
+        datum::DatumKind,
+        schema,
+        schema::Schema,
+        table::DEFAULT_SHARD_ID,
+        time::{Timestamp, TimestampPrecision},
     };
     use futures::future::BoxFuture;
     use object_store::local_file;
@@ -673,6 +678,7 @@ mod tests {
             tests::default_schema, MemSizeOptions, TableCatalogInfo, TableConfig, TableData,
             TableDesc, TableShardInfo,
         },
+        table_options::{ColumnTtl, DownsampleAggregator, DownsamplePolicy, FieldGroup},
         MetricsOptions, TableOptions,
     };
 
@@ -701,6 +707,30 @@ mod tests {
             .unwrap()
     }
 
+    /// Options with the ones persisted as the extension of the manifest
+    /// records.
+    fn table_options_with_ext() -> TableOptions {
+        TableOptions {
+            expire_at_column: Some("key2".to_string()),
+            downsample: Some(DownsamplePolicy {
+                after: ReadableDuration::days(1),
+                interval: ReadableDuration::minutes(10),
+                aggregator: DownsampleAggregator::Max,
+            }),
+            downsample_resolution_column: Some("field1".to_string()),
+            field_groups: vec![FieldGroup {
+                name: "group1".to_string(),
+                columns: vec!["field2".to_string(), "field3".to_string()],
+            }],
+            timestamp_precision: TimestampPrecision::Microsecond,
+            column_ttls: vec![ColumnTtl {
+                column: "field4".to_string(),
+                ttl: ReadableDuration::hours(12),
+            }],
+            ..Default::default()
+        }
+    }
+
     fn build_runtime(thread_num: usize) -> Arc<Runtime> {
         Arc::new(
             runtime::Builder::default()
@@ -878,7 +908,7 @@ mod tests {
                 table_id,
                 table_name,
                 schema: common_types::tests::build_schema(),
-                opts: TableOptions::default(),
+                opts: table_options_with_ext(),
             })
         }
 
@@ -904,6 +934,8 @@ mod tests {
                 files_to_delete: vec![],
                 mems_to_remove: vec![],
                 max_file_id: 0,
+                // The bound is persisted in the extension of the record.
+                delete_before: Some(Timestamp::new(1000)),
             })
         }
 
@@ -913,7 +945,7 @@ mod tests {
                 table_id,
                 options: TableOptions {
                     enable_ttl: false,
-                    ..Default::default()
+                    ..table_options_with_ext()
                 },
             })
         }
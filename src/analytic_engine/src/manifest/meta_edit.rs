@@ -20,9 +20,10 @@
 use std::convert::TryFrom;
 
 use anyhow::Context;
-use bytes_ext::{Buf, BufMut};
+use bytes_ext::{Buf, BufMut, SafeBufMut};
 use common_types::{
     schema::{Schema, Version},
+    time::Timestamp,
     SequenceNumber,
 };
 use horaedbproto::{manifest as manifest_pb, schema as schema_pb};
@@ -31,6 +32,7 @@ use table_engine::table::TableId;
 use wal::log_batch::{Payload, PayloadDecodeContext, PayloadDecoder};
 
 use crate::{
+    format_version::{MANIFEST_EXT_VERSION, MANIFEST_FORMAT, STAMP_SIZE},
     manifest::{meta_snapshot::MetaSnapshot, Error, Result},
     space::SpaceId,
     sst::manager::FileId,
@@ -39,6 +41,7 @@ use crate::{
         version::TableVersionMeta,
         version_edit::{AddFile, DeleteFile, VersionEdit},
     },
+    table_options::TableOptionsExt,
     TableOptions,
 };
 
@@ -86,6 +89,72 @@ impl MetaUpdate {
             MetaUpdate::DropTable(v) => v.space_id,
         }
     }
+
+    fn table_options(&self) -> Option<&TableOptions> {
+        match self {
+            MetaUpdate::AddTable(v) => Some(&v.opts),
+            MetaUpdate::AlterOptions(v) => Some(&v.options),
+            MetaUpdate::VersionEdit(_) | MetaUpdate::AlterSchema(_) | MetaUpdate::DropTable(_) => {
+                None
+            }
+        }
+    }
+
+    fn table_options_mut(&mut self) -> Option<&mut TableOptions> {
+        match self {
+            MetaUpdate::AddTable(v) => Some(&mut v.opts),
+            MetaUpdate::AlterOptions(v) => Some(&mut v.options),
+            MetaUpdate::VersionEdit(_) | MetaUpdate::AlterSchema(_) | MetaUpdate::DropTable(_) => {
+                None
+            }
+        }
+    }
+}
+
+/// Extension of the manifest records and the alter options payloads,
+/// carrying the table options missing in [manifest_pb::TableOptions] and the
+/// delete bound missing in [manifest_pb::VersionEditMeta].
+///
+/// It's encoded as a field whose tag is unused by [manifest_pb::MetaUpdate],
+/// [manifest_pb::Snapshot] and [manifest_pb::AlterOptionsMeta], and appended to
+/// their encoded bytes, so the record is still decoded as them.
+///
+/// The records carrying it are stamped with [MANIFEST_EXT_VERSION] at least, so
+/// the older binaries refuse them instead of dropping the options.
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct ManifestExt {
+    #[prost(message, optional, tag = "1000")]
+    pub(crate) table_options: Option<TableOptionsExt>,
+    /// See [VersionEdit::delete_before].
+    #[prost(int64, optional, tag = "1001")]
+    pub(crate) delete_before: Option<i64>,
+}
+
+// The extension must never be written with a version older binaries accept.
+const _: () = assert!(MANIFEST_FORMAT.current >= MANIFEST_EXT_VERSION);
+
+impl ManifestExt {
+    pub(crate) fn new(table_options: Option<&TableOptions>) -> Self {
+        Self {
+            table_options: table_options.and_then(TableOptions::to_pb_ext),
+            delete_before: None,
+        }
+    }
+
+    fn delete_before(&self) -> Option<Timestamp> {
+        self.delete_before.map(Timestamp::new)
+    }
+
+    /// Restore the extended options into `table_options`.
+    fn apply_to(&self, table_options: Option<&mut TableOptions>) -> Result<()> {
+        if let (Some(ext), Some(table_options)) = (&self.table_options, table_options) {
+            *table_options = table_options
+                .merge_pb_ext(ext)
+                .map_err(anyhow::Error::new)?;
+        }
+
+        Ok(())
+    }
 }
 
 impl TryFrom<manifest_pb::MetaUpdate> for MetaUpdate {
@@ -206,6 +275,8 @@ pub struct VersionEditMeta {
     /// No need to persist.
     pub mems_to_remove: Vec<MemTableId>,
     pub max_file_id: FileId,
+    /// Persisted in the [ManifestExt].
+    pub delete_before: Option<Timestamp>,
 }
 
 impl VersionEditMeta {
@@ -218,6 +289,7 @@ impl VersionEditMeta {
             files_to_add: self.files_to_add,
             files_to_delete: self.files_to_delete,
             max_file_id: self.max_file_id,
+            delete_before: self.delete_before,
         }
     }
 }
@@ -263,6 +335,7 @@ impl TryFrom<manifest_pb::VersionEditMeta> for VersionEditMeta {
             files_to_delete,
             mems_to_remove: Vec::default(),
             max_file_id: src.max_file_id,
+            delete_before: None,
         })
     }
 }
@@ -337,11 +410,24 @@ impl TryFrom<manifest_pb::AlterOptionsMeta> for AlterOptionsMeta {
 /// An adapter to implement [wal::log_batch::Payload] for
 /// [proto::meta_update::MetaUpdate]
 #[derive(Debug)]
-pub struct MetaUpdatePayload(manifest_pb::MetaUpdate);
+pub struct MetaUpdatePayload {
+    meta_update: manifest_pb::MetaUpdate,
+    ext: ManifestExt,
+}
 
 impl From<MetaUpdate> for MetaUpdatePayload {
     fn from(src: MetaUpdate) -> Self {
-        Self(src.into())
+        let ext = ManifestExt {
+            delete_before: match &src {
+                MetaUpdate::VersionEdit(v) => v.delete_before.map(|v| v.as_i64()),
+                _ => None,
+            },
+            ..ManifestExt::new(src.table_options())
+        };
+        Self {
+            meta_update: src.into(),
+            ext,
+        }
     }
 }
 
@@ -355,11 +441,14 @@ impl Payload for MetaUpdatePayload {
     type Error = Error;
 
     fn encode_size(&self) -> usize {
-        self.0.encoded_len()
+        STAMP_SIZE + self.meta_update.encoded_len() + self.ext.encoded_len()
     }
 
     fn encode_to<B: BufMut>(&self, buf: &mut B) -> Result<()> {
-        self.0.encode(buf).map_err(anyhow::Error::new)?;
+        buf.try_put(&MANIFEST_FORMAT.stamp())
+            .map_err(anyhow::Error::new)?;
+        self.meta_update.encode(buf).map_err(anyhow::Error::new)?;
+        self.ext.encode(buf).map_err(anyhow::Error::new)?;
         Ok(())
     }
 }
@@ -372,9 +461,19 @@ impl PayloadDecoder for MetaUpdateDecoder {
     type Target = MetaUpdate;
 
     fn decode<B: Buf>(&self, _ctx: &PayloadDecodeContext, buf: &mut B) -> Result<Self::Target> {
+        let body = MANIFEST_FORMAT
+            .decode(buf.chunk())
+            .map_err(anyhow::Error::new)?;
         let meta_update_pb =
-            manifest_pb::MetaUpdate::decode(buf.chunk()).map_err(anyhow::Error::new)?;
-        MetaUpdate::try_from(meta_update_pb)
+            manifest_pb::MetaUpdate::decode(body.as_ref()).map_err(anyhow::Error::new)?;
+        let ext = ManifestExt::decode(body.as_ref()).map_err(anyhow::Error::new)?;
+
+        let mut meta_update = MetaUpdate::try_from(meta_update_pb)?;
+        ext.apply_to(meta_update.table_options_mut())?;
+        if let MetaUpdate::VersionEdit(v) = &mut meta_update {
+            v.delete_before = ext.delete_before();
+        }
+        Ok(meta_update)
     }
 }
 
@@ -390,6 +489,44 @@ pub struct Snapshot {
     pub data: Option<MetaSnapshot>,
 }
 
+impl Snapshot {
+    /// Encode the snapshot into the stamped record.
+    pub fn encode_record(&self) -> Result<Vec<u8>> {
+        let snapshot_pb = manifest_pb::Snapshot::from(self.clone());
+        let ext = ManifestExt {
+            delete_before: self
+                .data
+                .as_ref()
+                .and_then(|v| v.version_meta.as_ref())
+                .and_then(|v| v.delete_before)
+                .map(|v| v.as_i64()),
+            ..ManifestExt::new(self.data.as_ref().map(|v| &v.table_meta.opts))
+        };
+
+        let mut buf = MANIFEST_FORMAT.stamp().to_vec();
+        snapshot_pb.encode(&mut buf).map_err(anyhow::Error::new)?;
+        ext.encode(&mut buf).map_err(anyhow::Error::new)?;
+        Ok(buf)
+    }
+
+    /// Decode the snapshot from the stamped record.
+    pub fn decode_record(buf: &[u8]) -> Result<Self> {
+        // Data written by a newer binary is refused here to avoid being
+        // misinterpreted.
+        let body = MANIFEST_FORMAT.decode(buf).map_err(anyhow::Error::new)?;
+        let snapshot_pb =
+            manifest_pb::Snapshot::decode(body.as_ref()).map_err(anyhow::Error::new)?;
+        let ext = ManifestExt::decode(body.as_ref()).map_err(anyhow::Error::new)?;
+
+        let mut snapshot = Snapshot::try_from(snapshot_pb)?;
+        ext.apply_to(snapshot.data.as_mut().map(|v| &mut v.table_meta.opts))?;
+        if let Some(version_meta) = snapshot.data.as_mut().and_then(|v| v.version_meta.as_mut()) {
+            version_meta.delete_before = ext.delete_before();
+        }
+        Ok(snapshot)
+    }
+}
+
 impl TryFrom<manifest_pb::Snapshot> for Snapshot {
     type Error = Error;
 
@@ -432,6 +569,7 @@ impl From<Snapshot> for manifest_pb::Snapshot {
                 files_to_delete: vec![],
                 mems_to_remove: vec![],
                 max_file_id: version_meta.max_file_id,
+                delete_before: version_meta.delete_before,
             });
             (
                 table_meta,
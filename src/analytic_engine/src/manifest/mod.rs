@@ -31,7 +31,11 @@ use generic_error::GenericResult;
 use macros::define_result;
 use table_engine::table::TableId;
 
-use crate::{manifest::meta_edit::MetaEditRequest, space::SpaceId, table::data::TableCatalogInfo};
+use crate::{
+    manifest::{meta_edit::MetaEditRequest, meta_snapshot::MetaSnapshot},
+    space::SpaceId,
+    table::data::TableCatalogInfo,
+};
 
 define_result!(error::Error);
 
@@ -53,6 +57,9 @@ pub trait Manifest: Send + Sync + fmt::Debug {
     /// Recover table metas from storage.
     async fn recover(&self, load_request: &LoadRequest) -> GenericResult<()>;
 
+    /// Load table metas from storage without applying them to the table.
+    async fn load(&self, load_request: &LoadRequest) -> GenericResult<Option<MetaSnapshot>>;
+
     async fn do_snapshot(&self, request: SnapshotRequest) -> GenericResult<()>;
 }
 
@@ -32,9 +32,15 @@ use horaedbproto::{manifest as manifest_pb, table_requests};
 use macros::define_result;
 use prost::Message;
 use snafu::{Backtrace, OptionExt, ResultExt, Snafu};
+use uuid::Uuid;
 use wal::log_batch::{Payload, PayloadDecodeContext, PayloadDecoder};
 
-use crate::{instance::write::WalEncodeVersion, table_options, TableOptions};
+use crate::{
+    format_version::{self, STAMP_SIZE, WAL_PAYLOAD_FORMAT, WRITE_BATCH_ID_SIZE},
+    instance::write::WalEncodeVersion,
+    manifest::meta_edit::ManifestExt,
+    table_options, TableOptions,
+};
 
 #[derive(Debug, Snafu)]
 pub enum Error {
@@ -50,6 +56,9 @@ pub enum Error {
     #[snafu(display("Failed to decode header, err:{}", source))]
     DecodeHeader { source: bytes_ext::Error },
 
+    #[snafu(display("Failed to decode format version, err:{}", source))]
+    DecodeFormatVersion { source: format_version::Error },
+
     #[snafu(display(
         "Invalid wal entry header, value:{}.\nBacktrace:\n{}",
         value,
@@ -95,6 +104,34 @@ pub enum Error {
 
 define_result!(Error);
 
+impl Error {
+    /// Whether the error is caused by the corrupted payload.
+    ///
+    /// The other errors, e.g. the missing schema of the table or the
+    /// unsupported format version, don't mean the payload is broken, so they
+    /// must not be skipped as the corrupted ones.
+    pub fn is_corrupted(&self) -> bool {
+        match self {
+            Error::DecodeHeader { .. }
+            | Error::InvalidHeader { .. }
+            | Error::DecodeBody { .. }
+            | Error::DecodeSchema { .. }
+            | Error::DecodeRow { .. }
+            | Error::DecodeColumn { .. }
+            | Error::BuildRowGroup { .. }
+            | Error::TableOptionsNotFound { .. }
+            | Error::InvalidTableOptions { .. } => true,
+            Error::DecodeFormatVersion { source } => {
+                matches!(source, format_version::Error::TruncatedStamp { .. })
+            }
+            Error::EncodeHeader { .. }
+            | Error::EncodeBody { .. }
+            | Error::InvalidWriteReqVersion { .. }
+            | Error::TableSchemaNotFound { .. } => false,
+        }
+    }
+}
+
 /// Wal entry header
 #[derive(Clone, Copy)]
 enum Header {
@@ -118,8 +155,11 @@ impl Header {
     }
 }
 
-fn write_header<B: BufMut>(header: Header, buf: &mut B) -> Result<()> {
-    buf.try_put_u8(header.to_u8()).context(EncodeHeader)
+fn write_header<B: BufMut>(header: Header, batch_id: &Uuid, buf: &mut B) -> Result<()> {
+    buf.try_put(&WAL_PAYLOAD_FORMAT.stamp())
+        .context(EncodeHeader)?;
+    buf.try_put_u8(header.to_u8()).context(EncodeHeader)?;
+    buf.try_put(batch_id.as_bytes()).context(EncodeHeader)
 }
 
 /// Header size in bytes
@@ -128,9 +168,18 @@ const HEADER_SIZE: usize = 1;
 /// Write request to persist in wal
 #[derive(Debug)]
 pub enum WritePayload<'a> {
-    Write(&'a table_requests::WriteRequest),
+    /// The `batch_id` is unique for each write payload, so that the payloads
+    /// appended more than once by the retries can be deduplicated on replay.
+    Write {
+        request: &'a table_requests::WriteRequest,
+        batch_id: Uuid,
+    },
     AlterSchema(&'a manifest_pb::AlterSchemaMeta),
-    AlterOption(&'a manifest_pb::AlterOptionsMeta),
+    AlterOption {
+        meta: &'a manifest_pb::AlterOptionsMeta,
+        /// The options missing in the `meta`.
+        ext: &'a ManifestExt,
+    },
 }
 
 impl<'a> Payload for WritePayload<'a> {
@@ -138,27 +187,28 @@ impl<'a> Payload for WritePayload<'a> {
 
     fn encode_size(&self) -> usize {
         let body_size = match self {
-            WritePayload::Write(req) => req.encoded_len(),
+            WritePayload::Write { request, .. } => request.encoded_len(),
             WritePayload::AlterSchema(req) => req.encoded_len(),
-            WritePayload::AlterOption(req) => req.encoded_len(),
+            WritePayload::AlterOption { meta, ext } => meta.encoded_len() + ext.encoded_len(),
         };
 
-        HEADER_SIZE + body_size
+        STAMP_SIZE + HEADER_SIZE + WRITE_BATCH_ID_SIZE + body_size
     }
 
     fn encode_to<B: BufMut>(&self, buf: &mut B) -> Result<()> {
         match self {
-            WritePayload::Write(req) => {
-                write_header(Header::Write, buf)?;
-                req.encode(buf).context(EncodeBody)
+            WritePayload::Write { request, batch_id } => {
+                write_header(Header::Write, batch_id, buf)?;
+                request.encode(buf).context(EncodeBody)
             }
             WritePayload::AlterSchema(req) => {
-                write_header(Header::AlterSchema, buf)?;
+                write_header(Header::AlterSchema, &Uuid::nil(), buf)?;
                 req.encode(buf).context(EncodeBody)
             }
-            WritePayload::AlterOption(req) => {
-                write_header(Header::AlterOption, buf)?;
-                req.encode(buf).context(EncodeBody)
+            WritePayload::AlterOption { meta, ext } => {
+                write_header(Header::AlterOption, &Uuid::nil(), buf)?;
+                meta.encode(buf).context(EncodeBody)?;
+                ext.encode(buf).context(EncodeBody)
             }
         }
     }
@@ -166,20 +216,31 @@ impl<'a> Payload for WritePayload<'a> {
 
 impl<'a> From<&'a table_requests::WriteRequest> for WritePayload<'a> {
     fn from(write_request: &'a table_requests::WriteRequest) -> Self {
-        Self::Write(write_request)
+        Self::Write {
+            request: write_request,
+            batch_id: Uuid::new_v4(),
+        }
     }
 }
 
 /// Payload decoded from wal
 #[derive(Debug)]
 pub enum ReadPayload {
-    Write { row_group: RowGroup },
-    AlterSchema { schema: Schema },
-    AlterOptions { options: TableOptions },
+    /// The `batch_id` is `None` for the payloads written without it.
+    Write {
+        row_group: RowGroup,
+        batch_id: Option<Uuid>,
+    },
+    AlterSchema {
+        schema: Schema,
+    },
+    AlterOptions {
+        options: TableOptions,
+    },
 }
 
 impl ReadPayload {
-    fn decode_write_from_pb(schema: &Schema, buf: &[u8]) -> Result<Self> {
+    fn decode_write_from_pb(schema: &Schema, batch_id: Uuid, buf: &[u8]) -> Result<Self> {
         let write_req_pb: table_requests::WriteRequest =
             Message::decode(buf).context(DecodeBody)?;
 
@@ -187,15 +248,20 @@ impl ReadPayload {
             let version = write_req_pb.version;
             WalEncodeVersion::try_from_u32(version).context(InvalidWriteReqVersion { version })?
         };
-        match version {
-            WalEncodeVersion::RowWise => Self::decode_rowwise_write_req(write_req_pb),
+        let row_group = match version {
+            WalEncodeVersion::RowWise => Self::decode_rowwise_write_req(write_req_pb)?,
             WalEncodeVersion::Columnar => {
-                Self::decode_columnar_write_req(schema.clone(), write_req_pb)
+                Self::decode_columnar_write_req(schema.clone(), write_req_pb)?
             }
-        }
+        };
+
+        Ok(Self::Write {
+            row_group,
+            batch_id: (!batch_id.is_nil()).then_some(batch_id),
+        })
     }
 
-    fn decode_rowwise_write_req(write_req_pb: table_requests::WriteRequest) -> Result<Self> {
+    fn decode_rowwise_write_req(write_req_pb: table_requests::WriteRequest) -> Result<RowGroup> {
         // Consume and convert schema in pb
         let schema: Schema = write_req_pb
             .schema
@@ -217,14 +283,13 @@ impl ReadPayload {
 
         // The `rows` are decoded according to the schema, so there is no need to do one
         // more check here.
-        let row_group = RowGroup::new_unchecked(schema, rows);
-        Ok(Self::Write { row_group })
+        Ok(RowGroup::new_unchecked(schema, rows))
     }
 
     fn decode_columnar_write_req(
         schema: Schema,
         write_req_pb: table_requests::WriteRequest,
-    ) -> Result<Self> {
+    ) -> Result<RowGroup> {
         let encoded_cols = write_req_pb.cols;
         let mut row_group_builder =
             RowGroupBuilderFromColumn::with_capacity(schema, encoded_cols.len());
@@ -244,8 +309,7 @@ impl ReadPayload {
                 .context(BuildRowGroup)?;
         }
 
-        let row_group = row_group_builder.build();
-        Ok(Self::Write { row_group })
+        Ok(row_group_builder.build())
     }
 
     fn decode_alter_schema_from_pb(buf: &[u8]) -> Result<Self> {
@@ -265,18 +329,55 @@ impl ReadPayload {
     fn decode_alter_option_from_pb(buf: &[u8]) -> Result<Self> {
         let alter_option_meta_pb: manifest_pb::AlterOptionsMeta =
             Message::decode(buf).context(DecodeBody)?;
+        let ext: ManifestExt = Message::decode(buf).context(DecodeBody)?;
 
         // Consume and convert options in pb
-        let options: TableOptions = alter_option_meta_pb
+        let mut options: TableOptions = alter_option_meta_pb
             .options
             .context(TableOptionsNotFound)?
             .try_into()
             .context(InvalidTableOptions)?;
+        if let Some(ext) = &ext.table_options {
+            options = options.merge_pb_ext(ext).context(InvalidTableOptions)?;
+        }
 
         Ok(Self::AlterOptions { options })
     }
 }
 
+fn decode_header<B: Buf>(buf: &mut B) -> Result<(Header, Uuid)> {
+    let header_value = buf.try_get_u8().context(DecodeHeader)?;
+    let header = match Header::from_u8(header_value) {
+        Some(header) => header,
+        None => {
+            return InvalidHeader {
+                value: header_value,
+            }
+            .fail()
+        }
+    };
+
+    let mut batch_id = [0; WRITE_BATCH_ID_SIZE];
+    buf.try_copy_to_slice(&mut batch_id).context(DecodeHeader)?;
+
+    Ok((header, Uuid::from_bytes(batch_id)))
+}
+
+/// Decode only the header of the payload, and return the write batch id if
+/// it's a write payload written with it.
+pub fn decode_write_batch_id<B: Buf>(buf: &mut B) -> Result<Option<Uuid>> {
+    let body = WAL_PAYLOAD_FORMAT
+        .decode(buf.chunk())
+        .context(DecodeFormatVersion)?;
+    let (header, batch_id) = decode_header(&mut body.as_ref())?;
+    let batch_id = match header {
+        Header::Write => (!batch_id.is_nil()).then_some(batch_id),
+        Header::AlterSchema | Header::AlterOption => None,
+    };
+
+    Ok(batch_id)
+}
+
 /// The provider is used to provide the schema according to the table id.
 pub trait TableSchemaProvider {
     fn table_schema(&self, table_id: TableId) -> Option<Schema>;
@@ -311,16 +412,11 @@ where
     type Target = ReadPayload;
 
     fn decode<B: Buf>(&self, ctx: &PayloadDecodeContext, buf: &mut B) -> Result<Self::Target> {
-        let header_value = buf.try_get_u8().context(DecodeHeader)?;
-        let header = match Header::from_u8(header_value) {
-            Some(header) => header,
-            None => {
-                return InvalidHeader {
-                    value: header_value,
-                }
-                .fail()
-            }
-        };
+        let body = WAL_PAYLOAD_FORMAT
+            .decode(buf.chunk())
+            .context(DecodeFormatVersion)?;
+        let buf = &mut body.as_ref();
+        let (header, batch_id) = decode_header(buf)?;
 
         let chunk = buf.chunk();
         let schema = self
@@ -328,7 +424,7 @@ where
             .table_schema(ctx.table_id)
             .context(TableSchemaNotFound)?;
         let payload = match header {
-            Header::Write => ReadPayload::decode_write_from_pb(&schema, chunk)?,
+            Header::Write => ReadPayload::decode_write_from_pb(&schema, batch_id, chunk)?,
             Header::AlterSchema => ReadPayload::decode_alter_schema_from_pb(chunk)?,
             Header::AlterOption => ReadPayload::decode_alter_option_from_pb(chunk)?,
         };
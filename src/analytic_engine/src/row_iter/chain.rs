@@ -26,6 +26,7 @@ use common_types::{
     record_batch::FetchedRecordBatch,
     request_id::RequestId,
     schema::RecordSchemaWithKey,
+    SequenceNumber,
 };
 use generic_error::GenericError;
 use logger::debug;
@@ -81,6 +82,9 @@ pub struct ChainConfig<'a> {
     /// Predicate of the query.
     pub predicate: PredicateRef,
     pub num_streams_to_prefetch: usize,
+    /// Max visible sequence (inclusive) of the memtables, all the rows are
+    /// visible if not set.
+    pub max_visible_sequence: Option<SequenceNumber>,
 
     pub sst_read_options_builder: SstReadOptionsBuilder,
     /// Sst factory
@@ -143,6 +147,7 @@ impl<'a> Builder<'a> {
             need_dedup: false,
             reverse: false,
             deadline: self.config.deadline,
+            max_visible_sequence: self.config.max_visible_sequence,
         };
 
         let sst_stream_ctx = SstStreamContext {
@@ -0,0 +1,229 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Iterator setting the expired values of the columns with their own ttl to
+//! null.
+
+use async_trait::async_trait;
+use common_types::{
+    datum::Datum,
+    record_batch::{FetchedRecordBatch, FetchedRecordBatchBuilder},
+    schema::RecordSchemaWithKey,
+    time::Timestamp,
+};
+use generic_error::{BoxError, GenericError};
+use macros::define_result;
+use snafu::{ResultExt, Snafu};
+
+use crate::{row_iter::FetchedRecordBatchIterator, table_options::ColumnTtl};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Failed to append row, err:{}", source))]
+    AppendRow {
+        source: common_types::record_batch::Error,
+    },
+
+    #[snafu(display("Failed to build record batch, err:{}", source))]
+    BuildRecordBatch {
+        source: common_types::record_batch::Error,
+    },
+
+    #[snafu(display("Failed to read data from the sub iterator, err:{}", source))]
+    ReadFromSubIter { source: GenericError },
+}
+
+define_result!(Error);
+
+/// Set the values of the column to null if the timestamp of their rows is
+/// earlier than `now - ttl` of the column, and the other columns are kept.
+///
+/// The columns not in the schema of the `iter` are ignored.
+pub struct ColumnExpireIterator<I> {
+    iter: I,
+    timestamp_idx: Option<usize>,
+    /// Index of the column and its expire time.
+    expire_times: Vec<(usize, Timestamp)>,
+    builder: FetchedRecordBatchBuilder,
+}
+
+impl<I: FetchedRecordBatchIterator> ColumnExpireIterator<I> {
+    pub fn new(iter: I, column_ttls: &[ColumnTtl], timestamp_column: &str, now: Timestamp) -> Self {
+        let schema = iter.schema();
+        let timestamp_idx = schema.index_of(timestamp_column);
+        let expire_times = column_ttls
+            .iter()
+            .filter_map(|column_ttl| {
+                let idx = schema.index_of(&column_ttl.column)?;
+                Some((idx, now.sub_duration_or_min(column_ttl.ttl.0)))
+            })
+            .collect();
+        let builder = FetchedRecordBatchBuilder::new(
+            schema.to_record_schema(),
+            Some(schema.primary_key_idx().to_vec()),
+        );
+
+        Self {
+            iter,
+            timestamp_idx,
+            expire_times,
+            builder,
+        }
+    }
+
+    fn expire_batch(
+        &mut self,
+        timestamp_idx: usize,
+        batch: FetchedRecordBatch,
+    ) -> Result<FetchedRecordBatch> {
+        let timestamps = batch.column(timestamp_idx);
+        let is_expired = |row_idx: usize, expire_time: Timestamp| {
+            timestamps
+                .datum(row_idx)
+                .as_timestamp()
+                .is_some_and(|ts| ts < expire_time)
+        };
+        // The expire time of the column with the longest ttl is the earliest one.
+        let earliest = self
+            .expire_times
+            .iter()
+            .map(|(_, expire_time)| *expire_time)
+            .min()
+            .unwrap_or(Timestamp::MIN);
+        if !(0..batch.num_rows()).any(|row_idx| is_expired(row_idx, earliest)) {
+            return Ok(batch);
+        }
+
+        for row_idx in 0..batch.num_rows() {
+            let mut row = batch.clone_row_at(row_idx);
+            for (column_idx, expire_time) in &self.expire_times {
+                if is_expired(row_idx, *expire_time) {
+                    row[*column_idx] = Datum::Null;
+                }
+            }
+            self.builder.append_row(row).context(AppendRow)?;
+        }
+
+        self.builder.build().context(BuildRecordBatch)
+    }
+}
+
+#[async_trait]
+impl<I: FetchedRecordBatchIterator> FetchedRecordBatchIterator for ColumnExpireIterator<I> {
+    type Error = Error;
+
+    fn schema(&self) -> &RecordSchemaWithKey {
+        self.iter.schema()
+    }
+
+    async fn next_batch(&mut self) -> Result<Option<FetchedRecordBatch>> {
+        let batch = self
+            .iter
+            .next_batch()
+            .await
+            .box_err()
+            .context(ReadFromSubIter)?;
+        match (batch, self.timestamp_idx) {
+            (Some(batch), Some(timestamp_idx)) if !self.expire_times.is_empty() => {
+                self.expire_batch(timestamp_idx, batch).map(Some)
+            }
+            (batch, _) => Ok(batch),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common_types::tests::{build_row, build_row_opt, build_schema};
+    use time_ext::ReadableDuration;
+
+    use super::*;
+    use crate::row_iter::tests::{
+        build_fetched_record_batch_with_key, check_iterator, VectorIterator,
+    };
+
+    #[tokio::test]
+    async fn test_column_expire_iterator() {
+        let schema = build_schema();
+        let iter = VectorIterator::new(
+            schema.to_record_schema_with_key(),
+            vec![build_fetched_record_batch_with_key(
+                schema.clone(),
+                vec![
+                    build_row(b"a", 10, 10.0, "v1", 1000, 1_000_000),
+                    build_row(b"b", 20, 10.0, "v2", 1000, 1_000_000),
+                    build_row(b"c", 30, 10.0, "v3", 1000, 1_000_000),
+                ],
+            )],
+        );
+
+        let column_ttls = vec![
+            ColumnTtl {
+                column: "field2".to_string(),
+                ttl: ReadableDuration::millis(15),
+            },
+            ColumnTtl {
+                column: "field4".to_string(),
+                ttl: ReadableDuration::millis(25),
+            },
+            ColumnTtl {
+                column: "not_exist".to_string(),
+                ttl: ReadableDuration::millis(1),
+            },
+        ];
+        let mut iter = ColumnExpireIterator::new(iter, &column_ttls, "key2", Timestamp::new(40));
+        check_iterator(
+            &mut iter,
+            vec![
+                build_row_opt(b"a", 10, Some(10.0), None, Some(1000), None),
+                build_row_opt(b"b", 20, Some(10.0), None, Some(1000), Some(1_000_000)),
+                build_row_opt(
+                    b"c",
+                    30,
+                    Some(10.0),
+                    Some("v3"),
+                    Some(1000),
+                    Some(1_000_000),
+                ),
+            ],
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_column_expire_iterator_without_expired() {
+        let schema = build_schema();
+        let rows = vec![
+            build_row(b"a", 10, 10.0, "v1", 1000, 1_000_000),
+            build_row(b"b", 20, 10.0, "v2", 1000, 1_000_000),
+        ];
+        let iter = VectorIterator::new(
+            schema.to_record_schema_with_key(),
+            vec![build_fetched_record_batch_with_key(
+                schema.clone(),
+                rows.clone(),
+            )],
+        );
+
+        let column_ttls = vec![ColumnTtl {
+            column: "field2".to_string(),
+            ttl: ReadableDuration::millis(100),
+        }];
+        let mut iter = ColumnExpireIterator::new(iter, &column_ttls, "key2", Timestamp::new(20));
+        check_iterator(&mut iter, rows).await;
+    }
+}
@@ -0,0 +1,555 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Iterator downsampling the aged rows into coarser resolution during
+//! compaction.
+
+use std::cmp::Ordering;
+
+use async_trait::async_trait;
+use common_types::{
+    datum::{Datum, DatumKind},
+    record_batch::{FetchedRecordBatch, FetchedRecordBatchBuilder},
+    row::Row,
+    schema::RecordSchemaWithKey,
+    time::Timestamp,
+};
+use generic_error::{BoxError, GenericError};
+use macros::define_result;
+use snafu::{ResultExt, Snafu};
+
+use crate::{
+    row_iter::FetchedRecordBatchIterator,
+    table_options::{DownsampleAggregator, DownsamplePolicy},
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Failed to append row, err:{}", source))]
+    AppendRow {
+        source: common_types::record_batch::Error,
+    },
+
+    #[snafu(display("Failed to build record batch, err:{}", source))]
+    BuildRecordBatch {
+        source: common_types::record_batch::Error,
+    },
+
+    #[snafu(display("Failed to read data from the sub iterator, err:{}", source))]
+    ReadFromSubIter { source: GenericError },
+}
+
+define_result!(Error);
+
+struct DownsampleContext {
+    /// The rows earlier than this timestamp are downsampled.
+    threshold: Timestamp,
+    interval_ms: i64,
+    aggregator: DownsampleAggregator,
+    timestamp_idx: usize,
+    /// Indexes of the primary key columns except the timestamp.
+    series_key_indexes: Vec<usize>,
+    /// Index of the resolution column and the resolution datum to fill.
+    resolution: Option<(usize, Datum)>,
+}
+
+/// The rows of a series in the same interval being aggregated.
+struct Group {
+    /// The aggregated row, whose timestamp is the start of the interval.
+    row: Row,
+    /// Sum and count of the numeric fields, only used by
+    /// [DownsampleAggregator::Avg].
+    sums: Vec<(f64, u64)>,
+    /// The interval is downsampled before and the row is already written, so
+    /// the late rows of the interval are passed through rather than merged
+    /// into it.
+    downsampled: bool,
+}
+
+/// Aggregate the rows older than the `after` of the [DownsamplePolicy] into
+/// one row per `interval` for every series, and the newer rows are passed
+/// through.
+///
+/// REQUIRE: The timestamp is the last primary key, so the rows of a series in
+/// the same interval are adjacent. Otherwise all the rows are passed through.
+///
+/// The downsampled rows are told by the non-null resolution column. The rows
+/// arriving after their interval has been downsampled are kept as is, because
+/// the downsampled row carries no count to merge them with. And the caller
+/// must read all the ssts of the downsampled intervals, or an interval
+/// downsampled before is aggregated again into a duplicated row.
+pub struct DownsampleIterator<I> {
+    iter: I,
+    ctx: Option<DownsampleContext>,
+    builder: FetchedRecordBatchBuilder,
+    group: Option<Group>,
+    exhausted: bool,
+}
+
+impl<I: FetchedRecordBatchIterator> DownsampleIterator<I> {
+    pub fn new(
+        iter: I,
+        policy: Option<&DownsamplePolicy>,
+        timestamp_column: &str,
+        resolution_column: Option<&str>,
+        now: Timestamp,
+    ) -> Self {
+        let schema = iter.schema();
+        let ctx = policy.and_then(|policy| {
+            let timestamp_idx = schema.index_of(timestamp_column)?;
+            let (last_key_idx, series_key_indexes) = schema.primary_key_idx().split_last()?;
+            if *last_key_idx != timestamp_idx {
+                return None;
+            }
+
+            // Align the threshold to the interval, so no interval is downsampled partially.
+            let interval_ms = policy.interval.as_millis() as i64;
+            let threshold = now
+                .sub_duration_or_min(policy.after.0)
+                .checked_floor_by_i64(interval_ms)?;
+
+            Some(DownsampleContext {
+                threshold,
+                interval_ms,
+                aggregator: policy.aggregator,
+                timestamp_idx,
+                series_key_indexes: series_key_indexes.to_vec(),
+                resolution: resolution_column
+                    .and_then(|column| schema.index_of(column))
+                    .map(|idx| {
+                        let resolution = match schema.columns()[idx].data_type {
+                            DatumKind::UInt64 => Datum::UInt64(interval_ms as u64),
+                            _ => Datum::Int64(interval_ms),
+                        };
+                        (idx, resolution)
+                    }),
+            })
+        });
+        let builder = FetchedRecordBatchBuilder::new(
+            schema.to_record_schema(),
+            Some(schema.primary_key_idx().to_vec()),
+        );
+
+        Self {
+            iter,
+            ctx,
+            builder,
+            group: None,
+            exhausted: false,
+        }
+    }
+
+    fn flush_group(&mut self) -> Result<()> {
+        let Some(mut group) = self.group.take() else {
+            return Ok(());
+        };
+
+        if group.downsampled {
+            return Ok(());
+        }
+
+        let ctx = self.ctx.as_ref().unwrap();
+        if ctx.aggregator == DownsampleAggregator::Avg {
+            // The fields with only one value are kept as is.
+            for (idx, (sum, count)) in group.sums.iter().enumerate() {
+                if *count > 1 {
+                    let kind = group.row[idx].kind();
+                    group.row[idx] = datum_from_f64(kind, sum / *count as f64);
+                }
+            }
+        }
+        if let Some((idx, resolution)) = &ctx.resolution {
+            group.row[*idx] = resolution.clone();
+        }
+
+        self.builder.append_row(group.row).context(AppendRow)
+    }
+
+    fn downsample_row(&mut self, mut row: Row) -> Result<()> {
+        let ctx = self.ctx.as_ref().unwrap();
+        let timestamp = match row[ctx.timestamp_idx].as_timestamp() {
+            Some(ts) if ts < ctx.threshold => ts,
+            _ => {
+                self.flush_group()?;
+                return self.builder.append_row(row).context(AppendRow);
+            }
+        };
+
+        // The timestamp is earlier than the aligned threshold, so it never overflows.
+        let start = Datum::Timestamp(timestamp.checked_floor_by_i64(ctx.interval_ms).unwrap());
+        let is_same_group = self.group.as_ref().is_some_and(|group| {
+            group.row[ctx.timestamp_idx] == start
+                && ctx
+                    .series_key_indexes
+                    .iter()
+                    .all(|idx| group.row[*idx] == row[*idx])
+        });
+        if is_same_group && self.group.as_ref().unwrap().downsampled {
+            return self.builder.append_row(row).context(AppendRow);
+        }
+        row[ctx.timestamp_idx] = start;
+
+        if !is_same_group {
+            // The downsampled row is the first one of its interval, as its timestamp is
+            // the start of the interval.
+            let downsampled = ctx
+                .resolution
+                .as_ref()
+                .is_some_and(|(idx, _)| !row[*idx].is_null());
+            self.flush_group()?;
+            if downsampled {
+                self.builder.append_row(row.clone()).context(AppendRow)?;
+            }
+            let sums = (0..row.num_columns())
+                .map(|idx| match datum_to_f64(&row[idx]) {
+                    Some(v) => (v, 1),
+                    None => (0.0, 0),
+                })
+                .collect();
+            self.group = Some(Group {
+                row,
+                sums,
+                downsampled,
+            });
+            return Ok(());
+        }
+
+        let ctx = self.ctx.as_ref().unwrap();
+        let group = self.group.as_mut().unwrap();
+        for idx in 0..row.num_columns() {
+            if idx == ctx.timestamp_idx || ctx.series_key_indexes.contains(&idx) {
+                continue;
+            }
+
+            let datum = &row[idx];
+            if datum.is_null() {
+                continue;
+            }
+            let Some(value) = datum_to_f64(datum) else {
+                // Keep the last value of the non-numeric fields.
+                group.row[idx] = datum.clone();
+                continue;
+            };
+
+            let current = &mut group.row[idx];
+            let replace = match ctx.aggregator {
+                DownsampleAggregator::Avg => {
+                    let (sum, count) = &mut group.sums[idx];
+                    *sum += value;
+                    *count += 1;
+                    current.is_null()
+                }
+                DownsampleAggregator::Last => true,
+                DownsampleAggregator::Min => {
+                    current.is_null() || datum.partial_cmp(current) == Some(Ordering::Less)
+                }
+                DownsampleAggregator::Max => {
+                    current.is_null() || datum.partial_cmp(current) == Some(Ordering::Greater)
+                }
+            };
+            if replace {
+                *current = datum.clone();
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the value of the numeric datum, None for the other kinds.
+fn datum_to_f64(datum: &Datum) -> Option<f64> {
+    match datum {
+        Datum::Double(v) => Some(*v),
+        Datum::Float(v) => Some(*v as f64),
+        Datum::UInt64(v) => Some(*v as f64),
+        Datum::UInt32(v) => Some(*v as f64),
+        Datum::UInt16(v) => Some(*v as f64),
+        Datum::UInt8(v) => Some(*v as f64),
+        Datum::Int64(v) => Some(*v as f64),
+        Datum::Int32(v) => Some(*v as f64),
+        Datum::Int16(v) => Some(*v as f64),
+        Datum::Int8(v) => Some(*v as f64),
+        _ => None,
+    }
+}
+
+/// Convert the value back to the datum of `kind`, the integers are rounded.
+fn datum_from_f64(kind: DatumKind, v: f64) -> Datum {
+    match kind {
+        DatumKind::Double => Datum::Double(v),
+        DatumKind::Float => Datum::Float(v as f32),
+        DatumKind::UInt64 => Datum::UInt64(v.round() as u64),
+        DatumKind::UInt32 => Datum::UInt32(v.round() as u32),
+        DatumKind::UInt16 => Datum::UInt16(v.round() as u16),
+        DatumKind::UInt8 => Datum::UInt8(v.round() as u8),
+        DatumKind::Int64 => Datum::Int64(v.round() as i64),
+        DatumKind::Int32 => Datum::Int32(v.round() as i32),
+        DatumKind::Int16 => Datum::Int16(v.round() as i16),
+        DatumKind::Int8 => Datum::Int8(v.round() as i8),
+        _ => Datum::Null,
+    }
+}
+
+#[async_trait]
+impl<I: FetchedRecordBatchIterator> FetchedRecordBatchIterator for DownsampleIterator<I> {
+    type Error = Error;
+
+    fn schema(&self) -> &RecordSchemaWithKey {
+        self.iter.schema()
+    }
+
+    async fn next_batch(&mut self) -> Result<Option<FetchedRecordBatch>> {
+        if self.ctx.is_none() {
+            return self
+                .iter
+                .next_batch()
+                .await
+                .box_err()
+                .context(ReadFromSubIter);
+        }
+
+        while !self.exhausted {
+            let batch = self
+                .iter
+                .next_batch()
+                .await
+                .box_err()
+                .context(ReadFromSubIter)?;
+            match batch {
+                Some(batch) => {
+                    for row_idx in 0..batch.num_rows() {
+                        self.downsample_row(batch.clone_row_at(row_idx))?;
+                    }
+                }
+                None => {
+                    self.exhausted = true;
+                    self.flush_group()?;
+                }
+            }
+
+            // The last group may be continued by the next batch, so it is kept.
+            if !self.builder.is_empty() {
+                return self.builder.build().context(BuildRecordBatch).map(Some);
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes_ext::Bytes;
+    use common_types::{
+        column_schema,
+        schema::{self, Schema},
+        tests::{build_row, build_schema},
+    };
+    use time_ext::ReadableDuration;
+
+    use super::*;
+    use crate::row_iter::tests::{
+        build_fetched_record_batch_with_key, check_iterator, VectorIterator,
+    };
+
+    fn build_policy(aggregator: DownsampleAggregator) -> DownsamplePolicy {
+        DownsamplePolicy {
+            after: ReadableDuration::millis(1000),
+            interval: ReadableDuration::millis(100),
+            aggregator,
+        }
+    }
+
+    fn build_iter(batches: Vec<Vec<Row>>) -> VectorIterator {
+        build_iter_with_schema(build_schema(), batches)
+    }
+
+    fn build_iter_with_schema(schema: Schema, batches: Vec<Vec<Row>>) -> VectorIterator {
+        VectorIterator::new(
+            schema.to_record_schema_with_key(),
+            batches
+                .into_iter()
+                .map(|rows| build_fetched_record_batch_with_key(schema.clone(), rows))
+                .collect(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_downsample_iterator() {
+        let iter = build_iter(vec![
+            vec![
+                build_row(b"a", 10, 1.0, "v1", 0, 0),
+                build_row(b"a", 50, 3.0, "v2", 0, 0),
+            ],
+            vec![
+                build_row(b"a", 120, 5.0, "v3", 0, 0),
+                // Not aged yet.
+                build_row(b"a", 1000, 7.0, "v4", 0, 0),
+                build_row(b"b", 90, 2.0, "v5", 0, 0),
+            ],
+        ]);
+
+        let policy = build_policy(DownsampleAggregator::Avg);
+        let mut iter =
+            DownsampleIterator::new(iter, Some(&policy), "key2", None, Timestamp::new(2050));
+        check_iterator(
+            &mut iter,
+            vec![
+                build_row(b"a", 0, 2.0, "v2", 0, 0),
+                build_row(b"a", 100, 5.0, "v3", 0, 0),
+                build_row(b"a", 1000, 7.0, "v4", 0, 0),
+                build_row(b"b", 0, 2.0, "v5", 0, 0),
+            ],
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_downsample_aggregators() {
+        let rows = vec![
+            build_row(b"a", 10, 3.0, "v1", 0, 0),
+            build_row(b"a", 20, 1.0, "v2", 0, 0),
+            build_row(b"a", 30, 2.0, "v3", 0, 0),
+        ];
+        for (aggregator, expected) in [
+            (DownsampleAggregator::Last, 2.0),
+            (DownsampleAggregator::Min, 1.0),
+            (DownsampleAggregator::Max, 3.0),
+        ] {
+            let policy = build_policy(aggregator);
+            let mut iter = DownsampleIterator::new(
+                build_iter(vec![rows.clone()]),
+                Some(&policy),
+                "key2",
+                None,
+                Timestamp::new(2000),
+            );
+            check_iterator(&mut iter, vec![build_row(b"a", 0, expected, "v3", 0, 0)]).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_downsample_iterator_without_policy() {
+        let rows = vec![
+            build_row(b"a", 10, 1.0, "v1", 0, 0),
+            build_row(b"a", 50, 3.0, "v2", 0, 0),
+        ];
+        let mut iter = DownsampleIterator::new(
+            build_iter(vec![rows.clone()]),
+            None,
+            "key2",
+            None,
+            Timestamp::new(2000),
+        );
+        check_iterator(&mut iter, rows).await;
+    }
+
+    /// Schema of (key1(varbinary), key2(timestamp), field1(double),
+    /// resolution(int64)).
+    fn build_schema_with_resolution() -> Schema {
+        schema::Builder::new()
+            .auto_increment_column_id(true)
+            .add_key_column(
+                column_schema::Builder::new("key1".to_string(), DatumKind::Varbinary)
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .add_key_column(
+                column_schema::Builder::new("key2".to_string(), DatumKind::Timestamp)
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .add_normal_column(
+                column_schema::Builder::new("field1".to_string(), DatumKind::Double)
+                    .is_nullable(true)
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .add_normal_column(
+                column_schema::Builder::new("resolution".to_string(), DatumKind::Int64)
+                    .is_nullable(true)
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .build()
+            .unwrap()
+    }
+
+    fn build_row_with_resolution(timestamp: i64, field1: f64, resolution: Option<i64>) -> Row {
+        Row::from_datums(vec![
+            Datum::Varbinary(Bytes::from_static(b"a")),
+            Datum::Timestamp(Timestamp::new(timestamp)),
+            Datum::Double(field1),
+            resolution.map(Datum::Int64).unwrap_or(Datum::Null),
+        ])
+    }
+
+    #[tokio::test]
+    async fn test_downsample_late_rows() {
+        let rows = vec![
+            // Downsampled by the previous compaction.
+            build_row_with_resolution(0, 2.0, Some(100)),
+            // Arriving after the interval is downsampled.
+            build_row_with_resolution(30, 8.0, None),
+            build_row_with_resolution(60, 4.0, None),
+            build_row_with_resolution(120, 1.0, None),
+            build_row_with_resolution(150, 3.0, None),
+        ];
+
+        let policy = build_policy(DownsampleAggregator::Avg);
+        let mut iter = DownsampleIterator::new(
+            build_iter_with_schema(build_schema_with_resolution(), vec![rows]),
+            Some(&policy),
+            "key2",
+            Some("resolution"),
+            Timestamp::new(2000),
+        );
+        // The late rows are kept as is instead of being averaged with the downsampled
+        // row as a single point.
+        check_iterator(
+            &mut iter,
+            vec![
+                build_row_with_resolution(0, 2.0, Some(100)),
+                build_row_with_resolution(30, 8.0, None),
+                build_row_with_resolution(60, 4.0, None),
+                build_row_with_resolution(100, 2.0, Some(100)),
+            ],
+        )
+        .await;
+
+        // Only the downsampled row is left in the interval.
+        let mut iter = DownsampleIterator::new(
+            build_iter_with_schema(
+                build_schema_with_resolution(),
+                vec![vec![build_row_with_resolution(0, 2.0, Some(100))]],
+            ),
+            Some(&policy),
+            "key2",
+            Some("resolution"),
+            Timestamp::new(2000),
+        );
+        check_iterator(
+            &mut iter,
+            vec![build_row_with_resolution(0, 2.0, Some(100))],
+        )
+        .await;
+    }
+}
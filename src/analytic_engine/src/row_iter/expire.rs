@@ -0,0 +1,171 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Iterator filtering out the rows expired according to the `expire_at`
+//! column.
+
+use arrow::array::BooleanArray;
+use async_trait::async_trait;
+use common_types::{
+    record_batch::FetchedRecordBatch, schema::RecordSchemaWithKey, time::Timestamp,
+};
+use generic_error::{BoxError, GenericError};
+use macros::define_result;
+use snafu::{ResultExt, Snafu};
+
+use crate::row_iter::FetchedRecordBatchIterator;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Failed to select rows, err:{}", source))]
+    SelectRows {
+        source: common_types::record_batch::Error,
+    },
+
+    #[snafu(display("Failed to read data from the sub iterator, err:{}", source))]
+    ReadFromSubIter { source: GenericError },
+}
+
+define_result!(Error);
+
+/// Filter out the rows whose `expire_at` is not after `now`, and the rows
+/// without `expire_at` (null) never expire.
+///
+/// All the rows are passed through if the `expire_at` column is not set.
+pub struct ExpireFilterIterator<I> {
+    iter: I,
+    /// Index of the `expire_at` column in the schema of the `iter`.
+    column_idx: Option<usize>,
+    now: Timestamp,
+}
+
+impl<I: FetchedRecordBatchIterator> ExpireFilterIterator<I> {
+    pub fn new(iter: I, expire_at_column: Option<&str>, now: Timestamp) -> Self {
+        let column_idx = expire_at_column.and_then(|column| iter.schema().index_of(column));
+
+        Self {
+            iter,
+            column_idx,
+            now,
+        }
+    }
+
+    fn filter_batch(&self, column_idx: usize, batch: &mut FetchedRecordBatch) -> Result<()> {
+        let column = batch.column(column_idx);
+        let selected: Vec<bool> = (0..batch.num_rows())
+            .map(|i| match column.datum(i).as_timestamp() {
+                Some(expire_at) => expire_at > self.now,
+                None => true,
+            })
+            .collect();
+        if selected.iter().all(|v| *v) {
+            return Ok(());
+        }
+
+        batch
+            .select_data(&BooleanArray::from(selected))
+            .context(SelectRows)
+    }
+}
+
+#[async_trait]
+impl<I: FetchedRecordBatchIterator> FetchedRecordBatchIterator for ExpireFilterIterator<I> {
+    type Error = Error;
+
+    fn schema(&self) -> &RecordSchemaWithKey {
+        self.iter.schema()
+    }
+
+    async fn next_batch(&mut self) -> Result<Option<FetchedRecordBatch>> {
+        let Some(column_idx) = self.column_idx else {
+            return self
+                .iter
+                .next_batch()
+                .await
+                .box_err()
+                .context(ReadFromSubIter);
+        };
+
+        while let Some(mut batch) = self
+            .iter
+            .next_batch()
+            .await
+            .box_err()
+            .context(ReadFromSubIter)?
+        {
+            self.filter_batch(column_idx, &mut batch)?;
+            if !batch.is_empty() {
+                return Ok(Some(batch));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common_types::tests::{build_row, build_schema};
+
+    use super::*;
+    use crate::row_iter::tests::{
+        build_fetched_record_batch_with_key, check_iterator, VectorIterator,
+    };
+
+    #[tokio::test]
+    async fn test_expire_filter_iterator() {
+        let schema = build_schema();
+        let iter = VectorIterator::new(
+            schema.to_record_schema_with_key(),
+            vec![build_fetched_record_batch_with_key(
+                schema.clone(),
+                vec![
+                    build_row(b"a", 10, 10.0, "v1", 1000, 1_000_000),
+                    build_row(b"b", 20, 10.0, "v2", 1000, 1_000_000),
+                    build_row(b"c", 30, 10.0, "v3", 1000, 1_000_000),
+                ],
+            )],
+        );
+
+        // Use the timestamp column `key2` as the `expire_at` column.
+        let mut iter = ExpireFilterIterator::new(iter, Some("key2"), Timestamp::new(20));
+        check_iterator(
+            &mut iter,
+            vec![build_row(b"c", 30, 10.0, "v3", 1000, 1_000_000)],
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_expire_filter_iterator_without_column() {
+        let schema = build_schema();
+        let rows = vec![
+            build_row(b"a", 10, 10.0, "v1", 1000, 1_000_000),
+            build_row(b"b", 20, 10.0, "v2", 1000, 1_000_000),
+        ];
+        let iter = VectorIterator::new(
+            schema.to_record_schema_with_key(),
+            vec![build_fetched_record_batch_with_key(
+                schema.clone(),
+                rows.clone(),
+            )],
+        );
+
+        let mut iter = ExpireFilterIterator::new(iter, None, Timestamp::new(20));
+        check_iterator(&mut iter, rows).await;
+    }
+}
@@ -200,6 +200,7 @@ impl<'a> MergeBuilder<'a> {
             need_dedup: self.config.need_dedup,
             reverse: self.config.reverse,
             deadline: self.config.deadline,
+            max_visible_sequence: Some(self.config.sequence),
         };
 
         let sst_stream_ctx = SstStreamContext {
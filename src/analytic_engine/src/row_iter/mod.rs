@@ -25,7 +25,10 @@ use generic_error::BoxError;
 use crate::sst::writer::RecordBatchStream;
 
 pub mod chain;
+pub mod column_expire;
 pub mod dedup;
+pub mod downsample;
+pub mod expire;
 pub mod merge;
 pub mod record_batch_stream;
 #[cfg(test)]
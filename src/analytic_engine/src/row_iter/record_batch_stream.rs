@@ -244,7 +244,10 @@ pub fn stream_from_memtable(
         deadline: ctx.deadline,
         ..Default::default()
     };
-    let max_seq = memtable.last_sequence();
+    let max_seq = match ctx.max_visible_sequence {
+        Some(seq) => memtable.last_sequence().min(seq),
+        None => memtable.last_sequence(),
+    };
     let fetched_cols = ctx
         .fetched_schema
         .columns()
@@ -282,6 +285,9 @@ pub struct MemtableStreamContext {
     pub need_dedup: bool,
     pub reverse: bool,
     pub deadline: Option<Instant>,
+    /// Rows with sequence greater than it are invisible, all the rows in the
+    /// memtable are visible if not set.
+    pub max_visible_sequence: Option<SequenceNumber>,
 }
 
 /// Build the filtered by `sst_read_options.predicate`
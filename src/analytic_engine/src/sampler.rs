@@ -18,7 +18,8 @@
 //! Segment duration sampler.
 
 use std::{
-    collections::HashSet,
+    collections::{hash_map::DefaultHasher, HashSet},
+    hash::{Hash, Hasher},
     sync::{Arc, Mutex},
     time::Duration,
 };
@@ -27,7 +28,7 @@ use common_types::{
     datum::DatumView,
     row::Row,
     schema::Schema,
-    time::{TimeRange, Timestamp},
+    time::{TimeRange, Timestamp, TimestampPrecision},
 };
 use hyperloglog::HyperLogLog;
 use macros::define_result;
@@ -85,6 +86,9 @@ pub trait DurationSampler {
     /// Returns a suggested duration to partition the timestamps or default
     /// duration if no enough timestamp has been sampled.
     ///
+    /// The duration is in millis regardless of the timestamp precision, just
+    /// like the segment duration option.
+    ///
     /// Note that this method may be invoked more than once.
     fn suggest_duration(&self) -> Duration;
 
@@ -115,16 +119,36 @@ impl State {
 
 pub struct DefaultSampler {
     state: Mutex<State>,
+    /// Precision of the sampled timestamps.
+    precision: TimestampPrecision,
+    /// Max timestamp that wont overflow even using max duration in the
+    /// precision.
+    max_timestamp: i64,
 }
 
 impl Default for DefaultSampler {
     fn default() -> Self {
+        Self::new(TimestampPrecision::Millisecond)
+    }
+}
+
+impl DefaultSampler {
+    pub fn new(precision: TimestampPrecision) -> Self {
+        let max_duration =
+            Duration::from_millis(AVAILABLE_DURATIONS[AVAILABLE_DURATIONS.len() - 1]);
+        let max_timestamp = match precision {
+            TimestampPrecision::Millisecond => MAX_TIMESTAMP_MS_FOR_DURATION,
+            _ => i64::MAX - 2 * precision.scale_duration(max_duration).as_millis() as i64,
+        };
+
         Self {
             state: Mutex::new(State {
                 deduped_timestamps: HashSet::with_capacity(INIT_CAPACITY),
                 duration: None,
                 sorted_timestamps: Vec::new(),
             }),
+            precision,
+            max_timestamp,
         }
     }
 }
@@ -132,7 +156,7 @@ impl Default for DefaultSampler {
 impl DurationSampler for DefaultSampler {
     fn collect(&self, timestamp: Timestamp) -> Result<()> {
         ensure!(
-            timestamp.as_i64() < MAX_TIMESTAMP_MS_FOR_DURATION,
+            timestamp.as_i64() < self.max_timestamp,
             Context { timestamp }
         );
 
@@ -149,8 +173,9 @@ impl DurationSampler for DefaultSampler {
         }
 
         let timestamps = self.compute_sorted_timestamps();
+        // The durations to pick are in millis, so does the interval.
         let picked = match evaluate_interval(&timestamps) {
-            Some(interval) => pick_duration(interval),
+            Some(interval) => pick_duration(self.interval_to_millis(interval)),
             None => table_options::DEFAULT_SEGMENT_DURATION,
         };
 
@@ -165,7 +190,7 @@ impl DurationSampler for DefaultSampler {
     }
 
     fn ranges(&self) -> Vec<TimeRange> {
-        let duration = self.suggest_duration();
+        let duration = self.precision.scale_duration(self.suggest_duration());
         let sorted_timestamps = self.cached_sorted_timestamps();
         // This type hint is needed to make `ranges.last()` work.
         let mut ranges: Vec<TimeRange> = Vec::new();
@@ -187,6 +212,14 @@ impl DurationSampler for DefaultSampler {
 }
 
 impl DefaultSampler {
+    fn interval_to_millis(&self, interval: u64) -> u64 {
+        let interval = i64::try_from(interval).unwrap_or(i64::MAX);
+        self.precision
+            .checked_to_millis(interval)
+            .map(|v| v as u64)
+            .unwrap_or(u64::MAX)
+    }
+
     fn cached_sorted_timestamps(&self) -> Vec<Timestamp> {
         self.state.lock().unwrap().sorted_timestamps.clone()
     }
@@ -361,6 +394,40 @@ impl PrimaryKeySampler {
     }
 }
 
+/// SeriesCounter estimates the number of distinct series of the collected
+/// rows, a series is identified by the primary key without the timestamp.
+pub struct SeriesCounter {
+    hll: Mutex<HyperLogLog>,
+}
+
+impl Default for SeriesCounter {
+    fn default() -> Self {
+        Self {
+            hll: Mutex::new(HyperLogLog::new(HLL_ERROR_RATE)),
+        }
+    }
+}
+
+impl SeriesCounter {
+    pub fn collect<'a>(&self, schema: &Schema, rows: impl Iterator<Item = &'a Row>) {
+        let timestamp_index = schema.timestamp_index();
+        let mut hll = self.hll.lock().unwrap();
+        for row in rows {
+            let mut hasher = DefaultHasher::new();
+            for idx in schema.primary_key_indexes() {
+                if *idx != timestamp_index {
+                    row[*idx].as_view().hash(&mut hasher);
+                }
+            }
+            hll.insert(&hasher.finish());
+        }
+    }
+
+    pub fn estimate(&self) -> u64 {
+        self.hll.lock().unwrap().len().round() as u64
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use common_types::tests::{build_row_for_cpu, build_schema_for_cpu};
@@ -492,6 +559,50 @@ mod tests {
         assert_eq!(&[time_range1, time_range2], &sampler.ranges()[..]);
     }
 
+    #[test]
+    fn test_suggest_duration_in_precision() {
+        let now = 1672502400000i64;
+        for precision in [
+            TimestampPrecision::Second,
+            TimestampPrecision::Microsecond,
+            TimestampPrecision::Nanosecond,
+        ] {
+            let sampler = DefaultSampler::new(precision);
+            // Intervals: 1d, 1d, 1d
+            for i in 0..4 {
+                let ts_ms = now + i * DAY_MS as i64;
+                let ts = precision.checked_from_millis(ts_ms).unwrap();
+                sampler.collect(Timestamp::new(ts)).unwrap();
+            }
+
+            // The suggested duration is in millis, the same as the ms table.
+            let duration = sampler.suggest_duration();
+            assert_eq!(
+                Duration::from_millis(180 * DAY_MS),
+                duration,
+                "precision:{precision}"
+            );
+
+            // The ranges are in the precision.
+            let ranges = sampler.ranges();
+            assert_eq!(1, ranges.len(), "precision:{precision}");
+            let bucket = TimeRange::bucket_of(
+                Timestamp::new(precision.checked_from_millis(now).unwrap()),
+                precision.scale_duration(duration),
+            )
+            .unwrap();
+            assert_eq!(bucket, ranges[0], "precision:{precision}");
+        }
+
+        // The max timestamp is scaled to the precision.
+        let sampler = DefaultSampler::new(TimestampPrecision::Nanosecond);
+        let now_ns = TimestampPrecision::Nanosecond.now();
+        assert!(sampler.collect(now_ns).is_ok());
+        assert!(sampler
+            .collect(Timestamp::new(MAX_TIMESTAMP_MS_FOR_DURATION - 1))
+            .is_err());
+    }
+
     fn test_suggest_duration_and_ranges_case(
         timestamps: &[i64],
         duration: u64,
@@ -615,4 +726,21 @@ mod tests {
         ];
         collect_and_suggest(rows, vec![2, 3, 0, 1]);
     }
+
+    #[test]
+    fn test_series_counter() {
+        let schema = build_schema_for_cpu();
+        let counter = SeriesCounter::default();
+        assert_eq!(0, counter.estimate());
+
+        // Rows with the same key but different timestamps belong to one series.
+        let rows = [
+            build_row_for_cpu(1, 100, "horaedb", "a", 1, 1.0),
+            build_row_for_cpu(1, 101, "horaedb", "a", 2, 1.0),
+            build_row_for_cpu(2, 100, "horaedb", "a", 3, 1.0),
+            build_row_for_cpu(3, 102, "horaedb", "b", 4, 1.0),
+        ];
+        counter.collect(&schema, rows.iter());
+        assert_eq!(3, counter.estimate());
+    }
 }
@@ -23,12 +23,14 @@ use futures::Future;
 use macros::define_result;
 use object_store::{
     aliyun,
+    circuit_breaker::StoreWithCircuitBreaker,
     config::{ObjectStoreOptions, StorageOptions},
     disk_cache::DiskCacheStore,
     local_file,
     mem_cache::{MemCache, MemCacheStore},
     metrics::StoreWithMetrics,
     prefix::StoreWithPrefix,
+    restore_retry::StoreWithRestoreRetry,
     s3, ObjectStoreRef,
 };
 use snafu::{ResultExt, Snafu};
@@ -39,7 +41,12 @@ use crate::{
     compaction::runner::CompactionRunnerRef,
     context::OpenContext,
     engine::TableEngineImpl,
-    instance::open::{InstanceContext, ManifestStorages},
+    format_version,
+    hook::EngineHooks,
+    instance::{
+        open::{InstanceContext, ManifestStorages},
+        InstanceRef,
+    },
     sst::{
         factory::{FactoryImpl, ObjectStorePicker, ObjectStorePickerRef, ReadFrequency},
         meta_data::cache::{MetaCache, MetaCacheRef},
@@ -54,6 +61,11 @@ pub enum Error {
         source: crate::instance::engine::Error,
     },
 
+    #[snafu(display("Failed to check format versions, err:{}", source))]
+    CheckFormatVersion {
+        source: crate::format_version::Error,
+    },
+
     #[snafu(display("Failed to execute in runtime, err:{}", source))]
     RuntimeExec { source: runtime::Error },
 
@@ -86,6 +98,10 @@ pub struct TableEngineContext {
     pub table_engine: TableEngineRef,
     // TODO: unused now, will be used in remote compaction.
     pub local_compaction_runner: Option<CompactionRunnerRef>,
+    /// The default object store of the engine.
+    pub default_store: ObjectStoreRef,
+    /// The disk cache of the object store, None if it's disabled.
+    pub disk_cache: Option<Arc<DiskCacheStore>>,
 }
 
 /// Builder for [TableEngine].
@@ -96,12 +112,24 @@ pub struct EngineBuilder<'a> {
     pub config: &'a Config,
     pub engine_runtimes: Arc<EngineRuntimes>,
     pub opened_wals: OpenedWals,
+    pub hooks: EngineHooks,
 }
 
 impl<'a> EngineBuilder<'a> {
     pub async fn build(self) -> Result<TableEngineContext> {
+        self.build_with_instance().await.map(|(ctx, _)| ctx)
+    }
+
+    /// Build the engine, and the instance of the engine is returned too.
+    pub(crate) async fn build_with_instance(self) -> Result<(TableEngineContext, InstanceRef)> {
         let opened_storages =
             open_storage(self.config.storage.clone(), self.engine_runtimes.clone()).await?;
+        let default_store = opened_storages.default_store().clone();
+        // Refuse the records newer than this binary before replaying them.
+        format_version::check_stamped_versions(&default_store)
+            .await
+            .context(CheckFormatVersion)?;
+        let disk_cache = opened_storages.disk_cache.clone();
         let manifest_storages = ManifestStorages {
             wal_manager: self.opened_wals.manifest_wal.clone(),
             oss_storage: opened_storages.default_store().clone(),
@@ -116,15 +144,19 @@ impl<'a> EngineBuilder<'a> {
             self.opened_wals.data_wal,
             manifest_storages,
             Arc::new(opened_storages),
+            self.hooks,
         )
         .await?;
 
-        let table_engine = Arc::new(TableEngineImpl::new(instance));
-
-        Ok(TableEngineContext {
+        let table_engine = Arc::new(TableEngineImpl::new(instance.clone()));
+        let ctx = TableEngineContext {
             table_engine,
             local_compaction_runner,
-        })
+            default_store,
+            disk_cache,
+        };
+
+        Ok((ctx, instance))
     }
 }
 
@@ -134,6 +166,7 @@ async fn build_instance_context(
     wal_manager: WalManagerRef,
     manifest_storages: ManifestStorages,
     store_picker: ObjectStorePickerRef,
+    hooks: EngineHooks,
 ) -> Result<InstanceContext> {
     let meta_cache: Option<MetaCacheRef> = config
         .sst_meta_cache_cap
@@ -143,6 +176,7 @@ async fn build_instance_context(
         config,
         runtimes: engine_runtimes,
         meta_cache,
+        hooks,
     };
 
     let instance_ctx = InstanceContext::new(
@@ -162,6 +196,7 @@ async fn build_instance_context(
 struct OpenedStorages {
     default_store: ObjectStoreRef,
     store_with_readonly_cache: ObjectStoreRef,
+    disk_cache: Option<Arc<DiskCacheStore>>,
 }
 
 impl ObjectStorePicker for OpenedStorages {
@@ -231,6 +266,17 @@ fn open_storage(
             engine_runtimes.io_runtime.clone(),
         ));
 
+        // The retried reads of the archived objects shouldn't trip the circuit breaker,
+        // so the retry is applied before it.
+        if opts.restore_retry.enable {
+            store = Arc::new(StoreWithRestoreRetry::new(store, opts.restore_retry));
+        }
+
+        if opts.circuit_breaker.enable {
+            store = Arc::new(StoreWithCircuitBreaker::new(store, opts.circuit_breaker));
+        }
+
+        let mut disk_cache = None;
         if opts.disk_cache_capacity.as_byte() > 0 {
             let path = Path::new(&opts.disk_cache_dir).join(DISK_CACHE_DIR_NAME);
             tokio::fs::create_dir_all(&path).await.context(CreateDir {
@@ -238,7 +284,7 @@ fn open_storage(
             })?;
 
             // TODO: Consider the readonly cache.
-            store = Arc::new(
+            let disk_cache_store = Arc::new(
                 DiskCacheStore::try_new(
                     path.to_string_lossy().into_owned(),
                     opts.disk_cache_capacity.as_byte() as usize,
@@ -249,7 +295,9 @@ fn open_storage(
                 )
                 .await
                 .context(OpenObjectStore)?,
-            ) as _;
+            );
+            store = disk_cache_store.clone() as _;
+            disk_cache = Some(disk_cache_store);
         }
 
         if opts.mem_cache_capacity.as_byte() > 0 {
@@ -257,6 +305,7 @@ fn open_storage(
                 MemCache::try_new(
                     opts.mem_cache_partition_bits,
                     NonZeroUsize::new(opts.mem_cache_capacity.as_byte() as usize).unwrap(),
+                    opts.mem_cache_admission,
                 )
                 .context(OpenMemCache)?,
             );
@@ -266,12 +315,14 @@ fn open_storage(
             Ok(OpenedStorages {
                 default_store,
                 store_with_readonly_cache,
+                disk_cache,
             })
         } else {
             let store_with_readonly_cache = store.clone();
             Ok(OpenedStorages {
                 default_store: store,
                 store_with_readonly_cache,
+                disk_cache,
             })
         }
     })
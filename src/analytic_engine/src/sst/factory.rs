@@ -25,7 +25,7 @@ use macros::define_result;
 use object_store::{ObjectStoreRef, Path};
 use runtime::Runtime;
 use snafu::{ResultExt, Snafu};
-use table_engine::predicate::PredicateRef;
+use table_engine::{predicate::PredicateRef, table::QueryStatsRef};
 use trace_metric::MetricsCollector;
 
 use super::parquet::encoding::ColumnEncoding;
@@ -43,7 +43,7 @@ use crate::{
         reader::SstReader,
         writer::SstWriter,
     },
-    table_options::{Compression, StorageFormat, StorageFormatHint},
+    table_options::{Compression, FieldGroup, StorageFormat, StorageFormatHint},
 };
 
 #[derive(Debug, Snafu)]
@@ -125,6 +125,9 @@ pub struct ScanOptions {
     pub max_record_batches_in_flight: usize,
     /// The number of streams to prefetch when scan
     pub num_streams_to_prefetch: usize,
+    /// The byte ranges within this gap are fetched from the object store by one
+    /// request, and no range will be coalesced if it's not set
+    pub get_range_coalesce_gap: Option<usize>,
 }
 
 impl Default for ScanOptions {
@@ -133,6 +136,7 @@ impl Default for ScanOptions {
             background_read_parallelism: 1,
             max_record_batches_in_flight: 64,
             num_streams_to_prefetch: 2,
+            get_range_coalesce_gap: Some(0),
         }
     }
 }
@@ -147,6 +151,9 @@ pub struct SstReadOptions {
     pub predicate: PredicateRef,
     pub meta_cache: Option<MetaCacheRef>,
     pub scan_options: ScanOptions,
+    /// Statistics of the query issuing the read, None for the reads not
+    /// issued by queries (e.g. compaction).
+    pub query_stats: Option<QueryStatsRef>,
 
     pub runtime: Arc<Runtime>,
 }
@@ -162,6 +169,8 @@ pub struct SstWriteOptions {
     pub compression: Compression,
     pub max_buffer_size: usize,
     pub column_stats: HashMap<String, ColumnStats>,
+    /// Field groups whose columns are stored adjacently
+    pub field_groups: Vec<FieldGroup>,
 }
 
 impl From<&ColumnStats> for ColumnEncoding {
@@ -230,6 +239,7 @@ impl Factory for FactoryImpl {
             compression: options.compression.into(),
             sst_level: level,
             column_encodings,
+            field_groups: options.field_groups.clone(),
         };
         Ok(Box::new(ParquetSstWriter::new(
             path,
@@ -20,7 +20,8 @@ use std::sync::atomic::{AtomicU64, Ordering};
 use lazy_static::lazy_static;
 use prometheus::{
     exponential_buckets, register_counter, register_histogram, register_histogram_vec,
-    register_int_counter_vec, Counter, Histogram, HistogramVec, IntCounter, IntCounterVec,
+    register_int_counter, register_int_counter_vec, Counter, Histogram, HistogramVec, IntCounter,
+    IntCounterVec,
 };
 
 lazy_static! {
@@ -32,6 +33,21 @@ lazy_static! {
         exponential_buckets(100.0, 2.0, 5).unwrap()
     ).unwrap();
 
+    pub static ref SST_REQUESTED_RANGES_COUNTER: IntCounter = register_int_counter!(
+        "sst_requested_ranges",
+        "The number of byte ranges requested by the sst reader"
+    ).unwrap();
+
+    pub static ref SST_FETCHED_RANGES_COUNTER: IntCounter = register_int_counter!(
+        "sst_fetched_ranges",
+        "The number of byte ranges fetched from the object store after coalescing"
+    ).unwrap();
+
+    pub static ref SST_COALESCED_GAP_BYTES_COUNTER: IntCounter = register_int_counter!(
+        "sst_coalesced_gap_bytes",
+        "The number of bytes fetched for the gaps between the coalesced ranges"
+    ).unwrap();
+
     pub static ref META_DATA_CACHE_HIT_COUNTER: Counter = register_counter!(
         "META_DATA_CACHE_HIT",
         "The counter for meta data cache hit"
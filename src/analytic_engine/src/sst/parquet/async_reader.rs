@@ -52,7 +52,7 @@ use parquet_ext::{
 };
 use runtime::{AbortOnDropMany, JoinHandle, Runtime};
 use snafu::ResultExt;
-use table_engine::predicate::PredicateRef;
+use table_engine::{predicate::PredicateRef, table::QueryStatsRef};
 use time_ext::InstantExt;
 use tokio::sync::{
     mpsc::{self, Receiver, Sender},
@@ -68,7 +68,10 @@ use crate::{
             cache::{MetaCacheRef, MetaData},
             SstMetaData,
         },
-        metrics::MaybeTableLevelMetrics,
+        metrics::{
+            MaybeTableLevelMetrics, SST_COALESCED_GAP_BYTES_COUNTER, SST_FETCHED_RANGES_COUNTER,
+            SST_REQUESTED_RANGES_COUNTER,
+        },
         parquet::{
             encoding::ParquetDecoder,
             meta_data::{filter::ParquetFilter, ColumnValueSet},
@@ -94,6 +97,7 @@ pub struct Reader<'a> {
     predicate: PredicateRef,
     /// Current frequency decides the cache policy.
     frequency: ReadFrequency,
+    get_range_coalesce_gap: Option<usize>,
     /// Init those fields in `init_if_necessary`
     meta_data: Option<MetaData>,
 
@@ -105,6 +109,7 @@ pub struct Reader<'a> {
     df_plan_metrics: ExecutionPlanMetricsSet,
 
     table_level_sst_metrics: Option<Arc<MaybeTableLevelMetrics>>,
+    query_stats: Option<QueryStatsRef>,
 }
 
 #[derive(Default, Debug, Clone, TraceMetricWhenDrop)]
@@ -142,12 +147,14 @@ impl<'a> Reader<'a> {
             meta_cache: options.meta_cache.clone(),
             predicate: options.predicate.clone(),
             frequency: options.frequency,
+            get_range_coalesce_gap: options.scan_options.get_range_coalesce_gap,
             meta_data: None,
             row_projector_builder: options.row_projector_builder.clone(),
             row_projector: None,
             metrics,
             df_plan_metrics,
             table_level_sst_metrics: options.maybe_table_level_metrics.clone(),
+            query_stats: options.query_stats.clone(),
         }
     }
 
@@ -312,6 +319,7 @@ impl<'a> Reader<'a> {
         let mut streams = Vec::with_capacity(target_row_group_chunks.len());
         let metrics_collector = ObjectStoreMetricsObserver {
             table_level_sst_metrics: self.table_level_sst_metrics.clone(),
+            query_stats: self.query_stats.clone(),
         };
         for chunk in target_row_group_chunks {
             let object_store_reader = ObjectStoreReader::with_metrics(
@@ -319,7 +327,8 @@ impl<'a> Reader<'a> {
                 self.path.clone(),
                 parquet_metadata.clone(),
                 metrics_collector.clone(),
-            );
+            )
+            .with_coalesce_gap(self.get_range_coalesce_gap);
             let mut builder = ParquetRecordBatchStreamBuilder::new(object_store_reader)
                 .await
                 .with_context(|| ParquetError)?;
@@ -613,6 +622,21 @@ impl<'a> SstReader for Reader<'a> {
 
         Ok(Box::new(NoopPrefetcher(stream)))
     }
+
+    async fn maybe_contains(&mut self) -> Result<bool> {
+        self.init_if_necessary().await?;
+
+        let meta_data = self.meta_data.as_ref().unwrap();
+        let custom = meta_data.custom();
+        let target_row_groups = self.prune_row_groups(
+            custom.schema.to_arrow_schema_ref(),
+            meta_data.parquet().row_groups(),
+            custom.parquet_filter.as_ref(),
+            custom.column_values.as_ref(),
+        )?;
+
+        Ok(!target_row_groups.is_empty())
+    }
 }
 
 struct RecordBatchReceiver {
@@ -748,6 +772,10 @@ impl<'a> SstReader for ThreadedReader<'a> {
         self.inner.meta_data().await
     }
 
+    async fn maybe_contains(&mut self) -> Result<bool> {
+        self.inner.maybe_contains().await
+    }
+
     async fn read(
         &mut self,
     ) -> Result<Box<dyn PrefetchableStream<Item = Result<FetchedRecordBatch>>>> {
@@ -798,6 +826,7 @@ impl<'a> SstReader for ThreadedReader<'a> {
 #[derive(Clone)]
 struct ObjectStoreMetricsObserver {
     table_level_sst_metrics: Option<Arc<MaybeTableLevelMetrics>>,
+    query_stats: Option<QueryStatsRef>,
 }
 
 impl MetricsObserver for ObjectStoreMetricsObserver {
@@ -811,6 +840,21 @@ impl MetricsObserver for ObjectStoreMetricsObserver {
                 .num_fetched_sst_bytes
                 .fetch_add(num_bytes as u64, Ordering::Relaxed);
         }
+        if let Some(stats) = &self.query_stats {
+            stats.add_scanned_bytes(num_bytes as u64);
+        }
+    }
+
+    fn ranges_coalesced(
+        &self,
+        _: &Path,
+        num_ranges: usize,
+        num_fetched_ranges: usize,
+        num_gap_bytes: usize,
+    ) {
+        SST_REQUESTED_RANGES_COUNTER.inc_by(num_ranges as u64);
+        SST_FETCHED_RANGES_COUNTER.inc_by(num_fetched_ranges as u64);
+        SST_COALESCED_GAP_BYTES_COUNTER.inc_by(num_gap_bytes as u64);
     }
 }
 
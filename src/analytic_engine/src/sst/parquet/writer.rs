@@ -52,7 +52,7 @@ use crate::{
         },
     },
     table::sst_util,
-    table_options::StorageFormat,
+    table_options::{FieldGroup, StorageFormat},
 };
 
 const KEEP_COLUMN_VALUE_THRESHOLD: usize = 20;
@@ -114,6 +114,7 @@ pub struct WriteOptions {
     pub compression: Compression,
     pub sst_level: Level,
     pub column_encodings: HashMap<String, ColumnEncoding>,
+    pub field_groups: Vec<FieldGroup>,
 }
 
 impl WriteOptions {
@@ -407,6 +408,34 @@ impl<'a> RecordBatchGroupWriter<'a> {
     }
 }
 
+/// Returns the order of the columns stored in the sst, where the key columns
+/// come first, followed by the columns of the field groups, and the other
+/// columns are put at the end.
+///
+/// None is returned if the order is the same as the schema.
+fn column_order(schema: &Schema, field_groups: &[FieldGroup]) -> Option<Vec<usize>> {
+    if field_groups.is_empty() {
+        return None;
+    }
+
+    let num_columns = schema.num_columns();
+    let mut order: Vec<_> = (0..num_columns)
+        .filter(|idx| schema.primary_key_indexes().contains(idx))
+        .collect();
+    let grouped_columns = field_groups
+        .iter()
+        .flat_map(|group| group.columns.iter())
+        .filter_map(|column| schema.index_of(column));
+    for idx in grouped_columns.chain(0..num_columns) {
+        if !order.contains(&idx) {
+            order.push(idx);
+        }
+    }
+
+    let is_same = order.iter().enumerate().all(|(i, idx)| i == *idx);
+    (!is_same).then_some(order)
+}
+
 async fn write_metadata(
     meta_sink: MultiUploadWriter,
     parquet_metadata: ParquetMetaData,
@@ -441,12 +470,35 @@ impl<'a> SstWriter for ParquetSstWriter<'a> {
             request_id, meta, self.options.num_rows_per_row_group
         );
 
+        // Store the columns of the same field group adjacently, and the reader finds
+        // the columns by the schema in the sst meta.
+        let reordered_meta;
+        let (meta, input) = match column_order(&meta.schema, &self.options.field_groups) {
+            Some(order) => {
+                let schema = meta.schema.reorder_columns(&order);
+                let record_schema = schema.to_record_schema();
+                let input: RecordBatchStream = Box::new(input.map(move |batch| {
+                    batch.and_then(|batch| {
+                        batch
+                            .reorder_columns(record_schema.clone(), &order)
+                            .box_err()
+                    })
+                }));
+                reordered_meta = MetaData {
+                    schema,
+                    ..meta.clone()
+                };
+                (&reordered_meta, input)
+            }
+            None => (meta, input),
+        };
         let write_options = WriteOptions {
             num_rows_per_row_group: self.options.num_rows_per_row_group,
             max_buffer_size: self.options.max_buffer_size,
             compression: self.options.compression,
             sst_level: self.options.sst_level,
             column_encodings: std::mem::take(&mut self.options.column_encodings),
+            field_groups: std::mem::take(&mut self.options.field_groups),
         };
         let group_writer = RecordBatchGroupWriter::new(request_id, input, meta, write_options);
 
@@ -636,6 +688,7 @@ mod tests {
                 compression: table_options::Compression::Uncompressed,
                 max_buffer_size: 0,
                 column_stats: Default::default(),
+                field_groups: Vec::new(),
             };
 
             let root = tempdir().unwrap().as_ref().to_string_lossy().to_string();
@@ -732,6 +785,7 @@ mod tests {
                 predicate: Arc::new(Predicate::empty()),
                 meta_cache: None,
                 scan_options,
+                query_stats: None,
                 runtime: runtime.clone(),
                 row_projector_builder,
             };
@@ -878,6 +932,7 @@ mod tests {
             compression: Compression::UNCOMPRESSED,
             sst_level: Level::default(),
             column_encodings: Default::default(),
+            field_groups: Vec::new(),
         };
         let meta_data = MetaData {
             min_key: Default::default(),
@@ -905,6 +960,32 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_column_order() {
+        // key1, key2, field1, field2, field3, field4
+        let schema = build_schema();
+        assert_eq!(None, column_order(&schema, &[]));
+
+        let groups = vec![
+            FieldGroup {
+                name: "g1".to_string(),
+                columns: vec!["field4".to_string(), "field2".to_string()],
+            },
+            FieldGroup {
+                name: "g2".to_string(),
+                columns: vec!["not_exist".to_string(), "field3".to_string()],
+            },
+        ];
+        assert_eq!(Some(vec![0, 1, 5, 3, 4, 2]), column_order(&schema, &groups));
+
+        // The order is not changed.
+        let groups = vec![FieldGroup {
+            name: "g1".to_string(),
+            columns: vec!["field1".to_string(), "field2".to_string()],
+        }];
+        assert_eq!(None, column_order(&schema, &groups));
+    }
+
     fn check_sample_column_encoding(
         mut sampler: ColumnEncodingSampler<'_>,
         expect_enable_dicts: Vec<Option<bool>>,
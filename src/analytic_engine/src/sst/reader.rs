@@ -109,6 +109,11 @@ pub trait SstReader {
     async fn read(
         &mut self,
     ) -> Result<Box<dyn PrefetchableStream<Item = Result<FetchedRecordBatch>>>>;
+
+    /// Whether the sst may contain the rows matching the predicate, which is
+    /// decided by the meta data and the filters only, without reading any
+    /// data.
+    async fn maybe_contains(&mut self) -> Result<bool>;
 }
 
 #[cfg(test)]
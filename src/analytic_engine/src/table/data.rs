@@ -24,7 +24,7 @@ use std::{
     fmt::Formatter,
     num::NonZeroUsize,
     sync::{
-        atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering},
+        atomic::{AtomicBool, AtomicI64, AtomicU32, AtomicU64, AtomicUsize, Ordering},
         Arc, Mutex,
     },
     time::Duration,
@@ -41,7 +41,7 @@ use common_types::{
 };
 use generic_error::{GenericError, GenericResult};
 use id_allocator::IdAllocator;
-use logger::{debug, info};
+use logger::{debug, info, warn};
 use macros::define_result;
 use object_store::Path;
 use snafu::{ensure, Backtrace, OptionExt, ResultExt, Snafu};
@@ -61,12 +61,15 @@ use crate::{
         skiplist::factory::SkiplistMemTableFactory,
         MemtableType,
     },
+    sampler::SeriesCounter,
     space::SpaceId,
     sst::{file::FilePurger, manager::FileId},
     table::{
         metrics::{Metrics, MetricsContext},
         sst_util,
+        summary::TableSummary,
         version::{MemTableForWrite, MemTableState, SamplingMemTable, TableVersion},
+        write_schema_cache::WriteSchemaCache,
     },
     table_options::UpdateMode,
     MetricsOptions, TableOptions,
@@ -135,6 +138,8 @@ mod hack {
         Closed,
         /// No write/alter are allowed after table is dropped.
         Dropped,
+        /// Closed by the idle table reaper, and it will be reopened on access.
+        IdleClosed,
     }
 }
 
@@ -200,8 +205,9 @@ pub struct TableData {
     /// carefully to avoid the reader seeing inconsistent segment duration
     /// and memtables/ssts during query/compaction/flush .
     opts: ArcSwap<TableOptions>,
-    /// MemTable factory of this table
-    memtable_factory: MemTableFactoryRef,
+    /// MemTable factory of this table, replaced when the memtable options are
+    /// altered, and the memtables created before are kept until flushed.
+    memtable_factory: ArcSwap<MemTableFactoryRef>,
     /// Space memtable memory usage collector
     mem_usage_collector: CollectorRef,
 
@@ -221,11 +227,31 @@ pub struct TableData {
 
     /// Allocating file id
     allocator: IdAllocator,
+    /// Max file id recovered from the manifest when the table is opened, the
+    /// ids of the files written since then are greater than it
+    recovered_max_file_id: FileId,
 
     /// Last flush time
     ///
     /// Not persist, used to determine if this table should flush.
     last_flush_time_ms: AtomicU64,
+    /// Last read/write time
+    ///
+    /// Not persist, used to determine if this table is idle.
+    last_access_time_ms: AtomicU64,
+    /// Max timestamp of the last written batch
+    ///
+    /// Not persist, used to detect the disorder between write batches.
+    last_write_max_timestamp: AtomicI64,
+    /// The time when the table is opened
+    opened_time_ms: u64,
+
+    /// Summary of the ssts used as the estimated statistics
+    summary: Mutex<TableSummary>,
+    /// Estimates the number of series written since the table is opened
+    series_counter: SeriesCounter,
+    /// Cache of the schema compatibility checks of the writes
+    pub write_schema_cache: WriteSchemaCache,
 
     /// Table Status
     status: AtomicTableStatus,
@@ -239,8 +265,11 @@ pub struct TableData {
     /// Whether enable primary key sampling
     enable_primary_key_sampling: bool,
 
-    /// Whether enable layered memtable
-    pub enable_layered_memtable: bool,
+    /// Whether layered memtable is enabled
+    ///
+    /// It's kept true after layered memtable is disabled by altering options,
+    /// because the layered memtables created before may be still unflushed.
+    enable_layered_memtable: AtomicBool,
 
     /// Metrics of this table
     pub metrics: Metrics,
@@ -286,6 +315,33 @@ fn compute_mutable_limit(
     limit as u32
 }
 
+/// Build the memtable factory according to the memtable options.
+fn build_memtable_factory(opts: &TableOptions) -> Result<MemTableFactoryRef> {
+    let memtable_factory: MemTableFactoryRef = match opts.memtable_type {
+        MemtableType::SkipList => Arc::new(SkiplistMemTableFactory),
+        MemtableType::Column => Arc::new(ColumnarMemTableFactory),
+    };
+    if !opts.layered_memtable_opts.enable {
+        return Ok(memtable_factory);
+    }
+
+    let mutable_segment_switch_threshold = opts
+        .layered_memtable_opts
+        .mutable_segment_switch_threshold
+        .0 as usize;
+    ensure!(
+        mutable_segment_switch_threshold > 0,
+        InvalidTableOpts {
+            msg: "layered memtable is enabled but mutable_switch_threshold is 0",
+        }
+    );
+
+    Ok(Arc::new(LayeredMemtableFactory::new(
+        memtable_factory,
+        mutable_segment_switch_threshold,
+    )))
+}
+
 pub struct MemSizeOptions {
     pub collector: CollectorRef,
     pub size_sampling_interval: ReadableDuration,
@@ -325,32 +381,8 @@ impl TableData {
             ..
         } = config;
 
-        let memtable_factory: MemTableFactoryRef = match opts.memtable_type {
-            MemtableType::SkipList => Arc::new(SkiplistMemTableFactory),
-            MemtableType::Column => Arc::new(ColumnarMemTableFactory),
-        };
-
+        let memtable_factory = build_memtable_factory(&opts)?;
         let enable_layered_memtable = opts.layered_memtable_opts.enable;
-        let memtable_factory = if enable_layered_memtable {
-            let mutable_segment_switch_threshold = opts
-                .layered_memtable_opts
-                .mutable_segment_switch_threshold
-                .0 as usize;
-
-            ensure!(
-                mutable_segment_switch_threshold > 0,
-                InvalidTableOpts {
-                    msg: "layered memtable is enabled but mutable_switch_threshold is 0",
-                }
-            );
-
-            Arc::new(LayeredMemtableFactory::new(
-                memtable_factory,
-                mutable_segment_switch_threshold,
-            ))
-        } else {
-            memtable_factory
-        };
 
         let purge_queue = purger.create_purge_queue(space_id, id);
         let current_version =
@@ -361,6 +393,7 @@ impl TableData {
             opts.write_buffer_size,
             preflush_write_buffer_size_ratio,
         ));
+        let now_ms = time_ext::current_time_millis();
 
         Ok(Self {
             id,
@@ -375,13 +408,20 @@ impl TableData {
             mutable_limit,
             mutable_limit_write_buffer_ratio: preflush_write_buffer_size_ratio,
             opts: ArcSwap::new(Arc::new(opts)),
-            memtable_factory,
+            memtable_factory: ArcSwap::new(Arc::new(memtable_factory)),
             mem_usage_collector: mem_size_options.collector,
             current_version,
             last_sequence: AtomicU64::new(0),
             last_memtable_id: AtomicU64::new(0),
             allocator: IdAllocator::new(0, 0, DEFAULT_ALLOC_STEP),
+            recovered_max_file_id: 0,
             last_flush_time_ms: AtomicU64::new(0),
+            last_access_time_ms: AtomicU64::new(now_ms),
+            last_write_max_timestamp: AtomicI64::new(i64::MIN),
+            opened_time_ms: now_ms,
+            summary: Mutex::new(TableSummary::default()),
+            series_counter: SeriesCounter::default(),
+            write_schema_cache: WriteSchemaCache::default(),
             status: TableStatus::Ok.into(),
             metrics,
             shard_info: TableShardInfo::new(shard_id),
@@ -389,7 +429,7 @@ impl TableData {
             manifest_updates: AtomicUsize::new(0),
             manifest_snapshot_every_n_updates,
             enable_primary_key_sampling,
-            enable_layered_memtable,
+            enable_layered_memtable: AtomicBool::new(enable_layered_memtable),
         })
     }
 
@@ -402,7 +442,7 @@ impl TableData {
         shard_id: ShardId,
         config: TableConfig,
         mem_size_options: MemSizeOptions,
-        allocator: IdAllocator,
+        max_file_id: FileId,
         table_catalog_info: TableCatalogInfo,
     ) -> Result<Self> {
         let TableConfig {
@@ -464,6 +504,7 @@ impl TableData {
             add_meta.opts.write_buffer_size,
             preflush_write_buffer_size_ratio,
         ));
+        let now_ms = time_ext::current_time_millis();
 
         Ok(Self {
             id: add_meta.table_id,
@@ -474,13 +515,20 @@ impl TableData {
             mutable_limit,
             mutable_limit_write_buffer_ratio: preflush_write_buffer_size_ratio,
             opts: ArcSwap::new(Arc::new(add_meta.opts)),
-            memtable_factory,
+            memtable_factory: ArcSwap::new(Arc::new(memtable_factory)),
             mem_usage_collector: mem_size_options.collector,
             current_version,
             last_sequence: AtomicU64::new(0),
             last_memtable_id: AtomicU64::new(0),
-            allocator,
+            allocator: IdAllocator::new(max_file_id, max_file_id, DEFAULT_ALLOC_STEP),
+            recovered_max_file_id: max_file_id,
             last_flush_time_ms: AtomicU64::new(0),
+            last_access_time_ms: AtomicU64::new(now_ms),
+            last_write_max_timestamp: AtomicI64::new(i64::MIN),
+            opened_time_ms: now_ms,
+            summary: Mutex::new(TableSummary::default()),
+            series_counter: SeriesCounter::default(),
+            write_schema_cache: WriteSchemaCache::default(),
             status: TableStatus::Ok.into(),
             metrics,
             shard_info: TableShardInfo::new(shard_id),
@@ -488,7 +536,7 @@ impl TableData {
             manifest_updates: AtomicUsize::new(0),
             manifest_snapshot_every_n_updates,
             enable_primary_key_sampling,
-            enable_layered_memtable,
+            enable_layered_memtable: AtomicBool::new(enable_layered_memtable),
         })
     }
 
@@ -500,6 +548,8 @@ impl TableData {
     /// Set current schema of the table.
     pub fn set_schema(&self, schema: Schema) {
         *self.schema.lock().unwrap() = schema;
+        // The cached checks against the old schema are never used again.
+        self.write_schema_cache.clear();
     }
 
     /// Get current version of schema.
@@ -542,14 +592,104 @@ impl TableData {
         self.last_flush_time_ms.store(time, Ordering::Release);
     }
 
+    /// Get last read/write time
+    #[inline]
+    pub fn last_access_time(&self) -> u64 {
+        self.last_access_time_ms.load(Ordering::Relaxed)
+    }
+
+    /// Mark the table is accessed now.
+    #[inline]
+    pub fn touch(&self) {
+        self.last_access_time_ms
+            .store(time_ext::current_time_millis(), Ordering::Relaxed);
+    }
+
+    /// Replace the max timestamp of the last written batch, and return the
+    /// previous one.
+    #[inline]
+    pub fn swap_last_write_max_timestamp(&self, max_timestamp: i64) -> i64 {
+        self.last_write_max_timestamp
+            .swap(max_timestamp, Ordering::Relaxed)
+    }
+
+    /// Refresh the summary of the ssts at `now`, returns the number of the
+    /// ssts added or removed since the last refresh.
+    pub fn refresh_summary(&self, now: u64) -> usize {
+        let ssts = self.current_version.sst_metas();
+        self.summary.lock().unwrap().refresh(ssts.iter(), now)
+    }
+
+    /// The time when the summary is refreshed in millis, 0 if never.
+    #[inline]
+    pub fn summary_refreshed_at(&self) -> u64 {
+        self.summary.lock().unwrap().refreshed_at()
+    }
+
+    /// Estimated number of rows in the ssts and the staleness of the estimate.
+    ///
+    /// The number of rows is None if the confidence of the estimate decayed by
+    /// `half_life` is below `min_confidence`.
+    pub fn estimated_num_rows(
+        &self,
+        now: u64,
+        half_life: Duration,
+        min_confidence: f64,
+    ) -> (Option<u64>, Option<Duration>) {
+        let summary = self.summary.lock().unwrap();
+        let num_rows =
+            (summary.confidence(now, half_life) >= min_confidence).then(|| summary.num_rows());
+        (num_rows, summary.staleness(now))
+    }
+
+    /// Collect the series of the rows written to the table.
+    #[inline]
+    pub fn collect_series(&self, row_group: &RowGroup) {
+        self.series_counter
+            .collect(row_group.schema(), row_group.iter());
+    }
+
+    /// Estimated number of series written since the table is opened.
+    #[inline]
+    pub fn estimated_num_series(&self) -> u64 {
+        self.series_counter.estimate()
+    }
+
+    /// Get the time when the table is opened
+    #[inline]
+    pub fn opened_time(&self) -> u64 {
+        self.opened_time_ms
+    }
+
     #[inline]
     pub fn table_options(&self) -> Arc<TableOptions> {
         self.opts.load().clone()
     }
 
     /// Update table options.
-    #[inline]
+    ///
+    /// The memtable factory is rebuilt if the memtable options are changed, so
+    /// the memtables created after are built with the new options.
     pub fn set_table_options(&self, opts: TableOptions) {
+        let old_opts = self.opts.load();
+        if old_opts.memtable_type != opts.memtable_type
+            || old_opts.layered_memtable_opts != opts.layered_memtable_opts
+        {
+            match build_memtable_factory(&opts) {
+                Ok(memtable_factory) => {
+                    if opts.layered_memtable_opts.enable {
+                        self.enable_layered_memtable.store(true, Ordering::Relaxed);
+                    }
+                    self.memtable_factory.store(Arc::new(memtable_factory));
+                }
+                // The altered options are validated in advance, so it's unlikely to happen.
+                Err(e) => warn!(
+                    "Failed to rebuild memtable factory, keep the old one, table:{}, err:{}",
+                    self.name, e
+                ),
+            }
+        }
+
         let mutable_limit = compute_mutable_limit(
             opts.write_buffer_size,
             self.mutable_limit_write_buffer_ratio,
@@ -558,6 +698,12 @@ impl TableData {
         self.opts.store(Arc::new(opts))
     }
 
+    /// Whether the memtables may be layered memtables.
+    #[inline]
+    pub fn enable_layered_memtable(&self) -> bool {
+        self.enable_layered_memtable.load(Ordering::Relaxed)
+    }
+
     #[inline]
     pub fn is_dropped(&self) -> bool {
         self.status.load(Ordering::SeqCst) == TableStatus::Dropped
@@ -574,11 +720,23 @@ impl TableData {
         self.status.store(TableStatus::Closed, Ordering::SeqCst)
     }
 
+    #[inline]
+    pub fn is_idle_closed(&self) -> bool {
+        self.status.load(Ordering::SeqCst) == TableStatus::IdleClosed
+    }
+
+    /// Set the table is closed by the idle table reaper, and it should be
+    /// reopened before the next access.
+    #[inline]
+    pub fn set_idle_closed(&self) {
+        self.status.store(TableStatus::IdleClosed, Ordering::SeqCst)
+    }
+
     #[inline]
     pub fn allow_compaction(&self) -> bool {
         match self.status.load(Ordering::SeqCst) {
             TableStatus::Ok => true,
-            TableStatus::Closed | TableStatus::Dropped => false,
+            TableStatus::Closed | TableStatus::Dropped | TableStatus::IdleClosed => false,
         }
     }
 
@@ -624,6 +782,7 @@ impl TableData {
         };
         let mem = self
             .memtable_factory
+            .load()
             .create_memtable(memtable_opts)
             .context(CreateMemTable)?;
 
@@ -650,7 +809,11 @@ impl TableData {
                 Ok(MemTableForWrite::Normal(mem_state))
             }
             None => {
-                let mut sampling_mem = SamplingMemTable::new(mem, self.alloc_memtable_id());
+                let mut sampling_mem = SamplingMemTable::new(
+                    mem,
+                    self.alloc_memtable_id(),
+                    table_options.timestamp_precision,
+                );
                 if self.enable_primary_key_sampling && table_options.support_sample_pk() {
                     sampling_mem.set_pk_sampler(table_schema);
                 }
@@ -714,6 +877,11 @@ impl TableData {
         should_flush
     }
 
+    #[inline]
+    pub fn recovered_max_file_id(&self) -> FileId {
+        self.recovered_max_file_id
+    }
+
     /// Use allocator to alloc a file id for a new file.
     pub async fn alloc_file_id(&self, manifest: &ManifestRef) -> Result<FileId> {
         // Persist next max file id to manifest.
@@ -740,6 +908,7 @@ impl TableData {
             files_to_delete: vec![],
             mems_to_remove: vec![],
             max_file_id: next_max_file_id,
+            delete_before: None,
         };
         let edit_req = {
             let meta_update = MetaUpdate::VersionEdit(manifest_update);
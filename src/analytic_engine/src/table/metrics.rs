@@ -20,7 +20,7 @@
 use std::{
     sync::{
         atomic::{AtomicU64, Ordering},
-        Arc,
+        Arc, Mutex,
     },
     time::Duration,
 };
@@ -31,7 +31,8 @@ use prometheus::{
     exponential_buckets,
     local::{LocalHistogram, LocalHistogramTimer},
     register_histogram, register_histogram_vec, register_int_counter, register_int_counter_vec,
-    Histogram, HistogramTimer, HistogramVec, IntCounter, IntCounterVec,
+    register_int_gauge_vec, Histogram, HistogramTimer, HistogramVec, IntCounter, IntCounterVec,
+    IntGauge, IntGaugeVec,
 };
 use table_engine::{partition::maybe_extract_partitioned_table_name, table::TableStats};
 
@@ -55,6 +56,21 @@ lazy_static! {
     )
     .unwrap();
 
+    // Gauges:
+    static ref TABLE_WAL_UNFLUSHED_ENTRIES_GAUGE: IntGaugeVec = register_int_gauge_vec!(
+        "table_wal_unflushed_entries",
+        "Number of the wal entries not flushed, which is the gap between the last sequence and the flushed sequence",
+        &["shard_id", "table"]
+    )
+    .unwrap();
+
+    static ref TABLE_WAL_UNFLUSHED_BYTES_GAUGE: IntGaugeVec = register_int_gauge_vec!(
+        "table_wal_unflushed_bytes",
+        "Approximate bytes of the wal entries not flushed",
+        &["shard_id", "table"]
+    )
+    .unwrap();
+
     static ref TABLE_WRITE_FIELDS_COUNTER: IntCounter = register_int_counter!(
         "table_write_fields_counter",
         "Fields counter of table write"
@@ -154,23 +170,92 @@ lazy_static! {
     // End of histograms.
 }
 
+/// Window in seconds to compute the recent rate of requests.
+const RATE_WINDOW_SECS: u64 = 60;
+
+/// Counter of the requests in the recent [RATE_WINDOW_SECS] seconds, one slot
+/// per second.
+///
+/// The slots are reset without lock so the rate is approximate, which is
+/// enough for statistics.
+struct RecentRate {
+    slot_secs: Vec<AtomicU64>,
+    slot_counts: Vec<AtomicU64>,
+}
+
+impl Default for RecentRate {
+    fn default() -> Self {
+        let new_slots = || (0..RATE_WINDOW_SECS).map(|_| AtomicU64::new(0)).collect();
+        Self {
+            slot_secs: new_slots(),
+            slot_counts: new_slots(),
+        }
+    }
+}
+
+impl RecentRate {
+    fn record(&self, now_ms: u64) {
+        let sec = now_ms / 1000;
+        let idx = (sec % RATE_WINDOW_SECS) as usize;
+        if self.slot_secs[idx].swap(sec, Ordering::Relaxed) != sec {
+            self.slot_counts[idx].store(0, Ordering::Relaxed);
+        }
+        self.slot_counts[idx].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Requests per second in the recent window.
+    fn rate(&self, now_ms: u64) -> f64 {
+        let sec = now_ms / 1000;
+        let total: u64 = self
+            .slot_secs
+            .iter()
+            .zip(&self.slot_counts)
+            .filter(|(slot_sec, _)| {
+                let slot_sec = slot_sec.load(Ordering::Relaxed);
+                slot_sec <= sec && slot_sec + RATE_WINDOW_SECS > sec
+            })
+            .map(|(_, count)| count.load(Ordering::Relaxed))
+            .sum();
+
+        total as f64 / RATE_WINDOW_SECS as f64
+    }
+}
+
 #[derive(Default)]
 struct AtomicTableStats {
     num_write: AtomicU64,
     num_read: AtomicU64,
     num_flush: AtomicU64,
+    recent_writes: RecentRate,
+    recent_reads: RecentRate,
 }
 
 impl From<&AtomicTableStats> for TableStats {
     fn from(stats: &AtomicTableStats) -> Self {
+        let now = time_ext::current_time_millis();
         Self {
             num_write: stats.num_write.load(Ordering::Relaxed),
             num_read: stats.num_read.load(Ordering::Relaxed),
             num_flush: stats.num_flush.load(Ordering::Relaxed),
+            write_rate: stats.recent_writes.rate(now),
+            read_rate: stats.recent_reads.rate(now),
+            ..Default::default()
         }
     }
 }
 
+/// The wal entries of a table not flushed yet, which have to be replayed on
+/// recovery.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UnflushedWal {
+    pub entries: u64,
+    /// Approximate size of the entries, the size of the replayed entries is
+    /// unknown.
+    pub bytes: u64,
+    /// Approximate time (in millis) when the oldest unflushed entry is written.
+    pub first_write_time_ms: Option<u64>,
+}
+
 /// Table metrics.
 ///
 /// Now the registered labels won't remove from the metrics vec to avoid panic
@@ -201,6 +286,22 @@ pub struct Metrics {
     table_write_queue_writer_duration: Histogram,
     table_write_total_duration: Histogram,
     table_write_bytes_counter: IntCounter,
+
+    unflushed_wal: Mutex<UnflushedWal>,
+    wal_unflushed_entries_gauge: IntGauge,
+    wal_unflushed_bytes_gauge: IntGauge,
+}
+
+impl Drop for Metrics {
+    fn drop(&mut self) {
+        // The labels are kept, so remove the entries of the closed table from the
+        // gauges.
+        if let Ok(unflushed) = self.unflushed_wal.get_mut() {
+            self.wal_unflushed_entries_gauge
+                .sub(unflushed.entries as i64);
+            self.wal_unflushed_bytes_gauge.sub(unflushed.bytes as i64);
+        }
+    }
 }
 
 pub struct MaybeTableLevelMetrics {
@@ -271,8 +372,6 @@ impl Metrics {
         let table_write_bytes_counter =
             TABLE_WRITE_BYTES_COUNTER.with_label_values(&[&shard_id_label, &maybe_table_name]);
         Self {
-            maybe_table_name,
-            shard_id_label,
             stats: Arc::new(AtomicTableStats::default()),
             compaction_input_sst_size_histogram: TABLE_COMPACTION_SST_SIZE_HISTOGRAM
                 .with_label_values(&["input"]),
@@ -307,6 +406,14 @@ impl Metrics {
             table_write_total_duration: TABLE_WRITE_DURATION_HISTOGRAM
                 .with_label_values(&["total"]),
             table_write_bytes_counter,
+
+            unflushed_wal: Mutex::new(UnflushedWal::default()),
+            wal_unflushed_entries_gauge: TABLE_WAL_UNFLUSHED_ENTRIES_GAUGE
+                .with_label_values(&[&shard_id_label, &maybe_table_name]),
+            wal_unflushed_bytes_gauge: TABLE_WAL_UNFLUSHED_BYTES_GAUGE
+                .with_label_values(&[&shard_id_label, &maybe_table_name]),
+            maybe_table_name,
+            shard_id_label,
         }
     }
 
@@ -327,6 +434,9 @@ impl Metrics {
     #[inline]
     pub fn on_write_request_begin(&self) {
         self.stats.num_write.fetch_add(1, Ordering::Relaxed);
+        self.stats
+            .recent_writes
+            .record(time_ext::current_time_millis());
         TABLE_WRITE_REQUEST_COUNTER.inc();
     }
 
@@ -337,9 +447,65 @@ impl Metrics {
         self.table_write_bytes_counter.inc_by(num_bytes as u64);
     }
 
+    /// Record the entries written to the wal.
+    pub fn on_wal_write(&self, num_entries: usize, num_bytes: usize) {
+        let mut unflushed = self.unflushed_wal.lock().unwrap();
+        unflushed.entries += num_entries as u64;
+        unflushed.bytes += num_bytes as u64;
+        if unflushed.first_write_time_ms.is_none() {
+            unflushed.first_write_time_ms = Some(time_ext::current_time_millis());
+        }
+        self.wal_unflushed_entries_gauge.add(num_entries as i64);
+        self.wal_unflushed_bytes_gauge.add(num_bytes as i64);
+    }
+
+    /// Record the entries up to `flushed_sequence` are flushed, which is also
+    /// called after the entries are replayed on recovery.
+    ///
+    /// The gauges are updated by the deltas, so they can be summed up when the
+    /// tables share the same label.
+    pub fn on_wal_flushed(&self, last_sequence: u64, flushed_sequence: u64) {
+        let mut unflushed = self.unflushed_wal.lock().unwrap();
+        let entries = last_sequence.saturating_sub(flushed_sequence);
+        // Assume the entries have the same size.
+        let bytes = if unflushed.entries == 0 {
+            0
+        } else {
+            (unflushed.bytes as u128 * entries.min(unflushed.entries) as u128
+                / unflushed.entries as u128) as u64
+        };
+
+        self.wal_unflushed_entries_gauge
+            .add(entries as i64 - unflushed.entries as i64);
+        self.wal_unflushed_bytes_gauge
+            .add(bytes as i64 - unflushed.bytes as i64);
+        // The write time of the remaining entries is unknown, so keep the old one
+        // and start counting from now for the replayed entries.
+        let first_write_time_ms = if entries == 0 {
+            None
+        } else {
+            unflushed
+                .first_write_time_ms
+                .or_else(|| Some(time_ext::current_time_millis()))
+        };
+        *unflushed = UnflushedWal {
+            entries,
+            bytes,
+            first_write_time_ms,
+        };
+    }
+
+    /// The wal entries not flushed yet.
+    pub fn unflushed_wal(&self) -> UnflushedWal {
+        *self.unflushed_wal.lock().unwrap()
+    }
+
     #[inline]
     pub fn on_read_request_begin(&self) {
         self.stats.num_read.fetch_add(1, Ordering::Relaxed);
+        self.stats
+            .recent_reads
+            .record(time_ext::current_time_millis());
         TABLE_READ_REQUEST_COUNTER.inc();
     }
 
@@ -23,27 +23,28 @@ use std::{
     sync::{Arc, Mutex},
 };
 
+use arc_swap::ArcSwap;
 use async_trait::async_trait;
 use common_types::{
     row::{Row, RowGroup},
     schema::Schema,
-    time::TimeRange,
+    time::{TimeRange, Timestamp},
 };
 use datafusion::{common::Column, logical_expr::Expr};
 use future_ext::CancellationSafeFuture;
 use futures::TryStreamExt;
 use generic_error::BoxError;
-use logger::{error, warn};
+use logger::{error, info, warn};
 use snafu::{ensure, OptionExt, ResultExt};
 use table_engine::{
     partition::PartitionInfo,
     predicate::PredicateBuilder,
     stream::{PartitionedStreams, SendableRecordBatchStream},
     table::{
-        AlterOptions, AlterSchema, AlterSchemaRequest, Compact, Flush, FlushRequest, Get,
-        GetInvalidPrimaryKey, GetNullPrimaryKey, GetRequest, MergeWrite, ReadOptions, ReadRequest,
-        Result, Scan, Table, TableId, TableStats, TooManyPendingWrites, WaitForPendingWrites,
-        Write, WriteRequest,
+        AlterOptions, AlterSchema, AlterSchemaRequest, CheckExistence, Compact, DeleteData,
+        ExistsRequest, Flush, FlushRequest, Get, GetInvalidPrimaryKey, GetNullPrimaryKey,
+        GetRequest, MergeWrite, ReadOptions, ReadRequest, Reopen, Result, Scan, Table, TableId,
+        TableStats, TooManyPendingWrites, WaitForPendingWrites, Write, WriteRequest,
     },
     ANALYTIC_ENGINE_TYPE,
 };
@@ -52,15 +53,17 @@ use trace_metric::MetricsCollector;
 
 use self::data::TableDataRef;
 use crate::{
-    instance::{alter::Alterer, write::Writer, InstanceRef},
-    space::{SpaceAndTable, SpaceRef},
+    instance::{alter::Alterer, write::Writer, write_epoch::WriteEpoch, InstanceRef},
+    space::{SpaceAndTable, SpaceId, SpaceRef},
 };
 
 pub mod data;
 pub mod metrics;
 pub mod sst_util;
+pub mod summary;
 pub mod version;
 pub mod version_edit;
+pub mod write_schema_cache;
 
 const GET_METRICS_COLLECTOR_NAME: &str = "get";
 // Additional 1/10 of the pending writes capacity is reserved for new pending
@@ -92,7 +95,10 @@ impl WriteRequests {
 
 /// Table trait implementation
 pub struct TableImpl {
-    space: SpaceRef,
+    /// Id of the table
+    id: TableId,
+    /// Name of the table
+    name: String,
     /// Instance
     instance: InstanceRef,
     /// Engine type
@@ -100,7 +106,10 @@ pub struct TableImpl {
 
     /// Holds a strong reference to prevent the underlying table from being
     /// dropped when this handle exist.
-    table_data: TableDataRef,
+    ///
+    /// It will be replaced by the reopened one if the table is closed by the
+    /// idle table reaper.
+    space_table: ArcSwap<SpaceAndTable>,
 
     /// Buffer for written rows.
     pending_writes: Arc<Mutex<PendingWriteQueue>>,
@@ -109,23 +118,51 @@ pub struct TableImpl {
 impl TableImpl {
     pub fn new(instance: InstanceRef, space_table: SpaceAndTable) -> Self {
         let pending_writes = Mutex::new(PendingWriteQueue::new(instance.max_rows_in_write_queue));
-        let table_data = space_table.table_data().clone();
-        let space = space_table.space().clone();
+        let table_data = space_table.table_data();
         Self {
-            space,
+            id: table_data.id,
+            name: table_data.name.clone(),
             instance,
             engine_type: ANALYTIC_ENGINE_TYPE.to_string(),
-            table_data,
+            space_table: ArcSwap::new(Arc::new(space_table)),
             pending_writes: Arc::new(pending_writes),
         }
     }
+
+    /// Get the current data of the table.
+    #[inline]
+    fn table_data(&self) -> TableDataRef {
+        self.space_table.load().table_data().clone()
+    }
+
+    /// Get the space and data of the table to read or write, and the table will
+    /// be reopened if it's closed by the idle table reaper.
+    async fn space_table_for_access(&self) -> Result<Arc<SpaceAndTable>> {
+        let space_table = self.space_table.load_full();
+        if !space_table.table_data().is_idle_closed() {
+            space_table.table_data().touch();
+            return Ok(space_table);
+        }
+
+        let reopened = self
+            .instance
+            .reopen_idle_table(space_table.space().id, self.id)
+            .await
+            .box_err()
+            .context(Reopen { table: self.name() })?;
+        let reopened = Arc::new(reopened);
+        reopened.table_data().touch();
+        self.space_table.store(reopened.clone());
+
+        Ok(reopened)
+    }
 }
 
 impl fmt::Debug for TableImpl {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("TableImpl")
-            .field("space_id", &self.space.id)
-            .field("table_id", &self.table_data.id)
+            .field("space_id", &self.space_table.load().space().id)
+            .field("table_id", &self.id)
             .finish()
     }
 }
@@ -142,6 +179,8 @@ struct PendingWrites {
     writes: Vec<WriteRequest>,
     notifiers: Vec<Sender<Result<()>>>,
     num_rows: usize,
+    /// The oldest epoch of the shard when the pending writes are accepted
+    write_epoch: Option<WriteEpoch>,
 }
 
 impl PendingWrites {
@@ -150,13 +189,14 @@ impl PendingWrites {
             writes: Vec::with_capacity(cap),
             notifiers: Vec::with_capacity(cap),
             num_rows: 0,
+            write_epoch: None,
         }
     }
 
     /// Try to push the request into the pending queue.
     ///
     /// This push will be rejected if the schema is different.
-    fn try_push(&mut self, request: WriteRequest) -> QueueResult {
+    fn try_push(&mut self, request: WriteRequest, write_epoch: Option<WriteEpoch>) -> QueueResult {
         if !self.is_same_schema(request.row_group.schema()) {
             return QueueResult::Reject(request);
         }
@@ -173,6 +213,11 @@ impl PendingWrites {
 
         self.num_rows += request.row_group.num_rows();
         self.writes.push(request);
+        // The merged write is refused if any of the pending writes is stale.
+        self.write_epoch = match (self.write_epoch, write_epoch) {
+            (Some(current), Some(epoch)) => Some(current.min(epoch)),
+            (current, epoch) => current.or(epoch),
+        };
 
         res
     }
@@ -222,12 +267,12 @@ impl PendingWriteQueue {
     /// If the queue is full or the schema is different, return the request
     /// back. Otherwise, return a receiver to let the caller wait for the write
     /// result.
-    fn try_push(&mut self, request: WriteRequest) -> QueueResult {
+    fn try_push(&mut self, request: WriteRequest, write_epoch: Option<WriteEpoch>) -> QueueResult {
         if self.is_full() {
             return QueueResult::Reject(request);
         }
 
-        self.pending_writes.try_push(request)
+        self.pending_writes.try_push(request, write_epoch)
     }
 
     #[inline]
@@ -279,30 +324,33 @@ impl TableImpl {
     /// writing all the writes in the queue.
     ///
     /// NOTE: The write request will be rejected if the queue is full.
-    async fn write_with_pending_queue(&self, request: WriteRequest) -> Result<usize> {
+    async fn write_with_pending_queue(
+        &self,
+        space_table: &SpaceAndTable,
+        request: WriteRequest,
+        write_epoch: Option<WriteEpoch>,
+    ) -> Result<usize> {
+        let table_data = space_table.table_data();
         let num_rows = request.row_group.num_rows();
 
         // Failed to acquire the serial_exec, put the request into the
         // pending queue.
         let queue_res = {
             let mut pending_queue = self.pending_writes.lock().unwrap();
-            pending_queue.try_push(request)
+            pending_queue.try_push(request, write_epoch)
         };
 
         match queue_res {
             QueueResult::First => {
-                let _timer = self
-                    .table_data
-                    .metrics
-                    .start_table_write_queue_writer_timer();
+                let _timer = table_data.metrics.start_table_write_queue_writer_timer();
 
                 // This is the first request in the queue, and we should
                 // take responsibilities for merging and writing the
                 // requests in the queue.
                 let write_requests = WriteRequests::new(
                     self.instance.clone(),
-                    self.space.clone(),
-                    self.table_data.clone(),
+                    space_table.space().clone(),
+                    table_data.clone(),
                     self.pending_writes.clone(),
                 );
 
@@ -320,10 +368,7 @@ impl TableImpl {
             QueueResult::Waiter(rx) => {
                 // The request is successfully pushed into the queue, and just wait for the
                 // write result.
-                let _timer = self
-                    .table_data
-                    .metrics
-                    .start_table_write_queue_waiter_timer();
+                let _timer = table_data.metrics.start_table_write_queue_waiter_timer();
 
                 // We have ever observed that `rx` is closed in production but it is impossible
                 // in theory(especially after warping actual write by
@@ -370,19 +415,32 @@ impl TableImpl {
         let merged_write_request =
             merge_pending_write_requests(pending_writes.writes, pending_writes.num_rows);
 
-        let mut writer = Writer::new(
-            write_requests.instance,
-            write_requests.space,
-            write_requests.table_data.clone(),
-            &mut serial_exec,
-        );
-        let write_res = writer
-            .write(merged_write_request)
+        let write_res = if write_requests.table_data.is_idle_closed() {
+            drop(serial_exec);
+            Self::write_reopened(
+                &write_requests.instance,
+                write_requests.space.id,
+                &write_requests.table_data,
+                merged_write_request,
+                pending_writes.write_epoch,
+            )
             .await
-            .box_err()
-            .context(Write {
-                table: write_requests.table_data.name.clone(),
-            });
+        } else {
+            let mut writer = Writer::new(
+                write_requests.instance,
+                write_requests.space,
+                write_requests.table_data.clone(),
+                &mut serial_exec,
+            )
+            .with_write_epoch(pending_writes.write_epoch);
+            writer
+                .write(merged_write_request)
+                .await
+                .box_err()
+                .context(Write {
+                    table: write_requests.table_data.name.clone(),
+                })
+        };
 
         // There is no waiter for pending writes, return the write result.
         let notifiers = pending_writes.notifiers;
@@ -419,6 +477,42 @@ impl TableImpl {
         }
     }
 
+    /// Reopen the table closed by the idle table reaper after it's accessed by
+    /// the write, and retry the write once.
+    async fn write_reopened(
+        instance: &InstanceRef,
+        space_id: SpaceId,
+        table_data: &TableDataRef,
+        request: WriteRequest,
+        write_epoch: Option<WriteEpoch>,
+    ) -> Result<usize> {
+        info!(
+            "Table is closed for idle before writing, reopen and retry, table:{}",
+            table_data.name
+        );
+        let space_table = instance
+            .reopen_idle_table(space_id, table_data.id)
+            .await
+            .box_err()
+            .context(Reopen {
+                table: &table_data.name,
+            })?;
+        let table_data = space_table.table_data();
+        table_data.touch();
+
+        let mut serial_exec = table_data.serial_exec.lock().await;
+        let mut writer = Writer::new(
+            instance.clone(),
+            space_table.space().clone(),
+            table_data.clone(),
+            &mut serial_exec,
+        )
+        .with_write_epoch(write_epoch);
+        writer.write(request).await.box_err().context(Write {
+            table: &table_data.name,
+        })
+    }
+
     #[inline]
     fn should_queue_write_request(&self, request: &WriteRequest) -> bool {
         request.row_group.num_rows() < self.instance.max_rows_in_write_queue
@@ -440,19 +534,19 @@ pub fn support_pushdown(schema: &Schema, need_dedup: bool, col_names: &[String])
 #[async_trait]
 impl Table for TableImpl {
     fn name(&self) -> &str {
-        &self.table_data.name
+        &self.name
     }
 
     fn id(&self) -> TableId {
-        self.table_data.id
+        self.id
     }
 
     fn schema(&self) -> Schema {
-        self.table_data.schema()
+        self.table_data().schema()
     }
 
     fn options(&self) -> HashMap<String, String> {
-        self.table_data.table_options().to_raw_map()
+        self.table_data().table_options().to_raw_map()
     }
 
     fn partition_info(&self) -> Option<PartitionInfo> {
@@ -464,29 +558,77 @@ impl Table for TableImpl {
     }
 
     fn stats(&self) -> TableStats {
-        self.table_data.metrics.table_stats()
+        let table_data = self.table_data();
+        let version = table_data.current_version();
+        let summary_config = &self.instance.table_summary;
+        let (num_rows, stats_staleness) = if summary_config.enable {
+            table_data.estimated_num_rows(
+                time_ext::current_time_millis(),
+                summary_config.half_life.0,
+                summary_config.min_confidence,
+            )
+        } else {
+            (None, None)
+        };
+
+        let memtable_size = version.total_memory_usage() as u64;
+        let sst_size = version.total_sst_size();
+
+        TableStats {
+            data_size: sst_size + memtable_size,
+            num_rows,
+            stats_staleness,
+            memtable_size,
+            sst_size,
+            num_ssts_per_level: version.num_ssts_per_level(),
+            time_range: version.time_range(),
+            num_series: Some(table_data.estimated_num_series()),
+            ..table_data.metrics.table_stats()
+        }
     }
 
     fn support_pushdown(&self, read_schema: &Schema, col_names: &[String]) -> bool {
-        let need_dedup = self.table_data.table_options().need_dedup();
+        let need_dedup = self.table_data().table_options().need_dedup();
 
         support_pushdown(read_schema, need_dedup, col_names)
     }
 
     async fn write(&self, request: WriteRequest) -> Result<usize> {
-        let _timer = self.table_data.metrics.start_table_total_timer();
+        let space_table = self.space_table_for_access().await?;
+        let table_data = space_table.table_data();
+        let _timer = table_data.metrics.start_table_total_timer();
 
+        // The epoch of the shard when the write is accepted.
+        let write_epoch = self
+            .instance
+            .shard_write_epochs
+            .current(table_data.shard_info.shard_id);
         if self.should_queue_write_request(&request) {
-            return self.write_with_pending_queue(request).await;
+            return self
+                .write_with_pending_queue(&space_table, request, write_epoch)
+                .await;
+        }
+
+        let mut serial_exec = table_data.serial_exec.lock().await;
+        if table_data.is_idle_closed() {
+            drop(serial_exec);
+            return Self::write_reopened(
+                &self.instance,
+                space_table.space().id,
+                table_data,
+                request,
+                write_epoch,
+            )
+            .await;
         }
 
-        let mut serial_exec = self.table_data.serial_exec.lock().await;
         let mut writer = Writer::new(
             self.instance.clone(),
-            self.space.clone(),
-            self.table_data.clone(),
+            space_table.space().clone(),
+            table_data.clone(),
             &mut serial_exec,
-        );
+        )
+        .with_write_epoch(write_epoch);
         writer
             .write(request)
             .await
@@ -496,9 +638,10 @@ impl Table for TableImpl {
 
     async fn read(&self, mut request: ReadRequest) -> Result<SendableRecordBatchStream> {
         request.opts.read_parallelism = 1;
+        let space_table = self.space_table_for_access().await?;
         let mut streams = self
             .instance
-            .partitioned_read_from_table(&self.table_data, request)
+            .partitioned_read_from_table(space_table.table_data(), request)
             .await
             .box_err()
             .context(Scan { table: self.name() })?;
@@ -588,9 +731,10 @@ impl Table for TableImpl {
     }
 
     async fn partitioned_read(&self, request: ReadRequest) -> Result<PartitionedStreams> {
+        let space_table = self.space_table_for_access().await?;
         let streams = self
             .instance
-            .partitioned_read_from_table(&self.table_data, request)
+            .partitioned_read_from_table(space_table.table_data(), request)
             .await
             .box_err()
             .context(Scan { table: self.name() })?;
@@ -599,13 +743,11 @@ impl Table for TableImpl {
     }
 
     async fn alter_schema(&self, request: AlterSchemaRequest) -> Result<usize> {
-        let mut serial_exec = self.table_data.serial_exec.lock().await;
-        let mut alterer = Alterer::new(
-            self.table_data.clone(),
-            &mut serial_exec,
-            self.instance.clone(),
-        )
-        .await;
+        let space_table = self.space_table_for_access().await?;
+        let table_data = space_table.table_data();
+        let mut serial_exec = table_data.serial_exec.lock().await;
+        let mut alterer =
+            Alterer::new(table_data.clone(), &mut serial_exec, self.instance.clone()).await;
 
         alterer
             .alter_schema_of_table(request)
@@ -616,13 +758,11 @@ impl Table for TableImpl {
     }
 
     async fn alter_options(&self, options: HashMap<String, String>) -> Result<usize> {
-        let mut serial_exec = self.table_data.serial_exec.lock().await;
-        let alterer = Alterer::new(
-            self.table_data.clone(),
-            &mut serial_exec,
-            self.instance.clone(),
-        )
-        .await;
+        let space_table = self.space_table_for_access().await?;
+        let table_data = space_table.table_data();
+        let mut serial_exec = table_data.serial_exec.lock().await;
+        let alterer =
+            Alterer::new(table_data.clone(), &mut serial_exec, self.instance.clone()).await;
 
         alterer
             .alter_options_of_table(options)
@@ -633,21 +773,41 @@ impl Table for TableImpl {
     }
 
     async fn flush(&self, request: FlushRequest) -> Result<()> {
+        let space_table = self.space_table_for_access().await?;
         self.instance
-            .manual_flush_table(&self.table_data, request)
+            .manual_flush_table(space_table.table_data(), request)
             .await
             .box_err()
             .context(Flush { table: self.name() })
     }
 
     async fn compact(&self) -> Result<()> {
+        let space_table = self.space_table_for_access().await?;
         self.instance
-            .manual_compact_table(&self.table_data)
+            .manual_compact_table(space_table.table_data())
             .await
             .box_err()
             .context(Compact { table: self.name() })?;
         Ok(())
     }
+
+    async fn delete_before(&self, before: Timestamp) -> Result<()> {
+        let space_table = self.space_table_for_access().await?;
+        self.instance
+            .manual_delete_table(space_table.table_data(), before)
+            .await
+            .box_err()
+            .context(DeleteData { table: self.name() })
+    }
+
+    async fn maybe_exists(&self, request: ExistsRequest) -> Result<bool> {
+        let space_table = self.space_table_for_access().await?;
+        self.instance
+            .maybe_exists_in_table(space_table.table_data(), request)
+            .await
+            .box_err()
+            .context(CheckExistence { table: self.name() })
+    }
 }
 
 #[cfg(test)]
@@ -688,17 +848,17 @@ mod tests {
     fn test_queue_write_requests() {
         let mut queue = PendingWriteQueue::new(100);
         let req0 = build_test_write_request(0, 99, 0);
-        let res0 = queue.try_push(req0);
+        let res0 = queue.try_push(req0, None);
         assert!(!queue.is_full());
         assert!(matches!(res0, QueueResult::First));
 
         let req1 = build_test_write_request(10, 10, 0);
-        let res1 = queue.try_push(req1);
+        let res1 = queue.try_push(req1, None);
         assert!(queue.is_full());
         assert!(matches!(res1, QueueResult::Waiter(_)));
 
         let req2 = build_test_write_request(20, 17, 0);
-        let res2 = queue.try_push(req2);
+        let res2 = queue.try_push(req2, None);
         assert!(queue.is_full());
         assert!(matches!(res2, QueueResult::Reject(_)));
         if let QueueResult::Reject(req) = res2 {
@@ -719,29 +879,43 @@ mod tests {
     fn test_queue_write_requests_with_different_schema() {
         let mut queue = PendingWriteQueue::new(100);
         let req0 = build_test_write_request(0, 10, 0);
-        let res0 = queue.try_push(req0);
+        let res0 = queue.try_push(req0, None);
         assert!(matches!(res0, QueueResult::First));
 
         let req1 = build_test_write_request(1, 10, 1);
-        let res1 = queue.try_push(req1);
+        let res1 = queue.try_push(req1, None);
         assert!(matches!(res1, QueueResult::Reject(_)));
     }
 
+    #[test]
+    fn test_queue_write_requests_with_epoch() {
+        let mut queue = PendingWriteQueue::new(100);
+        queue.try_push(build_test_write_request(0, 10, 0), None);
+        queue.try_push(build_test_write_request(10, 10, 0), Some(5));
+        queue.try_push(build_test_write_request(20, 10, 0), Some(3));
+        queue.try_push(build_test_write_request(30, 10, 0), Some(4));
+
+        // The oldest epoch is kept.
+        let pending_writes = queue.take_pending_writes();
+        assert_eq!(pending_writes.write_epoch, Some(3));
+        assert_eq!(queue.pending_writes.write_epoch, None);
+    }
+
     #[test]
     fn test_merge_pending_write_requests() {
         let mut queue = PendingWriteQueue::new(100);
         let mut total_requests = Vec::with_capacity(3);
         let req0 = build_test_write_request(0, 40, 0);
         total_requests.push(req0.clone());
-        queue.try_push(req0);
+        queue.try_push(req0, None);
 
         let req1 = build_test_write_request(10, 40, 0);
         total_requests.push(req1.clone());
-        queue.try_push(req1);
+        queue.try_push(req1, None);
 
         let req2 = build_test_write_request(10, 40, 0);
         total_requests.push(req2.clone());
-        queue.try_push(req2);
+        queue.try_push(req2, None);
 
         let pending_writes = queue.take_pending_writes();
         let mut merged_request =
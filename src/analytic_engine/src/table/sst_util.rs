@@ -45,3 +45,17 @@ pub fn new_sst_file_path(space_id: SpaceId, table_id: TableId, file_id: FileId)
 pub fn new_metadata_path(sst_file_path: &str) -> String {
     format!("{sst_file_path}.{SST_CUSTOM_METADATA_FILE_SUFFIX}")
 }
+
+/// Parse the file id from the name of the sst file or its custom metadata
+/// file, None if it's neither of them.
+pub fn parse_sst_file_id(file_name: &str) -> Option<FileId> {
+    let file_name = file_name
+        .strip_suffix(SST_CUSTOM_METADATA_FILE_SUFFIX)
+        .and_then(|v| v.strip_suffix('.'))
+        .unwrap_or(file_name);
+
+    file_name
+        .strip_suffix(SST_FILE_SUFFIX)
+        .and_then(|v| v.strip_suffix('.'))
+        .and_then(|v| v.parse().ok())
+}
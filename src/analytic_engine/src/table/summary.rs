@@ -0,0 +1,175 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Summary of the ssts of a table, used as the estimated statistics of the
+//! table by the query optimizer.
+//!
+//! The summary is refreshed incrementally: only the stats of the ssts added or
+//! removed since the last refresh are merged. The confidence of the summary
+//! decays by its age, and the summary is too stale to be trusted once the
+//! confidence drops below the threshold.
+
+use std::{collections::HashMap, time::Duration};
+
+use crate::sst::{file::FileMeta, manager::FileId};
+
+/// Stats of a single sst merged into the summary.
+#[derive(Debug, Clone, Copy)]
+struct SstStats {
+    row_num: u64,
+    size: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct TableSummary {
+    ssts: HashMap<FileId, SstStats>,
+    num_rows: u64,
+    size: u64,
+    /// The time when the summary is refreshed in millis, 0 if it's never
+    /// refreshed
+    refreshed_at: u64,
+}
+
+impl TableSummary {
+    /// Merge the stats of the current `ssts` of the table at `now`, returns
+    /// the number of the ssts added or removed.
+    pub fn refresh<'a>(&mut self, ssts: impl Iterator<Item = &'a FileMeta>, now: u64) -> usize {
+        let mut current = HashMap::with_capacity(self.ssts.len());
+        let mut changed = 0;
+        for sst in ssts {
+            let stats = match self.ssts.remove(&sst.id) {
+                Some(stats) => stats,
+                None => {
+                    changed += 1;
+                    self.num_rows += sst.row_num;
+                    self.size += sst.size;
+                    SstStats {
+                        row_num: sst.row_num,
+                        size: sst.size,
+                    }
+                }
+            };
+            current.insert(sst.id, stats);
+        }
+
+        // The remaining ssts have been removed by compaction or expiration.
+        for stats in self.ssts.values() {
+            changed += 1;
+            self.num_rows -= stats.row_num;
+            self.size -= stats.size;
+        }
+
+        self.ssts = current;
+        self.refreshed_at = now;
+
+        changed
+    }
+
+    #[inline]
+    pub fn num_rows(&self) -> u64 {
+        self.num_rows
+    }
+
+    #[inline]
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    #[inline]
+    pub fn num_ssts(&self) -> usize {
+        self.ssts.len()
+    }
+
+    /// The time when the summary is refreshed in millis, 0 if it's never
+    /// refreshed.
+    #[inline]
+    pub fn refreshed_at(&self) -> u64 {
+        self.refreshed_at
+    }
+
+    /// Time elapsed since the last refresh, None if it's never refreshed.
+    pub fn staleness(&self, now: u64) -> Option<Duration> {
+        (self.refreshed_at > 0)
+            .then(|| Duration::from_millis(now.saturating_sub(self.refreshed_at)))
+    }
+
+    /// The confidence in (0, 1] of the summary, which halves every `half_life`
+    /// since the last refresh, and 0 if it's never refreshed.
+    pub fn confidence(&self, now: u64, half_life: Duration) -> f64 {
+        match self.staleness(now) {
+            Some(_) if half_life.is_zero() => 1.0,
+            Some(staleness) => 0.5f64.powf(staleness.as_secs_f64() / half_life.as_secs_f64()),
+            None => 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common_types::time::TimeRange;
+
+    use super::*;
+    use crate::table_options::StorageFormat;
+
+    fn new_sst(id: FileId, row_num: u64, size: u64) -> FileMeta {
+        FileMeta {
+            id,
+            size,
+            row_num,
+            time_range: TimeRange::empty(),
+            max_seq: 0,
+            storage_format: StorageFormat::default(),
+            associated_files: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_refresh_incrementally() {
+        let mut summary = TableSummary::default();
+        assert!(summary.staleness(100).is_none());
+        assert_eq!(summary.confidence(100, Duration::from_secs(1)), 0.0);
+
+        let ssts = vec![new_sst(1, 10, 100), new_sst(2, 20, 200)];
+        assert_eq!(summary.refresh(ssts.iter(), 1000), 2);
+        assert_eq!(summary.num_rows(), 30);
+        assert_eq!(summary.size(), 300);
+        assert_eq!(summary.refreshed_at(), 1000);
+
+        // Sst 1 and 2 are compacted into sst 3.
+        let ssts = vec![new_sst(3, 25, 250)];
+        assert_eq!(summary.refresh(ssts.iter(), 2000), 3);
+        assert_eq!(summary.num_rows(), 25);
+        assert_eq!(summary.size(), 250);
+        assert_eq!(summary.num_ssts(), 1);
+
+        // Nothing changed.
+        assert_eq!(summary.refresh(ssts.iter(), 3000), 0);
+        assert_eq!(summary.num_rows(), 25);
+        assert_eq!(summary.staleness(5000), Some(Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn test_confidence_decay() {
+        let mut summary = TableSummary::default();
+        summary.refresh(std::iter::empty(), 1000);
+
+        let half_life = Duration::from_secs(10);
+        assert_eq!(summary.confidence(1000, half_life), 1.0);
+        assert!((summary.confidence(11_000, half_life) - 0.5).abs() < 1e-9);
+        assert!((summary.confidence(21_000, half_life) - 0.25).abs() < 1e-9);
+    }
+}
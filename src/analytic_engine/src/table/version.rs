@@ -29,7 +29,7 @@ use std::{
 use common_types::{
     row::Row,
     schema::{self, Schema},
-    time::{TimeRange, Timestamp},
+    time::{TimeRange, Timestamp, TimestampPrecision},
     SequenceNumber,
 };
 use macros::define_result;
@@ -40,12 +40,12 @@ use time_ext::ReadableDuration;
 use crate::{
     compaction::{
         picker::{self, CompactionPickerRef, PickerContext},
-        CompactionTask, ExpiredFiles,
+        CompactionTask, CompactionTaskBuilder, ExpiredFiles,
     },
     memtable::{self, key::KeySequence, MemTableRef, PutContext},
     sampler::{DefaultSampler, PrimaryKeySampler, SamplerRef, MAX_SUGGEST_PRIMARY_KEY_NUM},
     sst::{
-        file::{FileHandle, FilePurgeQueue, SST_LEVEL_NUM},
+        file::{FileHandle, FileMeta, FilePurgeQueue, SST_LEVEL_NUM},
         manager::{FileId, LevelsController},
     },
     table::{
@@ -91,12 +91,16 @@ pub struct SamplingMemTable {
 }
 
 impl SamplingMemTable {
-    pub fn new(memtable: MemTableRef, id: MemTableId) -> Self {
+    pub fn new(
+        memtable: MemTableRef,
+        id: MemTableId,
+        timestamp_precision: TimestampPrecision,
+    ) -> Self {
         SamplingMemTable {
             mem: memtable,
             id,
             freezed: false,
-            sampler: Arc::new(DefaultSampler::default()),
+            sampler: Arc::new(DefaultSampler::new(timestamp_precision)),
             pk_sampler: None,
         }
     }
@@ -331,6 +335,22 @@ impl MemTableView {
         mutable_usage + immutable_usage
     }
 
+    /// Get the time range covered by all the memtables.
+    fn time_range(&self) -> Option<TimeRange> {
+        self.sampling_mem
+            .as_ref()
+            .and_then(|v| v.mem.time_range())
+            .into_iter()
+            .chain(
+                self.mutables
+                    .0
+                    .values()
+                    .chain(self.immutables.0.values())
+                    .filter_map(|v| v.mem.time_range()),
+            )
+            .reduce(|a, b| a.merge_range(b))
+    }
+
     /// Instead of replace the old memtable by a new memtable, we just move the
     /// old memtable to immutable memtables and left mutable memtables
     /// empty. New mutable memtable will be constructed via put request.
@@ -575,6 +595,51 @@ impl ReadView {
     pub fn contains_sampling(&self) -> bool {
         self.sampling_mem.is_some()
     }
+
+    /// Time range covering all the rows in the memtables and ssts.
+    ///
+    /// The aligned time range is used for the memtables as they may be still
+    /// written, and the whole time range is returned if the sampling memtable
+    /// exists as its rows may spread over any time.
+    pub fn time_range(&self) -> TimeRange {
+        if self.contains_sampling() {
+            return TimeRange::min_to_max();
+        }
+
+        self.memtables
+            .iter()
+            .map(|mem| mem.aligned_time_range)
+            .chain(self.leveled_ssts.iter().flatten().map(|f| f.time_range()))
+            .reduce(|a, b| a.merge_range(b))
+            .unwrap_or_else(TimeRange::empty)
+    }
+
+    /// Build a new read view which only contains the memtables and ssts
+    /// intersecting with the given `time_range`.
+    pub fn filter_by_time_range(&self, time_range: TimeRange) -> ReadView {
+        let memtables = self
+            .memtables
+            .iter()
+            .filter(|mem| mem.real_time_range().intersect_with(time_range))
+            .cloned()
+            .collect();
+        let leveled_ssts = self
+            .leveled_ssts
+            .iter()
+            .map(|ssts| {
+                ssts.iter()
+                    .filter(|file| file.intersect_with_time_range(time_range))
+                    .cloned()
+                    .collect()
+            })
+            .collect();
+
+        ReadView {
+            sampling_mem: self.sampling_mem.clone(),
+            memtables,
+            leveled_ssts,
+        }
+    }
 }
 
 /// Data of TableVersion
@@ -593,6 +658,9 @@ struct TableVersionInner {
     /// than the max one. And this field is only a mem state for Manifest,
     /// it can only be updated during recover or by Manifest.
     max_file_id: FileId,
+    /// The rows whose timestamp is before it are deleted and filtered out by
+    /// the reads and compactions.
+    delete_before: Option<Timestamp>,
 }
 
 impl TableVersionInner {
@@ -634,6 +702,7 @@ impl TableVersion {
                 levels_controller: LevelsController::new(purge_queue),
                 flushed_sequence: 0,
                 max_file_id: 0,
+                delete_before: None,
             }),
 
             cached_mem_size: SamplingCachedUsize::new(mem_usage_sampling_interval.as_millis()),
@@ -767,6 +836,8 @@ impl TableVersion {
 
         inner.max_file_id = cmp::max(inner.max_file_id, edit.max_file_id);
 
+        inner.delete_before = cmp::max(inner.delete_before, edit.delete_before);
+
         // Add sst files to level first.
         for add_file in edit.files_to_add {
             inner
@@ -795,6 +866,8 @@ impl TableVersion {
 
         inner.max_file_id = cmp::max(inner.max_file_id, meta.max_file_id);
 
+        inner.delete_before = cmp::max(inner.delete_before, meta.delete_before);
+
         for add_file in meta.files.into_values() {
             inner
                 .levels_controller
@@ -853,12 +926,135 @@ impl TableVersion {
         inner.levels_controller.expired_ssts(expire_time)
     }
 
+    /// Pick the ssts lying entirely before `before` for deletion.
+    ///
+    /// The ssts crossing `before` and the ones being compacted are kept, whose
+    /// deleted rows are filtered out by the reads and dropped by the later
+    /// compactions according to the delete bound.
+    pub fn pick_for_delete(&self, before: Timestamp) -> CompactionTask {
+        // Hold the write lock so the picked files won't be picked by the
+        // compaction concurrently before they are marked.
+        let inner = self.inner.write().unwrap();
+        let controller = &inner.levels_controller;
+
+        let expired = controller
+            .levels()
+            .filter_map(|level| {
+                let files: Vec<_> = controller
+                    .iter_ssts_at_level(level)
+                    .filter(|file| {
+                        file.time_range().exclusive_end() <= before && !file.being_compacted()
+                    })
+                    .cloned()
+                    .collect();
+                (!files.is_empty()).then_some(ExpiredFiles { level, files })
+            })
+            .collect();
+
+        let mut builder = CompactionTaskBuilder::with_expired(expired);
+        builder.delete_before(before);
+
+        builder.build()
+    }
+
+    /// The bound of the deleted rows, see [VersionEdit::delete_before].
+    pub fn delete_before(&self) -> Option<Timestamp> {
+        let inner = self.inner.read().unwrap();
+
+        inner.delete_before
+    }
+
     pub fn flushed_sequence(&self) -> SequenceNumber {
         let inner = self.inner.read().unwrap();
 
         inner.flushed_sequence
     }
 
+    /// Total size of the ssts in bytes.
+    pub fn total_sst_size(&self) -> u64 {
+        let inner = self.inner.read().unwrap();
+        let controller = &inner.levels_controller;
+        controller
+            .levels()
+            .flat_map(|level| controller.iter_ssts_at_level(level))
+            .map(|file| file.size())
+            .sum()
+    }
+
+    /// Number of the ssts in each level, indexed by the level.
+    pub fn num_ssts_per_level(&self) -> Vec<usize> {
+        let inner = self.inner.read().unwrap();
+        let controller = &inner.levels_controller;
+        controller
+            .levels()
+            .map(|level| controller.iter_ssts_at_level(level).count())
+            .collect()
+    }
+
+    /// Whether the `files` contain all the ssts overlapping with them, that
+    /// is, no other version of the rows in them lives in any other sst.
+    ///
+    /// The ssts carry no key range, so the time range is used to tell the
+    /// overlapping ones.
+    pub fn contains_all_overlapping_ssts(&self, files: &[FileHandle]) -> bool {
+        let time_ranges: Vec<_> = files.iter().map(|file| file.time_range()).collect();
+        self.contains_all_ssts_overlapping_with(files, &time_ranges)
+    }
+
+    /// Like [TableVersion::contains_all_overlapping_ssts], but the time
+    /// ranges of the `files` are widened to the boundaries of the intervals of
+    /// `interval_ms`, so no row of the intervals touched by the `files` lives
+    /// in any other sst.
+    pub fn contains_all_ssts_of_intervals(&self, files: &[FileHandle], interval_ms: i64) -> bool {
+        let time_ranges: Vec<_> = files
+            .iter()
+            .map(|file| align_time_range(file.time_range(), interval_ms))
+            .collect();
+        self.contains_all_ssts_overlapping_with(files, &time_ranges)
+    }
+
+    fn contains_all_ssts_overlapping_with(
+        &self,
+        files: &[FileHandle],
+        time_ranges: &[TimeRange],
+    ) -> bool {
+        let inner = self.inner.read().unwrap();
+        let controller = &inner.levels_controller;
+        controller
+            .levels()
+            .flat_map(|level| controller.iter_ssts_at_level(level))
+            .filter(|sst| files.iter().all(|file| file.id() != sst.id()))
+            .all(|sst| {
+                time_ranges
+                    .iter()
+                    .all(|time_range| !time_range.intersect_with(sst.time_range()))
+            })
+    }
+
+    /// Time range covering all the data in the memtables and ssts, None if
+    /// there is no data.
+    pub fn time_range(&self) -> Option<TimeRange> {
+        let inner = self.inner.read().unwrap();
+        let controller = &inner.levels_controller;
+        controller
+            .levels()
+            .flat_map(|level| controller.iter_ssts_at_level(level))
+            .map(|file| file.time_range())
+            .chain(inner.memtable_view.time_range())
+            .reduce(|a, b| a.merge_range(b))
+    }
+
+    /// Metas of all the ssts.
+    pub fn sst_metas(&self) -> Vec<FileMeta> {
+        let inner = self.inner.read().unwrap();
+        let controller = &inner.levels_controller;
+        controller
+            .levels()
+            .flat_map(|level| controller.iter_ssts_at_level(level))
+            .map(|file| file.meta())
+            .collect()
+    }
+
     pub fn snapshot(&self) -> TableVersionSnapshot {
         let inner = self.inner.read().unwrap();
         let controller = &inner.levels_controller;
@@ -880,6 +1076,7 @@ impl TableVersion {
             flushed_sequence: inner.flushed_sequence,
             files,
             max_file_id: inner.max_file_id,
+            delete_before: inner.delete_before,
         }
     }
 }
@@ -888,6 +1085,7 @@ pub struct TableVersionSnapshot {
     pub flushed_sequence: SequenceNumber,
     pub files: HashMap<FileId, AddFile>,
     pub max_file_id: FileId,
+    pub delete_before: Option<Timestamp>,
 }
 
 /// During recovery, we apply all version edit to [TableVersionMeta] first, then
@@ -898,6 +1096,7 @@ pub struct TableVersionMeta {
     pub flushed_sequence: SequenceNumber,
     pub files: HashMap<FileId, AddFile>,
     pub max_file_id: FileId,
+    pub delete_before: Option<Timestamp>,
 }
 
 impl TableVersionMeta {
@@ -919,6 +1118,8 @@ impl TableVersionMeta {
         for delete_file in edit.files_to_delete {
             self.files.remove(&delete_file.file_id);
         }
+
+        self.delete_before = cmp::max(self.delete_before, edit.delete_before);
     }
 
     /// Returns the max file id in the files to add.
@@ -934,6 +1135,22 @@ impl TableVersionMeta {
     }
 }
 
+/// Widen the `time_range` to the boundaries of the intervals of
+/// `interval_ms`, the range is unchanged if the boundaries overflow.
+fn align_time_range(time_range: TimeRange, interval_ms: i64) -> TimeRange {
+    let start = time_range
+        .inclusive_start()
+        .checked_floor_by_i64(interval_ms);
+    let end = time_range
+        .exclusive_end()
+        .checked_add_i64(interval_ms - 1)
+        .and_then(|end| end.checked_floor_by_i64(interval_ms));
+    match (start, end) {
+        (Some(start), Some(end)) => TimeRange::new(start, end).unwrap_or(time_range),
+        _ => time_range,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1017,7 +1234,8 @@ mod tests {
         let schema = memtable.schema().clone();
 
         let memtable_id = 1;
-        let sampling_mem = SamplingMemTable::new(memtable, memtable_id);
+        let sampling_mem =
+            SamplingMemTable::new(memtable, memtable_id, TimestampPrecision::Millisecond);
 
         version.set_sampling(sampling_mem);
 
@@ -1062,7 +1280,8 @@ mod tests {
 
         let memtable_id = 1;
         let last_sequence = 1000;
-        let sampling_mem = SamplingMemTable::new(memtable, memtable_id);
+        let sampling_mem =
+            SamplingMemTable::new(memtable, memtable_id, TimestampPrecision::Millisecond);
 
         version.set_sampling(sampling_mem);
 
@@ -1106,7 +1325,8 @@ mod tests {
 
         let memtable_id1 = 1;
         let last_sequence = 1000;
-        let sampling_mem = SamplingMemTable::new(memtable, memtable_id1);
+        let sampling_mem =
+            SamplingMemTable::new(memtable, memtable_id1, TimestampPrecision::Millisecond);
 
         version.set_sampling(sampling_mem);
         assert_eq!(
@@ -1187,7 +1407,8 @@ mod tests {
         let memtable = MemTableMocker.build();
 
         let memtable_id1 = 1;
-        let sampling_mem = SamplingMemTable::new(memtable, memtable_id1);
+        let sampling_mem =
+            SamplingMemTable::new(memtable, memtable_id1, TimestampPrecision::Millisecond);
 
         // Prepare sampling memtable.
         version.set_sampling(sampling_mem);
@@ -1223,6 +1444,7 @@ mod tests {
             files_to_add: vec![add_file],
             files_to_delete: vec![],
             max_file_id: 0,
+            delete_before: None,
         };
         version.apply_edit(edit);
 
@@ -1234,4 +1456,155 @@ mod tests {
         assert_eq!(1, read_view.leveled_ssts[0].len());
         assert_eq!(file_id, read_view.leveled_ssts[0][0].id());
     }
+
+    #[test]
+    fn test_table_version_pick_for_delete() {
+        let version = new_table_version();
+
+        let files_to_add = [(1, 0, 100), (2, 100, 200), (3, 200, 300)]
+            .into_iter()
+            .map(|(file_id, start, end)| {
+                AddFileMocker::new(file_id)
+                    .time_range(TimeRange::new_unchecked_for_test(start, end))
+                    .build()
+            })
+            .collect();
+        let edit = VersionEdit {
+            flushed_sequence: 0,
+            mems_to_remove: vec![],
+            files_to_add,
+            files_to_delete: vec![],
+            max_file_id: 0,
+            delete_before: None,
+        };
+        version.apply_edit(edit);
+
+        let expired = |task: &CompactionTask| {
+            task.expired()
+                .iter()
+                .flat_map(|v| &v.files)
+                .map(|f| f.id())
+                .collect::<Vec<_>>()
+        };
+
+        // Only the sst lying entirely before the bound is dropped.
+        let task = version.pick_for_delete(Timestamp::new(150));
+        assert_eq!(Some(Timestamp::new(150)), task.delete_before());
+        assert!(task.inputs().is_empty());
+        assert_eq!(vec![1], expired(&task));
+
+        // The ssts being compacted are skipped until the task is dropped.
+        assert_eq!(
+            vec![2],
+            expired(&version.pick_for_delete(Timestamp::new(250)))
+        );
+        drop(task);
+        assert_eq!(
+            vec![1, 2],
+            expired(&version.pick_for_delete(Timestamp::new(250)))
+        );
+
+        // The bound is kept by the version once the edit is applied.
+        assert_eq!(None, version.delete_before());
+        version.apply_edit(VersionEdit {
+            flushed_sequence: 0,
+            mems_to_remove: vec![],
+            files_to_add: vec![],
+            files_to_delete: vec![],
+            max_file_id: 0,
+            delete_before: Some(Timestamp::new(150)),
+        });
+        version.apply_edit(VersionEdit {
+            flushed_sequence: 0,
+            mems_to_remove: vec![],
+            files_to_add: vec![],
+            files_to_delete: vec![],
+            max_file_id: 0,
+            delete_before: Some(Timestamp::new(100)),
+        });
+        assert_eq!(Some(Timestamp::new(150)), version.delete_before());
+    }
+
+    #[test]
+    fn test_read_view_filter_by_time_range() {
+        let version = new_table_version();
+
+        let files_to_add = [(1, 0, 100), (2, 100, 200), (3, 200, 300)]
+            .into_iter()
+            .map(|(file_id, start, end)| {
+                AddFileMocker::new(file_id)
+                    .time_range(TimeRange::new_unchecked_for_test(start, end))
+                    .build()
+            })
+            .collect();
+        let edit = VersionEdit {
+            flushed_sequence: 0,
+            mems_to_remove: vec![],
+            files_to_add,
+            files_to_delete: vec![],
+            max_file_id: 0,
+            delete_before: None,
+        };
+        version.apply_edit(edit);
+
+        let read_view = version.pick_read_view(TimeRange::min_to_max());
+        let file_ids = |view: &ReadView| {
+            view.leveled_ssts
+                .iter()
+                .flatten()
+                .map(|f| f.id())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(vec![1, 2, 3], file_ids(&read_view));
+
+        let filtered = read_view.filter_by_time_range(TimeRange::new_unchecked_for_test(50, 150));
+        assert_eq!(read_view.leveled_ssts.len(), filtered.leveled_ssts.len());
+        assert_eq!(vec![1, 2], file_ids(&filtered));
+
+        let filtered = read_view.filter_by_time_range(TimeRange::new_unchecked_for_test(300, 400));
+        assert!(file_ids(&filtered).is_empty());
+
+        // The filtered view is a copy, the pinned view is untouched.
+        assert_eq!(vec![1, 2, 3], file_ids(&read_view));
+    }
+
+    #[test]
+    fn test_contains_all_overlapping_ssts() {
+        let version = new_table_version();
+
+        let files_to_add = [(1, 0, 100), (2, 50, 150), (3, 200, 300), (4, 350, 400)]
+            .into_iter()
+            .map(|(file_id, start, end)| {
+                AddFileMocker::new(file_id)
+                    .time_range(TimeRange::new_unchecked_for_test(start, end))
+                    .build()
+            })
+            .collect();
+        let edit = VersionEdit {
+            flushed_sequence: 0,
+            mems_to_remove: vec![],
+            files_to_add,
+            files_to_delete: vec![],
+            max_file_id: 0,
+            delete_before: None,
+        };
+        version.apply_edit(edit);
+
+        let pick = |file_ids: &[FileId]| {
+            let read_view = version.pick_read_view(TimeRange::min_to_max());
+            read_view
+                .leveled_ssts
+                .into_iter()
+                .flatten()
+                .filter(|f| file_ids.contains(&f.id()))
+                .collect::<Vec<_>>()
+        };
+        assert!(!version.contains_all_overlapping_ssts(&pick(&[2])));
+        assert!(version.contains_all_overlapping_ssts(&pick(&[1, 2])));
+        assert!(version.contains_all_overlapping_ssts(&pick(&[3])));
+
+        // The sst 3 shares the interval [200, 400) with the sst 4.
+        assert!(!version.contains_all_ssts_of_intervals(&pick(&[3]), 200));
+        assert!(version.contains_all_ssts_of_intervals(&pick(&[3]), 100));
+    }
 }
@@ -19,7 +19,10 @@
 
 use std::convert::TryFrom;
 
-use common_types::{time::TimeRange, SequenceNumber};
+use common_types::{
+    time::{TimeRange, Timestamp},
+    SequenceNumber,
+};
 use horaedbproto::manifest as manifest_pb;
 use macros::define_result;
 use snafu::{Backtrace, OptionExt, ResultExt, Snafu};
@@ -158,6 +161,9 @@ pub struct VersionEdit {
     /// Sst files to delete.
     pub files_to_delete: Vec<DeleteFile>,
     pub max_file_id: FileId,
+    /// The rows whose timestamp is before it are deleted, the bound of the
+    /// table only grows.
+    pub delete_before: Option<Timestamp>,
 }
 
 #[cfg(test)]
@@ -0,0 +1,125 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Cache of the schema compatibility checks of the writes.
+
+use std::{
+    hash::{BuildHasher, Hash, Hasher},
+    sync::Mutex,
+};
+
+use common_types::schema::{CompatError, IndexInWriterSchema, Schema, Version};
+use hash_ext::build_fixed_seed_ahasher_builder;
+use lru::LruCache;
+
+/// Max number of the writer schemas cached for a table.
+const DEFAULT_CAPACITY: usize = 16;
+
+/// Caches the [IndexInWriterSchema] of the compatible writer schemas, keyed by
+/// the version of the table schema and the hash of the writer schema.
+///
+/// The key changes with the version of the table schema after altering, so
+/// the stale entries are never hit.
+pub struct WriteSchemaCache {
+    cache: Mutex<LruCache<(Version, u64), IndexInWriterSchema>>,
+}
+
+impl Default for WriteSchemaCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl WriteSchemaCache {
+    pub fn new(cap: usize) -> Self {
+        Self {
+            cache: Mutex::new(LruCache::new(cap)),
+        }
+    }
+
+    /// Same as [Schema::compatible_for_write], but the result is reused for
+    /// the writer schemas checked before.
+    pub fn compatible_for_write(
+        &self,
+        table_schema: &Schema,
+        writer_schema: &Schema,
+    ) -> std::result::Result<IndexInWriterSchema, CompatError> {
+        let key = (table_schema.version(), hash_writer_schema(writer_schema));
+        if let Some(index_in_writer) = self.cache.lock().unwrap().get(&key) {
+            return Ok(index_in_writer.clone());
+        }
+
+        let mut index_in_writer = IndexInWriterSchema::default();
+        table_schema.compatible_for_write(writer_schema, &mut index_in_writer)?;
+        self.cache.lock().unwrap().put(key, index_in_writer.clone());
+
+        Ok(index_in_writer)
+    }
+
+    /// Remove all the cached entries.
+    pub fn clear(&self) {
+        self.cache.lock().unwrap().clear();
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.cache.lock().unwrap().len()
+    }
+}
+
+/// Hash what the compatibility check depends on, that's the name, type and
+/// nullability of the columns in order.
+fn hash_writer_schema(schema: &Schema) -> u64 {
+    let mut hasher = build_fixed_seed_ahasher_builder().build_hasher();
+    for column in schema.columns() {
+        column.name.hash(&mut hasher);
+        column.data_type.into_u8().hash(&mut hasher);
+        column.is_nullable.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use common_types::tests::{build_schema, build_schema_with_dictionary};
+
+    use super::*;
+
+    #[test]
+    fn test_write_schema_cache() {
+        let cache = WriteSchemaCache::default();
+        let schema = build_schema();
+
+        let index_in_writer = cache.compatible_for_write(&schema, &schema).unwrap();
+        for i in 0..schema.num_columns() {
+            assert_eq!(Some(i), index_in_writer.column_index_in_writer(i));
+        }
+        assert_eq!(1, cache.len());
+
+        // The second check hits the cache.
+        cache.compatible_for_write(&schema, &schema).unwrap();
+        assert_eq!(1, cache.len());
+
+        // The incompatible writer schemas are not cached.
+        let table_schema = build_schema_with_dictionary();
+        assert!(cache.compatible_for_write(&table_schema, &schema).is_err());
+        assert_eq!(1, cache.len());
+
+        cache.clear();
+        assert_eq!(0, cache.len());
+    }
+}
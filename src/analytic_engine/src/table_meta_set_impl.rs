@@ -20,7 +20,6 @@
 use std::{fmt, num::NonZeroUsize, sync::Arc};
 
 use anyhow::Context;
-use id_allocator::IdAllocator;
 use logger::debug;
 use table_engine::table::TableId;
 
@@ -38,7 +37,7 @@ use crate::{
     table::{
         data::{
             MemSizeOptions, TableCatalogInfo, TableConfig, TableData, TableDataRef, TableDesc,
-            TableShardInfo, DEFAULT_ALLOC_STEP,
+            TableShardInfo,
         },
         version::{TableVersionMeta, TableVersionSnapshot},
         version_edit::VersionEdit,
@@ -250,14 +249,11 @@ impl TableMetaSetImpl {
             .get_by_id(space_id)
             .ok_or_else(|| anyhow::anyhow!("space not found, space_id:{space_id}"))?;
 
-        // Apply max file id to the allocator
-        let allocator = match version_meta.clone() {
-            Some(version_meta) => {
-                let max_file_id = version_meta.max_file_id_to_add();
-                IdAllocator::new(max_file_id, max_file_id, DEFAULT_ALLOC_STEP)
-            }
-            None => IdAllocator::new(0, 0, DEFAULT_ALLOC_STEP),
-        };
+        // The max file id to apply to the allocator.
+        let max_file_id = version_meta
+            .as_ref()
+            .map(|v| v.max_file_id_to_add())
+            .unwrap_or(0);
 
         let table_name = table_meta.table_name.clone();
         let mem_size_options = MemSizeOptions {
@@ -277,7 +273,7 @@ impl TableMetaSetImpl {
                     try_compat_old_layered_memtable_opts: self.try_compat_old_layered_memtable_opts,
                 },
                 mem_size_options,
-                allocator,
+                max_file_id,
                 table_catalog_info,
             )
             .context(format!(
@@ -343,11 +339,13 @@ impl TableMetaSet for TableMetaSetImpl {
                 flushed_sequence,
                 files,
                 max_file_id,
+                delete_before,
             } = version_snapshot;
             let version_meta = TableVersionMeta {
                 flushed_sequence,
                 files,
                 max_file_id,
+                delete_before,
             };
 
             Some(MetaSnapshot {
@@ -17,19 +17,29 @@
 
 //! Constants for table options.
 
-use std::{collections::HashMap, str::FromStr, string::ToString, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+    string::ToString,
+    time::Duration,
+};
 
 use common_types::{
-    time::Timestamp, ARENA_BLOCK_SIZE, COMPACTION_STRATEGY, COMPRESSION, ENABLE_TTL,
-    LAYERED_ENABLE, LAYERED_MUTABLE_SWITCH_THRESHOLD, MEMTABLE_TYPE, NUM_ROWS_PER_ROW_GROUP,
-    OPTION_KEY_ENABLE_TTL, SEGMENT_DURATION, STORAGE_FORMAT, TTL, UPDATE_MODE, WRITE_BUFFER_SIZE,
+    datum::DatumKind,
+    schema::Schema,
+    time::{Timestamp, TimestampPrecision},
+    ARENA_BLOCK_SIZE, COLUMN_TTL, COMPACTION_STRATEGY, COMPRESSION, DOWNSAMPLE_AFTER,
+    DOWNSAMPLE_AGGREGATOR, DOWNSAMPLE_INTERVAL, DOWNSAMPLE_RESOLUTION_COLUMN, ENABLE_TTL,
+    EXPIRE_AT_COLUMN, FIELD_GROUPS, LAYERED_ENABLE, LAYERED_MUTABLE_SWITCH_THRESHOLD,
+    MEMTABLE_TYPE, NUM_ROWS_PER_ROW_GROUP, OPTION_KEY_ENABLE_TTL, SEGMENT_DURATION, STORAGE_FORMAT,
+    TIMESTAMP_PRECISION, TTL, UPDATE_MODE, WRITE_BUFFER_SIZE,
 };
 use datafusion::parquet::basic::Compression as ParquetCompression;
 use horaedbproto::manifest as manifest_pb;
 use macros::define_result;
 use serde::{Deserialize, Serialize};
 use size_ext::ReadableSize;
-use snafu::{Backtrace, GenerateBacktrace, OptionExt, ResultExt, Snafu};
+use snafu::{ensure, Backtrace, GenerateBacktrace, OptionExt, ResultExt, Snafu};
 use time_ext::{parse_duration, DurationExt, ReadableDuration, TimeUnit};
 
 use crate::{
@@ -47,6 +57,10 @@ const COMPRESSION_SNAPPY: &str = "SNAPPY";
 const COMPRESSION_ZSTD: &str = "ZSTD";
 const STORAGE_FORMAT_AUTO: &str = "AUTO";
 const STORAGE_FORMAT_COLUMNAR: &str = "COLUMNAR";
+const DOWNSAMPLE_AGGREGATOR_AVG: &str = "AVG";
+const DOWNSAMPLE_AGGREGATOR_LAST: &str = "LAST";
+const DOWNSAMPLE_AGGREGATOR_MIN: &str = "MIN";
+const DOWNSAMPLE_AGGREGATOR_MAX: &str = "MAX";
 
 /// Default bucket duration (1d)
 const BUCKET_DURATION_1D: Duration = Duration::from_secs(24 * 60 * 60);
@@ -60,6 +74,8 @@ const DEFAULT_WRITE_BUFFER_SIZE: u32 = 32 * 1024 * 1024;
 const DEFAULT_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
 /// Default row number of a row group.
 const DEFAULT_NUM_ROW_PER_ROW_GROUP: usize = 8192;
+/// Default interval of the downsampled data (5m).
+const DEFAULT_DOWNSAMPLE_INTERVAL: Duration = Duration::from_secs(5 * 60);
 
 /// Max arena block size (2G)
 const MAX_ARENA_BLOCK_SIZE: u32 = 2 * 1024 * 1024 * 1024;
@@ -68,6 +84,19 @@ const MIN_ARENA_BLOCK_SIZE: u32 = 1024;
 const MIN_NUM_ROWS_PER_ROW_GROUP: usize = 100;
 const MAX_NUM_ROWS_PER_ROW_GROUP: usize = 10_000_000;
 
+/// Options missing in [manifest_pb::TableOptions], which are persisted by
+/// [TableOptionsExt].
+const PB_EXT_OPTIONS: &[&str] = &[
+    EXPIRE_AT_COLUMN,
+    DOWNSAMPLE_AFTER,
+    DOWNSAMPLE_INTERVAL,
+    DOWNSAMPLE_AGGREGATOR,
+    DOWNSAMPLE_RESOLUTION_COLUMN,
+    FIELD_GROUPS,
+    TIMESTAMP_PRECISION,
+    COLUMN_TTL,
+];
+
 #[derive(Debug, Snafu)]
 #[allow(clippy::enum_variant_names)]
 pub enum Error {
@@ -146,6 +175,44 @@ pub enum Error {
 
     #[snafu(display("Layered memtable options is missing.\nBacktrace:\n{backtrace}",))]
     MissingLayeredMemtableOptions { backtrace: Backtrace },
+
+    #[snafu(display(
+        "Failed to parse downsample aggregator, name:{}.\nBacktrace:\n{}",
+        name,
+        backtrace
+    ))]
+    ParseDownsampleAggregator { name: String, backtrace: Backtrace },
+
+    #[snafu(display(
+        "Failed to parse field groups, value:{}, msg:{}.\nBacktrace:\n{}",
+        value,
+        msg,
+        backtrace
+    ))]
+    ParseFieldGroups {
+        value: String,
+        msg: String,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display(
+        "Failed to parse column ttl, value:{}, msg:{}.\nBacktrace:\n{}",
+        value,
+        msg,
+        backtrace
+    ))]
+    ParseColumnTtl {
+        value: String,
+        msg: String,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display(
+        "Failed to parse timestamp precision, value:{}.\nBacktrace:\n{}",
+        value,
+        backtrace
+    ))]
+    ParseTimestampPrecision { value: String, backtrace: Backtrace },
 }
 
 define_result!(Error);
@@ -245,6 +312,195 @@ impl From<Compression> for ParquetCompression {
     }
 }
 
+/// Aggregator merging the numeric fields of the rows in the same downsample
+/// interval, and the other fields always keep the last value.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub enum DownsampleAggregator {
+    #[default]
+    Avg,
+    Last,
+    Min,
+    Max,
+}
+
+impl DownsampleAggregator {
+    pub fn parse_from(name: &str) -> Result<Self> {
+        if name.eq_ignore_ascii_case(DOWNSAMPLE_AGGREGATOR_AVG) {
+            Ok(DownsampleAggregator::Avg)
+        } else if name.eq_ignore_ascii_case(DOWNSAMPLE_AGGREGATOR_LAST) {
+            Ok(DownsampleAggregator::Last)
+        } else if name.eq_ignore_ascii_case(DOWNSAMPLE_AGGREGATOR_MIN) {
+            Ok(DownsampleAggregator::Min)
+        } else if name.eq_ignore_ascii_case(DOWNSAMPLE_AGGREGATOR_MAX) {
+            Ok(DownsampleAggregator::Max)
+        } else {
+            ParseDownsampleAggregator { name }.fail()
+        }
+    }
+}
+
+impl ToString for DownsampleAggregator {
+    fn to_string(&self) -> String {
+        match self {
+            DownsampleAggregator::Avg => DOWNSAMPLE_AGGREGATOR_AVG.to_string(),
+            DownsampleAggregator::Last => DOWNSAMPLE_AGGREGATOR_LAST.to_string(),
+            DownsampleAggregator::Min => DOWNSAMPLE_AGGREGATOR_MIN.to_string(),
+            DownsampleAggregator::Max => DOWNSAMPLE_AGGREGATOR_MAX.to_string(),
+        }
+    }
+}
+
+/// Policy of downsampling the aged data during compaction.
+///
+/// The rows older than `after` are aggregated into one row per `interval` for
+/// every series, whose timestamp is the start of the interval.
+#[derive(Debug, Clone, Deserialize, PartialEq, Serialize)]
+pub struct DownsamplePolicy {
+    /// Age of the data to downsample.
+    pub after: ReadableDuration,
+    /// Resolution of the downsampled data.
+    pub interval: ReadableDuration,
+    pub aggregator: DownsampleAggregator,
+}
+
+impl DownsamplePolicy {
+    fn new(after: ReadableDuration) -> Self {
+        Self {
+            after,
+            interval: DEFAULT_DOWNSAMPLE_INTERVAL.into(),
+            aggregator: DownsampleAggregator::default(),
+        }
+    }
+
+    /// Returns the policy whose durations are scaled to the `precision`.
+    pub fn scale_to(&self, precision: TimestampPrecision) -> Self {
+        Self {
+            after: precision.scale_duration(self.after.0).into(),
+            interval: precision.scale_duration(self.interval.0).into(),
+            aggregator: self.aggregator,
+        }
+    }
+}
+
+/// Group of the fields accessed together, whose columns are stored adjacently
+/// in the sst.
+#[derive(Debug, Clone, Deserialize, Eq, PartialEq, Serialize)]
+pub struct FieldGroup {
+    pub name: String,
+    pub columns: Vec<String>,
+}
+
+impl FieldGroup {
+    /// Parse the field groups in the format of `group1:col1,col2;group2:col3`,
+    /// and the empty string means no field group.
+    pub fn parse_groups(value: &str) -> Result<Vec<FieldGroup>> {
+        let mut groups: Vec<FieldGroup> = Vec::new();
+        for group in value.split(';').map(str::trim).filter(|v| !v.is_empty()) {
+            let (name, columns) = group.split_once(':').context(ParseFieldGroups {
+                value,
+                msg: format!("missing group name, group:{group}"),
+            })?;
+            let name = name.trim();
+            let columns: Vec<_> = columns
+                .split(',')
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(ToString::to_string)
+                .collect();
+            ensure!(
+                !name.is_empty() && !columns.is_empty(),
+                ParseFieldGroups {
+                    value,
+                    msg: format!("empty group name or columns, group:{group}"),
+                }
+            );
+            ensure!(
+                groups.iter().all(|g| g.name != name),
+                ParseFieldGroups {
+                    value,
+                    msg: format!("duplicate group name, name:{name}"),
+                }
+            );
+
+            groups.push(FieldGroup {
+                name: name.to_string(),
+                columns,
+            });
+        }
+
+        Ok(groups)
+    }
+
+    pub fn groups_to_string(groups: &[FieldGroup]) -> String {
+        groups
+            .iter()
+            .map(|group| format!("{}:{}", group.name, group.columns.join(",")))
+            .collect::<Vec<_>>()
+            .join(";")
+    }
+}
+
+/// Retention of the values of a field column, shorter than the table ttl.
+///
+/// The values older than the ttl are set to null during compaction, while the
+/// other columns of the rows are kept.
+#[derive(Debug, Clone, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ColumnTtl {
+    pub column: String,
+    pub ttl: ReadableDuration,
+}
+
+impl ColumnTtl {
+    /// Parse the column ttls in the format of `col1:1d,col2:12h`, and the empty
+    /// string means no column ttl.
+    pub fn parse_ttls(value: &str) -> Result<Vec<ColumnTtl>> {
+        let mut ttls: Vec<ColumnTtl> = Vec::new();
+        for item in value.split(',').map(str::trim).filter(|v| !v.is_empty()) {
+            let (column, ttl) = item.split_once(':').context(ParseColumnTtl {
+                value,
+                msg: format!("missing ttl, item:{item}"),
+            })?;
+            let column = column.trim();
+            ensure!(
+                !column.is_empty(),
+                ParseColumnTtl {
+                    value,
+                    msg: format!("empty column name, item:{item}"),
+                }
+            );
+            ensure!(
+                ttls.iter().all(|v| v.column != column),
+                ParseColumnTtl {
+                    value,
+                    msg: format!("duplicate column, column:{column}"),
+                }
+            );
+            let ttl = parse_duration(ttl.trim()).context(ParseDuration)?;
+
+            ttls.push(ColumnTtl {
+                column: column.to_string(),
+                ttl,
+            });
+        }
+
+        Ok(ttls)
+    }
+
+    pub fn ttls_to_string(ttls: &[ColumnTtl]) -> String {
+        ttls.iter()
+            .map(|v| format!("{}:{}", v.column, v.ttl))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    pub fn scale_to(&self, precision: TimestampPrecision) -> Self {
+        Self {
+            column: self.column.clone(),
+            ttl: precision.scale_duration(self.ttl.0).into(),
+        }
+    }
+}
+
 /// A hint for building sst.
 #[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
 pub enum StorageFormatHint {
@@ -392,6 +648,20 @@ pub struct TableOptions {
     pub update_mode: UpdateMode,
     /// Hint for storage format.
     pub storage_format_hint: StorageFormatHint,
+    /// The timestamp column whose values are the expire time of the rows.
+    ///
+    /// The expired rows are filtered out during scan and purged during
+    /// compaction, regardless of the table level ttl.
+    pub expire_at_column: Option<String>,
+    /// The integer column filled with the resolution (in the unit of the
+    /// timestamp precision) of the downsampled rows, so the queries and the
+    /// later compactions can tell them from the raw rows. Required by
+    /// downsample.
+    pub downsample_resolution_column: Option<String>,
+    /// Precision of the values in the timestamp column, and the timestamp
+    /// related options are applied in this precision. The values are read in
+    /// millis by the queries.
+    pub timestamp_precision: TimestampPrecision,
 
     // The following options can be altered.
     /// Enable ttl
@@ -413,6 +683,36 @@ pub struct TableOptions {
     pub memtable_type: MemtableType,
     /// Layered memtable options
     pub layered_memtable_opts: LayeredMemtableOptions,
+    /// Downsample the aged data during compaction, disabled if `None`.
+    pub downsample: Option<DownsamplePolicy>,
+    /// Field groups stored adjacently in the sst, the existing ssts are
+    /// regrouped when they are compacted.
+    pub field_groups: Vec<FieldGroup>,
+    /// Field columns whose values expire earlier than the rows.
+    pub column_ttls: Vec<ColumnTtl>,
+}
+
+/// Changes between the options before and after altering, used to decide how
+/// to apply the new options without reopening the table.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OptionsChange {
+    /// The compaction strategy is changed.
+    pub compaction: bool,
+    /// The ttl is changed.
+    pub ttl: bool,
+    /// The field groups are changed, and the ssts need regrouping.
+    pub field_groups: bool,
+    /// The memtable options are changed, which take effect on the memtables
+    /// created after altering.
+    pub memtable: bool,
+}
+
+impl OptionsChange {
+    /// Whether a compaction should be triggered to apply the change at once.
+    #[inline]
+    pub fn need_compaction(&self) -> bool {
+        self.compaction || self.ttl || self.field_groups
+    }
 }
 
 impl TableOptions {
@@ -421,9 +721,12 @@ impl TableOptions {
         merge_table_options(map, &opt, is_create)
     }
 
+    /// Returns the segment duration scaled to the timestamp precision, which
+    /// is applicable to the values of the timestamp column.
     #[inline]
     pub fn segment_duration(&self) -> Option<Duration> {
-        self.segment_duration.map(|v| v.0)
+        self.segment_duration
+            .map(|v| self.timestamp_precision.scale_duration(v.0))
     }
 
     #[inline]
@@ -435,6 +738,17 @@ impl TableOptions {
         }
     }
 
+    /// Compute the changes from `self` to `new`.
+    pub fn diff(&self, new: &TableOptions) -> OptionsChange {
+        OptionsChange {
+            compaction: self.compaction_strategy != new.compaction_strategy,
+            ttl: self.ttl() != new.ttl(),
+            field_groups: self.field_groups != new.field_groups,
+            memtable: self.memtable_type != new.memtable_type
+                || self.layered_memtable_opts != new.layered_memtable_opts,
+        }
+    }
+
     // for show create table
     pub fn to_raw_map(&self) -> HashMap<String, String> {
         let mut m = [
@@ -480,6 +794,38 @@ impl TableOptions {
         .into_iter()
         .collect();
         self.compaction_strategy.fill_raw_map(&mut m);
+        if let Some(column) = &self.expire_at_column {
+            m.insert(EXPIRE_AT_COLUMN.to_string(), column.clone());
+        }
+        if let Some(policy) = &self.downsample {
+            m.insert(DOWNSAMPLE_AFTER.to_string(), policy.after.to_string());
+            m.insert(DOWNSAMPLE_INTERVAL.to_string(), policy.interval.to_string());
+            m.insert(
+                DOWNSAMPLE_AGGREGATOR.to_string(),
+                policy.aggregator.to_string(),
+            );
+        }
+        if let Some(column) = &self.downsample_resolution_column {
+            m.insert(DOWNSAMPLE_RESOLUTION_COLUMN.to_string(), column.clone());
+        }
+        if self.timestamp_precision != TimestampPrecision::default() {
+            m.insert(
+                TIMESTAMP_PRECISION.to_string(),
+                self.timestamp_precision.to_string(),
+            );
+        }
+        if !self.field_groups.is_empty() {
+            m.insert(
+                FIELD_GROUPS.to_string(),
+                FieldGroup::groups_to_string(&self.field_groups),
+            );
+        }
+        if !self.column_ttls.is_empty() {
+            m.insert(
+                COLUMN_TTL.to_string(),
+                ColumnTtl::ttls_to_string(&self.column_ttls),
+            );
+        }
 
         m
     }
@@ -509,6 +855,103 @@ impl TableOptions {
             ));
         }
 
+        if let Some(policy) = &self.downsample {
+            // The downsampled rows must stay in the segment of the raw rows.
+            let segment_duration = self
+                .segment_duration
+                .map(|v| v.0)
+                .unwrap_or(DEFAULT_SEGMENT_DURATION)
+                .as_millis_u64();
+            let interval = policy.interval.as_millis();
+            if interval == 0 || segment_duration % interval != 0 {
+                return Some(format!(
+                    "downsample interval must divide the segment duration, interval:{}, segment_duration:{:?}",
+                    policy.interval, self.segment_duration,
+                ));
+            }
+        }
+
+        None
+    }
+
+    /// Check if the options are valid for the table schema.
+    /// If invalid, Some(reason) will be returned.
+    pub fn check_schema_validity(&self, schema: &Schema) -> Option<String> {
+        // The rows in the same interval of a series must be adjacent in the merged
+        // data, which requires the timestamp to be the last primary key.
+        if self.downsample.is_some()
+            && schema.primary_key_indexes().last() != Some(&schema.timestamp_index())
+        {
+            return Some(format!(
+                "downsample requires the timestamp column to be the last primary key, timestamp:{}",
+                schema.timestamp_name(),
+            ));
+        }
+
+        // The downsampled rows are told from the late raw rows by the resolution
+        // column, so they are never merged again.
+        if self.downsample.is_some() && self.downsample_resolution_column.is_none() {
+            return Some("downsample requires the downsample_resolution_column".to_string());
+        }
+
+        if let Some(column) = &self.downsample_resolution_column {
+            let is_valid = schema
+                .index_of(column)
+                .map(|idx| {
+                    !schema.primary_key_indexes().contains(&idx)
+                        && matches!(
+                            schema.column(idx).data_type,
+                            DatumKind::Int64 | DatumKind::UInt64
+                        )
+                })
+                .unwrap_or(false);
+            if !is_valid {
+                return Some(format!(
+                    "downsample_resolution_column must be a non-key int64 or uint64 column, column:{column}"
+                ));
+            }
+        }
+
+        let mut grouped_columns = HashSet::new();
+        for group in &self.field_groups {
+            for column in &group.columns {
+                let is_field = schema
+                    .index_of(column)
+                    .map(|idx| !schema.primary_key_indexes().contains(&idx))
+                    .unwrap_or(false);
+                if !is_field {
+                    return Some(format!(
+                        "field group must only contain non-key columns, group:{}, column:{column}",
+                        group.name
+                    ));
+                }
+                if !grouped_columns.insert(column) {
+                    return Some(format!(
+                        "column belongs to multiple field groups, column:{column}"
+                    ));
+                }
+            }
+        }
+
+        // The expired values are set to null, and the expire time of the rows must be
+        // kept.
+        for column_ttl in &self.column_ttls {
+            let column = &column_ttl.column;
+            let is_valid = schema
+                .index_of(column)
+                .map(|idx| {
+                    !schema.primary_key_indexes().contains(&idx)
+                        && schema.column(idx).is_nullable
+                        && self.expire_at_column.as_ref() != Some(column)
+                })
+                .unwrap_or(false);
+            if !is_valid {
+                return Some(format!(
+                    "column_ttl must be set on nullable non-key columns except the expire_at_column, column:{column}"
+                ));
+            }
+        }
+
         None
     }
 
@@ -660,6 +1103,31 @@ impl From<TableOptions> for manifest_pb::TableOptions {
     }
 }
 
+/// Table options missing in [manifest_pb::TableOptions], persisted alongside it
+/// in the manifest.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct TableOptionsExt {
+    /// Options in the format of [TableOptions::to_raw_map].
+    #[prost(map = "string, string", tag = "1")]
+    pub options: HashMap<String, String>,
+}
+
+impl TableOptions {
+    /// Returns the options missing in [manifest_pb::TableOptions], or None if
+    /// all of them are default.
+    pub fn to_pb_ext(&self) -> Option<TableOptionsExt> {
+        let mut options = self.to_raw_map();
+        options.retain(|k, _| PB_EXT_OPTIONS.contains(&k.as_str()));
+
+        (!options.is_empty()).then_some(TableOptionsExt { options })
+    }
+
+    /// Restore the options persisted by [TableOptions::to_pb_ext].
+    pub fn merge_pb_ext(&self, ext: &TableOptionsExt) -> Result<Self> {
+        merge_table_options(&ext.options, self, true)
+    }
+}
+
 impl From<UpdateMode> for manifest_pb::UpdateMode {
     fn from(v: UpdateMode) -> Self {
         match v {
@@ -739,6 +1207,12 @@ impl TryFrom<manifest_pb::TableOptions> for TableOptions {
             storage_format_hint: StorageFormatHint::try_from(storage_format_hint)?,
             memtable_type: MemtableType::SkipList,
             layered_memtable_opts,
+            expire_at_column: None,
+            downsample_resolution_column: None,
+            timestamp_precision: TimestampPrecision::default(),
+            downsample: None,
+            field_groups: Vec::new(),
+            column_ttls: Vec::new(),
         };
 
         Ok(table_opts)
@@ -760,6 +1234,12 @@ impl Default for TableOptions {
             storage_format_hint: StorageFormatHint::default(),
             memtable_type: MemtableType::SkipList,
             layered_memtable_opts: LayeredMemtableOptions::default(),
+            expire_at_column: None,
+            downsample_resolution_column: None,
+            timestamp_precision: TimestampPrecision::default(),
+            downsample: None,
+            field_groups: Vec::new(),
+            column_ttls: Vec::new(),
         }
     }
 }
@@ -796,6 +1276,16 @@ fn merge_table_options(
         if let Some(v) = options.get(UPDATE_MODE) {
             base_table_opts.update_mode = UpdateMode::parse_from(v)?;
         }
+        if let Some(v) = options.get(EXPIRE_AT_COLUMN) {
+            base_table_opts.expire_at_column = Some(v.clone());
+        }
+        if let Some(v) = options.get(DOWNSAMPLE_RESOLUTION_COLUMN) {
+            base_table_opts.downsample_resolution_column = Some(v.clone());
+        }
+        if let Some(v) = options.get(TIMESTAMP_PRECISION) {
+            base_table_opts.timestamp_precision =
+                TimestampPrecision::parse_from(v).context(ParseTimestampPrecision { value: v })?;
+        }
     }
 
     if let Some(v) = options.get(TTL) {
@@ -855,6 +1345,32 @@ fn merge_table_options(
             .layered_memtable_opts
             .mutable_segment_switch_threshold = threshold;
     }
+    if let Some(v) = options.get(DOWNSAMPLE_AFTER) {
+        base_table_opts.downsample = if v.is_empty() {
+            None
+        } else {
+            let after = parse_duration(v).context(ParseDuration)?;
+            match base_table_opts.downsample.take() {
+                Some(policy) => Some(DownsamplePolicy { after, ..policy }),
+                None => Some(DownsamplePolicy::new(after)),
+            }
+        };
+    }
+    if let Some(v) = options.get(FIELD_GROUPS) {
+        base_table_opts.field_groups = FieldGroup::parse_groups(v)?;
+    }
+    if let Some(v) = options.get(COLUMN_TTL) {
+        base_table_opts.column_ttls = ColumnTtl::parse_ttls(v)?;
+    }
+    // The interval and aggregator only take effect when downsample is enabled.
+    if let Some(policy) = &mut base_table_opts.downsample {
+        if let Some(v) = options.get(DOWNSAMPLE_INTERVAL) {
+            policy.interval = parse_duration(v).context(ParseDuration)?;
+        }
+        if let Some(v) = options.get(DOWNSAMPLE_AGGREGATOR) {
+            policy.aggregator = DownsampleAggregator::parse_from(v)?;
+        }
+    }
 
     Ok(base_table_opts)
 }
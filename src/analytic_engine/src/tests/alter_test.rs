@@ -17,7 +17,10 @@
 
 //! Alter test
 
-use std::collections::{BTreeMap, HashMap};
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::Duration,
+};
 
 use common_types::{
     column_schema,
@@ -27,7 +30,7 @@ use common_types::{
     time::Timestamp,
 };
 use logger::info;
-use table_engine::table::AlterSchemaRequest;
+use table_engine::table::{AlterSchemaRequest, FlushRequest};
 use wal::manager::WalsOpener;
 
 use crate::{
@@ -424,6 +427,7 @@ fn test_alter_table_options<T: EngineBuildContext>(engine_context: T) {
 
         alter_mutable_option_case(&mut test_ctx, alter_test_table1, "enable_ttl", "false").await;
         alter_mutable_option_case(&mut test_ctx, alter_test_table1, "enable_ttl", "true").await;
+        alter_mutable_option_case(&mut test_ctx, alter_test_table1, "ttl", "14d").await;
 
         alter_mutable_option_case(
             &mut test_ctx,
@@ -451,6 +455,116 @@ fn test_alter_table_options<T: EngineBuildContext>(engine_context: T) {
     });
 }
 
+#[test]
+fn test_alter_options_trigger_compaction_rocks() {
+    let rocksdb_ctxs = rocksdb_ctxs();
+    for ctx in rocksdb_ctxs {
+        test_alter_options_trigger_compaction(ctx);
+    }
+}
+
+#[test]
+fn test_alter_options_trigger_compaction_mem_wal() {
+    let memory_ctxs = memory_ctxs();
+    for ctx in memory_ctxs {
+        test_alter_options_trigger_compaction(ctx);
+    }
+}
+
+fn test_alter_options_trigger_compaction<T: EngineBuildContext>(engine_context: T) {
+    let env = TestEnv::builder().build();
+    let mut test_ctx = env.new_context(engine_context);
+
+    env.block_on(async {
+        test_ctx.open().await;
+        let start_ms = test_ctx.start_ms();
+
+        // Two ssts are not enough to trigger a compaction with the default
+        // strategy, but enough after the min threshold is altered.
+        let strategy_table = "alter_compaction_strategy_table";
+        let fixed_schema_table = test_ctx.create_fixed_schema_table(strategy_table).await;
+        for offset in 0..2 {
+            write_and_flush(
+                &test_ctx,
+                &fixed_schema_table,
+                strategy_table,
+                start_ms + offset,
+            )
+            .await;
+        }
+        assert_eq!(2, num_ssts(&test_ctx, strategy_table).await);
+
+        let new_opts = HashMap::from([
+            ("compaction_strategy".to_string(), "size_tiered".to_string()),
+            ("compaction_min_threshold".to_string(), "2".to_string()),
+        ]);
+        test_ctx
+            .try_alter_options(strategy_table, new_opts)
+            .await
+            .unwrap();
+        wait_for_ssts(&test_ctx, strategy_table, 1).await;
+
+        // The sst written without ttl is expired after the ttl is enabled.
+        let ttl_table = "alter_ttl_table";
+        let fixed_schema_table = test_ctx
+            .create_fixed_schema_table_with(ttl_table, |builder| builder.enable_ttl(false))
+            .await;
+        write_and_flush(&test_ctx, &fixed_schema_table, ttl_table, start_ms).await;
+        assert_eq!(1, num_ssts(&test_ctx, ttl_table).await);
+
+        let new_opts = HashMap::from([
+            ("enable_ttl".to_string(), "true".to_string()),
+            ("ttl".to_string(), "1d".to_string()),
+        ]);
+        test_ctx
+            .try_alter_options(ttl_table, new_opts)
+            .await
+            .unwrap();
+        wait_for_ssts(&test_ctx, ttl_table, 0).await;
+    });
+}
+
+async fn write_and_flush<T: WalsOpener>(
+    test_ctx: &TestContext<T>,
+    fixed_schema_table: &FixedSchemaTable,
+    table_name: &str,
+    timestamp: i64,
+) {
+    let rows = [(
+        "key1",
+        Timestamp::new(timestamp),
+        "tag1-1",
+        11.0,
+        110.0,
+        "tag2-1",
+    )];
+    let row_group = fixed_schema_table.rows_to_row_group(&rows);
+    test_ctx.write_to_table(table_name, row_group).await;
+    test_ctx
+        .flush_table_with_request(table_name, FlushRequest { sync: true })
+        .await;
+}
+
+async fn num_ssts<T: WalsOpener>(test_ctx: &TestContext<T>, table_name: &str) -> usize {
+    let table_data = test_ctx.table_data(table_name).await;
+    table_data.current_version().sst_metas().len()
+}
+
+/// Wait for the compaction scheduled by altering to finish.
+async fn wait_for_ssts<T: WalsOpener>(test_ctx: &TestContext<T>, table_name: &str, expect: usize) {
+    for _ in 0..100 {
+        if num_ssts(test_ctx, table_name).await == expect {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    panic!(
+        "Compaction is not finished after altering options, table:{table_name}, ssts:{}",
+        num_ssts(test_ctx, table_name).await
+    );
+}
+
 async fn alter_immutable_option_case<T: WalsOpener>(
     test_ctx: &TestContext<T>,
     table_name: &str,
@@ -17,13 +17,23 @@
 
 //! Compaction integration tests.
 
-use common_types::time::Timestamp;
+use common_types::{
+    column_schema,
+    datum::{Datum, DatumKind},
+    row::{Row, RowGroup},
+    time::{TimeRange, Timestamp},
+    EXPIRE_AT_COLUMN,
+};
 use table_engine::table::FlushRequest;
 
 use crate::{
     compaction::SizeTieredCompactionOptions,
-    tests::util::{
-        self, EngineBuildContext, MemoryEngineBuildContext, RocksDBEngineBuildContext, TestEnv,
+    sst::file::Level,
+    tests::{
+        table::{self, FixedSchemaTable},
+        util::{
+            self, EngineBuildContext, MemoryEngineBuildContext, RocksDBEngineBuildContext, TestEnv,
+        },
     },
 };
 
@@ -116,3 +126,96 @@ fn test_table_compact_current_segment<T: EngineBuildContext>(engine_context: T)
         .await;
     });
 }
+
+#[test]
+fn test_compact_part_of_expired_versions_rocks() {
+    let rocksdb_ctx = RocksDBEngineBuildContext::default();
+    test_compact_part_of_expired_versions(rocksdb_ctx);
+}
+
+#[test]
+fn test_compact_part_of_expired_versions_mem_wal() {
+    let memory_ctx = MemoryEngineBuildContext::default();
+    test_compact_part_of_expired_versions(memory_ctx);
+}
+
+fn test_compact_part_of_expired_versions<T: EngineBuildContext>(engine_context: T) {
+    let env = TestEnv::builder().build();
+    let mut test_ctx = env.new_context(engine_context);
+
+    env.block_on(async {
+        test_ctx.open().await;
+
+        let test_table = "compact_expired_test_table";
+        let schema = FixedSchemaTable::default_schema_builder()
+            .add_normal_column(
+                column_schema::Builder::new("expire_at".to_string(), DatumKind::Timestamp)
+                    .is_nullable(true)
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+        let fixed_schema_table = test_ctx
+            .create_fixed_schema_table_with(test_table, |builder| {
+                builder
+                    .table_schema(schema.clone())
+                    .enable_ttl(false)
+                    .option(EXPIRE_AT_COLUMN, "expire_at")
+            })
+            .await;
+
+        let start_ms = test_ctx.start_ms();
+        let new_row_group = |expire_at: Datum| {
+            let row = Row::from_datums(vec![
+                "key1".into(),
+                Timestamp::new(start_ms).into(),
+                "tag1".into(),
+                1.0.into(),
+                2.0.into(),
+                "tag2".into(),
+                expire_at,
+            ]);
+            RowGroup::try_new(schema.clone(), vec![row]).unwrap()
+        };
+
+        // The first version never expires and the second one is already expired,
+        // each in its own sst.
+        for expire_at in [Datum::Null, Timestamp::new(start_ms).into()] {
+            test_ctx
+                .write_to_table(test_table, new_row_group(expire_at))
+                .await;
+            test_ctx
+                .flush_table_with_request(test_table, FlushRequest { sync: true })
+                .await;
+        }
+
+        // Compact only the sst of the second version.
+        let table_data = test_ctx.table_data(test_table).await;
+        let read_view = table_data
+            .current_version()
+            .pick_read_view(TimeRange::min_to_max());
+        let ssts = &read_view.leveled_ssts[Level::MIN.as_usize()];
+        assert_eq!(2, ssts.len());
+        let newest = ssts.iter().max_by_key(|f| f.max_sequence()).unwrap();
+        test_ctx
+            .instance()
+            .compact_ssts_for_test(&table_data, Level::MIN, vec![newest.clone()])
+            .await
+            .unwrap();
+        drop(read_view);
+
+        // The expired version still hides the older one.
+        for read_opts in table::read_opts_list() {
+            let record_batches = test_ctx
+                .read_table(
+                    test_table,
+                    fixed_schema_table.new_read_all_request(read_opts),
+                )
+                .await;
+            let num_rows: usize = record_batches.iter().map(|b| b.num_rows()).sum();
+            assert_eq!(0, num_rows);
+        }
+    });
+}
@@ -0,0 +1,244 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Engine hook tests.
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use async_trait::async_trait;
+use common_types::{row::RowGroup, time::Timestamp};
+use generic_error::GenericResult;
+use table_engine::table::{FlushRequest, ReadOptions, WriteRequest};
+use wal::manager::WalsOpener;
+
+use crate::{
+    compaction::SizeTieredCompactionOptions,
+    hook::EngineHook,
+    table::data::TableData,
+    tests::{
+        table::{FixedSchemaTable, RowTuple},
+        util::{self, EngineBuildContext, RocksDBEngineBuildContext, TestContext, TestEnv},
+    },
+};
+
+/// Hook vetoing the operations once they are turned on.
+#[derive(Debug, Default)]
+struct VetoHook {
+    write: AtomicBool,
+    flush: AtomicBool,
+    compaction: AtomicBool,
+}
+
+impl VetoHook {
+    fn check(vetoed: &AtomicBool, op: &str) -> GenericResult<()> {
+        if vetoed.load(Ordering::Relaxed) {
+            return Err(format!("{op} is vetoed").into());
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl EngineHook for VetoHook {
+    async fn before_write(&self, _table: &TableData, _rows: &RowGroup) -> GenericResult<()> {
+        Self::check(&self.write, "write")
+    }
+
+    async fn before_flush(&self, _table: &TableData) -> GenericResult<()> {
+        Self::check(&self.flush, "flush")
+    }
+
+    async fn before_compaction(&self, _table: &TableData) -> GenericResult<()> {
+        Self::check(&self.compaction, "compaction")
+    }
+}
+
+fn new_rows(start_ms: i64, num_rows: i64) -> Vec<RowTuple<'static>> {
+    (0..num_rows)
+        .map(|i| {
+            (
+                "key1",
+                Timestamp::new(start_ms + i),
+                "tag1-1",
+                11.0,
+                110.0,
+                "tag2-1",
+            )
+        })
+        .collect()
+}
+
+async fn num_rows_in_table<T: WalsOpener>(
+    test_ctx: &TestContext<T>,
+    fixed_schema_table: &FixedSchemaTable,
+    table_name: &str,
+) -> usize {
+    test_ctx
+        .read_table(
+            table_name,
+            fixed_schema_table.new_read_all_request(ReadOptions::default()),
+        )
+        .await
+        .iter()
+        .map(|batch| batch.num_rows())
+        .sum()
+}
+
+#[test]
+fn test_hook_veto_write_rocks() {
+    let rocksdb_ctx = RocksDBEngineBuildContext::default();
+    test_hook_veto_write(rocksdb_ctx);
+}
+
+fn test_hook_veto_write<T: EngineBuildContext>(engine_context: T) {
+    let env = TestEnv::builder().build();
+    let mut test_ctx = env.new_context(engine_context);
+    let hook = Arc::new(VetoHook::default());
+    test_ctx.hooks_mut().register(hook.clone());
+
+    env.block_on(async {
+        test_ctx.open().await;
+
+        let test_table = "test_hook_veto_write";
+        let fixed_schema_table = test_ctx.create_fixed_schema_table(test_table).await;
+
+        let table_data = test_ctx.table_data(test_table).await;
+        let last_sequence = table_data.last_sequence();
+        hook.write.store(true, Ordering::Relaxed);
+        let rows = new_rows(test_ctx.start_ms(), 2);
+        let row_group = fixed_schema_table.rows_to_row_group(&rows);
+        let res = test_ctx
+            .table(test_table)
+            .write(WriteRequest { row_group })
+            .await;
+        assert!(res.is_err());
+
+        // Nothing is written into the memtable or the wal.
+        assert_eq!(last_sequence, table_data.last_sequence());
+        assert_eq!(
+            0,
+            num_rows_in_table(&test_ctx, &fixed_schema_table, test_table).await
+        );
+        test_ctx.reopen_with_tables(&[test_table]).await;
+        assert_eq!(
+            0,
+            num_rows_in_table(&test_ctx, &fixed_schema_table, test_table).await
+        );
+    });
+}
+
+#[test]
+fn test_hook_veto_flush_rocks() {
+    let rocksdb_ctx = RocksDBEngineBuildContext::default();
+    test_hook_veto_flush(rocksdb_ctx);
+}
+
+fn test_hook_veto_flush<T: EngineBuildContext>(engine_context: T) {
+    let env = TestEnv::builder().build();
+    let mut test_ctx = env.new_context(engine_context);
+    let hook = Arc::new(VetoHook::default());
+    test_ctx.hooks_mut().register(hook.clone());
+
+    env.block_on(async {
+        test_ctx.open().await;
+
+        let test_table = "test_hook_veto_flush";
+        let fixed_schema_table = test_ctx.create_fixed_schema_table(test_table).await;
+        let rows = new_rows(test_ctx.start_ms(), 2);
+        test_ctx
+            .write_to_table(test_table, fixed_schema_table.rows_to_row_group(&rows))
+            .await;
+
+        let table_data = test_ctx.table_data(test_table).await;
+        let flushed_sequence = table_data.current_version().flushed_sequence();
+        hook.flush.store(true, Ordering::Relaxed);
+        let res = test_ctx
+            .table(test_table)
+            .flush(FlushRequest { sync: true })
+            .await;
+        assert!(res.is_err());
+
+        // No sst is dumped and the rows are still in the memtables.
+        let version = table_data.current_version();
+        assert_eq!(0, version.num_ssts_per_level().iter().sum::<usize>());
+        assert_eq!(flushed_sequence, version.flushed_sequence());
+        util::check_read(
+            &test_ctx,
+            &fixed_schema_table,
+            "Test read after flush vetoed",
+            test_table,
+            &rows,
+        )
+        .await;
+    });
+}
+
+#[test]
+fn test_hook_veto_compaction_rocks() {
+    let rocksdb_ctx = RocksDBEngineBuildContext::default();
+    test_hook_veto_compaction(rocksdb_ctx);
+}
+
+fn test_hook_veto_compaction<T: EngineBuildContext>(engine_context: T) {
+    let env = TestEnv::builder().build();
+    let mut test_ctx = env.new_context(engine_context);
+    let hook = Arc::new(VetoHook::default());
+    test_ctx.hooks_mut().register(hook.clone());
+
+    env.block_on(async {
+        test_ctx.open().await;
+
+        let test_table = "test_hook_veto_compaction";
+        let fixed_schema_table = test_ctx.create_fixed_schema_table(test_table).await;
+
+        // Write enough ssts to be picked by the compaction.
+        let num_ssts = SizeTieredCompactionOptions::default().min_threshold as i64 * 2;
+        let rows = new_rows(test_ctx.start_ms(), num_ssts);
+        for row in &rows {
+            test_ctx
+                .write_to_table(test_table, fixed_schema_table.rows_to_row_group(&[*row]))
+                .await;
+            test_ctx
+                .flush_table_with_request(test_table, FlushRequest { sync: true })
+                .await;
+        }
+
+        hook.compaction.store(true, Ordering::Relaxed);
+        let table_data = test_ctx.table_data(test_table).await;
+        let ssts_before = table_data.current_version().num_ssts_per_level();
+        assert_eq!(num_ssts as usize, ssts_before.iter().sum::<usize>());
+        let res = test_ctx.table(test_table).compact().await;
+        assert!(res.is_err());
+
+        // The ssts are left untouched.
+        assert_eq!(
+            ssts_before,
+            table_data.current_version().num_ssts_per_level()
+        );
+        util::check_read(
+            &test_ctx,
+            &fixed_schema_table,
+            "Test read after compaction vetoed",
+            test_table,
+            &rows,
+        )
+        .await;
+    });
+}
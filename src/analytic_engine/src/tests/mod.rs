@@ -24,6 +24,8 @@ mod compaction_test;
 #[cfg(test)]
 mod drop_test;
 #[cfg(test)]
+mod hook_test;
+#[cfg(test)]
 mod open_test;
 #[cfg(test)]
 mod read_write_test;
@@ -17,8 +17,25 @@
 
 //! Engine open test.
 
-use crate::tests::util::{
-    EngineBuildContext, MemoryEngineBuildContext, RocksDBEngineBuildContext, TestEnv,
+use std::path::{Path, PathBuf};
+
+use common_types::{
+    column_schema, datum::DatumKind, schema::Schema, table::DEFAULT_SHARD_ID, time::Timestamp,
+    COLUMN_TTL, DOWNSAMPLE_AFTER, DOWNSAMPLE_AGGREGATOR, DOWNSAMPLE_INTERVAL,
+    DOWNSAMPLE_RESOLUTION_COLUMN, EXPIRE_AT_COLUMN, FIELD_GROUPS, TIMESTAMP_PRECISION,
+};
+use object_store::config::ObjectStoreOptions;
+use time_ext::ReadableDuration;
+
+use crate::{
+    table::{data::DEFAULT_ALLOC_STEP, sst_util},
+    tests::{
+        table::FixedSchemaTable,
+        util::{
+            self, EngineBuildContext, MemoryEngineBuildContext, ObjectStoreEngineBuildContext,
+            RocksDBEngineBuildContext, TestEnv,
+        },
+    },
 };
 
 #[test]
@@ -33,6 +50,12 @@ fn test_open_engine_mem_wal() {
     test_open_engine(memory_ctx);
 }
 
+#[test]
+fn test_open_engine_object_store_wal() {
+    let object_store_ctx = ObjectStoreEngineBuildContext::default();
+    test_open_engine(object_store_ctx);
+}
+
 fn test_open_engine<T: EngineBuildContext>(engine_context: T) {
     let env = TestEnv::builder().build();
     let mut test_ctx = env.new_context(engine_context);
@@ -44,3 +67,241 @@ fn test_open_engine<T: EngineBuildContext>(engine_context: T) {
         test_ctx.reopen().await;
     });
 }
+
+#[test]
+fn test_check_open_shard_rocks() {
+    let rocksdb_ctx = RocksDBEngineBuildContext::default();
+    test_check_open_shard(rocksdb_ctx);
+}
+
+fn test_check_open_shard<T: EngineBuildContext>(engine_context: T) {
+    let env = TestEnv::builder().build();
+    let mut test_ctx = env.new_context(engine_context);
+
+    env.block_on(async {
+        test_ctx.open().await;
+
+        let test_table = "test_check_open_shard_table";
+        let fixed_schema_table = test_ctx.create_fixed_schema_table(test_table).await;
+        let start_ms = test_ctx.start_ms();
+        let rows = [(
+            "key1",
+            Timestamp::new(start_ms),
+            "tag1-1",
+            11.0,
+            110.0,
+            "tag2-1",
+        )];
+        let row_group = fixed_schema_table.rows_to_row_group(&rows);
+        test_ctx.write_to_table(test_table, row_group).await;
+        test_ctx.flush_table(test_table).await;
+
+        let table_id = test_ctx.table(test_table).id();
+        let report = test_ctx
+            .check_open_shard(vec![(table_id, test_table)], DEFAULT_SHARD_ID)
+            .await;
+        assert!(report.passed, "report:{report:?}");
+        let items: Vec<_> = report.checks.iter().map(|v| v.item.as_str()).collect();
+        assert_eq!(
+            items,
+            vec!["memory", "wal", "manifest", "schema", "object_store"]
+        );
+
+        // The table name mismatches the one in the manifest.
+        let report = test_ctx
+            .check_open_shard(vec![(table_id, "another_table")], DEFAULT_SHARD_ID)
+            .await;
+        assert!(!report.passed);
+        let failed: Vec<_> = report
+            .checks
+            .iter()
+            .filter(|v| !v.passed)
+            .map(|v| v.item.as_str())
+            .collect();
+        assert_eq!(failed, vec!["schema"]);
+    });
+}
+
+#[test]
+fn test_reconcile_ssts_on_open_rocks() {
+    let rocksdb_ctx = RocksDBEngineBuildContext::default();
+    test_reconcile_ssts_on_open(rocksdb_ctx);
+}
+
+fn find_sst_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            find_sst_files(&path, files);
+        } else if path.extension().is_some_and(|v| v == "sst") {
+            files.push(path);
+        }
+    }
+}
+
+fn test_reconcile_ssts_on_open<T: EngineBuildContext>(engine_context: T) {
+    let env = TestEnv::builder().build();
+    let mut test_ctx = env.new_context(engine_context);
+    let data_dir = match &test_ctx.config_mut().storage.object_store {
+        ObjectStoreOptions::Local(opts) => PathBuf::from(&opts.data_dir),
+        _ => unreachable!(),
+    };
+
+    env.block_on(async {
+        test_ctx.open().await;
+
+        let test_table = "test_reconcile_ssts_table";
+        let fixed_schema_table = test_ctx.create_fixed_schema_table(test_table).await;
+        let start_ms = test_ctx.start_ms();
+        let rows = [(
+            "key1",
+            Timestamp::new(start_ms),
+            "tag1-1",
+            11.0,
+            110.0,
+            "tag2-1",
+        )];
+        let row_group = fixed_schema_table.rows_to_row_group(&rows);
+        test_ctx.write_to_table(test_table, row_group).await;
+        test_ctx.flush_table(test_table).await;
+
+        let mut sst_files = Vec::new();
+        find_sst_files(&data_dir, &mut sst_files);
+        assert_eq!(sst_files.len(), 1);
+        let sst_file = &sst_files[0];
+        let sst_dir = sst_file.parent().unwrap();
+        let file_id =
+            sst_util::parse_sst_file_id(sst_file.file_name().unwrap().to_str().unwrap()).unwrap();
+
+        // Mock the files left by an interrupted flush, and the file allocated
+        // after the table is opened.
+        let orphan_file = sst_dir.join(sst_util::sst_file_name(file_id + 1));
+        let orphan_meta_file = sst_dir.join(sst_util::new_metadata_path(&sst_util::sst_file_name(
+            file_id + 1,
+        )));
+        let new_file = sst_dir.join(sst_util::sst_file_name(file_id + DEFAULT_ALLOC_STEP * 10));
+        for path in [&orphan_file, &orphan_meta_file, &new_file] {
+            std::fs::write(path, b"partial").unwrap();
+        }
+
+        // The orphan files are only reported by default.
+        test_ctx.reopen_with_tables(&[test_table]).await;
+        assert!(orphan_file.exists());
+
+        // The orphan files are kept within the grace period.
+        test_ctx.config_mut().sst_reconciler.delete_orphan_files = true;
+        test_ctx.reopen_with_tables(&[test_table]).await;
+        assert!(orphan_file.exists());
+
+        test_ctx.config_mut().sst_reconciler.orphan_grace_period = ReadableDuration::secs(0);
+        test_ctx.reopen_with_tables(&[test_table]).await;
+
+        assert!(sst_file.exists());
+        assert!(!orphan_file.exists());
+        assert!(!orphan_meta_file.exists());
+        assert!(new_file.exists());
+        util::check_read(
+            &test_ctx,
+            &fixed_schema_table,
+            "Test read after reconciling ssts",
+            test_table,
+            &rows,
+        )
+        .await;
+    });
+}
+
+/// Check the `options` of the table are kept after the table is reopened.
+fn test_options_after_reopen<T: EngineBuildContext>(
+    engine_context: T,
+    schema: Schema,
+    options: &[(&str, &str)],
+) {
+    let env = TestEnv::builder().build();
+    let mut test_ctx = env.new_context(engine_context);
+
+    env.block_on(async {
+        test_ctx.open().await;
+
+        let test_table = "test_options_after_reopen_table";
+        test_ctx
+            .create_fixed_schema_table_with(test_table, |builder| {
+                options
+                    .iter()
+                    .fold(builder.table_schema(schema), |builder, (key, value)| {
+                        builder.option(key, value)
+                    })
+            })
+            .await;
+        test_ctx.reopen_with_tables(&[test_table]).await;
+
+        let table_options = test_ctx.table(test_table).options();
+        for (key, value) in options {
+            assert_eq!(table_options.get(*key).map(String::as_str), Some(*value));
+        }
+    });
+}
+
+#[test]
+fn test_expire_at_column_after_reopen_rocks() {
+    let rocksdb_ctx = RocksDBEngineBuildContext::default();
+    test_options_after_reopen(
+        rocksdb_ctx,
+        FixedSchemaTable::default_schema_builder().build().unwrap(),
+        &[(EXPIRE_AT_COLUMN, "ts")],
+    );
+}
+
+#[test]
+fn test_downsample_after_reopen_rocks() {
+    let rocksdb_ctx = RocksDBEngineBuildContext::default();
+    let schema = FixedSchemaTable::default_schema_builder()
+        .add_normal_column(
+            column_schema::Builder::new("resolution".to_string(), DatumKind::Int64)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .build()
+        .unwrap();
+    test_options_after_reopen(
+        rocksdb_ctx,
+        schema,
+        &[
+            (DOWNSAMPLE_AFTER, "1d"),
+            (DOWNSAMPLE_INTERVAL, "10m"),
+            (DOWNSAMPLE_AGGREGATOR, "MAX"),
+            (DOWNSAMPLE_RESOLUTION_COLUMN, "resolution"),
+        ],
+    );
+}
+
+#[test]
+fn test_field_groups_after_reopen_rocks() {
+    let rocksdb_ctx = RocksDBEngineBuildContext::default();
+    test_options_after_reopen(
+        rocksdb_ctx,
+        FixedSchemaTable::default_schema_builder().build().unwrap(),
+        &[(FIELD_GROUPS, "doubles:double_field1,double_field2")],
+    );
+}
+
+#[test]
+fn test_timestamp_precision_after_reopen_rocks() {
+    let rocksdb_ctx = RocksDBEngineBuildContext::default();
+    test_options_after_reopen(
+        rocksdb_ctx,
+        FixedSchemaTable::default_schema_builder().build().unwrap(),
+        &[(TIMESTAMP_PRECISION, "us")],
+    );
+}
+
+#[test]
+fn test_column_ttl_after_reopen_rocks() {
+    let rocksdb_ctx = RocksDBEngineBuildContext::default();
+    test_options_after_reopen(
+        rocksdb_ctx,
+        FixedSchemaTable::default_schema_builder().build().unwrap(),
+        &[(COLUMN_TTL, "double_field1:12h,string_field2:1d")],
+    );
+}
@@ -17,15 +17,28 @@
 
 //! Read write test.
 
-use std::{thread, time};
+use std::{sync::Arc, thread, time};
 
-use common_types::time::Timestamp;
+use common_types::{
+    request_id::RequestId,
+    time::{TimeRange, Timestamp},
+};
+use datafusion::prelude::{col, lit};
 use logger::info;
+use table_engine::{
+    predicate::PredicateBuilder,
+    table::{ExistsRequest, ReadOptions, ReadOrder, ReadSnapshot, WriteRequest},
+};
+use time_ext::ReadableDuration;
 use wal::manager::WalsOpener;
 
 use crate::{
+    instance::idle_table_reaper::IdleTableReaperConfig,
     table_options,
-    tests::util::{self, memory_ctxs, rocksdb_ctxs, EngineBuildContext, TestContext, TestEnv},
+    tests::util::{
+        self, memory_ctxs, object_store_ctxs, rocksdb_ctxs, EngineBuildContext,
+        ObjectStoreEngineBuildContext, TestContext, TestEnv,
+    },
 };
 
 #[test]
@@ -184,6 +197,329 @@ fn test_multi_table_read_write<T: EngineBuildContext>(engine_context: T) {
     });
 }
 
+#[test]
+fn test_read_time_ordered_with_limit_rocks() {
+    let rocksdb_ctxs = rocksdb_ctxs();
+    for ctx in rocksdb_ctxs {
+        test_read_time_ordered_with_limit(ctx);
+    }
+}
+
+#[test]
+fn test_read_time_ordered_with_limit_mem_wal() {
+    let memory_ctxs = memory_ctxs();
+    for ctx in memory_ctxs {
+        test_read_time_ordered_with_limit(ctx);
+    }
+}
+
+fn test_read_time_ordered_with_limit<T: EngineBuildContext>(engine_context: T) {
+    let env = TestEnv::builder().build();
+    let mut test_ctx = env.new_context(engine_context);
+
+    env.block_on(async {
+        test_ctx.open().await;
+
+        let test_table = "test_read_time_ordered_with_limit";
+        let fixed_schema_table = test_ctx
+            .create_fixed_schema_table_with(test_table, |builder| {
+                builder.option(common_types::SEGMENT_DURATION, "2h")
+            })
+            .await;
+
+        let segment_ms = table_options::DEFAULT_SEGMENT_DURATION.as_millis() as i64;
+        let start_ms = Timestamp::new(test_ctx.start_ms())
+            .truncate_by(table_options::DEFAULT_SEGMENT_DURATION)
+            .as_i64();
+        // Three segments.
+        let rows = [
+            (
+                "key1",
+                Timestamp::new(start_ms),
+                "tag1-1",
+                11.0,
+                110.0,
+                "tag2-1",
+            ),
+            (
+                "key2",
+                Timestamp::new(start_ms + 1),
+                "tag1-2",
+                12.0,
+                110.0,
+                "tag2-2",
+            ),
+            (
+                "key3",
+                Timestamp::new(start_ms + segment_ms),
+                "tag1-3",
+                13.0,
+                110.0,
+                "tag2-3",
+            ),
+            (
+                "key4",
+                Timestamp::new(start_ms + 2 * segment_ms),
+                "tag1-4",
+                14.0,
+                110.0,
+                "tag2-4",
+            ),
+            (
+                "key5",
+                Timestamp::new(start_ms + 2 * segment_ms + 1),
+                "tag1-5",
+                15.0,
+                110.0,
+                "tag2-5",
+            ),
+        ];
+        let row_group = fixed_schema_table.rows_to_row_group(&rows);
+        test_ctx.write_to_table(test_table, row_group).await;
+
+        // A segment is read entirely, and the read stops before the segments
+        // behind the rows within the limit.
+        let cases = [
+            (ReadOrder::Desc, 1, vec![rows[3], rows[4]]),
+            (ReadOrder::Desc, 3, vec![rows[3], rows[4], rows[2]]),
+            (ReadOrder::Asc, 1, vec![rows[0], rows[1]]),
+            (ReadOrder::Asc, 3, vec![rows[0], rows[1], rows[2]]),
+            (ReadOrder::Asc, 10, rows.to_vec()),
+        ];
+        for (order, limit, expect_rows) in cases {
+            let read_opts = ReadOptions {
+                batch_size: 1,
+                limit: Some(limit),
+                order,
+                ..Default::default()
+            };
+            let record_batches = test_ctx
+                .read_table(
+                    test_table,
+                    fixed_schema_table.new_read_all_request(read_opts),
+                )
+                .await;
+            fixed_schema_table.assert_batch_eq_to_rows(&record_batches, &expect_rows);
+        }
+    });
+}
+
+#[test]
+fn test_read_with_snapshot_rocks() {
+    let rocksdb_ctxs = rocksdb_ctxs();
+    for ctx in rocksdb_ctxs {
+        test_read_with_snapshot(ctx);
+    }
+}
+
+#[test]
+fn test_read_with_snapshot_mem_wal() {
+    let memory_ctxs = memory_ctxs();
+    for ctx in memory_ctxs {
+        test_read_with_snapshot(ctx);
+    }
+}
+
+fn test_read_with_snapshot<T: EngineBuildContext>(engine_context: T) {
+    let env = TestEnv::builder().build();
+    let mut test_ctx = env.new_context(engine_context);
+
+    env.block_on(async {
+        test_ctx.open().await;
+
+        let test_table = "test_read_with_snapshot";
+        let fixed_schema_table = test_ctx.create_fixed_schema_table(test_table).await;
+
+        let start_ms = test_ctx.start_ms();
+        let rows = [
+            (
+                "key1",
+                Timestamp::new(start_ms),
+                "tag1-1",
+                11.0,
+                110.0,
+                "tag2-1",
+            ),
+            (
+                "key2",
+                Timestamp::new(start_ms),
+                "tag1-2",
+                12.0,
+                110.0,
+                "tag2-2",
+            ),
+        ];
+        let row_group = fixed_schema_table.rows_to_row_group(&rows);
+        test_ctx.write_to_table(test_table, row_group).await;
+
+        let snapshot = Arc::new(ReadSnapshot::default());
+        let new_snapshot_request = || {
+            let opts = ReadOptions {
+                snapshot: Some(snapshot.clone()),
+                ..Default::default()
+            };
+            fixed_schema_table.new_read_all_request(opts)
+        };
+
+        // The first read pins the version of the table.
+        let record_batches = test_ctx
+            .read_table(test_table, new_snapshot_request())
+            .await;
+        fixed_schema_table.assert_batch_eq_to_rows(&record_batches, &rows);
+
+        // Write concurrently with the reads sharing the snapshot, and flush so the
+        // data is moved to the ssts.
+        let new_rows = [(
+            "key3",
+            Timestamp::new(start_ms + 1),
+            "tag1-3",
+            13.0,
+            110.0,
+            "tag2-3",
+        )];
+        let row_group = fixed_schema_table.rows_to_row_group(&new_rows);
+        test_ctx.write_to_table(test_table, row_group).await;
+        test_ctx.flush_table(test_table).await;
+
+        // The read sharing the snapshot sees the same data as the first one.
+        let record_batches = test_ctx
+            .read_table(test_table, new_snapshot_request())
+            .await;
+        fixed_schema_table.assert_batch_eq_to_rows(&record_batches, &rows);
+
+        // The read without the snapshot sees the new data.
+        let all_rows: Vec<_> = rows.iter().chain(new_rows.iter()).cloned().collect();
+        util::check_read(
+            &test_ctx,
+            &fixed_schema_table,
+            "Test read without snapshot",
+            test_table,
+            &all_rows,
+        )
+        .await;
+    });
+}
+
+#[test]
+fn test_write_tables_atomically_rocks() {
+    let rocksdb_ctxs = rocksdb_ctxs();
+    for ctx in rocksdb_ctxs {
+        test_write_tables_atomically(ctx);
+    }
+}
+
+fn test_write_tables_atomically<T: EngineBuildContext>(engine_context: T) {
+    let env = TestEnv::builder().build();
+    let mut test_ctx = env.new_context(engine_context);
+
+    env.block_on(async {
+        test_ctx.open().await;
+
+        let test_table1 = "test_write_tables_atomically1";
+        let test_table2 = "test_write_tables_atomically2";
+
+        let fixed_schema_table = test_ctx.create_fixed_schema_table(test_table1).await;
+        let _ = test_ctx.create_fixed_schema_table(test_table2).await;
+
+        let start_ms = test_ctx.start_ms();
+        let rows = [
+            (
+                "key1",
+                Timestamp::new(start_ms),
+                "tag1-1",
+                11.0,
+                110.0,
+                "tag2-1",
+            ),
+            (
+                "key2",
+                Timestamp::new(start_ms + 1),
+                "tag1-2",
+                12.0,
+                110.0,
+                "tag2-2",
+            ),
+        ];
+
+        let row_group1 = fixed_schema_table.rows_to_row_group(&rows);
+        let row_group2 = fixed_schema_table.rows_to_row_group(&rows);
+        let num_rows = test_ctx
+            .write_to_tables_atomically(vec![(test_table1, row_group1), (test_table2, row_group2)])
+            .await;
+        assert_eq!(num_rows, vec![2, 2]);
+
+        // Both tables are recovered from the wal after reopen.
+        test_ctx
+            .reopen_with_tables(&[test_table1, test_table2])
+            .await;
+        for table in [test_table1, test_table2] {
+            util::check_read(
+                &test_ctx,
+                &fixed_schema_table,
+                "Test read tables written atomically after reopen",
+                table,
+                &rows,
+            )
+            .await;
+        }
+    });
+}
+
+#[test]
+fn test_close_table_for_recovery_rocks() {
+    let rocksdb_ctxs = rocksdb_ctxs();
+    for ctx in rocksdb_ctxs {
+        test_close_table_for_recovery(ctx);
+    }
+}
+
+#[test]
+fn test_close_table_for_recovery_object_store_wal() {
+    for ctx in object_store_ctxs() {
+        test_close_table_for_recovery(ctx);
+    }
+}
+
+/// The table closed after failing to apply a write should be recovered from
+/// the wal on the next access.
+fn test_close_table_for_recovery<T: EngineBuildContext>(engine_context: T) {
+    let env = TestEnv::builder().build();
+    let mut test_ctx = env.new_context(engine_context);
+
+    env.block_on(async {
+        test_ctx.open().await;
+
+        let test_table = "test_close_table_for_recovery";
+        let fixed_schema_table = test_ctx.create_fixed_schema_table(test_table).await;
+        let start_ms = test_ctx.start_ms();
+        let rows = [(
+            "key1",
+            Timestamp::new(start_ms),
+            "tag1-1",
+            11.0,
+            110.0,
+            "tag2-1",
+        )];
+        test_ctx
+            .write_to_table(test_table, fixed_schema_table.rows_to_row_group(&rows))
+            .await;
+
+        // Drop the memtables without flushing them.
+        let table_data = test_ctx.table_data(test_table).await;
+        test_ctx.instance().close_table_for_recovery(&table_data);
+        assert!(table_data.is_idle_closed());
+
+        util::check_read(
+            &test_ctx,
+            &fixed_schema_table,
+            "Test read after closing for recovery",
+            test_table,
+            &rows,
+        )
+        .await;
+    });
+}
+
 #[test]
 fn test_table_write_read_rocks() {
     let rocksdb_ctxs = rocksdb_ctxs();
@@ -200,6 +536,12 @@ fn test_table_write_read_mem_wal() {
     }
 }
 
+#[test]
+fn test_table_write_read_object_store_wal() {
+    let object_store_ctx = ObjectStoreEngineBuildContext::default();
+    test_table_write_read(object_store_ctx);
+}
+
 fn test_table_write_read<T: EngineBuildContext>(engine_context: T) {
     let env = TestEnv::builder().build();
     let mut test_ctx = env.new_context(engine_context);
@@ -252,6 +594,21 @@ fn test_table_write_read<T: EngineBuildContext>(engine_context: T) {
         )
         .await;
 
+        // The read stops after producing the rows within the limit.
+        let read_opts = ReadOptions {
+            batch_size: 1,
+            read_parallelism: 1,
+            limit: Some(2),
+            ..Default::default()
+        };
+        let record_batches = test_ctx
+            .read_table(
+                test_table1,
+                fixed_schema_table.new_read_all_request(read_opts),
+            )
+            .await;
+        fixed_schema_table.assert_batch_eq_to_rows(&record_batches, &rows[..2]);
+
         // Reopen db.
         test_ctx.reopen_with_tables(&[test_table1]).await;
 
@@ -348,6 +705,94 @@ fn test_table_write_get<T: EngineBuildContext>(engine_context: T) {
     });
 }
 
+#[test]
+fn test_table_write_exists_rocks() {
+    let rocksdb_ctxs = rocksdb_ctxs();
+    for ctx in rocksdb_ctxs {
+        test_table_write_exists(ctx);
+    }
+}
+
+#[test]
+fn test_table_write_exists_mem_wal() {
+    let memory_ctxs = memory_ctxs();
+    for ctx in memory_ctxs {
+        test_table_write_exists(ctx);
+    }
+}
+
+fn test_table_write_exists<T: EngineBuildContext>(engine_context: T) {
+    let env = TestEnv::builder().build();
+    let mut test_ctx = env.new_context(engine_context);
+
+    env.block_on(async {
+        test_ctx.open().await;
+
+        let test_table1 = "test_table1";
+        let fixed_schema_table = test_ctx.create_fixed_schema_table(test_table1).await;
+
+        let start_ms = test_ctx.start_ms();
+        let rows = [
+            (
+                "key1",
+                Timestamp::new(start_ms),
+                "tag1-1",
+                11.0,
+                110.0,
+                "tag2-1",
+            ),
+            (
+                "key2",
+                Timestamp::new(start_ms + 1),
+                "tag1-2",
+                12.0,
+                110.0,
+                "tag2-2",
+            ),
+        ];
+        let row_group = fixed_schema_table.rows_to_row_group(&rows);
+
+        // Write data to table.
+        test_ctx.write_to_table(test_table1, row_group).await;
+
+        let new_request = |key: &str, start: i64, end: i64| ExistsRequest {
+            request_id: RequestId::next_id(),
+            predicate: PredicateBuilder::default()
+                .set_time_range(TimeRange::new_unchecked(
+                    Timestamp::new(start),
+                    Timestamp::new(end),
+                ))
+                .add_pushdown_exprs(&[col("key").eq(lit(key))])
+                .build(),
+        };
+        // One case is (key, start, end, exists).
+        let cases = [
+            ("key1", start_ms, start_ms + 2, true),
+            ("key2", start_ms, start_ms + 2, true),
+            ("key3", start_ms, start_ms + 2, false),
+            ("key1", start_ms + 100, start_ms + 200, false),
+        ];
+
+        // Check the memtables.
+        for (key, start, end, expect) in cases {
+            let exists = test_ctx
+                .maybe_exists_in_table(test_table1, new_request(key, start, end))
+                .await;
+            assert_eq!(expect, exists, "key:{key}, start:{start}, end:{end}");
+        }
+
+        // Check the ssts, the keys not in the range of the sst are pruned by the
+        // meta data.
+        test_ctx.flush_table(test_table1).await;
+        for (key, start, end, expect) in cases {
+            let exists = test_ctx
+                .maybe_exists_in_table(test_table1, new_request(key, start, end))
+                .await;
+            assert_eq!(expect, exists, "key:{key}, start:{start}, end:{end}");
+        }
+    });
+}
+
 #[test]
 fn test_table_write_get_override_rocks() {
     let rocksdb_ctxs = rocksdb_ctxs();
@@ -695,3 +1140,166 @@ fn test_write_buffer_size_overflow<T: WalsOpener>(
         .await;
     });
 }
+
+#[test]
+fn test_scan_wal_rocks() {
+    let rocksdb_ctxs = rocksdb_ctxs();
+    for ctx in rocksdb_ctxs {
+        test_scan_wal(ctx);
+    }
+}
+
+#[test]
+fn test_scan_wal_mem_wal() {
+    let memory_ctxs = memory_ctxs();
+    for ctx in memory_ctxs {
+        test_scan_wal(ctx);
+    }
+}
+
+fn test_scan_wal<T: EngineBuildContext>(engine_context: T) {
+    let env = TestEnv::builder().build();
+    let mut test_ctx = env.new_context(engine_context);
+
+    env.block_on(async {
+        test_ctx.open().await;
+
+        let test_table = "test_scan_wal_table";
+        let fixed_schema_table = test_ctx.create_fixed_schema_table(test_table).await;
+        let start_ms = test_ctx.start_ms();
+        let rows1 = [
+            (
+                "key1",
+                Timestamp::new(start_ms),
+                "tag1-1",
+                11.0,
+                110.0,
+                "tag2-1",
+            ),
+            (
+                "key2",
+                Timestamp::new(start_ms),
+                "tag1-2",
+                12.0,
+                110.0,
+                "tag2-2",
+            ),
+        ];
+        let rows2 = [(
+            "key1",
+            Timestamp::new(start_ms + 10),
+            "tag1-3",
+            13.0,
+            110.0,
+            "tag2-3",
+        )];
+        for rows in [&rows1[..], &rows2[..]] {
+            let row_group = fixed_schema_table.rows_to_row_group(rows);
+            test_ctx.write_to_table(test_table, row_group).await;
+        }
+
+        // Scan the entries page by page.
+        let result = test_ctx.scan_wal(test_table, 0, None, 1).await;
+        assert_eq!(1, result.entries.len());
+        let entry = &result.entries[0];
+        assert_eq!("write", entry.kind);
+        assert_eq!(2, entry.num_rows);
+        assert_eq!(1, entry.preview.len());
+        let next_sequence = result.next_sequence.unwrap();
+        assert!(next_sequence > entry.sequence);
+
+        let result = test_ctx.scan_wal(test_table, next_sequence, None, 1).await;
+        assert_eq!(1, result.entries.len());
+        assert_eq!(1, result.entries[0].num_rows);
+        assert!(result.next_sequence.is_none());
+
+        // Only the rows in the time range are returned.
+        let time_range =
+            TimeRange::new(Timestamp::new(start_ms + 10), Timestamp::new(start_ms + 20));
+        let result = test_ctx.scan_wal(test_table, 0, time_range, 10).await;
+        assert_eq!(1, result.entries.len());
+        assert_eq!(next_sequence, result.entries[0].sequence);
+    });
+}
+
+#[test]
+fn test_write_racing_idle_close_rocks() {
+    let rocksdb_ctxs = rocksdb_ctxs();
+    for ctx in rocksdb_ctxs {
+        test_write_racing_idle_close(ctx);
+    }
+}
+
+/// The table closed by the idle table reaper after it's accessed by a write
+/// should be reopened by the write.
+fn test_write_racing_idle_close<T: EngineBuildContext>(engine_context: T) {
+    let env = TestEnv::builder().build();
+    let mut test_ctx = env.new_context(engine_context);
+
+    env.block_on(async {
+        test_ctx.open().await;
+
+        let test_table = "test_write_racing_idle_close";
+        let fixed_schema_table = test_ctx.create_fixed_schema_table(test_table).await;
+        let start_ms = test_ctx.start_ms();
+        let rows = [
+            (
+                "key1",
+                Timestamp::new(start_ms),
+                "tag1-1",
+                11.0,
+                110.0,
+                "tag2-1",
+            ),
+            (
+                "key2",
+                Timestamp::new(start_ms + 1),
+                "tag1-2",
+                12.0,
+                120.0,
+                "tag2-2",
+            ),
+        ];
+        test_ctx
+            .write_to_table(test_table, fixed_schema_table.rows_to_row_group(&rows[..1]))
+            .await;
+
+        // Hold the lock of the table, so the reaper waits for it before the write.
+        let table_data = test_ctx.table_data(test_table).await;
+        let serial_exec = table_data.serial_exec.lock().await;
+        let close = {
+            let instance = test_ctx.instance().clone();
+            let table_data = table_data.clone();
+            let config = IdleTableReaperConfig {
+                enable: true,
+                idle_threshold: ReadableDuration::secs(0),
+                min_open_duration: ReadableDuration::secs(0),
+                ..Default::default()
+            };
+            tokio::spawn(async move { instance.close_idle_table(&table_data, &config).await })
+        };
+        tokio::time::sleep(time::Duration::from_millis(100)).await;
+
+        let write = {
+            let table = test_ctx.table(test_table);
+            let row_group = fixed_schema_table.rows_to_row_group(&rows[1..]);
+            tokio::spawn(async move { table.write(WriteRequest { row_group }).await })
+        };
+        tokio::time::sleep(time::Duration::from_millis(100)).await;
+        assert!(!table_data.is_idle_closed());
+
+        drop(serial_exec);
+        assert!(close.await.unwrap().unwrap());
+        assert!(table_data.is_idle_closed());
+        assert_eq!(write.await.unwrap().unwrap(), 1);
+
+        util::check_read(
+            &test_ctx,
+            &fixed_schema_table,
+            "Test write racing idle close",
+            test_table,
+            &rows,
+        )
+        .await;
+    });
+}
@@ -34,7 +34,7 @@ use table_engine::{
     self,
     engine::{CreateTableParams, CreateTableRequest, TableState},
     predicate::Predicate,
-    table::{GetRequest, ReadOptions, ReadRequest, SchemaId, TableId, TableSeq},
+    table::{GetRequest, ReadOptions, ReadOrder, ReadRequest, SchemaId, TableId, TableSeq},
 };
 use time_ext::ReadableDuration;
 use trace_metric::MetricsCollector;
@@ -169,21 +169,41 @@ pub fn read_opts_list() -> Vec<ReadOptions> {
             batch_size: 1,
             read_parallelism: 1,
             deadline: None,
+            snapshot: None,
+            stats: None,
+            no_cache: false,
+            limit: None,
+            order: ReadOrder::None,
         },
         ReadOptions {
             batch_size: 1,
             read_parallelism: 4,
             deadline: None,
+            snapshot: None,
+            stats: None,
+            no_cache: false,
+            limit: None,
+            order: ReadOrder::None,
         },
         ReadOptions {
             batch_size: 100,
             read_parallelism: 1,
             deadline: None,
+            snapshot: None,
+            stats: None,
+            no_cache: false,
+            limit: None,
+            order: ReadOrder::None,
         },
         ReadOptions {
             batch_size: 100,
             read_parallelism: 4,
             deadline: None,
+            snapshot: None,
+            stats: None,
+            no_cache: false,
+            limit: None,
+            order: ReadOrder::None,
         },
     ]
 }
@@ -299,6 +319,19 @@ impl Builder {
         self
     }
 
+    pub fn table_schema(mut self, schema: Schema) -> Self {
+        self.create_request.params.table_schema = schema;
+        self
+    }
+
+    pub fn option(mut self, key: &str, value: &str) -> Self {
+        self.create_request
+            .params
+            .table_options
+            .insert(key.to_string(), value.to_string());
+        self
+    }
+
     pub fn build_fixed(self) -> FixedSchemaTable {
         FixedSchemaTable {
             create_request: self.create_request,
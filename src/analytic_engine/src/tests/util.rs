@@ -24,34 +24,45 @@ use common_types::{
     record_batch::RecordBatch,
     row::{Row, RowGroup},
     table::{ShardId, DEFAULT_SHARD_ID},
-    time::Timestamp,
+    time::{TimeRange, Timestamp},
+    SequenceNumber,
 };
 use futures::stream::StreamExt;
 use logger::info;
-use object_store::config::{LocalOptions, ObjectStoreOptions, StorageOptions};
+use object_store::{
+    admission::AdmissionPolicy,
+    config::{LocalOptions, ObjectStoreOptions, StorageOptions},
+};
 use runtime::PriorityRuntime;
 use size_ext::ReadableSize;
 use table_engine::{
     engine::{
-        CreateTableRequest, DropTableRequest, EngineRuntimes, OpenShardRequest, OpenTableRequest,
-        Result as EngineResult, TableDef, TableEngineRef,
+        CreateTableRequest, DropTableRequest, EngineRuntimes, OpenShardCheckReport,
+        OpenShardRequest, OpenTableRequest, Result as EngineResult, ScanWalRequest, ScanWalResult,
+        TableDef, TableEngineRef, TableWriteRequest, WriteTablesRequest,
     },
     table::{
-        AlterSchemaRequest, FlushRequest, GetRequest, ReadRequest, Result, SchemaId, TableId,
-        TableRef, WriteRequest,
+        AlterSchemaRequest, ExistsRequest, FlushRequest, GetRequest, ReadRequest, Result, SchemaId,
+        TableId, TableRef, WriteRequest,
     },
+    ANALYTIC_ENGINE_TYPE,
 };
 use tempfile::TempDir;
 use time_ext::ReadableDuration;
 use wal::{
     config::{Config as WalConfig, StorageConfig},
     manager::{OpenedWals, WalRuntimes, WalsOpener},
+    object_store_impl::{config::ObjectStoreStorageConfig, wal_manager::ObjectStoreWalsOpener},
     rocksdb_impl::{config::RocksDBStorageConfig, manager::RocksDBWalsOpener},
     table_kv_impl::wal::MemWalsOpener,
 };
 
 use crate::{
+    engine::build_space_id,
+    hook::EngineHooks,
+    instance::InstanceRef,
     setup::{EngineBuilder, TableEngineContext},
+    table::data::TableDataRef,
     tests::table::{self, FixedSchemaTable, RowTuple},
     Config, RecoverMode,
 };
@@ -111,10 +122,12 @@ pub struct TestContext<T> {
     wals_opener: T,
     runtimes: Arc<EngineRuntimes>,
     engine: Option<TableEngineRef>,
+    instance: Option<InstanceRef>,
     opened_wals: Option<OpenedWals>,
     schema_id: SchemaId,
     last_table_seq: u32,
     open_method: OpenTablesMethod,
+    hooks: EngineHooks,
 
     name_to_tables: HashMap<String, TableRef>,
 }
@@ -141,11 +154,14 @@ impl<T: WalsOpener> TestContext<T> {
             config: &self.config,
             engine_runtimes: self.runtimes.clone(),
             opened_wals: opened_wals.clone(),
+            hooks: self.hooks.clone(),
         };
         self.opened_wals = Some(opened_wals);
 
-        let TableEngineContext { table_engine, .. } = engine_builder.build().await.unwrap();
+        let (TableEngineContext { table_engine, .. }, instance) =
+            engine_builder.build_with_instance().await.unwrap();
         self.engine = Some(table_engine);
+        self.instance = Some(instance);
     }
 
     pub async fn reopen(&mut self) {
@@ -155,6 +171,7 @@ impl<T: WalsOpener> TestContext<T> {
 
             // Close engine.
             let engine = self.engine.take().unwrap();
+            self.instance = None;
             engine.close().await.unwrap();
         }
 
@@ -175,6 +192,7 @@ impl<T: WalsOpener> TestContext<T> {
 
             // Close engine.
             let engine = self.engine.take().unwrap();
+            self.instance = None;
             engine.close().await.unwrap();
         }
 
@@ -207,6 +225,7 @@ impl<T: WalsOpener> TestContext<T> {
 
             // Close engine.
             let engine = self.engine.take().unwrap();
+            self.instance = None;
             engine.close().await.unwrap();
         }
 
@@ -246,6 +265,52 @@ impl<T: WalsOpener> TestContext<T> {
         }
     }
 
+    pub async fn check_open_shard(
+        &self,
+        table_infos: Vec<(TableId, &str)>,
+        shard_id: ShardId,
+    ) -> OpenShardCheckReport {
+        let table_defs = table_infos
+            .into_iter()
+            .map(|table| TableDef {
+                catalog_name: "horaedb".to_string(),
+                schema_name: "public".to_string(),
+                schema_id: self.schema_id,
+                id: table.0,
+                name: table.1.to_string(),
+            })
+            .collect();
+
+        let request = OpenShardRequest {
+            shard_id,
+            table_defs,
+            engine: table_engine::ANALYTIC_ENGINE_TYPE.to_string(),
+        };
+
+        self.engine().check_open_shard(request).await.unwrap()
+    }
+
+    pub async fn scan_wal(
+        &self,
+        table_name: &str,
+        start_sequence: SequenceNumber,
+        time_range: Option<TimeRange>,
+        limit: usize,
+    ) -> ScanWalResult {
+        let request = ScanWalRequest {
+            engine: table_engine::ANALYTIC_ENGINE_TYPE.to_string(),
+            schema_id: self.schema_id,
+            table_name: table_name.to_string(),
+            start_sequence,
+            end_sequence: None,
+            time_range,
+            limit,
+            max_preview_rows: 1,
+        };
+
+        self.engine().scan_wal(request).await.unwrap()
+    }
+
     async fn open_table(&mut self, table_id: TableId, table_name: &str) {
         let table = self
             .engine()
@@ -316,12 +381,22 @@ impl<T: WalsOpener> TestContext<T> {
     }
 
     pub async fn create_fixed_schema_table(&mut self, table_name: &str) -> FixedSchemaTable {
-        let fixed_schema_table = FixedSchemaTable::builder()
+        self.create_fixed_schema_table_with(table_name, |builder| builder)
+            .await
+    }
+
+    /// Create the table whose request is customized by `customize`.
+    pub async fn create_fixed_schema_table_with(
+        &mut self,
+        table_name: &str,
+        customize: impl FnOnce(table::Builder) -> table::Builder,
+    ) -> FixedSchemaTable {
+        let builder = FixedSchemaTable::builder()
             .schema_id(self.schema_id)
             .table_name(table_name.to_string())
             .table_id(self.next_table_id())
-            .ttl("7d".parse::<ReadableDuration>().unwrap())
-            .build_fixed();
+            .ttl("7d".parse::<ReadableDuration>().unwrap());
+        let fixed_schema_table = customize(builder).build_fixed();
 
         self.create_table(fixed_schema_table.create_request().clone())
             .await;
@@ -342,6 +417,23 @@ impl<T: WalsOpener> TestContext<T> {
         table.write(WriteRequest { row_group }).await.unwrap();
     }
 
+    pub async fn write_to_tables_atomically(&self, requests: Vec<(&str, RowGroup)>) -> Vec<usize> {
+        let requests = requests
+            .into_iter()
+            .map(|(table_name, row_group)| TableWriteRequest {
+                schema_id: self.schema_id,
+                table_id: self.table(table_name).id(),
+                request: WriteRequest { row_group },
+            })
+            .collect();
+        let request = WriteTablesRequest {
+            engine: ANALYTIC_ENGINE_TYPE.to_string(),
+            requests,
+        };
+
+        self.engine().write_tables(request).await.unwrap()
+    }
+
     pub async fn read_table(
         &self,
         table_name: &str,
@@ -387,6 +479,12 @@ impl<T: WalsOpener> TestContext<T> {
         table.get(request).await.unwrap()
     }
 
+    pub async fn maybe_exists_in_table(&self, table_name: &str, request: ExistsRequest) -> bool {
+        let table = self.table(table_name);
+
+        table.maybe_exists(request).await.unwrap()
+    }
+
     pub async fn flush_table(&self, table_name: &str) {
         let table = self.table(table_name);
 
@@ -434,6 +532,23 @@ impl<T: WalsOpener> TestContext<T> {
         self.engine.as_ref().unwrap()
     }
 
+    /// Get the data of the opened table.
+    pub(crate) async fn table_data(&self, table_name: &str) -> TableDataRef {
+        let space_id = build_space_id(self.schema_id);
+        self.instance()
+            .find_table(space_id, table_name)
+            .await
+            .unwrap()
+            .unwrap()
+            .table_data()
+            .clone()
+    }
+
+    #[inline]
+    pub(crate) fn instance(&self) -> &InstanceRef {
+        self.instance.as_ref().unwrap()
+    }
+
     fn next_table_id(&mut self) -> TableId {
         self.last_table_seq += 1;
         table::new_table_id(2, self.last_table_seq)
@@ -451,6 +566,11 @@ impl<T> TestContext<T> {
         &mut self.config
     }
 
+    /// Hooks registered to the engine when it's opened.
+    pub fn hooks_mut(&mut self) -> &mut EngineHooks {
+        &mut self.hooks
+    }
+
     pub fn clone_engine(&self) -> TableEngineRef {
         self.engine.clone().unwrap()
     }
@@ -479,11 +599,13 @@ impl TestEnv {
             wals_opener,
             runtimes: self.runtimes.clone(),
             engine: None,
+            instance: None,
             opened_wals: None,
             schema_id: SchemaId::from_u32(100),
             last_table_seq: 1,
             name_to_tables: HashMap::new(),
             open_method: build_context.open_method(),
+            hooks: EngineHooks::default(),
         }
     }
 
@@ -504,6 +626,7 @@ impl Builder {
             storage: StorageOptions {
                 mem_cache_capacity: ReadableSize::mb(0),
                 mem_cache_partition_bits: 0,
+                mem_cache_admission: AdmissionPolicy::Lru,
                 disk_cache_dir: "".to_string(),
                 disk_cache_capacity: ReadableSize::mb(0),
                 disk_cache_page_size: ReadableSize::mb(0),
@@ -513,6 +636,8 @@ impl Builder {
                     max_retries: 3,
                     timeout: Default::default(),
                 }),
+                circuit_breaker: Default::default(),
+                restore_retry: Default::default(),
             },
             wal: WalConfig {
                 storage: StorageConfig::RocksDB(Box::new(RocksDBStorageConfig {
@@ -520,6 +645,9 @@ impl Builder {
                     ..Default::default()
                 })),
                 disable_data: false,
+                circuit_breaker: Default::default(),
+                compression: Default::default(),
+                group_commit: Default::default(),
             },
             ..Default::default()
         };
@@ -584,6 +712,7 @@ impl Default for RocksDBEngineBuildContext {
             storage: StorageOptions {
                 mem_cache_capacity: ReadableSize::mb(0),
                 mem_cache_partition_bits: 0,
+                mem_cache_admission: AdmissionPolicy::Lru,
                 disk_cache_dir: "".to_string(),
                 disk_cache_capacity: ReadableSize::mb(0),
                 disk_cache_page_size: ReadableSize::mb(0),
@@ -593,6 +722,8 @@ impl Default for RocksDBEngineBuildContext {
                     max_retries: 3,
                     timeout: Default::default(),
                 }),
+                circuit_breaker: Default::default(),
+                restore_retry: Default::default(),
             },
             wal: WalConfig {
                 storage: StorageConfig::RocksDB(Box::new(RocksDBStorageConfig {
@@ -600,6 +731,9 @@ impl Default for RocksDBEngineBuildContext {
                     ..Default::default()
                 })),
                 disable_data: false,
+                circuit_breaker: Default::default(),
+                compression: Default::default(),
+                group_commit: Default::default(),
             },
             ..Default::default()
         };
@@ -619,6 +753,7 @@ impl Clone for RocksDBEngineBuildContext {
         let storage = StorageOptions {
             mem_cache_capacity: ReadableSize::mb(0),
             mem_cache_partition_bits: 0,
+            mem_cache_admission: AdmissionPolicy::Lru,
             disk_cache_dir: "".to_string(),
             disk_cache_capacity: ReadableSize::mb(0),
             disk_cache_page_size: ReadableSize::mb(0),
@@ -628,6 +763,8 @@ impl Clone for RocksDBEngineBuildContext {
                 max_retries: 3,
                 timeout: Default::default(),
             }),
+            circuit_breaker: Default::default(),
+            restore_retry: Default::default(),
         };
 
         config.storage = storage;
@@ -637,6 +774,9 @@ impl Clone for RocksDBEngineBuildContext {
                 ..Default::default()
             })),
             disable_data: false,
+            circuit_breaker: Default::default(),
+            compression: Default::default(),
+            group_commit: Default::default(),
         };
         Self {
             config,
@@ -685,6 +825,7 @@ impl Default for MemoryEngineBuildContext {
             storage: StorageOptions {
                 mem_cache_capacity: ReadableSize::mb(0),
                 mem_cache_partition_bits: 0,
+                mem_cache_admission: AdmissionPolicy::Lru,
                 disk_cache_dir: "".to_string(),
                 disk_cache_capacity: ReadableSize::mb(0),
                 disk_cache_page_size: ReadableSize::mb(0),
@@ -694,10 +835,15 @@ impl Default for MemoryEngineBuildContext {
                     max_retries: 3,
                     timeout: Default::default(),
                 }),
+                circuit_breaker: Default::default(),
+                restore_retry: Default::default(),
             },
             wal: WalConfig {
                 storage: StorageConfig::Obkv(Box::default()),
                 disable_data: false,
+                circuit_breaker: Default::default(),
+                compression: Default::default(),
+                group_commit: Default::default(),
             },
             ..Default::default()
         };
@@ -725,12 +871,105 @@ impl EngineBuildContext for MemoryEngineBuildContext {
     }
 }
 
+pub struct ObjectStoreEngineBuildContext {
+    config: Config,
+    open_method: OpenTablesMethod,
+}
+
+impl ObjectStoreEngineBuildContext {
+    pub fn new(mode: RecoverMode, open_method: OpenTablesMethod) -> Self {
+        let mut context = Self::default();
+        context.config.recover_mode = mode;
+        context.open_method = open_method;
+
+        context
+    }
+}
+
+impl Default for ObjectStoreEngineBuildContext {
+    fn default() -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        let data_dir = dir.path().to_str().unwrap().to_string();
+
+        let config = Config {
+            storage: StorageOptions {
+                mem_cache_capacity: ReadableSize::mb(0),
+                mem_cache_partition_bits: 0,
+                mem_cache_admission: AdmissionPolicy::Lru,
+                disk_cache_dir: "".to_string(),
+                disk_cache_capacity: ReadableSize::mb(0),
+                disk_cache_page_size: ReadableSize::mb(0),
+                disk_cache_partition_bits: 0,
+                object_store: ObjectStoreOptions::Local(LocalOptions {
+                    data_dir: data_dir.clone(),
+                    max_retries: 3,
+                    timeout: Default::default(),
+                }),
+                circuit_breaker: Default::default(),
+                restore_retry: Default::default(),
+            },
+            wal: WalConfig {
+                // Keep the wal apart from the ssts and manifest snapshots.
+                storage: StorageConfig::ObjectStore(Box::new(ObjectStoreStorageConfig {
+                    object_store: ObjectStoreOptions::Local(LocalOptions {
+                        data_dir: format!("{data_dir}/wal"),
+                        max_retries: 3,
+                        timeout: Default::default(),
+                    }),
+                })),
+                disable_data: false,
+                circuit_breaker: Default::default(),
+                compression: Default::default(),
+                group_commit: Default::default(),
+            },
+            ..Default::default()
+        };
+
+        Self {
+            config,
+            open_method: OpenTablesMethod::WithOpenTable,
+        }
+    }
+}
+
+impl Clone for ObjectStoreEngineBuildContext {
+    fn clone(&self) -> Self {
+        // The cloned context writes into new directories.
+        let Config { storage, wal, .. } = Self::default().config;
+        Self {
+            config: Config {
+                storage,
+                wal,
+                ..self.config.clone()
+            },
+            open_method: self.open_method,
+        }
+    }
+}
+
+impl EngineBuildContext for ObjectStoreEngineBuildContext {
+    type WalsOpener = ObjectStoreWalsOpener;
+
+    fn wals_opener(&self) -> Self::WalsOpener {
+        ObjectStoreWalsOpener
+    }
+
+    fn config(&self) -> Config {
+        self.config.clone()
+    }
+
+    fn open_method(&self) -> OpenTablesMethod {
+        self.open_method
+    }
+}
+
 pub fn rocksdb_ctxs() -> Vec<RocksDBEngineBuildContext> {
     vec![
         RocksDBEngineBuildContext::new(RecoverMode::TableBased, OpenTablesMethod::WithOpenTable),
         RocksDBEngineBuildContext::new(RecoverMode::ShardBased, OpenTablesMethod::WithOpenTable),
         RocksDBEngineBuildContext::new(RecoverMode::TableBased, OpenTablesMethod::WithOpenShard),
         RocksDBEngineBuildContext::new(RecoverMode::ShardBased, OpenTablesMethod::WithOpenShard),
+        RocksDBEngineBuildContext::new(RecoverMode::Auto, OpenTablesMethod::WithOpenShard),
     ]
 }
 
@@ -740,5 +979,19 @@ pub fn memory_ctxs() -> Vec<MemoryEngineBuildContext> {
         MemoryEngineBuildContext::new(RecoverMode::ShardBased, OpenTablesMethod::WithOpenTable),
         MemoryEngineBuildContext::new(RecoverMode::TableBased, OpenTablesMethod::WithOpenShard),
         MemoryEngineBuildContext::new(RecoverMode::ShardBased, OpenTablesMethod::WithOpenShard),
+        MemoryEngineBuildContext::new(RecoverMode::Auto, OpenTablesMethod::WithOpenShard),
+    ]
+}
+
+pub fn object_store_ctxs() -> Vec<ObjectStoreEngineBuildContext> {
+    vec![
+        ObjectStoreEngineBuildContext::new(
+            RecoverMode::TableBased,
+            OpenTablesMethod::WithOpenTable,
+        ),
+        ObjectStoreEngineBuildContext::new(
+            RecoverMode::ShardBased,
+            OpenTablesMethod::WithOpenShard,
+        ),
     ]
 }
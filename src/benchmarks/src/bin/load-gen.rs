@@ -0,0 +1,102 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::{process, sync::Arc};
+
+use benchmarks::{
+    load_gen::{self, EmbeddedTarget, HttpTarget, LoadGenConfig, LoadTarget, TargetKind},
+    util,
+};
+use clap::{Arg, Command};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+struct Config {
+    runtime_thread_num: usize,
+    load_gen: LoadGenConfig,
+    /// Config of the instance opened by the `Embedded` target.
+    embedded: horaedb::config::Config,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Self {
+            runtime_thread_num: 4,
+            load_gen: LoadGenConfig::default(),
+            embedded: horaedb::config::Config::default(),
+        }
+    }
+}
+
+fn main() {
+    let matches = Command::new("Load Generator")
+        .arg(
+            Arg::new("config")
+                .short('c')
+                .long("config")
+                .required(false)
+                .num_args(1)
+                .help("Set configuration file, eg: \"/path/load_gen.toml\""),
+        )
+        .get_matches();
+
+    let config: Config = match matches.get_one::<String>("config") {
+        Some(path) => {
+            let mut toml_buf = String::new();
+            toml_ext::parse_toml_from_path(path, &mut toml_buf).expect("Failed to parse config.")
+        }
+        None => Config::default(),
+    };
+
+    // The embedded instance is opened outside of the runtime of the load.
+    let embedded = match config.load_gen.target {
+        TargetKind::Http => {
+            env_logger::init();
+            None
+        }
+        TargetKind::Embedded => {
+            let log_runtime =
+                logger::init_log(&config.embedded.logger).expect("Failed to init log.");
+            match EmbeddedTarget::open(config.embedded, log_runtime) {
+                Ok(target) => Some(Arc::new(target)),
+                Err(e) => {
+                    eprintln!("Failed to open embedded instance, err:{e}");
+                    process::exit(1);
+                }
+            }
+        }
+    };
+    let target: Arc<dyn LoadTarget> = match &embedded {
+        Some(target) => target.clone(),
+        None => Arc::new(HttpTarget::new(&config.load_gen.endpoint)),
+    };
+
+    let runtime = util::new_runtime(config.runtime_thread_num);
+    let result = runtime.block_on(load_gen::run(config.load_gen, target));
+    if let Some(target) = embedded.and_then(Arc::into_inner) {
+        target.close();
+    }
+
+    match result {
+        Ok(report) => println!("{}", report.pretty()),
+        Err(e) => {
+            eprintln!("Load generation failed, err:{e}");
+            process::exit(1);
+        }
+    }
+}
@@ -20,6 +20,7 @@
 use common_types::SequenceNumber;
 
 pub mod config;
+pub mod load_gen;
 pub mod merge_memtable_bench;
 pub mod merge_sst_bench;
 pub mod parquet_bench;
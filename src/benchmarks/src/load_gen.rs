@@ -0,0 +1,489 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Load generator issuing time-series writes and query mixes against a
+//! running server or an embedded instance, reporting throughput and latency
+//! percentiles.
+
+use std::{
+    fmt::Write as _,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use common_types::time::Timestamp;
+use generic_error::{BoxError, GenericError, GenericResult};
+use horaedb::embedded::{self, Horaedb};
+use logger::{info, warn, RuntimeLevel};
+use macros::define_result;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use time_ext::ReadableDuration;
+use tokio::{sync::Mutex, task::JoinError};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Failed to create table, table:{}, err:{}", table, source))]
+    CreateTable { table: String, source: GenericError },
+
+    #[snafu(display("Load generation task failed, err:{}", source))]
+    JoinTask { source: JoinError },
+
+    #[snafu(display("Failed to open embedded instance, err:{}", source))]
+    OpenEmbedded { source: embedded::Error },
+}
+
+define_result!(Error);
+
+/// Placeholder in the query templates replaced by the table name.
+const TABLE_PLACEHOLDER: &str = "{table}";
+
+/// Kind of the target the load is generated against.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+pub enum TargetKind {
+    /// A running server accessed by its http sql api.
+    #[default]
+    Http,
+    /// An instance opened in process by the embedded mode.
+    Embedded,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct LoadGenConfig {
+    pub target: TargetKind,
+    /// Http endpoint of the server, eg: `127.0.0.1:5440`, only used by the
+    /// `Http` target.
+    pub endpoint: String,
+    pub table: String,
+    /// Create the table before generating load.
+    pub create_table: bool,
+    /// Total duration of the load.
+    pub duration: ReadableDuration,
+
+    /// Number of distinct series (tag combinations) to write.
+    pub num_series: usize,
+    pub rows_per_write: usize,
+    pub write_concurrency: usize,
+
+    pub query_concurrency: usize,
+    /// Weighted query mix, `{table}` in the sql will be replaced by the
+    /// table name.
+    pub queries: Vec<QueryTemplate>,
+}
+
+impl Default for LoadGenConfig {
+    fn default() -> Self {
+        Self {
+            target: TargetKind::Http,
+            endpoint: "127.0.0.1:5440".to_string(),
+            table: "load_gen".to_string(),
+            create_table: true,
+            duration: ReadableDuration::secs(60),
+            num_series: 1000,
+            rows_per_write: 100,
+            write_concurrency: 4,
+            query_concurrency: 1,
+            queries: vec![QueryTemplate {
+                sql: "SELECT count(*) FROM {table}".to_string(),
+                weight: 1,
+            }],
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct QueryTemplate {
+    pub sql: String,
+    pub weight: usize,
+}
+
+/// Target the load is generated against.
+#[async_trait]
+pub trait LoadTarget: Send + Sync {
+    async fn execute(&self, sql: &str) -> GenericResult<()>;
+}
+
+/// Target accessing a running server by its http sql api.
+pub struct HttpTarget {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl HttpTarget {
+    pub fn new(endpoint: &str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: format!("http://{endpoint}/sql"),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct SqlRequest<'a> {
+    query: &'a str,
+}
+
+#[async_trait]
+impl LoadTarget for HttpTarget {
+    async fn execute(&self, sql: &str) -> GenericResult<()> {
+        let resp = self
+            .client
+            .post(&self.url)
+            .json(&SqlRequest { query: sql })
+            .send()
+            .await
+            .box_err()?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(format!("sql failed, status:{status}, body:{body}").into());
+        }
+
+        Ok(())
+    }
+}
+
+/// Target accessing an instance opened in process by the embedded mode, which
+/// measures the engine without the network services.
+pub struct EmbeddedTarget {
+    horaedb: Horaedb,
+    schema: String,
+}
+
+impl EmbeddedTarget {
+    /// Open the embedded instance, the sqls are executed in its default
+    /// schema.
+    ///
+    /// It should be called outside of any async context, like [Horaedb::open].
+    pub fn open(config: horaedb::config::Config, log_runtime: RuntimeLevel) -> Result<Self> {
+        let horaedb = Horaedb::open(config, log_runtime).context(OpenEmbedded)?;
+        let schema = horaedb.default_schema_name();
+
+        Ok(Self { horaedb, schema })
+    }
+
+    /// Close the embedded instance outside of any async context.
+    pub fn close(self) {
+        self.horaedb.close()
+    }
+}
+
+#[async_trait]
+impl LoadTarget for EmbeddedTarget {
+    async fn execute(&self, sql: &str) -> GenericResult<()> {
+        self.horaedb
+            .execute_sql(&self.schema, sql)
+            .await
+            .box_err()?;
+
+        Ok(())
+    }
+}
+
+/// Latencies recorded for one kind of operation.
+#[derive(Default)]
+struct LatencyRecorder {
+    latencies: Mutex<Vec<Duration>>,
+    rows: AtomicU64,
+    errors: AtomicU64,
+}
+
+impl LatencyRecorder {
+    async fn record(&self, latency: Duration, rows: u64) {
+        self.latencies.lock().await.push(latency);
+        self.rows.fetch_add(rows, Ordering::Relaxed);
+    }
+
+    fn record_error(&self) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    async fn summary(&self, elapsed: Duration) -> LatencySummary {
+        let latencies = self.latencies.lock().await.clone();
+        LatencySummary::new(
+            latencies,
+            self.rows.load(Ordering::Relaxed),
+            self.errors.load(Ordering::Relaxed),
+            elapsed,
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct LatencySummary {
+    pub requests: u64,
+    pub errors: u64,
+    pub requests_per_sec: f64,
+    pub rows_per_sec: f64,
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+impl LatencySummary {
+    fn new(mut latencies: Vec<Duration>, rows: u64, errors: u64, elapsed: Duration) -> Self {
+        latencies.sort_unstable();
+
+        // The nearest-rank percentile.
+        let percentile = |p: f64| -> Duration {
+            if latencies.is_empty() {
+                return Duration::ZERO;
+            }
+            let idx = ((latencies.len() as f64 * p).ceil() as usize).saturating_sub(1);
+            latencies[idx.min(latencies.len() - 1)]
+        };
+
+        let secs = elapsed.as_secs_f64().max(f64::EPSILON);
+        Self {
+            requests: latencies.len() as u64,
+            errors,
+            requests_per_sec: latencies.len() as f64 / secs,
+            rows_per_sec: rows as f64 / secs,
+            p50: percentile(0.5),
+            p90: percentile(0.9),
+            p99: percentile(0.99),
+            max: latencies.last().copied().unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct LoadGenReport {
+    pub elapsed: Duration,
+    pub write: LatencySummary,
+    pub query: LatencySummary,
+}
+
+impl LoadGenReport {
+    pub fn pretty(&self) -> String {
+        let mut buf = String::new();
+        let _ = writeln!(buf, "elapsed:{:?}", self.elapsed);
+        for (name, summary) in [("write", &self.write), ("query", &self.query)] {
+            let _ = writeln!(
+                buf,
+                "{name}: requests:{}, errors:{}, qps:{:.2}, rows/s:{:.2}, p50:{:?}, p90:{:?}, p99:{:?}, max:{:?}",
+                summary.requests,
+                summary.errors,
+                summary.requests_per_sec,
+                summary.rows_per_sec,
+                summary.p50,
+                summary.p90,
+                summary.p99,
+                summary.max,
+            );
+        }
+        buf
+    }
+}
+
+fn create_table_sql(table: &str) -> String {
+    format!(
+        "CREATE TABLE IF NOT EXISTS {table} (
+            t timestamp NOT NULL,
+            host string TAG,
+            region string TAG,
+            value double,
+            TIMESTAMP KEY(t)
+        ) ENGINE=Analytic"
+    )
+}
+
+fn build_insert_sql(table: &str, num_series: usize, rows: usize) -> String {
+    let mut rng = rand::thread_rng();
+    let now = Timestamp::now().as_i64();
+    let mut sql = format!("INSERT INTO {table} (t, host, region, value) VALUES ");
+    for i in 0..rows {
+        if i > 0 {
+            sql.push(',');
+        }
+        let series = rng.gen_range(0..num_series.max(1));
+        let value: f64 = rng.gen();
+        let _ = write!(
+            sql,
+            "({}, 'host-{series}', 'region-{}', {value})",
+            now + i as i64,
+            series % 16,
+        );
+    }
+    sql
+}
+
+/// Pick a query from the weighted templates.
+fn pick_query<'a>(queries: &'a [QueryTemplate], total_weight: usize) -> Option<&'a QueryTemplate> {
+    if total_weight == 0 {
+        return None;
+    }
+
+    let point = rand::thread_rng().gen_range(0..total_weight);
+    select_query(queries, point)
+}
+
+/// Select the query whose weight range covers the `point`, the ranges of the
+/// queries are laid out in order.
+fn select_query(queries: &[QueryTemplate], mut point: usize) -> Option<&QueryTemplate> {
+    for query in queries {
+        if point < query.weight {
+            return Some(query);
+        }
+        point -= query.weight;
+    }
+    None
+}
+
+/// Generate the load described by `config` against the `target` and report
+/// the collected statistics.
+pub async fn run(config: LoadGenConfig, target: Arc<dyn LoadTarget>) -> Result<LoadGenReport> {
+    info!("Load generation starts, config:{:?}", config);
+
+    if config.create_table {
+        target
+            .execute(&create_table_sql(&config.table))
+            .await
+            .context(CreateTable {
+                table: &config.table,
+            })?;
+    }
+
+    let write_recorder = Arc::new(LatencyRecorder::default());
+    let query_recorder = Arc::new(LatencyRecorder::default());
+    let begin = Instant::now();
+    let deadline = begin + config.duration.0;
+    let config = Arc::new(config);
+
+    let mut handles = Vec::with_capacity(config.write_concurrency + config.query_concurrency);
+    for _ in 0..config.write_concurrency {
+        let (config, target, recorder) = (config.clone(), target.clone(), write_recorder.clone());
+        handles.push(tokio::spawn(async move {
+            while Instant::now() < deadline {
+                let sql = build_insert_sql(&config.table, config.num_series, config.rows_per_write);
+                let start = Instant::now();
+                match target.execute(&sql).await {
+                    Ok(_) => {
+                        recorder
+                            .record(start.elapsed(), config.rows_per_write as u64)
+                            .await
+                    }
+                    Err(e) => {
+                        warn!("Load generation write failed, err:{e}");
+                        recorder.record_error();
+                    }
+                }
+            }
+        }));
+    }
+
+    let total_weight: usize = config.queries.iter().map(|q| q.weight).sum();
+    for _ in 0..config.query_concurrency {
+        let (config, target, recorder) = (config.clone(), target.clone(), query_recorder.clone());
+        handles.push(tokio::spawn(async move {
+            while Instant::now() < deadline {
+                let Some(query) = pick_query(&config.queries, total_weight) else {
+                    return;
+                };
+                let sql = query.sql.replace(TABLE_PLACEHOLDER, &config.table);
+                let start = Instant::now();
+                match target.execute(&sql).await {
+                    Ok(_) => recorder.record(start.elapsed(), 0).await,
+                    Err(e) => {
+                        warn!("Load generation query failed, sql:{sql}, err:{e}");
+                        recorder.record_error();
+                    }
+                }
+            }
+        }));
+    }
+
+    for handle in handles {
+        handle.await.context(JoinTask)?;
+    }
+
+    let elapsed = begin.elapsed();
+    Ok(LoadGenReport {
+        elapsed,
+        write: write_recorder.summary(elapsed).await,
+        query: query_recorder.summary(elapsed).await,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_queries(weights: &[usize]) -> Vec<QueryTemplate> {
+        weights
+            .iter()
+            .enumerate()
+            .map(|(i, weight)| QueryTemplate {
+                sql: format!("query-{i}"),
+                weight: *weight,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_select_query() {
+        let queries = new_queries(&[1, 0, 3]);
+        let cases = [(0, "query-0"), (1, "query-2"), (3, "query-2")];
+        for (point, expect) in cases {
+            assert_eq!(expect, select_query(&queries, point).unwrap().sql);
+        }
+        assert!(select_query(&queries, 4).is_none());
+    }
+
+    #[test]
+    fn test_pick_query() {
+        let queries = new_queries(&[1, 0, 3]);
+        assert!(pick_query(&queries, 0).is_none());
+        // The query without weight is never picked.
+        for _ in 0..100 {
+            let query = pick_query(&queries, 4).unwrap();
+            assert_ne!("query-1", query.sql);
+        }
+    }
+
+    #[test]
+    fn test_latency_summary() {
+        // 100 latencies from 1ms to 100ms in reversed order.
+        let latencies = (1..=100).rev().map(Duration::from_millis).collect();
+        let summary = LatencySummary::new(latencies, 1000, 2, Duration::from_secs(10));
+        assert_eq!(100, summary.requests);
+        assert_eq!(2, summary.errors);
+        assert_eq!(10.0, summary.requests_per_sec);
+        assert_eq!(100.0, summary.rows_per_sec);
+        assert_eq!(Duration::from_millis(50), summary.p50);
+        assert_eq!(Duration::from_millis(90), summary.p90);
+        assert_eq!(Duration::from_millis(99), summary.p99);
+        assert_eq!(Duration::from_millis(100), summary.max);
+
+        let summary = LatencySummary::new(vec![Duration::from_millis(7)], 0, 0, Duration::ZERO);
+        assert_eq!(Duration::from_millis(7), summary.p50);
+        assert_eq!(Duration::from_millis(7), summary.p99);
+
+        let summary = LatencySummary::new(vec![], 0, 1, Duration::from_secs(1));
+        assert_eq!(0, summary.requests);
+        assert_eq!(Duration::ZERO, summary.p99);
+        assert_eq!(Duration::ZERO, summary.max);
+    }
+}
@@ -218,6 +218,7 @@ fn mock_sst_read_options_builder(
         background_read_parallelism: 1,
         max_record_batches_in_flight: 1024,
         num_streams_to_prefetch: 0,
+        get_range_coalesce_gap: None,
     };
 
     SstReadOptionsBuilder::new(
@@ -82,6 +82,7 @@ impl MergeSstBench {
             background_read_parallelism: 1,
             max_record_batches_in_flight: 1024,
             num_streams_to_prefetch: 0,
+            get_range_coalesce_gap: None,
         };
 
         let scan_type = ScanType::Query;
@@ -215,6 +216,7 @@ impl MergeSstBench {
             sst_read_options_builder: self.sst_read_options_builder.clone(),
             store_picker: &store_picker,
             num_streams_to_prefetch: 0,
+            max_visible_sequence: None,
         })
         .ssts(vec![self.file_handles.clone()]);
 
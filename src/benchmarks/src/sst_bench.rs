@@ -62,6 +62,7 @@ impl SstBench {
             background_read_parallelism: 1,
             max_record_batches_in_flight: 1024,
             num_streams_to_prefetch: 0,
+            get_range_coalesce_gap: None,
         };
         let sst_read_options_builder = SstReadOptionsBuilder::new(
             ScanType::Query,
@@ -73,6 +73,7 @@ async fn create_sst_from_stream(config: SstConfig, record_batch_stream: RecordBa
         compression: config.compression,
         max_buffer_size: 1024 * 1024 * 10,
         column_stats: Default::default(),
+        field_groups: Vec::new(),
     };
 
     info!(
@@ -126,6 +127,7 @@ pub async fn rebuild_sst(config: RebuildSstConfig, runtime: Arc<Runtime>) {
         background_read_parallelism: 1,
         max_record_batches_in_flight: 1024,
         num_streams_to_prefetch: 2,
+        get_range_coalesce_gap: None,
     };
 
     let fetched_schema = projected_schema.to_record_schema();
@@ -138,6 +140,7 @@ pub async fn rebuild_sst(config: RebuildSstConfig, runtime: Arc<Runtime>) {
         predicate: config.predicate.into_predicate(),
         meta_cache: None,
         scan_options,
+        query_stats: None,
         runtime,
         row_projector_builder,
     };
@@ -240,6 +243,7 @@ pub async fn merge_sst(config: MergeSstConfig, runtime: Arc<Runtime>) {
         background_read_parallelism: 1,
         max_record_batches_in_flight: 1024,
         num_streams_to_prefetch: 0,
+        get_range_coalesce_gap: None,
     };
 
     let request_id = RequestId::next_id();
@@ -20,6 +20,7 @@
 use std::{collections::HashMap, future::Future, sync::Arc};
 
 use analytic_engine::{
+    hook::EngineHooks,
     memtable::{key::KeySequence, MemTableRef, PutContext},
     setup::{EngineBuilder, TableEngineContext},
     space::SpaceId,
@@ -49,6 +50,7 @@ use common_types::{
 use futures::stream::StreamExt;
 use macros::define_result;
 use object_store::{
+    admission::AdmissionPolicy,
     config::{LocalOptions, ObjectStoreOptions, StorageOptions},
     ObjectStoreRef, Path,
 };
@@ -148,6 +150,7 @@ pub async fn load_sst_to_memtable(
         background_read_parallelism: 1,
         max_record_batches_in_flight: 1024,
         num_streams_to_prefetch: 0,
+        get_range_coalesce_gap: None,
     };
     let projected_schema = ProjectedSchema::no_projection(schema.clone());
 
@@ -161,6 +164,7 @@ pub async fn load_sst_to_memtable(
         predicate: Arc::new(Predicate::empty()),
         meta_cache: None,
         scan_options,
+        query_stats: None,
         runtime,
         row_projector_builder,
     };
@@ -302,6 +306,7 @@ impl Builder {
             storage: StorageOptions {
                 mem_cache_capacity: ReadableSize::mb(0),
                 mem_cache_partition_bits: 0,
+                mem_cache_admission: AdmissionPolicy::Lru,
                 disk_cache_dir: "".to_string(),
                 disk_cache_capacity: ReadableSize::mb(0),
                 disk_cache_page_size: ReadableSize::mb(0),
@@ -311,6 +316,8 @@ impl Builder {
                     max_retries: 3,
                     timeout: Default::default(),
                 }),
+                circuit_breaker: Default::default(),
+                restore_retry: Default::default(),
             },
             wal: WalConfig {
                 storage: StorageConfig::RocksDB(Box::new(RocksDBStorageConfig {
@@ -318,6 +325,9 @@ impl Builder {
                     ..Default::default()
                 })),
                 disable_data: false,
+                circuit_breaker: Default::default(),
+                compression: Default::default(),
+                group_commit: Default::default(),
             },
             ..Default::default()
         };
@@ -382,6 +392,7 @@ impl Default for RocksDBEngineBuildContext {
             storage: StorageOptions {
                 mem_cache_capacity: ReadableSize::mb(0),
                 mem_cache_partition_bits: 0,
+                mem_cache_admission: AdmissionPolicy::Lru,
                 disk_cache_dir: "".to_string(),
                 disk_cache_capacity: ReadableSize::mb(0),
                 disk_cache_page_size: ReadableSize::mb(0),
@@ -391,6 +402,8 @@ impl Default for RocksDBEngineBuildContext {
                     max_retries: 3,
                     timeout: Default::default(),
                 }),
+                circuit_breaker: Default::default(),
+                restore_retry: Default::default(),
             },
             wal: WalConfig {
                 storage: StorageConfig::RocksDB(Box::new(RocksDBStorageConfig {
@@ -398,6 +411,9 @@ impl Default for RocksDBEngineBuildContext {
                     ..Default::default()
                 })),
                 disable_data: false,
+                circuit_breaker: Default::default(),
+                compression: Default::default(),
+                group_commit: Default::default(),
             },
             ..Default::default()
         };
@@ -417,6 +433,7 @@ impl Clone for RocksDBEngineBuildContext {
         let storage = StorageOptions {
             mem_cache_capacity: ReadableSize::mb(0),
             mem_cache_partition_bits: 0,
+            mem_cache_admission: AdmissionPolicy::Lru,
             disk_cache_dir: "".to_string(),
             disk_cache_capacity: ReadableSize::mb(0),
             disk_cache_page_size: ReadableSize::mb(0),
@@ -426,6 +443,8 @@ impl Clone for RocksDBEngineBuildContext {
                 max_retries: 3,
                 timeout: Default::default(),
             }),
+            circuit_breaker: Default::default(),
+            restore_retry: Default::default(),
         };
 
         config.storage = storage;
@@ -435,6 +454,9 @@ impl Clone for RocksDBEngineBuildContext {
                 ..Default::default()
             })),
             disable_data: false,
+            circuit_breaker: Default::default(),
+            compression: Default::default(),
+            group_commit: Default::default(),
         };
         Self {
             config,
@@ -522,6 +544,7 @@ impl<T: WalsOpener> TestContext<T> {
             config: &self.config,
             engine_runtimes: self.runtimes.clone(),
             opened_wals: opened_wals.clone(),
+            hooks: EngineHooks::default(),
         };
         self.opened_wals = Some(opened_wals);
 
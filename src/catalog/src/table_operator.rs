@@ -15,7 +15,7 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use std::time::Instant;
+use std::{collections::HashSet, time::Instant};
 
 use generic_error::BoxError;
 use logger::{error, info, warn};
@@ -122,7 +122,13 @@ impl TableOperator {
         }
     }
 
-    pub async fn close_shard(&self, request: CloseShardRequest, opts: CloseOptions) -> Result<()> {
+    /// Close the tables of the shard, and return the closed tables with their
+    /// final sequences.
+    pub async fn close_shard(
+        &self,
+        request: CloseShardRequest,
+        opts: CloseOptions,
+    ) -> Result<Vec<engine::ClosedTable>> {
         let instant = Instant::now();
         let table_engine = opts.table_engine;
         let shard_id = request.shard_id;
@@ -146,26 +152,27 @@ impl TableOperator {
         let close_results = table_engine.close_shard(engine_close_shard_req).await;
 
         // Check and unregister successful closed table from schema.
-        let mut success_count = 0_u32;
+        let mut closed_tables = Vec::with_capacity(schemas.len());
         let mut close_table_errs = Vec::new();
 
         for (schema, close_result) in schemas.into_iter().zip(close_results.into_iter()) {
             match close_result {
-                Ok(table_name) => {
-                    schema.unregister_table(&table_name);
-                    success_count += 1;
+                Ok(closed_table) => {
+                    schema.unregister_table(&closed_table.table_name);
+                    closed_tables.push(closed_table);
                 }
                 Err(e) => close_table_errs.push(e),
             }
         }
 
+        let success_count = closed_tables.len();
         info!(
             "Close shard finished, shard id:{shard_id}, cost:{}ms, success_count:{success_count}, close_table_errs:{close_table_errs:?}",
             instant.saturating_elapsed().as_millis(),
         );
 
         if close_table_errs.is_empty() {
-            Ok(())
+            Ok(closed_tables)
         } else {
             TableOperatorNoCause {
                 msg: format!(
@@ -239,6 +246,32 @@ impl TableOperator {
             })
     }
 
+    /// Returns the tables existing among `table_names`.
+    pub fn existing_tables(
+        &self,
+        catalog_name: &str,
+        schema_name: &str,
+        table_names: &[String],
+    ) -> Result<HashSet<String>> {
+        let schema = self.schema_by_name(catalog_name, schema_name)?;
+
+        let mut tables = HashSet::new();
+        for table_name in table_names {
+            let table =
+                schema
+                    .table_by_name(table_name)
+                    .box_err()
+                    .context(TableOperatorWithCause {
+                        msg: format!("failed to find table, table_name:{table_name}"),
+                    })?;
+            if table.is_some() {
+                tables.insert(table_name.clone());
+            }
+        }
+
+        Ok(tables)
+    }
+
     pub async fn drop_table_on_shard(
         &self,
         request: DropTableRequest,
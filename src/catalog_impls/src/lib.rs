@@ -23,7 +23,11 @@ use catalog::{
     schema::NameRef,
     CatalogRef,
 };
-use system_catalog::{tables::Tables, SystemTableAdapter};
+use system_catalog::{
+    queries::{ActiveQueriesRef, Queries},
+    tables::Tables,
+    SystemTableAdapter,
+};
 
 use crate::system_tables::{SystemTables, SystemTablesBuilder};
 
@@ -40,10 +44,11 @@ pub struct CatalogManagerImpl {
 }
 
 impl CatalogManagerImpl {
-    pub fn new(manager: ManagerRef) -> Self {
+    pub fn new(manager: ManagerRef, active_queries: ActiveQueriesRef) -> Self {
         let mut system_tables_builder = SystemTablesBuilder::new();
         system_tables_builder = system_tables_builder
-            .insert_table(SystemTableAdapter::new(Tables::new(manager.clone())));
+            .insert_table(SystemTableAdapter::new(Tables::new(manager.clone())))
+            .insert_table(SystemTableAdapter::new(Queries::new(active_queries)));
         Self {
             system_tables: system_tables_builder.build(),
             user_catalog_manager: manager,
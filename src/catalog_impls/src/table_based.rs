@@ -1029,6 +1029,8 @@ mod tests {
         let engine_proxy = Arc::new(TableEngineProxy {
             memory,
             analytic: engine.clone(),
+            external: None,
+            union: None,
         });
 
         let catalog_manager = build_catalog_manager(engine.clone()).await;
@@ -1075,6 +1077,8 @@ mod tests {
         let engine_proxy = Arc::new(TableEngineProxy {
             memory,
             analytic: engine.clone(),
+            external: None,
+            union: None,
         });
 
         let catalog_manager = build_catalog_manager(engine.clone()).await;
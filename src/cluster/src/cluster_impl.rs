@@ -16,6 +16,7 @@
 // under the License.
 
 use std::{
+    collections::HashMap,
     sync::{Arc, Mutex, RwLock},
     time::Duration,
 };
@@ -41,13 +42,14 @@ use tokio::{
 };
 
 use crate::{
-    config::{ClusterConfig, EtcdClientConfig},
-    shard_lock_manager::{self, ShardLockManager, ShardLockManagerRef},
+    config::{ClusterConfig, EtcdClientConfig, PendingWriteConfig, WarmupConfig},
+    rebalance::{RebalanceAnalyzer, RebalanceProposal, ShardLoad},
+    shard_lock_manager::{self, LeaseOptions, ShardLockManager, ShardLockManagerRef},
     shard_set::{Shard, ShardRef, ShardSet},
     topology::ClusterTopology,
     Cluster, ClusterNodesNotFound, ClusterNodesResp, EtcdClientFailureWithCause,
-    InitEtcdClientConfig, InvalidArguments, MetaClientFailure, OpenShard, OpenShardWithCause,
-    Result, ShardNotFound, TableStatus,
+    InitEtcdClientConfig, InvalidArguments, MetaClientFailure, OpenShard, OpenShardNoCause,
+    OpenShardWithCause, Result, ShardNotFound, TableStatus, UpdateShardLockLease,
 };
 
 /// ClusterImpl is an implementation of [`Cluster`] based [`MetaClient`].
@@ -57,6 +59,8 @@ use crate::{
 ///  - Handle the heartbeat between horaedb-server and HoraeMeta;
 ///  - Provide the cluster topology.
 pub struct ClusterImpl {
+    /// The endpoint of this node
+    node_name: String,
     inner: Arc<Inner>,
     runtime: Arc<Runtime>,
     config: ClusterConfig,
@@ -92,7 +96,7 @@ impl ClusterImpl {
             &config.meta_client.cluster_name,
         )?;
         let shard_lock_mgr_config = shard_lock_manager::Config {
-            node_name,
+            node_name: node_name.clone(),
             lock_key_prefix: shard_lock_key_prefix,
             lock_lease_ttl_sec: config.etcd_client.shard_lock_lease_ttl_sec,
             lock_lease_check_interval: config.etcd_client.shard_lock_lease_check_interval.0,
@@ -102,8 +106,13 @@ impl ClusterImpl {
         };
         let shard_lock_manager = ShardLockManager::new(shard_lock_mgr_config, etcd_client);
 
-        let inner = Arc::new(Inner::new(shard_set, meta_client)?);
+        let inner = Arc::new(Inner::new(
+            shard_set,
+            meta_client,
+            config.pending_write.clone(),
+        )?);
         Ok(Self {
+            node_name,
             inner,
             runtime,
             config,
@@ -117,15 +126,16 @@ impl ClusterImpl {
         let interval = self.heartbeat_interval();
         let error_wait_lease = self.error_wait_lease();
         let inner = self.inner.clone();
+        let warmup = self.config.warmup.clone();
         let (tx, mut rx) = mpsc::channel(1);
 
         let handle = self.runtime.spawn(async move {
             loop {
-                let shard_infos = inner
-                    .shard_set
-                    .all_shards()
+                let shards = inner.shard_set.all_shards();
+                inner.prefetch_meta(&shards, &warmup).await;
+                let shard_infos = shards
                     .iter()
-                    .map(|shard| shard.shard_info())
+                    .map(|shard| shard.announced_shard_info(&warmup))
                     .collect();
                 info!("Node heartbeat to meta, shard infos:{:?}", shard_infos);
 
@@ -182,17 +192,52 @@ struct Inner {
     shard_set: ShardSet,
     meta_client: MetaClientRef,
     topology: RwLock<ClusterTopology>,
+    pending_write: PendingWriteConfig,
 }
 
 impl Inner {
-    fn new(shard_set: ShardSet, meta_client: MetaClientRef) -> Result<Self> {
+    fn new(
+        shard_set: ShardSet,
+        meta_client: MetaClientRef,
+        pending_write: PendingWriteConfig,
+    ) -> Result<Self> {
         Ok(Self {
             shard_set,
             meta_client,
             topology: Default::default(),
+            pending_write,
         })
     }
 
+    /// Prefetch the meta cache for the opened shards in warm-up, so that they
+    /// can be announced as ready.
+    async fn prefetch_meta(&self, shards: &[ShardRef], warmup: &WarmupConfig) {
+        if !warmup.enable || !warmup.require_meta_prefetch {
+            return;
+        }
+
+        let pending: Vec<_> = shards
+            .iter()
+            .filter(|shard| shard.is_opened() && !shard.is_meta_prefetched())
+            .collect();
+        if pending.is_empty() {
+            return;
+        }
+
+        match self.fetch_nodes().await {
+            Ok(_) => {
+                for shard in pending {
+                    info!(
+                        "Meta cache is prefetched for shard, id:{}",
+                        shard.shard_info().id
+                    );
+                    shard.mark_meta_prefetched();
+                }
+            }
+            Err(e) => warn!("Failed to prefetch meta cache for warming up shards, err:{e}"),
+        }
+    }
+
     async fn route_tables(&self, req: &RouteTablesRequest) -> Result<RouteTablesResponse> {
         // TODO: we should use self.topology to cache the route result to reduce the
         // pressure on the HoraeMeta.
@@ -252,62 +297,107 @@ impl Inner {
     }
 
     async fn open_shard(&self, shard_info: &ShardInfo) -> Result<ShardRef> {
-        if let Some(shard) = self.shard_set.get(shard_info.id) {
-            let cur_shard_info = shard.shard_info();
-            if cur_shard_info.version == shard_info.version {
-                info!(
-                    "No need to open the exactly same shard again, shard_info:{:?}",
-                    shard_info
-                );
-                return Ok(shard);
+        let mut shards = self.open_shards(std::slice::from_ref(shard_info)).await?;
+        shards.pop().context(OpenShard {
+            shard_id: shard_info.id,
+            msg: "shard is missing from the opened shards",
+        })
+    }
+
+    /// Open the shards, whose tables are fetched from HoraeMeta in one rpc.
+    ///
+    /// The returned shards are in the same order as `shard_infos`.
+    async fn open_shards(&self, shard_infos: &[ShardInfo]) -> Result<Vec<ShardRef>> {
+        let mut opened = Vec::with_capacity(shard_infos.len());
+        let mut shard_ids_to_fetch = Vec::new();
+        for shard_info in shard_infos {
+            let shard = self.check_opened_shard(shard_info)?;
+            if shard.is_none() {
+                shard_ids_to_fetch.push(shard_info.id);
             }
-            ensure!(
-                cur_shard_info.version < shard_info.version,
-                OpenShard {
-                    shard_id: shard_info.id,
-                    msg: format!("open a shard with a smaller version, curr_shard_info:{cur_shard_info:?}, new_shard_info:{shard_info:?}"),
-                }
-            );
+            opened.push(shard);
         }
 
-        let req = GetTablesOfShardsRequest {
-            shard_ids: vec![shard_info.id],
+        let mut tables_by_shard = if shard_ids_to_fetch.is_empty() {
+            HashMap::new()
+        } else {
+            let req = GetTablesOfShardsRequest {
+                shard_ids: shard_ids_to_fetch.clone(),
+            };
+            let resp = self
+                .meta_client
+                .get_tables_of_shards(req)
+                .await
+                .box_err()
+                .with_context(|| OpenShardWithCause {
+                    msg: format!("shard_ids:{shard_ids_to_fetch:?}"),
+                })?;
+            resp.tables_by_shard
         };
 
-        let mut resp = self
-            .meta_client
-            .get_tables_of_shards(req)
-            .await
-            .box_err()
-            .with_context(|| OpenShardWithCause {
-                msg: format!("shard_info:{shard_info:?}"),
-            })?;
-
         ensure!(
-            resp.tables_by_shard.len() == 1,
-            OpenShard {
-                shard_id: shard_info.id,
-                msg: "expect only one shard tables"
+            tables_by_shard.len() == shard_ids_to_fetch.len(),
+            OpenShardNoCause {
+                msg: format!(
+                    "expect tables of {} shards, but got {}, shard_ids:{shard_ids_to_fetch:?}",
+                    shard_ids_to_fetch.len(),
+                    tables_by_shard.len()
+                ),
             }
         );
 
-        let tables_of_shard = resp
-            .tables_by_shard
-            .remove(&shard_info.id)
-            .context(OpenShard {
+        let mut shards = Vec::with_capacity(shard_infos.len());
+        for (shard_info, shard) in shard_infos.iter().zip(opened) {
+            if let Some(shard) = shard {
+                shards.push(shard);
+                continue;
+            }
+
+            let tables_of_shard = tables_by_shard.remove(&shard_info.id).context(OpenShard {
                 shard_id: shard_info.id,
                 msg: "shard tables are missing from the response",
             })?;
 
-        let shard_id = tables_of_shard.shard_info.id;
-        let shard = Arc::new(Shard::new(tables_of_shard));
+            let shard_id = tables_of_shard.shard_info.id;
+            let shard = Arc::new(Shard::new(
+                tables_of_shard,
+                self.pending_write.max_pending_writes,
+            ));
+
+            info!("Insert shard to shard_set, id:{shard_id}, shard:{shard:?}");
+            if let Some(old_shard) = self.shard_set.insert(shard_id, shard.clone()) {
+                info!("Remove old shard, id:{shard_id}, old:{old_shard:?}");
+            }
+            shards.push(shard);
+        }
+
+        Ok(shards)
+    }
+
+    /// Return the shard if the exactly same shard is already opened, and
+    /// return error if the new shard is outdated.
+    fn check_opened_shard(&self, shard_info: &ShardInfo) -> Result<Option<ShardRef>> {
+        let Some(shard) = self.shard_set.get(shard_info.id) else {
+            return Ok(None);
+        };
 
-        info!("Insert shard to shard_set, id:{shard_id}, shard:{shard:?}");
-        if let Some(old_shard) = self.shard_set.insert(shard_id, shard.clone()) {
-            info!("Remove old shard, id:{shard_id}, old:{old_shard:?}");
+        let cur_shard_info = shard.shard_info();
+        if cur_shard_info.version == shard_info.version {
+            info!(
+                "No need to open the exactly same shard again, shard_info:{:?}",
+                shard_info
+            );
+            return Ok(Some(shard));
         }
+        ensure!(
+            cur_shard_info.version < shard_info.version,
+            OpenShard {
+                shard_id: shard_info.id,
+                msg: format!("open a shard with a smaller version, curr_shard_info:{cur_shard_info:?}, new_shard_info:{shard_info:?}"),
+            }
+        );
 
-        Ok(shard)
+        Ok(None)
     }
 
     fn shard(&self, shard_id: ShardId) -> Option<ShardRef> {
@@ -380,6 +470,10 @@ impl Cluster for ClusterImpl {
         self.inner.open_shard(shard_info).await
     }
 
+    async fn open_shards(&self, shard_infos: &[ShardInfo]) -> Result<Vec<ShardRef>> {
+        self.inner.open_shards(shard_infos).await
+    }
+
     fn shard(&self, shard_id: ShardId) -> Option<ShardRef> {
         self.inner.shard(shard_id)
     }
@@ -390,6 +484,16 @@ impl Cluster for ClusterImpl {
             .map(|shard| TableStatus::from(shard.get_status()))
     }
 
+    async fn wait_table_ready(&self, schema_name: &str, table_name: &str) -> Result<()> {
+        let Some(shard) = self.inner.get_shard_by_table_name(schema_name, table_name) else {
+            return Ok(());
+        };
+
+        shard
+            .wait_opened(self.config.pending_write.max_wait.0)
+            .await
+    }
+
     async fn close_shard(&self, shard_id: ShardId) -> Result<ShardRef> {
         self.inner.close_shard(shard_id)
     }
@@ -409,6 +513,37 @@ impl Cluster for ClusterImpl {
     fn shard_lock_manager(&self) -> ShardLockManagerRef {
         self.shard_lock_manager.clone()
     }
+
+    async fn update_shard_lock_lease(&self, ttl_sec: u64, check_interval: Duration) -> Result<()> {
+        let mut etcd_client_config = self.config.etcd_client.clone();
+        etcd_client_config.shard_lock_lease_ttl_sec = ttl_sec;
+        etcd_client_config.shard_lock_lease_check_interval = check_interval.into();
+        if let Err(e) = etcd_client_config.validate() {
+            return InvalidArguments { msg: e }.fail();
+        }
+
+        // NOTE: the timeout of the etcd client is not changed, which is set when it is
+        // connected.
+        let lease_options = LeaseOptions {
+            ttl_sec,
+            check_interval,
+            rpc_timeout: etcd_client_config.rpc_timeout(),
+        };
+        self.shard_lock_manager
+            .update_lease_options(lease_options)
+            .await
+            .context(UpdateShardLockLease)
+    }
+
+    async fn propose_rebalance(
+        &self,
+        shard_loads: Vec<ShardLoad>,
+    ) -> Result<Vec<RebalanceProposal>> {
+        let nodes = self.inner.fetch_nodes().await?;
+        let analyzer = RebalanceAnalyzer::new(self.config.rebalance.clone());
+
+        Ok(analyzer.propose(&self.node_name, &shard_loads, &nodes.cluster_nodes))
+    }
 }
 
 /// Build the connect options for accessing etcd cluster.
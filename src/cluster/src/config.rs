@@ -15,13 +15,18 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use std::time::Duration;
+use std::{collections::HashMap, time::Duration};
 
-use common_types::schema::TIMESTAMP_COLUMN;
+use common_types::{
+    schema::TIMESTAMP_COLUMN, COMPACTION_STRATEGIES, COMPACTION_STRATEGY, MEMTABLE_TYPE,
+    SEGMENT_DURATION, TTL,
+};
 use meta_client::meta_impl::MetaClientConfig;
 use serde::{Deserialize, Serialize};
 use table_engine::ANALYTIC_ENGINE_TYPE;
-use time_ext::ReadableDuration;
+use time_ext::{parse_duration, ReadableDuration};
+
+use crate::rebalance::RebalanceConfig;
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(default)]
@@ -29,6 +34,7 @@ use time_ext::ReadableDuration;
 pub struct SchemaConfig {
     pub default_engine_type: String,
     pub default_timestamp_column_name: String,
+    pub default_table_options: DefaultTableOptions,
 }
 
 impl Default for SchemaConfig {
@@ -36,10 +42,67 @@ impl Default for SchemaConfig {
         Self {
             default_engine_type: ANALYTIC_ENGINE_TYPE.to_string(),
             default_timestamp_column_name: TIMESTAMP_COLUMN.to_string(),
+            default_table_options: DefaultTableOptions::default(),
         }
     }
 }
 
+/// Default options of the tables created under a schema, including the auto
+/// created ones.
+///
+/// The values are in the same format as the table options, and the options
+/// specified on the table creation take precedence.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct DefaultTableOptions {
+    pub ttl: Option<String>,
+    pub segment_duration: Option<String>,
+    pub compaction_strategy: Option<String>,
+    pub memtable_type: Option<String>,
+}
+
+impl DefaultTableOptions {
+    /// Fill the unset `options` with the defaults.
+    pub fn apply_to(&self, options: &mut HashMap<String, String>) {
+        let defaults = [
+            (TTL, &self.ttl),
+            (SEGMENT_DURATION, &self.segment_duration),
+            (COMPACTION_STRATEGY, &self.compaction_strategy),
+            (MEMTABLE_TYPE, &self.memtable_type),
+        ];
+        for (key, value) in defaults {
+            if let Some(value) = value {
+                options
+                    .entry(key.to_string())
+                    .or_insert_with(|| value.clone());
+            }
+        }
+    }
+
+    /// Validate the defaults, so the invalid ones are found on loading the
+    /// config instead of creating the tables.
+    pub fn validate(&self) -> Result<(), String> {
+        let durations = [(TTL, &self.ttl), (SEGMENT_DURATION, &self.segment_duration)];
+        for (key, value) in durations {
+            if let Some(value) = value {
+                parse_duration(value)
+                    .map_err(|e| format!("invalid default {key}:{value}, err:{e}"))?;
+            }
+        }
+
+        if let Some(strategy) = &self.compaction_strategy {
+            let name = strategy.trim().to_lowercase();
+            if !COMPACTION_STRATEGIES.contains(&name.as_str()) {
+                return Err(format!(
+                    "invalid default {COMPACTION_STRATEGY}:{strategy}, expect one of {COMPACTION_STRATEGIES:?}"
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
 const DEFAULT_ETCD_ROOT_PATH: &str = "/horaedb";
 const MIN_SHARD_LOCK_LEASE_TTL_SEC: u64 = 15;
 
@@ -135,4 +198,124 @@ pub struct ClusterConfig {
     pub cmd_channel_buffer_size: usize,
     pub meta_client: MetaClientConfig,
     pub etcd_client: EtcdClientConfig,
+    pub rebalance: RebalanceConfig,
+    pub pending_write: PendingWriteConfig,
+    pub warmup: WarmupConfig,
+}
+
+/// Config of the writes queued for the opening shards.
+#[derive(Clone, Deserialize, Debug, Serialize)]
+#[serde(default)]
+pub struct PendingWriteConfig {
+    /// Max number of the writes waiting for one opening shard, and the writes
+    /// beyond it are rejected at once
+    pub max_pending_writes: usize,
+    /// The writes still waiting after this duration are rejected
+    pub max_wait: ReadableDuration,
+}
+
+impl Default for PendingWriteConfig {
+    fn default() -> Self {
+        Self {
+            max_pending_writes: 1024,
+            max_wait: ReadableDuration::secs(3),
+        }
+    }
+}
+
+/// Config of the warm-up gate of the opened shards.
+///
+/// An opened shard is reported to HoraeMeta as partially opened until it is
+/// warmed up, so that the router won't send the traffic to a cache-cold shard.
+#[derive(Clone, Deserialize, Debug, Serialize)]
+#[serde(default)]
+pub struct WarmupConfig {
+    pub enable: bool,
+    /// Whether the meta cache (cluster topology) must be prefetched before the
+    /// shard is announced as ready
+    pub require_meta_prefetch: bool,
+    /// The shard must keep opened without being reopened (replayed) for this
+    /// duration before it is announced as ready
+    pub min_stable_duration: ReadableDuration,
+}
+
+impl Default for WarmupConfig {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            require_meta_prefetch: true,
+            min_stable_duration: ReadableDuration::secs(30),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn default_table_options() -> DefaultTableOptions {
+        DefaultTableOptions {
+            ttl: Some("7d".to_string()),
+            segment_duration: Some("2h".to_string()),
+            compaction_strategy: Some("time_window".to_string()),
+            memtable_type: None,
+        }
+    }
+
+    #[test]
+    fn test_table_options_take_precedence() {
+        let defaults = default_table_options();
+        let mut options = HashMap::from([
+            (TTL.to_string(), "1d".to_string()),
+            (COMPACTION_STRATEGY.to_string(), "size_tiered".to_string()),
+        ]);
+        defaults.apply_to(&mut options);
+
+        let expect = HashMap::from([
+            (TTL.to_string(), "1d".to_string()),
+            (COMPACTION_STRATEGY.to_string(), "size_tiered".to_string()),
+            (SEGMENT_DURATION.to_string(), "2h".to_string()),
+        ]);
+        assert_eq!(expect, options);
+
+        // All the defaults are filled if no options are specified.
+        let mut options = HashMap::new();
+        defaults.apply_to(&mut options);
+        let expect = HashMap::from([
+            (TTL.to_string(), "7d".to_string()),
+            (COMPACTION_STRATEGY.to_string(), "time_window".to_string()),
+            (SEGMENT_DURATION.to_string(), "2h".to_string()),
+        ]);
+        assert_eq!(expect, options);
+    }
+
+    #[test]
+    fn test_validate_default_table_options() {
+        assert!(DefaultTableOptions::default().validate().is_ok());
+        assert!(default_table_options().validate().is_ok());
+
+        let invalid_ttl = DefaultTableOptions {
+            ttl: Some("7 days".to_string()),
+            ..default_table_options()
+        };
+        assert!(invalid_ttl.validate().is_err());
+
+        let invalid_segment_duration = DefaultTableOptions {
+            segment_duration: Some("-1h".to_string()),
+            ..default_table_options()
+        };
+        assert!(invalid_segment_duration.validate().is_err());
+
+        let invalid_strategy = DefaultTableOptions {
+            compaction_strategy: Some("leveled".to_string()),
+            ..default_table_options()
+        };
+        assert!(invalid_strategy.validate().is_err());
+
+        let upper_case_strategy = DefaultTableOptions {
+            compaction_strategy: Some("Size_Tiered".to_string()),
+            ..default_table_options()
+        };
+        assert!(upper_case_strategy.validate().is_ok());
+    }
 }
@@ -25,7 +25,7 @@
 
 #![feature(trait_alias)]
 
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use async_trait::async_trait;
 use common_types::schema::SchemaName;
@@ -38,10 +38,14 @@ use meta_client::types::{
 use shard_lock_manager::ShardLockManagerRef;
 use snafu::{Backtrace, Snafu};
 
-use crate::shard_set::ShardRef;
+use crate::{
+    rebalance::{RebalanceProposal, ShardLoad},
+    shard_set::ShardRef,
+};
 
 pub mod cluster_impl;
 pub mod config;
+pub mod rebalance;
 pub mod shard_lock_manager;
 pub mod shard_operation;
 pub mod shard_operator;
@@ -161,6 +165,18 @@ pub enum Error {
         "Cluster nodes are not found in the topology, version:{version}.\nBacktrace:\n{backtrace}",
     ))]
     ClusterNodesNotFound { version: u64, backtrace: Backtrace },
+
+    #[snafu(display(
+        "Shard is opening, shard_id:{shard_id}, msg:{msg}.\nBacktrace:\n{backtrace}"
+    ))]
+    ShardOpening {
+        shard_id: ShardId,
+        msg: String,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Failed to update shard lock lease, err:{source}"))]
+    UpdateShardLockLease { source: shard_lock_manager::Error },
 }
 
 define_result!(Error);
@@ -199,6 +215,12 @@ pub trait Cluster {
     /// Fetch related information and open shard.
     async fn open_shard(&self, shard_info: &ShardInfo) -> Result<ShardRef>;
 
+    /// Fetch related information of all the shards in one request and open
+    /// them.
+    ///
+    /// The returned shards are in the same order as `shard_infos`.
+    async fn open_shards(&self, shard_infos: &[ShardInfo]) -> Result<Vec<ShardRef>>;
+
     /// Get shard.
     ///
     /// If target shard has opened in cluster, return it. Otherwise, return
@@ -207,6 +229,13 @@ pub trait Cluster {
 
     fn get_table_status(&self, schema_name: &str, table_name: &str) -> Option<TableStatus>;
 
+    /// Wait for the shard of the table to finish opening, so the writes
+    /// arriving during the recovery of the shard can be applied after it.
+    ///
+    /// Return [Error::ShardOpening] if too many writes are waiting for the
+    /// shard or the shard is still opening after the configured deadline.
+    async fn wait_table_ready(&self, schema_name: &str, table_name: &str) -> Result<()>;
+
     /// Close shard.
     ///
     /// Return error if the shard is not found.
@@ -218,4 +247,19 @@ pub trait Cluster {
     async fn route_tables(&self, req: &RouteTablesRequest) -> Result<RouteTablesResponse>;
     async fn fetch_nodes(&self) -> Result<ClusterNodesResp>;
     fn shard_lock_manager(&self) -> ShardLockManagerRef;
+
+    /// Adjust the ttl and check interval of the shard lock lease at runtime.
+    ///
+    /// The granted shard locks are renewed with the new lease without being
+    /// released.
+    async fn update_shard_lock_lease(&self, ttl_sec: u64, check_interval: Duration) -> Result<()>;
+
+    /// Propose migrating some shards out of this node according to the loads
+    /// of the local shards and the shard distribution of the cluster.
+    ///
+    /// The proposals are advisory, nothing is migrated.
+    async fn propose_rebalance(
+        &self,
+        shard_loads: Vec<ShardLoad>,
+    ) -> Result<Vec<RebalanceProposal>>;
 }
@@ -0,0 +1,211 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Advisory shard rebalancing.
+//!
+//! The local node compares the number of its shards with the other nodes in
+//! the cluster, and proposes migrating some of its shards to the less loaded
+//! nodes. The proposals are only suggestions for the HoraeMeta or operators,
+//! nothing is migrated by the node itself.
+
+use std::collections::BTreeMap;
+
+use common_types::table::ShardId;
+use meta_client::types::NodeShard;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RebalanceConfig {
+    /// The node holding more shards than `avg * max_skew_ratio` is considered
+    /// skewed
+    pub max_skew_ratio: f64,
+}
+
+impl Default for RebalanceConfig {
+    fn default() -> Self {
+        Self {
+            max_skew_ratio: 1.2,
+        }
+    }
+}
+
+/// Load of a shard opened on the local node.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ShardLoad {
+    pub shard_id: ShardId,
+    pub num_tables: usize,
+    /// Approximate data size of the tables in the shard in bytes
+    pub data_size: u64,
+    pub num_write: u64,
+    pub num_read: u64,
+}
+
+/// Suggestion of migrating a shard to another node.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RebalanceProposal {
+    pub shard_id: ShardId,
+    pub from: String,
+    pub to: String,
+    pub reason: String,
+}
+
+pub struct RebalanceAnalyzer {
+    config: RebalanceConfig,
+}
+
+impl RebalanceAnalyzer {
+    pub fn new(config: RebalanceConfig) -> Self {
+        Self { config }
+    }
+
+    /// Propose the shards to migrate out of the local node.
+    ///
+    /// `cluster_nodes` is the shard distribution of the whole cluster, and only
+    /// the leader shards are taken into account. The smallest shards are
+    /// picked first to reduce the cost of the migration.
+    pub fn propose(
+        &self,
+        local_endpoint: &str,
+        shard_loads: &[ShardLoad],
+        cluster_nodes: &[NodeShard],
+    ) -> Vec<RebalanceProposal> {
+        // Use BTreeMap to make the proposals deterministic.
+        let mut shards_per_node: BTreeMap<&str, usize> = BTreeMap::new();
+        for node_shard in cluster_nodes {
+            let num_shards = shards_per_node
+                .entry(node_shard.endpoint.as_str())
+                .or_default();
+            if node_shard.shard_info.is_leader() {
+                *num_shards += 1;
+            }
+        }
+        // The local shards are more accurate than the cached cluster topology.
+        shards_per_node.insert(local_endpoint, shard_loads.len());
+        if shards_per_node.len() < 2 {
+            return Vec::new();
+        }
+
+        let total_shards: usize = shards_per_node.values().sum();
+        let avg = total_shards as f64 / shards_per_node.len() as f64;
+        let local_shards = shard_loads.len();
+        if local_shards as f64 <= avg * self.config.max_skew_ratio {
+            return Vec::new();
+        }
+
+        let mut candidates: Vec<_> = shard_loads.iter().collect();
+        candidates.sort_by_key(|load| (load.data_size, load.shard_id));
+
+        let target = avg.ceil() as usize;
+        let mut proposals = Vec::new();
+        let mut remaining = local_shards;
+        for load in candidates {
+            if remaining <= target {
+                break;
+            }
+
+            let (to, num_shards) = shards_per_node
+                .iter_mut()
+                .filter(|(endpoint, _)| **endpoint != local_endpoint)
+                .min_by_key(|(_, num_shards)| **num_shards)
+                .unwrap();
+            // Moving the shard doesn't make the distribution better.
+            if *num_shards + 1 >= remaining {
+                break;
+            }
+
+            proposals.push(RebalanceProposal {
+                shard_id: load.shard_id,
+                from: local_endpoint.to_string(),
+                to: to.to_string(),
+                reason: format!(
+                    "shard count skew, local_shards:{local_shards}, avg_shards:{avg:.2}, target_shards:{num_shards}"
+                ),
+            });
+            *num_shards += 1;
+            remaining -= 1;
+        }
+
+        proposals
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use meta_client::types::{ShardInfo, ShardRole};
+
+    use super::*;
+
+    fn node_shard(endpoint: &str, shard_id: ShardId) -> NodeShard {
+        NodeShard {
+            endpoint: endpoint.to_string(),
+            shard_info: ShardInfo {
+                id: shard_id,
+                role: ShardRole::Leader,
+                version: 0,
+                status: Default::default(),
+            },
+        }
+    }
+
+    fn shard_load(shard_id: ShardId, data_size: u64) -> ShardLoad {
+        ShardLoad {
+            shard_id,
+            data_size,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_propose_rebalance() {
+        let analyzer = RebalanceAnalyzer::new(RebalanceConfig::default());
+        let local = "node0:8831";
+        let loads: Vec<_> = (0..6).map(|i| shard_load(i, 100 - i as u64)).collect();
+        let mut cluster_nodes: Vec<_> = (0..6).map(|i| node_shard(local, i)).collect();
+        cluster_nodes.push(node_shard("node1:8831", 6));
+        cluster_nodes.push(node_shard("node2:8831", 7));
+        cluster_nodes.push(node_shard("node2:8831", 8));
+
+        // avg is 3, so 3 smallest shards should be moved out.
+        let proposals = analyzer.propose(local, &loads, &cluster_nodes);
+        let moves: Vec<_> = proposals
+            .iter()
+            .map(|p| (p.shard_id, p.to.as_str()))
+            .collect();
+        assert_eq!(
+            vec![(5, "node1:8831"), (4, "node1:8831"), (3, "node2:8831")],
+            moves
+        );
+    }
+
+    #[test]
+    fn test_propose_rebalance_balanced() {
+        let analyzer = RebalanceAnalyzer::new(RebalanceConfig::default());
+        let local = "node0:8831";
+        let loads: Vec<_> = (0..2).map(|i| shard_load(i, 100)).collect();
+        let cluster_nodes = vec![
+            node_shard(local, 0),
+            node_shard(local, 1),
+            node_shard("node1:8831", 2),
+            node_shard("node1:8831", 3),
+        ];
+        assert!(analyzer.propose(local, &loads, &cluster_nodes).is_empty());
+
+        // Single node cluster.
+        assert!(analyzer.propose(local, &loads, &[]).is_empty());
+    }
+}
@@ -18,6 +18,7 @@
 use std::{
     collections::HashMap,
     future::Future,
+    pin::Pin,
     sync::{Arc, Mutex, RwLock},
     time::{Duration, Instant},
 };
@@ -129,22 +130,76 @@ pub enum Error {
         source: etcd_client::Error,
         backtrace: Backtrace,
     },
+
+    #[snafu(display("Failed to move lock to the new lease in etcd, shard_id:{shard_id}, lease_id:{lease_id}, err:{source}.\nBacktrace:\n{backtrace:?}"))]
+    MoveLockInEtcd {
+        shard_id: ShardId,
+        lease_id: i64,
+        source: etcd_client::Error,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display(
+        "Failed to execute txn of moving lock, the lock may be held by others, shard_id:{shard_id}, lease_id:{lease_id}.\nBacktrace:\n{backtrace:?}"
+    ))]
+    MoveLockTxn {
+        shard_id: ShardId,
+        lease_id: i64,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Invalid lease options, msg:{msg}.\nBacktrace:\n{backtrace:?}"))]
+    InvalidLeaseOptions { msg: String, backtrace: Backtrace },
+
+    #[snafu(display("Failed to renew the lease of shard locks, shard_ids:{shard_ids:?}"))]
+    RenewLeases { shard_ids: Vec<ShardId> },
 }
 
 define_result!(Error);
 
 pub type ShardLockManagerRef = Arc<ShardLockManager>;
 
+/// Options of the shard lock lease, which can be adjusted at runtime.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LeaseOptions {
+    /// The time to live of the lease in seconds
+    pub ttl_sec: u64,
+    /// The interval to check whether the lease is expired
+    pub check_interval: Duration,
+    /// The timeout for etcd rpc
+    pub rpc_timeout: Duration,
+}
+
+impl LeaseOptions {
+    fn validate(&self) -> Result<()> {
+        ensure!(
+            self.ttl_sec > 0,
+            InvalidLeaseOptions {
+                msg: "ttl_sec should be positive",
+            }
+        );
+        ensure!(
+            self.check_interval < Duration::from_secs(self.ttl_sec),
+            InvalidLeaseOptions {
+                msg: format!(
+                    "check_interval({:?}) should be less than ttl_sec({}s)",
+                    self.check_interval, self.ttl_sec
+                ),
+            }
+        );
+
+        Ok(())
+    }
+}
+
 /// Shard lock manager is implemented based on etcd.
 ///
 /// Only with the lock held, the shard can be operated by this node.
 pub struct ShardLockManager {
     lock_key_prefix: String,
     lock_value: Bytes,
-    lock_lease_ttl_sec: u64,
-    lock_lease_check_interval: Duration,
+    lease_options: RwLock<LeaseOptions>,
     enable_fast_reacquire_lock: bool,
-    rpc_timeout: Duration,
 
     etcd_client: Client,
     runtime: RuntimeRef,
@@ -203,6 +258,36 @@ enum KeepAliveResult {
     },
 }
 
+type LockExpiredFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+type BoxedLockExpiredFn = Box<dyn FnOnce(ShardId) -> LockExpiredFuture + Send>;
+
+/// The callback to call when the shard lock is expired.
+///
+/// It is shared by the keepalive procedures of the same lock, so that it can be
+/// handed over to the new procedure when the lease of the lock is renewed. And
+/// it is called at most once.
+#[derive(Clone)]
+struct OnLockExpired(Arc<Mutex<Option<BoxedLockExpiredFn>>>);
+
+impl OnLockExpired {
+    fn new<OnExpired, Fut>(on_lock_expired: OnExpired) -> Self
+    where
+        OnExpired: FnOnce(ShardId) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let f: BoxedLockExpiredFn =
+            Box::new(move |shard_id| Box::pin(on_lock_expired(shard_id)) as LockExpiredFuture);
+        Self(Arc::new(Mutex::new(Some(f))))
+    }
+
+    async fn call(&self, shard_id: ShardId) {
+        let f = self.0.lock().unwrap().take();
+        if let Some(f) = f {
+            f(shard_id).await;
+        }
+    }
+}
+
 /// The lease of the shard lock.
 struct Lease {
     /// The lease id
@@ -237,6 +322,10 @@ impl Lease {
         state.duration_until_expired()
     }
 
+    fn expired_at(&self) -> Instant {
+        self.state.read().unwrap().expired_at
+    }
+
     /// Keep alive the lease once, the state will be updated whatever the
     /// keepalive result is.
     async fn keep_alive_once(
@@ -371,9 +460,16 @@ pub struct ShardLock {
     /// The timeout for etcd rpc
     rpc_timeout: Duration,
 
+    /// The create revision of the lock key in etcd, which increases whenever
+    /// the lock is re-created by any node, so it serves as the epoch of the
+    /// lock
+    epoch: Option<u64>,
     lease: Option<Arc<Lease>>,
     lease_check_handle: Option<JoinHandle<()>>,
     lease_keepalive_stopper: Option<oneshot::Sender<()>>,
+    /// The callback set in the `grant`, which is reused when the lease is
+    /// renewed
+    on_lock_expired: Option<OnLockExpired>,
 }
 
 /// The information about an etcd lease.
@@ -388,26 +484,32 @@ impl ShardLock {
         shard_id: ShardId,
         key_prefix: &str,
         value: Bytes,
-        ttl_sec: u64,
-        lease_check_interval: Duration,
+        lease_options: LeaseOptions,
         enable_fast_reacquire: bool,
-        rpc_timeout: Duration,
     ) -> Self {
         Self {
             shard_id,
             key: Self::lock_key(key_prefix, shard_id),
             value,
-            ttl_sec,
-            lease_check_interval,
+            ttl_sec: lease_options.ttl_sec,
+            lease_check_interval: lease_options.check_interval,
             enable_fast_reacquire,
-            rpc_timeout,
+            rpc_timeout: lease_options.rpc_timeout,
 
+            epoch: None,
             lease: None,
             lease_check_handle: None,
             lease_keepalive_stopper: None,
+            on_lock_expired: None,
         }
     }
 
+    fn set_lease_options(&mut self, lease_options: LeaseOptions) {
+        self.ttl_sec = lease_options.ttl_sec;
+        self.lease_check_interval = lease_options.check_interval;
+        self.rpc_timeout = lease_options.rpc_timeout;
+    }
+
     fn lock_key(key_prefix: &str, shard_id: ShardId) -> Bytes {
         // The shard id in the key is padded with at most 20 zeros to make it sortable.
         let key = format!("{key_prefix}/{shard_id:0>20}");
@@ -420,7 +522,12 @@ impl ShardLock {
     /// The slow way has to wait for the previous lock lease expired. And the
     /// fast way is to reuse the lease if it is not expired and the lock's value
     /// is the same.
-    async fn maybe_fast_acquire_lock(&self, etcd_client: &mut Client) -> Result<Option<LeaseInfo>> {
+    ///
+    /// The lease and the epoch of the lock are returned if acquired.
+    async fn maybe_fast_acquire_lock(
+        &self,
+        etcd_client: &mut Client,
+    ) -> Result<Option<(LeaseInfo, u64)>> {
         if !self.enable_fast_reacquire {
             return Ok(None);
         }
@@ -473,14 +580,27 @@ impl ShardLock {
         }
 
         let lease_expired_at = Instant::now() + Duration::from_secs(ttl_sec as u64);
-        Ok(Some(LeaseInfo {
+        let lease_info = LeaseInfo {
             id: lease_id,
             expired_at: lease_expired_at,
-        }))
+        };
+        // The lock is reused, so is its epoch.
+        Ok(Some((lease_info, kv.create_revision() as u64)))
     }
 
-    async fn slow_acquire_lock(&self, etcd_client: &mut Client) -> Result<LeaseInfo> {
+    /// Create the lock with a new lease, and return the lease and the epoch of
+    /// the lock.
+    async fn slow_acquire_lock(&self, etcd_client: &mut Client) -> Result<(LeaseInfo, u64)> {
         // Grant the lease first.
+        let lease_info = self.grant_lease(etcd_client).await?;
+        let epoch = self
+            .create_lock_with_lease(lease_info.id, etcd_client)
+            .await?;
+
+        Ok((lease_info, epoch))
+    }
+
+    async fn grant_lease(&self, etcd_client: &mut Client) -> Result<LeaseInfo> {
         let resp = etcd_client
             .lease_grant(self.ttl_sec as i64, None)
             .await
@@ -496,11 +616,8 @@ impl ShardLock {
         );
 
         let lease_expired_at = Instant::now() + Duration::from_secs(resp.ttl() as u64);
-        let lease_id = resp.id();
-        self.create_lock_with_lease(lease_id, etcd_client).await?;
-
         Ok(LeaseInfo {
-            id: lease_id,
+            id: resp.id(),
             expired_at: lease_expired_at,
         })
     }
@@ -525,7 +642,7 @@ impl ShardLock {
             return Ok(false);
         }
 
-        let lease_info = match self.maybe_fast_acquire_lock(etcd_client).await {
+        let (lease_info, epoch) = match self.maybe_fast_acquire_lock(etcd_client).await {
             Ok(Some(v)) => {
                 info!("Shard lock is acquired fast, shard_id:{}", self.shard_id);
                 v
@@ -546,6 +663,14 @@ impl ShardLock {
             }
         };
 
+        info!(
+            "Shard lock is acquired, shard_id:{}, epoch:{epoch}",
+            self.shard_id
+        );
+        self.epoch = Some(epoch);
+
+        let on_lock_expired = OnLockExpired::new(on_lock_expired);
+        self.on_lock_expired = Some(on_lock_expired.clone());
         self.keep_lease_alive(
             lease_info.id,
             lease_info.expired_at,
@@ -558,6 +683,83 @@ impl ShardLock {
         Ok(true)
     }
 
+    /// Apply the new lease options to the granted lock without releasing it.
+    ///
+    /// If the ttl changes, a new lease with the new ttl is granted and the lock
+    /// is moved onto it in one txn, and then the previous lease is revoked.
+    /// Otherwise, the keepalive procedure is restarted on the current lease to
+    /// apply the new check interval and rpc timeout.
+    ///
+    /// The lock is kept on the previous lease if it fails to be moved.
+    async fn renew_lease(
+        &mut self,
+        lease_options: LeaseOptions,
+        etcd_client: &mut Client,
+        runtime: &RuntimeRef,
+    ) -> Result<()> {
+        self.set_lease_options(lease_options);
+
+        let (prev_lease, on_lock_expired) = match (&self.lease, &self.on_lock_expired) {
+            (Some(lease), Some(on_lock_expired)) if !lease.is_expired() => {
+                (lease.clone(), on_lock_expired.clone())
+            }
+            _ => {
+                info!(
+                    "No valid lease to renew, the new options will be used in next grant, shard_id:{}",
+                    self.shard_id
+                );
+                return Ok(());
+            }
+        };
+
+        let ttl_changed = prev_lease.ttl != Duration::from_secs(self.ttl_sec);
+        let lease_info = if ttl_changed {
+            let lease_info = self.grant_lease(etcd_client).await?;
+            if let Err(e) = self.move_lock_to_lease(lease_info.id, etcd_client).await {
+                if let Err(revoke_err) = etcd_client.lease_revoke(lease_info.id).await {
+                    warn!(
+                        "Failed to revoke the unused lease, shard_id:{}, lease_id:{}, err:{revoke_err}",
+                        self.shard_id, lease_info.id
+                    );
+                }
+                return Err(e);
+            }
+            lease_info
+        } else {
+            LeaseInfo {
+                id: prev_lease.id,
+                expired_at: prev_lease.expired_at(),
+            }
+        };
+
+        self.stop_keepalive().await;
+        self.keep_lease_alive(
+            lease_info.id,
+            lease_info.expired_at,
+            on_lock_expired,
+            etcd_client,
+            runtime,
+        )
+        .await?;
+
+        // The lock is not attached to the previous lease any more, so revoking it
+        // won't release the lock.
+        if ttl_changed {
+            if let Err(e) = etcd_client.lease_revoke(prev_lease.id).await {
+                warn!(
+                    "Failed to revoke the previous lease, shard_id:{}, lease_id:{}, err:{e}",
+                    self.shard_id, prev_lease.id
+                );
+            }
+        }
+
+        info!(
+            "Finish renewing the lease of shard lock, shard_id:{}, lease_id:{}, options:{lease_options:?}",
+            self.shard_id, lease_info.id
+        );
+        Ok(())
+    }
+
     /// Revoke the shard lock.
     ///
     /// NOTE: the `on_lock_expired` callback set in the `grant` won't be
@@ -579,6 +781,15 @@ impl ShardLock {
         Ok(())
     }
 
+    /// Get the epoch of the lock, which is only available when it's valid.
+    fn epoch(&self) -> Option<u64> {
+        if self.is_valid() {
+            self.epoch
+        } else {
+            None
+        }
+    }
+
     /// Check whether the shard lock is valid.
     ///
     /// The shard lock is valid if the associated lease is not expired.
@@ -613,7 +824,39 @@ impl ShardLock {
         );
     }
 
-    async fn create_lock_with_lease(&self, lease_id: i64, etcd_client: &mut Client) -> Result<()> {
+    /// Attach the lock held by this node to another lease.
+    async fn move_lock_to_lease(&self, lease_id: i64, etcd_client: &mut Client) -> Result<()> {
+        // The lock can be moved only if it is still held by this node.
+        let held_by_self = Compare::value(self.key.clone(), CompareOp::Equal, self.value.clone());
+        let move_key = {
+            let options = PutOptions::new().with_lease(lease_id);
+            TxnOp::put(self.key.clone(), self.value.clone(), Some(options))
+        };
+
+        let move_if_held = Txn::new().when([held_by_self]).and_then([move_key]);
+
+        let resp = etcd_client
+            .txn(move_if_held)
+            .await
+            .context(MoveLockInEtcd {
+                shard_id: self.shard_id,
+                lease_id,
+            })?;
+
+        ensure!(
+            resp.succeeded(),
+            MoveLockTxn {
+                shard_id: self.shard_id,
+                lease_id,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Create the lock key attached to the lease, and the revision of the
+    /// creation is returned.
+    async fn create_lock_with_lease(&self, lease_id: i64, etcd_client: &mut Client) -> Result<u64> {
         // In etcd, the version is 0 if the key does not exist.
         let not_exist = Compare::version(self.key.clone(), CompareOp::Equal, 0);
         let create_key = {
@@ -637,7 +880,10 @@ impl ShardLock {
             }
         );
 
-        Ok(())
+        // The key is created by this txn, so its create revision is the revision of
+        // the txn.
+        let revision = resp.header().map(|v| v.revision()).unwrap_or_default();
+        Ok(revision as u64)
     }
 
     /// Keep alive the lease.
@@ -646,18 +892,14 @@ impl ShardLock {
     /// but it won't be triggered if the lock is revoked in purpose.
     /// The keepalive procedure supports retrying if the failure is caused by
     /// rpc error, and it will stop if the lease is expired.
-    async fn keep_lease_alive<OnExpired, Fut>(
+    async fn keep_lease_alive(
         &mut self,
         lease_id: i64,
         expired_at: Instant,
-        on_lock_expired: OnExpired,
+        on_lock_expired: OnLockExpired,
         etcd_client: &Client,
         runtime: &RuntimeRef,
-    ) -> Result<()>
-    where
-        OnExpired: FnOnce(ShardId) -> Fut + Send + 'static,
-        Fut: Future<Output = ()> + Send + 'static,
-    {
+    ) -> Result<()> {
         // Try to acquire the lock to ensure there is only one running keepalive
         // procedure.
         let initial_state = LeaseState::new(expired_at);
@@ -706,7 +948,7 @@ impl ShardLock {
                              lease_id:{lease_id}"
                         );
 
-                        on_lock_expired(shard_id).await;
+                        on_lock_expired.call(shard_id).await;
                         return;
                     }
                 }
@@ -734,7 +976,7 @@ impl ShardLock {
                         _ = timer => {
                             if lease_for_bg.is_expired() {
                                 warn!("The lease of the shard lock is expired, shard_id:{shard_id}");
-                                on_lock_expired(shard_id).await;
+                                on_lock_expired.call(shard_id).await;
                                 return
                             }
                         }
@@ -755,7 +997,7 @@ impl ShardLock {
                                 Err(_) => {
                                     // Unreachable! Because the notifier will always send a value before it is closed.
                                     error!("The notifier for lease keeping alive is closed, will trigger callback, shard_id:{shard_id}");
-                                    on_lock_expired(shard_id).await;
+                                    on_lock_expired.call(shard_id).await;
                                     return;
                                 }
                             }
@@ -797,12 +1039,16 @@ impl ShardLockManager {
 
         let value = Bytes::from(ShardLockValue { node_name }.encode_to_vec());
 
+        let lease_options = LeaseOptions {
+            ttl_sec: lock_lease_ttl_sec,
+            check_interval: lock_lease_check_interval,
+            rpc_timeout,
+        };
+
         ShardLockManager {
             lock_key_prefix,
             lock_value: value,
-            lock_lease_ttl_sec,
-            lock_lease_check_interval,
-            rpc_timeout,
+            lease_options: RwLock::new(lease_options),
             enable_fast_reacquire_lock,
             etcd_client,
             runtime,
@@ -830,6 +1076,7 @@ impl ShardLockManager {
         if let Some(shard_lock) = shard_locks.get_mut(&shard_id) {
             let mut etcd_client = self.etcd_client.clone();
             warn!("The shard lock was created before, and grant it again now, shard_id:{shard_id}");
+            shard_lock.set_lease_options(self.lease_options());
             shard_lock
                 .grant(on_lock_expired, &mut etcd_client, &self.runtime)
                 .await?;
@@ -840,10 +1087,8 @@ impl ShardLockManager {
                 shard_id,
                 &self.lock_key_prefix,
                 self.lock_value.clone(),
-                self.lock_lease_ttl_sec,
-                self.lock_lease_check_interval,
+                self.lease_options(),
                 self.enable_fast_reacquire_lock,
-                self.rpc_timeout,
             );
 
             let mut etcd_client = self.etcd_client.clone();
@@ -884,12 +1129,87 @@ impl ShardLockManager {
         info!("Finish revoke lock for shard, shard_id:{shard_id}");
         res
     }
+
+    /// Get the epoch of the shard lock held by this node.
+    ///
+    /// The epoch increases whenever the lock is taken over, so the writes
+    /// accepted with an older epoch should be refused. Returns None if the
+    /// lock is not held.
+    pub async fn lock_epoch(&self, shard_id: u32) -> Option<u64> {
+        let shard_locks = self.shard_locks.read().await;
+        shard_locks.get(&shard_id).and_then(|v| v.epoch())
+    }
+
+    /// Get the current lease options.
+    pub fn lease_options(&self) -> LeaseOptions {
+        *self.lease_options.read().unwrap()
+    }
+
+    /// Update the lease options at runtime.
+    ///
+    /// The new options are used by the locks granted later, and the granted
+    /// locks are renewed with them without being released. Returns error if
+    /// any lock fails to be renewed, and such lock is kept on its previous
+    /// lease.
+    pub async fn update_lease_options(&self, lease_options: LeaseOptions) -> Result<()> {
+        lease_options.validate()?;
+
+        info!("Try to update the lease options of shard locks, options:{lease_options:?}");
+
+        // Hold the write lock to avoid racing with granting or revoking.
+        let mut shard_locks = self.shard_locks.write().await;
+        *self.lease_options.write().unwrap() = lease_options;
+
+        let mut failed_shard_ids = Vec::new();
+        for (shard_id, shard_lock) in shard_locks.iter_mut() {
+            let mut etcd_client = self.etcd_client.clone();
+            if let Err(e) = shard_lock
+                .renew_lease(lease_options, &mut etcd_client, &self.runtime)
+                .await
+            {
+                error!("Failed to renew the lease of shard lock, shard_id:{shard_id}, err:{e}");
+                failed_shard_ids.push(*shard_id);
+            }
+        }
+
+        ensure!(
+            failed_shard_ids.is_empty(),
+            RenewLeases {
+                shard_ids: failed_shard_ids,
+            }
+        );
+
+        info!("Finish updating the lease options of shard locks, options:{lease_options:?}");
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_validate_lease_options() {
+        let valid = LeaseOptions {
+            ttl_sec: 30,
+            check_interval: Duration::from_millis(200),
+            rpc_timeout: Duration::from_secs(5),
+        };
+        assert!(valid.validate().is_ok());
+
+        let zero_ttl = LeaseOptions {
+            ttl_sec: 0,
+            ..valid
+        };
+        assert!(zero_ttl.validate().is_err());
+
+        let long_check_interval = LeaseOptions {
+            check_interval: Duration::from_secs(30),
+            ..valid
+        };
+        assert!(long_check_interval.validate().is_err());
+    }
+
     #[test]
     fn test_format_shard_lock_key() {
         let key_prefix = "/horaedb/defaultCluster";
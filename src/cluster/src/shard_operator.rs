@@ -29,7 +29,7 @@ use generic_error::BoxError;
 use logger::info;
 use snafu::ResultExt;
 use table_engine::{
-    engine::{CreateTableParams, TableEngineRef, TableState},
+    engine::{ClosedTable, CreateTableParams, TableEngineRef, TableState},
     table::TableId,
 };
 
@@ -168,7 +168,9 @@ impl ShardOperator {
         Ok(())
     }
 
-    pub async fn close(&self, ctx: CloseContext) -> Result<()> {
+    /// Close the shard, and return the closed tables with their final
+    /// sequences.
+    pub async fn close(&self, ctx: CloseContext) -> Result<Vec<ClosedTable>> {
         let (shard_info, tables) = {
             let data = self.data.read().unwrap();
             let shard_info = data.shard_info.clone();
@@ -202,7 +204,8 @@ impl ShardOperator {
             table_engine: ctx.table_engine,
         };
 
-        ctx.table_operator
+        let closed_tables = ctx
+            .table_operator
             .close_shard(close_shard_request, opts)
             .await
             .box_err()
@@ -220,7 +223,7 @@ impl ShardOperator {
 
         info!("ShardOperator close sequentially finish, shard_info:{shard_info:?}");
 
-        Ok(())
+        Ok(closed_tables)
     }
 
     pub async fn create_table(&self, ctx: CreateTableContext) -> Result<ShardVersion> {
@@ -15,20 +15,26 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
 use common_types::table::ShardVersion;
 use generic_error::BoxError;
 use meta_client::types::{ShardId, ShardInfo, ShardStatus, TableInfo, TablesOfShard};
 use snafu::{ensure, OptionExt, ResultExt};
+use table_engine::engine::ClosedTable;
 
 use crate::{
+    config::WarmupConfig,
     shard_operator::{
         CloseContext, CloseTableContext, CreateTableContext, DropTableContext, OpenContext,
         OpenTableContext, ShardOperator,
     },
-    OpenShardNoCause, OpenShardWithCause, Result, ShardVersionMismatch, TableAlreadyExists,
-    TableNotFound, UpdateFrozenShard,
+    OpenShardNoCause, OpenShardWithCause, Result, ShardOpening, ShardVersionMismatch,
+    TableAlreadyExists, TableNotFound, UpdateFrozenShard,
 };
 
 /// Shard set
@@ -71,6 +77,21 @@ impl ShardSet {
 pub struct Shard {
     data: ShardDataRef,
     operator: tokio::sync::Mutex<ShardOperator>,
+    /// Bounds the writes waiting for the shard to finish opening
+    pending_writes: tokio::sync::Semaphore,
+    /// Wakes up the pending writes once the shard is opened
+    opened_notify: tokio::sync::Notify,
+    warmup: Mutex<WarmupState>,
+}
+
+/// Warm-up state of the shard, see [`WarmupConfig`].
+#[derive(Debug, Default)]
+struct WarmupState {
+    /// The time when the shard finished opening
+    opened_at: Option<Instant>,
+    meta_prefetched: bool,
+    /// The warm-up is skipped by the operator
+    forced: bool,
 }
 
 impl std::fmt::Debug for Shard {
@@ -80,7 +101,11 @@ impl std::fmt::Debug for Shard {
 }
 
 impl Shard {
-    pub fn new(tables_of_shard: TablesOfShard) -> Self {
+    pub fn new(tables_of_shard: TablesOfShard, max_pending_writes: usize) -> Self {
+        let warmup = WarmupState {
+            opened_at: tables_of_shard.shard_info.is_opened().then(Instant::now),
+            ..Default::default()
+        };
         let data = Arc::new(std::sync::RwLock::new(ShardData {
             shard_info: tables_of_shard.shard_info,
             tables: tables_of_shard.tables,
@@ -88,7 +113,13 @@ impl Shard {
 
         let operator = tokio::sync::Mutex::new(ShardOperator { data: data.clone() });
 
-        Self { data, operator }
+        Self {
+            data,
+            operator,
+            pending_writes: tokio::sync::Semaphore::new(max_pending_writes),
+            opened_notify: tokio::sync::Notify::new(),
+            warmup: Mutex::new(warmup),
+        }
     }
 
     pub fn shard_info(&self) -> ShardInfo {
@@ -97,6 +128,12 @@ impl Shard {
         data.shard_info.clone()
     }
 
+    pub fn tables(&self) -> Vec<TableInfo> {
+        let data = self.data.read().unwrap();
+
+        data.tables.clone()
+    }
+
     pub fn find_table(&self, schema_name: &str, table_name: &str) -> Option<TableInfo> {
         let data = self.data.read().unwrap();
         data.find_table(schema_name, table_name)
@@ -128,6 +165,8 @@ impl Shard {
         if ret.is_ok() {
             let mut data = self.data.write().unwrap();
             data.finish_open();
+            self.warmup.lock().unwrap().opened_at = Some(Instant::now());
+            self.opened_notify.notify_waiters();
         }
         // If open failed, shard status is unchanged(`Opening`), so it can be
         // rescheduled to open again.
@@ -135,6 +174,39 @@ impl Shard {
         ret
     }
 
+    /// Wait for the shard to finish opening.
+    ///
+    /// Return error at once if there are too many waiters, or if the shard is
+    /// still opening after `timeout`.
+    pub async fn wait_opened(&self, timeout: Duration) -> Result<()> {
+        let shard_id = self.shard_info().id;
+        let _permit = self
+            .pending_writes
+            .try_acquire()
+            .ok()
+            .context(ShardOpening {
+                shard_id,
+                msg: "too many pending writes",
+            })?;
+
+        // Register the waiter before checking the status to avoid missing the
+        // notification.
+        let notified = self.opened_notify.notified();
+        tokio::pin!(notified);
+        notified.as_mut().enable();
+        if self.is_opened() {
+            return Ok(());
+        }
+
+        tokio::time::timeout(timeout, notified)
+            .await
+            .ok()
+            .with_context(|| ShardOpening {
+                shard_id,
+                msg: format!("still opening after {timeout:?}"),
+            })
+    }
+
     pub fn get_status(&self) -> ShardStatus {
         let data = self.data.read().unwrap();
         data.shard_info.status.clone()
@@ -150,7 +222,52 @@ impl Shard {
         data.is_frozen()
     }
 
-    pub async fn close(&self, ctx: CloseContext) -> Result<()> {
+    pub fn is_meta_prefetched(&self) -> bool {
+        self.warmup.lock().unwrap().meta_prefetched
+    }
+
+    /// Mark the meta cache needed by the shard as prefetched.
+    pub fn mark_meta_prefetched(&self) {
+        self.warmup.lock().unwrap().meta_prefetched = true;
+    }
+
+    /// Skip the warm-up and announce the shard as ready as soon as it is
+    /// opened.
+    pub fn force_ready(&self) {
+        self.warmup.lock().unwrap().forced = true;
+    }
+
+    /// Whether the opened shard has finished its warm-up.
+    pub fn is_warmed_up(&self, config: &WarmupConfig) -> bool {
+        if !config.enable {
+            return true;
+        }
+
+        let warmup = self.warmup.lock().unwrap();
+        if warmup.forced {
+            return true;
+        }
+        if config.require_meta_prefetch && !warmup.meta_prefetched {
+            return false;
+        }
+        warmup
+            .opened_at
+            .map(|opened_at| opened_at.elapsed() >= config.min_stable_duration.0)
+            .unwrap_or(false)
+    }
+
+    /// The shard info announced to HoraeMeta, whose status is kept as opening
+    /// until the shard is warmed up.
+    pub fn announced_shard_info(&self, config: &WarmupConfig) -> ShardInfo {
+        let mut shard_info = self.shard_info();
+        if shard_info.status == ShardStatus::Ready && !self.is_warmed_up(config) {
+            shard_info.status = ShardStatus::Opening;
+        }
+
+        shard_info
+    }
+
+    pub async fn close(&self, ctx: CloseContext) -> Result<Vec<ClosedTable>> {
         let operator = self.operator.lock().await;
         operator.close(ctx).await
     }
@@ -365,3 +482,89 @@ impl ShardData {
 }
 
 pub type ShardDataRef = Arc<std::sync::RwLock<ShardData>>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_shard(status: ShardStatus, max_pending_writes: usize) -> Shard {
+        let tables_of_shard = TablesOfShard {
+            shard_info: ShardInfo {
+                id: 1,
+                status,
+                ..Default::default()
+            },
+            tables: Vec::new(),
+        };
+        Shard::new(tables_of_shard, max_pending_writes)
+    }
+
+    #[tokio::test]
+    async fn test_wait_opened() {
+        let timeout = Duration::from_millis(10);
+
+        let shard = new_shard(ShardStatus::Ready, 1);
+        assert!(shard.wait_opened(timeout).await.is_ok());
+
+        let shard = new_shard(ShardStatus::Opening, 1);
+        assert!(shard.wait_opened(timeout).await.is_err());
+
+        // No room for the pending writes.
+        let shard = new_shard(ShardStatus::Ready, 0);
+        assert!(shard.wait_opened(timeout).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_wait_opened_notified() {
+        let shard = Arc::new(new_shard(ShardStatus::Opening, 1));
+        let waiter = {
+            let shard = shard.clone();
+            tokio::spawn(async move { shard.wait_opened(Duration::from_secs(10)).await })
+        };
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        shard.data.write().unwrap().finish_open();
+        shard.opened_notify.notify_waiters();
+
+        assert!(waiter.await.unwrap().is_ok());
+    }
+
+    #[test]
+    fn test_warmup_gate() {
+        let config = WarmupConfig {
+            enable: true,
+            require_meta_prefetch: true,
+            min_stable_duration: Duration::ZERO.into(),
+        };
+
+        let shard = new_shard(ShardStatus::Ready, 1);
+        assert!(!shard.is_warmed_up(&config));
+        assert_eq!(
+            shard.announced_shard_info(&config).status,
+            ShardStatus::Opening
+        );
+
+        shard.mark_meta_prefetched();
+        assert!(shard.is_warmed_up(&config));
+        assert_eq!(
+            shard.announced_shard_info(&config).status,
+            ShardStatus::Ready
+        );
+
+        // Not stable for long enough.
+        let config = WarmupConfig {
+            min_stable_duration: Duration::from_secs(3600).into(),
+            ..config
+        };
+        assert!(!shard.is_warmed_up(&config));
+        shard.force_ready();
+        assert!(shard.is_warmed_up(&config));
+
+        // The frozen shard is announced as is.
+        let shard = new_shard(ShardStatus::Frozen, 1);
+        assert_eq!(
+            shard.announced_shard_info(&config).status,
+            ShardStatus::Frozen
+        );
+    }
+}
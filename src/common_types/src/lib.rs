@@ -48,6 +48,8 @@ pub const TTL: &str = "ttl";
 pub const ARENA_BLOCK_SIZE: &str = "arena_block_size";
 pub const WRITE_BUFFER_SIZE: &str = "write_buffer_size";
 pub const COMPACTION_STRATEGY: &str = "compaction_strategy";
+/// Names of the compaction strategies, the values of [COMPACTION_STRATEGY].
+pub const COMPACTION_STRATEGIES: [&str; 3] = ["default", "size_tiered", "time_window"];
 pub const NUM_ROWS_PER_ROW_GROUP: &str = "num_rows_per_row_group";
 pub const UPDATE_MODE: &str = "update_mode";
 pub const COMPRESSION: &str = "compression";
@@ -55,6 +57,14 @@ pub const STORAGE_FORMAT: &str = "storage_format";
 pub const MEMTABLE_TYPE: &str = "memtable_type";
 pub const LAYERED_MUTABLE_SWITCH_THRESHOLD: &str = "layered_mutable_switch_threshold";
 pub const LAYERED_ENABLE: &str = "layered_enable";
+pub const EXPIRE_AT_COLUMN: &str = "expire_at_column";
+pub const DOWNSAMPLE_AFTER: &str = "downsample_after";
+pub const DOWNSAMPLE_INTERVAL: &str = "downsample_interval";
+pub const DOWNSAMPLE_AGGREGATOR: &str = "downsample_aggregator";
+pub const DOWNSAMPLE_RESOLUTION_COLUMN: &str = "downsample_resolution_column";
+pub const FIELD_GROUPS: &str = "field_groups";
+pub const COLUMN_TTL: &str = "column_ttl";
+pub const TIMESTAMP_PRECISION: &str = "timestamp_precision";
 
 #[cfg(any(test, feature = "test"))]
 pub mod tests;
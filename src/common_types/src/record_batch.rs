@@ -459,6 +459,32 @@ impl FetchedRecordBatch {
         &self.data.column_blocks
     }
 
+    /// Reorder the columns to the `schema`, whose i-th column is the
+    /// `order[i]`-th column of this batch.
+    ///
+    /// REQUIRE: `order` is a permutation of the column indexes.
+    pub fn reorder_columns(self, schema: RecordSchema, order: &[usize]) -> Result<Self> {
+        let num_rows = self.num_rows();
+        let column_blocks = order
+            .iter()
+            .map(|idx| self.data.column_blocks[*idx].clone())
+            .collect();
+        let primary_key_indexes = self.primary_key_indexes.map(|indexes| {
+            indexes
+                .iter()
+                .map(|key_idx| order.iter().position(|idx| idx == key_idx).unwrap())
+                .collect()
+        });
+        let options = RecordBatchOptions::new().with_row_count(Some(num_rows));
+        let data = RecordBatchData::new(schema.to_arrow_schema_ref(), column_blocks, options)?;
+
+        Ok(Self {
+            schema,
+            primary_key_indexes,
+            data,
+        })
+    }
+
     pub fn clone_row_at(&self, index: usize) -> Row {
         let datums = self
             .data
@@ -18,6 +18,7 @@
 //! Row type
 
 use std::{
+    cmp::Ordering,
     collections::HashMap,
     ops::{Index, IndexMut},
 };
@@ -324,6 +325,16 @@ impl RowGroup {
         std::mem::take(&mut self.rows)
     }
 
+    /// Sort the rows with the comparator, the relative order of the equal rows
+    /// is preserved.
+    #[inline]
+    pub fn sort_rows_by<F>(&mut self, compare: F)
+    where
+        F: FnMut(&Row, &Row) -> Ordering,
+    {
+        self.rows.sort_by(compare)
+    }
+
     #[inline]
     pub fn into_schema(self) -> Schema {
         self.schema
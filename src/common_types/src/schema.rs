@@ -890,6 +890,45 @@ impl Schema {
         }
     }
 
+    /// Returns a schema with the same columns and version, whose i-th column is
+    /// the `order[i]`-th column of this schema.
+    ///
+    /// REQUIRE: `order` is a permutation of the column indexes.
+    pub fn reorder_columns(&self, order: &[usize]) -> Schema {
+        assert_eq!(order.len(), self.num_columns());
+
+        let mut new_indexes = vec![0; order.len()];
+        for (new_idx, old_idx) in order.iter().enumerate() {
+            new_indexes[*old_idx] = new_idx;
+        }
+        let columns: Vec<_> = order.iter().map(|idx| self.column(*idx).clone()).collect();
+        let primary_key_indexes: Vec<_> = self
+            .primary_key_indexes
+            .iter()
+            .map(|idx| new_indexes[*idx])
+            .collect();
+        let timestamp_index = new_indexes[self.timestamp_index];
+
+        let fields = columns
+            .iter()
+            .map(|c| c.to_arrow_field())
+            .collect::<Vec<_>>();
+        let meta = Builder::build_arrow_schema_meta(
+            primary_key_indexes.clone(),
+            timestamp_index,
+            self.version,
+        );
+
+        Schema {
+            arrow_schema: Arc::new(ArrowSchema::new_with_metadata(fields, meta)),
+            primary_key_indexes,
+            timestamp_index,
+            tsid_index: self.tsid_index.map(|idx| new_indexes[idx]),
+            column_schemas: Arc::new(ColumnSchemas::new(columns)),
+            version: self.version,
+        }
+    }
+
     pub fn is_unique_column(&self, col_name: &str) -> bool {
         // primary key is obvious unique.
         let is_primary_key = self
@@ -1437,6 +1476,31 @@ mod tests {
         assert_eq!(schema, schema_from_pb);
     }
 
+    #[test]
+    fn test_reorder_columns() {
+        let schema = build_test_schema();
+        let reordered = schema.reorder_columns(&[3, 1, 0, 2]);
+
+        let names: Vec<_> = reordered
+            .columns()
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect();
+        assert_eq!(vec!["field2", "timestamp", "key1", "field1"], names);
+        assert_eq!(&[2, 1], reordered.primary_key_indexes());
+        assert_eq!(1, reordered.timestamp_index());
+        assert_eq!(schema.version(), reordered.version());
+        assert_eq!("field1", reordered.as_arrow_schema_ref().field(3).name());
+
+        // The meta of the arrow schema is reordered too.
+        let schema_from_arrow = Schema::try_from(reordered.as_arrow_schema_ref().clone()).unwrap();
+        assert_eq!(
+            reordered.primary_key_indexes(),
+            schema_from_arrow.primary_key_indexes()
+        );
+        assert_eq!(1, schema_from_arrow.timestamp_index());
+    }
+
     #[test]
     fn test_build_unordered() {
         let schema = Builder::new()
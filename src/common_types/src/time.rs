@@ -21,6 +21,7 @@
 
 use std::{
     convert::{TryFrom, TryInto},
+    fmt,
     time::{self, Duration, SystemTime},
 };
 
@@ -29,6 +30,7 @@ use datafusion::{
     scalar::ScalarValue,
 };
 use horaedbproto::time_range;
+use serde::{Deserialize, Serialize};
 use snafu::{Backtrace, OptionExt, Snafu};
 
 /// Error of time module.
@@ -175,6 +177,148 @@ impl From<&i64> for Timestamp {
     }
 }
 
+/// Precision of the values in the timestamp column of a table.
+///
+/// The values are always stored as i64 in the unit of the precision, and the
+/// durations configured in millis (ttl, segment duration, etc.) are scaled by
+/// [TimestampPrecision::scale_duration] before applied to the values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum TimestampPrecision {
+    Second,
+    #[default]
+    Millisecond,
+    Microsecond,
+    Nanosecond,
+}
+
+impl TimestampPrecision {
+    pub fn parse_from(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "s" | "second" => Some(Self::Second),
+            "ms" | "millisecond" => Some(Self::Millisecond),
+            "us" | "microsecond" => Some(Self::Microsecond),
+            "ns" | "nanosecond" => Some(Self::Nanosecond),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Second => "s",
+            Self::Millisecond => "ms",
+            Self::Microsecond => "us",
+            Self::Nanosecond => "ns",
+        }
+    }
+
+    /// Number of the units in one second.
+    #[inline]
+    pub fn units_per_sec(&self) -> i64 {
+        match self {
+            Self::Second => 1,
+            Self::Millisecond => 1_000,
+            Self::Microsecond => 1_000_000,
+            Self::Nanosecond => 1_000_000_000,
+        }
+    }
+
+    /// Returns current timestamp in this precision.
+    pub fn now(&self) -> Timestamp {
+        let now_ms = Timestamp::now().as_i64();
+        self.checked_from_millis(now_ms)
+            .map(Timestamp)
+            .unwrap_or(Timestamp::MAX)
+    }
+
+    /// Returns the earliest expired timestamp in this precision.
+    #[inline]
+    pub fn expire_time(&self, ttl: Duration) -> Timestamp {
+        self.now().sub_duration_or_min(self.scale_duration(ttl))
+    }
+
+    /// Convert the millis into the value in this precision, returns None if
+    /// overflow occurred.
+    pub fn checked_from_millis(&self, millis: i64) -> Option<i64> {
+        match self {
+            Self::Second => Some(millis.div_euclid(1_000)),
+            Self::Millisecond => Some(millis),
+            Self::Microsecond => millis.checked_mul(1_000),
+            Self::Nanosecond => millis.checked_mul(1_000_000),
+        }
+    }
+
+    /// Convert the millis into the smallest value in this precision not
+    /// earlier than it, returns None if overflow occurred.
+    ///
+    /// It differs from [TimestampPrecision::checked_from_millis] only in the
+    /// second precision, and converts the exclusive upper bounds, e.g. `ts <
+    /// millis` equals to `ts < checked_from_millis_ceil(millis)`.
+    pub fn checked_from_millis_ceil(&self, millis: i64) -> Option<i64> {
+        match self {
+            Self::Second => {
+                Some(millis.div_euclid(1_000) + i64::from(millis.rem_euclid(1_000) != 0))
+            }
+            _ => self.checked_from_millis(millis),
+        }
+    }
+
+    /// Convert the value in this precision into millis, returns None if
+    /// overflow occurred.
+    ///
+    /// The sub-millisecond part is floored.
+    pub fn checked_to_millis(&self, value: i64) -> Option<i64> {
+        match self {
+            Self::Second => value.checked_mul(1_000),
+            Self::Millisecond => Some(value),
+            Self::Microsecond => Some(value.div_euclid(1_000)),
+            Self::Nanosecond => Some(value.div_euclid(1_000_000)),
+        }
+    }
+
+    /// Convert the time range in millis into the range of the values in this
+    /// precision, whose millis (see [TimestampPrecision::checked_to_millis])
+    /// are exactly in the `range`. The bounds overflowed are saturated.
+    pub fn range_from_millis(&self, range: TimeRange) -> TimeRange {
+        let convert = |millis: Timestamp| {
+            let millis = millis.as_i64();
+            match self.checked_from_millis_ceil(millis) {
+                Some(v) => Timestamp::new(v),
+                None if millis < 0 => Timestamp::MIN,
+                None => Timestamp::MAX,
+            }
+        };
+
+        TimeRange::new(
+            convert(range.inclusive_start()),
+            convert(range.exclusive_end()),
+        )
+        .unwrap_or_else(TimeRange::empty)
+    }
+
+    /// Scale the duration so that its millis equals to the number of the units
+    /// in the original duration, which makes the helpers of [Timestamp] and
+    /// [TimeRange] working in millis applicable to the values in this
+    /// precision.
+    ///
+    /// The scaled duration is at least 1ms.
+    pub fn scale_duration(&self, duration: Duration) -> Duration {
+        let scaled = match self {
+            Self::Second => Duration::from_millis(duration.as_secs()),
+            Self::Millisecond => duration,
+            Self::Microsecond => duration.saturating_mul(1_000),
+            Self::Nanosecond => duration.saturating_mul(1_000_000),
+        };
+
+        scaled.max(Duration::from_millis(1))
+    }
+}
+
+impl fmt::Display for TimestampPrecision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 /// Unix timestamp range in millis
 ///
 /// The start time is inclusive and the end time is exclusive: [start, end).
@@ -350,7 +494,7 @@ impl TimeRange {
 mod test {
     use std::time::Duration;
 
-    use crate::time::{TimeRange, Timestamp};
+    use crate::time::{TimeRange, Timestamp, TimestampPrecision};
 
     #[test]
     fn test_timestamp() {
@@ -408,4 +552,61 @@ mod test {
         let range = TimeRange::bucket_of(ts, Duration::from_millis(25920000000)).unwrap();
         assert!(range.contains(ts), "range:{range:?}");
     }
+
+    #[test]
+    fn test_timestamp_precision() {
+        for (s, precision) in [
+            ("s", TimestampPrecision::Second),
+            ("ms", TimestampPrecision::Millisecond),
+            ("us", TimestampPrecision::Microsecond),
+            ("ns", TimestampPrecision::Nanosecond),
+        ] {
+            assert_eq!(Some(precision), TimestampPrecision::parse_from(s));
+            assert_eq!(s, precision.to_string());
+        }
+        assert!(TimestampPrecision::parse_from("min").is_none());
+
+        let ns = TimestampPrecision::Nanosecond;
+        assert_eq!(Some(1_500_000_000), ns.checked_from_millis(1500));
+        assert_eq!(Some(1500), ns.checked_to_millis(1_500_999_999));
+        assert_eq!(Some(-1), ns.checked_to_millis(-1));
+        assert!(ns.checked_from_millis(i64::MAX).is_none());
+
+        let s = TimestampPrecision::Second;
+        assert_eq!(Some(1), s.checked_from_millis(1999));
+        assert_eq!(Some(2), s.checked_from_millis_ceil(1999));
+        assert_eq!(Some(2), s.checked_from_millis_ceil(2000));
+        assert_eq!(Some(-1), s.checked_from_millis_ceil(-1999));
+        assert_eq!(Some(1_500_000_000), ns.checked_from_millis_ceil(1500));
+        assert!(s.checked_to_millis(i64::MAX).is_none());
+
+        assert_eq!(
+            TimeRange::new_unchecked_for_test(2, 3),
+            s.range_from_millis(TimeRange::new_unchecked_for_test(1500, 2500))
+        );
+        assert_eq!(
+            TimeRange::new_unchecked_for_test(1_500_000_000, 2_500_000_000),
+            ns.range_from_millis(TimeRange::new_unchecked_for_test(1500, 2500))
+        );
+        assert_eq!(
+            TimeRange::min_to_max(),
+            ns.range_from_millis(TimeRange::min_to_max())
+        );
+        assert_eq!(
+            TimeRange::new_unchecked_for_test(2, 2),
+            s.range_from_millis(TimeRange::new_unchecked_for_test(1500, 2000))
+        );
+
+        let hour = Duration::from_secs(3600);
+        assert_eq!(Duration::from_millis(3600), s.scale_duration(hour));
+        assert_eq!(hour, TimestampPrecision::Millisecond.scale_duration(hour));
+        assert_eq!(
+            Duration::from_millis(3_600_000_000_000),
+            ns.scale_duration(hour)
+        );
+        assert_eq!(
+            Duration::from_millis(1),
+            s.scale_duration(Duration::from_millis(10))
+        );
+    }
 }
@@ -216,6 +216,18 @@ impl RecordBatchesEncoder {
         Ok(())
     }
 
+    /// Take the bytes encoded so far out of the encoder, so that they can be
+    /// sent before the rest batches are written.
+    ///
+    /// The taken bytes are never compressed, so it should only be used with
+    /// [CompressionMethod::None].
+    pub fn take_encoded(&mut self) -> Vec<u8> {
+        self.stream_writer
+            .as_mut()
+            .map(|writer| std::mem::take(writer.get_mut()))
+            .unwrap_or_default()
+    }
+
     /// Finish encoding and generate the final encoded bytes, which may be
     /// compressed.
     pub fn finish(mut self) -> Result<CompressOutput> {
@@ -435,4 +447,28 @@ mod tests {
         assert_eq!(output.method, CompressionMethod::None);
         assert!(output.payload.is_empty());
     }
+
+    #[test]
+    fn test_take_encoded_bytes() {
+        let batches = (0..10).map(|i| create_batch(i, 128)).collect::<Vec<_>>();
+
+        let compress_opts = CompressOptions {
+            compress_min_length: 0,
+            method: CompressionMethod::None,
+        };
+        let mut encoder = RecordBatchesEncoder::new(compress_opts);
+        let mut payload = Vec::new();
+        for batch in &batches {
+            encoder.write(batch).unwrap();
+            let encoded = encoder.take_encoded();
+            assert!(!encoded.is_empty());
+            payload.extend(encoded);
+        }
+        let output = encoder.finish().unwrap();
+        assert_eq!(output.method, CompressionMethod::None);
+        payload.extend(output.payload);
+
+        let decoded_batches = decode_record_batches(payload, CompressionMethod::None).unwrap();
+        assert_eq!(decoded_batches, batches);
+    }
 }
@@ -0,0 +1,341 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Circuit breaker guarding the calls to an external dependency.
+//!
+//! The breaker is closed at first and all the calls are allowed. It turns to
+//! open after `failure_threshold` consecutive failures, and all the calls fail
+//! fast during `open_duration`. After that, the breaker turns to half open and
+//! only allows at most `half_open_max_probes` calls to probe the dependency,
+//! the breaker is closed again if a probe succeeds, otherwise it is open again.
+
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use logger::{info, warn};
+use macros::define_result;
+use prometheus::{IntCounter, IntGauge};
+use serde::{Deserialize, Serialize};
+use snafu::{Backtrace, Snafu};
+use time_ext::ReadableDuration;
+
+use crate::metrics::{
+    CIRCUIT_BREAKER_FAILURE_COUNTER, CIRCUIT_BREAKER_OPENED_COUNTER,
+    CIRCUIT_BREAKER_REJECTED_COUNTER, CIRCUIT_BREAKER_STATE_GAUGE,
+};
+
+mod metrics;
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub))]
+pub enum Error {
+    #[snafu(display(
+        "Circuit breaker is open, dependency:{}, retry_after:{:?}.\nBacktrace:\n{}",
+        dependency,
+        retry_after,
+        backtrace
+    ))]
+    Open {
+        dependency: String,
+        retry_after: Duration,
+        backtrace: Backtrace,
+    },
+}
+
+define_result!(Error);
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    /// Whether to guard the dependency by the circuit breaker.
+    pub enable: bool,
+    /// The breaker turns to open after so many consecutive failures.
+    pub failure_threshold: usize,
+    /// How long the breaker keeps open before probing the dependency.
+    pub open_duration: ReadableDuration,
+    /// Max number of the concurrent probes when the breaker is half open.
+    pub half_open_max_probes: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            failure_threshold: 5,
+            open_duration: ReadableDuration::secs(10),
+            half_open_max_probes: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    Closed,
+    HalfOpen,
+    Open,
+}
+
+impl State {
+    fn as_metric_value(&self) -> i64 {
+        match self {
+            State::Closed => 0,
+            State::HalfOpen => 1,
+            State::Open => 2,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
+    state: State,
+    consecutive_failures: usize,
+    /// When the breaker turns to the current state.
+    since: Instant,
+    /// Probes in flight when the breaker is half open.
+    probes: usize,
+}
+
+pub struct CircuitBreaker {
+    dependency: String,
+    config: Config,
+    inner: Mutex<Inner>,
+
+    state_gauge: IntGauge,
+    failure_counter: IntCounter,
+    rejected_counter: IntCounter,
+    opened_counter: IntCounter,
+}
+
+pub type CircuitBreakerRef = Arc<CircuitBreaker>;
+
+impl fmt::Debug for CircuitBreaker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CircuitBreaker")
+            .field("dependency", &self.dependency)
+            .field("config", &self.config)
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl CircuitBreaker {
+    pub fn new(dependency: impl Into<String>, config: Config) -> Self {
+        let dependency = dependency.into();
+        let state_gauge = CIRCUIT_BREAKER_STATE_GAUGE.with_label_values(&[&dependency]);
+        state_gauge.set(State::Closed.as_metric_value());
+
+        Self {
+            failure_counter: CIRCUIT_BREAKER_FAILURE_COUNTER.with_label_values(&[&dependency]),
+            rejected_counter: CIRCUIT_BREAKER_REJECTED_COUNTER.with_label_values(&[&dependency]),
+            opened_counter: CIRCUIT_BREAKER_OPENED_COUNTER.with_label_values(&[&dependency]),
+            state_gauge,
+            dependency,
+            config,
+            inner: Mutex::new(Inner {
+                state: State::Closed,
+                consecutive_failures: 0,
+                since: Instant::now(),
+                probes: 0,
+            }),
+        }
+    }
+
+    #[inline]
+    pub fn dependency(&self) -> &str {
+        &self.dependency
+    }
+
+    pub fn state(&self) -> State {
+        self.inner.lock().unwrap().state
+    }
+
+    /// Check whether the call to the dependency is allowed, and the result of
+    /// the allowed call should be reported by [CircuitBreaker::on_success] or
+    /// [CircuitBreaker::on_failure].
+    pub fn acquire(&self) -> Result<()> {
+        if !self.config.enable {
+            return Ok(());
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+        let open_duration = self.config.open_duration.0;
+        match inner.state {
+            State::Closed => return Ok(()),
+            State::Open => {
+                let elapsed = inner.since.elapsed();
+                if elapsed < open_duration {
+                    return self.reject(open_duration - elapsed);
+                }
+                self.transfer(&mut inner, State::HalfOpen);
+            }
+            State::HalfOpen => {
+                // The result of the probes may be never reported if the calls are cancelled,
+                // so allow new probes after waiting for another `open_duration`.
+                if inner.probes >= self.config.half_open_max_probes
+                    && inner.since.elapsed() < open_duration
+                {
+                    return self.reject(open_duration - inner.since.elapsed());
+                }
+                if inner.probes >= self.config.half_open_max_probes {
+                    inner.since = Instant::now();
+                    inner.probes = 0;
+                }
+            }
+        }
+
+        inner.probes += 1;
+        Ok(())
+    }
+
+    pub fn on_success(&self) {
+        if !self.config.enable {
+            return;
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+        inner.consecutive_failures = 0;
+        if inner.state != State::Closed {
+            self.transfer(&mut inner, State::Closed);
+        }
+    }
+
+    pub fn on_failure(&self) {
+        if !self.config.enable {
+            return;
+        }
+
+        self.failure_counter.inc();
+        let mut inner = self.inner.lock().unwrap();
+        inner.consecutive_failures += 1;
+        match inner.state {
+            State::Closed => {
+                if inner.consecutive_failures >= self.config.failure_threshold {
+                    self.transfer(&mut inner, State::Open);
+                }
+            }
+            State::HalfOpen => self.transfer(&mut inner, State::Open),
+            // The calls allowed before opening may fail later, and they shouldn't prolong the
+            // open state.
+            State::Open => (),
+        }
+    }
+
+    /// Report the result of the call allowed by [CircuitBreaker::acquire].
+    pub fn on_result(&self, success: bool) {
+        if success {
+            self.on_success()
+        } else {
+            self.on_failure()
+        }
+    }
+
+    fn reject(&self, retry_after: Duration) -> Result<()> {
+        self.rejected_counter.inc();
+        Open {
+            dependency: &self.dependency,
+            retry_after,
+        }
+        .fail()
+    }
+
+    fn transfer(&self, inner: &mut Inner, state: State) {
+        match state {
+            State::Open => {
+                warn!(
+                    "Circuit breaker turns to open, dependency:{}, consecutive_failures:{}",
+                    self.dependency, inner.consecutive_failures
+                );
+                self.opened_counter.inc();
+            }
+            State::HalfOpen | State::Closed => info!(
+                "Circuit breaker turns to {state:?}, dependency:{}",
+                self.dependency
+            ),
+        }
+
+        inner.state = state;
+        inner.since = Instant::now();
+        inner.probes = 0;
+        self.state_gauge.set(state.as_metric_value());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_breaker(open_duration: ReadableDuration) -> CircuitBreaker {
+        let config = Config {
+            enable: true,
+            failure_threshold: 2,
+            open_duration,
+            half_open_max_probes: 1,
+        };
+        CircuitBreaker::new("test", config)
+    }
+
+    #[test]
+    fn test_open_after_consecutive_failures() {
+        let breaker = new_breaker(ReadableDuration::secs(60));
+
+        breaker.acquire().unwrap();
+        breaker.on_failure();
+        breaker.acquire().unwrap();
+        breaker.on_success();
+        breaker.acquire().unwrap();
+        breaker.on_failure();
+        assert_eq!(State::Closed, breaker.state());
+
+        breaker.acquire().unwrap();
+        breaker.on_failure();
+        assert_eq!(State::Open, breaker.state());
+        assert!(matches!(breaker.acquire(), Err(Error::Open { .. })));
+    }
+
+    #[test]
+    fn test_half_open_probe() {
+        let breaker = new_breaker(ReadableDuration::millis(0));
+        for _ in 0..2 {
+            breaker.acquire().unwrap();
+            breaker.on_failure();
+        }
+        assert_eq!(State::Open, breaker.state());
+
+        // Only one probe is allowed.
+        breaker.acquire().unwrap();
+        assert_eq!(State::HalfOpen, breaker.state());
+        breaker.on_failure();
+        assert_eq!(State::Open, breaker.state());
+
+        breaker.acquire().unwrap();
+        breaker.on_success();
+        assert_eq!(State::Closed, breaker.state());
+    }
+
+    #[test]
+    fn test_disabled() {
+        let breaker = CircuitBreaker::new("test_disabled", Config::default());
+        for _ in 0..10 {
+            breaker.acquire().unwrap();
+            breaker.on_failure();
+        }
+        assert_eq!(State::Closed, breaker.state());
+    }
+}
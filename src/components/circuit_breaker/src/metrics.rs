@@ -0,0 +1,48 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Metrics of the circuit breakers.
+
+use lazy_static::lazy_static;
+use prometheus::{register_int_counter_vec, register_int_gauge_vec, IntCounterVec, IntGaugeVec};
+
+lazy_static! {
+    pub static ref CIRCUIT_BREAKER_STATE_GAUGE: IntGaugeVec = register_int_gauge_vec!(
+        "circuit_breaker_state",
+        "State of the circuit breaker, 0 for closed, 1 for half open and 2 for open",
+        &["dependency"]
+    )
+    .unwrap();
+    pub static ref CIRCUIT_BREAKER_FAILURE_COUNTER: IntCounterVec = register_int_counter_vec!(
+        "circuit_breaker_failures",
+        "Failed calls recorded by the circuit breaker",
+        &["dependency"]
+    )
+    .unwrap();
+    pub static ref CIRCUIT_BREAKER_REJECTED_COUNTER: IntCounterVec = register_int_counter_vec!(
+        "circuit_breaker_rejected",
+        "Calls rejected by the open circuit breaker",
+        &["dependency"]
+    )
+    .unwrap();
+    pub static ref CIRCUIT_BREAKER_OPENED_COUNTER: IntCounterVec = register_int_counter_vec!(
+        "circuit_breaker_opened",
+        "Times of the circuit breaker turning to open",
+        &["dependency"]
+    )
+    .unwrap();
+}
@@ -0,0 +1,621 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Governor of the local disk space used by the node.
+//!
+//! The disk usage of every [DiskConsumer] is checked against its quota
+//! periodically. Once a quota is exceeded or the free space of the filesystem
+//! drops below the reserved space, the evictable consumers are evicted in the
+//! priority order: the cache first, then the spilled results. If the space
+//! still can't be reclaimed, the node turns to degraded and rejects the writes
+//! until the usage drops back.
+
+use std::{
+    collections::HashMap,
+    fmt, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, RwLock, Weak,
+    },
+};
+
+use async_trait::async_trait;
+use generic_error::{BoxError, GenericResult};
+use logger::{error, info, warn};
+use macros::define_result;
+use runtime::{JoinHandle, Runtime};
+use serde::{Deserialize, Serialize};
+use size_ext::ReadableSize;
+use snafu::{Backtrace, Snafu};
+use time_ext::ReadableDuration;
+use tokio::{
+    sync::oneshot::{self, Receiver, Sender},
+    time,
+};
+
+use crate::metrics::{DISK_EVICTED_BYTES_COUNTER, DISK_QUOTA_DEGRADED_GAUGE, DISK_USAGE_GAUGE};
+
+mod metrics;
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub))]
+pub enum Error {
+    #[snafu(display(
+        "Writes are rejected by the disk quota, reason:{}.\nBacktrace:\n{}",
+        reason,
+        backtrace
+    ))]
+    Degraded {
+        reason: String,
+        backtrace: Backtrace,
+    },
+}
+
+define_result!(Error);
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    /// Whether to enforce the disk quota.
+    pub enable: bool,
+    /// The interval to check the disk usage.
+    pub check_interval: ReadableDuration,
+    /// Quota of the wal, which can't be evicted, so the writes are rejected
+    /// once it's exceeded. 0 means unlimited.
+    pub wal_quota: ReadableSize,
+    /// Quota of the disk cache, 0 means unlimited.
+    pub cache_quota: ReadableSize,
+    /// Quota of the spilled query results, 0 means unlimited.
+    pub spill_quota: ReadableSize,
+    /// Quota of the total usage of all the categories, 0 means unlimited.
+    pub total_quota: ReadableSize,
+    /// The exceeded usage is evicted down to this ratio of the quota, leaving
+    /// some headroom to avoid evicting in every check.
+    pub evict_target_ratio: f64,
+    /// The free space reserved in the filesystems, the node turns to degraded
+    /// if the free space can't be kept above it.
+    pub min_free_space: ReadableSize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            check_interval: ReadableDuration::secs(10),
+            wal_quota: ReadableSize(0),
+            cache_quota: ReadableSize(0),
+            spill_quota: ReadableSize(0),
+            total_quota: ReadableSize(0),
+            evict_target_ratio: 0.9,
+            min_free_space: ReadableSize::gb(1),
+        }
+    }
+}
+
+impl Config {
+    fn quota(&self, category: Category) -> Option<u64> {
+        let quota = match category {
+            Category::Wal => self.wal_quota,
+            Category::Cache => self.cache_quota,
+            Category::Spill => self.spill_quota,
+        };
+        non_zero(quota)
+    }
+
+    fn evict_target(&self, quota: u64) -> u64 {
+        (quota as f64 * self.evict_target_ratio.clamp(0.0, 1.0)) as u64
+    }
+}
+
+fn non_zero(size: ReadableSize) -> Option<u64> {
+    let bytes = size.as_byte();
+    (bytes > 0).then_some(bytes)
+}
+
+/// Category of the disk usage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Category {
+    Wal,
+    Cache,
+    Spill,
+}
+
+impl Category {
+    /// The evictable categories in the order of eviction.
+    const EVICTION_ORDER: [Category; 2] = [Category::Cache, Category::Spill];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Category::Wal => "wal",
+            Category::Cache => "cache",
+            Category::Spill => "spill",
+        }
+    }
+}
+
+/// Consumer of the local disk space.
+#[async_trait]
+pub trait DiskConsumer: fmt::Debug + Send + Sync {
+    fn category(&self) -> Category;
+
+    /// The local directory of the data, the free space of its filesystem is
+    /// watched.
+    fn dir(&self) -> Option<&Path> {
+        None
+    }
+
+    /// The bytes used on the local disk.
+    async fn usage(&self) -> GenericResult<u64>;
+
+    /// Try to free at least `bytes`, returns the bytes actually freed.
+    async fn evict(&self, _bytes: u64) -> GenericResult<u64> {
+        Ok(0)
+    }
+}
+
+pub type DiskConsumerRef = Arc<dyn DiskConsumer>;
+
+/// Consumer of a local directory which can't be evicted, e.g. the wal.
+#[derive(Debug)]
+pub struct DirConsumer {
+    category: Category,
+    dir: PathBuf,
+}
+
+impl DirConsumer {
+    pub fn new(category: Category, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            category,
+            dir: dir.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl DiskConsumer for DirConsumer {
+    fn category(&self) -> Category {
+        self.category
+    }
+
+    fn dir(&self) -> Option<&Path> {
+        Some(&self.dir)
+    }
+
+    async fn usage(&self) -> GenericResult<u64> {
+        let dir = self.dir.clone();
+        tokio::task::spawn_blocking(move || dir_size(&dir))
+            .await
+            .box_err()?
+            .box_err()
+    }
+}
+
+/// Total size of the files under the `path`, 0 if it doesn't exist.
+fn dir_size(path: &Path) -> io::Result<u64> {
+    let entries = match std::fs::read_dir(path) {
+        Ok(v) => v,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+
+    let mut size = 0;
+    for entry in entries {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            size += dir_size(&entry.path())?;
+        } else if file_type.is_file() {
+            size += entry.metadata()?.len();
+        }
+    }
+
+    Ok(size)
+}
+
+/// Free space of the filesystem where the `path` is located.
+fn free_space(path: &Path) -> GenericResult<u64> {
+    let stat = nix::sys::statvfs::statvfs(path).box_err()?;
+    Ok(stat.blocks_available() as u64 * stat.fragment_size() as u64)
+}
+
+/// Governor enforcing the disk quota of the node.
+pub struct DiskGovernor {
+    config: Config,
+    consumers: RwLock<Vec<DiskConsumerRef>>,
+    degraded: AtomicBool,
+    /// Why the node is degraded, None if it's not degraded.
+    degraded_reason: Mutex<Option<String>>,
+    stop_sender: Mutex<Option<Sender<()>>>,
+    handle: tokio::sync::Mutex<Option<JoinHandle<()>>>,
+}
+
+pub type DiskGovernorRef = Arc<DiskGovernor>;
+
+impl fmt::Debug for DiskGovernor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DiskGovernor")
+            .field("config", &self.config)
+            .field("consumers", &self.consumers)
+            .field("degraded_reason", &self.degraded_reason)
+            .finish()
+    }
+}
+
+impl DiskGovernor {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            consumers: RwLock::new(Vec::new()),
+            degraded: AtomicBool::new(false),
+            degraded_reason: Mutex::new(None),
+            stop_sender: Mutex::new(None),
+            handle: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    pub fn register(&self, consumer: DiskConsumerRef) {
+        info!("Register disk consumer, consumer:{consumer:?}");
+
+        self.consumers.write().unwrap().push(consumer);
+    }
+
+    #[inline]
+    pub fn is_degraded(&self) -> bool {
+        self.degraded.load(Ordering::Relaxed)
+    }
+
+    /// Fails if the node is degraded and the writes should be rejected.
+    pub fn ensure_writable(&self) -> Result<()> {
+        if !self.is_degraded() {
+            return Ok(());
+        }
+
+        let reason = self
+            .degraded_reason
+            .lock()
+            .unwrap()
+            .clone()
+            .unwrap_or_default();
+        Degraded { reason }.fail()
+    }
+
+    pub fn start(self: &Arc<Self>, runtime: &Runtime) {
+        let (tx, rx) = oneshot::channel();
+        *self.stop_sender.lock().unwrap() = Some(tx);
+
+        let handle = runtime.spawn(Self::check_loop(Arc::downgrade(self), rx));
+        *self.handle.try_lock().unwrap() = Some(handle);
+    }
+
+    pub async fn stop(&self) {
+        info!("Try to stop disk governor");
+
+        if let Some(tx) = self.stop_sender.lock().unwrap().take() {
+            if tx.send(()).is_err() {
+                error!("Disk governor already exited");
+            }
+        }
+
+        let mut handle = self.handle.lock().await;
+        if let Some(h) = handle.take() {
+            if let Err(e) = h.await {
+                error!("Failed to wait disk governor to exit, err:{e}");
+            }
+        }
+    }
+
+    async fn check_loop(governor: Weak<DiskGovernor>, mut stop_receiver: Receiver<()>) {
+        loop {
+            let interval = {
+                let Some(governor) = governor.upgrade() else {
+                    break;
+                };
+                governor.check().await;
+                governor.config.check_interval.0
+            };
+
+            // Stop if the stopping message is received or the sender is dropped.
+            if time::timeout(interval, &mut stop_receiver).await.is_ok() {
+                break;
+            }
+        }
+
+        info!("Disk governor exit");
+    }
+
+    /// Check the disk usage, evict the evictable consumers if necessary and
+    /// update the degraded state.
+    pub async fn check(&self) {
+        let consumers = self.consumers.read().unwrap().clone();
+        let mut usages = HashMap::with_capacity(consumers.len());
+        for consumer in &consumers {
+            match consumer.usage().await {
+                Ok(usage) => *usages.entry(consumer.category()).or_insert(0) += usage,
+                Err(e) => warn!("Failed to get disk usage, consumer:{consumer:?}, err:{e}"),
+            }
+        }
+
+        // Evict the categories exceeding their own quotas.
+        for category in Category::EVICTION_ORDER {
+            let usage = usages.get(&category).copied().unwrap_or(0);
+            if let Some(quota) = self.config.quota(category) {
+                if usage > quota {
+                    let to_free = usage - self.config.evict_target(quota);
+                    let freed = self.evict_category(&consumers, category, to_free).await;
+                    usages.insert(category, usage.saturating_sub(freed));
+                }
+            }
+        }
+
+        // Evict in the order of priority if the total quota is exceeded or the free
+        // space is not enough.
+        let mut to_free = self.total_to_free(&usages);
+        if let Some(free) = self.min_free_space(&consumers) {
+            let min_free_space = self.config.min_free_space.as_byte();
+            if free < min_free_space {
+                to_free = to_free.max(min_free_space - free);
+            }
+        }
+        for category in Category::EVICTION_ORDER {
+            if to_free == 0 {
+                break;
+            }
+            let freed = self.evict_category(&consumers, category, to_free).await;
+            if let Some(usage) = usages.get_mut(&category) {
+                *usage = usage.saturating_sub(freed);
+            }
+            to_free = to_free.saturating_sub(freed);
+        }
+
+        for category in [Category::Wal, Category::Cache, Category::Spill] {
+            DISK_USAGE_GAUGE
+                .with_label_values(&[category.as_str()])
+                .set(usages.get(&category).copied().unwrap_or(0) as i64);
+        }
+
+        let reason = self.degraded_reason(&consumers, &usages);
+        self.set_degraded(reason);
+    }
+
+    fn total_to_free(&self, usages: &HashMap<Category, u64>) -> u64 {
+        let total: u64 = usages.values().sum();
+        match non_zero(self.config.total_quota) {
+            Some(quota) if total > quota => total - self.config.evict_target(quota),
+            _ => 0,
+        }
+    }
+
+    /// The minimal free space of the filesystems of the consumers.
+    fn min_free_space(&self, consumers: &[DiskConsumerRef]) -> Option<u64> {
+        consumers
+            .iter()
+            .filter_map(|consumer| consumer.dir())
+            .filter_map(|dir| match free_space(dir) {
+                Ok(v) => Some(v),
+                Err(e) => {
+                    warn!("Failed to get free space, dir:{}, err:{e}", dir.display());
+                    None
+                }
+            })
+            .min()
+    }
+
+    async fn evict_category(
+        &self,
+        consumers: &[DiskConsumerRef],
+        category: Category,
+        bytes: u64,
+    ) -> u64 {
+        let mut freed = 0;
+        for consumer in consumers.iter().filter(|v| v.category() == category) {
+            if freed >= bytes {
+                break;
+            }
+            match consumer.evict(bytes - freed).await {
+                Ok(v) => freed += v,
+                Err(e) => warn!("Failed to evict disk consumer, consumer:{consumer:?}, err:{e}"),
+            }
+        }
+
+        if freed > 0 {
+            info!(
+                "Disk consumers evicted, category:{}, expect:{bytes}, freed:{freed}",
+                category.as_str()
+            );
+            DISK_EVICTED_BYTES_COUNTER
+                .with_label_values(&[category.as_str()])
+                .inc_by(freed);
+        }
+
+        freed
+    }
+
+    fn degraded_reason(
+        &self,
+        consumers: &[DiskConsumerRef],
+        usages: &HashMap<Category, u64>,
+    ) -> Option<String> {
+        let wal_usage = usages.get(&Category::Wal).copied().unwrap_or(0);
+        if let Some(quota) = self.config.quota(Category::Wal) {
+            if wal_usage > quota {
+                return Some(format!(
+                    "wal quota exceeded, usage:{wal_usage}, quota:{quota}"
+                ));
+            }
+        }
+
+        let total: u64 = usages.values().sum();
+        if let Some(quota) = non_zero(self.config.total_quota) {
+            if total > quota {
+                return Some(format!(
+                    "total quota exceeded, usage:{total}, quota:{quota}"
+                ));
+            }
+        }
+
+        let min_free_space = self.config.min_free_space.as_byte();
+        if let Some(free) = self.min_free_space(consumers) {
+            if free < min_free_space {
+                return Some(format!(
+                    "free space is not enough, free:{free}, reserved:{min_free_space}"
+                ));
+            }
+        }
+
+        None
+    }
+
+    fn set_degraded(&self, reason: Option<String>) {
+        let degraded = reason.is_some();
+        let was_degraded = self.degraded.swap(degraded, Ordering::Relaxed);
+        match (&reason, was_degraded) {
+            (Some(reason), false) => {
+                warn!("Node turns to degraded and rejects writes, reason:{reason}")
+            }
+            (None, true) => info!("Node recovers from degraded"),
+            _ => {}
+        }
+
+        *self.degraded_reason.lock().unwrap() = reason;
+        DISK_QUOTA_DEGRADED_GAUGE.set(degraded as i64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicU64;
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct MockConsumer {
+        category: Category,
+        usage: AtomicU64,
+        evictable: bool,
+    }
+
+    impl MockConsumer {
+        fn new(category: Category, usage: u64, evictable: bool) -> Arc<Self> {
+            Arc::new(Self {
+                category,
+                usage: AtomicU64::new(usage),
+                evictable,
+            })
+        }
+    }
+
+    #[async_trait]
+    impl DiskConsumer for MockConsumer {
+        fn category(&self) -> Category {
+            self.category
+        }
+
+        async fn usage(&self) -> GenericResult<u64> {
+            Ok(self.usage.load(Ordering::Relaxed))
+        }
+
+        async fn evict(&self, bytes: u64) -> GenericResult<u64> {
+            if !self.evictable {
+                return Ok(0);
+            }
+            let usage = self.usage.load(Ordering::Relaxed);
+            let freed = usage.min(bytes);
+            self.usage.store(usage - freed, Ordering::Relaxed);
+            Ok(freed)
+        }
+    }
+
+    fn new_config(total_quota: u64) -> Config {
+        Config {
+            enable: true,
+            total_quota: ReadableSize(total_quota),
+            evict_target_ratio: 0.5,
+            min_free_space: ReadableSize(0),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_evict_in_priority_order() {
+        let governor = DiskGovernor::new(new_config(100));
+        let wal = MockConsumer::new(Category::Wal, 20, false);
+        let cache = MockConsumer::new(Category::Cache, 30, true);
+        let spill = MockConsumer::new(Category::Spill, 60, true);
+        governor.register(wal.clone());
+        governor.register(spill.clone());
+        governor.register(cache.clone());
+
+        // Total is 110, evicted down to 50: all the cache and then 30 of the spill.
+        governor.check().await;
+        assert_eq!(20, wal.usage.load(Ordering::Relaxed));
+        assert_eq!(0, cache.usage.load(Ordering::Relaxed));
+        assert_eq!(30, spill.usage.load(Ordering::Relaxed));
+        assert!(!governor.is_degraded());
+        assert!(governor.ensure_writable().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_degraded_if_not_evictable() {
+        let governor = DiskGovernor::new(new_config(100));
+        let wal = MockConsumer::new(Category::Wal, 120, false);
+        let cache = MockConsumer::new(Category::Cache, 10, true);
+        governor.register(wal.clone());
+        governor.register(cache.clone());
+
+        governor.check().await;
+        assert_eq!(0, cache.usage.load(Ordering::Relaxed));
+        assert!(governor.is_degraded());
+        assert!(governor.ensure_writable().is_err());
+
+        // Recover after the wal is truncated.
+        wal.usage.store(40, Ordering::Relaxed);
+        governor.check().await;
+        assert!(!governor.is_degraded());
+    }
+
+    #[tokio::test]
+    async fn test_category_quota() {
+        let mut config = new_config(0);
+        config.cache_quota = ReadableSize(40);
+        config.wal_quota = ReadableSize(10);
+        let governor = DiskGovernor::new(config);
+        let wal = MockConsumer::new(Category::Wal, 20, false);
+        let cache = MockConsumer::new(Category::Cache, 60, true);
+        governor.register(wal.clone());
+        governor.register(cache.clone());
+
+        governor.check().await;
+        assert_eq!(20, cache.usage.load(Ordering::Relaxed));
+        assert!(governor.is_degraded());
+    }
+
+    #[tokio::test]
+    async fn test_dir_consumer() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        std::fs::write(dir.path().join("a"), vec![0; 10]).unwrap();
+        std::fs::write(dir.path().join("sub").join("b"), vec![0; 20]).unwrap();
+
+        let consumer = DirConsumer::new(Category::Wal, dir.path());
+        assert_eq!(30, consumer.usage().await.unwrap());
+        assert!(free_space(dir.path()).unwrap() > 0);
+
+        let consumer = DirConsumer::new(Category::Wal, dir.path().join("not_exist"));
+        assert_eq!(0, consumer.usage().await.unwrap());
+    }
+}
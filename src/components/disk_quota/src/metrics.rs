@@ -0,0 +1,44 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Metrics of the disk quota.
+
+use lazy_static::lazy_static;
+use prometheus::{
+    register_int_counter_vec, register_int_gauge, register_int_gauge_vec, IntCounterVec, IntGauge,
+    IntGaugeVec,
+};
+
+lazy_static! {
+    pub static ref DISK_USAGE_GAUGE: IntGaugeVec = register_int_gauge_vec!(
+        "disk_quota_usage_bytes",
+        "Local disk usage of the categories tracked by the disk quota",
+        &["category"]
+    )
+    .unwrap();
+    pub static ref DISK_EVICTED_BYTES_COUNTER: IntCounterVec = register_int_counter_vec!(
+        "disk_quota_evicted_bytes",
+        "Bytes evicted by the disk quota",
+        &["category"]
+    )
+    .unwrap();
+    pub static ref DISK_QUOTA_DEGRADED_GAUGE: IntGauge = register_int_gauge!(
+        "disk_quota_degraded",
+        "Whether the node is degraded and rejects writes by the disk quota"
+    )
+    .unwrap();
+}
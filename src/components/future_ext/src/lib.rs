@@ -21,4 +21,6 @@ mod cancel;
 mod retry;
 
 pub use cancel::CancellationSafeFuture;
-pub use retry::{retry_async, BackoffConfig, RetryConfig};
+pub use retry::{
+    retry_async, Backoff, BackoffConfig, RetryBudget, RetryConfig, RetryPolicy, RetryPolicyConfig,
+};
@@ -17,11 +17,31 @@
 
 //! Util function to retry future.
 
-use std::time::Duration;
+use std::{
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
 use futures::Future;
+use lazy_static::lazy_static;
+use prometheus::{register_int_counter_vec, IntCounterVec};
 use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+use time_ext::ReadableDuration;
+
+lazy_static! {
+    static ref RETRY_COUNTER: IntCounterVec = register_int_counter_vec!(
+        "retry_counter",
+        "Counter of the retries of the clients",
+        &["client", "outcome"]
+    )
+    .unwrap();
+}
 
+#[derive(Debug, Clone)]
 pub struct RetryConfig {
     pub max_retries: usize,
     pub backoff: BackoffConfig,
@@ -38,6 +58,7 @@ impl Default for RetryConfig {
 
 // This backoff implementation is ported from
 // https://github.com/apache/arrow-rs/blob/dfb642809e93c2c1b8343692f4e4b3080000f988/object_store/src/client/backoff.rs#L26
+#[derive(Debug, Clone)]
 pub struct BackoffConfig {
     /// The initial backoff duration
     pub init_backoff: Duration,
@@ -120,6 +141,176 @@ where
     f().await
 }
 
+/// Serializable config of the [RetryPolicy].
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RetryPolicyConfig {
+    /// Max number of retries of a request, no retry if it is zero
+    pub max_retries: usize,
+    /// The initial backoff, which grows exponentially with jitter
+    pub init_backoff: ReadableDuration,
+    /// The upper bound of the backoff
+    pub max_backoff: ReadableDuration,
+    /// Number of retries earned by every request, which limits the retries
+    /// to a ratio of the requests, no budget if it is zero
+    pub budget_ratio: f64,
+    /// Number of retries allowed in a burst, regardless of the ratio
+    pub budget_burst: usize,
+}
+
+impl Default for RetryPolicyConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            init_backoff: ReadableDuration::millis(100),
+            max_backoff: ReadableDuration::secs(15),
+            budget_ratio: 0.2,
+            budget_burst: 10,
+        }
+    }
+}
+
+impl From<&RetryPolicyConfig> for RetryConfig {
+    fn from(config: &RetryPolicyConfig) -> Self {
+        Self {
+            max_retries: config.max_retries,
+            backoff: BackoffConfig {
+                init_backoff: config.init_backoff.0,
+                max_backoff: config.max_backoff.0,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Cost of a retry in the budget, the balance is kept in thousandths of the
+/// retries to support fractional ratios.
+const RETRY_COST: i64 = 1000;
+
+/// Budget shared by the requests of a client, which stops the retries from
+/// amplifying the load when the downstream keeps failing.
+#[derive(Debug)]
+pub struct RetryBudget {
+    balance: AtomicI64,
+    max_balance: i64,
+    deposit: i64,
+}
+
+impl RetryBudget {
+    /// Every request earns `ratio` retries, and at most `burst` retries can be
+    /// saved up.
+    pub fn new(ratio: f64, burst: usize) -> Self {
+        let max_balance = burst as i64 * RETRY_COST;
+        Self {
+            balance: AtomicI64::new(max_balance),
+            max_balance,
+            deposit: (ratio * RETRY_COST as f64) as i64,
+        }
+    }
+
+    fn deposit(&self) {
+        let _ = self
+            .balance
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |balance| {
+                Some((balance + self.deposit).min(self.max_balance))
+            });
+    }
+
+    fn try_withdraw(&self) -> bool {
+        self.balance
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |balance| {
+                (balance >= RETRY_COST).then_some(balance - RETRY_COST)
+            })
+            .is_ok()
+    }
+}
+
+/// Retry policy of a client, which retries the retryable errors with jittered
+/// exponential backoff within the budget, and reports the outcomes labeled by
+/// the client name.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    client: &'static str,
+    config: RetryConfig,
+    budget: Option<Arc<RetryBudget>>,
+}
+
+impl RetryPolicy {
+    pub fn new(client: &'static str, config: RetryConfig) -> Self {
+        Self {
+            client,
+            config,
+            budget: None,
+        }
+    }
+
+    pub fn from_config(client: &'static str, config: &RetryPolicyConfig) -> Self {
+        let policy = Self::new(client, config.into());
+        if config.budget_ratio > 0.0 {
+            policy.with_budget(Arc::new(RetryBudget::new(
+                config.budget_ratio,
+                config.budget_burst,
+            )))
+        } else {
+            policy
+        }
+    }
+
+    pub fn with_budget(mut self, budget: Arc<RetryBudget>) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Call `f` until it succeeds, or fails with an error not retryable
+    /// according to `is_retryable`, or the retries are used up.
+    pub async fn retry<F, Fut, T, E, R>(&self, f: F, is_retryable: R) -> Result<T, E>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+        R: Fn(&E) -> bool,
+    {
+        if let Some(budget) = &self.budget {
+            budget.deposit();
+        }
+
+        let mut backoff = Backoff::new(&self.config.backoff);
+        let mut retries = 0;
+        loop {
+            let e = match f().await {
+                Ok(v) => return Ok(v),
+                Err(e) => e,
+            };
+
+            let give_up = if !is_retryable(&e) {
+                Some("non_retryable")
+            } else if retries >= self.config.max_retries {
+                Some("exhausted")
+            } else if !self
+                .budget
+                .as_ref()
+                .map(|budget| budget.try_withdraw())
+                .unwrap_or(true)
+            {
+                Some("budget_exhausted")
+            } else {
+                None
+            };
+            if let Some(outcome) = give_up {
+                RETRY_COUNTER
+                    .with_label_values(&[self.client, outcome])
+                    .inc();
+                return Err(e);
+            }
+
+            RETRY_COUNTER
+                .with_label_values(&[self.client, "retry"])
+                .inc();
+            tokio::time::sleep(backoff.next()).await;
+            retries += 1;
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::atomic::{AtomicU8, Ordering};
@@ -178,6 +369,48 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_retry_policy() {
+        let config = RetryConfig {
+            max_retries: 3,
+            backoff: BackoffConfig {
+                init_backoff: Duration::from_millis(1),
+                max_backoff: Duration::from_millis(1),
+                base: 2.,
+            },
+        };
+
+        // non retryable errors are returned directly
+        {
+            let policy = RetryPolicy::new("test", config.clone());
+            let runs = AtomicU8::new(0);
+            let f = || {
+                runs.fetch_add(1, Ordering::Relaxed);
+                futures::future::err::<i32, i32>(1)
+            };
+
+            let ret = policy.retry(f, |e| *e != 1).await;
+            assert!(ret.is_err());
+            assert_eq!(1, runs.load(Ordering::Relaxed));
+        }
+
+        // retries are limited by the budget
+        {
+            let budget = Arc::new(RetryBudget::new(0.0, 1));
+            let policy = RetryPolicy::new("test", config).with_budget(budget);
+            let runs = AtomicU8::new(0);
+            let f = || {
+                runs.fetch_add(1, Ordering::Relaxed);
+                futures::future::err::<i32, i32>(1)
+            };
+
+            assert!(policy.retry(f, |_| true).await.is_err());
+            assert_eq!(2, runs.load(Ordering::Relaxed));
+            assert!(policy.retry(f, |_| true).await.is_err());
+            assert_eq!(3, runs.load(Ordering::Relaxed));
+        }
+    }
+
     #[test]
     fn test_backoff() {
         let init_backoff_secs = 1.0;
@@ -15,6 +15,8 @@
 // specific language governing permissions and limitations
 // under the License.
 
+#[doc(hidden)]
+pub use std::format_args as log_format_args;
 use std::{
     fmt,
     fs::{File, OpenOptions},
@@ -31,6 +33,8 @@ pub use log::{
     debug as log_debug, error as log_error, info as log_info, max_level, trace as log_trace,
     warn as log_warn, SetLoggerError,
 };
+#[doc(hidden)]
+pub use runtime::verbose::current_request_id as verbose_request_id;
 use runtime::Priority;
 use serde::{Deserialize, Serialize};
 pub use slog::Level;
@@ -42,6 +46,8 @@ const ASYNC_CHAN_SIZE: usize = 102400;
 const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.3f";
 pub const SLOW_QUERY_TAG: &str = "slow";
 pub const DEFAULT_TAG: &str = "";
+/// Tag of the debug logs emitted by requests marked as verbose.
+pub const VERBOSE_TAG: &str = "verbose";
 
 // Thanks to tikv
 // https://github.com/tikv/tikv/blob/eaeb39a2c85684de08c48cf4b9426b3faf4defe6/components/tikv_util/src/logger/mod.rs
@@ -473,6 +479,7 @@ pub struct SlowTimer<'a> {
     slow_threshold: Duration,
     start_time: Instant,
     priority: Option<Priority>,
+    fingerprint: Option<String>,
 }
 
 impl<'a> Drop for SlowTimer<'a> {
@@ -480,10 +487,11 @@ impl<'a> Drop for SlowTimer<'a> {
         let cost = self.elapsed();
         if cost > self.slow_threshold {
             slow_query!(
-                "Normal query elapsed:{:?}, id:{}, priority:{:?}, query:{}",
+                "Normal query elapsed:{:?}, id:{}, priority:{:?}, fingerprint:{:?}, query:{}",
                 cost,
                 self.request_id,
                 self.priority,
+                self.fingerprint,
                 self.sql,
             );
         }
@@ -498,6 +506,7 @@ impl<'a> SlowTimer<'a> {
             slow_threshold: threshold,
             start_time: Instant::now(),
             priority: None,
+            fingerprint: None,
         }
     }
 
@@ -512,6 +521,10 @@ impl<'a> SlowTimer<'a> {
     pub fn priority(&mut self, priority: Priority) {
         self.priority = Some(priority);
     }
+
+    pub fn fingerprint(&mut self, fingerprint: String) {
+        self.fingerprint = Some(fingerprint);
+    }
 }
 
 #[macro_export(local_inner_macros)]
@@ -554,7 +567,15 @@ macro_rules! debug {
     }};
 
     ($($arg:tt)+) => {{
-        log_debug!(target: logger::DEFAULT_TAG, $($arg)+);
+        match logger::verbose_request_id() {
+            Some(request_id) => log_info!(
+                target: logger::VERBOSE_TAG,
+                "verbose request_id:{}, {}",
+                request_id,
+                log_format_args!($($arg)+)
+            ),
+            None => log_debug!(target: logger::DEFAULT_TAG, $($arg)+),
+        }
     }}
 }
 
@@ -565,7 +586,15 @@ macro_rules! trace {
     }};
 
     ($($arg:tt)+) => {{
-        log_trace!(target: logger::DEFAULT_TAG, $($arg)+);
+        match logger::verbose_request_id() {
+            Some(request_id) => log_info!(
+                target: logger::VERBOSE_TAG,
+                "verbose request_id:{}, {}",
+                request_id,
+                log_format_args!($($arg)+)
+            ),
+            None => log_trace!(target: logger::DEFAULT_TAG, $($arg)+),
+        }
     }}
 }
 
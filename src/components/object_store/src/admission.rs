@@ -0,0 +1,203 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+//! Admission policy of the memory cache.
+//!
+//! A plain LRU cache admits every loaded block, so one large scan is enough to
+//! flush all the hot blocks out of it. [TinyLfu] keeps an approximate access
+//! frequency of the recent keys, and once the cache is full, a new block is
+//! only admitted if it is accessed more frequently than the block it would
+//! evict.
+
+use std::hash::{BuildHasher, Hash};
+
+use hash_ext::ahash::RandomState;
+use serde::{Deserialize, Serialize};
+
+/// Width of each row of the frequency sketch in one cache partition.
+const DEFAULT_SKETCH_WIDTH: usize = 1 << 12;
+const SKETCH_DEPTH: usize = 4;
+/// The counters are saturated at 15 like a 4-bit counter, which is enough to
+/// tell the hot keys from the cold ones.
+const MAX_COUNTER: u8 = 15;
+/// Counters are halved after `width * SAMPLE_FACTOR` accesses so the sketch
+/// follows the changes of the workload.
+const SAMPLE_FACTOR: usize = 10;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum AdmissionPolicy {
+    /// Admit all the blocks, and evict the least recently used ones.
+    #[default]
+    Lru,
+    /// Admit a block only if it's more frequently accessed than the victim.
+    TinyLfu,
+}
+
+/// Count-min sketch estimating the access frequency of the keys.
+#[derive(Debug)]
+pub struct FrequencySketch {
+    table: Vec<u8>,
+    width_mask: usize,
+    hash_builder: RandomState,
+    additions: usize,
+    sample_size: usize,
+}
+
+impl FrequencySketch {
+    pub fn new(width: usize) -> Self {
+        let width = width.max(1).next_power_of_two();
+        Self {
+            table: vec![0; width * SKETCH_DEPTH],
+            width_mask: width - 1,
+            // A different seed from the one used for partitioning, otherwise keys in one
+            // partition will be gathered in a part of the sketch.
+            hash_builder: RandomState::with_seeds(
+                0x243f_6a88_85a3_08d3,
+                0x1319_8a2e_0370_7344,
+                0xa409_3822_299f_31d0,
+                0x082e_fa98_ec4e_6c89,
+            ),
+            additions: 0,
+            sample_size: width * SAMPLE_FACTOR,
+        }
+    }
+
+    /// Record an access of the key.
+    pub fn increment<K: Hash + ?Sized>(&mut self, key: &K) {
+        let hash = self.hash_builder.hash_one(key);
+        for row in 0..SKETCH_DEPTH {
+            let idx = self.index_of(hash, row);
+            if self.table[idx] < MAX_COUNTER {
+                self.table[idx] += 1;
+            }
+        }
+
+        self.additions += 1;
+        if self.additions >= self.sample_size {
+            self.reset();
+        }
+    }
+
+    /// Estimate the access frequency of the key.
+    pub fn frequency<K: Hash + ?Sized>(&self, key: &K) -> u8 {
+        let hash = self.hash_builder.hash_one(key);
+        (0..SKETCH_DEPTH)
+            .map(|row| self.table[self.index_of(hash, row)])
+            .min()
+            .unwrap_or_default()
+    }
+
+    fn index_of(&self, hash: u64, row: usize) -> usize {
+        // Double hashing to derive the index of every row from one hash.
+        let h1 = hash as usize;
+        let h2 = ((hash >> 32) as usize) | 1;
+        let col = h1.wrapping_add(row.wrapping_mul(h2)) & self.width_mask;
+        row * (self.width_mask + 1) + col
+    }
+
+    /// Halve all the counters.
+    fn reset(&mut self) {
+        for counter in &mut self.table {
+            *counter >>= 1;
+        }
+        self.additions /= 2;
+    }
+}
+
+/// TinyLFU admission filter.
+#[derive(Debug)]
+pub struct TinyLfu {
+    sketch: FrequencySketch,
+}
+
+impl Default for TinyLfu {
+    fn default() -> Self {
+        Self::new(DEFAULT_SKETCH_WIDTH)
+    }
+}
+
+impl TinyLfu {
+    pub fn new(sketch_width: usize) -> Self {
+        Self {
+            sketch: FrequencySketch::new(sketch_width),
+        }
+    }
+
+    pub fn record_access<K: Hash + ?Sized>(&mut self, key: &K) {
+        self.sketch.increment(key);
+    }
+
+    /// Whether the `candidate` should be admitted by evicting the `victim`.
+    pub fn admit<K: Hash + ?Sized>(&self, candidate: &K, victim: &K) -> bool {
+        self.sketch.frequency(candidate) > self.sketch.frequency(victim)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frequency_sketch() {
+        let mut sketch = FrequencySketch::new(64);
+        assert_eq!(sketch.frequency("a"), 0);
+
+        for _ in 0..3 {
+            sketch.increment("a");
+        }
+        sketch.increment("b");
+        assert_eq!(sketch.frequency("a"), 3);
+        assert_eq!(sketch.frequency("b"), 1);
+
+        // Counters are saturated.
+        for _ in 0..100 {
+            sketch.increment("c");
+        }
+        assert_eq!(sketch.frequency("c"), MAX_COUNTER);
+    }
+
+    #[test]
+    fn test_frequency_sketch_reset() {
+        let mut sketch = FrequencySketch::new(64);
+        for _ in 0..8 {
+            sketch.increment("a");
+        }
+        sketch.reset();
+        assert_eq!(sketch.frequency("a"), 4);
+
+        // The counters are halved after `sample_size` additions.
+        let mut sketch = FrequencySketch::new(4);
+        for _ in 0..sketch.sample_size - 1 {
+            sketch.increment("b");
+        }
+        assert_eq!(sketch.frequency("b"), MAX_COUNTER);
+        sketch.increment("b");
+        assert_eq!(sketch.frequency("b"), MAX_COUNTER / 2);
+    }
+
+    #[test]
+    fn test_tiny_lfu_admit() {
+        let mut filter = TinyLfu::new(64);
+        filter.record_access("hot");
+        filter.record_access("hot");
+        filter.record_access("cold");
+
+        assert!(filter.admit("hot", "cold"));
+        assert!(!filter.admit("cold", "hot"));
+        // Equal frequency is not admitted to keep the cache stable.
+        assert!(!filter.admit("cold", "cold"));
+    }
+}
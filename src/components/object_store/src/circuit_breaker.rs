@@ -0,0 +1,220 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Object store guarded by a circuit breaker.
+
+use std::{fmt::Display, future::Future, ops::Range};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use circuit_breaker::CircuitBreaker;
+use futures::{stream, stream::BoxStream, StreamExt};
+use upstream::{
+    path::Path, Error as StoreError, GetOptions, GetResult, ListResult, MultipartUpload,
+    ObjectMeta, ObjectStore, PutMultipartOpts, PutOptions, PutPayload, PutResult, Result,
+};
+
+use crate::{restore_retry, ObjectStoreRef};
+
+const CIRCUIT_BREAKER: &str = "CIRCUIT_BREAKER";
+
+/// A object store wrapper failing fast when the underlying store keeps
+/// failing, instead of piling up the requests on the timeouts.
+#[derive(Debug)]
+pub struct StoreWithCircuitBreaker {
+    store: ObjectStoreRef,
+    breaker: CircuitBreaker,
+}
+
+impl StoreWithCircuitBreaker {
+    pub fn new(store: ObjectStoreRef, config: circuit_breaker::Config) -> Self {
+        Self {
+            store,
+            breaker: CircuitBreaker::new("object_store", config),
+        }
+    }
+
+    fn acquire(&self) -> Result<()> {
+        self.breaker
+            .acquire()
+            .map_err(|source| StoreError::Generic {
+                store: CIRCUIT_BREAKER,
+                source: Box::new(source),
+            })
+    }
+
+    fn on_result<T>(&self, res: &Result<T>) {
+        let success = match res {
+            Ok(_) => true,
+            Err(e) => !is_store_failure(e),
+        };
+        self.breaker.on_result(success);
+    }
+
+    async fn call<T>(&self, f: impl Future<Output = Result<T>>) -> Result<T> {
+        self.acquire()?;
+        let res = f.await;
+        self.on_result(&res);
+        res
+    }
+}
+
+/// Errors which are caused by the request rather than the unavailable store
+/// shouldn't trip the breaker, neither the reads of the archived objects.
+fn is_store_failure(e: &StoreError) -> bool {
+    !restore_retry::is_restore_pending(e)
+        && !matches!(
+            e,
+            StoreError::NotFound { .. }
+                | StoreError::InvalidPath { .. }
+                | StoreError::NotSupported { .. }
+                | StoreError::AlreadyExists { .. }
+                | StoreError::Precondition { .. }
+                | StoreError::NotModified { .. }
+                | StoreError::NotImplemented
+        )
+}
+
+impl Display for StoreWithCircuitBreaker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Store with circuit breaker, underlying store:{}",
+            self.store
+        )
+    }
+}
+
+#[async_trait]
+impl ObjectStore for StoreWithCircuitBreaker {
+    async fn put(&self, location: &Path, payload: PutPayload) -> Result<PutResult> {
+        self.call(self.store.put(location, payload)).await
+    }
+
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        self.call(self.store.put_opts(location, payload, opts))
+            .await
+    }
+
+    async fn put_multipart(&self, location: &Path) -> Result<Box<dyn MultipartUpload>> {
+        self.call(self.store.put_multipart(location)).await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        self.call(self.store.put_multipart_opts(location, opts))
+            .await
+    }
+
+    async fn get(&self, location: &Path) -> Result<GetResult> {
+        self.call(self.store.get(location)).await
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        self.call(self.store.get_opts(location, options)).await
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        self.call(self.store.get_range(location, range)).await
+    }
+
+    async fn get_ranges(&self, location: &Path, ranges: &[Range<usize>]) -> Result<Vec<Bytes>> {
+        self.call(self.store.get_ranges(location, ranges)).await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        self.call(self.store.head(location)).await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        self.call(self.store.delete(location)).await
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        if let Err(e) = self.acquire() {
+            return stream::once(async { Err(e) }).boxed();
+        }
+
+        self.store
+            .list(prefix)
+            .map(|res| {
+                self.on_result(&res);
+                res
+            })
+            .boxed()
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        self.call(self.store.list_with_delimiter(prefix)).await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        self.call(self.store.copy(from, to)).await
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        self.call(self.store.rename(from, to)).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.call(self.store.copy_if_not_exists(from, to)).await
+    }
+
+    async fn rename_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.call(self.store.rename_if_not_exists(from, to)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tempfile::tempdir;
+    use time_ext::ReadableDuration;
+
+    use super::*;
+    use crate::local_file;
+
+    #[tokio::test]
+    async fn test_not_found_not_trip_breaker() {
+        let dir = tempdir().unwrap();
+        let local_path = dir.path().to_string_lossy().to_string();
+        let local = local_file::try_new_with_default(local_path).unwrap();
+        let config = circuit_breaker::Config {
+            enable: true,
+            failure_threshold: 1,
+            open_duration: ReadableDuration::secs(60),
+            half_open_max_probes: 1,
+        };
+        let store = StoreWithCircuitBreaker::new(Arc::new(local), config);
+
+        let location = Path::from("not_exist");
+        for _ in 0..3 {
+            let res = store.head(&location).await;
+            assert!(matches!(res, Err(StoreError::NotFound { .. })));
+        }
+        assert_eq!(circuit_breaker::State::Closed, store.breaker.state());
+    }
+}
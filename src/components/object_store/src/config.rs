@@ -21,6 +21,8 @@ use serde::{Deserialize, Serialize};
 use size_ext::ReadableSize;
 use time_ext::ReadableDuration;
 
+use crate::{admission::AdmissionPolicy, restore_retry};
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(default)]
 /// Options for storage backend
@@ -28,6 +30,7 @@ pub struct StorageOptions {
     // 0 means disable mem cache
     pub mem_cache_capacity: ReadableSize,
     pub mem_cache_partition_bits: usize,
+    pub mem_cache_admission: AdmissionPolicy,
     // 0 means disable disk cache
     // Note: disk_cache_capacity % (disk_cache_page_size * (1 << disk_cache_partition_bits)) should
     // be 0
@@ -36,6 +39,12 @@ pub struct StorageOptions {
     pub disk_cache_partition_bits: usize,
     pub disk_cache_dir: String,
     pub object_store: ObjectStoreOptions,
+    /// Circuit breaker of the underlying object store, the caches are not
+    /// guarded by it.
+    pub circuit_breaker: circuit_breaker::Config,
+    /// Retry of reading the objects transitioned to the archive storage
+    /// classes by the lifecycle policies.
+    pub restore_retry: restore_retry::Config,
 }
 
 impl Default for StorageOptions {
@@ -45,11 +54,14 @@ impl Default for StorageOptions {
         StorageOptions {
             mem_cache_capacity: ReadableSize::mb(512),
             mem_cache_partition_bits: 6,
+            mem_cache_admission: AdmissionPolicy::default(),
             disk_cache_dir: root_path.clone(),
             disk_cache_capacity: ReadableSize::gb(0),
             disk_cache_page_size: ReadableSize::mb(2),
             disk_cache_partition_bits: 4,
             object_store: ObjectStoreOptions::Local(LocalOptions::new_with_default(root_path)),
+            circuit_breaker: circuit_breaker::Config::default(),
+            restore_retry: restore_retry::Config::default(),
         }
     }
 }
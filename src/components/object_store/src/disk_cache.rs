@@ -28,7 +28,9 @@ use async_trait::async_trait;
 use bytes::{Bytes, BytesMut};
 use chrono::{DateTime, Utc};
 use crc::{Crc, CRC_32_ISCSI};
+use disk_quota::{Category, DiskConsumer};
 use futures::stream::BoxStream;
+use generic_error::GenericResult;
 use hash_ext::SeaHasherBuilder;
 use logger::{debug, warn};
 use lru::LruCache;
@@ -399,6 +401,52 @@ impl DiskCache {
         }
     }
 
+    /// Bytes of all the cached pages.
+    fn usage(&self) -> usize {
+        self.meta_cache
+            .get_all_partition()
+            .iter()
+            .map(|partition| {
+                partition
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .map(|(_, page_meta)| page_meta.file_size)
+                    .sum::<usize>()
+            })
+            .sum()
+    }
+
+    /// Evict the least recently used pages of every partition in turn until
+    /// `bytes` are freed, returns the freed bytes.
+    async fn evict(&self, bytes: usize) -> usize {
+        let mut freed = 0;
+        let mut evicted_files = Vec::new();
+        let partitions = self.meta_cache.get_all_partition();
+        while freed < bytes {
+            let mut has_evicted = false;
+            for partition in partitions {
+                if freed >= bytes {
+                    break;
+                }
+                if let Some((filename, page_meta)) = partition.lock().unwrap().pop_lru() {
+                    freed += page_meta.file_size;
+                    evicted_files.push(filename);
+                    has_evicted = true;
+                }
+            }
+            if !has_evicted {
+                break;
+            }
+        }
+
+        for filename in evicted_files {
+            self.remove_file_by_name(&filename).await;
+        }
+
+        freed
+    }
+
     async fn remove_file_by_name(&self, filename: &str) {
         debug!("Try to remove file:{filename}");
 
@@ -826,6 +874,25 @@ impl Display for DiskCacheStore {
     }
 }
 
+#[async_trait]
+impl DiskConsumer for DiskCacheStore {
+    fn category(&self) -> Category {
+        Category::Cache
+    }
+
+    fn dir(&self) -> Option<&std::path::Path> {
+        Some(std::path::Path::new(&self.cache.root_dir))
+    }
+
+    async fn usage(&self) -> GenericResult<u64> {
+        Ok(self.cache.usage() as u64)
+    }
+
+    async fn evict(&self, bytes: u64) -> GenericResult<u64> {
+        Ok(self.cache.evict(bytes as usize).await as u64)
+    }
+}
+
 #[async_trait]
 impl ObjectStore for DiskCacheStore {
     async fn put(&self, location: &Path, payload: PutPayload) -> Result<PutResult> {
@@ -1253,6 +1320,38 @@ mod test {
         });
     }
 
+    #[test]
+    fn test_disk_cache_evict() {
+        let rt = Arc::new(Builder::default().build().unwrap());
+        rt.block_on(async {
+            let page_size = 16;
+            let location = Path::from("evict.sst");
+            let store = prepare_store(page_size, 64, 0, rt.clone()).await;
+            store
+                .inner
+                .put(&location, Bytes::from(vec![1u8; 64]).into())
+                .await
+                .unwrap();
+
+            let _ = store.inner.get_range(&location, 0..16).await.unwrap();
+            let _ = store.inner.get_range(&location, 16..32).await.unwrap();
+            let _ = store.inner.get_range(&location, 32..48).await.unwrap();
+            let page_file_size = PageFileWriter::encoded_size(page_size) as u64;
+            assert_eq!(3 * page_file_size, store.inner.usage().await.unwrap());
+
+            // The least recently used page is evicted.
+            let freed = store.inner.evict(1).await.unwrap();
+            assert_eq!(page_file_size, freed);
+            assert_eq!(2 * page_file_size, store.inner.usage().await.unwrap());
+            assert!(!test_file_exists(&store.cache_dir, &location, &(0..16)));
+            assert!(test_file_exists(&store.cache_dir, &location, &(16..32)));
+
+            let freed = store.inner.evict(u64::MAX).await.unwrap();
+            assert_eq!(2 * page_file_size, freed);
+            assert_eq!(0, store.inner.usage().await.unwrap());
+        });
+    }
+
     #[test]
     fn test_disk_cache_remove_cache_file_two_partition() {
         let rt = Arc::new(Builder::default().build().unwrap());
@@ -21,11 +21,13 @@ use std::sync::Arc;
 
 pub use opendal::Error as OpenDalError;
 pub use upstream::{
-    path::Path, Error as ObjectStoreError, GetResult, ListResult, ObjectMeta, ObjectStore,
-    PutPayloadMut,
+    path::Path, Error as ObjectStoreError, GetOptions, GetResult, ListResult, MultipartUpload,
+    ObjectMeta, ObjectStore, PutMultipartOpts, PutOptions, PutPayload, PutPayloadMut, PutResult,
 };
 
+pub mod admission;
 pub mod aliyun;
+pub mod circuit_breaker;
 pub mod config;
 pub mod disk_cache;
 pub mod local_file;
@@ -33,6 +35,8 @@ pub mod mem_cache;
 pub mod metrics;
 pub mod multi_part;
 pub mod prefix;
+pub mod rate_limit;
+pub mod restore_retry;
 pub mod s3;
 #[cfg(test)]
 pub mod test_util;
@@ -18,6 +18,7 @@
 //! An implementation of ObjectStore, which support
 //! 1. Cache based on memory, and support evict based on memory usage
 //! 2. Builtin Partition to reduce lock contention
+//! 3. Optional scan-resistant admission policy, see [AdmissionPolicy]
 
 use std::{
     fmt::{self, Display},
@@ -40,7 +41,11 @@ use upstream::{
 };
 
 use crate::{
-    metrics::{OBJECT_STORE_MEMORY_CACHE_HIT, OBJECT_STORE_MEMORY_CACHE_MISS},
+    admission::{AdmissionPolicy, TinyLfu},
+    metrics::{
+        OBJECT_STORE_MEMORY_CACHE_HIT, OBJECT_STORE_MEMORY_CACHE_MISS,
+        OBJECT_STORE_MEMORY_CACHE_REJECTED,
+    },
     ObjectStoreRef,
 };
 
@@ -60,53 +65,111 @@ impl WeightScale<String, Bytes> for CustomScale {
     }
 }
 
+/// One partition of the [MemCache].
+struct Partition {
+    lru: CLruCache<String, Bytes, RandomState, CustomScale>,
+    /// Admission filter, all the blocks are admitted if it's not set.
+    admission: Option<TinyLfu>,
+}
+
+impl Partition {
+    fn get(&mut self, key: &str) -> Option<Bytes> {
+        if let Some(admission) = &mut self.admission {
+            admission.record_access(key);
+        }
+        self.lru.get(key).cloned()
+    }
+
+    fn peek(&self, key: &str) -> Option<Bytes> {
+        self.lru.peek(key).cloned()
+    }
+
+    fn insert(&mut self, key: String, value: Bytes) {
+        if let Some(admission) = &self.admission {
+            let is_overflow = self.lru.weight() + value.len() > self.lru.capacity();
+            if is_overflow && !self.lru.contains(&key) {
+                // Only compare with the least recently used block, which is the first one to
+                // be evicted.
+                if let Some((victim, _)) = self.lru.back() {
+                    if !admission.admit(key.as_str(), victim.as_str()) {
+                        OBJECT_STORE_MEMORY_CACHE_REJECTED.inc();
+                        return;
+                    }
+                }
+            }
+        }
+
+        // don't care error now.
+        _ = self.lru.put_with_weight(key, value);
+    }
+}
+
 pub struct MemCache {
     /// Max memory this store can use
     mem_cap: NonZeroUsize,
-    inner: PartitionedMutex<CLruCache<String, Bytes, RandomState, CustomScale>, RandomState>,
+    admission_policy: AdmissionPolicy,
+    inner: PartitionedMutex<Partition, RandomState>,
 }
 
 pub type MemCacheRef = Arc<MemCache>;
 
 impl MemCache {
-    pub fn try_new(partition_bits: usize, mem_cap: NonZeroUsize) -> Result<Self> {
-        let init_lru = |partition_num: usize| -> Result<_> {
+    pub fn try_new(
+        partition_bits: usize,
+        mem_cap: NonZeroUsize,
+        admission_policy: AdmissionPolicy,
+    ) -> Result<Self> {
+        let init_partition = |partition_num: usize| -> Result<_> {
             let cap_per_part =
                 NonZeroUsize::new(mem_cap.get() / partition_num).context(InvalidCapacity)?;
-            Ok(CLruCache::with_config(
+            let lru = CLruCache::with_config(
                 CLruCacheConfig::new(cap_per_part)
                     .with_hasher(build_fixed_seed_ahasher_builder())
                     .with_scale(CustomScale),
-            ))
+            );
+            let admission = match admission_policy {
+                AdmissionPolicy::Lru => None,
+                AdmissionPolicy::TinyLfu => Some(TinyLfu::default()),
+            };
+            Ok(Partition { lru, admission })
         };
 
         let inner = PartitionedMutex::try_new_with_bit_len(
-            init_lru,
+            init_partition,
             partition_bits,
             build_fixed_seed_ahasher_builder(),
         )?;
 
-        Ok(Self { mem_cap, inner })
+        Ok(Self {
+            mem_cap,
+            admission_policy,
+            inner,
+        })
     }
 
     fn get(&self, key: &str) -> Option<Bytes> {
-        self.inner.lock(&key).get(key).cloned()
+        self.inner.lock(&key).get(key)
     }
 
+    /// Get the cached value without affecting the recency and the frequency of
+    /// the key.
     fn peek(&self, key: &str) -> Option<Bytes> {
-        self.inner.lock(&key).peek(key).cloned()
+        self.inner.lock(&key).peek(key)
     }
 
     fn insert(&self, key: String, value: Bytes) {
-        // don't care error now.
-        _ = self.inner.lock(&key).put_with_weight(key, value);
+        self.inner.lock(&key).insert(key, value);
     }
 
     /// Give a description of the cache state.
 
     #[cfg(test)]
-    fn keys(&self, part: &CLruCache<String, Bytes, RandomState, CustomScale>) -> Vec<String> {
-        part.iter().map(|(key, _)| key).cloned().collect::<Vec<_>>()
+    fn keys(&self, part: &Partition) -> Vec<String> {
+        part.lru
+            .iter()
+            .map(|(key, _)| key)
+            .cloned()
+            .collect::<Vec<_>>()
     }
 
     #[cfg(test)]
@@ -126,6 +189,7 @@ impl Display for MemCache {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("MemCache")
             .field("mem_cap", &self.mem_cap)
+            .field("admission_policy", &self.admission_policy)
             .field("partitions", &self.inner.get_all_partition().len())
             .finish()
     }
@@ -299,11 +363,20 @@ mod test {
     use crate::local_file;
 
     fn prepare_store(bits: usize, mem_cap: usize) -> MemCacheStore {
+        prepare_store_with_admission(bits, mem_cap, AdmissionPolicy::Lru)
+    }
+
+    fn prepare_store_with_admission(
+        bits: usize,
+        mem_cap: usize,
+        admission_policy: AdmissionPolicy,
+    ) -> MemCacheStore {
         let local_path = tempdir().unwrap().as_ref().to_string_lossy().to_string();
         let local_store = Arc::new(local_file::try_new_with_default(local_path).unwrap());
 
-        let mem_cache =
-            Arc::new(MemCache::try_new(bits, NonZeroUsize::new(mem_cap).unwrap()).unwrap());
+        let mem_cache = Arc::new(
+            MemCache::try_new(bits, NonZeroUsize::new(mem_cap).unwrap(), admission_policy).unwrap(),
+        );
         MemCacheStore::new(mem_cache, local_store)
     }
 
@@ -396,6 +469,51 @@ mod test {
             .is_some());
     }
 
+    #[tokio::test]
+    async fn test_mem_cache_tiny_lfu_admission() {
+        // single partition, room for 2 blocks
+        let store = prepare_store_with_admission(0, 10, AdmissionPolicy::TinyLfu);
+        let location = Path::from("1.sst");
+        store
+            .put(&location, Bytes::from_static(&[1; 1024]).into())
+            .await
+            .unwrap();
+
+        // [5, 10) is read once, and [0, 5) is hot.
+        let range0_5 = 0..5;
+        let range5_10 = 5..10;
+        _ = store.get_range(&location, range5_10.clone()).await.unwrap();
+        for _ in 0..3 {
+            _ = store.get_range(&location, range0_5.clone()).await.unwrap();
+        }
+
+        // A scan reads the following ranges only once, and it shouldn't evict any of
+        // the cached blocks.
+        for start in (10..100).step_by(5) {
+            _ = store.get_range(&location, start..start + 5).await.unwrap();
+        }
+        assert_eq!("0: [1.sst-0-5,1.sst-5-10]", store.cache.state_desc());
+
+        // The block read more frequently than the victim is admitted.
+        let range10_15 = 10..15;
+        _ = store
+            .get_range(&location, range10_15.clone())
+            .await
+            .unwrap();
+        assert!(store
+            .cache
+            .peek(&MemCacheStore::cache_key(&location, &range5_10))
+            .is_none());
+        assert!(store
+            .cache
+            .peek(&MemCacheStore::cache_key(&location, &range10_15))
+            .is_some());
+        assert!(store
+            .cache
+            .peek(&MemCacheStore::cache_key(&location, &range0_5))
+            .is_some());
+    }
+
     #[test]
     fn test_mem_cache_capacity() {
         // 4 partitions
@@ -407,7 +525,7 @@ mod test {
                 .inner
                 .get_all_partition()
                 .iter()
-                .map(|p| p.lock().unwrap().capacity().to_string())
+                .map(|p| p.lock().unwrap().lru.capacity().to_string())
                 .collect::<Vec<_>>()
                 .join(",")
         );
@@ -93,6 +93,11 @@ lazy_static! {
         "object store memory cache miss"
     )
     .unwrap();
+    pub static ref OBJECT_STORE_MEMORY_CACHE_REJECTED: IntCounter = register_int_counter!(
+        "object_store_memory_cache_rejected",
+        "object store memory cache rejected by admission policy"
+    )
+    .unwrap();
     pub static ref OBJECT_STORE_DISK_CACHE_HIT: IntCounter = register_int_counter!(
         "object_store_disk_cache_hit",
         "object store disk cache hit"
@@ -0,0 +1,317 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Object store limiting the bytes read from and written to the underlying
+//! store by token buckets, so the background jobs (e.g. compaction) won't
+//! starve the foreground queries.
+
+use std::{
+    fmt::Display,
+    ops::Range,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use upstream::{
+    path::Path, GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta, ObjectStore,
+    PutMultipartOpts, PutOptions, PutPayload, PutResult, Result, UploadPart,
+};
+
+use crate::ObjectStoreRef;
+
+#[derive(Debug)]
+struct Bucket {
+    /// Available bytes, negative if the bytes are borrowed by the acquired
+    /// requests.
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// Token bucket refilled at `bytes_per_sec`, and at most one second of bytes
+/// are accumulated for the burst.
+#[derive(Debug)]
+pub struct RateLimiter {
+    bytes_per_sec: f64,
+    bucket: Mutex<Bucket>,
+}
+
+impl RateLimiter {
+    pub fn new(bytes_per_sec: u64) -> Self {
+        let bytes_per_sec = bytes_per_sec.max(1) as f64;
+        Self {
+            bytes_per_sec,
+            bucket: Mutex::new(Bucket {
+                tokens: bytes_per_sec,
+                last_refill: Instant::now(),
+            }),
+        }
+    }
+
+    /// Take the bytes from the bucket, and returns how long to wait until the
+    /// borrowed bytes are refilled.
+    fn reserve(&self, bytes: usize, now: Instant) -> Duration {
+        let mut bucket = self.bucket.lock().unwrap();
+        if now > bucket.last_refill {
+            let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+            bucket.tokens = (bucket.tokens + elapsed * self.bytes_per_sec).min(self.bytes_per_sec);
+            bucket.last_refill = now;
+        }
+
+        bucket.tokens -= bytes as f64;
+        if bucket.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-bucket.tokens / self.bytes_per_sec)
+        }
+    }
+
+    /// Wait until the bytes are allowed to be transferred.
+    pub async fn acquire(&self, bytes: usize) {
+        let wait = self.reserve(bytes, Instant::now());
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+pub type RateLimiterRef = Arc<RateLimiter>;
+
+/// A object store wrapper whose reads and writes are limited by the rate
+/// limiters, and no limit is applied if the limiter is not set.
+///
+/// The bytes of a read are acquired before its data is returned, and the
+/// bytes of a write are acquired before it's sent.
+#[derive(Debug)]
+pub struct StoreWithRateLimit {
+    store: ObjectStoreRef,
+    read_limiter: Option<RateLimiterRef>,
+    write_limiter: Option<RateLimiterRef>,
+}
+
+impl StoreWithRateLimit {
+    pub fn new(
+        store: ObjectStoreRef,
+        read_limiter: Option<RateLimiterRef>,
+        write_limiter: Option<RateLimiterRef>,
+    ) -> Self {
+        Self {
+            store,
+            read_limiter,
+            write_limiter,
+        }
+    }
+
+    async fn acquire_read(&self, bytes: usize) {
+        if let Some(limiter) = &self.read_limiter {
+            limiter.acquire(bytes).await;
+        }
+    }
+
+    async fn acquire_write(&self, bytes: usize) {
+        if let Some(limiter) = &self.write_limiter {
+            limiter.acquire(bytes).await;
+        }
+    }
+
+    fn wrap_upload(&self, upload: Box<dyn MultipartUpload>) -> Box<dyn MultipartUpload> {
+        match &self.write_limiter {
+            Some(limiter) => Box::new(RateLimitedUpload {
+                upload,
+                limiter: limiter.clone(),
+            }),
+            None => upload,
+        }
+    }
+}
+
+impl Display for StoreWithRateLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Store with rate limit, underlying store:{}", self.store)
+    }
+}
+
+#[derive(Debug)]
+struct RateLimitedUpload {
+    upload: Box<dyn MultipartUpload>,
+    limiter: RateLimiterRef,
+}
+
+#[async_trait]
+impl MultipartUpload for RateLimitedUpload {
+    fn put_part(&mut self, data: PutPayload) -> UploadPart {
+        let limiter = self.limiter.clone();
+        let bytes = data.content_length();
+        let part = self.upload.put_part(data);
+        Box::pin(async move {
+            limiter.acquire(bytes).await;
+            part.await
+        })
+    }
+
+    async fn complete(&mut self) -> Result<PutResult> {
+        self.upload.complete().await
+    }
+
+    async fn abort(&mut self) -> Result<()> {
+        self.upload.abort().await
+    }
+}
+
+#[async_trait]
+impl ObjectStore for StoreWithRateLimit {
+    async fn put(&self, location: &Path, payload: PutPayload) -> Result<PutResult> {
+        self.acquire_write(payload.content_length()).await;
+        self.store.put(location, payload).await
+    }
+
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        self.acquire_write(payload.content_length()).await;
+        self.store.put_opts(location, payload, opts).await
+    }
+
+    async fn put_multipart(&self, location: &Path) -> Result<Box<dyn MultipartUpload>> {
+        let upload = self.store.put_multipart(location).await?;
+        Ok(self.wrap_upload(upload))
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        let upload = self.store.put_multipart_opts(location, opts).await?;
+        Ok(self.wrap_upload(upload))
+    }
+
+    async fn get(&self, location: &Path) -> Result<GetResult> {
+        let res = self.store.get(location).await?;
+        self.acquire_read(res.range.len()).await;
+        Ok(res)
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        let res = self.store.get_opts(location, options).await?;
+        self.acquire_read(res.range.len()).await;
+        Ok(res)
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        self.acquire_read(range.len()).await;
+        self.store.get_range(location, range).await
+    }
+
+    async fn get_ranges(&self, location: &Path, ranges: &[Range<usize>]) -> Result<Vec<Bytes>> {
+        self.acquire_read(ranges.iter().map(|range| range.len()).sum())
+            .await;
+        self.store.get_ranges(location, ranges).await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        self.store.head(location).await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        self.store.delete(location).await
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.store.list(prefix)
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        self.store.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        self.store.copy(from, to).await
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        self.store.rename(from, to).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.store.copy_if_not_exists(from, to).await
+    }
+
+    async fn rename_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.store.rename_if_not_exists(from, to).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limiter_reserve() {
+        let limiter = RateLimiter::new(1000);
+        let start = limiter.bucket.lock().unwrap().last_refill;
+
+        // The burst of one second is allowed.
+        assert_eq!(Duration::ZERO, limiter.reserve(1000, start));
+        // Borrow 500 bytes.
+        assert_eq!(Duration::from_millis(500), limiter.reserve(500, start));
+        // 300 bytes are refilled, and 200 bytes are still borrowed.
+        assert_eq!(
+            Duration::from_millis(400),
+            limiter.reserve(200, start + Duration::from_millis(300))
+        );
+
+        // At most one second of bytes are accumulated.
+        let later = start + Duration::from_secs(10);
+        assert_eq!(Duration::ZERO, limiter.reserve(1000, later));
+        assert_eq!(Duration::from_millis(1), limiter.reserve(1, later));
+    }
+
+    #[tokio::test]
+    async fn test_store_with_rate_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let local_path = dir.path().to_string_lossy().to_string();
+        let local = crate::local_file::try_new_with_default(local_path).unwrap();
+        let created = Instant::now();
+        let read_limiter = Arc::new(RateLimiter::new(100));
+        let write_limiter = Arc::new(RateLimiter::new(100));
+        let store = StoreWithRateLimit::new(
+            Arc::new(local),
+            Some(read_limiter.clone()),
+            Some(write_limiter.clone()),
+        );
+
+        let location = Path::from("data");
+        store
+            .put(&location, PutPayload::from_static(&[1; 60]))
+            .await
+            .unwrap();
+        let data = store.get_range(&location, 10..50).await.unwrap();
+        assert_eq!(40, data.len());
+
+        // The transferred bytes are taken from the buckets, regardless of the refilled
+        // ones.
+        let refilled = created.elapsed().as_secs_f64() * 100.0;
+        assert!(write_limiter.bucket.lock().unwrap().tokens <= 40.0 + refilled);
+        assert!(read_limiter.bucket.lock().unwrap().tokens <= 60.0 + refilled);
+    }
+}
@@ -0,0 +1,301 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Object store retrying the reads of the objects which are transitioned to
+//! the archive storage classes by the lifecycle policies and are still being
+//! restored.
+
+use std::{
+    fmt::Display,
+    future::Future,
+    ops::Range,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use future_ext::{BackoffConfig, RetryConfig, RetryPolicy};
+use futures::stream::BoxStream;
+use logger::warn;
+use serde::{Deserialize, Serialize};
+use snafu::Snafu;
+use time_ext::ReadableDuration;
+use upstream::{
+    path::Path, Error as StoreError, GetOptions, GetResult, ListResult, MultipartUpload,
+    ObjectMeta, ObjectStore, PutMultipartOpts, PutOptions, PutPayload, PutResult, Result,
+};
+
+use crate::ObjectStoreRef;
+
+const RESTORE_RETRY: &str = "RESTORE_RETRY";
+
+/// The error code returned by s3 and oss when reading an archived object which
+/// is not restored yet.
+const INVALID_OBJECT_STATE: &str = "InvalidObjectState";
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display(
+        "Object is still being restored from the archive storage class, location:{location}, attempts:{attempts}, err:{source}"
+    ))]
+    RestorePending {
+        location: String,
+        attempts: usize,
+        source: StoreError,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    /// Whether to retry the reads of the objects being restored
+    pub enable: bool,
+    /// Max number of retries before giving up
+    pub max_retries: usize,
+    /// The backoff before the first retry, which grows exponentially with
+    /// jitter after every retry
+    pub initial_backoff: ReadableDuration,
+    /// The upper bound of the backoff
+    pub max_backoff: ReadableDuration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            max_retries: 10,
+            initial_backoff: ReadableDuration::secs(1),
+            max_backoff: ReadableDuration::minutes(1),
+        }
+    }
+}
+
+impl From<&Config> for RetryConfig {
+    fn from(config: &Config) -> Self {
+        Self {
+            max_retries: config.max_retries,
+            backoff: BackoffConfig {
+                init_backoff: config.initial_backoff.0,
+                max_backoff: config.max_backoff.0,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Whether the error is caused by reading an object not restored from the
+/// archive storage class yet, including the one given up by
+/// [StoreWithRestoreRetry].
+pub fn is_restore_pending(e: &StoreError) -> bool {
+    if let StoreError::Generic { source, .. } = e {
+        if source.downcast_ref::<Error>().is_some() {
+            return true;
+        }
+    }
+
+    e.to_string().contains(INVALID_OBJECT_STATE)
+}
+
+/// A object store wrapper retrying the reads of the archived objects with
+/// backoff, so that the readers can tolerate the restore latency.
+#[derive(Debug)]
+pub struct StoreWithRestoreRetry {
+    store: ObjectStoreRef,
+    policy: RetryPolicy,
+}
+
+impl StoreWithRestoreRetry {
+    pub fn new(store: ObjectStoreRef, config: Config) -> Self {
+        Self {
+            store,
+            policy: RetryPolicy::new("object_store_restore", (&config).into()),
+        }
+    }
+
+    async fn retry<T, F, Fut>(&self, location: &Path, f: F) -> Result<T>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let attempts = AtomicUsize::new(0);
+        let res = self
+            .policy
+            .retry(
+                || {
+                    attempts.fetch_add(1, Ordering::Relaxed);
+                    f()
+                },
+                |e| {
+                    let pending = is_restore_pending(e);
+                    if pending {
+                        warn!("Object is being restored, location:{location}, err:{e}");
+                    }
+                    pending
+                },
+            )
+            .await;
+
+        res.map_err(|e| {
+            if !is_restore_pending(&e) {
+                return e;
+            }
+            StoreError::Generic {
+                store: RESTORE_RETRY,
+                source: Box::new(Error::RestorePending {
+                    location: location.to_string(),
+                    attempts: attempts.load(Ordering::Relaxed),
+                    source: e,
+                }),
+            }
+        })
+    }
+}
+
+impl Display for StoreWithRestoreRetry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Store with restore retry, underlying store:{}",
+            self.store
+        )
+    }
+}
+
+#[async_trait]
+impl ObjectStore for StoreWithRestoreRetry {
+    async fn put(&self, location: &Path, payload: PutPayload) -> Result<PutResult> {
+        self.store.put(location, payload).await
+    }
+
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        self.store.put_opts(location, payload, opts).await
+    }
+
+    async fn put_multipart(&self, location: &Path) -> Result<Box<dyn MultipartUpload>> {
+        self.store.put_multipart(location).await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        self.store.put_multipart_opts(location, opts).await
+    }
+
+    async fn get(&self, location: &Path) -> Result<GetResult> {
+        self.retry(location, || self.store.get(location)).await
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        self.retry(location, || self.store.get_opts(location, options.clone()))
+            .await
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        self.retry(location, || self.store.get_range(location, range.clone()))
+            .await
+    }
+
+    async fn get_ranges(&self, location: &Path, ranges: &[Range<usize>]) -> Result<Vec<Bytes>> {
+        self.retry(location, || self.store.get_ranges(location, ranges))
+            .await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        self.store.head(location).await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        self.store.delete(location).await
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.store.list(prefix)
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        self.store.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        self.store.copy(from, to).await
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        self.store.rename(from, to).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.store.copy_if_not_exists(from, to).await
+    }
+
+    async fn rename_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.store.rename_if_not_exists(from, to).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_restore_pending_error() {
+        let archived = StoreError::Generic {
+            store: "S3",
+            source: "<Code>InvalidObjectState</Code><Message>The operation is not valid for the object's storage class</Message>".into(),
+        };
+        assert!(is_restore_pending(&archived));
+
+        let given_up = StoreError::Generic {
+            store: RESTORE_RETRY,
+            source: Box::new(Error::RestorePending {
+                location: "1/2/3.sst".to_string(),
+                attempts: 3,
+                source: archived,
+            }),
+        };
+        assert!(is_restore_pending(&given_up));
+
+        let not_found = StoreError::NotFound {
+            path: "1/2/3.sst".to_string(),
+            source: "not found".into(),
+        };
+        assert!(!is_restore_pending(&not_found));
+    }
+
+    #[test]
+    fn test_retry_config() {
+        let config = Config {
+            enable: true,
+            max_retries: 10,
+            initial_backoff: ReadableDuration::secs(1),
+            max_backoff: ReadableDuration::secs(5),
+        };
+        let retry_config = RetryConfig::from(&config);
+        assert_eq!(10, retry_config.max_retries);
+        assert_eq!(Duration::from_secs(1), retry_config.backoff.init_backoff);
+        assert_eq!(Duration::from_secs(5), retry_config.backoff.max_backoff);
+    }
+}
@@ -26,13 +26,22 @@ use futures::{
     future::{BoxFuture, FutureExt},
     TryFutureExt,
 };
-use object_store::{ObjectStoreRef, Path};
+use object_store::{ObjectStoreError, ObjectStoreRef, Path};
 use parquet::{arrow::async_reader::AsyncFileReader, file::metadata::ParquetMetaData};
 
 /// The observer for metrics of [ObjectStoreReader].
 pub trait MetricsObserver: Send {
     fn elapsed(&self, path: &Path, elapsed: Duration);
     fn num_bytes_fetched(&self, path: &Path, num_bytes: usize);
+    /// `num_ranges` requested ranges are fetched by `num_fetched_ranges`
+    /// coalesced ranges, with `num_gap_bytes` more bytes fetched.
+    fn ranges_coalesced(
+        &self,
+        path: &Path,
+        num_ranges: usize,
+        num_fetched_ranges: usize,
+        num_gap_bytes: usize,
+    );
 }
 
 #[derive(Debug, Clone)]
@@ -42,6 +51,27 @@ impl MetricsObserver for NoopMetricsObserver {
     fn elapsed(&self, _: &Path, _: Duration) {}
 
     fn num_bytes_fetched(&self, _: &Path, _: usize) {}
+
+    fn ranges_coalesced(&self, _: &Path, _: usize, _: usize, _: usize) {}
+}
+
+/// Merge the ranges whose gap between each other is not larger than `gap`, and
+/// the merged ranges are sorted by their starts.
+pub fn coalesce_ranges(ranges: &[Range<usize>], gap: usize) -> Vec<Range<usize>> {
+    let mut ranges = ranges.to_vec();
+    ranges.sort_unstable_by_key(|range| range.start);
+
+    let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end.saturating_add(gap) => {
+                last.end = last.end.max(range.end);
+            }
+            _ => merged.push(range),
+        }
+    }
+
+    merged
 }
 
 /// The implementation based on `ObjectStore` for [`AsyncFileReader`].
@@ -52,6 +82,9 @@ pub struct ObjectStoreReader<T: MetricsObserver> {
     meta_data: Arc<ParquetMetaData>,
     begin: Instant,
     metrics: T,
+    /// Ranges within this gap are fetched by one request, and no range will be
+    /// coalesced if it's not set.
+    coalesce_gap: Option<usize>,
 }
 
 impl ObjectStoreReader<NoopMetricsObserver> {
@@ -73,8 +106,42 @@ impl<T: MetricsObserver> ObjectStoreReader<T> {
             meta_data,
             begin: Instant::now(),
             metrics,
+            coalesce_gap: None,
         }
     }
+
+    pub fn with_coalesce_gap(mut self, coalesce_gap: Option<usize>) -> Self {
+        self.coalesce_gap = coalesce_gap;
+        self
+    }
+
+    async fn fetch_coalesced_ranges(
+        &self,
+        ranges: &[Range<usize>],
+        gap: usize,
+    ) -> Result<Vec<Bytes>, ObjectStoreError> {
+        let merged = coalesce_ranges(ranges, gap);
+        let fetched = self.storage.get_ranges(&self.path, &merged).await?;
+
+        let num_requested_bytes: usize = ranges.iter().map(|v| v.len()).sum();
+        let num_fetched_bytes: usize = merged.iter().map(|v| v.len()).sum();
+        self.metrics.ranges_coalesced(
+            &self.path,
+            ranges.len(),
+            merged.len(),
+            num_fetched_bytes.saturating_sub(num_requested_bytes),
+        );
+
+        // Every requested range must be contained by one merged range.
+        Ok(ranges
+            .iter()
+            .map(|range| {
+                let idx = merged.partition_point(|v| v.start <= range.start) - 1;
+                let offset = merged[idx].start;
+                fetched[idx].slice(range.start - offset..range.end - offset)
+            })
+            .collect())
+    }
 }
 
 impl<T: MetricsObserver> Drop for ObjectStoreReader<T> {
@@ -110,15 +177,15 @@ impl<T: MetricsObserver> AsyncFileReader for ObjectStoreReader<T> {
         ranges: Vec<Range<usize>>,
     ) -> BoxFuture<'_, parquet::errors::Result<Vec<Bytes>>> {
         async move {
-            let get_res = self
-                .storage
-                .get_ranges(&self.path, &ranges)
-                .map_err(|e| {
-                    parquet::errors::ParquetError::General(format!(
-                        "Failed to fetch ranges from object store, err:{e}"
-                    ))
-                })
-                .await;
+            let get_res = match self.coalesce_gap {
+                Some(gap) => self.fetch_coalesced_ranges(&ranges, gap).await,
+                None => self.storage.get_ranges(&self.path, &ranges).await,
+            };
+            let get_res = get_res.map_err(|e| {
+                parquet::errors::ParquetError::General(format!(
+                    "Failed to fetch ranges from object store, err:{e}"
+                ))
+            });
 
             if let Ok(bytes) = &get_res {
                 let num_bytes: usize = bytes.iter().map(|v| v.len()).sum();
@@ -136,3 +203,26 @@ impl<T: MetricsObserver> AsyncFileReader for ObjectStoreReader<T> {
         Box::pin(async move { Ok(self.meta_data.clone()) })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coalesce_ranges() {
+        let cases = vec![
+            (vec![], 0, vec![]),
+            (vec![0..10], 0, vec![0..10]),
+            (vec![0..10, 10..20, 30..40], 0, vec![0..20, 30..40]),
+            (vec![30..40, 0..10, 10..20], 0, vec![0..20, 30..40]),
+            (vec![0..10, 10..20, 30..40], 10, vec![0..40]),
+            (vec![0..10, 15..20, 30..40], 5, vec![0..20, 30..40]),
+            // Overlapped and contained ranges.
+            (vec![0..10, 5..15, 6..8], 0, vec![0..15]),
+        ];
+
+        for (ranges, gap, expect) in cases {
+            assert_eq!(coalesce_ranges(&ranges, gap), expect, "ranges:{ranges:?}");
+        }
+    }
+}
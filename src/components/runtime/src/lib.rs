@@ -34,6 +34,7 @@ use tokio::{
 
 mod metrics;
 mod priority_runtime;
+pub mod verbose;
 
 pub use priority_runtime::{Priority, PriorityRuntime};
 
@@ -82,7 +83,7 @@ impl Runtime {
         F::Output: Send + 'static,
     {
         JoinHandle {
-            inner: self.rt.spawn(future),
+            inner: self.rt.spawn(verbose::inherit(future)),
         }
     }
 
@@ -94,7 +95,7 @@ impl Runtime {
         R: Send + 'static,
     {
         JoinHandle {
-            inner: self.rt.spawn_blocking(func),
+            inner: self.rt.spawn_blocking(verbose::inherit_blocking(func)),
         }
     }
 
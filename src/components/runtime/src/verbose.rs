@@ -0,0 +1,88 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Request scoped verbose logging.
+//!
+//! A request marked as verbose carries its request id in a task local, and
+//! the mark is inherited by the tasks spawned on any [`Runtime`] while
+//! serving it, so the debug logs of this single request can be emitted
+//! without raising the global log level.
+//!
+//! [`Runtime`]: crate::Runtime
+
+use std::{future::Future, sync::Arc};
+
+tokio::task_local! {
+    static VERBOSE_REQUEST_ID: Option<Arc<str>>;
+}
+
+/// Returns the request id if current task is serving a verbose request.
+pub fn current_request_id() -> Option<Arc<str>> {
+    VERBOSE_REQUEST_ID.try_with(|id| id.clone()).ok().flatten()
+}
+
+/// Run the `future` as a verbose request identified by `request_id`, nothing
+/// is marked if `request_id` is `None`.
+pub async fn scope<F: Future>(request_id: Option<Arc<str>>, future: F) -> F::Output {
+    VERBOSE_REQUEST_ID.scope(request_id, future).await
+}
+
+/// Make the `future` inherit the verbose mark of current task.
+pub(crate) fn inherit<F: Future>(future: F) -> impl Future<Output = F::Output> {
+    VERBOSE_REQUEST_ID.scope(current_request_id(), future)
+}
+
+/// Make the blocking `func` inherit the verbose mark of current task.
+pub(crate) fn inherit_blocking<F, R>(func: F) -> impl FnOnce() -> R
+where
+    F: FnOnce() -> R,
+{
+    let request_id = current_request_id();
+    move || VERBOSE_REQUEST_ID.sync_scope(request_id, func)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Builder;
+
+    #[test]
+    fn test_verbose_scope() {
+        let rt = Arc::new(Builder::default().worker_threads(2).build().unwrap());
+        let rt2 = rt.clone();
+
+        rt.block_on(async move {
+            assert!(current_request_id().is_none());
+
+            let id: Arc<str> = Arc::from("req-1");
+            let (spawned, blocking) = scope(Some(id), async move {
+                assert_eq!(current_request_id().as_deref(), Some("req-1"));
+
+                let spawned = rt2.spawn(async { current_request_id() });
+                let blocking = rt2.spawn_blocking(current_request_id);
+                (spawned.await.unwrap(), blocking.await.unwrap())
+            })
+            .await;
+            assert_eq!(spawned.as_deref(), Some("req-1"));
+            assert_eq!(blocking.as_deref(), Some("req-1"));
+
+            assert!(current_request_id().is_none());
+            let not_marked = scope(None, async { current_request_id() }).await;
+            assert!(not_marked.is_none());
+        });
+    }
+}
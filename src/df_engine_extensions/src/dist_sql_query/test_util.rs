@@ -52,7 +52,7 @@ use table_engine::{
     memory::MemoryTable,
     predicate::PredicateBuilder,
     remote::model::TableIdentifier,
-    table::{ReadOptions, ReadRequest, TableId, TableRef},
+    table::{ReadOptions, ReadOrder, ReadRequest, TableId, TableRef},
     ANALYTIC_ENGINE_TYPE,
 };
 use trace_metric::MetricsCollector;
@@ -436,6 +436,11 @@ impl ExecutableScanBuilder for MockScanBuilder {
                 batch_size: ctx.batch_size,
                 read_parallelism: ctx.read_parallelism,
                 deadline: None,
+                snapshot: None,
+                stats: None,
+                no_cache: false,
+                limit: None,
+                order: ReadOrder::None,
             },
             projected_schema: ctx.projected_schema.clone(),
             predicate: ctx.predicate.clone(),
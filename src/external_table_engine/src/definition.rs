@@ -0,0 +1,174 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Definition of the external tables.
+//!
+//! The definitions are kept out of the manifest of the analytic engine, they
+//! are persisted as json files in the object store, so the external tables can
+//! be reopened after restart.
+
+use std::collections::HashMap;
+
+use generic_error::BoxError;
+use logger::warn;
+use object_store::{ObjectStoreRef, Path};
+use serde::{Deserialize, Serialize};
+use snafu::{ensure, OptionExt, ResultExt};
+use table_engine::table::SchemaId;
+
+use crate::error::{
+    DeleteDefinition, InvalidLocation, LoadDefinition, MissingOption, Result, StoreDefinition,
+};
+
+/// Option of the path prefix of the parquet files, relative to the
+/// [data_prefix](crate::Config::data_prefix).
+pub const LOCATION: &str = "location";
+/// Option of the timestamp column of the table.
+pub const TIMESTAMP_COLUMN: &str = "timestamp_column";
+
+const DEFINITION_DIR: &str = "external_tables";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TableDefinition {
+    /// Path prefix of the parquet files in the object store.
+    pub location: String,
+    /// Name of the timestamp column, the first timestamp column of the parquet
+    /// files is used if not set.
+    pub timestamp_column: Option<String>,
+}
+
+impl TableDefinition {
+    pub fn from_options(options: &HashMap<String, String>) -> Result<Self> {
+        let location = options
+            .get(LOCATION)
+            .context(MissingOption { option: LOCATION })?;
+
+        Ok(Self {
+            location: normalize_location(location)?,
+            timestamp_column: options.get(TIMESTAMP_COLUMN).cloned(),
+        })
+    }
+
+    pub fn to_options(&self) -> HashMap<String, String> {
+        let mut options = HashMap::from([(LOCATION.to_string(), self.location.clone())]);
+        if let Some(column) = &self.timestamp_column {
+            options.insert(TIMESTAMP_COLUMN.to_string(), column.clone());
+        }
+
+        options
+    }
+}
+
+/// Normalize the location and refuse the one which may escape the data prefix.
+pub(crate) fn normalize_location(location: &str) -> Result<String> {
+    let normalized = location.trim_matches('/');
+    ensure!(
+        !normalized.is_empty(),
+        InvalidLocation {
+            location,
+            msg: "location is empty",
+        }
+    );
+    for segment in normalized.split('/') {
+        ensure!(
+            !segment.is_empty() && segment != "." && segment != ".." && !segment.contains('\\'),
+            InvalidLocation {
+                location,
+                msg: format!("invalid segment:{segment:?}"),
+            }
+        );
+    }
+
+    Ok(normalized.to_string())
+}
+
+fn definition_path(schema_id: SchemaId, table_name: &str) -> Path {
+    Path::from(format!(
+        "{DEFINITION_DIR}/{}/{table_name}.json",
+        schema_id.as_u32()
+    ))
+}
+
+pub async fn store_definition(
+    store: &ObjectStoreRef,
+    schema_id: SchemaId,
+    table_name: &str,
+    definition: &TableDefinition,
+) -> Result<()> {
+    let path = definition_path(schema_id, table_name);
+    let payload = serde_json::to_vec(definition)
+        .box_err()
+        .with_context(|| StoreDefinition {
+            path: path.to_string(),
+        })?;
+    store
+        .put(&path, payload.into())
+        .await
+        .box_err()
+        .with_context(|| StoreDefinition {
+            path: path.to_string(),
+        })?;
+
+    Ok(())
+}
+
+/// Load the definition of the table, returns None if not found.
+pub async fn load_definition(
+    store: &ObjectStoreRef,
+    schema_id: SchemaId,
+    table_name: &str,
+) -> Result<Option<TableDefinition>> {
+    let path = definition_path(schema_id, table_name);
+    let get_res = store.get(&path).await;
+    if let Err(object_store::ObjectStoreError::NotFound { .. }) = &get_res {
+        warn!("External table definition doesn't exist, path:{}", path);
+        return Ok(None);
+    }
+
+    let payload = get_res
+        .box_err()
+        .with_context(|| LoadDefinition {
+            path: path.to_string(),
+        })?
+        .bytes()
+        .await
+        .box_err()
+        .with_context(|| LoadDefinition {
+            path: path.to_string(),
+        })?;
+    let definition = serde_json::from_slice(&payload)
+        .box_err()
+        .with_context(|| LoadDefinition {
+            path: path.to_string(),
+        })?;
+
+    Ok(Some(definition))
+}
+
+pub async fn delete_definition(
+    store: &ObjectStoreRef,
+    schema_id: SchemaId,
+    table_name: &str,
+) -> Result<()> {
+    let path = definition_path(schema_id, table_name);
+    match store.delete(&path).await {
+        Ok(()) | Err(object_store::ObjectStoreError::NotFound { .. }) => Ok(()),
+        Err(source) => Err(source).context(DeleteDefinition {
+            path: path.to_string(),
+        }),
+    }
+}
@@ -0,0 +1,131 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use generic_error::GenericError;
+use macros::define_result;
+use snafu::{Backtrace, Snafu};
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub))]
+pub enum Error {
+    #[snafu(display(
+        "Table option is missing, option:{}.\nBacktrace:\n{}",
+        option,
+        backtrace
+    ))]
+    MissingOption {
+        option: String,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display(
+        "Invalid table location, location:{}, msg:{}.\nBacktrace:\n{}",
+        location,
+        msg,
+        backtrace
+    ))]
+    InvalidLocation {
+        location: String,
+        msg: String,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display(
+        "Data prefix of the external tables is not configured.\nBacktrace:\n{}",
+        backtrace
+    ))]
+    DataPrefixNotConfigured { backtrace: Backtrace },
+
+    #[snafu(display(
+        "Invalid data prefix of the external tables, data_prefix:{}, err:{}",
+        data_prefix,
+        source
+    ))]
+    InvalidDataPrefix {
+        data_prefix: String,
+        source: object_store::ObjectStoreError,
+    },
+
+    #[snafu(display("Failed to list parquet files, location:{}, err:{}", location, source))]
+    ListFiles {
+        location: String,
+        source: object_store::ObjectStoreError,
+    },
+
+    #[snafu(display(
+        "No parquet file is found, location:{}.\nBacktrace:\n{}",
+        location,
+        backtrace
+    ))]
+    NoParquetFile {
+        location: String,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Failed to read parquet meta data, path:{}, err:{}", path, source))]
+    ReadMetaData {
+        path: String,
+        source: parquet::errors::ParquetError,
+    },
+
+    #[snafu(display("Failed to read parquet file, path:{}, err:{}", path, source))]
+    ReadParquet {
+        path: String,
+        source: parquet::errors::ParquetError,
+    },
+
+    #[snafu(display("Failed to infer schema, msg:{}.\nBacktrace:\n{}", msg, backtrace))]
+    InferSchema { msg: String, backtrace: Backtrace },
+
+    #[snafu(display("Failed to build column schema, column:{}, err:{}", column, source))]
+    BuildColumnSchema {
+        column: String,
+        source: common_types::column_schema::Error,
+    },
+
+    #[snafu(display("Failed to build schema, err:{}", source))]
+    BuildSchema { source: common_types::schema::Error },
+
+    #[snafu(display(
+        "Declared column is incompatible with the parquet files, column:{}, msg:{}.\nBacktrace:\n{}",
+        column,
+        msg,
+        backtrace
+    ))]
+    IncompatibleColumn {
+        column: String,
+        msg: String,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Failed to store table definition, path:{}, err:{}", path, source))]
+    StoreDefinition { path: String, source: GenericError },
+
+    #[snafu(display("Failed to load table definition, path:{}, err:{}", path, source))]
+    LoadDefinition { path: String, source: GenericError },
+
+    #[snafu(display("Failed to delete table definition, path:{}, err:{}", path, source))]
+    DeleteDefinition {
+        path: String,
+        source: object_store::ObjectStoreError,
+    },
+
+    #[snafu(display("Failed to convert record batch, err:{}", source))]
+    ConvertRecordBatch { source: GenericError },
+}
+
+define_result!(Error);
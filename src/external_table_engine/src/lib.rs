@@ -0,0 +1,515 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! External table engine implementations.
+//!
+//! An external table is a read-only table backed by the parquet files under a
+//! location of the object store, the files are not managed by HoraeDB, and the
+//! schema of the table is inferred from the files.
+//!
+//! The locations of the tables are resolved under the prefix configured by
+//! [Config::data_prefix], so the tables can't read the data out of it.
+
+pub mod config;
+pub mod definition;
+pub mod error;
+mod scan;
+mod schema;
+mod table;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use generic_error::BoxError;
+use logger::info;
+use object_store::{prefix::StoreWithPrefix, ObjectStoreRef};
+use snafu::{OptionExt, ResultExt};
+use table_engine::{
+    engine::{
+        CloseShardRequest, CloseTableRequest, ClosedTable, CreateTableParams, CreateTableRequest,
+        DropTableRequest, InvalidArguments, OpenShardRequest, OpenShardResult, OpenTableRequest,
+        Result, TableEngine, Unexpected,
+    },
+    table::{SchemaId, Table, TableId, TableRef},
+    EXTERNAL_ENGINE_TYPE,
+};
+
+pub use crate::config::Config;
+use crate::{
+    definition::TableDefinition,
+    error::{DataPrefixNotConfigured, InvalidDataPrefix},
+    table::ExternalTable,
+};
+
+/// External table engine implementation.
+pub struct ExternalTableEngine {
+    /// Store of the table definitions.
+    store: ObjectStoreRef,
+    /// Store of the parquet files limited to the configured prefix, None if
+    /// the prefix is not configured.
+    data_store: Option<ObjectStoreRef>,
+}
+
+impl ExternalTableEngine {
+    pub fn try_new(store: ObjectStoreRef, config: &Config) -> error::Result<Self> {
+        let data_prefix = config.data_prefix.trim_matches('/');
+        let data_store = if data_prefix.is_empty() {
+            None
+        } else {
+            let data_store = StoreWithPrefix::new(data_prefix.to_string(), store.clone())
+                .context(InvalidDataPrefix { data_prefix })?;
+            Some(Arc::new(data_store) as _)
+        };
+
+        Ok(Self { store, data_store })
+    }
+
+    async fn build_table(
+        &self,
+        table_name: String,
+        table_id: TableId,
+        definition: TableDefinition,
+    ) -> error::Result<ExternalTable> {
+        let data_store = self.data_store.clone().context(DataPrefixNotConfigured)?;
+        // The definitions persisted before are checked too.
+        definition::normalize_location(&definition.location)?;
+        let arrow_schema = scan::read_arrow_schema(&data_store, &definition.location).await?;
+        let schema = schema::infer_schema(&arrow_schema, definition.timestamp_column.as_deref())?;
+
+        Ok(ExternalTable::new(
+            table_name, table_id, schema, definition, data_store,
+        ))
+    }
+
+    async fn open_external_table(
+        &self,
+        schema_id: SchemaId,
+        table_name: String,
+        table_id: TableId,
+    ) -> error::Result<Option<TableRef>> {
+        let Some(definition) =
+            definition::load_definition(&self.store, schema_id, &table_name).await?
+        else {
+            return Ok(None);
+        };
+
+        let table = self.build_table(table_name, table_id, definition).await?;
+        Ok(Some(Arc::new(table)))
+    }
+}
+
+#[async_trait]
+impl TableEngine for ExternalTableEngine {
+    fn engine_type(&self) -> &str {
+        EXTERNAL_ENGINE_TYPE
+    }
+
+    async fn close(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn validate_create_table(&self, params: &CreateTableParams) -> Result<()> {
+        self.data_store
+            .as_ref()
+            .context(DataPrefixNotConfigured)
+            .box_err()
+            .context(InvalidArguments {
+                table: &params.table_name,
+            })?;
+        TableDefinition::from_options(&params.table_options)
+            .box_err()
+            .context(InvalidArguments {
+                table: &params.table_name,
+            })?;
+
+        Ok(())
+    }
+
+    async fn create_table(&self, request: CreateTableRequest) -> Result<TableRef> {
+        let params = request.params;
+        info!(
+            "External table engine create table, table:{}, options:{:?}",
+            params.table_name, params.table_options
+        );
+
+        let mut definition = TableDefinition::from_options(&params.table_options)
+            .box_err()
+            .context(InvalidArguments {
+                table: &params.table_name,
+            })?;
+        // Keep the timestamp column the same as the declared one.
+        definition
+            .timestamp_column
+            .get_or_insert_with(|| params.table_schema.timestamp_name().to_string());
+
+        let table = self
+            .build_table(
+                params.table_name.clone(),
+                request.table_id,
+                definition.clone(),
+            )
+            .await
+            .box_err()
+            .context(InvalidArguments {
+                table: &params.table_name,
+            })?;
+        schema::check_declared_schema(&params.table_schema, &table.schema())
+            .box_err()
+            .context(InvalidArguments {
+                table: &params.table_name,
+            })?;
+
+        definition::store_definition(
+            &self.store,
+            request.schema_id,
+            &params.table_name,
+            &definition,
+        )
+        .await
+        .box_err()
+        .context(Unexpected)?;
+
+        Ok(Arc::new(table))
+    }
+
+    async fn drop_table(&self, request: DropTableRequest) -> Result<bool> {
+        definition::delete_definition(&self.store, request.schema_id, &request.table_name)
+            .await
+            .box_err()
+            .context(Unexpected)?;
+
+        Ok(true)
+    }
+
+    async fn open_table(&self, request: OpenTableRequest) -> Result<Option<TableRef>> {
+        self.open_external_table(request.schema_id, request.table_name, request.table_id)
+            .await
+            .box_err()
+            .context(Unexpected)
+    }
+
+    async fn close_table(&self, _request: CloseTableRequest) -> Result<()> {
+        Ok(())
+    }
+
+    async fn open_shard(&self, request: OpenShardRequest) -> Result<OpenShardResult> {
+        let mut shard_result = OpenShardResult::with_capacity(request.table_defs.len());
+        for table_def in request.table_defs {
+            let table_id = table_def.id;
+            let open_res = self
+                .open_external_table(table_def.schema_id, table_def.name, table_id)
+                .await
+                .box_err();
+            shard_result.insert(table_id, open_res);
+        }
+
+        Ok(shard_result)
+    }
+
+    async fn close_shard(&self, request: CloseShardRequest) -> Vec<Result<ClosedTable>> {
+        request
+            .table_defs
+            .into_iter()
+            .map(|table_def| Ok(ClosedTable::new(table_def.name)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use arrow::{
+        array::{ArrayRef, Float64Array, StringArray, TimestampMillisecondArray},
+        datatypes::{DataType, Field, Schema as ArrowSchema, TimeUnit},
+        record_batch::RecordBatch as ArrowRecordBatch,
+    };
+    use common_types::{
+        datum::DatumKind, projected_schema::ProjectedSchema, record_batch::RecordBatch,
+        request_id::RequestId,
+    };
+    use datafusion::{
+        logical_expr::{col, lit},
+        scalar::ScalarValue,
+    };
+    use futures::TryStreamExt;
+    use object_store::{local_file, Path};
+    use parquet::arrow::ArrowWriter;
+    use table_engine::{
+        predicate::{Predicate, PredicateBuilder, PredicateRef},
+        table::ReadRequest,
+    };
+    use trace_metric::MetricsCollector;
+
+    use super::*;
+    use crate::error::Error;
+
+    const DATA_PREFIX: &str = "external";
+    const LOCATION: &str = "data";
+
+    fn new_engine(store: &ObjectStoreRef) -> ExternalTableEngine {
+        let config = Config {
+            data_prefix: DATA_PREFIX.to_string(),
+        };
+        ExternalTableEngine::try_new(store.clone(), &config).unwrap()
+    }
+
+    fn new_store(dir: &tempfile::TempDir) -> ObjectStoreRef {
+        let data_dir = dir.path().to_str().unwrap().to_string();
+        Arc::new(local_file::try_new_with_default(data_dir).unwrap())
+    }
+
+    /// Write a parquet file of the rows, the value column is written only if
+    /// `values` is set.
+    async fn write_parquet(
+        store: &ObjectStoreRef,
+        name: &str,
+        timestamps: Vec<i64>,
+        values: Option<Vec<f64>>,
+    ) {
+        let num_rows = timestamps.len();
+        let mut fields = vec![
+            Field::new(
+                "ts",
+                DataType::Timestamp(TimeUnit::Millisecond, None),
+                false,
+            ),
+            Field::new("host", DataType::Utf8, true),
+        ];
+        let mut columns: Vec<ArrayRef> = vec![
+            Arc::new(TimestampMillisecondArray::from(timestamps)),
+            Arc::new(StringArray::from(vec!["host1"; num_rows])),
+        ];
+        if let Some(values) = values {
+            fields.push(Field::new("value", DataType::Float64, true));
+            columns.push(Arc::new(Float64Array::from(values)));
+        }
+        let batch = ArrowRecordBatch::try_new(Arc::new(ArrowSchema::new(fields)), columns).unwrap();
+
+        let mut buf = Vec::new();
+        let mut writer = ArrowWriter::try_new(&mut buf, batch.schema(), None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+
+        let path = Path::from(format!("{DATA_PREFIX}/{LOCATION}/{name}"));
+        store.put(&path, buf.into()).await.unwrap();
+    }
+
+    async fn new_table(store: &ObjectStoreRef) -> ExternalTable {
+        write_parquet(
+            store,
+            "1.parquet",
+            vec![1000, 1001, 1002],
+            Some(vec![1.0, 2.0, 3.0]),
+        )
+        .await;
+        write_parquet(store, "2.parquet", vec![5000, 5001, 5002], None).await;
+
+        let engine = new_engine(store);
+        let definition = TableDefinition {
+            location: LOCATION.to_string(),
+            timestamp_column: None,
+        };
+        engine
+            .build_table("test_table".to_string(), TableId::from(1), definition)
+            .await
+            .unwrap()
+    }
+
+    fn new_read_request(
+        table: &ExternalTable,
+        projection: Option<Vec<usize>>,
+        predicate: PredicateRef,
+    ) -> ReadRequest {
+        ReadRequest {
+            request_id: RequestId::next_id(),
+            opts: Default::default(),
+            projected_schema: ProjectedSchema::new(table.schema(), projection).unwrap(),
+            predicate,
+            metrics_collector: MetricsCollector::default(),
+            priority: Default::default(),
+        }
+    }
+
+    async fn read_all(table: &ExternalTable, request: ReadRequest) -> Vec<RecordBatch> {
+        table
+            .read(request)
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_infer_schema_from_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = new_store(&dir);
+        let table = new_table(&store).await;
+
+        let schema = table.schema();
+        assert_eq!("ts", schema.timestamp_name());
+        let columns = schema
+            .columns()
+            .iter()
+            .map(|column| (column.name.as_str(), column.data_type))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                ("ts", DatumKind::Timestamp),
+                ("host", DatumKind::String),
+                ("value", DatumKind::Double),
+            ],
+            columns
+        );
+        assert_eq!(
+            HashMap::from([("location".to_string(), LOCATION.to_string())]),
+            table.options()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_with_projection() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = new_store(&dir);
+        let table = new_table(&store).await;
+
+        let request = new_read_request(&table, Some(vec![0, 2]), Arc::new(Predicate::empty()));
+        let batches = read_all(&table, request).await;
+        let mut values = Vec::new();
+        for batch in &batches {
+            let names = batch
+                .schema()
+                .columns()
+                .iter()
+                .map(|column| column.name.as_str())
+                .collect::<Vec<_>>();
+            assert_eq!(vec!["ts", "value"], names);
+            for i in 0..batch.num_rows() {
+                values.push(batch.column(1).datum(i));
+            }
+        }
+
+        // The value column missing in the second file is filled with nulls.
+        assert_eq!(6, values.len());
+        assert!(values[..3].iter().all(|v| !v.is_null()));
+        assert!(values[3..].iter().all(|v| v.is_null()));
+    }
+
+    #[tokio::test]
+    async fn test_read_with_filter() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = new_store(&dir);
+        let table = new_table(&store).await;
+
+        // The row groups of the first file are pruned by the filter.
+        let filter = col("ts").gt_eq(lit(ScalarValue::TimestampMillisecond(Some(5000), None)));
+        let predicate = PredicateBuilder::default()
+            .add_pushdown_exprs(&[filter])
+            .build();
+        let request = new_read_request(&table, None, predicate);
+        let batches = read_all(&table, request).await;
+        let num_rows: usize = batches.iter().map(|batch| batch.num_rows()).sum();
+        assert_eq!(3, num_rows);
+
+        // Nothing is read if all the row groups are pruned.
+        let filter = col("ts").gt(lit(ScalarValue::TimestampMillisecond(Some(10000), None)));
+        let predicate = PredicateBuilder::default()
+            .add_pushdown_exprs(&[filter])
+            .build();
+        let request = new_read_request(&table, None, predicate);
+        assert!(read_all(&table, request).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_missing_location() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = new_store(&dir);
+        let engine = new_engine(&store);
+
+        let err = TableDefinition::from_options(&HashMap::new()).unwrap_err();
+        assert!(matches!(err, Error::MissingOption { .. }), "err:{err}");
+
+        // No parquet file is found under the location.
+        let definition = TableDefinition {
+            location: "not_exist".to_string(),
+            timestamp_column: None,
+        };
+        let err = engine
+            .build_table("test_table".to_string(), TableId::from(1), definition)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::NoParquetFile { .. }), "err:{err}");
+    }
+
+    #[test]
+    fn test_invalid_location() {
+        let options =
+            |location: &str| HashMap::from([("location".to_string(), location.to_string())]);
+
+        let definition = TableDefinition::from_options(&options("/data/2024/")).unwrap();
+        assert_eq!("data/2024", definition.location);
+
+        for location in [
+            "",
+            "/",
+            "..",
+            "data/../manifest",
+            "./data",
+            "data//2024",
+            "data\\..",
+        ] {
+            let err = TableDefinition::from_options(&options(location)).unwrap_err();
+            assert!(matches!(err, Error::InvalidLocation { .. }), "err:{err}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_within_data_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = new_store(&dir);
+        // The files out of the data prefix are invisible to the external tables.
+        let path = Path::from(format!("{LOCATION}/1.parquet"));
+        store.put(&path, Vec::new().into()).await.unwrap();
+
+        let engine = new_engine(&store);
+        let definition = TableDefinition {
+            location: LOCATION.to_string(),
+            timestamp_column: None,
+        };
+        let err = engine
+            .build_table(
+                "test_table".to_string(),
+                TableId::from(1),
+                definition.clone(),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::NoParquetFile { .. }), "err:{err}");
+
+        // No external table can be built without the data prefix.
+        let engine = ExternalTableEngine::try_new(store, &Config::default()).unwrap();
+        let err = engine
+            .build_table("test_table".to_string(), TableId::from(1), definition)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, Error::DataPrefixNotConfigured { .. }),
+            "err:{err}"
+        );
+    }
+}
@@ -0,0 +1,256 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Scan of the parquet files of the external tables.
+
+use std::{
+    ops::Range,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use arrow::{
+    array::new_null_array,
+    compute,
+    datatypes::SchemaRef as ArrowSchemaRef,
+    record_batch::{RecordBatch as ArrowRecordBatch, RecordBatchOptions},
+};
+use async_trait::async_trait;
+use bytes_ext::Bytes;
+use common_types::{
+    projected_schema::ProjectedSchema, record_batch::RecordBatch, schema::RecordSchema,
+};
+use futures::{
+    future,
+    stream::{self, BoxStream},
+    Stream, StreamExt, TryStreamExt,
+};
+use generic_error::{BoxError, GenericResult};
+use object_store::{ObjectMeta, ObjectStoreRef, Path};
+use parquet::arrow::{
+    arrow_reader::{ArrowReaderMetadata, ArrowReaderOptions},
+    ParquetRecordBatchStreamBuilder, ProjectionMask,
+};
+use parquet_ext::{
+    meta_data::{fetch_parquet_metadata, ChunkReader},
+    prune::min_max,
+    reader::ObjectStoreReader,
+};
+use snafu::{ensure, ResultExt};
+use table_engine::{
+    predicate::PredicateRef,
+    stream::{self as table_stream, ErrWithSource, RecordBatchStream},
+};
+
+use crate::error::{
+    ConvertRecordBatch, ListFiles, NoParquetFile, ReadMetaData, ReadParquet, Result,
+};
+
+const PARQUET_EXTENSION: &str = "parquet";
+
+/// List the parquet files under the `location`, sorted by path.
+pub async fn list_parquet_files(store: &ObjectStoreRef, location: &str) -> Result<Vec<ObjectMeta>> {
+    let prefix = Path::from(location);
+    let mut files: Vec<_> = store
+        .list(Some(&prefix))
+        .try_filter(|meta| future::ready(meta.location.extension() == Some(PARQUET_EXTENSION)))
+        .try_collect()
+        .await
+        .context(ListFiles { location })?;
+    files.sort_unstable_by(|a, b| a.location.cmp(&b.location));
+
+    Ok(files)
+}
+
+/// Read the arrow schema from the first parquet file under the `location`.
+pub async fn read_arrow_schema(store: &ObjectStoreRef, location: &str) -> Result<ArrowSchemaRef> {
+    let files = list_parquet_files(store, location).await?;
+    ensure!(!files.is_empty(), NoParquetFile { location });
+
+    let reader_meta = read_meta_data(store, &files[0]).await?;
+    Ok(reader_meta.schema().clone())
+}
+
+struct ChunkReaderAdapter<'a> {
+    path: &'a Path,
+    store: &'a ObjectStoreRef,
+}
+
+#[async_trait]
+impl<'a> ChunkReader for ChunkReaderAdapter<'a> {
+    async fn get_bytes(&self, range: Range<usize>) -> GenericResult<Bytes> {
+        self.store.get_range(self.path, range).await.box_err()
+    }
+}
+
+async fn read_meta_data(store: &ObjectStoreRef, file: &ObjectMeta) -> Result<ArrowReaderMetadata> {
+    let chunk_reader = ChunkReaderAdapter {
+        path: &file.location,
+        store,
+    };
+    let (meta_data, _) = fetch_parquet_metadata(file.size, &chunk_reader)
+        .await
+        .with_context(|| ReadMetaData {
+            path: file.location.to_string(),
+        })?;
+
+    ArrowReaderMetadata::try_new(Arc::new(meta_data), ArrowReaderOptions::new()).with_context(
+        || ReadMetaData {
+            path: file.location.to_string(),
+        },
+    )
+}
+
+/// Context shared by the scans of the files of one read request.
+pub struct ScanContext {
+    pub store: ObjectStoreRef,
+    pub projected_schema: ProjectedSchema,
+    pub predicate: PredicateRef,
+    pub batch_size: usize,
+}
+
+impl ScanContext {
+    /// Build a stream to scan the `files` one by one.
+    pub fn scan_files(self: &Arc<Self>, files: Vec<ObjectMeta>) -> ParquetScan {
+        let ctx = self.clone();
+        let stream = stream::iter(files)
+            .then(move |file| {
+                let ctx = ctx.clone();
+                async move { ctx.scan_file(file).await }
+            })
+            .try_flatten()
+            .boxed();
+
+        ParquetScan {
+            schema: self.projected_schema.to_record_schema(),
+            stream,
+        }
+    }
+
+    async fn scan_file(
+        &self,
+        file: ObjectMeta,
+    ) -> table_stream::Result<BoxStream<'static, table_stream::Result<RecordBatch>>> {
+        let path = file.location.clone();
+        let reader_meta =
+            read_meta_data(&self.store, &file)
+                .await
+                .box_err()
+                .context(ErrWithSource {
+                    msg: "read parquet meta data",
+                })?;
+        let file_schema = reader_meta.schema().clone();
+        let meta_data = reader_meta.metadata().clone();
+
+        // Prune the row groups by the min-max statistics.
+        let row_groups = min_max::prune_row_groups(
+            file_schema.clone(),
+            self.predicate.exprs(),
+            meta_data.row_groups(),
+        );
+        if row_groups.is_empty() {
+            return Ok(stream::empty().boxed());
+        }
+
+        let target_schema = self.projected_schema.to_projected_arrow_schema();
+        let projection = target_schema
+            .fields()
+            .iter()
+            .filter_map(|field| file_schema.index_of(field.name()).ok())
+            .collect::<Vec<_>>();
+        let projection_mask =
+            ProjectionMask::roots(meta_data.file_metadata().schema_descr(), projection);
+
+        let reader = ObjectStoreReader::new(self.store.clone(), path.clone(), meta_data);
+        let stream = ParquetRecordBatchStreamBuilder::new_with_metadata(reader, reader_meta)
+            .with_row_groups(row_groups)
+            .with_projection(projection_mask)
+            .with_batch_size(self.batch_size)
+            .build()
+            .with_context(|| ReadParquet {
+                path: path.to_string(),
+            })
+            .box_err()
+            .context(ErrWithSource {
+                msg: "build parquet stream",
+            })?;
+
+        let stream = stream.map(move |batch| {
+            batch
+                .with_context(|| ReadParquet {
+                    path: path.to_string(),
+                })
+                .and_then(|batch| conform_record_batch(batch, &target_schema))
+                .box_err()
+                .context(ErrWithSource {
+                    msg: "read parquet file",
+                })
+        });
+
+        Ok(stream.boxed())
+    }
+}
+
+/// Conform the record batch read from the file to the `target_schema`, the
+/// types of the columns are casted if necessary, and the columns missing in the
+/// file are filled with nulls.
+fn conform_record_batch(
+    batch: ArrowRecordBatch,
+    target_schema: &ArrowSchemaRef,
+) -> Result<RecordBatch> {
+    let num_rows = batch.num_rows();
+    let columns = target_schema
+        .fields()
+        .iter()
+        .map(|field| match batch.column_by_name(field.name()) {
+            Some(column) if column.data_type() == field.data_type() => Ok(column.clone()),
+            Some(column) => compute::cast(column, field.data_type()),
+            None => Ok(new_null_array(field.data_type(), num_rows)),
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .box_err()
+        .context(ConvertRecordBatch)?;
+
+    let options = RecordBatchOptions::new().with_row_count(Some(num_rows));
+    let batch = ArrowRecordBatch::try_new_with_options(target_schema.clone(), columns, &options)
+        .box_err()
+        .context(ConvertRecordBatch)?;
+    RecordBatch::try_from(batch)
+        .box_err()
+        .context(ConvertRecordBatch)
+}
+
+/// Stream of the record batches read from parquet files.
+pub struct ParquetScan {
+    schema: RecordSchema,
+    stream: BoxStream<'static, table_stream::Result<RecordBatch>>,
+}
+
+impl Stream for ParquetScan {
+    type Item = table_stream::Result<RecordBatch>;
+
+    fn poll_next(mut self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.stream.poll_next_unpin(ctx)
+    }
+}
+
+impl RecordBatchStream for ParquetScan {
+    fn schema(&self) -> &RecordSchema {
+        &self.schema
+    }
+}
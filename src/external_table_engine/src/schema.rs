@@ -0,0 +1,208 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Schema inference of the external tables.
+
+use arrow::datatypes::Schema as ArrowSchema;
+use common_types::{
+    column_schema::Builder as ColumnSchemaBuilder,
+    datum::DatumKind,
+    schema::{Builder as SchemaBuilder, Schema, TSID_COLUMN},
+};
+use logger::warn;
+use snafu::{ensure, OptionExt, ResultExt};
+
+use crate::error::{BuildColumnSchema, BuildSchema, IncompatibleColumn, InferSchema, Result};
+
+/// Infer the table schema from the arrow schema of the parquet files.
+///
+/// The timestamp column is the only key column, and the columns of the
+/// unsupported types are ignored.
+pub fn infer_schema(arrow_schema: &ArrowSchema, timestamp_column: Option<&str>) -> Result<Schema> {
+    let timestamp_column = match timestamp_column {
+        Some(column) => column,
+        None => arrow_schema
+            .fields()
+            .iter()
+            .find(|field| {
+                DatumKind::from_data_type(field.data_type()) == Some(DatumKind::Timestamp)
+            })
+            .map(|field| field.name().as_str())
+            .context(InferSchema {
+                msg: "no timestamp column is found",
+            })?,
+    };
+    let timestamp_field = arrow_schema
+        .field_with_name(timestamp_column)
+        .ok()
+        .with_context(|| InferSchema {
+            msg: format!("timestamp column is not found, column:{timestamp_column}"),
+        })?;
+    ensure!(
+        DatumKind::from_data_type(timestamp_field.data_type()) == Some(DatumKind::Timestamp),
+        InferSchema {
+            msg: format!(
+                "invalid type of timestamp column, column:{timestamp_column}, type:{}",
+                timestamp_field.data_type()
+            ),
+        }
+    );
+
+    let timestamp_column_schema =
+        ColumnSchemaBuilder::new(timestamp_column.to_string(), DatumKind::Timestamp)
+            .is_nullable(false)
+            .build()
+            .context(BuildColumnSchema {
+                column: timestamp_column,
+            })?;
+    let mut builder = SchemaBuilder::with_capacity(arrow_schema.fields().len())
+        .auto_increment_column_id(true)
+        .primary_key_indexes(vec![0])
+        .add_key_column(timestamp_column_schema)
+        .context(BuildSchema)?;
+
+    for field in arrow_schema.fields() {
+        if field.name() == timestamp_column {
+            continue;
+        }
+
+        let kind = match DatumKind::from_data_type(field.data_type()) {
+            Some(kind) if kind != DatumKind::Null => kind,
+            _ => {
+                warn!(
+                    "Ignore column of unsupported type in external table, column:{}, type:{}",
+                    field.name(),
+                    field.data_type()
+                );
+                continue;
+            }
+        };
+        let column_schema = ColumnSchemaBuilder::new(field.name().clone(), kind)
+            .is_nullable(field.is_nullable())
+            .build()
+            .context(BuildColumnSchema {
+                column: field.name(),
+            })?;
+        builder = builder
+            .add_normal_column(column_schema)
+            .context(BuildSchema)?;
+    }
+
+    builder.build().context(BuildSchema)
+}
+
+/// Check the columns declared in the create table request are provided by the
+/// parquet files.
+pub fn check_declared_schema(declared: &Schema, inferred: &Schema) -> Result<()> {
+    for column in declared.columns() {
+        // The tsid column is generated by the planner.
+        if column.name == TSID_COLUMN {
+            continue;
+        }
+
+        let inferred_column = inferred
+            .index_of(&column.name)
+            .map(|idx| inferred.column(idx))
+            .with_context(|| IncompatibleColumn {
+                column: &column.name,
+                msg: "column is not found in the parquet files",
+            })?;
+        ensure!(
+            inferred_column.data_type == column.data_type,
+            IncompatibleColumn {
+                column: &column.name,
+                msg: format!(
+                    "declared type:{}, inferred type:{}",
+                    column.data_type, inferred_column.data_type
+                ),
+            }
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::datatypes::{DataType, Field, TimeUnit};
+
+    use super::*;
+
+    fn build_arrow_schema() -> ArrowSchema {
+        ArrowSchema::new(vec![
+            Field::new("host", DataType::Utf8, true),
+            Field::new("ts", DataType::Timestamp(TimeUnit::Nanosecond, None), false),
+            Field::new("value", DataType::Float64, true),
+            Field::new(
+                "tags",
+                DataType::List(Field::new("item", DataType::Utf8, true).into()),
+                true,
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_infer_schema() {
+        let schema = infer_schema(&build_arrow_schema(), None).unwrap();
+
+        assert_eq!(schema.timestamp_name(), "ts");
+        assert_eq!(schema.primary_key_indexes(), &[0]);
+        let columns = schema
+            .columns()
+            .iter()
+            .map(|column| (column.name.as_str(), column.data_type))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            columns,
+            vec![
+                ("ts", DatumKind::Timestamp),
+                ("host", DatumKind::String),
+                ("value", DatumKind::Double),
+            ]
+        );
+
+        assert!(infer_schema(&build_arrow_schema(), Some("value")).is_err());
+        assert!(infer_schema(&build_arrow_schema(), Some("not_exist")).is_err());
+    }
+
+    #[test]
+    fn test_check_declared_schema() {
+        let inferred = infer_schema(&build_arrow_schema(), None).unwrap();
+        let declared = |kind| {
+            SchemaBuilder::new()
+                .auto_increment_column_id(true)
+                .primary_key_indexes(vec![0])
+                .add_key_column(
+                    ColumnSchemaBuilder::new("ts".to_string(), DatumKind::Timestamp)
+                        .build()
+                        .unwrap(),
+                )
+                .unwrap()
+                .add_normal_column(
+                    ColumnSchemaBuilder::new("value".to_string(), kind)
+                        .build()
+                        .unwrap(),
+                )
+                .unwrap()
+                .build()
+                .unwrap()
+        };
+
+        check_declared_schema(&declared(DatumKind::Double), &inferred).unwrap();
+        assert!(check_declared_schema(&declared(DatumKind::Int64), &inferred).is_err());
+    }
+}
@@ -0,0 +1,177 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! External table backed by parquet files.
+
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use async_trait::async_trait;
+use common_types::{row::Row, schema::Schema};
+use generic_error::BoxError;
+use object_store::ObjectStoreRef;
+use snafu::ResultExt;
+use table_engine::{
+    stream::{PartitionedStreams, SendableRecordBatchStream},
+    table::{
+        AlterSchemaRequest, FlushRequest, GetRequest, ReadRequest, Result, Scan, Table, TableId,
+        TableStats, UnsupportedMethod, WriteRequest,
+    },
+    EXTERNAL_ENGINE_TYPE,
+};
+
+use crate::{
+    definition::TableDefinition,
+    scan::{self, ScanContext},
+};
+
+/// Read-only table whose data is the parquet files under a location of the
+/// object store.
+pub struct ExternalTable {
+    name: String,
+    id: TableId,
+    schema: Schema,
+    definition: TableDefinition,
+    store: ObjectStoreRef,
+}
+
+impl ExternalTable {
+    pub fn new(
+        name: String,
+        id: TableId,
+        schema: Schema,
+        definition: TableDefinition,
+        store: ObjectStoreRef,
+    ) -> Self {
+        Self {
+            name,
+            id,
+            schema,
+            definition,
+            store,
+        }
+    }
+
+    fn unsupported<T>(&self, method: &str) -> Result<T> {
+        UnsupportedMethod {
+            table: &self.name,
+            method,
+        }
+        .fail()
+    }
+}
+
+impl fmt::Debug for ExternalTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExternalTable")
+            .field("name", &self.name)
+            .field("id", &self.id)
+            .field("schema", &self.schema)
+            .field("definition", &self.definition)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl Table for ExternalTable {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn id(&self) -> TableId {
+        self.id
+    }
+
+    fn schema(&self) -> Schema {
+        self.schema.clone()
+    }
+
+    fn options(&self) -> HashMap<String, String> {
+        self.definition.to_options()
+    }
+
+    fn engine_type(&self) -> &str {
+        EXTERNAL_ENGINE_TYPE
+    }
+
+    fn stats(&self) -> TableStats {
+        TableStats::default()
+    }
+
+    // The row groups are pruned by the predicate, but the rows are not filtered.
+    fn support_pushdown(&self, _read_schema: &Schema, _col_names: &[String]) -> bool {
+        false
+    }
+
+    async fn write(&self, _request: WriteRequest) -> Result<usize> {
+        self.unsupported("write")
+    }
+
+    async fn read(&self, mut request: ReadRequest) -> Result<SendableRecordBatchStream> {
+        request.opts.read_parallelism = 1;
+        let mut streams = self.partitioned_read(request).await?;
+        assert_eq!(streams.streams.len(), 1);
+
+        Ok(streams.streams.pop().unwrap())
+    }
+
+    async fn get(&self, _request: GetRequest) -> Result<Option<Row>> {
+        self.unsupported("get")
+    }
+
+    /// The parquet files are distributed to the streams in a round-robin way.
+    async fn partitioned_read(&self, request: ReadRequest) -> Result<PartitionedStreams> {
+        let files = scan::list_parquet_files(&self.store, &self.definition.location)
+            .await
+            .box_err()
+            .context(Scan { table: &self.name })?;
+
+        let read_parallelism = request.opts.read_parallelism.max(1);
+        let mut files_of_streams = vec![Vec::new(); read_parallelism];
+        for (idx, file) in files.into_iter().enumerate() {
+            files_of_streams[idx % read_parallelism].push(file);
+        }
+
+        let ctx = Arc::new(ScanContext {
+            store: self.store.clone(),
+            projected_schema: request.projected_schema,
+            predicate: request.predicate,
+            batch_size: request.opts.batch_size,
+        });
+        let streams = files_of_streams
+            .into_iter()
+            .map(|files| Box::pin(ctx.scan_files(files)) as _)
+            .collect();
+
+        Ok(PartitionedStreams { streams })
+    }
+
+    async fn alter_schema(&self, _request: AlterSchemaRequest) -> Result<usize> {
+        self.unsupported("alter_schema")
+    }
+
+    async fn alter_options(&self, _options: HashMap<String, String>) -> Result<usize> {
+        self.unsupported("alter_options")
+    }
+
+    async fn flush(&self, _request: FlushRequest) -> Result<()> {
+        self.unsupported("flush")
+    }
+
+    async fn compact(&self) -> Result<()> {
+        self.unsupported("compact")
+    }
+}
@@ -66,6 +66,9 @@ pub struct Config {
     /// Analytic engine config.
     pub analytic: analytic_engine::Config,
 
+    /// External table engine config.
+    pub external_table: external_table_engine::Config,
+
     /// Query engine config.
     pub query_engine: query_engine::config::Config,
 
@@ -0,0 +1,310 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Embedded mode, which opens the analytic engine in process and serves the
+//! requests without any network services.
+
+use std::{sync::Arc, time::Duration};
+
+use horaedbproto::storage::{WriteRequest, WriteResponse};
+use http::StatusCode;
+use interpreters::interpreter::Output;
+use logger::{info, warn, RuntimeLevel};
+use macros::define_result;
+use proxy::{context::RequestContext, http::sql::Request, Context, Proxy};
+use server::server::Server;
+use snafu::{ensure, Backtrace, ResultExt, Snafu};
+use table_engine::engine::EngineRuntimes;
+
+use crate::{
+    config::{ClusterDeployment, Config},
+    setup,
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display(
+        "Embedded mode doesn't support deployment with meta.\nBacktrace:\n{}",
+        backtrace
+    ))]
+    UnsupportedDeployment { backtrace: Backtrace },
+
+    #[snafu(display("Invalid config, msg:{}.\nBacktrace:\n{}", msg, backtrace))]
+    InvalidConfig { msg: String, backtrace: Backtrace },
+
+    #[snafu(display("Failed to start embedded server, err:{}", source))]
+    Start { source: server::server::Error },
+
+    #[snafu(display("Failed to build request context, err:{}", source))]
+    BuildContext { source: proxy::context::Error },
+
+    #[snafu(display("Failed to execute sql, sql:{}, err:{}", sql, source))]
+    ExecuteSql {
+        sql: String,
+        source: proxy::error::Error,
+    },
+
+    #[snafu(display(
+        "Failed to write, code:{}, msg:{}.\nBacktrace:\n{}",
+        code,
+        msg,
+        backtrace
+    ))]
+    Write {
+        code: u32,
+        msg: String,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Failed to join embedded task, err:{}", source))]
+    JoinTask { source: runtime::Error },
+}
+
+define_result!(Error);
+
+/// HoraeDB running in embedded mode.
+///
+/// The instance owns its runtimes, so it should be opened and closed outside
+/// of any async context. It's stopped on drop if not closed explicitly, but
+/// [Horaedb::close] is preferred to wait for the shutdown.
+pub struct Horaedb {
+    runtimes: Arc<EngineRuntimes>,
+    server: Option<Server>,
+    proxy: Arc<Proxy>,
+    timeout: Option<Duration>,
+}
+
+impl Horaedb {
+    /// Open the embedded instance with the `config`.
+    ///
+    /// Only standalone deployment (without meta) is supported.
+    pub fn open(config: Config, log_runtime: RuntimeLevel) -> Result<Self> {
+        ensure!(
+            !matches!(
+                config.cluster_deployment,
+                Some(ClusterDeployment::WithMeta(_))
+            ),
+            UnsupportedDeployment
+        );
+
+        let runtimes = Arc::new(setup::build_engine_runtimes(&config.runtime));
+        let log_runtime = Arc::new(log_runtime);
+        let timeout = config.server.timeout.map(|v| v.0);
+
+        info!("Embedded horaedb starts up, config:{:#?}", config);
+
+        if let Err(msg) = setup::validate_config(&config) {
+            return InvalidConfig { msg }.fail();
+        }
+
+        let server = runtimes.default_runtime.block_on(async {
+            let mut server = setup::build_server(config, runtimes.clone(), log_runtime).await;
+            server.start_embedded().await.context(Start)?;
+
+            Ok::<_, Error>(server)
+        })?;
+        let proxy = server.proxy();
+
+        Ok(Self {
+            runtimes,
+            server: Some(server),
+            proxy,
+            timeout,
+        })
+    }
+
+    /// Name of the default schema of the instance.
+    pub fn default_schema_name(&self) -> String {
+        self.proxy
+            .instance()
+            .catalog_manager
+            .default_schema_name()
+            .to_string()
+    }
+
+    /// Execute one sql statement in the given `schema`.
+    pub async fn execute_sql(&self, schema: &str, sql: &str) -> Result<Output> {
+        let ctx = RequestContext::builder()
+            .catalog(
+                self.proxy
+                    .instance()
+                    .catalog_manager
+                    .default_catalog_name()
+                    .to_string(),
+            )
+            .schema(schema.to_string())
+            .timeout(self.timeout)
+            .build()
+            .context(BuildContext)?;
+        let req = Request {
+            query: sql.to_string(),
+        };
+
+        let proxy = self.proxy.clone();
+        let handle = self
+            .runtimes
+            .default_runtime
+            .spawn(async move { proxy.handle_http_sql_query(&ctx, req).await });
+        handle.await.context(JoinTask)?.context(ExecuteSql { sql })
+    }
+
+    /// Write rows in the format of the grpc write request.
+    pub async fn write(&self, req: WriteRequest) -> Result<WriteResponse> {
+        let ctx = Context::new(self.timeout, None, None);
+
+        let proxy = self.proxy.clone();
+        let handle = self
+            .runtimes
+            .default_runtime
+            .spawn(async move { proxy.handle_write(ctx, req).await });
+        let resp = handle.await.context(JoinTask)?;
+
+        if let Some(header) = &resp.header {
+            ensure!(
+                header.code == StatusCode::OK.as_u16() as u32,
+                Write {
+                    code: header.code,
+                    msg: header.error.clone(),
+                }
+            );
+        }
+
+        Ok(resp)
+    }
+
+    /// Close the instance gracefully.
+    pub fn close(mut self) {
+        if let Some(server) = self.server.take() {
+            self.runtimes
+                .default_runtime
+                .block_on(server.stop_embedded());
+        }
+
+        info!("Embedded horaedb is closed");
+    }
+}
+
+impl Drop for Horaedb {
+    fn drop(&mut self) {
+        let Some(server) = self.server.take() else {
+            return;
+        };
+
+        warn!("Embedded horaedb is dropped without close, try to stop it");
+        // Block on the shutdown in another thread, because it may be dropped
+        // in an async context where blocking is not allowed.
+        let runtime = self.runtimes.default_runtime.clone();
+        let res =
+            std::thread::scope(|s| s.spawn(|| runtime.block_on(server.stop_embedded())).join());
+        if res.is_err() {
+            warn!("Failed to stop the dropped embedded horaedb");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use horaedbproto::storage::{
+        value, Field, FieldGroup, RequestContext as GrpcRequestContext, Tag, Value,
+        WriteSeriesEntry, WriteTableRequest,
+    };
+    use object_store::config::{LocalOptions, ObjectStoreOptions};
+    use wal::config::StorageConfig;
+
+    use super::*;
+
+    const TABLE: &str = "embedded_test";
+
+    fn new_config(data_dir: &str) -> Config {
+        let mut config = Config::default();
+        config.analytic.storage.disk_cache_dir = data_dir.to_string();
+        config.analytic.storage.object_store =
+            ObjectStoreOptions::Local(LocalOptions::new_with_default(data_dir.to_string()));
+        config.analytic.wal.storage = StorageConfig::Memory(Default::default());
+        config
+    }
+
+    fn new_write_request(schema: &str, num_rows: usize) -> WriteRequest {
+        let field_groups = (0..num_rows)
+            .map(|i| FieldGroup {
+                timestamp: 1000 + i as i64,
+                fields: vec![Field {
+                    name_index: 0,
+                    value: Some(Value {
+                        value: Some(value::Value::Float64Value(i as f64)),
+                    }),
+                }],
+            })
+            .collect();
+        let entry = WriteSeriesEntry {
+            tags: vec![Tag {
+                name_index: 0,
+                value: Some(Value {
+                    value: Some(value::Value::StringValue("host1".to_string())),
+                }),
+            }],
+            field_groups,
+        };
+
+        WriteRequest {
+            context: Some(GrpcRequestContext {
+                database: schema.to_string(),
+            }),
+            table_requests: vec![WriteTableRequest {
+                table: TABLE.to_string(),
+                tag_names: vec!["host".to_string()],
+                field_names: vec!["value".to_string()],
+                entries: vec![entry],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_embedded_lifecycle() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = new_config(dir.path().to_str().unwrap());
+        let log_runtime = logger::init_log(&config.logger).unwrap();
+
+        let horaedb = Horaedb::open(config, log_runtime).unwrap();
+        let schema = horaedb.default_schema_name();
+        let runtime = horaedb.runtimes.default_runtime.clone();
+
+        runtime.block_on(async {
+            // The table is created automatically on the first write.
+            let resp = horaedb.write(new_write_request(&schema, 10)).await.unwrap();
+            assert_eq!(10, resp.success);
+
+            let output = horaedb
+                .execute_sql(&schema, &format!("SELECT * FROM {TABLE}"))
+                .await
+                .unwrap();
+            let Output::Records(batches) = output else {
+                panic!("expect records of the query");
+            };
+            let num_rows: usize = batches.iter().map(|batch| batch.num_rows()).sum();
+            assert_eq!(10, num_rows);
+
+            // Writing to an unknown schema is rejected.
+            assert!(horaedb
+                .write(new_write_request("unknown_schema", 1))
+                .await
+                .is_err());
+        });
+
+        horaedb.close();
+    }
+}
@@ -16,5 +16,6 @@
 // under the License.
 
 pub mod config;
+pub mod embedded;
 pub mod setup;
 mod signal_handler;
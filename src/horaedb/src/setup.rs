@@ -21,6 +21,7 @@ use std::sync::Arc;
 
 use analytic_engine::{
     self,
+    hook::{EngineHooks, SstChangeNotifier},
     setup::{EngineBuilder, TableEngineContext},
 };
 use catalog::{manager::ManagerRef, schema::OpenOptions, table_operator::TableOperator};
@@ -28,11 +29,15 @@ use catalog_impls::{table_based::TableBasedManager, volatile, CatalogManagerImpl
 use cluster::{cluster_impl::ClusterImpl, config::ClusterConfig, shard_set::ShardSet};
 use datafusion::execution::runtime_env::RuntimeConfig as DfRuntimeConfig;
 use df_operator::registry::{FunctionRegistry, FunctionRegistryImpl};
+use disk_quota::{Category, DirConsumer, DiskGovernor, DiskGovernorRef};
+use external_table_engine::ExternalTableEngine;
 use interpreters::table_manipulator::{catalog_based, meta_based};
 use logger::{info, warn, RuntimeLevel};
 use meta_client::{meta_impl, types::NodeMetaInfo};
+use object_store::config::ObjectStoreOptions;
 use proxy::{
     limiter::Limiter,
+    query_registry::QueryRegistry,
     schema_config_provider::{
         cluster_based::ClusterBasedProvider, config_based::ConfigBasedProvider,
     },
@@ -42,7 +47,8 @@ use runtime::PriorityRuntime;
 use server::{
     config::{StaticRouteConfig, StaticTopologyConfig},
     local_tables::LocalTablesRecoverer,
-    server::{Builder, DatafusionContext},
+    server::{Builder, DatafusionContext, Server},
+    static_route::StaticRouteReloader,
 };
 use table_engine::{
     engine::{EngineRuntimes, TableEngineRef},
@@ -53,6 +59,7 @@ use tracing_util::{
     self,
     tracing_appender::{non_blocking::WorkerGuard, rolling::Rotation},
 };
+use union_table_engine::UnionTableEngine;
 use wal::{
     config::StorageConfig,
     manager::{WalRuntimes, WalsOpener},
@@ -96,7 +103,7 @@ fn build_runtime(name: &str, threads_num: usize) -> runtime::Runtime {
     build_runtime_with_stack_size(name, threads_num, None)
 }
 
-fn build_engine_runtimes(config: &RuntimeConfig) -> EngineRuntimes {
+pub(crate) fn build_engine_runtimes(config: &RuntimeConfig) -> EngineRuntimes {
     let read_stack_size = config.read_thread_stack_size.as_byte() as usize;
     EngineRuntimes {
         read_runtime: PriorityRuntime::new(
@@ -119,11 +126,40 @@ fn build_engine_runtimes(config: &RuntimeConfig) -> EngineRuntimes {
     }
 }
 
-fn validate_config(config: &Config) {
+/// Validate the `config`, returns the reason if it's invalid.
+pub(crate) fn validate_config(config: &Config) -> Result<(), String> {
     let is_data_wal_disabled = config.analytic.wal.disable_data;
     if is_data_wal_disabled {
         warn!("disable data wal may cause data loss, please check whether this configuration is correct")
     }
+
+    // The atomic write of the tables is done by one write of the data wal.
+    let wal_storage = &config.analytic.wal.storage;
+    if config.server.atomic_write && !is_data_wal_disabled && !wal_storage.support_atomic_write() {
+        return Err(format!(
+            "server.atomic_write is not supported by the wal, wal_storage:{wal_storage:?}"
+        ));
+    }
+
+    let default_options = &config.server.default_schema_config.default_table_options;
+    default_options
+        .validate()
+        .map_err(|msg| format!("server.default_schema_config is invalid, {msg}"))?;
+    if let Some(ClusterDeployment::NoMeta(static_route)) = &config.cluster_deployment {
+        for schema_shard_view in &static_route.topology.schema_shards {
+            schema_shard_view
+                .default_table_options
+                .validate()
+                .map_err(|msg| {
+                    format!(
+                        "default_table_options of schema:{} is invalid, {msg}",
+                        schema_shard_view.schema
+                    )
+                })?;
+        }
+    }
+
+    Ok(())
 }
 
 /// Run a server, returns when the server is shutdown by user
@@ -134,85 +170,119 @@ pub fn run_server(config: Config, log_runtime: RuntimeLevel) {
 
     info!("Server starts up, config:{:#?}", config);
 
-    validate_config(&config);
+    if let Err(msg) = validate_config(&config) {
+        panic!("Invalid config, {msg}");
+    }
 
     runtimes.default_runtime.block_on(async {
-        match config.analytic.wal.storage {
-            StorageConfig::RocksDB(_) => {
-                #[cfg(feature = "wal-rocksdb")]
-                {
-                    use wal::rocksdb_impl::manager::RocksDBWalsOpener;
-                    run_server_with_runtimes::<RocksDBWalsOpener>(
-                        config,
-                        engine_runtimes,
-                        log_runtime,
-                    )
-                    .await
-                }
-                #[cfg(not(feature = "wal-rocksdb"))]
-                {
-                    panic!("RocksDB WAL not bundled!");
-                }
+        // Build and start server
+        let mut server = build_server(config, engine_runtimes, log_runtime).await;
+        server.start().await.expect("Failed to start server");
+
+        // Wait for signal
+        signal_handler::wait_for_signal();
+
+        // Stop server
+        server.stop().await;
+    });
+}
+
+/// Build a server with the wal chosen by the `config`, the server isn't
+/// started yet.
+pub(crate) async fn build_server(
+    config: Config,
+    engine_runtimes: Arc<EngineRuntimes>,
+    log_runtime: Arc<RuntimeLevel>,
+) -> Server {
+    match config.analytic.wal.storage {
+        StorageConfig::RocksDB(_) => {
+            #[cfg(feature = "wal-rocksdb")]
+            {
+                use wal::rocksdb_impl::manager::RocksDBWalsOpener;
+                build_server_with_runtimes::<RocksDBWalsOpener>(
+                    config,
+                    engine_runtimes,
+                    log_runtime,
+                )
+                .await
             }
+            #[cfg(not(feature = "wal-rocksdb"))]
+            {
+                panic!("RocksDB WAL not bundled!");
+            }
+        }
 
-            StorageConfig::Obkv(_) => {
-                #[cfg(feature = "wal-table-kv")]
-                {
-                    use wal::table_kv_impl::wal::ObkvWalsOpener;
-                    run_server_with_runtimes::<ObkvWalsOpener>(
-                        config,
-                        engine_runtimes,
-                        log_runtime,
-                    )
-                    .await;
-                }
-                #[cfg(not(feature = "wal-table-kv"))]
-                {
-                    panic!("Table KV WAL not bundled!");
-                }
+        StorageConfig::Obkv(_) => {
+            #[cfg(feature = "wal-table-kv")]
+            {
+                use wal::table_kv_impl::wal::ObkvWalsOpener;
+                build_server_with_runtimes::<ObkvWalsOpener>(config, engine_runtimes, log_runtime)
+                    .await
+            }
+            #[cfg(not(feature = "wal-table-kv"))]
+            {
+                panic!("Table KV WAL not bundled!");
             }
+        }
 
-            StorageConfig::Kafka(_) => {
-                #[cfg(feature = "wal-message-queue")]
-                {
-                    use wal::message_queue_impl::wal::KafkaWalsOpener;
-                    run_server_with_runtimes::<KafkaWalsOpener>(
-                        config,
-                        engine_runtimes,
-                        log_runtime,
-                    )
-                    .await;
-                }
-                #[cfg(not(feature = "wal-message-queue"))]
-                {
-                    panic!("Message Queue WAL not bundled!");
-                }
+        StorageConfig::Kafka(_) => {
+            #[cfg(feature = "wal-message-queue")]
+            {
+                use wal::message_queue_impl::wal::KafkaWalsOpener;
+                build_server_with_runtimes::<KafkaWalsOpener>(config, engine_runtimes, log_runtime)
+                    .await
             }
-            StorageConfig::Local(_) => {
-                #[cfg(feature = "wal-local-storage")]
-                {
-                    use wal::local_storage_impl::wal_manager::LocalStorageWalsOpener;
-                    run_server_with_runtimes::<LocalStorageWalsOpener>(
-                        config,
-                        engine_runtimes,
-                        log_runtime,
-                    )
-                    .await;
-                }
-                #[cfg(not(feature = "wal-local-storage"))]
-                {
-                    panic!("Local Storage WAL not bundled!");
-                }
+            #[cfg(not(feature = "wal-message-queue"))]
+            {
+                panic!("Message Queue WAL not bundled!");
             }
         }
-    });
+        StorageConfig::Local(_) => {
+            #[cfg(feature = "wal-local-storage")]
+            {
+                use wal::local_storage_impl::wal_manager::LocalStorageWalsOpener;
+                build_server_with_runtimes::<LocalStorageWalsOpener>(
+                    config,
+                    engine_runtimes,
+                    log_runtime,
+                )
+                .await
+            }
+            #[cfg(not(feature = "wal-local-storage"))]
+            {
+                panic!("Local Storage WAL not bundled!");
+            }
+        }
+        StorageConfig::ObjectStore(_) => {
+            #[cfg(feature = "wal-object-store")]
+            {
+                use wal::object_store_impl::wal_manager::ObjectStoreWalsOpener;
+                build_server_with_runtimes::<ObjectStoreWalsOpener>(
+                    config,
+                    engine_runtimes,
+                    log_runtime,
+                )
+                .await
+            }
+            #[cfg(not(feature = "wal-object-store"))]
+            {
+                panic!("Object Store WAL not bundled!");
+            }
+        }
+        StorageConfig::Memory(_) => {
+            use wal::memory_impl::wal_manager::MemoryWalsOpener;
+            build_server_with_runtimes::<MemoryWalsOpener>(config, engine_runtimes, log_runtime)
+                .await
+        }
+    }
 }
 
-async fn run_server_with_runtimes<T>(
+async fn build_server_with_runtimes<T>(
     config: Config,
     engine_runtimes: Arc<EngineRuntimes>,
     log_runtime: Arc<RuntimeLevel>,
-) where
+) -> Server
+where
     T: WalsOpener,
 {
     // Init function registry.
@@ -267,19 +337,15 @@ async fn run_server_with_runtimes<T>(
         }
     };
 
-    // Build and start server
-    let mut server = builder.build().expect("Failed to create server");
-    server.start().await.expect("Failed to start server");
-
-    // Wait for signal
-    signal_handler::wait_for_signal();
-
-    // Stop server
-    server.stop().await;
+    builder.build().expect("Failed to create server")
 }
 
 // Build proxy for all table engines.
-async fn build_table_engine_proxy(analytic: TableEngineRef) -> Arc<TableEngineProxy> {
+async fn build_table_engine_proxy(
+    analytic: TableEngineRef,
+    external: TableEngineRef,
+    union: TableEngineRef,
+) -> Arc<TableEngineProxy> {
     // Create memory engine
     let memory = MemoryTableEngine;
 
@@ -287,6 +353,8 @@ async fn build_table_engine_proxy(analytic: TableEngineRef) -> Arc<TableEnginePr
     Arc::new(TableEngineProxy {
         memory,
         analytic: analytic.clone(),
+        external: Some(external),
+        union: Some(union),
     })
 }
 
@@ -344,17 +412,39 @@ async fn build_with_meta<T: WalsOpener>(
     let opened_wals = wal_opener
         .open_wals(&config.analytic.wal, make_wal_runtime(runtimes.clone()))
         .await
-        .expect("Failed to setup analytic engine");
+        .expect("Failed to setup analytic engine")
+        .with_group_commit(&config.analytic.wal, runtimes.write_runtime.clone())
+        .with_compression(&config.analytic.wal)
+        .with_circuit_breaker(&config.analytic.wal.circuit_breaker);
+    let disk_governor = build_disk_governor(config);
+    let sst_change_notifier = build_sst_change_notifier(config);
     let engine_builder = EngineBuilder {
         config: &config.analytic,
         engine_runtimes: runtimes.clone(),
         opened_wals: opened_wals.clone(),
+        hooks: build_engine_hooks(disk_governor.as_ref(), sst_change_notifier.as_ref()),
     };
-    let TableEngineContext { table_engine, .. } = engine_builder
+    let TableEngineContext {
+        table_engine,
+        default_store,
+        disk_cache,
+        ..
+    } = engine_builder
         .build()
         .await
         .expect("Failed to setup analytic engine");
-    let engine_proxy = build_table_engine_proxy(table_engine).await;
+    if let (Some(disk_governor), Some(disk_cache)) = (&disk_governor, disk_cache) {
+        disk_governor.register(disk_cache);
+    }
+    let external_engine = Arc::new(
+        ExternalTableEngine::try_new(default_store.clone(), &config.external_table)
+            .expect("Failed to setup external table engine"),
+    );
+    let union_engine = Arc::new(UnionTableEngine::new(
+        default_store.clone(),
+        table_engine.clone(),
+    ));
+    let engine_proxy = build_table_engine_proxy(table_engine, external_engine, union_engine).await;
 
     let meta_based_manager_ref = Arc::new(volatile::ManagerImpl::new(
         shard_set,
@@ -363,7 +453,11 @@ async fn build_with_meta<T: WalsOpener>(
     ));
 
     // Build catalog manager.
-    let catalog_manager = Arc::new(CatalogManagerImpl::new(meta_based_manager_ref));
+    let query_registry = Arc::new(QueryRegistry::default());
+    let catalog_manager = Arc::new(CatalogManagerImpl::new(
+        meta_based_manager_ref,
+        query_registry.clone(),
+    ));
 
     let table_manipulator = Arc::new(meta_based::TableManipulatorImpl::new(meta_client));
 
@@ -376,6 +470,11 @@ async fn build_with_meta<T: WalsOpener>(
         .opened_wals(opened_wals)
         .router(router)
         .schema_config_provider(schema_config_provider)
+        .spill_store(default_store)
+        .spill_on_local_disk(is_local_store(config))
+        .query_registry(query_registry)
+        .disk_governor(disk_governor)
+        .sst_change_notifier(sst_change_notifier)
 }
 
 async fn build_without_meta<T: WalsOpener>(
@@ -388,18 +487,40 @@ async fn build_without_meta<T: WalsOpener>(
     let opened_wals = wal_builder
         .open_wals(&config.analytic.wal, make_wal_runtime(runtimes.clone()))
         .await
-        .expect("Failed to setup analytic engine");
+        .expect("Failed to setup analytic engine")
+        .with_group_commit(&config.analytic.wal, runtimes.write_runtime.clone())
+        .with_compression(&config.analytic.wal)
+        .with_circuit_breaker(&config.analytic.wal.circuit_breaker);
+    let disk_governor = build_disk_governor(config);
+    let sst_change_notifier = build_sst_change_notifier(config);
 
     let engine_builder = EngineBuilder {
         config: &config.analytic,
         engine_runtimes: runtimes.clone(),
         opened_wals: opened_wals.clone(),
+        hooks: build_engine_hooks(disk_governor.as_ref(), sst_change_notifier.as_ref()),
     };
-    let TableEngineContext { table_engine, .. } = engine_builder
+    let TableEngineContext {
+        table_engine,
+        default_store,
+        disk_cache,
+        ..
+    } = engine_builder
         .build()
         .await
         .expect("Failed to setup analytic engine");
-    let engine_proxy = build_table_engine_proxy(table_engine).await;
+    if let (Some(disk_governor), Some(disk_cache)) = (&disk_governor, disk_cache) {
+        disk_governor.register(disk_cache);
+    }
+    let external_engine = Arc::new(
+        ExternalTableEngine::try_new(default_store.clone(), &config.external_table)
+            .expect("Failed to setup external table engine"),
+    );
+    let union_engine = Arc::new(UnionTableEngine::new(
+        default_store.clone(),
+        table_engine.clone(),
+    ));
+    let engine_proxy = build_table_engine_proxy(table_engine, external_engine, union_engine).await;
 
     // Create catalog manager, use analytic engine as backend.
     let analytic = engine_proxy.analytic.clone();
@@ -413,7 +534,11 @@ async fn build_without_meta<T: WalsOpener>(
         .await
         .expect("Failed to fetch table infos for opening");
 
-    let catalog_manager = Arc::new(CatalogManagerImpl::new(Arc::new(table_based_manager)));
+    let query_registry = Arc::new(QueryRegistry::default());
+    let catalog_manager = Arc::new(CatalogManagerImpl::new(
+        Arc::new(table_based_manager),
+        query_registry.clone(),
+    ));
     let table_operator = TableOperator::new(catalog_manager.clone());
     let table_manipulator = Arc::new(catalog_based::TableManipulatorImpl::new(
         table_operator.clone(),
@@ -445,6 +570,11 @@ async fn build_without_meta<T: WalsOpener>(
         schema_configs,
         config.server.default_schema_config.clone(),
     ));
+    let static_route_reloader = Arc::new(StaticRouteReloader::new(
+        router.clone(),
+        schema_config_provider.clone(),
+        catalog_manager.clone(),
+    ));
 
     builder
         .table_engine(engine_proxy)
@@ -453,7 +583,61 @@ async fn build_without_meta<T: WalsOpener>(
         .router(router)
         .opened_wals(opened_wals)
         .schema_config_provider(schema_config_provider)
+        .static_route_reloader(static_route_reloader)
         .local_tables_recoverer(local_tables_recoverer)
+        .spill_store(default_store)
+        .spill_on_local_disk(is_local_store(config))
+        .query_registry(query_registry)
+        .disk_governor(disk_governor)
+        .sst_change_notifier(sst_change_notifier)
+}
+
+/// Build the governor of the local disk space if it's enabled, the local wal
+/// is tracked by it, and other consumers are registered once they are built.
+fn build_disk_governor(config: &Config) -> Option<DiskGovernorRef> {
+    if !config.server.disk_quota.enable {
+        return None;
+    }
+
+    let disk_governor = Arc::new(DiskGovernor::new(config.server.disk_quota.clone()));
+    for dir in config.analytic.wal.local_dirs() {
+        disk_governor.register(Arc::new(DirConsumer::new(Category::Wal, dir)));
+    }
+
+    Some(disk_governor)
+}
+
+/// Build the notifier of the sst changes if it's enabled, which is subscribed
+/// by `/debug/sst_changes`.
+fn build_sst_change_notifier(config: &Config) -> Option<Arc<SstChangeNotifier>> {
+    let notify_config = &config.server.sst_change_notify;
+    if !notify_config.enable {
+        return None;
+    }
+
+    Some(Arc::new(SstChangeNotifier::new(notify_config.capacity)))
+}
+
+fn build_engine_hooks(
+    disk_governor: Option<&DiskGovernorRef>,
+    sst_change_notifier: Option<&Arc<SstChangeNotifier>>,
+) -> EngineHooks {
+    let mut hooks = EngineHooks::default();
+    if let Some(disk_governor) = disk_governor {
+        hooks.register(disk_governor.clone());
+    }
+    if let Some(notifier) = sst_change_notifier {
+        hooks.register(notifier.clone());
+    }
+
+    hooks
+}
+
+fn is_local_store(config: &Config) -> bool {
+    matches!(
+        config.analytic.storage.object_store,
+        ObjectStoreOptions::Local(_)
+    )
 }
 
 async fn create_static_topology_schema(
@@ -476,3 +660,62 @@ async fn create_static_topology_schema(
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use cluster::config::DefaultTableOptions;
+    use server::config::SchemaShardView;
+
+    use super::*;
+
+    #[test]
+    fn test_validate_atomic_write() {
+        let mut config = Config::default();
+        config.server.atomic_write = true;
+        assert!(validate_config(&config).is_ok());
+
+        for storage in [
+            StorageConfig::Kafka(Box::default()),
+            StorageConfig::Obkv(Box::default()),
+        ] {
+            config.analytic.wal.storage = storage;
+            config.analytic.wal.disable_data = false;
+            let msg = validate_config(&config).unwrap_err();
+            assert!(msg.contains("server.atomic_write"), "{msg}");
+
+            // The data wal is not written if it's disabled.
+            config.analytic.wal.disable_data = true;
+            assert!(validate_config(&config).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_validate_default_table_options() {
+        let mut config = Config::default();
+        config
+            .server
+            .default_schema_config
+            .default_table_options
+            .ttl = Some("1x".to_string());
+        let msg = validate_config(&config).unwrap_err();
+        assert!(msg.contains("server.default_schema_config"), "{msg}");
+
+        let mut config = Config::default();
+        let schema_shard_view = SchemaShardView {
+            schema: "public".to_string(),
+            default_table_options: DefaultTableOptions {
+                compaction_strategy: Some("unknown".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        config.cluster_deployment = Some(ClusterDeployment::NoMeta(StaticRouteConfig {
+            rules: Default::default(),
+            topology: StaticTopologyConfig {
+                schema_shards: vec![schema_shard_view],
+            },
+        }));
+        let msg = validate_config(&config).unwrap_err();
+        assert!(msg.contains("schema:public"), "{msg}");
+    }
+}
@@ -22,8 +22,10 @@ use std::{sync::Arc, time::Instant};
 use common_types::request_id::RequestId;
 use macros::define_result;
 use query_engine::context::{Context as QueryContext, ContextRef as QueryContextRef};
+use query_frontend::hint::QueryHints;
 use runtime::Priority;
 use snafu::Snafu;
+use table_engine::table::{QueryStatsRef, ReadSnapshotRef};
 
 #[derive(Debug, Snafu)]
 pub enum Error {}
@@ -43,6 +45,9 @@ pub struct Context {
     /// If time range exceeds this threshold, the query will be marked as
     /// expensive
     expensive_query_threshold: u64,
+    read_snapshot: Option<ReadSnapshotRef>,
+    query_stats: Option<QueryStatsRef>,
+    query_hints: QueryHints,
 }
 
 impl Context {
@@ -54,6 +59,9 @@ impl Context {
             default_schema: String::new(),
             enable_partition_table_access: false,
             expensive_query_threshold: 24 * 3600 * 1000, // default 24 hours
+            read_snapshot: None,
+            query_stats: None,
+            query_hints: QueryHints::default(),
         }
     }
 
@@ -65,6 +73,10 @@ impl Context {
             default_catalog: self.default_catalog.clone(),
             default_schema: self.default_schema.clone(),
             priority,
+            read_snapshot: self.read_snapshot.clone(),
+            query_stats: self.query_stats.clone(),
+            read_parallelism: self.query_hints.parallelism,
+            no_cache: self.query_hints.no_cache,
         };
         Ok(Arc::new(ctx))
     }
@@ -93,6 +105,11 @@ impl Context {
     pub fn expensive_query_threshold(&self) -> u64 {
         self.expensive_query_threshold
     }
+
+    #[inline]
+    pub fn query_hints(&self) -> &QueryHints {
+        &self.query_hints
+    }
 }
 
 #[must_use]
@@ -103,6 +120,9 @@ pub struct Builder {
     default_schema: String,
     enable_partition_table_access: bool,
     expensive_query_threshold: u64,
+    read_snapshot: Option<ReadSnapshotRef>,
+    query_stats: Option<QueryStatsRef>,
+    query_hints: QueryHints,
 }
 
 impl Builder {
@@ -122,6 +142,24 @@ impl Builder {
         self
     }
 
+    /// Share the snapshot with other queries.
+    pub fn read_snapshot(mut self, read_snapshot: Option<ReadSnapshotRef>) -> Self {
+        self.read_snapshot = read_snapshot;
+        self
+    }
+
+    /// Collect the statistics of the reads into `query_stats`.
+    pub fn query_stats(mut self, query_stats: Option<QueryStatsRef>) -> Self {
+        self.query_stats = query_stats;
+        self
+    }
+
+    /// Hints given in the comments of the query.
+    pub fn query_hints(mut self, query_hints: QueryHints) -> Self {
+        self.query_hints = query_hints;
+        self
+    }
+
     pub fn build(self) -> Context {
         Context {
             request_id: self.request_id,
@@ -130,6 +168,9 @@ impl Builder {
             default_schema: self.default_schema,
             enable_partition_table_access: self.enable_partition_table_access,
             expensive_query_threshold: self.expensive_query_threshold,
+            read_snapshot: self.read_snapshot,
+            query_stats: self.query_stats,
+            query_hints: self.query_hints,
         }
     }
 }
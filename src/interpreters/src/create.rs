@@ -17,10 +17,14 @@
 
 //! Interpreter for create statements
 
+use std::collections::HashSet;
+
 use async_trait::async_trait;
+use futures::{stream, StreamExt};
+use logger::{info, warn};
 use macros::define_result;
-use query_frontend::plan::CreateTablePlan;
-use snafu::{ResultExt, Snafu};
+use query_frontend::plan::{CreateTablePlan, CreateTablesPlan, DropTablePlan};
+use snafu::{ensure, Backtrace, ResultExt, Snafu};
 use table_engine::engine::TableEngineRef;
 
 use crate::{
@@ -34,10 +38,33 @@ use crate::{
 pub enum Error {
     #[snafu(display("Failed to create table by table manipulator, err:{}", source))]
     ManipulateTable { source: table_manipulator::Error },
+
+    #[snafu(display(
+        "Table is created more than once in a batch, table:{}.\nBacktrace:\n{}",
+        table,
+        backtrace
+    ))]
+    DuplicateTable { table: String, backtrace: Backtrace },
+
+    #[snafu(display("Table already exists, table:{}.\nBacktrace:\n{}", table, backtrace))]
+    TableExists { table: String, backtrace: Backtrace },
+
+    #[snafu(display(
+        "Failed to drop the tables created in a failed batch, tables:{:?}, err:{}",
+        tables,
+        source
+    ))]
+    RollbackCreateTables {
+        tables: Vec<String>,
+        source: table_manipulator::Error,
+    },
 }
 
 define_result!(Error);
 
+/// Max number of the tables created concurrently in a batch.
+const MAX_CONCURRENT_CREATES: usize = 16;
+
 /// Create interpreter
 pub struct CreateInterpreter {
     ctx: Context,
@@ -79,3 +106,143 @@ impl Interpreter for CreateInterpreter {
         self.execute_create().await.context(Create)
     }
 }
+
+/// Interpreter to create tables in batch
+pub struct CreateTablesInterpreter {
+    ctx: Context,
+    plan: CreateTablesPlan,
+    table_engine: TableEngineRef,
+    table_manipulator: TableManipulatorRef,
+}
+
+impl CreateTablesInterpreter {
+    pub fn create(
+        ctx: Context,
+        plan: CreateTablesPlan,
+        table_engine: TableEngineRef,
+        table_manipulator: TableManipulatorRef,
+    ) -> InterpreterPtr {
+        Box::new(Self {
+            ctx,
+            plan,
+            table_engine,
+            table_manipulator,
+        })
+    }
+}
+
+impl CreateTablesInterpreter {
+    /// Create the tables concurrently, and drop the created ones if any of the
+    /// tables fails to be created.
+    ///
+    /// The existing tables are checked in one round trip before creating, and
+    /// they are skipped (if `if_not_exists` is set) so they are never dropped
+    /// by the rollback. There is no batch creation api of horaemeta, so the
+    /// tables are created by concurrent requests.
+    async fn execute_create_tables(self: Box<Self>) -> Result<Output> {
+        let Self {
+            ctx,
+            plan,
+            table_engine,
+            table_manipulator,
+        } = *self;
+
+        let mut names = HashSet::with_capacity(plan.plans.len());
+        for plan in &plan.plans {
+            ensure!(
+                names.insert(plan.table.clone()),
+                DuplicateTable { table: &plan.table }
+            );
+        }
+
+        let names = names.into_iter().collect::<Vec<_>>();
+        let existing = table_manipulator
+            .existing_tables(ctx.clone(), &names)
+            .await
+            .context(ManipulateTable)?;
+        let mut plans = Vec::with_capacity(plan.plans.len() - existing.len());
+        for mut plan in plan.plans {
+            if existing.contains(&plan.table) {
+                ensure!(plan.if_not_exists, TableExists { table: plan.table });
+                continue;
+            }
+            // The table created by others concurrently fails the batch, instead of being
+            // dropped by the rollback.
+            plan.if_not_exists = false;
+            plans.push(plan);
+        }
+
+        let mut drop_plans = plans
+            .iter()
+            .map(|plan| {
+                Some(DropTablePlan {
+                    engine: plan.engine.clone(),
+                    if_exists: true,
+                    table: plan.table.clone(),
+                    partition_info: plan.partition_info.clone(),
+                })
+            })
+            .collect::<Vec<_>>();
+        let num_tables = drop_plans.len();
+
+        let results = stream::iter(plans.into_iter().enumerate())
+            .map(|(idx, plan)| {
+                let create =
+                    table_manipulator.create_table(ctx.clone(), plan, table_engine.clone());
+                async move { (idx, create.await) }
+            })
+            .buffer_unordered(MAX_CONCURRENT_CREATES)
+            .collect::<Vec<_>>()
+            .await;
+
+        let mut created = Vec::with_capacity(results.len());
+        let mut first_err = None;
+        for (idx, result) in results {
+            match result {
+                Ok(_) => created.push(idx),
+                Err(e) => {
+                    first_err.get_or_insert(e);
+                }
+            }
+        }
+
+        let Some(err) = first_err else {
+            info!(
+                "Create tables in batch successfully, num_tables:{num_tables}, num_existing:{}",
+                existing.len()
+            );
+            return Ok(Output::AffectedRows(0));
+        };
+
+        warn!(
+            "Failed to create tables in batch, drop the created tables, num_created:{}, err:{}",
+            created.len(),
+            err
+        );
+        let mut undropped = Vec::new();
+        for idx in created {
+            let drop_plan = drop_plans[idx].take().unwrap();
+            let table = drop_plan.table.clone();
+            if let Err(e) = table_manipulator
+                .drop_table(ctx.clone(), drop_plan, table_engine.clone())
+                .await
+            {
+                warn!("Failed to drop the table created in batch, table:{table}, err:{e}");
+                undropped.push(table);
+            }
+        }
+
+        if !undropped.is_empty() {
+            return Err(err).context(RollbackCreateTables { tables: undropped });
+        }
+
+        Err(err).context(ManipulateTable)
+    }
+}
+
+#[async_trait]
+impl Interpreter for CreateTablesInterpreter {
+    async fn execute(self: Box<Self>) -> InterpreterResult<Output> {
+        self.execute_create_tables().await.context(Create)
+    }
+}
@@ -0,0 +1,67 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Interpreter for delete statement
+
+use async_trait::async_trait;
+use macros::define_result;
+use query_frontend::plan::DeletePlan;
+use snafu::{ResultExt, Snafu};
+
+use crate::interpreter::{
+    Delete, Interpreter, InterpreterPtr, Output, Result as InterpreterResult,
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Failed to delete data of table, table:{}, err:{}", table, source))]
+    DeleteData {
+        table: String,
+        source: table_engine::table::Error,
+    },
+}
+
+define_result!(Error);
+
+pub struct DeleteInterpreter {
+    plan: DeletePlan,
+}
+
+impl DeleteInterpreter {
+    pub fn create(plan: DeletePlan) -> InterpreterPtr {
+        Box::new(Self { plan })
+    }
+
+    async fn execute_delete(self: Box<Self>) -> Result<Output> {
+        let DeletePlan { table, before } = self.plan;
+
+        table.delete_before(before).await.context(DeleteData {
+            table: table.name(),
+        })?;
+
+        // The number of the deleted rows is unknown as the deleted rows are
+        // filtered out by the reads instead of being removed at once.
+        Ok(Output::AffectedRows(0))
+    }
+}
+
+#[async_trait]
+impl Interpreter for DeleteInterpreter {
+    async fn execute(self: Box<Self>) -> InterpreterResult<Output> {
+        self.execute_delete().await.context(Delete)
+    }
+}
@@ -26,12 +26,14 @@ use table_engine::engine::TableEngineRef;
 use crate::{
     alter_table::AlterTableInterpreter,
     context::Context,
-    create::CreateInterpreter,
+    create::{CreateInterpreter, CreateTablesInterpreter},
+    delete::DeleteInterpreter,
     describe::DescribeInterpreter,
     drop::DropInterpreter,
     exists::ExistsInterpreter,
     insert::InsertInterpreter,
     interpreter::{InterpreterPtr, Result},
+    kill::{KillQueryInterpreter, QueryKillerRef},
     select::SelectInterpreter,
     show::ShowInterpreter,
     table_manipulator::TableManipulatorRef,
@@ -46,6 +48,7 @@ pub struct Factory {
     catalog_manager: ManagerRef,
     table_engine: TableEngineRef,
     table_manipulator: TableManipulatorRef,
+    query_killer: Option<QueryKillerRef>,
 }
 
 impl Factory {
@@ -64,9 +67,15 @@ impl Factory {
             catalog_manager,
             table_engine,
             table_manipulator,
+            query_killer: None,
         }
     }
 
+    pub fn with_query_killer(mut self, query_killer: QueryKillerRef) -> Self {
+        self.query_killer = Some(query_killer);
+        self
+    }
+
     pub fn create(self, ctx: Context, plan: Plan) -> Result<InterpreterPtr> {
         let validate_ctx = ValidateContext {
             enable_partition_table_access: ctx.enable_partition_table_access(),
@@ -88,6 +97,9 @@ impl Factory {
             Plan::Create(p) => {
                 CreateInterpreter::create(ctx, p, self.table_engine, self.table_manipulator)
             }
+            Plan::CreateTables(p) => {
+                CreateTablesInterpreter::create(ctx, p, self.table_engine, self.table_manipulator)
+            }
             Plan::Drop(p) => {
                 DropInterpreter::create(ctx, p, self.table_engine, self.table_manipulator)
             }
@@ -95,6 +107,8 @@ impl Factory {
             Plan::AlterTable(p) => AlterTableInterpreter::create(p),
             Plan::Show(p) => ShowInterpreter::create(ctx, p, self.catalog_manager),
             Plan::Exists(p) => ExistsInterpreter::create(p),
+            Plan::KillQuery(p) => KillQueryInterpreter::create(p, self.query_killer),
+            Plan::Delete(p) => DeleteInterpreter::create(p),
         };
 
         Ok(interpreter)
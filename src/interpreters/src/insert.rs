@@ -276,17 +276,27 @@ async fn write_record_batches(
     prepare_and_write_table(table, row_group, default_value_map).await
 }
 
-async fn prepare_and_write_table(
-    table: TableRef,
+/// Build the request to write the `row_group` to the `table`, the tsid and the
+/// default values of the rows are filled.
+pub fn build_write_request(
+    table: &TableRef,
     mut row_group: RowGroup,
     default_value_map: &BTreeMap<usize, DfLogicalExpr>,
-) -> InterpreterResult<usize> {
-    maybe_generate_tsid(&mut row_group).context(Insert)?;
+) -> Result<WriteRequest> {
+    maybe_generate_tsid(&mut row_group)?;
 
     // Fill default values
-    fill_default_values(table.clone(), &mut row_group, default_value_map).context(Insert)?;
+    fill_default_values(table.clone(), &mut row_group, default_value_map)?;
+
+    Ok(WriteRequest { row_group })
+}
 
-    let request = WriteRequest { row_group };
+async fn prepare_and_write_table(
+    table: TableRef,
+    row_group: RowGroup,
+    default_value_map: &BTreeMap<usize, DfLogicalExpr>,
+) -> InterpreterResult<usize> {
+    let request = build_write_request(&table, row_group, default_value_map).context(Insert)?;
 
     let num_rows = table
         .write(request)
@@ -18,6 +18,8 @@
 //! Interpreter trait
 
 use async_trait::async_trait;
+use common_types::record_batch::RecordBatch;
+use futures::stream::{self, BoxStream, StreamExt};
 use macros::define_result;
 use snafu::Snafu;
 
@@ -39,6 +41,9 @@ pub enum Error {
     #[snafu(display("Failed to execute insert, err:{}", source))]
     Insert { source: crate::insert::Error },
 
+    #[snafu(display("Failed to execute delete, err:{}", source))]
+    Delete { source: crate::delete::Error },
+
     #[snafu(display("Failed to execute describe, err:{}", source))]
     Describe { source: crate::describe::Error },
 
@@ -54,9 +59,15 @@ pub enum Error {
     #[snafu(display("Failed to execute show database, err:{}", source))]
     ShowDatabases { source: crate::show::Error },
 
+    #[snafu(display("Failed to execute show table stats, err:{}", source))]
+    ShowTableStats { source: crate::show::Error },
+
     #[snafu(display("Failed to execute exists, err:{}", source))]
     Exists { source: crate::exists::Error },
 
+    #[snafu(display("Failed to execute kill query, err:{}", source))]
+    KillQuery { source: crate::kill::Error },
+
     #[snafu(display("Failed to transfer output to records"))]
     TryIntoRecords,
 
@@ -66,7 +77,6 @@ pub enum Error {
 
 define_result!(Error);
 
-// TODO(yingwen): Maybe add a stream variant for streaming result
 /// The interpreter output
 #[derive(Clone)]
 pub enum Output {
@@ -88,10 +98,40 @@ impl TryFrom<Output> for RecordBatchVec {
     }
 }
 
+pub type RecordBatchStream = BoxStream<'static, Result<RecordBatch>>;
+
+/// The interpreter output whose records are returned as a stream, so the large
+/// result needn't to be materialized in memory.
+pub enum OutputStream {
+    /// Affected rows number
+    AffectedRows(usize),
+    /// A stream of RecordBatch
+    Records(RecordBatchStream),
+}
+
+impl From<Output> for OutputStream {
+    fn from(output: Output) -> Self {
+        match output {
+            Output::AffectedRows(n) => OutputStream::AffectedRows(n),
+            Output::Records(records) => {
+                OutputStream::Records(stream::iter(records.into_iter().map(Ok)).boxed())
+            }
+        }
+    }
+}
+
 /// Interpreter executes the plan it holds
 #[async_trait]
 pub trait Interpreter {
     async fn execute(self: Box<Self>) -> Result<Output>;
+
+    /// Execute the plan and return the records as a stream.
+    ///
+    /// The output is collected by [Interpreter::execute] by default, only the
+    /// interpreters producing large results need to override it.
+    async fn execute_to_stream(self: Box<Self>) -> Result<OutputStream> {
+        self.execute().await.map(OutputStream::from)
+    }
 }
 
 /// A pointer to Interpreter
@@ -0,0 +1,74 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+//! Interpreter for kill query statement
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use macros::define_result;
+use query_frontend::plan::KillQueryPlan;
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
+
+use crate::interpreter::{
+    Interpreter, InterpreterPtr, KillQuery, Output, Result as InterpreterResult,
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Kill query is not supported by this node"))]
+    KillNotSupported,
+
+    #[snafu(display("Query not found, query_id:{query_id}"))]
+    QueryNotFound { query_id: String },
+}
+
+define_result!(Error);
+
+/// Killer of the in-flight queries.
+pub trait QueryKiller: Send + Sync {
+    /// Kill the query with the given id, return false if the query is not
+    /// found.
+    fn kill(&self, query_id: &str) -> bool;
+}
+
+pub type QueryKillerRef = Arc<dyn QueryKiller>;
+
+pub struct KillQueryInterpreter {
+    plan: KillQueryPlan,
+    killer: Option<QueryKillerRef>,
+}
+
+impl KillQueryInterpreter {
+    pub fn create(plan: KillQueryPlan, killer: Option<QueryKillerRef>) -> InterpreterPtr {
+        Box::new(Self { plan, killer })
+    }
+
+    async fn execute_kill(self: Box<Self>) -> Result<Output> {
+        let KillQueryPlan { query_id } = self.plan;
+        let killer = self.killer.context(KillNotSupported)?;
+        ensure!(killer.kill(&query_id), QueryNotFound { query_id });
+
+        Ok(Output::AffectedRows(1))
+    }
+}
+
+#[async_trait]
+impl Interpreter for KillQueryInterpreter {
+    async fn execute(self: Box<Self>) -> InterpreterResult<Output> {
+        self.execute_kill().await.context(KillQuery)
+    }
+}
@@ -26,12 +26,14 @@ use common_types::record_batch::RecordBatch;
 pub mod alter_table;
 pub mod context;
 pub mod create;
+pub mod delete;
 pub mod describe;
 pub mod drop;
 pub mod exists;
 pub mod factory;
 pub mod insert;
 pub mod interpreter;
+pub mod kill;
 mod metrics;
 pub mod select;
 pub mod show;
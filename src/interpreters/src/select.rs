@@ -18,7 +18,7 @@
 //! Interpreter for select statement
 
 use async_trait::async_trait;
-use futures::TryStreamExt;
+use futures::{stream, StreamExt, TryStreamExt};
 use generic_error::{BoxError, GenericError};
 use logger::debug;
 use macros::define_result;
@@ -30,10 +30,14 @@ use query_engine::{
 use query_frontend::plan::{PriorityContext, QueryPlan};
 use runtime::{Priority, PriorityRuntime};
 use snafu::{ResultExt, Snafu};
+use tokio::sync::mpsc;
 
 use crate::{
     context::Context,
-    interpreter::{Interpreter, InterpreterPtr, Output, Result as InterpreterResult, Select},
+    interpreter::{
+        Interpreter, InterpreterPtr, Output, OutputStream, RecordBatchStream,
+        Result as InterpreterResult, Select,
+    },
     metrics::ENGINE_QUERY_COUNTER,
 };
 
@@ -51,6 +55,8 @@ pub enum Error {
 
 define_result!(Error);
 
+const LOW_PRIORITY_STREAM_CHANNEL_LEN: usize = 4;
+
 /// Select interpreter
 pub struct SelectInterpreter {
     ctx: Context,
@@ -78,27 +84,32 @@ impl SelectInterpreter {
     }
 }
 
-#[async_trait]
-impl Interpreter for SelectInterpreter {
-    async fn execute(self: Box<Self>) -> InterpreterResult<Output> {
-        let request_id = self.ctx.request_id();
-        let plan = self.plan;
+impl SelectInterpreter {
+    /// Build the physical plan of the query and decide its priority.
+    ///
+    /// None is returned if the query has invalid query range, and the result
+    /// is empty.
+    async fn prepare(
+        ctx: &Context,
+        plan: QueryPlan,
+        physical_planner: &PhysicalPlannerRef,
+    ) -> Result<Option<PreparedQuery>> {
+        let request_id = ctx.request_id();
         let priority = match plan
             .decide_query_priority(PriorityContext {
-                time_range_threshold: self.ctx.expensive_query_threshold(),
+                time_range_threshold: ctx.expensive_query_threshold(),
             })
             .box_err()
             .with_context(|| ExecutePlan {
                 msg: format!("decide query priority failed, id:{request_id}"),
-            })
-            .context(Select)?
-        {
-            Some(v) => v,
+            })? {
+            // The priority given by the hint takes precedence.
+            Some(v) => ctx.query_hints().priority.unwrap_or(v),
             None => {
                 debug!(
                     "Query has invalid query range, return empty result directly, id:{request_id}, plan:{plan:?}"
                 );
-                return Ok(Output::Records(Vec::new()));
+                return Ok(None);
             }
         };
 
@@ -106,31 +117,60 @@ impl Interpreter for SelectInterpreter {
             .with_label_values(&[priority.as_str()])
             .inc();
 
-        let query_ctx = self
-            .ctx
+        let query_ctx = ctx
             .new_query_context(priority)
-            .context(CreateQueryContext)
-            .context(Select)?;
+            .context(CreateQueryContext)?;
 
         debug!(
             "Interpreter execute select begin, request_id:{request_id}, plan:{plan:?}, priority:{priority:?}"
         );
 
         // Create physical plan.
-        let physical_plan = self
-            .physical_planner
+        let physical_plan = physical_planner
             .plan(&query_ctx, plan)
             .await
             .box_err()
             .context(ExecutePlan {
                 msg: "failed to build physical plan",
-            })
-            .context(Select)?;
+            })?;
+
+        Ok(Some(PreparedQuery {
+            query_ctx,
+            physical_plan,
+            priority,
+        }))
+    }
+}
+
+struct PreparedQuery {
+    query_ctx: QueryContextRef,
+    physical_plan: PhysicalPlanRef,
+    priority: Priority,
+}
+
+#[async_trait]
+impl Interpreter for SelectInterpreter {
+    async fn execute(self: Box<Self>) -> InterpreterResult<Output> {
+        let Self {
+            ctx,
+            plan,
+            executor,
+            physical_planner,
+            query_runtime,
+        } = *self;
+        let Some(PreparedQuery {
+            query_ctx,
+            physical_plan,
+            priority,
+        }) = Self::prepare(&ctx, plan, &physical_planner)
+            .await
+            .context(Select)?
+        else {
+            return Ok(Output::Records(Vec::new()));
+        };
 
         if matches!(priority, Priority::Low) {
-            let executor = self.executor;
-            return self
-                .query_runtime
+            return query_runtime
                 .spawn_with_priority(
                     async move {
                         execute_and_collect(query_ctx, executor, physical_plan)
@@ -144,10 +184,57 @@ impl Interpreter for SelectInterpreter {
                 .context(Select)?;
         }
 
-        execute_and_collect(query_ctx, self.executor, physical_plan)
+        execute_and_collect(query_ctx, executor, physical_plan)
             .await
             .context(Select)
     }
+
+    async fn execute_to_stream(self: Box<Self>) -> InterpreterResult<OutputStream> {
+        let Self {
+            ctx,
+            plan,
+            executor,
+            physical_planner,
+            query_runtime,
+        } = *self;
+        let Some(PreparedQuery {
+            query_ctx,
+            physical_plan,
+            priority,
+        }) = Self::prepare(&ctx, plan, &physical_planner)
+            .await
+            .context(Select)?
+        else {
+            return Ok(OutputStream::Records(stream::empty().boxed()));
+        };
+
+        let records = execute_to_stream(query_ctx, executor, physical_plan)
+            .await
+            .context(Select)?;
+        if !matches!(priority, Priority::Low) {
+            return Ok(OutputStream::Records(records));
+        }
+
+        // Poll the stream in the low priority runtime, and the task exits once the
+        // receiver is dropped.
+        let (tx, rx) = mpsc::channel(LOW_PRIORITY_STREAM_CHANNEL_LEN);
+        let _ = query_runtime.spawn_with_priority(
+            async move {
+                let mut records = records;
+                while let Some(item) = records.next().await {
+                    if tx.send(item).await.is_err() {
+                        break;
+                    }
+                }
+            },
+            Priority::Low,
+        );
+        let records = stream::unfold(rx, |mut rx| async move {
+            rx.recv().await.map(|item| (item, rx))
+        });
+
+        Ok(OutputStream::Records(records.boxed()))
+    }
 }
 
 async fn execute_and_collect(
@@ -174,3 +261,27 @@ async fn execute_and_collect(
 
     Ok(Output::Records(record_batches))
 }
+
+async fn execute_to_stream(
+    query_ctx: QueryContextRef,
+    executor: ExecutorRef,
+    physical_plan: PhysicalPlanRef,
+) -> Result<RecordBatchStream> {
+    let record_batch_stream = executor
+        .execute(&query_ctx, physical_plan)
+        .await
+        .box_err()
+        .context(ExecutePlan {
+            msg: "failed to execute physical plan",
+        })?;
+
+    let records = record_batch_stream.map(|item| {
+        item.box_err()
+            .context(ExecutePlan {
+                msg: "failed to poll execution results",
+            })
+            .context(Select)
+    });
+
+    Ok(records.boxed())
+}
@@ -27,7 +27,7 @@ use catalog::{manager::ManagerRef, schema::Schema, Catalog};
 use macros::define_result;
 use query_frontend::{
     ast::ShowCreateObject,
-    plan::{QueryType, ShowCreatePlan, ShowPlan, ShowTablesPlan},
+    plan::{QueryType, ShowCreatePlan, ShowPlan, ShowTableStatsPlan, ShowTablesPlan},
 };
 use regex::Regex;
 use snafu::{Backtrace, OptionExt, ResultExt, Snafu};
@@ -36,13 +36,15 @@ use crate::{
     context::Context,
     interpreter::{
         Interpreter, InterpreterPtr, Output, Result as InterpreterResult, ShowCreateTable,
-        ShowDatabases, ShowTables,
+        ShowDatabases, ShowTableStats, ShowTables,
     },
     show_create::ShowCreateInterpreter,
 };
 
 const SHOW_TABLES_COLUMN_SCHEMA: &str = "Tables";
 const SHOW_DATABASES_COLUMN_SCHEMA: &str = "Schemas";
+const SHOW_TABLE_STATS_NAME_COLUMN: &str = "Name";
+const SHOW_TABLE_STATS_VALUE_COLUMN: &str = "Value";
 
 #[derive(Debug, Snafu)]
 #[snafu(visibility(pub(crate)))]
@@ -203,6 +205,77 @@ impl ShowInterpreter {
 
         Ok(Output::Records(vec![record_batch]))
     }
+
+    fn show_table_stats(plan: ShowTableStatsPlan) -> Result<Output> {
+        let stats = plan.table.stats();
+
+        let mut names = Vec::new();
+        let mut values = Vec::new();
+        let mut add_stat = |name: String, value: Option<String>| {
+            names.push(name);
+            values.push(value);
+        };
+        add_stat(
+            "memtable_size".to_string(),
+            Some(stats.memtable_size.to_string()),
+        );
+        add_stat("sst_size".to_string(), Some(stats.sst_size.to_string()));
+        add_stat("data_size".to_string(), Some(stats.data_size.to_string()));
+        for (level, num_ssts) in stats.num_ssts_per_level.iter().enumerate() {
+            add_stat(
+                format!("num_ssts_level_{level}"),
+                Some(num_ssts.to_string()),
+            );
+        }
+        add_stat(
+            "min_timestamp".to_string(),
+            stats
+                .time_range
+                .map(|range| range.inclusive_start().as_i64().to_string()),
+        );
+        add_stat(
+            "max_timestamp".to_string(),
+            stats
+                .time_range
+                .map(|range| (range.exclusive_end().as_i64() - 1).to_string()),
+        );
+        add_stat(
+            "num_series".to_string(),
+            stats.num_series.map(|v| v.to_string()),
+        );
+        add_stat(
+            "num_rows".to_string(),
+            stats.num_rows.map(|v| v.to_string()),
+        );
+        add_stat(
+            "write_rate".to_string(),
+            Some(format!("{:.2}", stats.write_rate)),
+        );
+        add_stat(
+            "read_rate".to_string(),
+            Some(format!("{:.2}", stats.read_rate)),
+        );
+        add_stat("num_write".to_string(), Some(stats.num_write.to_string()));
+        add_stat("num_read".to_string(), Some(stats.num_read.to_string()));
+        add_stat("num_flush".to_string(), Some(stats.num_flush.to_string()));
+
+        let schema = DataSchema::new(vec![
+            Field::new(SHOW_TABLE_STATS_NAME_COLUMN, DataType::Utf8, false),
+            Field::new(SHOW_TABLE_STATS_VALUE_COLUMN, DataType::Utf8, true),
+        ]);
+        let record_batch = RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(StringArray::from(names)),
+                Arc::new(StringArray::from(values)),
+            ],
+        )
+        .context(CreateRecordBatch)?;
+
+        let record_batch = record_batch.try_into().context(ToCommonRecordType)?;
+
+        Ok(Output::Records(vec![record_batch]))
+    }
 }
 
 fn to_pattern_re(pattern: &str) -> Result<Regex> {
@@ -227,6 +300,7 @@ impl Interpreter for ShowInterpreter {
             ShowPlan::ShowDatabase => {
                 Self::show_databases(self.ctx, self.catalog_manager).context(ShowDatabases)
             }
+            ShowPlan::ShowTableStatsPlan(t) => Self::show_table_stats(t).context(ShowTableStats),
         }
     }
 }
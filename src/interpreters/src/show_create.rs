@@ -156,7 +156,9 @@ impl ShowCreateInterpreter {
             }
             PartitionInfo::Key(v) => {
                 let rendered_partition_key = v.partition_key.join(",");
-                if v.linear {
+                if v.is_series() {
+                    format!(" PARTITION BY SERIES PARTITIONS {}", v.definitions.len())
+                } else if v.linear {
                     format!(
                         " PARTITION BY LINEAR KEY({rendered_partition_key}) PARTITIONS {}",
                         v.definitions.len()
@@ -197,6 +199,7 @@ mod test {
     use datafusion_proto::bytes::Serializeable;
     use table_engine::partition::{
         HashPartitionInfo, KeyPartitionInfo, PartitionDefinition, PartitionInfo,
+        SERIES_PARTITION_VERSION,
     };
 
     use super::*;
@@ -252,4 +255,20 @@ mod test {
             ShowCreateInterpreter::render_partition_info(Some(partition_info))
         );
     }
+
+    #[test]
+    fn test_render_series_partition_info() {
+        let partition_info = PartitionInfo::Key(KeyPartitionInfo {
+            version: SERIES_PARTITION_VERSION,
+            definitions: vec![PartitionDefinition::default(); 4],
+            partition_key: vec!["host".to_string(), "region".to_string()],
+            linear: false,
+        });
+
+        let expected = " PARTITION BY SERIES PARTITIONS 4".to_string();
+        assert_eq!(
+            expected,
+            ShowCreateInterpreter::render_partition_info(Some(partition_info))
+        );
+    }
 }
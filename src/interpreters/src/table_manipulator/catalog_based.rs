@@ -15,6 +15,8 @@
 // specific language governing permissions and limitations
 // under the License.
 
+use std::collections::HashSet;
+
 use async_trait::async_trait;
 use catalog::{
     schema::{CreateOptions, CreateTableRequest, DropOptions, DropTableRequest},
@@ -123,4 +125,10 @@ impl TableManipulator for TableManipulatorImpl {
 
         Ok(Output::AffectedRows(0))
     }
+
+    async fn existing_tables(&self, ctx: Context, tables: &[String]) -> Result<HashSet<String>> {
+        self.table_operator
+            .existing_tables(ctx.default_catalog(), ctx.default_schema(), tables)
+            .context(TableOperatorErr)
+    }
 }
@@ -15,12 +15,14 @@
 // specific language governing permissions and limitations
 // under the License.
 
+use std::collections::HashSet;
+
 use async_trait::async_trait;
 use common_types::schema::SchemaEncoder;
 use generic_error::BoxError;
 use logger::info;
 use meta_client::{
-    types::{CreateTableRequest, DropTableRequest, PartitionTableInfo},
+    types::{CreateTableRequest, DropTableRequest, PartitionTableInfo, RouteTablesRequest},
     MetaClientRef,
 };
 use query_frontend::plan::{CreateTablePlan, DropTablePlan};
@@ -33,7 +35,9 @@ use table_engine::{
 use crate::{
     context::Context,
     interpreter::Output,
-    table_manipulator::{CreateWithCause, DropWithCause, Result, TableManipulator},
+    table_manipulator::{
+        CreateWithCause, DropWithCause, FindTablesWithCause, Result, TableManipulator,
+    },
 };
 
 pub struct TableManipulatorImpl {
@@ -139,6 +143,25 @@ impl TableManipulator for TableManipulatorImpl {
 
         Ok(Output::AffectedRows(0))
     }
+
+    async fn existing_tables(&self, ctx: Context, tables: &[String]) -> Result<HashSet<String>> {
+        // Only the existing tables are routed, so all the tables are checked in one
+        // round trip.
+        let req = RouteTablesRequest {
+            schema_name: ctx.default_schema().to_string(),
+            table_names: tables.to_vec(),
+        };
+        let resp = self
+            .meta_client
+            .route_tables(req.clone())
+            .await
+            .box_err()
+            .with_context(|| FindTablesWithCause {
+                msg: format!("failed to route tables by meta client, req:{req:?}"),
+            })?;
+
+        Ok(resp.entries.into_keys().collect())
+    }
 }
 
 fn create_partition_table_info(
@@ -15,7 +15,7 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use std::sync::Arc;
+use std::{collections::HashSet, sync::Arc};
 
 use async_trait::async_trait;
 use generic_error::GenericError;
@@ -79,6 +79,9 @@ pub enum Error {
     #[snafu(display("Failed to create partition table without horaemeta, table:{}", table))]
     PartitionTableNotSupported { table: String },
 
+    #[snafu(display("Failed to find tables, msg:{}, err:{}", msg, source))]
+    FindTablesWithCause { msg: String, source: GenericError },
+
     #[snafu(display("Failed to operate table, err:{}", source))]
     TableOperator { source: catalog::Error },
 }
@@ -100,4 +103,7 @@ pub trait TableManipulator {
         plan: DropTablePlan,
         table_engine: TableEngineRef,
     ) -> Result<Output>;
+
+    /// Returns the tables already existing among `tables`.
+    async fn existing_tables(&self, ctx: Context, tables: &[String]) -> Result<HashSet<String>>;
 }
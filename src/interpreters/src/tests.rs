@@ -29,7 +29,11 @@ use datafusion::execution::runtime_env::RuntimeConfig;
 use df_operator::registry::{FunctionRegistry, FunctionRegistryImpl};
 use query_engine::{datafusion_impl::DatafusionQueryEngineImpl, QueryEngineRef};
 use query_frontend::{
-    config::DynamicConfig, parser::Parser, plan::Plan, planner::Planner, provider::MetaProvider,
+    config::DynamicConfig,
+    parser::Parser,
+    plan::{CreateTablesPlan, Plan},
+    planner::Planner,
+    provider::MetaProvider,
     tests::MockMetaProvider,
 };
 use runtime::{Builder, PriorityRuntime};
@@ -329,6 +333,90 @@ where
         );
     }
 
+    async fn create_tables(&self, sqls: &[&str]) -> Result<Output> {
+        let plans = sqls
+            .iter()
+            .map(|sql| match sql_to_plan(&self.meta_provider, sql) {
+                Plan::Create(plan) => plan,
+                plan => panic!("unexpected plan:{plan:?}"),
+            })
+            .collect();
+        let ctx = Context::builder(RequestId::next_id(), None)
+            .default_catalog_and_schema(DEFAULT_CATALOG.to_string(), DEFAULT_SCHEMA.to_string())
+            .build();
+        let factory = self.build_factory().await;
+        let interpreter = factory.create(ctx, Plan::CreateTables(CreateTablesPlan { plans }))?;
+        interpreter.execute().await
+    }
+
+    fn table_exists(&self, table_name: &str) -> bool {
+        self.catalog_manager
+            .catalog_by_name(DEFAULT_CATALOG)
+            .unwrap()
+            .unwrap()
+            .schema_by_name(DEFAULT_SCHEMA)
+            .unwrap()
+            .unwrap()
+            .table_by_name(table_name)
+            .unwrap()
+            .is_some()
+    }
+
+    async fn test_create_tables(&self) {
+        let create_sql = |table_name: &str, if_not_exists: bool| {
+            let if_not_exists = if if_not_exists { "IF NOT EXISTS" } else { "" };
+            format!("CREATE TABLE {if_not_exists} {table_name}(c1 string tag not null, ts timestamp not null, timestamp key(ts), primary key(c1, ts)) \
+            ENGINE=Analytic WITH (enable_ttl='false')")
+        };
+        // Partition table is not supported without horaemeta.
+        let partition_sql = "CREATE TABLE batch_partition(c1 string tag not null, ts timestamp not null, timestamp key(ts), primary key(c1, ts)) \
+            PARTITION BY KEY(c1) PARTITIONS 2 ENGINE=Analytic WITH (enable_ttl='false')";
+
+        // Duplicate tables are rejected before creating any of them.
+        let res = self
+            .create_tables(&[
+                &create_sql("batch_table1", false),
+                &create_sql("batch_table1", true),
+            ])
+            .await;
+        assert!(format!("{res:?}").contains("Table is created more than once in a batch"));
+        assert!(!self.table_exists("batch_table1"));
+
+        // Existing tables are rejected without `IF NOT EXISTS`.
+        let res = self
+            .create_tables(&[
+                &create_sql("batch_table1", false),
+                &create_sql("test_table", false),
+            ])
+            .await;
+        assert!(format!("{res:?}").contains("Table already exists"));
+        assert!(!self.table_exists("batch_table1"));
+
+        // Only the tables created by the failed batch are dropped.
+        let res = self
+            .create_tables(&[
+                &create_sql("batch_table1", false),
+                &create_sql("test_table", true),
+                partition_sql,
+            ])
+            .await;
+        assert!(res.is_err());
+        assert!(!self.table_exists("batch_table1"));
+        assert!(self.table_exists("test_table"));
+
+        let output = self
+            .create_tables(&[
+                &create_sql("batch_table1", false),
+                &create_sql("batch_table2", true),
+                &create_sql("test_table", true),
+            ])
+            .await
+            .unwrap();
+        assert!(matches!(output, Output::AffectedRows(0)));
+        assert!(self.table_exists("batch_table1"));
+        assert!(self.table_exists("batch_table2"));
+    }
+
     async fn test_drop_table(&self) {
         let sql = "drop table test_table";
         let output = self.sql_to_output(sql).await.unwrap();
@@ -416,6 +504,7 @@ async fn test_interpreters<T: EngineBuildContext>(
     env.test_select_table().await;
     env.test_show_create_table().await;
     env.test_alter_table().await;
+    env.test_create_tables().await;
     env.test_drop_table().await;
     env.test_insert_table_with_missing_columns().await;
     env.test_enable_partition_table_access().await;
@@ -76,6 +76,8 @@ impl Validator {
                 is_sub_table!(&plan.table)
             }
 
+            Plan::CreateTables(plan) => plan.plans.iter().any(|plan| is_sub_table!(&plan.table)),
+
             Plan::Drop(plan) => {
                 is_sub_table!(&plan.table)
             }
@@ -84,6 +86,10 @@ impl Validator {
                 is_sub_table!(plan.table.name())
             }
 
+            Plan::Delete(plan) => {
+                is_sub_table!(plan.table.name())
+            }
+
             Plan::Describe(plan) => {
                 is_sub_table!(plan.table.name())
             }
@@ -92,15 +98,17 @@ impl Validator {
                 is_sub_table!(plan.table.name())
             }
 
-            Plan::Show(show_plan) => {
-                if let ShowPlan::ShowCreatePlan(show_create_plan) = show_plan {
+            Plan::Show(show_plan) => match show_plan {
+                ShowPlan::ShowCreatePlan(show_create_plan) => {
                     is_sub_table!(show_create_plan.table.name())
-                } else {
-                    false
                 }
-            }
+                ShowPlan::ShowTableStatsPlan(show_table_stats_plan) => {
+                    is_sub_table!(show_table_stats_plan.table.name())
+                }
+                ShowPlan::ShowTablesPlan(_) | ShowPlan::ShowDatabase => false,
+            },
 
-            Plan::Exists(_) => false,
+            Plan::Exists(_) | Plan::KillQuery(_) => false,
         }
     }
 }
@@ -90,6 +90,9 @@ pub enum Error {
         msg: String,
         backtrace: Backtrace,
     },
+
+    #[snafu(display("Meta service is unavailable, err:{}", source))]
+    CircuitBreakerOpen { source: circuit_breaker::Error },
 }
 
 define_result!(Error);
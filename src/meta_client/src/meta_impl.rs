@@ -15,9 +15,11 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use std::sync::Arc;
+use std::{future::Future, sync::Arc};
 
 use async_trait::async_trait;
+use circuit_breaker::CircuitBreaker;
+use future_ext::{RetryPolicy, RetryPolicyConfig};
 use generic_error::BoxError;
 use horaedbproto::{
     common::ResponseHeader,
@@ -35,8 +37,9 @@ use crate::{
         GetTablesOfShardsRequest, GetTablesOfShardsResponse, NodeInfo, NodeMetaInfo, RequestHeader,
         RouteTablesRequest, RouteTablesResponse, ShardInfo,
     },
-    BadResponse, FailAllocSchemaId, FailConnect, FailCreateTable, FailDropTable, FailGetTables,
-    FailRouteTables, FailSendHeartbeat, MetaClient, MetaClientRef, MissingHeader, Result,
+    BadResponse, CircuitBreakerOpen, Error, FailAllocSchemaId, FailConnect, FailCreateTable,
+    FailDropTable, FailGetTables, FailRouteTables, FailSendHeartbeat, MetaClient, MetaClientRef,
+    MissingHeader, Result,
 };
 
 type MetaServiceGrpcClient = MetaRpcServiceClient<tonic::transport::Channel>;
@@ -49,6 +52,10 @@ pub struct MetaClientConfig {
     pub lease: ReadableDuration,
     pub timeout: ReadableDuration,
     pub cq_count: usize,
+    /// Circuit breaker of the rpc calls to the meta service.
+    pub circuit_breaker: circuit_breaker::Config,
+    /// Retry policy of the idempotent rpc calls to the meta service.
+    pub retry: RetryPolicyConfig,
 }
 
 impl Default for MetaClientConfig {
@@ -59,6 +66,8 @@ impl Default for MetaClientConfig {
             lease: ReadableDuration::secs(10),
             timeout: ReadableDuration::secs(5),
             cq_count: 8,
+            circuit_breaker: circuit_breaker::Config::default(),
+            retry: RetryPolicyConfig::default(),
         }
     }
 }
@@ -277,11 +286,146 @@ fn check_response_header(header: &Option<ResponseHeader>) -> Result<()> {
     }
 }
 
+/// Meta client failing fast when the meta service keeps unavailable.
+struct MetaClientWithCircuitBreaker {
+    client: MetaClientRef,
+    breaker: CircuitBreaker,
+}
+
+impl MetaClientWithCircuitBreaker {
+    async fn call<T>(&self, f: impl Future<Output = Result<T>>) -> Result<T> {
+        self.breaker.acquire().context(CircuitBreakerOpen)?;
+        let res = f.await;
+        let success = match &res {
+            Ok(_) => true,
+            Err(e) => !is_rpc_failure(e),
+        };
+        self.breaker.on_result(success);
+        res
+    }
+}
+
+/// The error responses from the meta service shouldn't trip the breaker, only
+/// the failed rpc calls should.
+fn is_rpc_failure(e: &Error) -> bool {
+    matches!(
+        e,
+        Error::FailConnect { .. }
+            | Error::FailSendHeartbeat { .. }
+            | Error::FailAllocSchemaId { .. }
+            | Error::FailCreateTable { .. }
+            | Error::FailDropTable { .. }
+            | Error::FailGetTables { .. }
+            | Error::FailRouteTables { .. }
+    )
+}
+
+#[async_trait]
+impl MetaClient for MetaClientWithCircuitBreaker {
+    async fn alloc_schema_id(&self, req: AllocSchemaIdRequest) -> Result<AllocSchemaIdResponse> {
+        self.call(self.client.alloc_schema_id(req)).await
+    }
+
+    async fn create_table(&self, req: CreateTableRequest) -> Result<CreateTableResponse> {
+        self.call(self.client.create_table(req)).await
+    }
+
+    async fn drop_table(&self, req: DropTableRequest) -> Result<DropTableResponse> {
+        self.call(self.client.drop_table(req)).await
+    }
+
+    async fn get_tables_of_shards(
+        &self,
+        req: GetTablesOfShardsRequest,
+    ) -> Result<GetTablesOfShardsResponse> {
+        self.call(self.client.get_tables_of_shards(req)).await
+    }
+
+    async fn route_tables(&self, req: RouteTablesRequest) -> Result<RouteTablesResponse> {
+        self.call(self.client.route_tables(req)).await
+    }
+
+    async fn get_nodes(&self, req: GetNodesRequest) -> Result<GetNodesResponse> {
+        self.call(self.client.get_nodes(req)).await
+    }
+
+    async fn send_heartbeat(&self, shard_infos: Vec<ShardInfo>) -> Result<()> {
+        self.call(self.client.send_heartbeat(shard_infos)).await
+    }
+}
+
+/// Meta client retrying the idempotent rpc calls failed by the rpc errors.
+struct MetaClientWithRetry {
+    client: MetaClientRef,
+    policy: RetryPolicy,
+}
+
+#[async_trait]
+impl MetaClient for MetaClientWithRetry {
+    async fn alloc_schema_id(&self, req: AllocSchemaIdRequest) -> Result<AllocSchemaIdResponse> {
+        self.client.alloc_schema_id(req).await
+    }
+
+    async fn create_table(&self, req: CreateTableRequest) -> Result<CreateTableResponse> {
+        self.client.create_table(req).await
+    }
+
+    async fn drop_table(&self, req: DropTableRequest) -> Result<DropTableResponse> {
+        self.client.drop_table(req).await
+    }
+
+    async fn get_tables_of_shards(
+        &self,
+        req: GetTablesOfShardsRequest,
+    ) -> Result<GetTablesOfShardsResponse> {
+        self.policy
+            .retry(
+                || self.client.get_tables_of_shards(req.clone()),
+                is_rpc_failure,
+            )
+            .await
+    }
+
+    async fn route_tables(&self, req: RouteTablesRequest) -> Result<RouteTablesResponse> {
+        self.policy
+            .retry(|| self.client.route_tables(req.clone()), is_rpc_failure)
+            .await
+    }
+
+    async fn get_nodes(&self, req: GetNodesRequest) -> Result<GetNodesResponse> {
+        self.policy
+            .retry(|| self.client.get_nodes(req.clone()), is_rpc_failure)
+            .await
+    }
+
+    async fn send_heartbeat(&self, shard_infos: Vec<ShardInfo>) -> Result<()> {
+        self.client.send_heartbeat(shard_infos).await
+    }
+}
+
 /// Create a meta client with given `config`.
 pub async fn build_meta_client(
     config: MetaClientConfig,
     node_meta_info: NodeMetaInfo,
 ) -> Result<MetaClientRef> {
-    let meta_client = MetaClientImpl::connect(config, node_meta_info).await?;
-    Ok(Arc::new(meta_client))
+    let breaker_config = config.circuit_breaker.clone();
+    let retry_config = config.retry.clone();
+    let mut meta_client: MetaClientRef =
+        Arc::new(MetaClientImpl::connect(config, node_meta_info).await?);
+    if breaker_config.enable {
+        meta_client = Arc::new(MetaClientWithCircuitBreaker {
+            client: meta_client,
+            breaker: CircuitBreaker::new("meta_client", breaker_config),
+        });
+    }
+    // Every retry is checked by the circuit breaker, and no retry once it is
+    // open.
+    if retry_config.max_retries > 0 {
+        meta_client = Arc::new(MetaClientWithRetry {
+            client: meta_client,
+            policy: RetryPolicy::from_config("meta_client", &retry_config),
+        });
+    }
+
+    Ok(meta_client)
 }
@@ -31,7 +31,7 @@ use generic_error::BoxError;
 use snafu::{OptionExt, ResultExt};
 use table_engine::{
     engine::{
-        CloseShardRequest, CloseTableRequest, CreateTableParams, CreateTableRequest,
+        CloseShardRequest, CloseTableRequest, ClosedTable, CreateTableParams, CreateTableRequest,
         DropTableRequest, OpenShardRequest, OpenShardResult, OpenTableRequest, Result, TableEngine,
         Unexpected, UnexpectedNoCause,
     },
@@ -106,7 +106,7 @@ impl TableEngine for PartitionTableEngine {
         Ok(OpenShardResult::default())
     }
 
-    async fn close_shard(&self, _request: CloseShardRequest) -> Vec<Result<String>> {
-        vec![Ok("".to_string())]
+    async fn close_shard(&self, _request: CloseShardRequest) -> Vec<Result<ClosedTable>> {
+        vec![Ok(ClosedTable::default())]
     }
 }
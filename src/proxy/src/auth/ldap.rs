@@ -0,0 +1,295 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Authentication by binding to a ldap server.
+
+use std::{
+    hash::{BuildHasher, Hash, Hasher},
+    num::NonZeroUsize,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use clru::CLruCache;
+use generic_error::BoxError;
+use hash_ext::ahash::RandomState;
+use http::StatusCode;
+use ldap3::{dn_escape, LdapConn, LdapConnSettings};
+use serde::{Deserialize, Serialize};
+use snafu::{ensure, ResultExt};
+use time_ext::ReadableDuration;
+use tokio::runtime::{Handle, RuntimeFlavor};
+
+use crate::{
+    auth::{AuthProvider, Credential},
+    error::{ErrNoCause, ErrWithCause, InternalNoCause, Result},
+};
+
+/// Placeholder of the user name in the bind dn template.
+const USERNAME_PLACEHOLDER: &str = "{username}";
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    /// Url of the ldap server, e.g. `ldap://127.0.0.1:389`
+    pub url: String,
+    /// Template of the dn to bind, and `{username}` in it is replaced with the
+    /// escaped user name
+    pub bind_dn_template: String,
+    /// Whether to upgrade the connection with StartTLS
+    pub starttls: bool,
+    /// Timeout to connect to the ldap server
+    pub timeout: ReadableDuration,
+    /// How long a successful bind is cached, and the cache is disabled if it
+    /// is zero
+    pub cache_ttl: ReadableDuration,
+    /// Max number of the users whose binds are cached
+    pub cache_capacity: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            url: "ldap://127.0.0.1:389".to_string(),
+            bind_dn_template: "uid={username},ou=users,dc=example,dc=com".to_string(),
+            starttls: false,
+            timeout: ReadableDuration::secs(5),
+            cache_ttl: ReadableDuration::minutes(5),
+            cache_capacity: 1024,
+        }
+    }
+}
+
+/// Successful binds cached by the user name.
+///
+/// Only the digests of the passwords are kept, and they are hashed with a
+/// random seed of the process.
+#[derive(Debug)]
+struct BindCache {
+    ttl: Duration,
+    hasher: RandomState,
+    // user name -> (digest of the password, expiration)
+    binds: Mutex<CLruCache<String, (u64, Instant)>>,
+}
+
+impl BindCache {
+    fn new(ttl: Duration, capacity: NonZeroUsize) -> Self {
+        Self {
+            ttl,
+            hasher: RandomState::new(),
+            binds: Mutex::new(CLruCache::new(capacity)),
+        }
+    }
+
+    fn digest(&self, password: &str) -> u64 {
+        let mut hasher = self.hasher.build_hasher();
+        password.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn contains(&self, username: &str, password: &str) -> bool {
+        let digest = self.digest(password);
+        let mut binds = self.binds.lock().unwrap();
+        match binds.get(username) {
+            Some((cached, expiration)) if Instant::now() < *expiration => *cached == digest,
+            Some(_) => {
+                binds.pop(username);
+                false
+            }
+            None => false,
+        }
+    }
+
+    fn insert(&self, username: &str, password: &str) {
+        let digest = self.digest(password);
+        self.binds
+            .lock()
+            .unwrap()
+            .put(username.to_string(), (digest, Instant::now() + self.ttl));
+    }
+}
+
+/// Provider authenticating by a simple bind with the user's dn and password.
+///
+/// The bind is blocking, so it runs in place of a blocking thread if called
+/// in the multi-thread runtime, and the successful binds are cached to avoid
+/// binding on every request.
+#[derive(Debug)]
+pub struct LdapAuth {
+    config: Config,
+    cache: Option<BindCache>,
+}
+
+impl LdapAuth {
+    pub fn try_new(config: Config) -> Result<Self> {
+        ensure!(
+            config.bind_dn_template.contains(USERNAME_PLACEHOLDER),
+            InternalNoCause {
+                msg: format!(
+                    "bind_dn_template of ldap must contain {USERNAME_PLACEHOLDER}, template:{}",
+                    config.bind_dn_template
+                ),
+            }
+        );
+
+        let cache = match NonZeroUsize::new(config.cache_capacity) {
+            Some(capacity) if !config.cache_ttl.0.is_zero() => {
+                Some(BindCache::new(config.cache_ttl.0, capacity))
+            }
+            _ => None,
+        };
+
+        Ok(Self { config, cache })
+    }
+
+    fn bind_dn(&self, username: &str) -> String {
+        self.config
+            .bind_dn_template
+            .replace(USERNAME_PLACEHOLDER, &dn_escape(username))
+    }
+
+    /// Bind without blocking the other tasks of the runtime.
+    fn bind(&self, username: &str, password: &str) -> Result<()> {
+        match Handle::try_current() {
+            Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+                tokio::task::block_in_place(|| self.bind_blocking(username, password))
+            }
+            _ => self.bind_blocking(username, password),
+        }
+    }
+
+    fn bind_blocking(&self, username: &str, password: &str) -> Result<()> {
+        let settings = LdapConnSettings::new()
+            .set_conn_timeout(self.config.timeout.0)
+            .set_starttls(self.config.starttls);
+        let mut conn = LdapConn::with_settings(settings, &self.config.url)
+            .box_err()
+            .context(ErrWithCause {
+                code: StatusCode::INTERNAL_SERVER_ERROR,
+                msg: format!("failed to connect to ldap server, url:{}", self.config.url),
+            })?;
+
+        let bind_res = conn
+            .simple_bind(&self.bind_dn(username), password)
+            .and_then(|res| res.success());
+        // The connection is useless after the bind, so ignore the unbind error.
+        let _ = conn.unbind();
+        bind_res.box_err().context(ErrWithCause {
+            code: StatusCode::UNAUTHORIZED,
+            msg: format!("failed to bind to ldap server, user:{username}"),
+        })?;
+
+        Ok(())
+    }
+}
+
+impl AuthProvider for LdapAuth {
+    fn authenticate(&self, credential: &Credential) -> Result<String> {
+        let (username, password) = match credential {
+            Credential::Password { username, password } => (username, password),
+            Credential::Token(_) => {
+                return ErrNoCause {
+                    code: StatusCode::UNAUTHORIZED,
+                    msg: "token is not supported by ldap auth",
+                }
+                .fail();
+            }
+        };
+        // An empty password results in an unauthenticated bind, which always
+        // succeeds.
+        ensure!(
+            !username.is_empty() && !password.is_empty(),
+            ErrNoCause {
+                code: StatusCode::UNAUTHORIZED,
+                msg: "empty user or password",
+            }
+        );
+
+        if let Some(cache) = &self.cache {
+            if cache.contains(username, password) {
+                return Ok(username.clone());
+            }
+        }
+
+        self.bind(username, password)?;
+
+        if let Some(cache) = &self.cache {
+            cache.insert(username, password);
+        }
+
+        Ok(username.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bind_dn() {
+        let auth = LdapAuth::try_new(Config::default()).unwrap();
+        assert_eq!(
+            auth.bind_dn("alice"),
+            "uid=alice,ou=users,dc=example,dc=com"
+        );
+        assert_eq!(auth.bind_dn("a,b"), "uid=a\\,b,ou=users,dc=example,dc=com");
+
+        let config = Config {
+            bind_dn_template: "ou=users,dc=example,dc=com".to_string(),
+            ..Default::default()
+        };
+        assert!(LdapAuth::try_new(config).is_err());
+    }
+
+    #[test]
+    fn test_bind_cache() {
+        let cache = BindCache::new(Duration::from_millis(100), NonZeroUsize::new(1).unwrap());
+        assert!(!cache.contains("alice", "secret"));
+
+        cache.insert("alice", "secret");
+        assert!(cache.contains("alice", "secret"));
+        assert!(!cache.contains("alice", "wrong"));
+        assert!(!cache.contains("bob", "secret"));
+
+        // The least recently used user is evicted.
+        cache.insert("bob", "secret");
+        assert!(!cache.contains("alice", "secret"));
+        assert!(cache.contains("bob", "secret"));
+
+        std::thread::sleep(Duration::from_millis(150));
+        assert!(!cache.contains("bob", "secret"));
+    }
+
+    #[test]
+    fn test_disable_bind_cache() {
+        let config = Config {
+            cache_ttl: ReadableDuration::secs(0),
+            ..Default::default()
+        };
+        assert!(LdapAuth::try_new(config).unwrap().cache.is_none());
+
+        let config = Config {
+            cache_capacity: 0,
+            ..Default::default()
+        };
+        assert!(LdapAuth::try_new(config).unwrap().cache.is_none());
+        assert!(LdapAuth::try_new(Config::default())
+            .unwrap()
+            .cache
+            .is_some());
+    }
+}
@@ -15,8 +15,21 @@
 // specific language governing permissions and limitations
 // under the License.
 
+//! Authentication of the requests.
+//!
+//! The credential carried by a request is verified by an [AuthProvider], and
+//! the same [Authenticator] is shared by the http, grpc and mysql frontends.
+
+use std::{fmt::Debug, sync::Arc};
+
+use logger::{debug, warn};
 use serde::{Deserialize, Serialize};
+use tonic::service::Interceptor;
+
+use crate::error::Result;
 
+pub mod ldap;
+pub mod oidc;
 pub mod with_file;
 
 /// Header of authorization
@@ -27,11 +40,314 @@ pub enum AuthType {
     #[default]
     #[serde(rename = "file")]
     File,
+    #[serde(rename = "ldap")]
+    Ldap,
+    #[serde(rename = "oidc")]
+    Oidc,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
 pub struct Config {
     pub enable: bool,
     pub auth_type: AuthType,
+    /// The credential file used by the `file` auth type
     pub source: String,
+    #[serde(default)]
+    pub ldap: ldap::Config,
+    #[serde(default)]
+    pub oidc: oidc::Config,
+    /// The users allowed to manage the queries of other users, e.g. to kill
+    /// them.
+    #[serde(default)]
+    pub admin_users: Vec<String>,
+}
+
+/// Credential carried by a request.
+#[derive(Clone, PartialEq, Eq)]
+pub enum Credential {
+    /// User name and password, e.g. from the http basic auth.
+    Password { username: String, password: String },
+    /// Bearer token, e.g. the id token issued by an OIDC provider.
+    Token(String),
+}
+
+impl Credential {
+    /// Parse the credential from the value of the authorization header.
+    ///
+    /// Returns None if the scheme is not supported or the value is malformed.
+    pub fn from_authorization(value: &str) -> Option<Self> {
+        let (scheme, param) = value.trim().split_once(' ')?;
+        let param = param.trim();
+        if scheme.eq_ignore_ascii_case("Basic") {
+            let decoded = base64::decode(param).ok()?;
+            let decoded = String::from_utf8(decoded).ok()?;
+            let (username, password) = decoded.split_once(':')?;
+            Some(Credential::Password {
+                username: username.to_string(),
+                password: password.to_string(),
+            })
+        } else if scheme.eq_ignore_ascii_case("Bearer") {
+            Some(Credential::Token(param.to_string()))
+        } else {
+            None
+        }
+    }
+}
+
+// Don't expose the secret in the logs.
+impl Debug for Credential {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Credential::Password { username, .. } => f
+                .debug_struct("Password")
+                .field("username", username)
+                .finish_non_exhaustive(),
+            Credential::Token(_) => f.debug_tuple("Token").finish_non_exhaustive(),
+        }
+    }
+}
+
+/// Provider to verify the credential of the requests.
+pub trait AuthProvider: Debug + Send + Sync {
+    /// Verify the credential and return the name of the authenticated user.
+    fn authenticate(&self, credential: &Credential) -> Result<String>;
+}
+
+pub type AuthProviderRef = Arc<dyn AuthProvider>;
+
+/// Authenticator shared by all the frontends.
+///
+/// All the requests are allowed if no provider is set.
+#[derive(Debug, Clone, Default)]
+pub struct Authenticator {
+    provider: Option<AuthProviderRef>,
+    admin_users: Vec<String>,
+}
+
+impl Authenticator {
+    pub fn new(provider: AuthProviderRef) -> Self {
+        Self {
+            provider: Some(provider),
+            admin_users: Vec::new(),
+        }
+    }
+
+    pub fn with_admin_users(mut self, admin_users: Vec<String>) -> Self {
+        self.admin_users = admin_users;
+        self
+    }
+
+    /// Build the authenticator from the config.
+    pub fn try_new(config: &Config) -> Result<Self> {
+        if !config.enable {
+            return Ok(Self::default());
+        }
+
+        let provider: AuthProviderRef = match config.auth_type {
+            AuthType::File => {
+                let mut provider = with_file::AuthWithFile::new(config.source.clone());
+                provider.load_credential()?;
+                Arc::new(provider)
+            }
+            AuthType::Ldap => Arc::new(ldap::LdapAuth::try_new(config.ldap.clone())?),
+            AuthType::Oidc => Arc::new(oidc::OidcAuth::try_new(&config.oidc)?),
+        };
+
+        Ok(Self::new(provider).with_admin_users(config.admin_users.clone()))
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.provider.is_some()
+    }
+
+    /// Whether the authenticated `user` is an administrator.
+    ///
+    /// All the users are administrators if the authentication is disabled, as
+    /// their names are not verified.
+    pub fn is_admin(&self, user: &str) -> bool {
+        !self.is_enabled() || self.admin_users.iter().any(|v| v == user)
+    }
+
+    /// Check the value of the authorization header.
+    pub fn identify(&self, authorization: Option<String>) -> bool {
+        self.identify_user(authorization.as_deref()).is_some()
+    }
+
+    /// Check the value of the authorization header and return the name of the
+    /// user, None is returned if the request is not authenticated.
+    ///
+    /// If the authentication is disabled, the user name carried by the header
+    /// is trusted, and it is empty if there is no such name.
+    pub fn identify_user(&self, authorization: Option<&str>) -> Option<String> {
+        let credential = authorization.and_then(Credential::from_authorization);
+        if !self.is_enabled() {
+            return match credential {
+                Some(Credential::Password { username, .. }) => Some(username),
+                _ => Some(String::new()),
+            };
+        }
+
+        self.authenticate(&credential?)
+    }
+
+    /// Check the user name and password and return the name of the user
+    /// verified by the provider, which may differ from `username`, e.g. the
+    /// OIDC provider ignores it and takes the subject of the token.
+    ///
+    /// If the authentication is disabled, the `username` is trusted.
+    pub fn identify_password(&self, username: &str, password: &str) -> Option<String> {
+        if !self.is_enabled() {
+            return Some(username.to_string());
+        }
+
+        let credential = Credential::Password {
+            username: username.to_string(),
+            password: password.to_string(),
+        };
+        self.authenticate(&credential)
+    }
+
+    fn authenticate(&self, credential: &Credential) -> Option<String> {
+        let provider = self.provider.as_ref()?;
+        match provider.authenticate(credential) {
+            Ok(user) => {
+                debug!("Request is authenticated, user:{user}");
+                Some(user)
+            }
+            Err(e) => {
+                warn!("Failed to authenticate request, credential:{credential:?}, err:{e}");
+                None
+            }
+        }
+    }
+}
+
+pub fn get_authorization<T>(req: &tonic::Request<T>) -> Option<String> {
+    req.metadata()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok().map(String::from))
+}
+
+impl Interceptor for Authenticator {
+    fn call(
+        &mut self,
+        request: tonic::Request<()>,
+    ) -> std::result::Result<tonic::Request<()>, tonic::Status> {
+        let authorization = get_authorization(&request);
+        if self.identify(authorization) {
+            Ok(request)
+        } else {
+            Err(tonic::Status::unauthenticated("unauthenticated"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::StatusCode;
+
+    use super::*;
+    use crate::error::ErrNoCause;
+
+    #[test]
+    fn test_parse_credential() {
+        let basic = format!("Basic {}", base64::encode("user:pass:word"));
+        assert_eq!(
+            Credential::from_authorization(&basic),
+            Some(Credential::Password {
+                username: "user".to_string(),
+                password: "pass:word".to_string(),
+            })
+        );
+        assert_eq!(
+            Credential::from_authorization("bearer xxx.yyy.zzz"),
+            Some(Credential::Token("xxx.yyy.zzz".to_string()))
+        );
+
+        for invalid in ["Basic !!!", "Basic", "Digest xxx", ""] {
+            assert!(Credential::from_authorization(invalid).is_none());
+        }
+    }
+
+    #[test]
+    fn test_disabled_authenticator() {
+        let authenticator = Authenticator::default();
+        assert!(authenticator.identify(None));
+        assert_eq!(
+            Some("user".to_string()),
+            authenticator.identify_password("user", "pass")
+        );
+
+        let basic = format!("Basic {}", base64::encode("user:pass"));
+        assert_eq!(
+            Some("user".to_string()),
+            authenticator.identify_user(Some(&basic))
+        );
+        assert_eq!(Some(String::new()), authenticator.identify_user(None));
+        assert!(authenticator.is_admin("user"));
+    }
+
+    #[derive(Debug)]
+    struct MockProvider;
+
+    impl AuthProvider for MockProvider {
+        fn authenticate(&self, credential: &Credential) -> Result<String> {
+            match credential {
+                Credential::Password { username, .. } => Ok(username.clone()),
+                Credential::Token(_) => ErrNoCause {
+                    code: StatusCode::UNAUTHORIZED,
+                    msg: "token is not supported",
+                }
+                .fail(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_admin_users() {
+        let authenticator =
+            Authenticator::new(Arc::new(MockProvider)).with_admin_users(vec!["root".to_string()]);
+        let basic = format!("Basic {}", base64::encode("user:pass"));
+        assert_eq!(
+            Some("user".to_string()),
+            authenticator.identify_user(Some(&basic))
+        );
+        assert_eq!(None, authenticator.identify_user(Some("Bearer xxx")));
+        assert_eq!(None, authenticator.identify_user(None));
+
+        assert!(authenticator.is_admin("root"));
+        assert!(!authenticator.is_admin("user"));
+    }
+
+    /// Provider verifying the password only, like the OIDC provider.
+    #[derive(Debug)]
+    struct TokenProvider;
+
+    impl AuthProvider for TokenProvider {
+        fn authenticate(&self, credential: &Credential) -> Result<String> {
+            match credential {
+                Credential::Password { password, .. } if password == "token" => {
+                    Ok("subject".to_string())
+                }
+                _ => ErrNoCause {
+                    code: StatusCode::UNAUTHORIZED,
+                    msg: "invalid token",
+                }
+                .fail(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_identify_password_by_provider() {
+        let authenticator =
+            Authenticator::new(Arc::new(TokenProvider)).with_admin_users(vec!["root".to_string()]);
+
+        // The claimed name is replaced by the verified one.
+        assert_eq!(
+            Some("subject".to_string()),
+            authenticator.identify_password("root", "token")
+        );
+        assert_eq!(None, authenticator.identify_password("root", "invalid"));
+    }
 }
@@ -0,0 +1,252 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Authentication by validating the JWT issued by an OIDC provider.
+
+use std::{collections::HashMap, fs, str::FromStr};
+
+use generic_error::BoxError;
+use http::StatusCode;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use snafu::{ensure, OptionExt, ResultExt};
+
+use crate::{
+    auth::{AuthProvider, Credential},
+    error::{ErrNoCause, ErrWithCause, Internal, InternalNoCause, Result},
+};
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    /// The expected issuer (`iss` claim) of the token, and it won't be checked
+    /// if empty
+    pub issuer: String,
+    /// The accepted audiences (`aud` claim) of the token
+    pub audience: Vec<String>,
+    /// The signing algorithm of the token, e.g. RS256, ES256 or HS256
+    pub algorithm: String,
+    /// Path of the PEM encoded public key for the RSA, EC and EdDSA algorithms
+    pub public_key_path: String,
+    /// The shared secret for the HMAC algorithms
+    pub secret: String,
+    /// The claim used as the user name
+    pub username_claim: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            issuer: String::new(),
+            audience: Vec::new(),
+            algorithm: "RS256".to_string(),
+            public_key_path: String::new(),
+            secret: String::new(),
+            username_claim: "sub".to_string(),
+        }
+    }
+}
+
+/// Provider validating the signature, expiration, audience and issuer of the
+/// bearer token.
+///
+/// The token can also be sent as the password if the frontend only supports
+/// password, e.g. mysql.
+///
+/// NOTE: the key is loaded at startup, and fetching keys from the JWKS
+/// endpoint of the provider is not supported.
+pub struct OidcAuth {
+    algorithm: Algorithm,
+    key: DecodingKey,
+    validation: Validation,
+    username_claim: String,
+}
+
+impl OidcAuth {
+    pub fn try_new(config: &Config) -> Result<Self> {
+        let algorithm = Algorithm::from_str(&config.algorithm)
+            .box_err()
+            .context(Internal {
+                msg: format!("invalid oidc algorithm, algorithm:{}", config.algorithm),
+            })?;
+        ensure!(
+            !config.audience.is_empty(),
+            InternalNoCause {
+                msg: "audience of oidc is required",
+            }
+        );
+
+        let key = Self::load_key(algorithm, config)?;
+        let mut validation = Validation::new(algorithm);
+        validation.set_audience(&config.audience);
+        if config.issuer.is_empty() {
+            validation.set_required_spec_claims(&["exp", "aud"]);
+        } else {
+            validation.set_issuer(&[&config.issuer]);
+            validation.set_required_spec_claims(&["exp", "aud", "iss"]);
+        }
+
+        Ok(Self {
+            algorithm,
+            key,
+            validation,
+            username_claim: config.username_claim.clone(),
+        })
+    }
+
+    fn load_key(algorithm: Algorithm, config: &Config) -> Result<DecodingKey> {
+        if let Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 = algorithm {
+            ensure!(
+                !config.secret.is_empty(),
+                InternalNoCause {
+                    msg: format!("secret of oidc is required by algorithm:{algorithm:?}"),
+                }
+            );
+            return Ok(DecodingKey::from_secret(config.secret.as_bytes()));
+        }
+
+        let pem = fs::read(&config.public_key_path)
+            .box_err()
+            .context(Internal {
+                msg: format!(
+                    "failed to read oidc public key, path:{}",
+                    config.public_key_path
+                ),
+            })?;
+        let key = match algorithm {
+            Algorithm::ES256 | Algorithm::ES384 => DecodingKey::from_ec_pem(&pem),
+            Algorithm::EdDSA => DecodingKey::from_ed_pem(&pem),
+            _ => DecodingKey::from_rsa_pem(&pem),
+        };
+
+        key.box_err().context(Internal {
+            msg: format!(
+                "invalid oidc public key, path:{}, algorithm:{algorithm:?}",
+                config.public_key_path
+            ),
+        })
+    }
+}
+
+impl std::fmt::Debug for OidcAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OidcAuth")
+            .field("algorithm", &self.algorithm)
+            .field("audience", &self.validation.aud)
+            .field("issuer", &self.validation.iss)
+            .field("username_claim", &self.username_claim)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AuthProvider for OidcAuth {
+    fn authenticate(&self, credential: &Credential) -> Result<String> {
+        let token = match credential {
+            Credential::Token(token) => token,
+            Credential::Password { password, .. } => password,
+        };
+
+        let token_data =
+            jsonwebtoken::decode::<HashMap<String, Value>>(token, &self.key, &self.validation)
+                .box_err()
+                .context(ErrWithCause {
+                    code: StatusCode::UNAUTHORIZED,
+                    msg: "invalid oidc token",
+                })?;
+
+        let username = token_data
+            .claims
+            .get(&self.username_claim)
+            .and_then(Value::as_str)
+            .with_context(|| ErrNoCause {
+                code: StatusCode::UNAUTHORIZED,
+                msg: format!("missing claim in oidc token, claim:{}", self.username_claim),
+            })?;
+
+        Ok(username.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use jsonwebtoken::{EncodingKey, Header};
+    use serde_json::json;
+
+    use super::*;
+
+    const SECRET: &str = "test-secret";
+
+    fn new_auth() -> OidcAuth {
+        let config = Config {
+            issuer: "https://issuer.example.com".to_string(),
+            audience: vec!["horaedb".to_string()],
+            algorithm: "HS256".to_string(),
+            secret: SECRET.to_string(),
+            ..Default::default()
+        };
+        OidcAuth::try_new(&config).unwrap()
+    }
+
+    fn new_token(aud: &str, exp_offset_secs: i64) -> String {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        let claims = json!({
+            "sub": "alice",
+            "iss": "https://issuer.example.com",
+            "aud": aud,
+            "exp": now + exp_offset_secs,
+        });
+        jsonwebtoken::encode(
+            &Header::new(Algorithm::HS256),
+            &claims,
+            &EncodingKey::from_secret(SECRET.as_bytes()),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_validate_token() {
+        let auth = new_auth();
+
+        let token = new_token("horaedb", 600);
+        let user = auth
+            .authenticate(&Credential::Token(token.clone()))
+            .unwrap();
+        assert_eq!(user, "alice");
+        let credential = Credential::Password {
+            username: String::new(),
+            password: token,
+        };
+        assert_eq!(auth.authenticate(&credential).unwrap(), "alice");
+
+        let wrong_audience = new_token("others", 600);
+        assert!(auth
+            .authenticate(&Credential::Token(wrong_audience))
+            .is_err());
+
+        let expired = new_token("horaedb", -600);
+        assert!(auth.authenticate(&Credential::Token(expired)).is_err());
+
+        let malformed = Credential::Token("xxx.yyy.zzz".to_string());
+        assert!(auth.authenticate(&malformed).is_err());
+    }
+}
@@ -23,26 +23,25 @@ use std::{
 };
 
 use generic_error::BoxError;
+use http::StatusCode;
 use snafu::{OptionExt, ResultExt};
-use tonic::service::Interceptor;
 
 use crate::{
-    auth::AUTHORIZATION,
-    error::{Internal, InternalNoCause, Result},
+    auth::{AuthProvider, Credential},
+    error::{ErrNoCause, Internal, InternalNoCause, Result},
 };
 
+/// Provider authenticating with the static credentials in a file.
 #[derive(Debug, Clone, Default)]
 pub struct AuthWithFile {
-    enable: bool,
     file_path: String,
     // name -> password
     users: HashMap<String, String>,
 }
 
 impl AuthWithFile {
-    pub fn new(enable: bool, file_path: String) -> Self {
+    pub fn new(file_path: String) -> Self {
         Self {
-            enable,
             file_path,
             users: HashMap::new(),
         }
@@ -50,10 +49,6 @@ impl AuthWithFile {
 
     // Load a csv format config
     pub fn load_credential(&mut self) -> Result<()> {
-        if !self.enable {
-            return Ok(());
-        }
-
         let path = Path::new(&self.file_path);
         if !path.exists() {
             return InternalNoCause {
@@ -80,57 +75,34 @@ impl AuthWithFile {
 
         Ok(())
     }
-
-    // TODO: currently we only support basic auth
-    // This function should return Result
-    pub fn identify(&self, input: Option<String>) -> bool {
-        if !self.enable {
-            return true;
-        }
-
-        let input = match input {
-            Some(v) => v,
-            None => return false,
-        };
-        let input = match input.split_once("Basic ") {
-            Some((_, encoded)) => match base64::decode(encoded) {
-                Ok(v) => v,
-                Err(_e) => return false,
-            },
-            None => return false,
-        };
-        let input = match std::str::from_utf8(&input) {
-            Ok(v) => v,
-            Err(_e) => return false,
-        };
-        match input.split_once(':') {
-            Some((user, pass)) => self
-                .users
-                .get(user)
-                .map(|expected| expected == pass)
-                .unwrap_or_default(),
-            None => false,
-        }
-    }
 }
 
-pub fn get_authorization<T>(req: &tonic::Request<T>) -> Option<String> {
-    req.metadata()
-        .get(AUTHORIZATION)
-        .and_then(|value| value.to_str().ok().map(String::from))
-}
+impl AuthProvider for AuthWithFile {
+    fn authenticate(&self, credential: &Credential) -> Result<String> {
+        let (username, password) = match credential {
+            Credential::Password { username, password } => (username, password),
+            Credential::Token(_) => {
+                return ErrNoCause {
+                    code: StatusCode::UNAUTHORIZED,
+                    msg: "token is not supported by file auth",
+                }
+                .fail();
+            }
+        };
 
-impl Interceptor for AuthWithFile {
-    fn call(
-        &mut self,
-        request: tonic::Request<()>,
-    ) -> std::result::Result<tonic::Request<()>, tonic::Status> {
-        // TODO: extract username from request
-        let authorization = get_authorization(&request);
-        if self.identify(authorization) {
-            Ok(request)
-        } else {
-            Err(tonic::Status::unauthenticated("unauthenticated"))
+        let matched = self
+            .users
+            .get(username)
+            .map(|expected| expected == password)
+            .unwrap_or_default();
+        if !matched {
+            return ErrNoCause {
+                code: StatusCode::UNAUTHORIZED,
+                msg: format!("invalid user or password, user:{username}"),
+            }
+            .fail();
         }
+
+        Ok(username.clone())
     }
 }
@@ -23,6 +23,8 @@ use common_types::request_id::RequestId;
 use macros::define_result;
 use snafu::{ensure, Backtrace, Snafu};
 
+use crate::trace::RequestTraceRef;
+
 #[allow(clippy::enum_variant_names)]
 #[derive(Debug, Snafu)]
 pub enum Error {
@@ -58,6 +60,12 @@ pub struct RequestContext {
     pub request_id: RequestId,
     /// authorization
     pub authorization: Option<String>,
+    /// Name of the authenticated user, empty if it is unknown
+    pub user: String,
+    /// Trace sample to return to the client, only set if it's requested
+    pub trace: Option<RequestTraceRef>,
+    /// Whether to emit all the debug logs of the request
+    pub verbose: bool,
 }
 
 impl RequestContext {
@@ -72,6 +80,9 @@ pub struct Builder {
     schema: String,
     timeout: Option<Duration>,
     authorization: Option<String>,
+    user: String,
+    trace: Option<RequestTraceRef>,
+    verbose: bool,
 }
 
 impl Builder {
@@ -95,6 +106,21 @@ impl Builder {
         self
     }
 
+    pub fn user(mut self, user: String) -> Self {
+        self.user = user;
+        self
+    }
+
+    pub fn trace(mut self, trace: Option<RequestTraceRef>) -> Self {
+        self.trace = trace;
+        self
+    }
+
+    pub fn verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
     pub fn build(self) -> Result<RequestContext> {
         ensure!(!self.catalog.is_empty(), MissingCatalog);
         ensure!(!self.schema.is_empty(), MissingSchema);
@@ -105,6 +131,9 @@ impl Builder {
             timeout: self.timeout,
             request_id: RequestId::next_id(),
             authorization: self.authorization,
+            user: self.user,
+            trace: self.trace,
+            verbose: self.verbose,
         })
     }
 }
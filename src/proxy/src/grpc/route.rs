@@ -18,14 +18,21 @@
 use horaedbproto::storage::{RouteRequest as RouteRequestPb, RouteResponse};
 use router::RouteRequest;
 
-use crate::{error, metrics::GRPC_HANDLER_COUNTER_VEC, Context, Proxy};
+use crate::{error, metrics::GRPC_HANDLER_COUNTER_VEC, shard_epoch::ShardEpochs, Context, Proxy};
 
 impl Proxy {
-    pub async fn handle_route(&self, _ctx: Context, req: RouteRequestPb) -> RouteResponse {
+    /// Handle the route request, and the shard epochs of the routed tables are
+    /// returned as well, which are not part of the [RouteResponse].
+    pub async fn handle_route(
+        &self,
+        _ctx: Context,
+        req: RouteRequestPb,
+    ) -> (RouteResponse, ShardEpochs) {
         let request = RouteRequest::new(req, true);
-        let routes = self.route(request).await;
+        let routes = self.route_with_epoch(request).await;
 
         let mut resp = RouteResponse::default();
+        let mut epochs = ShardEpochs::default();
         match routes {
             Err(e) => {
                 GRPC_HANDLER_COUNTER_VEC.route_failed.inc();
@@ -37,9 +44,13 @@ impl Proxy {
                 GRPC_HANDLER_COUNTER_VEC.route_succeeded.inc();
 
                 resp.header = Some(error::build_ok_header());
-                resp.routes = v;
+                epochs = v
+                    .iter()
+                    .filter_map(|v| v.epoch.map(|epoch| (v.route.table.clone(), epoch)))
+                    .collect();
+                resp.routes = v.into_iter().map(|v| v.route).collect();
             }
         }
-        resp
+        (resp, epochs)
     }
 }
@@ -90,8 +90,10 @@ impl Proxy {
         let req_context = req.context.as_ref().unwrap();
         let schema = &req_context.database;
 
+        // Verbose requests are not deduplicated so that each of them is executed
+        // and logged on its own.
         let result = match self.request_notifiers.clone() {
-            Some(request_notifiers) => {
+            Some(request_notifiers) if !ctx.is_verbose() => {
                 self.dedup_handle_sql(
                     ctx,
                     schema,
@@ -101,7 +103,7 @@ impl Proxy {
                 )
                 .await?
             }
-            None => {
+            _ => {
                 self.handle_sql(
                     ctx,
                     schema,
@@ -15,9 +15,38 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use std::collections::BTreeSet;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    time::Duration,
+};
 
-use crate::{handlers::prelude::*, limiter::BlockRule};
+use common_types::{
+    table::ShardId,
+    time::{TimeRange, Timestamp},
+    SequenceNumber,
+};
+use generic_error::BoxError;
+use snafu::{OptionExt, ResultExt};
+use table_engine::{
+    engine::{OpenShardCheckReport, OpenShardRequest, ScanWalRequest, ScanWalResult, TableDef},
+    table::{SchemaId, TableId},
+    ANALYTIC_ENGINE_TYPE,
+};
+use time_ext::ReadableDuration;
+
+use crate::{
+    auth::Credential,
+    handlers::{
+        error::{CheckOpenShard, InvalidScanWalRequest, ScanWal},
+        prelude::*,
+    },
+    limiter::{BlacklistAuditRecord, BlacklistEntry, BlacklistTarget, BlockRule, Criticality},
+};
+
+const DEFAULT_BLACKLIST_TTL: Duration = Duration::from_secs(10 * 60);
+const DEFAULT_SCAN_WAL_LIMIT: usize = 100;
+const MAX_SCAN_WAL_LIMIT: usize = 1000;
+const DEFAULT_MAX_PREVIEW_ROWS: usize = 5;
 
 #[derive(Debug, Deserialize)]
 pub enum Operation {
@@ -77,3 +106,234 @@ pub async fn handle_block(
         block_rules: limiter.get_block_rules().into_iter().collect(),
     })
 }
+
+#[derive(Debug, Deserialize)]
+pub struct TableCriticalityRequest {
+    operation: Operation,
+    /// The criticality is ignored when the operation is `Remove`
+    table_criticality: HashMap<String, Criticality>,
+}
+
+/// Tag the tables with their criticality, the writes to the less critical
+/// tables are shed first under pressure.
+pub async fn handle_table_criticality(
+    _ctx: RequestContext,
+    instance: InstanceRef,
+    request: TableCriticalityRequest,
+) -> Result<BTreeMap<String, Criticality>> {
+    let limiter = &instance.limiter;
+    match request.operation {
+        Operation::Add => limiter.add_table_criticality(request.table_criticality),
+        Operation::Set => limiter.set_table_criticality(request.table_criticality),
+        Operation::Remove => {
+            limiter.remove_table_criticality(request.table_criticality.into_keys())
+        }
+    }
+
+    Ok(limiter.get_table_criticality().into_iter().collect())
+}
+
+#[derive(Debug, Deserialize)]
+pub enum BlacklistOperation {
+    Add,
+    Remove,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BlacklistRequest {
+    operation: BlacklistOperation,
+    targets: Vec<BlacklistTarget>,
+    /// How long the targets are blacklisted, only used when adding.
+    #[serde(default = "default_blacklist_ttl")]
+    ttl: ReadableDuration,
+    #[serde(default)]
+    reason: String,
+}
+
+fn default_blacklist_ttl() -> ReadableDuration {
+    ReadableDuration(DEFAULT_BLACKLIST_TTL)
+}
+
+#[derive(Serialize)]
+pub struct BlacklistResponse {
+    entries: Vec<BlacklistEntry>,
+    audit_records: Vec<BlacklistAuditRecord>,
+}
+
+/// The user name in the authorization is recorded as the operator in the audit
+/// records.
+fn operator(ctx: &RequestContext) -> String {
+    match ctx
+        .authorization
+        .as_deref()
+        .and_then(Credential::from_authorization)
+    {
+        Some(Credential::Password { username, .. }) => username,
+        _ => "unknown".to_string(),
+    }
+}
+
+pub async fn handle_blacklist(
+    ctx: RequestContext,
+    instance: InstanceRef,
+    request: BlacklistRequest,
+) -> Result<BlacklistResponse> {
+    let limiter = &instance.limiter;
+    let operator = operator(&ctx);
+    match request.operation {
+        BlacklistOperation::Add => {
+            limiter.add_blacklist(request.targets, request.ttl.0, request.reason, &operator)
+        }
+        BlacklistOperation::Remove => limiter.remove_blacklist(&request.targets, &operator),
+    }
+
+    handle_get_blacklist(ctx, instance).await
+}
+
+pub async fn handle_get_blacklist(
+    _ctx: RequestContext,
+    instance: InstanceRef,
+) -> Result<BlacklistResponse> {
+    let limiter = &instance.limiter;
+    let mut entries = limiter.get_blacklist();
+    entries.sort_unstable_by(|a, b| a.target.cmp(&b.target));
+
+    Ok(BlacklistResponse {
+        entries,
+        audit_records: limiter.get_blacklist_audit_records(),
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CheckOpenShardTable {
+    schema_name: String,
+    schema_id: u32,
+    id: u64,
+    name: String,
+}
+
+/// Tables of the shard to open, which are provided by horaemeta.
+#[derive(Debug, Deserialize)]
+pub struct CheckOpenShardRequest {
+    shard_id: ShardId,
+    tables: Vec<CheckOpenShardTable>,
+}
+
+/// Check whether the shard could be opened by this node before it's moved here,
+/// nothing is opened by the check.
+pub async fn handle_check_open_shard(
+    ctx: RequestContext,
+    instance: InstanceRef,
+    request: CheckOpenShardRequest,
+) -> Result<OpenShardCheckReport> {
+    let shard_id = request.shard_id;
+    let table_defs = request
+        .tables
+        .into_iter()
+        .map(|table| TableDef {
+            catalog_name: ctx.catalog.clone(),
+            schema_name: table.schema_name,
+            schema_id: SchemaId::from_u32(table.schema_id),
+            id: TableId::new(table.id),
+            name: table.name,
+        })
+        .collect();
+    let request = OpenShardRequest {
+        shard_id,
+        table_defs,
+        engine: ANALYTIC_ENGINE_TYPE.to_string(),
+    };
+
+    instance
+        .table_engine
+        .check_open_shard(request)
+        .await
+        .context(CheckOpenShard { shard_id })
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct ScanWalHttpRequest {
+    table: String,
+    start_sequence: SequenceNumber,
+    end_sequence: Option<SequenceNumber>,
+    /// Inclusive start of the time range in milliseconds
+    start_time: Option<i64>,
+    /// Exclusive end of the time range in milliseconds
+    end_time: Option<i64>,
+    limit: usize,
+    max_preview_rows: usize,
+}
+
+impl Default for ScanWalHttpRequest {
+    fn default() -> Self {
+        Self {
+            table: String::new(),
+            start_sequence: 0,
+            end_sequence: None,
+            start_time: None,
+            end_time: None,
+            limit: DEFAULT_SCAN_WAL_LIMIT,
+            max_preview_rows: DEFAULT_MAX_PREVIEW_ROWS,
+        }
+    }
+}
+
+/// Scan the wal of a table opened by this node, the `next_sequence` of the
+/// result can be used as the `start_sequence` to fetch the next page.
+pub async fn handle_scan_wal(
+    ctx: RequestContext,
+    instance: InstanceRef,
+    request: ScanWalHttpRequest,
+) -> Result<ScanWalResult> {
+    let table = request.table;
+    let catalog = instance
+        .catalog_manager
+        .catalog_by_name(&ctx.catalog)
+        .box_err()
+        .context(ScanWal { table: &table })?
+        .with_context(|| InvalidScanWalRequest {
+            table: &table,
+            msg: format!("catalog:{} not found", ctx.catalog),
+        })?;
+    let schema = catalog
+        .schema_by_name(&ctx.schema)
+        .box_err()
+        .context(ScanWal { table: &table })?
+        .with_context(|| InvalidScanWalRequest {
+            table: &table,
+            msg: format!("schema:{} not found", ctx.schema),
+        })?;
+
+    let time_range = match (request.start_time, request.end_time) {
+        (None, None) => None,
+        (start, end) => {
+            let time_range = TimeRange::new(
+                Timestamp::new(start.unwrap_or(i64::MIN)),
+                Timestamp::new(end.unwrap_or(i64::MAX)),
+            )
+            .with_context(|| InvalidScanWalRequest {
+                table: &table,
+                msg: format!("invalid time range, start:{start:?}, end:{end:?}"),
+            })?;
+            Some(time_range)
+        }
+    };
+    let request = ScanWalRequest {
+        engine: ANALYTIC_ENGINE_TYPE.to_string(),
+        schema_id: schema.id(),
+        table_name: table.clone(),
+        start_sequence: request.start_sequence,
+        end_sequence: request.end_sequence,
+        time_range,
+        limit: request.limit.min(MAX_SCAN_WAL_LIMIT),
+        max_preview_rows: request.max_preview_rows,
+    };
+
+    instance
+        .table_engine
+        .scan_wal(request)
+        .await
+        .box_err()
+        .context(ScanWal { table })
+}
@@ -17,6 +17,8 @@
 
 //! Error of handlers
 
+use common_types::table::ShardId;
+use generic_error::GenericError;
 use macros::define_result;
 use snafu::{Backtrace, Snafu};
 use warp::reject::Reject;
@@ -86,6 +88,27 @@ pub enum Error {
         source: tokio::time::error::Elapsed,
         backtrace: Backtrace,
     },
+
+    #[snafu(display("Failed to check opening shard, shard_id:{}, err:{}", shard_id, source))]
+    CheckOpenShard {
+        shard_id: ShardId,
+        source: table_engine::engine::Error,
+    },
+
+    #[snafu(display("Failed to scan wal, table:{}, err:{}", table, source))]
+    ScanWal { table: String, source: GenericError },
+
+    #[snafu(display(
+        "Invalid request to scan wal, table:{}, msg:{}.\nBacktrace:\n{}",
+        table,
+        msg,
+        backtrace
+    ))]
+    InvalidScanWalRequest {
+        table: String,
+        msg: String,
+        backtrace: Backtrace,
+    },
 }
 
 define_result!(Error);
@@ -0,0 +1,319 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Reference vectors of the wire encodings, served to the authors of the client
+//! SDKs in other languages to validate their serialization against a live
+//! node.
+//!
+//! Every value vector carries the protobuf encoding of the `Value` message in
+//! base64, the client can compare its own encoding with it, or post its
+//! encoding to the server for verification.
+
+use common_types::time::TimestampPrecision;
+use horaedbproto::storage::{value, Value};
+use http::StatusCode;
+use prost::Message;
+use query_frontend::planner;
+use serde::{Deserialize, Serialize};
+use snafu::OptionExt;
+
+use crate::error::{ErrNoCause, Result};
+
+const PRECISIONS: [TimestampPrecision; 4] = [
+    TimestampPrecision::Second,
+    TimestampPrecision::Millisecond,
+    TimestampPrecision::Microsecond,
+    TimestampPrecision::Nanosecond,
+];
+
+#[derive(Debug, Serialize)]
+pub struct ValueVector {
+    pub name: &'static str,
+    /// Kind of the column the value is written into.
+    pub datum_kind: String,
+    /// Readable form of the value, e.g. `NaN`, `-0.0`.
+    pub value: String,
+    /// Bit pattern of the float values in hex, NaNs share the same payload.
+    pub float_bits: Option<String>,
+    /// Protobuf encoding of the `Value` message in base64.
+    pub encoded: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TimestampVector {
+    pub millis: i64,
+    pub precision: &'static str,
+    /// Value written into the timestamp column of the precision, absent if
+    /// it overflows i64 and the write is rejected.
+    pub value: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TestVectors {
+    pub values: Vec<ValueVector>,
+    pub timestamps: Vec<TimestampVector>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VerifyRequest {
+    /// Name of the value vector to verify against.
+    pub name: String,
+    /// Protobuf encoding of the `Value` message produced by the client in
+    /// base64.
+    pub encoded: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VerifyResponse {
+    pub name: String,
+    pub passed: bool,
+    /// Reason of the failure.
+    pub msg: Option<String>,
+}
+
+fn reference_values() -> Vec<(&'static str, value::Value)> {
+    use value::Value::*;
+
+    vec![
+        ("float64_zero", Float64Value(0.0)),
+        ("float64_negative_zero", Float64Value(-0.0)),
+        ("float64_nan", Float64Value(f64::NAN)),
+        ("float64_positive_infinity", Float64Value(f64::INFINITY)),
+        ("float64_negative_infinity", Float64Value(f64::NEG_INFINITY)),
+        ("float64_max", Float64Value(f64::MAX)),
+        ("float64_min_positive", Float64Value(f64::MIN_POSITIVE)),
+        ("float64_subnormal", Float64Value(f64::from_bits(1))),
+        ("float32_negative_zero", Float32Value(-0.0)),
+        ("float32_nan", Float32Value(f32::NAN)),
+        ("float32_positive_infinity", Float32Value(f32::INFINITY)),
+        ("float32_subnormal", Float32Value(f32::from_bits(1))),
+        ("int64_min", Int64Value(i64::MIN)),
+        ("int64_max", Int64Value(i64::MAX)),
+        ("int64_negative_one", Int64Value(-1)),
+        ("int32_min", Int32Value(i32::MIN)),
+        ("int16_min", Int16Value(i16::MIN as i32)),
+        ("int8_min", Int8Value(i8::MIN as i32)),
+        ("uint64_max", Uint64Value(u64::MAX)),
+        ("uint32_max", Uint32Value(u32::MAX)),
+        ("uint16_max", Uint16Value(u16::MAX as u32)),
+        ("uint8_max", Uint8Value(u8::MAX as u32)),
+        ("bool_true", BoolValue(true)),
+        ("bool_false", BoolValue(false)),
+        ("string_empty", StringValue(String::new())),
+        ("string_unicode", StringValue("时序 db ✓ 🚀".to_string())),
+        ("varbinary_empty", VarbinaryValue(Vec::new())),
+        (
+            "varbinary_bytes",
+            VarbinaryValue(vec![0x00, 0x01, 0x7f, 0x80, 0xff]),
+        ),
+        ("timestamp_epoch", TimestampValue(0)),
+        ("timestamp_before_epoch", TimestampValue(-1)),
+        ("timestamp_millis", TimestampValue(1_700_000_000_123)),
+    ]
+}
+
+fn readable_value(v: &value::Value) -> String {
+    match v {
+        value::Value::Float64Value(v) => format!("{v:?}"),
+        value::Value::Float32Value(v) => format!("{v:?}"),
+        value::Value::StringValue(v) => v.clone(),
+        value::Value::Int64Value(v) | value::Value::TimestampValue(v) => v.to_string(),
+        value::Value::Int32Value(v) | value::Value::Int16Value(v) | value::Value::Int8Value(v) => {
+            v.to_string()
+        }
+        value::Value::Uint64Value(v) => v.to_string(),
+        value::Value::Uint32Value(v)
+        | value::Value::Uint16Value(v)
+        | value::Value::Uint8Value(v) => v.to_string(),
+        value::Value::BoolValue(v) => v.to_string(),
+        value::Value::VarbinaryValue(v) => format!("{v:?}"),
+    }
+}
+
+fn float_bits(v: &value::Value) -> Option<String> {
+    match v {
+        value::Value::Float64Value(v) => Some(format!("{:#018x}", v.to_bits())),
+        value::Value::Float32Value(v) => Some(format!("{:#010x}", v.to_bits())),
+        _ => None,
+    }
+}
+
+fn encode(v: value::Value) -> Vec<u8> {
+    Value { value: Some(v) }.encode_to_vec()
+}
+
+/// Any NaN matches the NaN, and the zeros of different signs don't match.
+fn value_matches(expected: &value::Value, actual: &value::Value) -> bool {
+    match (expected, actual) {
+        (value::Value::Float64Value(a), value::Value::Float64Value(b)) => {
+            a.to_bits() == b.to_bits() || (a.is_nan() && b.is_nan())
+        }
+        (value::Value::Float32Value(a), value::Value::Float32Value(b)) => {
+            a.to_bits() == b.to_bits() || (a.is_nan() && b.is_nan())
+        }
+        _ => expected == actual,
+    }
+}
+
+pub fn test_vectors() -> TestVectors {
+    let values = reference_values()
+        .into_iter()
+        .map(|(name, v)| ValueVector {
+            name,
+            datum_kind: planner::try_get_data_type_from_value(&v)
+                .map(|kind| kind.to_string())
+                .unwrap_or_default(),
+            value: readable_value(&v),
+            float_bits: float_bits(&v),
+            encoded: base64::encode(encode(v)),
+        })
+        .collect();
+
+    let timestamps = [0, -1, 1_700_000_000_123, i64::MAX / 1_000]
+        .into_iter()
+        .flat_map(|millis| {
+            PRECISIONS.iter().map(move |precision| TimestampVector {
+                millis,
+                precision: precision.as_str(),
+                value: precision.checked_from_millis(millis),
+            })
+        })
+        .collect();
+
+    TestVectors { values, timestamps }
+}
+
+pub fn verify(req: VerifyRequest) -> Result<VerifyResponse> {
+    let expected = reference_values()
+        .into_iter()
+        .find_map(|(name, v)| (name == req.name).then_some(v))
+        .with_context(|| ErrNoCause {
+            code: StatusCode::BAD_REQUEST,
+            msg: format!("Unknown test vector, name:{}", req.name),
+        })?;
+    let encoded = base64::decode(&req.encoded).map_err(|e| {
+        ErrNoCause {
+            code: StatusCode::BAD_REQUEST,
+            msg: format!("Encoded value is not base64, name:{}, err:{e}", req.name),
+        }
+        .build()
+    })?;
+
+    let msg = match Value::decode(encoded.as_slice()) {
+        Ok(Value {
+            value: Some(actual),
+        }) if value_matches(&expected, &actual) => None,
+        Ok(Value {
+            value: Some(actual),
+        }) => Some(format!(
+            "Value mismatch, expected:{expected:?}, actual:{actual:?}"
+        )),
+        Ok(Value { value: None }) => Some("Value is not set".to_string()),
+        Err(e) => Some(format!("Failed to decode value, err:{e}")),
+    };
+
+    Ok(VerifyResponse {
+        name: req.name,
+        passed: msg.is_none(),
+        msg,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn verify_encoded(name: &str, encoded: Vec<u8>) -> VerifyResponse {
+        verify(VerifyRequest {
+            name: name.to_string(),
+            encoded: base64::encode(encoded),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_verify_reference_vectors() {
+        let vectors = test_vectors();
+        for vector in &vectors.values {
+            let resp = verify(VerifyRequest {
+                name: vector.name.to_string(),
+                encoded: vector.encoded.clone(),
+            })
+            .unwrap();
+            assert!(resp.passed, "vector:{vector:?}, resp:{resp:?}");
+        }
+
+        let names = vectors.values.iter().map(|v| v.name).collect::<Vec<_>>();
+        let mut dedup = names.clone();
+        dedup.sort_unstable();
+        dedup.dedup();
+        assert_eq!(names.len(), dedup.len());
+    }
+
+    #[test]
+    fn test_verify_special_floats() {
+        // NaN with another payload.
+        let nan = f64::from_bits(f64::NAN.to_bits() | 1);
+        assert!(verify_encoded("float64_nan", encode(value::Value::Float64Value(nan))).passed);
+        assert!(
+            !verify_encoded(
+                "float64_negative_zero",
+                encode(value::Value::Float64Value(0.0))
+            )
+            .passed
+        );
+        assert!(
+            !verify_encoded("float32_nan", encode(value::Value::Float64Value(f64::NAN))).passed
+        );
+    }
+
+    #[test]
+    fn test_verify_invalid_request() {
+        assert!(verify(VerifyRequest {
+            name: "unknown".to_string(),
+            encoded: String::new(),
+        })
+        .is_err());
+        assert!(verify(VerifyRequest {
+            name: "int64_max".to_string(),
+            encoded: "!!".to_string(),
+        })
+        .is_err());
+
+        let resp = verify_encoded("int64_max", vec![0xff]);
+        assert!(!resp.passed);
+        assert!(resp.msg.is_some());
+        let resp = verify_encoded("int64_max", Vec::new());
+        assert!(!resp.passed);
+    }
+
+    #[test]
+    fn test_timestamp_vectors() {
+        let vectors = test_vectors();
+        let find = |millis, precision| {
+            vectors
+                .timestamps
+                .iter()
+                .find(|v| v.millis == millis && v.precision == precision)
+                .unwrap()
+                .value
+        };
+        assert_eq!(Some(-1), find(-1, "s"));
+        assert_eq!(Some(1_700_000_000_123_000), find(1_700_000_000_123, "us"));
+        assert_eq!(None, find(i64::MAX / 1_000, "ns"));
+    }
+}
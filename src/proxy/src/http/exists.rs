@@ -0,0 +1,118 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Existence check of the series in a time range.
+
+use std::collections::BTreeMap;
+
+use common_types::{
+    datum::DatumKind,
+    time::{TimeRange, Timestamp},
+};
+use datafusion::prelude::{col, lit};
+use generic_error::BoxError;
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use snafu::{OptionExt, ResultExt};
+use table_engine::{predicate::PredicateBuilder, table::ExistsRequest as TableExistsRequest};
+
+use crate::{
+    context::RequestContext,
+    error::{ErrNoCause, ErrWithCause, Result},
+    Proxy,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct ExistsRequest {
+    pub table: String,
+    /// Inclusive start of the time range in millis.
+    pub start: i64,
+    /// Exclusive end of the time range in millis.
+    pub end: i64,
+    /// Values of the tags identifying the series.
+    pub tags: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExistsResponse {
+    /// False if the series surely doesn't exist in the time range, while true
+    /// means it may exist.
+    pub exists: bool,
+}
+
+impl Proxy {
+    /// Check whether the series may exist in the time range by the meta data
+    /// and filters of the table only, which is much cheaper than a query.
+    pub async fn handle_http_exists(
+        &self,
+        ctx: &RequestContext,
+        req: ExistsRequest,
+    ) -> Result<ExistsResponse> {
+        let time_range = TimeRange::new(Timestamp::new(req.start), Timestamp::new(req.end))
+            .with_context(|| ErrNoCause {
+                code: StatusCode::BAD_REQUEST,
+                msg: format!("Invalid time range, start:{}, end:{}", req.start, req.end),
+            })?;
+
+        let catalog = self.get_catalog(&ctx.catalog)?;
+        let schema = self.get_schema(&catalog, &ctx.schema)?;
+        let table = self
+            .get_table(&schema, &req.table)?
+            .with_context(|| ErrNoCause {
+                code: StatusCode::NOT_FOUND,
+                msg: format!("Table not found, table:{}", req.table),
+            })?;
+
+        let table_schema = table.schema();
+        let mut exprs = Vec::with_capacity(req.tags.len());
+        for (name, value) in req.tags {
+            let is_string_tag = table_schema
+                .column_with_name(&name)
+                .map(|column| column.is_tag && column.data_type == DatumKind::String)
+                .unwrap_or(false);
+            if !is_string_tag {
+                return ErrNoCause {
+                    code: StatusCode::BAD_REQUEST,
+                    msg: format!(
+                        "Column is not a string tag, table:{}, column:{name}",
+                        req.table
+                    ),
+                }
+                .fail();
+            }
+            exprs.push(col(name).eq(lit(value)));
+        }
+
+        let predicate = PredicateBuilder::default()
+            .set_time_range(time_range)
+            .add_pushdown_exprs(&exprs)
+            .build();
+        let exists = table
+            .maybe_exists(TableExistsRequest {
+                request_id: ctx.request_id.clone(),
+                predicate,
+            })
+            .await
+            .box_err()
+            .with_context(|| ErrWithCause {
+                code: StatusCode::INTERNAL_SERVER_ERROR,
+                msg: format!("Failed to check existence, table:{}", req.table),
+            })?;
+
+        Ok(ExistsResponse { exists })
+    }
+}
@@ -15,6 +15,9 @@
 // specific language governing permissions and limitations
 // under the License.
 
+pub mod conformance;
+pub mod exists;
 pub mod prom;
 pub mod route;
+pub mod spill;
 pub mod sql;
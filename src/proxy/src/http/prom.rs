@@ -79,7 +79,7 @@ impl Proxy {
             }),
             table_requests: write_table_requests,
         };
-        let ctx = ProxyContext::new(ctx.timeout, None, ctx.authorization);
+        let ctx = ProxyContext::new(ctx.timeout, None, ctx.authorization).with_verbose(ctx.verbose);
 
         match self.handle_write_internal(ctx, table_request).await {
             Ok(result) => {
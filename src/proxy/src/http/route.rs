@@ -16,7 +16,7 @@
 // under the License.
 
 use horaedbproto::storage::RouteRequest as RouteRequestPb;
-use router::{endpoint::Endpoint, RouteRequest};
+use router::{endpoint::Endpoint, RouteRequest, ShardEpoch};
 use serde::Serialize;
 
 use crate::{context::RequestContext, error::Result, Proxy};
@@ -30,6 +30,8 @@ pub struct RouteResponse {
 pub struct RouteItem {
     pub table: String,
     pub endpoint: Option<Endpoint>,
+    /// Epoch of the shard which the table belongs to, absent if unknown.
+    pub shard_epoch: Option<ShardEpoch>,
 }
 
 impl Proxy {
@@ -54,13 +56,14 @@ impl Proxy {
             inner: req_pb,
         };
 
-        let routes = self.route(request).await?;
+        let routes = self.route_with_epoch(request).await?;
 
         let routes = routes
             .into_iter()
-            .map(|route| RouteItem {
-                table: route.table,
-                endpoint: route.endpoint.map(|endpoint| endpoint.into()),
+            .map(|v| RouteItem {
+                table: v.route.table,
+                endpoint: v.route.endpoint.map(|endpoint| endpoint.into()),
+                shard_epoch: v.epoch,
             })
             .collect();
 
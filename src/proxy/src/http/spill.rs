@@ -0,0 +1,707 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Spill the large query results to the object store.
+//!
+//! The encoded result is split into parts of `part_size` bytes and every part
+//! is stored as an object, the whole result is the concatenation of all the
+//! parts in order. The manifest of the parts is returned to the client, so the
+//! client can download the parts separately and resume the broken downloads by
+//! range requests.
+//!
+//! The records are encoded and written to the parts while they are streamed
+//! out of the query, and a spilled result is only accessible to the user who
+//! spills it.
+
+use std::{
+    ops::Range,
+    sync::{Arc, Mutex, Weak},
+};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use common_types::request_id::RequestId;
+use disk_quota::{Category, DiskConsumer};
+use futures::StreamExt;
+use generic_error::{BoxError, GenericResult};
+use http::StatusCode;
+use interpreters::interpreter::Output;
+use logger::{error, info, warn};
+use object_store::{ObjectStoreRef, Path};
+use runtime::{JoinHandle, Runtime};
+use serde::{Deserialize, Serialize};
+use size_ext::ReadableSize;
+use snafu::{ensure, OptionExt, ResultExt};
+use time_ext::{current_time_millis, ReadableDuration};
+use tokio::{
+    sync::oneshot::{self, Receiver, Sender},
+    time,
+};
+
+use crate::{
+    error::{ErrNoCause, ErrWithCause, Internal, Result},
+    http::sql::{OutputEncoder, ResponseFormat},
+    read::SqlOutputStream,
+};
+
+const MANIFEST_FILE_NAME: &str = "manifest.json";
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    pub enable: bool,
+    /// Max size of each spilled part.
+    pub part_size: ReadableSize,
+    /// The spilled results are purged after the ttl.
+    pub ttl: ReadableDuration,
+    /// The interval to purge the expired spilled results.
+    pub purge_interval: ReadableDuration,
+    /// Directory of the spilled results in the object store.
+    pub dir: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            part_size: ReadableSize::mb(64),
+            ttl: ReadableDuration::hours(24),
+            purge_interval: ReadableDuration::minutes(10),
+            dir: "spilled_results".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SpilledPart {
+    pub index: usize,
+    /// The uri to fetch the part, range requests are supported.
+    pub uri: String,
+    pub size: usize,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SpillManifest {
+    /// Id of the spilled result, it is also the token to fetch the parts.
+    pub id: String,
+    /// The user spilling the result, and only the same user can access it.
+    #[serde(default)]
+    pub owner: String,
+    pub content_type: String,
+    pub total_size: usize,
+    pub created_at_ms: u64,
+    pub expire_at_ms: u64,
+    pub parts: Vec<SpilledPart>,
+}
+
+impl SpillManifest {
+    fn is_expired(&self, now_ms: u64) -> bool {
+        self.expire_at_ms <= now_ms
+    }
+}
+
+/// Spiller writing the query results to the object store and serving the
+/// spilled parts.
+///
+/// The expired results are purged periodically once it is started.
+#[derive(Debug)]
+pub struct ResultSpiller {
+    store: ObjectStoreRef,
+    config: Config,
+    stop_sender: Mutex<Option<Sender<()>>>,
+    handle: tokio::sync::Mutex<Option<JoinHandle<()>>>,
+}
+
+impl ResultSpiller {
+    pub fn new(store: ObjectStoreRef, config: Config) -> Self {
+        Self {
+            store,
+            config,
+            stop_sender: Mutex::new(None),
+            handle: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    /// Start the background task purging the expired results.
+    pub fn start(self: &Arc<Self>, runtime: &Runtime) {
+        let (tx, rx) = oneshot::channel();
+        *self.stop_sender.lock().unwrap() = Some(tx);
+
+        let handle = runtime.spawn(Self::purge_loop(Arc::downgrade(self), rx));
+        *self.handle.try_lock().unwrap() = Some(handle);
+    }
+
+    pub async fn stop(&self) {
+        info!("Try to stop result spiller");
+
+        if let Some(tx) = self.stop_sender.lock().unwrap().take() {
+            if tx.send(()).is_err() {
+                error!("Result spiller purger already exited");
+            }
+        }
+
+        let mut handle = self.handle.lock().await;
+        if let Some(h) = handle.take() {
+            if let Err(e) = h.await {
+                error!("Failed to wait result spiller purger to exit, err:{e}");
+            }
+        }
+    }
+
+    async fn purge_loop(spiller: Weak<ResultSpiller>, mut stop_receiver: Receiver<()>) {
+        loop {
+            let interval = {
+                let Some(spiller) = spiller.upgrade() else {
+                    break;
+                };
+                match spiller.purge_expired().await {
+                    Ok(0) => (),
+                    Ok(purged) => info!("Purge expired spilled results, purged:{purged}"),
+                    Err(e) => warn!("Failed to purge expired spilled results, err:{e}"),
+                }
+                spiller.config.purge_interval.0
+            };
+
+            // Stop if the stopping message is received or the sender is dropped.
+            if time::timeout(interval, &mut stop_receiver).await.is_ok() {
+                break;
+            }
+        }
+
+        info!("Result spiller purger exit");
+    }
+
+    /// Spill the `output` encoded in the `format` on behalf of the `owner`.
+    ///
+    /// The records are written to the parts while they are polled from the
+    /// stream, and the written parts are deleted if the spilling fails.
+    pub async fn spill(
+        &self,
+        request_id: &RequestId,
+        owner: &str,
+        output: SqlOutputStream,
+        format: ResponseFormat,
+    ) -> Result<SpillManifest> {
+        let created_at_ms = current_time_millis();
+        let id = format!("{created_at_ms}-{request_id}");
+
+        let mut writer = PartWriter::new(self, &id);
+        let parts = match Self::write_output(&mut writer, output, format).await {
+            Ok(()) => writer.finish().await,
+            Err(e) => Err(e),
+        };
+        let parts = match parts {
+            Ok(parts) => parts,
+            Err(e) => {
+                writer.abort().await;
+                return Err(e);
+            }
+        };
+
+        let manifest = SpillManifest {
+            id,
+            owner: owner.to_string(),
+            content_type: format.content_type().to_string(),
+            total_size: parts.iter().map(|part| part.size).sum(),
+            created_at_ms,
+            expire_at_ms: created_at_ms + self.config.ttl.as_millis(),
+            parts,
+        };
+        let encoded_manifest = serde_json::to_vec(&manifest).box_err().context(Internal {
+            msg: "encode spill manifest",
+        })?;
+        self.store
+            .put(&self.manifest_path(&manifest.id), encoded_manifest.into())
+            .await
+            .box_err()
+            .context(Internal {
+                msg: "put spill manifest",
+            })?;
+
+        info!(
+            "Spill query result finished, id:{}, parts:{}, total_size:{}",
+            manifest.id,
+            manifest.parts.len(),
+            manifest.total_size
+        );
+
+        Ok(manifest)
+    }
+
+    async fn write_output(
+        writer: &mut PartWriter<'_>,
+        output: SqlOutputStream,
+        format: ResponseFormat,
+    ) -> Result<()> {
+        match output {
+            SqlOutputStream::AffectedRows(n) => {
+                for encoded in OutputEncoder::new(Output::AffectedRows(n), format) {
+                    writer.write(&encoded?).await?;
+                }
+            }
+            SqlOutputStream::Records(mut records) => {
+                let mut encoder = OutputEncoder::new(Output::Records(Vec::new()), format);
+                while let Some(batch) = records.next().await {
+                    writer.write(&encoder.encode_record_batch(&batch?)?).await?;
+                }
+                writer.write(&encoder.finish()?).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get the manifest of the spilled result owned by the `user`, an error is
+    /// returned if it is not found or expired.
+    pub async fn manifest(&self, id: &str, user: &str) -> Result<SpillManifest> {
+        ensure!(
+            is_valid_id(id),
+            ErrNoCause {
+                code: StatusCode::BAD_REQUEST,
+                msg: format!("Invalid spilled result id, id:{id}"),
+            }
+        );
+
+        let manifest = self.read_manifest(id).await?;
+        ensure!(
+            manifest.owner == user,
+            ErrNoCause {
+                code: StatusCode::FORBIDDEN,
+                msg: format!("Spilled result is not owned by the user, id:{id}, user:{user}"),
+            }
+        );
+        ensure!(
+            !manifest.is_expired(current_time_millis()),
+            ErrNoCause {
+                code: StatusCode::GONE,
+                msg: format!("Spilled result is expired, id:{id}"),
+            }
+        );
+
+        Ok(manifest)
+    }
+
+    /// Fetch the `range` of the part, the whole part is returned if no range
+    /// is given.
+    ///
+    /// Returns the fetched bytes, the fetched range and the size of the part.
+    pub async fn fetch_part(
+        &self,
+        id: &str,
+        user: &str,
+        index: usize,
+        range: Option<&str>,
+    ) -> Result<(Bytes, Range<usize>, usize)> {
+        let manifest = self.manifest(id, user).await?;
+        let part = manifest.parts.get(index).with_context(|| ErrNoCause {
+            code: StatusCode::NOT_FOUND,
+            msg: format!("Spilled part is not found, id:{id}, index:{index}"),
+        })?;
+        let range = match range {
+            Some(range) => parse_range(range, part.size)?,
+            None => 0..part.size,
+        };
+
+        let bytes = if range.is_empty() {
+            Bytes::new()
+        } else {
+            self.store
+                .get_range(&self.part_path(id, index), range.clone())
+                .await
+                .box_err()
+                .context(Internal {
+                    msg: "fetch spilled part",
+                })?
+        };
+
+        Ok((bytes, range, part.size))
+    }
+
+    /// Delete the spilled result owned by the `user`.
+    pub async fn delete(&self, id: &str, user: &str) -> Result<()> {
+        let manifest = self.manifest(id, user).await?;
+        self.delete_spilled(&manifest).await
+    }
+
+    /// Purge the expired spilled results, returns the number of purged
+    /// results.
+    pub async fn purge_expired(&self) -> Result<usize> {
+        let now_ms = current_time_millis();
+        let mut purged = 0;
+        for manifest in self.list_manifests().await? {
+            if manifest.is_expired(now_ms) {
+                self.delete_spilled(&manifest).await?;
+                purged += 1;
+            }
+        }
+
+        Ok(purged)
+    }
+
+    /// Delete the oldest spilled results until `bytes` are freed, returns the
+    /// freed bytes.
+    pub async fn evict_oldest(&self, bytes: usize) -> Result<usize> {
+        let mut manifests = self.list_manifests().await?;
+        manifests.sort_by_key(|manifest| manifest.created_at_ms);
+
+        let mut freed = 0;
+        for manifest in manifests {
+            if freed >= bytes {
+                break;
+            }
+            info!(
+                "Evict spilled result, id:{}, size:{}",
+                manifest.id, manifest.total_size
+            );
+            self.delete_spilled(&manifest).await?;
+            freed += manifest.total_size;
+        }
+
+        Ok(freed)
+    }
+
+    /// Total size of all the spilled results.
+    pub async fn total_size(&self) -> Result<usize> {
+        let manifests = self.list_manifests().await?;
+        Ok(manifests.iter().map(|manifest| manifest.total_size).sum())
+    }
+
+    async fn list_manifests(&self) -> Result<Vec<SpillManifest>> {
+        let listed = self
+            .store
+            .list_with_delimiter(Some(&Path::from(self.config.dir.as_str())))
+            .await
+            .box_err()
+            .context(Internal {
+                msg: "list spilled results",
+            })?;
+
+        let mut manifests = Vec::with_capacity(listed.common_prefixes.len());
+        for dir in listed.common_prefixes {
+            let Some(id) = dir.filename() else {
+                continue;
+            };
+            match self.read_manifest(id).await {
+                Ok(v) => manifests.push(v),
+                Err(e) => warn!("Failed to read spill manifest, id:{id}, err:{e}"),
+            }
+        }
+
+        Ok(manifests)
+    }
+
+    async fn put_part(&self, id: &str, index: usize, part: Vec<u8>) -> Result<SpilledPart> {
+        let size = part.len();
+        self.store
+            .put(&self.part_path(id, index), part.into())
+            .await
+            .box_err()
+            .context(Internal {
+                msg: "put spilled part",
+            })?;
+
+        Ok(SpilledPart {
+            index,
+            uri: format!("/sql/spill/{id}/{index}"),
+            size,
+        })
+    }
+
+    async fn read_manifest(&self, id: &str) -> Result<SpillManifest> {
+        let get_res = self
+            .store
+            .get(&self.manifest_path(id))
+            .await
+            .box_err()
+            .with_context(|| ErrWithCause {
+                code: StatusCode::NOT_FOUND,
+                msg: format!("Spilled result is not found, id:{id}"),
+            })?;
+        let bytes = get_res.bytes().await.box_err().context(Internal {
+            msg: "read spill manifest",
+        })?;
+
+        serde_json::from_slice(&bytes).box_err().context(Internal {
+            msg: "decode spill manifest",
+        })
+    }
+
+    async fn delete_spilled(&self, manifest: &SpillManifest) -> Result<()> {
+        // Delete the manifest first, so the half deleted result can't be fetched.
+        self.store
+            .delete(&self.manifest_path(&manifest.id))
+            .await
+            .box_err()
+            .context(Internal {
+                msg: "delete spill manifest",
+            })?;
+
+        for part in &manifest.parts {
+            self.store
+                .delete(&self.part_path(&manifest.id, part.index))
+                .await
+                .box_err()
+                .context(Internal {
+                    msg: "delete spilled part",
+                })?;
+        }
+
+        Ok(())
+    }
+
+    fn manifest_path(&self, id: &str) -> Path {
+        Path::from(format!("{}/{id}/{MANIFEST_FILE_NAME}", self.config.dir))
+    }
+
+    fn part_path(&self, id: &str, index: usize) -> Path {
+        Path::from(format!("{}/{id}/part-{index}", self.config.dir))
+    }
+}
+
+/// Writer splitting the encoded output into parts.
+struct PartWriter<'a> {
+    spiller: &'a ResultSpiller,
+    id: &'a str,
+    part_size: usize,
+    buf: Vec<u8>,
+    parts: Vec<SpilledPart>,
+}
+
+impl<'a> PartWriter<'a> {
+    fn new(spiller: &'a ResultSpiller, id: &'a str) -> Self {
+        let part_size = (spiller.config.part_size.as_byte() as usize).max(1);
+        Self {
+            spiller,
+            id,
+            part_size,
+            buf: Vec::with_capacity(part_size),
+            parts: Vec::new(),
+        }
+    }
+
+    async fn write(&mut self, mut encoded: &[u8]) -> Result<()> {
+        while !encoded.is_empty() {
+            let len = encoded.len().min(self.part_size - self.buf.len());
+            self.buf.extend_from_slice(&encoded[..len]);
+            encoded = &encoded[len..];
+
+            if self.buf.len() == self.part_size {
+                let part = std::mem::replace(&mut self.buf, Vec::with_capacity(self.part_size));
+                self.put_part(part).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write the last part and return all the written parts.
+    async fn finish(&mut self) -> Result<Vec<SpilledPart>> {
+        if !self.buf.is_empty() || self.parts.is_empty() {
+            let part = std::mem::take(&mut self.buf);
+            self.put_part(part).await?;
+        }
+
+        Ok(std::mem::take(&mut self.parts))
+    }
+
+    /// Delete the written parts, the failure is only logged as there is no
+    /// manifest referring to them.
+    async fn abort(&mut self) {
+        for part in self.parts.drain(..) {
+            let path = self.spiller.part_path(self.id, part.index);
+            if let Err(e) = self.spiller.store.delete(&path).await {
+                warn!(
+                    "Failed to delete spilled part of aborted result, id:{}, index:{}, err:{e}",
+                    self.id, part.index
+                );
+            }
+        }
+    }
+
+    async fn put_part(&mut self, part: Vec<u8>) -> Result<()> {
+        let part = self
+            .spiller
+            .put_part(self.id, self.parts.len(), part)
+            .await?;
+        self.parts.push(part);
+
+        Ok(())
+    }
+}
+
+/// The spilled results are on the local disk if the spill store is local.
+#[async_trait]
+impl DiskConsumer for ResultSpiller {
+    fn category(&self) -> Category {
+        Category::Spill
+    }
+
+    async fn usage(&self) -> GenericResult<u64> {
+        self.total_size().await.map(|v| v as u64).box_err()
+    }
+
+    async fn evict(&self, bytes: u64) -> GenericResult<u64> {
+        self.evict_oldest(bytes as usize)
+            .await
+            .map(|v| v as u64)
+            .box_err()
+    }
+}
+
+/// The id is generated by the spiller, and it must not be able to escape the
+/// spill directory.
+fn is_valid_id(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+/// Parse the value of the `Range` header, only the single byte range is
+/// supported.
+fn parse_range(range: &str, size: usize) -> Result<Range<usize>> {
+    let invalid_range = || ErrNoCause {
+        code: StatusCode::RANGE_NOT_SATISFIABLE,
+        msg: format!("Invalid range, range:{range}, size:{size}"),
+    };
+
+    let spec = range
+        .trim()
+        .strip_prefix("bytes=")
+        .with_context(invalid_range)?;
+    let (start, end) = spec.split_once('-').with_context(invalid_range)?;
+    let (start, end) = match (start.trim(), end.trim()) {
+        // Suffix range, the last `n` bytes.
+        ("", n) => {
+            let n = n.parse::<usize>().ok().with_context(invalid_range)?;
+            (size.saturating_sub(n), size)
+        }
+        (start, "") => {
+            let start = start.parse::<usize>().ok().with_context(invalid_range)?;
+            (start, size)
+        }
+        (start, end) => {
+            let start = start.parse::<usize>().ok().with_context(invalid_range)?;
+            let end = end.parse::<usize>().ok().with_context(invalid_range)?;
+            // The end of the range header is inclusive.
+            (start, end.saturating_add(1).min(size))
+        }
+    };
+    ensure!(start < end || (start == 0 && size == 0), invalid_range());
+
+    Ok(start..end)
+}
+
+#[cfg(test)]
+mod tests {
+    use object_store::local_file;
+
+    use super::*;
+
+    fn new_spiller(dir: &tempfile::TempDir, part_size: u64) -> ResultSpiller {
+        let store =
+            local_file::try_new_with_default(dir.path().to_str().unwrap().to_string()).unwrap();
+        let config = Config {
+            enable: true,
+            part_size: ReadableSize(part_size),
+            ..Default::default()
+        };
+        ResultSpiller::new(Arc::new(store), config)
+    }
+
+    #[tokio::test]
+    async fn test_spill_and_fetch() {
+        let dir = tempfile::tempdir().unwrap();
+        let spiller = new_spiller(&dir, 4);
+        let request_id = RequestId::next_id();
+        let output = SqlOutputStream::AffectedRows(3);
+        let manifest = spiller
+            .spill(&request_id, "user", output, ResponseFormat::Csv)
+            .await
+            .unwrap();
+
+        let expected = b"affected_rows\n3\n";
+        assert_eq!(expected.len(), manifest.total_size);
+        assert_eq!(expected.len().div_ceil(4), manifest.parts.len());
+        assert_eq!("user", manifest.owner);
+
+        let mut fetched = Vec::new();
+        for part in &manifest.parts {
+            let (bytes, _, _) = spiller
+                .fetch_part(&manifest.id, "user", part.index, None)
+                .await
+                .unwrap();
+            fetched.extend_from_slice(&bytes);
+        }
+        assert_eq!(&expected[..], &fetched[..]);
+
+        // Only the owner can access the spilled result.
+        assert!(spiller.manifest(&manifest.id, "other").await.is_err());
+        assert!(spiller
+            .fetch_part(&manifest.id, "other", 0, None)
+            .await
+            .is_err());
+        assert!(spiller.delete(&manifest.id, "other").await.is_err());
+
+        spiller.delete(&manifest.id, "user").await.unwrap();
+        assert!(spiller.manifest(&manifest.id, "user").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_purge_expired() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut spiller = new_spiller(&dir, 1024);
+        spiller.config.ttl = ReadableDuration::millis(0);
+        let manifest = spiller
+            .spill(
+                &RequestId::next_id(),
+                "user",
+                SqlOutputStream::AffectedRows(1),
+                ResponseFormat::Json,
+            )
+            .await
+            .unwrap();
+        assert_eq!(1, manifest.parts.len());
+
+        assert_eq!(1, spiller.purge_expired().await.unwrap());
+        assert_eq!(0, spiller.total_size().await.unwrap());
+    }
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(0..10, parse_range("bytes=0-9", 100).unwrap());
+        assert_eq!(90..100, parse_range("bytes=90-", 100).unwrap());
+        assert_eq!(80..100, parse_range("bytes=-20", 100).unwrap());
+        assert_eq!(0..100, parse_range("bytes=-200", 100).unwrap());
+        assert_eq!(95..100, parse_range("bytes=95-200", 100).unwrap());
+
+        for invalid in [
+            "bytes=100-",
+            "bytes=10-5",
+            "bytes=a-b",
+            "0-9",
+            "bytes=0-1,3-4",
+        ] {
+            assert!(parse_range(invalid, 100).is_err(), "range:{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_valid_id() {
+        assert!(is_valid_id("1700000000000-42"));
+        assert!(!is_valid_id(""));
+        assert!(!is_valid_id("../manifest.json"));
+        assert!(!is_valid_id("a/b"));
+    }
+}
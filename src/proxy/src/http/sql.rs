@@ -15,13 +15,21 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use std::io::Cursor;
+use std::{io::Cursor, sync::Arc};
 
-use arrow::{ipc::reader::StreamReader, record_batch::RecordBatch as ArrowRecordBatch};
+use arrow::{
+    array::UInt64Array,
+    csv::WriterBuilder as CsvWriterBuilder,
+    datatypes::{DataType, Field, Schema as ArrowSchema},
+    ipc::reader::StreamReader,
+    record_batch::RecordBatch as ArrowRecordBatch,
+};
+use arrow_ext::ipc::{CompressOptions, CompressionMethod, RecordBatchesEncoder};
 use common_types::{
     datum::{Datum, DatumKind},
     record_batch::RecordBatch,
 };
+use futures::StreamExt;
 use generic_error::BoxError;
 use horaedbproto::storage::{
     arrow_payload::Compression, sql_query_response::Output as OutputPb, ArrowPayload,
@@ -30,16 +38,19 @@ use horaedbproto::storage::{
 use http::StatusCode;
 use interpreters::{interpreter::Output, RecordBatchVec};
 use logger::error;
+use query_frontend::{ast::Statement, parser::Parser};
 use serde::{
     ser::{SerializeMap, SerializeSeq},
     Deserialize, Serialize,
 };
-use snafu::{OptionExt, ResultExt};
+use snafu::{ensure, OptionExt, ResultExt};
+use sqlparser::ast::Statement as SqlStatement;
+use table_engine::table::ReadSnapshot;
 
 use crate::{
     context::RequestContext,
     error::{ErrNoCause, Internal, InternalNoCause, Result},
-    read::SqlResponse,
+    read::{SqlOutputStream, SqlResponse},
     Context, Proxy,
 };
 
@@ -50,7 +61,10 @@ impl Proxy {
         req: Request,
     ) -> Result<Output> {
         let schema = &ctx.schema;
-        let ctx = Context::new(ctx.timeout, None, ctx.authorization.clone());
+        let ctx = Context::new(ctx.timeout, None, ctx.authorization.clone())
+            .with_user(ctx.user.clone())
+            .with_trace(ctx.trace.clone())
+            .with_verbose(ctx.verbose);
 
         let query_res = self
             .handle_sql(
@@ -74,12 +88,162 @@ impl Proxy {
             Ok(SqlResponse::Local(output)) => Ok(output),
         }
     }
+
+    /// Execute the sql and return the records as a stream, so the large result
+    /// needn't to be materialized in memory.
+    ///
+    /// Note: the result forwarded from other nodes is still collected.
+    pub async fn handle_http_sql_query_stream(
+        &self,
+        ctx: &RequestContext,
+        req: Request,
+    ) -> Result<SqlOutputStream> {
+        let schema = &ctx.schema;
+        let ctx = Context::new(ctx.timeout, None, ctx.authorization.clone())
+            .with_user(ctx.user.clone())
+            .with_trace(ctx.trace.clone())
+            .with_verbose(ctx.verbose);
+
+        let query_res = self
+            .handle_sql_stream(
+                &ctx,
+                schema,
+                &req.query,
+                self.sub_table_access_perm.enable_http,
+            )
+            .await;
+
+        if let Err(e) = &query_res {
+            error!(
+                "Handle sql query stream failed, schema:{schema}, ctx:{ctx:?}, sql:{}, err:{e}",
+                req.query,
+            );
+        }
+        query_res
+    }
+
+    /// Execute the queries of the batch concurrently, and all the local reads
+    /// of them share the same snapshot of the tables.
+    ///
+    /// Only queries are allowed in the batch, and the batch is rejected if any
+    /// statement isn't a query.
+    ///
+    /// Note: the statements forwarded to other nodes don't share the snapshot.
+    pub async fn handle_http_sql_batch_query(
+        &self,
+        ctx: &RequestContext,
+        req: BatchRequest,
+    ) -> Result<Vec<BatchResponseItem>> {
+        ensure!(
+            req.queries.len() <= MAX_BATCH_QUERIES,
+            ErrNoCause {
+                code: StatusCode::BAD_REQUEST,
+                msg: format!(
+                    "Too many queries in a batch, num:{}, max:{MAX_BATCH_QUERIES}",
+                    req.queries.len()
+                ),
+            }
+        );
+        for (index, query) in req.queries.iter().enumerate() {
+            ensure!(
+                is_query(query),
+                ErrNoCause {
+                    code: StatusCode::BAD_REQUEST,
+                    msg: format!("Only queries are allowed in a batch, index:{index}, sql:{query}"),
+                }
+            );
+        }
+
+        let snapshot = Arc::new(ReadSnapshot::default());
+        let queries = req.queries.into_iter().enumerate().map(|(index, query)| {
+            let snapshot = snapshot.clone();
+            async move {
+                let query_ctx = Context::new(ctx.timeout, None, ctx.authorization.clone())
+                    .with_user(ctx.user.clone())
+                    .with_trace(ctx.trace.clone())
+                    .with_verbose(ctx.verbose)
+                    .with_read_snapshot(snapshot);
+                let query_res = self
+                    .handle_sql(
+                        &query_ctx,
+                        &ctx.schema,
+                        &query,
+                        self.sub_table_access_perm.enable_http,
+                        false,
+                    )
+                    .await
+                    .and_then(|resp| match resp {
+                        SqlResponse::Forwarded(resp) => convert_sql_response_to_output(resp),
+                        SqlResponse::Local(output) => Ok(output),
+                    });
+
+                match query_res {
+                    Ok(output) => BatchResponseItem {
+                        index,
+                        result: Some(convert_output(output)),
+                        error: None,
+                    },
+                    Err(e) => {
+                        error!(
+                            "Handle sql query in batch failed, schema:{}, ctx:{query_ctx:?}, sql:{query}, err:{e}",
+                            ctx.schema,
+                        );
+                        BatchResponseItem {
+                            index,
+                            result: None,
+                            error: Some(e.to_string()),
+                        }
+                    }
+                }
+            }
+        });
+
+        let items = futures::stream::iter(queries)
+            .buffered(BATCH_QUERY_PARALLELISM)
+            .collect()
+            .await;
+        Ok(items)
+    }
+}
+
+/// Max number of the queries in a batch.
+const MAX_BATCH_QUERIES: usize = 64;
+/// Max number of the queries of a batch executed concurrently.
+const BATCH_QUERY_PARALLELISM: usize = 8;
+
+/// Whether the sql is a single query, which can't modify any data.
+fn is_query(sql: &str) -> bool {
+    match Parser::parse_sql(sql) {
+        Ok(stmts) => {
+            stmts.len() == 1
+                && matches!(&stmts[0], Statement::Standard(stmt) if matches!(**stmt, SqlStatement::Query(_)))
+        }
+        // The invalid sql is reported by the execution.
+        Err(_) => true,
+    }
 }
+
 #[derive(Debug, Deserialize)]
 pub struct Request {
     pub query: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct BatchRequest {
+    pub queries: Vec<String>,
+}
+
+/// Result of one statement in the [BatchRequest], `index` is the position of
+/// the statement in the batch.
+#[derive(Serialize)]
+pub struct BatchResponseItem {
+    pub index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Response>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
 // TODO(yingwen): Improve serialize performance
 #[derive(Serialize)]
 #[serde(rename_all = "snake_case")]
@@ -139,6 +303,290 @@ impl Serialize for ResponseRows {
     }
 }
 
+/// Media type of the json response.
+pub const JSON_CONTENT_TYPE: &str = "application/json";
+/// Media type of the csv response.
+pub const CSV_CONTENT_TYPE: &str = "text/csv";
+/// Media type of the arrow ipc stream response.
+pub const ARROW_STREAM_CONTENT_TYPE: &str = "application/vnd.apache.arrow.stream";
+
+const AFFECTED_ROWS_COLUMN: &str = "affected_rows";
+
+/// Format of the sql query result returned over http.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseFormat {
+    Json,
+    Csv,
+    Arrow,
+}
+
+impl ResponseFormat {
+    /// Negotiate the response format from the value of the `Accept` header.
+    ///
+    /// Media ranges are tried in the order of their quality values, and the
+    /// first supported one wins. Returns `None` if none of them is supported.
+    pub fn from_accept(accept: &str) -> Option<Self> {
+        let mut media_ranges = accept
+            .split(',')
+            .filter_map(|media_range| {
+                let mut parts = media_range.split(';');
+                let media_type = parts.next()?.trim().to_ascii_lowercase();
+                let quality = parts
+                    .filter_map(|param| param.trim().strip_prefix("q="))
+                    .find_map(|q| q.trim().parse::<f32>().ok())
+                    .unwrap_or(1.0);
+                Some((media_type, quality))
+            })
+            .filter(|(_, quality)| *quality > 0.0)
+            .collect::<Vec<_>>();
+        // The sort is stable, so media ranges with the same quality keep their order.
+        media_ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        media_ranges
+            .iter()
+            .find_map(|(media_type, _)| match media_type.as_str() {
+                JSON_CONTENT_TYPE | "application/*" | "*/*" => Some(ResponseFormat::Json),
+                CSV_CONTENT_TYPE | "text/*" => Some(ResponseFormat::Csv),
+                ARROW_STREAM_CONTENT_TYPE => Some(ResponseFormat::Arrow),
+                _ => None,
+            })
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ResponseFormat::Json => JSON_CONTENT_TYPE,
+            ResponseFormat::Csv => CSV_CONTENT_TYPE,
+            ResponseFormat::Arrow => ARROW_STREAM_CONTENT_TYPE,
+        }
+    }
+}
+
+/// Encoder of the sql query output.
+///
+/// The output is encoded one record batch per iteration, so the response body
+/// can be sent to the client while the rest of the result is still being
+/// encoded. The json format produces the same body as [convert_output].
+///
+/// An empty body is produced for the arrow format if the query returns no
+/// record batch, as there is no schema to write.
+pub struct OutputEncoder {
+    format: ResponseFormat,
+    affected_rows: Option<usize>,
+    records: std::vec::IntoIter<RecordBatch>,
+    num_encoded_batches: usize,
+    num_encoded_rows: usize,
+    ipc_encoder: Option<RecordBatchesEncoder>,
+    finished: bool,
+}
+
+impl OutputEncoder {
+    /// Create an encoder of the `output`, the records of the streamed output
+    /// are fed by [OutputEncoder::encode_record_batch] batch by batch.
+    pub fn new(output: Output, format: ResponseFormat) -> Self {
+        let (affected_rows, records) = match output {
+            Output::AffectedRows(n) => (Some(n), Vec::new()),
+            Output::Records(records) => (None, records),
+        };
+
+        Self {
+            format,
+            affected_rows,
+            records: records.into_iter(),
+            num_encoded_batches: 0,
+            num_encoded_rows: 0,
+            ipc_encoder: Some(Self::new_ipc_encoder()),
+            finished: false,
+        }
+    }
+
+    fn new_ipc_encoder() -> RecordBatchesEncoder {
+        // The encoded bytes are sent out batch by batch, so they can't be compressed.
+        RecordBatchesEncoder::new(CompressOptions {
+            compress_min_length: 0,
+            method: CompressionMethod::None,
+        })
+    }
+
+    fn encode_affected_rows(&self, affected_rows: usize) -> Result<Vec<u8>> {
+        match self.format {
+            ResponseFormat::Json => serde_json::to_vec(&Response::AffectedRows(affected_rows))
+                .box_err()
+                .context(Internal {
+                    msg: "encode affected rows to json",
+                }),
+            ResponseFormat::Csv => {
+                Ok(format!("{AFFECTED_ROWS_COLUMN}\n{affected_rows}\n").into_bytes())
+            }
+            ResponseFormat::Arrow => {
+                let schema = Arc::new(ArrowSchema::new(vec![Field::new(
+                    AFFECTED_ROWS_COLUMN,
+                    DataType::UInt64,
+                    false,
+                )]));
+                let array = UInt64Array::from(vec![affected_rows as u64]);
+                let batch = ArrowRecordBatch::try_new(schema, vec![Arc::new(array)])
+                    .box_err()
+                    .context(Internal {
+                        msg: "build affected rows record batch",
+                    })?;
+
+                let mut encoder = Self::new_ipc_encoder();
+                encoder.write(&batch).box_err().context(Internal {
+                    msg: "encode affected rows to arrow",
+                })?;
+                let output = encoder.finish().box_err().context(Internal {
+                    msg: "finish arrow stream",
+                })?;
+
+                Ok(output.payload)
+            }
+        }
+    }
+
+    /// Encode the next record batch of the streamed output.
+    pub fn encode_record_batch(&mut self, batch: &RecordBatch) -> Result<Vec<u8>> {
+        ensure!(
+            !self.finished,
+            InternalNoCause {
+                msg: "output encoder is already finished",
+            }
+        );
+
+        let res = self.encode_batch(batch);
+        self.num_encoded_batches += 1;
+        if res.is_err() {
+            self.finished = true;
+        }
+        res
+    }
+
+    /// Finish the encoding of the streamed output, and returns the trailing
+    /// bytes.
+    pub fn finish(&mut self) -> Result<Vec<u8>> {
+        ensure!(
+            !self.finished,
+            InternalNoCause {
+                msg: "output encoder is already finished",
+            }
+        );
+
+        self.finished = true;
+        self.encode_end()
+    }
+
+    fn encode_batch(&mut self, batch: &RecordBatch) -> Result<Vec<u8>> {
+        match self.format {
+            ResponseFormat::Json => Ok(self.encode_json_batch(batch)),
+            ResponseFormat::Csv => self.encode_csv_batch(batch),
+            ResponseFormat::Arrow => self.encode_arrow_batch(batch),
+        }
+    }
+
+    fn encode_json_batch(&mut self, batch: &RecordBatch) -> Vec<u8> {
+        let mut buf = Vec::new();
+        if self.num_encoded_batches == 0 {
+            buf.extend_from_slice(b"{\"rows\":[");
+        }
+
+        let schema = batch.schema();
+        let column_names = (0..batch.num_columns())
+            .map(|col_idx| &schema.column(col_idx).name)
+            .collect::<Vec<_>>();
+        for row_idx in 0..batch.num_rows() {
+            let datums = (0..batch.num_columns())
+                .map(|col_idx| batch.column(col_idx).datum(row_idx))
+                .collect::<Vec<_>>();
+            let row = Row(column_names.iter().copied().zip(datums.iter()).collect());
+
+            if self.num_encoded_rows > 0 {
+                buf.push(b',');
+            }
+            // Serializing datums into a vec never fails.
+            serde_json::to_writer(&mut buf, &row).expect("serialize row to json");
+            self.num_encoded_rows += 1;
+        }
+
+        buf
+    }
+
+    fn encode_csv_batch(&mut self, batch: &RecordBatch) -> Result<Vec<u8>> {
+        let mut writer = CsvWriterBuilder::new()
+            .with_header(self.num_encoded_batches == 0)
+            .build(Vec::new());
+        writer
+            .write(batch.as_arrow_record_batch())
+            .box_err()
+            .context(Internal {
+                msg: "encode record batch to csv",
+            })?;
+
+        Ok(writer.into_inner())
+    }
+
+    fn encode_arrow_batch(&mut self, batch: &RecordBatch) -> Result<Vec<u8>> {
+        let encoder = self.ipc_encoder.as_mut().context(InternalNoCause {
+            msg: "arrow stream is already finished",
+        })?;
+        encoder
+            .write(batch.as_arrow_record_batch())
+            .box_err()
+            .context(Internal {
+                msg: "encode record batch to arrow",
+            })?;
+
+        Ok(encoder.take_encoded())
+    }
+
+    fn encode_end(&mut self) -> Result<Vec<u8>> {
+        match self.format {
+            ResponseFormat::Json if self.num_encoded_batches == 0 => Ok(b"{\"rows\":[]}".to_vec()),
+            ResponseFormat::Json => Ok(b"]}".to_vec()),
+            ResponseFormat::Csv => Ok(Vec::new()),
+            ResponseFormat::Arrow => match self.ipc_encoder.take() {
+                Some(encoder) => {
+                    let output = encoder.finish().box_err().context(Internal {
+                        msg: "finish arrow stream",
+                    })?;
+                    Ok(output.payload)
+                }
+                None => Ok(Vec::new()),
+            },
+        }
+    }
+}
+
+impl Iterator for OutputEncoder {
+    type Item = Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        if let Some(affected_rows) = self.affected_rows.take() {
+            self.finished = true;
+            return Some(self.encode_affected_rows(affected_rows));
+        }
+
+        let res = match self.records.next() {
+            Some(batch) => {
+                let res = self.encode_batch(&batch);
+                self.num_encoded_batches += 1;
+                res
+            }
+            None => {
+                self.finished = true;
+                self.encode_end()
+            }
+        };
+        if res.is_err() {
+            self.finished = true;
+        }
+
+        Some(res)
+    }
+}
+
 // Convert output to json
 pub fn convert_output(output: Output) -> Response {
     match output {
@@ -190,7 +638,9 @@ fn convert_records(records: RecordBatchVec) -> Response {
     })
 }
 
-fn convert_sql_response_to_output(sql_query_response: SqlQueryResponse) -> Result<Output> {
+pub(crate) fn convert_sql_response_to_output(
+    sql_query_response: SqlQueryResponse,
+) -> Result<Output> {
     if let Some(header) = sql_query_response.header {
         if header.code as u16 != StatusCode::OK.as_u16() {
             return ErrNoCause {
@@ -277,3 +727,91 @@ fn decode_arrow_payload(arrow_payload: ArrowPayload) -> Result<Vec<ArrowRecordBa
 
     Ok(record_batches)
 }
+
+#[cfg(test)]
+mod tests {
+    use arrow_ext::ipc::decode_record_batches;
+
+    use super::*;
+
+    #[test]
+    fn test_is_query() {
+        let cases = [
+            ("select * from t", true),
+            ("SELECT count(*) FROM t WHERE ts > 1", true),
+            ("insert into t(a, ts) values(1, 1)", false),
+            ("drop table t", false),
+            (
+                "create table t(a int, ts timestamp not null, timestamp key(ts))",
+                false,
+            ),
+            ("select 1; select 2", false),
+        ];
+
+        for (sql, expect) in cases {
+            assert_eq!(is_query(sql), expect, "sql:{sql}");
+        }
+    }
+
+    #[test]
+    fn test_response_format_from_accept() {
+        let cases = [
+            ("application/json", Some(ResponseFormat::Json)),
+            ("*/*", Some(ResponseFormat::Json)),
+            ("text/csv", Some(ResponseFormat::Csv)),
+            ("Text/CSV; charset=utf-8", Some(ResponseFormat::Csv)),
+            (ARROW_STREAM_CONTENT_TYPE, Some(ResponseFormat::Arrow)),
+            ("text/html, text/csv", Some(ResponseFormat::Csv)),
+            (
+                "text/csv;q=0.5, application/json",
+                Some(ResponseFormat::Json),
+            ),
+            ("text/csv;q=0, */*;q=0.1", Some(ResponseFormat::Json)),
+            ("text/html", None),
+            ("application/json;q=0", None),
+        ];
+
+        for (accept, expect) in cases {
+            assert_eq!(
+                ResponseFormat::from_accept(accept),
+                expect,
+                "accept:{accept}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_encode_affected_rows() {
+        let encode = |format| {
+            OutputEncoder::new(Output::AffectedRows(3), format)
+                .collect::<Result<Vec<_>>>()
+                .unwrap()
+                .concat()
+        };
+
+        let json = encode(ResponseFormat::Json);
+        assert_eq!(
+            json,
+            serde_json::to_vec(&convert_output(Output::AffectedRows(3))).unwrap()
+        );
+
+        let csv = encode(ResponseFormat::Csv);
+        assert_eq!(csv, b"affected_rows\n3\n");
+
+        let arrow = encode(ResponseFormat::Arrow);
+        let batches = decode_record_batches(arrow, CompressionMethod::None).unwrap();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].num_rows(), 1);
+        assert_eq!(batches[0].schema().field(0).name(), AFFECTED_ROWS_COLUMN);
+    }
+
+    #[test]
+    fn test_encode_empty_records() {
+        let json = OutputEncoder::new(Output::Records(Vec::new()), ResponseFormat::Json)
+            .collect::<Result<Vec<_>>>()
+            .unwrap()
+            .concat();
+        let expect = serde_json::to_vec(&convert_output(Output::Records(Vec::new()))).unwrap();
+        assert_eq!(json, expect);
+    }
+}
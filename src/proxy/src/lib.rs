@@ -35,10 +35,14 @@ pub mod instance;
 pub mod limiter;
 mod metrics;
 pub mod opentsdb;
+pub mod query_registry;
+pub mod query_retry;
 mod read;
 pub mod schema_config_provider;
+pub mod shard_epoch;
+pub mod trace;
 mod util;
-mod write;
+pub mod write;
 
 pub const FORWARDED_FROM: &str = "forwarded-from";
 
@@ -54,12 +58,14 @@ use catalog::{
     },
     CatalogRef,
 };
+use cluster::ClusterRef;
 use common_types::{request_id::RequestId, table::DEFAULT_SHARD_ID};
 use futures::FutureExt;
 use generic_error::BoxError;
 use horaedbproto::storage::{
     storage_service_client::StorageServiceClient, PrometheusRemoteQueryRequest,
-    PrometheusRemoteQueryResponse, Route,
+    PrometheusRemoteQueryResponse, RequestContext as GrpcRequestContext,
+    RouteRequest as RouteRequestPb,
 };
 use interpreters::{
     context::Context as InterpreterContext,
@@ -67,28 +73,31 @@ use interpreters::{
     interpreter::{InterpreterPtr, Output},
 };
 use logger::{error, info, warn};
-use query_frontend::plan::Plan;
-use router::{endpoint::Endpoint, RouteRequest, Router};
+use query_frontend::{hint::QueryHints, plan::Plan};
+use router::{endpoint::Endpoint, RouteRequest, RouteWithEpoch, Router};
 use serde::{Deserialize, Serialize};
 use snafu::{OptionExt, ResultExt};
 use table_engine::{
     engine::{CreateTableParams, EngineRuntimes, TableState},
     partition::PartitionInfo,
     remote::model::{GetTableInfoRequest, TableIdentifier, TableInfo},
-    table::{TableId, TableRef},
+    table::{QueryStatsRef, ReadSnapshotRef, TableId, TableRef},
     PARTITION_TABLE_ENGINE_TYPE,
 };
 use tonic::{transport::Channel, IntoRequest};
 
 use crate::{
-    auth::with_file::AuthWithFile,
+    auth::Authenticator,
     context::RequestContext,
     error::{ErrNoCause, ErrWithCause, Error, Internal, Result},
     forward::{ForwardRequest, ForwardResult, Forwarder, ForwarderRef},
     hotspot::HotspotRecorder,
     instance::InstanceRef,
+    query_registry::QueryRegistry,
     read::ReadRequestNotifiers,
     schema_config_provider::SchemaConfigProviderRef,
+    shard_epoch::ShardEpochs,
+    trace::RequestTraceRef,
 };
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -108,38 +117,48 @@ impl Default for SubTableAccessPerm {
 }
 
 pub struct Proxy {
-    auth: AuthWithFile,
+    auth: Authenticator,
     router: Arc<dyn Router + Send + Sync>,
     forwarder: ForwarderRef,
     instance: InstanceRef,
     resp_compress_min_length: usize,
     auto_create_table: bool,
+    /// Whether to write the local tables of one write request atomically if
+    /// the request doesn't decide it.
+    atomic_write: bool,
     schema_config_provider: SchemaConfigProviderRef,
     hotspot_recorder: Arc<HotspotRecorder>,
     engine_runtimes: Arc<EngineRuntimes>,
     cluster_with_meta: bool,
+    /// Cluster of the node, None if the node is deployed without HoraeMeta
+    cluster: Option<ClusterRef>,
     sub_table_access_perm: SubTableAccessPerm,
     request_notifiers: Option<ReadRequestNotifiers>,
     expensive_query_threshold: u64,
+    query_retry: query_retry::Config,
+    query_registry: Arc<QueryRegistry>,
 }
 
 impl Proxy {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
-        auth: AuthWithFile,
+        auth: Authenticator,
         router: Arc<dyn Router + Send + Sync>,
         instance: InstanceRef,
         forward_config: forward::Config,
         local_endpoint: Endpoint,
         resp_compress_min_length: usize,
         auto_create_table: bool,
+        atomic_write: bool,
         schema_config_provider: SchemaConfigProviderRef,
         hotspot_recorder: Arc<HotspotRecorder>,
         engine_runtimes: Arc<EngineRuntimes>,
-        cluster_with_meta: bool,
+        cluster: Option<ClusterRef>,
         sub_table_access_perm: SubTableAccessPerm,
         request_notifiers: Option<ReadRequestNotifiers>,
         expensive_query_threshold: u64,
+        query_retry: query_retry::Config,
+        query_registry: Arc<QueryRegistry>,
     ) -> Self {
         let forwarder = Arc::new(Forwarder::new(
             forward_config,
@@ -154,13 +173,17 @@ impl Proxy {
             forwarder,
             resp_compress_min_length,
             auto_create_table,
+            atomic_write,
             schema_config_provider,
             hotspot_recorder,
             engine_runtimes,
-            cluster_with_meta,
+            cluster_with_meta: cluster.is_some(),
+            cluster,
             sub_table_access_perm,
             request_notifiers,
             expensive_query_threshold,
+            query_retry,
+            query_registry,
         }
     }
 
@@ -168,6 +191,10 @@ impl Proxy {
         self.instance.clone()
     }
 
+    pub fn query_registry(&self) -> Arc<QueryRegistry> {
+        self.query_registry.clone()
+    }
+
     fn default_catalog_name(&self) -> NameRef {
         self.instance.catalog_manager.default_catalog_name()
     }
@@ -444,9 +471,9 @@ impl Proxy {
         Ok(())
     }
 
-    pub(crate) async fn route(&self, req: RouteRequest) -> Result<Vec<Route>> {
+    pub(crate) async fn route_with_epoch(&self, req: RouteRequest) -> Result<Vec<RouteWithEpoch>> {
         self.router
-            .route(req)
+            .route_with_epoch(req)
             .await
             .box_err()
             .context(ErrWithCause {
@@ -455,20 +482,38 @@ impl Proxy {
             })
     }
 
-    async fn execute_plan(
-        &self,
-        request_id: RequestId,
-        catalog: &str,
-        schema: &str,
-        plan: Plan,
-        deadline: Option<Instant>,
-    ) -> Result<Output> {
-        let interpreter =
-            self.build_interpreter(request_id, catalog, schema, plan, deadline, false)?;
-        Self::interpreter_execute_plan(interpreter, deadline).await
+    /// Fetch the latest shard epochs of the tables, and the tables failing to
+    /// be routed are just skipped.
+    ///
+    /// It is expected to be called only when a request fails, so the route
+    /// cache is bypassed to tell the client whether its routes are outdated.
+    pub async fn fetch_shard_epochs(&self, schema: &str, tables: Vec<String>) -> ShardEpochs {
+        if tables.is_empty() {
+            return ShardEpochs::default();
+        }
+
+        let req_pb = RouteRequestPb {
+            context: Some(GrpcRequestContext {
+                database: schema.to_string(),
+            }),
+            tables,
+        };
+        match self
+            .route_with_epoch(RouteRequest::new(req_pb, false))
+            .await
+        {
+            Ok(routes) => routes
+                .into_iter()
+                .filter_map(|v| v.epoch.map(|epoch| (v.route.table, epoch)))
+                .collect(),
+            Err(e) => {
+                warn!("Failed to fetch shard epochs, schema:{schema}, err:{e}");
+                ShardEpochs::default()
+            }
+        }
     }
 
-    async fn execute_plan_involving_partition_table(
+    async fn execute_plan(
         &self,
         request_id: RequestId,
         catalog: &str,
@@ -476,11 +521,21 @@ impl Proxy {
         plan: Plan,
         deadline: Option<Instant>,
     ) -> Result<Output> {
-        let interpreter =
-            self.build_interpreter(request_id, catalog, schema, plan, deadline, true)?;
+        let interpreter = self.build_interpreter(
+            request_id,
+            catalog,
+            schema,
+            plan,
+            deadline,
+            false,
+            None,
+            None,
+            QueryHints::default(),
+        )?;
         Self::interpreter_execute_plan(interpreter, deadline).await
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn build_interpreter(
         &self,
         request_id: RequestId,
@@ -489,12 +544,18 @@ impl Proxy {
         plan: Plan,
         deadline: Option<Instant>,
         enable_partition_table_access: bool,
+        read_snapshot: Option<ReadSnapshotRef>,
+        query_stats: Option<QueryStatsRef>,
+        query_hints: QueryHints,
     ) -> Result<InterpreterPtr> {
         let interpreter_ctx = InterpreterContext::builder(request_id, deadline)
             // Use current ctx's catalog and schema as default catalog and schema
             .default_catalog_and_schema(catalog.to_string(), schema.to_string())
             .enable_partition_table_access(enable_partition_table_access)
             .expensive_query_threshold(self.expensive_query_threshold)
+            .read_snapshot(read_snapshot)
+            .query_stats(query_stats)
+            .query_hints(query_hints)
             .build();
         let interpreter_factory = Factory::new(
             self.instance.query_engine.executor(),
@@ -503,7 +564,8 @@ impl Proxy {
             self.instance.table_engine.clone(),
             self.instance.table_manipulator.clone(),
             self.instance.query_runtime.clone(),
-        );
+        )
+        .with_query_killer(self.query_registry.clone());
         interpreter_factory
             .create(interpreter_ctx, plan)
             .box_err()
@@ -541,6 +603,33 @@ impl Proxy {
     pub fn check_auth(&self, authorization: Option<String>) -> bool {
         self.auth.identify(authorization)
     }
+
+    /// Authenticate the request and return the name of the user, see
+    /// [Authenticator::identify_user].
+    pub fn identify_user(&self, authorization: Option<&str>) -> Option<String> {
+        self.auth.identify_user(authorization)
+    }
+
+    /// Authenticate the user name and password and return the name of the
+    /// verified user, see [Authenticator::identify_password].
+    pub fn identify_password(&self, username: &str, password: &str) -> Option<String> {
+        self.auth.identify_password(username, password)
+    }
+
+    pub fn auth_enabled(&self) -> bool {
+        self.auth.is_enabled()
+    }
+
+    /// The name of the user issuing the request, empty if it is unknown.
+    fn request_user(&self, ctx: &Context) -> String {
+        match &ctx.user {
+            Some(user) => user.clone(),
+            None => self
+                .auth
+                .identify_user(ctx.authorization.as_deref())
+                .unwrap_or_default(),
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -549,6 +638,15 @@ pub struct Context {
     timeout: Option<Duration>,
     forwarded_from: Option<String>,
     authorization: Option<String>,
+    /// Name of the authenticated user, it is resolved from the authorization
+    /// if not set.
+    user: Option<String>,
+    trace: Option<RequestTraceRef>,
+    read_snapshot: Option<ReadSnapshotRef>,
+    verbose: bool,
+    /// Whether to write the tables atomically, the default of the server is
+    /// used if not set.
+    atomic_write: Option<bool>,
 }
 
 impl Context {
@@ -562,6 +660,64 @@ impl Context {
             timeout,
             forwarded_from,
             authorization,
+            user: None,
+            trace: None,
+            read_snapshot: None,
+            verbose: false,
+            atomic_write: None,
+        }
+    }
+
+    /// Set the name of the user already authenticated by the frontend.
+    pub fn with_user(mut self, user: String) -> Self {
+        self.user = Some(user);
+        self
+    }
+
+    /// Attach a trace sample to the context, the request id of the context is
+    /// used as the trace id.
+    pub fn with_trace(mut self, trace: Option<RequestTraceRef>) -> Self {
+        if let Some(trace) = &trace {
+            trace.set_trace_id(self.request_id.clone());
+        }
+        self.trace = trace;
+        self
+    }
+
+    /// Read the tables with the snapshot shared by other queries.
+    pub fn with_read_snapshot(mut self, read_snapshot: ReadSnapshotRef) -> Self {
+        self.read_snapshot = Some(read_snapshot);
+        self
+    }
+
+    /// Mark the request as verbose, all its debug logs are emitted with the
+    /// request id of the context.
+    pub fn with_verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    /// Decide whether to write the tables of the request atomically, instead of
+    /// the default of the server.
+    pub fn with_atomic_write(mut self, atomic_write: Option<bool>) -> Self {
+        self.atomic_write = atomic_write;
+        self
+    }
+
+    #[inline]
+    pub(crate) fn is_verbose(&self) -> bool {
+        self.verbose
+    }
+
+    /// The request id used to mark the verbose logs, `None` if the request is
+    /// not verbose.
+    pub(crate) fn verbose_request_id(&self) -> Option<Arc<str>> {
+        self.verbose.then(|| Arc::from(self.request_id.as_str()))
+    }
+
+    pub(crate) fn record_stage(&self, stage: &'static str, cost: Duration) {
+        if let Some(trace) = &self.trace {
+            trace.record(stage, cost);
         }
     }
 }
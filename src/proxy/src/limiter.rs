@@ -15,17 +15,22 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use std::{collections::HashSet, str::FromStr, sync::RwLock};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    str::FromStr,
+    sync::RwLock,
+    time::Duration,
+};
 
 use datafusion::logical_expr::logical_plan::LogicalPlan;
-use logger::error;
+use logger::{error, info};
 use macros::define_result;
-use query_frontend::plan::Plan;
+use query_frontend::plan::{Plan, QueryPlan};
 use serde::{Deserialize, Serialize};
 use snafu::Snafu;
 use time_ext::ReadableDuration;
 
-use crate::metrics::BLOCKED_REQUEST_COUNTER_VEC_GLOBAL;
+use crate::metrics::{BLOCKED_REQUEST_COUNTER_VEC_GLOBAL, SHED_WRITE_COUNTER_VEC_GLOBAL};
 
 #[derive(Snafu, Debug)]
 #[snafu(visibility(pub))]
@@ -35,6 +40,30 @@ pub enum Error {
 
     #[snafu(display("Table operation is blocked by rule:{:?}, op:{}", rule, op))]
     BlockedByRule { rule: BlockRule, op: String },
+
+    #[snafu(display(
+        "Query is blacklisted, target:{:?}, reason:{}, expire_at:{}",
+        target,
+        reason,
+        expire_at
+    ))]
+    Blacklisted {
+        target: BlacklistTarget,
+        reason: String,
+        expire_at: u64,
+    },
+
+    #[snafu(display(
+        "Write is shed under pressure, table:{}, criticality:{:?}, pressure:{:.2}",
+        table,
+        criticality,
+        pressure
+    ))]
+    WriteShed {
+        table: String,
+        criticality: Criticality,
+        pressure: f64,
+    },
 }
 
 define_result!(Error);
@@ -60,12 +89,161 @@ where
         .map_err(serde::de::Error::custom)
 }
 
+/// Criticality of a table, the writes to the less critical tables are shed
+/// first under pressure.
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+pub enum Criticality {
+    Low,
+    #[default]
+    Normal,
+    /// The writes are never shed.
+    Critical,
+}
+
+impl Criticality {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Criticality::Low => "low",
+            Criticality::Normal => "normal",
+            Criticality::Critical => "critical",
+        }
+    }
+}
+
+/// Thresholds of the write pressure reported by the table engine to shed the
+/// writes, where the pressure is the ratio of the memory used by the writes to
+/// its limit. The pressure above 1.0 means the flushes can't keep up with the
+/// writes.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct WriteSheddingConfig {
+    pub enable: bool,
+    /// The writes to the [Criticality::Low] tables are shed above it
+    pub low_threshold: f64,
+    /// The writes to the [Criticality::Normal] tables are shed above it
+    pub normal_threshold: f64,
+}
+
+impl Default for WriteSheddingConfig {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            low_threshold: 1.2,
+            normal_threshold: 1.5,
+        }
+    }
+}
+
+/// Max number of the audit records of the blacklist kept in memory.
+const MAX_BLACKLIST_AUDIT_RECORDS: usize = 1024;
+
+/// The target to reject temporarily, used to mitigate the incidents caused by
+/// some tables or queries.
+#[derive(Clone, Deserialize, Debug, PartialEq, Eq, Hash, Serialize, PartialOrd, Ord)]
+#[serde(tag = "type", content = "content")]
+pub enum BlacklistTarget {
+    /// Reject all the queries on the table.
+    Table(String),
+    /// Reject the queries whose fingerprint is the given one, see
+    /// [query_fingerprint].
+    Fingerprint(String),
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct BlacklistEntry {
+    pub target: BlacklistTarget,
+    pub reason: String,
+    pub operator: String,
+    /// Unix timestamp in millis.
+    pub created_at: u64,
+    /// Unix timestamp in millis.
+    pub expire_at: u64,
+}
+
+impl BlacklistEntry {
+    #[inline]
+    fn is_expired(&self, now: u64) -> bool {
+        self.expire_at <= now
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum BlacklistAction {
+    Add,
+    Remove,
+    Expire,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct BlacklistAuditRecord {
+    pub action: BlacklistAction,
+    pub target: BlacklistTarget,
+    pub reason: String,
+    pub operator: String,
+    /// Unix timestamp in millis.
+    pub timestamp: u64,
+}
+
+#[derive(Default)]
+struct Blacklist {
+    entries: HashMap<BlacklistTarget, BlacklistEntry>,
+    audit_records: VecDeque<BlacklistAuditRecord>,
+}
+
+impl Blacklist {
+    fn audit(&mut self, action: BlacklistAction, entry: &BlacklistEntry, operator: &str) {
+        info!(
+            "Blacklist is changed, action:{action:?}, target:{:?}, reason:{}, operator:{operator}, expire_at:{}",
+            entry.target, entry.reason, entry.expire_at
+        );
+
+        if self.audit_records.len() >= MAX_BLACKLIST_AUDIT_RECORDS {
+            self.audit_records.pop_front();
+        }
+        self.audit_records.push_back(BlacklistAuditRecord {
+            action,
+            target: entry.target.clone(),
+            reason: entry.reason.clone(),
+            operator: operator.to_string(),
+            timestamp: time_ext::current_time_millis(),
+        });
+    }
+
+    fn purge_expired(&mut self, now: u64) {
+        let expired: Vec<_> = self
+            .entries
+            .values()
+            .filter(|entry| entry.is_expired(now))
+            .map(|entry| entry.target.clone())
+            .collect();
+        for target in expired {
+            if let Some(entry) = self.entries.remove(&target) {
+                self.audit(BlacklistAction::Expire, &entry, &entry.operator);
+            }
+        }
+    }
+}
+
+/// Compute the fingerprint of the query, which is the hash of its logical plan.
+///
+/// It's printed in the slow query log and can be used to blacklist the query.
+pub fn query_fingerprint(plan: &QueryPlan) -> String {
+    let plan = plan.df_plan.display_indent().to_string();
+    format!("{:016x}", hash_ext::hash64(plan.as_bytes()))
+}
+
 #[derive(Default, Clone, Deserialize, Debug, Serialize)]
 #[serde(default)]
 pub struct LimiterConfig {
     pub write_block_list: Vec<String>,
     pub read_block_list: Vec<String>,
     pub rules: Vec<BlockRule>,
+    /// Criticality of the tables, the tables not listed are
+    /// [Criticality::Normal]
+    pub table_criticality: HashMap<String, Criticality>,
+    pub write_shedding: WriteSheddingConfig,
 }
 
 impl BlockRule {
@@ -122,6 +300,9 @@ pub struct Limiter {
     write_block_list: RwLock<HashSet<String>>,
     read_block_list: RwLock<HashSet<String>>,
     rules: RwLock<HashSet<BlockRule>>,
+    blacklist: RwLock<Blacklist>,
+    table_criticality: RwLock<HashMap<String, Criticality>>,
+    write_shedding: WriteSheddingConfig,
 }
 
 impl Default for Limiter {
@@ -130,6 +311,9 @@ impl Default for Limiter {
             write_block_list: RwLock::new(HashSet::new()),
             read_block_list: RwLock::new(HashSet::new()),
             rules: RwLock::new(HashSet::new()),
+            blacklist: RwLock::new(Blacklist::default()),
+            table_criticality: RwLock::new(HashMap::new()),
+            write_shedding: WriteSheddingConfig::default(),
         }
     }
 }
@@ -140,6 +324,9 @@ impl Limiter {
             write_block_list: RwLock::new(limit_config.write_block_list.into_iter().collect()),
             read_block_list: RwLock::new(limit_config.read_block_list.into_iter().collect()),
             rules: RwLock::new(limit_config.rules.into_iter().collect()),
+            blacklist: RwLock::new(Blacklist::default()),
+            table_criticality: RwLock::new(limit_config.table_criticality),
+            write_shedding: limit_config.write_shedding,
         }
     }
 
@@ -180,6 +367,20 @@ impl Limiter {
                     .fail()?;
                 }
             }
+            Plan::Delete(delete) => {
+                if self
+                    .write_block_list
+                    .read()
+                    .unwrap()
+                    .contains(delete.table.name())
+                {
+                    BlockedTable {
+                        table: delete.table.name(),
+                        op: plan.plan_type(),
+                    }
+                    .fail()?;
+                }
+            }
             _ => (),
         }
 
@@ -200,12 +401,53 @@ impl Limiter {
         })
     }
 
+    fn try_limit_by_blacklist(&self, plan: &Plan) -> Result<()> {
+        let query = match plan {
+            Plan::Query(v) => v,
+            _ => return Ok(()),
+        };
+
+        let blacklist = self.blacklist.read().unwrap();
+        if blacklist.entries.is_empty() {
+            return Ok(());
+        }
+
+        let now = time_ext::current_time_millis();
+        let mut fingerprint = None;
+        for entry in blacklist.entries.values() {
+            if entry.is_expired(now) {
+                continue;
+            }
+
+            let hit = match &entry.target {
+                BlacklistTarget::Table(table) => query
+                    .tables
+                    .get(query_frontend::planner::get_table_ref(table))
+                    .is_some(),
+                BlacklistTarget::Fingerprint(target) => {
+                    *fingerprint.get_or_insert_with(|| query_fingerprint(query)) == *target
+                }
+            };
+            if hit {
+                return Blacklisted {
+                    target: entry.target.clone(),
+                    reason: entry.reason.clone(),
+                    expire_at: entry.expire_at,
+                }
+                .fail();
+            }
+        }
+
+        Ok(())
+    }
+
     /// Try to limit the plan according the configured limiter.
     ///
     /// Error will throws if the plan is forbidden to execute.
     pub fn try_limit(&self, plan: &Plan) -> Result<()> {
         let result = {
             self.try_limit_by_block_list(plan)?;
+            self.try_limit_by_blacklist(plan)?;
             self.try_limit_by_rules(plan)
         };
 
@@ -218,6 +460,65 @@ impl Limiter {
         result
     }
 
+    /// Shed the write to the table if the write pressure exceeds the threshold
+    /// of the table's criticality.
+    pub fn try_shed_write(&self, table: &str, pressure: f64) -> Result<()> {
+        if !self.write_shedding.enable {
+            return Ok(());
+        }
+
+        let criticality = self.table_criticality(table);
+        let threshold = match criticality {
+            Criticality::Low => self.write_shedding.low_threshold,
+            Criticality::Normal => self.write_shedding.normal_threshold,
+            Criticality::Critical => return Ok(()),
+        };
+        if pressure <= threshold {
+            return Ok(());
+        }
+
+        SHED_WRITE_COUNTER_VEC_GLOBAL
+            .with_label_values(&[criticality.as_str()])
+            .inc();
+        WriteShed {
+            table,
+            criticality,
+            pressure,
+        }
+        .fail()
+    }
+
+    pub fn table_criticality(&self, table: &str) -> Criticality {
+        self.table_criticality
+            .read()
+            .unwrap()
+            .get(table)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn get_table_criticality(&self) -> HashMap<String, Criticality> {
+        self.table_criticality.read().unwrap().clone()
+    }
+
+    pub fn add_table_criticality(&self, table_criticality: HashMap<String, Criticality>) {
+        self.table_criticality
+            .write()
+            .unwrap()
+            .extend(table_criticality)
+    }
+
+    pub fn set_table_criticality(&self, table_criticality: HashMap<String, Criticality>) {
+        *self.table_criticality.write().unwrap() = table_criticality;
+    }
+
+    pub fn remove_table_criticality(&self, tables: impl IntoIterator<Item = String>) {
+        let mut table_criticality = self.table_criticality.write().unwrap();
+        for table in tables {
+            table_criticality.remove(&table);
+        }
+    }
+
     pub fn add_write_block_list(&self, block_list: Vec<String>) {
         self.write_block_list.write().unwrap().extend(block_list)
     }
@@ -276,18 +577,70 @@ impl Limiter {
         let new_rule_set: HashSet<_> = new_rules.into_iter().collect();
         *self.rules.write().unwrap() = new_rule_set;
     }
+
+    /// Blacklist the targets for the given duration, and the existing entries
+    /// of the same targets will be replaced.
+    pub fn add_blacklist(
+        &self,
+        targets: Vec<BlacklistTarget>,
+        ttl: Duration,
+        reason: String,
+        operator: &str,
+    ) {
+        let now = time_ext::current_time_millis();
+        let expire_at = now.saturating_add(ttl.as_millis() as u64);
+
+        let mut blacklist = self.blacklist.write().unwrap();
+        for target in targets {
+            let entry = BlacklistEntry {
+                target: target.clone(),
+                reason: reason.clone(),
+                operator: operator.to_string(),
+                created_at: now,
+                expire_at,
+            };
+            blacklist.audit(BlacklistAction::Add, &entry, operator);
+            blacklist.entries.insert(target, entry);
+        }
+    }
+
+    pub fn remove_blacklist(&self, targets: &[BlacklistTarget], operator: &str) {
+        let mut blacklist = self.blacklist.write().unwrap();
+        for target in targets {
+            if let Some(entry) = blacklist.entries.remove(target) {
+                blacklist.audit(BlacklistAction::Remove, &entry, operator);
+            }
+        }
+    }
+
+    /// Get the unexpired blacklist entries, and the expired ones are purged.
+    pub fn get_blacklist(&self) -> Vec<BlacklistEntry> {
+        let mut blacklist = self.blacklist.write().unwrap();
+        blacklist.purge_expired(time_ext::current_time_millis());
+        blacklist.entries.values().cloned().collect()
+    }
+
+    pub fn get_blacklist_audit_records(&self) -> Vec<BlacklistAuditRecord> {
+        let blacklist = self.blacklist.read().unwrap();
+        blacklist.audit_records.iter().cloned().collect()
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
     use common_types::request_id::RequestId;
     use query_frontend::{
         config::DynamicConfig, parser::Parser, plan::Plan, planner::Planner,
         tests::MockMetaProvider,
     };
 
-    use super::{BlockRule, LimiterConfig};
-    use crate::limiter::Limiter;
+    use super::{
+        BlacklistAction, BlacklistTarget, BlockRule, Criticality, LimiterConfig,
+        WriteSheddingConfig,
+    };
+    use crate::limiter::{query_fingerprint, Limiter};
 
     fn sql_to_plan(meta_provider: &MockMetaProvider, sql: &str) -> Plan {
         let dyn_config = DynamicConfig::default();
@@ -434,4 +787,111 @@ mod tests {
         limiter.set_block_rules(vec![BlockRule::QueryWithoutPredicate]);
         assert!(limiter.try_limit(&query_plan).is_err());
     }
+    #[test]
+    fn test_limiter_blacklist() {
+        let mock = MockMetaProvider::default();
+        let limiter = Limiter::default();
+
+        let query_plan = sql_to_plan(&mock, "select * from test_table");
+        let query_plan2 = sql_to_plan(&mock, "select * from test_table where field1 = 10");
+        let insert="INSERT INTO test_table(key1, key2, field1,field2) VALUES('tagk', 1638428434000,100, 'hello3')";
+        let insert_plan = sql_to_plan(&mock, insert);
+
+        let table = BlacklistTarget::Table("test_table".to_string());
+        limiter.add_blacklist(
+            vec![table.clone()],
+            Duration::from_secs(60),
+            "too many queries".to_string(),
+            "admin",
+        );
+        assert!(limiter.try_limit(&query_plan).is_err());
+        assert!(limiter.try_limit(&query_plan2).is_err());
+        // Only the queries are rejected.
+        assert!(limiter.try_limit(&insert_plan).is_ok());
+        limiter.remove_blacklist(&[table], "admin");
+        assert!(limiter.try_limit(&query_plan).is_ok());
+
+        let fingerprint = match &query_plan {
+            Plan::Query(v) => query_fingerprint(v),
+            _ => unreachable!(),
+        };
+        limiter.add_blacklist(
+            vec![BlacklistTarget::Fingerprint(fingerprint)],
+            Duration::from_secs(60),
+            "full scan".to_string(),
+            "admin",
+        );
+        assert!(limiter.try_limit(&query_plan).is_err());
+        assert!(limiter.try_limit(&query_plan2).is_ok());
+        assert_eq!(limiter.get_blacklist().len(), 1);
+
+        let actions: Vec<_> = limiter
+            .get_blacklist_audit_records()
+            .into_iter()
+            .map(|v| v.action)
+            .collect();
+        assert_eq!(
+            actions,
+            vec![
+                BlacklistAction::Add,
+                BlacklistAction::Remove,
+                BlacklistAction::Add
+            ]
+        );
+    }
+
+    #[test]
+    fn test_limiter_blacklist_expire() {
+        let mock = MockMetaProvider::default();
+        let limiter = Limiter::default();
+        let query_plan = sql_to_plan(&mock, "select * from test_table");
+
+        limiter.add_blacklist(
+            vec![BlacklistTarget::Table("test_table".to_string())],
+            Duration::ZERO,
+            "expired".to_string(),
+            "admin",
+        );
+        assert!(limiter.try_limit(&query_plan).is_ok());
+        assert!(limiter.get_blacklist().is_empty());
+
+        let records = limiter.get_blacklist_audit_records();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].action, BlacklistAction::Expire);
+    }
+
+    #[test]
+    fn test_limiter_shed_write() {
+        let limiter_config = LimiterConfig {
+            table_criticality: [
+                ("low_table".to_string(), Criticality::Low),
+                ("critical_table".to_string(), Criticality::Critical),
+            ]
+            .into_iter()
+            .collect(),
+            write_shedding: WriteSheddingConfig {
+                enable: true,
+                low_threshold: 0.8,
+                normal_threshold: 0.95,
+            },
+            ..Default::default()
+        };
+        let limiter = Limiter::new(limiter_config);
+
+        let tables = ["low_table", "normal_table", "critical_table"];
+        let shed_tables = |pressure| -> Vec<_> {
+            tables
+                .iter()
+                .filter(|table| limiter.try_shed_write(table, pressure).is_err())
+                .copied()
+                .collect()
+        };
+        assert!(shed_tables(0.5).is_empty());
+        assert_eq!(shed_tables(0.9), vec!["low_table"]);
+        assert_eq!(shed_tables(1.5), vec!["low_table", "normal_table"]);
+
+        limiter.remove_table_criticality(["low_table".to_string()]);
+        assert_eq!(limiter.table_criticality("low_table"), Criticality::Normal);
+        assert!(shed_tables(0.9).is_empty());
+    }
 }
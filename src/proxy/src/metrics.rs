@@ -38,6 +38,7 @@ make_auto_flush_static_metric! {
         query_succeeded_row,
         query_affected_row,
         dedupped_stream_query,
+        query_retried,
     }
 
     pub struct GrpcHandlerCounterVec: LocalIntCounter {
@@ -70,6 +71,19 @@ lazy_static! {
         &["type"]
     )
     .unwrap();
+    pub static ref SHED_WRITE_COUNTER_VEC_GLOBAL: IntCounterVec = register_int_counter_vec!(
+        "shed_write_counter",
+        "Writes shed under pressure by the criticality of the tables",
+        &["criticality"]
+    )
+    .unwrap();
+    pub static ref SHARD_EPOCH_DROPPED_COUNTER_VEC_GLOBAL: IntCounterVec =
+        register_int_counter_vec!(
+            "shard_epoch_dropped_counter",
+            "Shard epochs dropped from the response headers",
+            &["reason"]
+        )
+        .unwrap();
 }
 
 lazy_static! {
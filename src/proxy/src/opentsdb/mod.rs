@@ -69,7 +69,9 @@ impl Proxy {
             }),
             table_requests: write_table_requests,
         };
-        let proxy_context = Context::new(ctx.timeout, None, ctx.authorization);
+        let proxy_context = Context::new(ctx.timeout, None, ctx.authorization)
+            .with_trace(ctx.trace)
+            .with_verbose(ctx.verbose);
 
         match self
             .handle_write_internal(proxy_context, table_request)
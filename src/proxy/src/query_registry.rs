@@ -0,0 +1,208 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+//! Registry of the in-flight queries, used to list and kill them.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    time::Instant,
+};
+
+use interpreters::kill::QueryKiller;
+use logger::info;
+use system_catalog::queries::{ActiveQueries, QueryInfo};
+use table_engine::table::{QueryStats, QueryStatsRef};
+use time_ext::{current_time_millis, InstantExt};
+use tokio::sync::Notify;
+
+struct QueryEntry {
+    sql: String,
+    user: String,
+    start_time_ms: i64,
+    start: Instant,
+    stats: QueryStatsRef,
+    killed: AtomicBool,
+    kill_notify: Notify,
+}
+
+impl QueryEntry {
+    fn kill(&self) {
+        self.killed.store(true, Ordering::Release);
+        self.kill_notify.notify_waiters();
+    }
+}
+
+/// Registry of the queries being executed on this node.
+#[derive(Default)]
+pub struct QueryRegistry {
+    queries: RwLock<HashMap<String, Arc<QueryEntry>>>,
+}
+
+impl fmt::Debug for QueryRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QueryRegistry")
+            .field("num_queries", &self.queries.read().unwrap().len())
+            .finish()
+    }
+}
+
+impl QueryRegistry {
+    /// Register a query issued by the `user`, which is deregistered when the
+    /// returned guard is dropped.
+    pub fn register(self: &Arc<Self>, query_id: String, sql: &str, user: String) -> QueryGuard {
+        let entry = Arc::new(QueryEntry {
+            sql: sql.to_string(),
+            user,
+            start_time_ms: current_time_millis() as i64,
+            start: Instant::now(),
+            stats: Arc::new(QueryStats::default()),
+            killed: AtomicBool::new(false),
+            kill_notify: Notify::new(),
+        });
+        self.queries
+            .write()
+            .unwrap()
+            .insert(query_id.clone(), entry.clone());
+
+        QueryGuard {
+            registry: self.clone(),
+            query_id,
+            entry,
+        }
+    }
+
+    pub fn list(&self) -> Vec<QueryInfo> {
+        let mut queries: Vec<_> = self
+            .queries
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(query_id, entry)| QueryInfo {
+                query_id: query_id.clone(),
+                sql: entry.sql.clone(),
+                user: entry.user.clone(),
+                start_time_ms: entry.start_time_ms,
+                elapsed_ms: entry.start.saturating_elapsed().as_millis() as u64,
+                scanned_bytes: entry.stats.scanned_bytes(),
+                killed: entry.killed.load(Ordering::Acquire),
+            })
+            .collect();
+        queries.sort_by_key(|query| query.start_time_ms);
+
+        queries
+    }
+
+    /// The user issuing the query, None if the query is not found.
+    pub fn owner(&self, query_id: &str) -> Option<String> {
+        self.queries
+            .read()
+            .unwrap()
+            .get(query_id)
+            .map(|entry| entry.user.clone())
+    }
+
+    /// Kill the query, return false if the query is not found.
+    pub fn kill(&self, query_id: &str) -> bool {
+        let entry = self.queries.read().unwrap().get(query_id).cloned();
+        match entry {
+            Some(entry) => {
+                info!("Kill query, query_id:{query_id}, sql:{}", entry.sql);
+                entry.kill();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl ActiveQueries for QueryRegistry {
+    fn list(&self) -> Vec<QueryInfo> {
+        QueryRegistry::list(self)
+    }
+}
+
+impl QueryKiller for QueryRegistry {
+    fn kill(&self, query_id: &str) -> bool {
+        QueryRegistry::kill(self, query_id)
+    }
+}
+
+/// Guard of a registered query.
+pub struct QueryGuard {
+    registry: Arc<QueryRegistry>,
+    query_id: String,
+    entry: Arc<QueryEntry>,
+}
+
+impl QueryGuard {
+    pub fn stats(&self) -> QueryStatsRef {
+        self.entry.stats.clone()
+    }
+
+    /// Wait until the query is killed.
+    pub async fn killed(&self) {
+        loop {
+            let notified = self.entry.kill_notify.notified();
+            if self.entry.killed.load(Ordering::Acquire) {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+impl Drop for QueryGuard {
+    fn drop(&mut self) {
+        self.registry
+            .queries
+            .write()
+            .unwrap()
+            .remove(&self.query_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_register_and_kill() {
+        let registry = Arc::new(QueryRegistry::default());
+        let guard = registry.register("1".to_string(), "select 1", "user".to_string());
+        guard.stats().add_scanned_bytes(10);
+
+        let queries = registry.list();
+        assert_eq!(1, queries.len());
+        assert_eq!("select 1", queries[0].sql);
+        assert_eq!(10, queries[0].scanned_bytes);
+        assert!(!queries[0].killed);
+        assert_eq!(Some("user".to_string()), registry.owner("1"));
+        assert_eq!(None, registry.owner("2"));
+
+        assert!(!registry.kill("2"));
+        assert!(registry.kill("1"));
+        guard.killed().await;
+        assert!(registry.list()[0].killed);
+
+        drop(guard);
+        assert!(registry.list().is_empty());
+    }
+}
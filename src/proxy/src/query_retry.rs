@@ -0,0 +1,209 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Retry of the queries failed by transient engine errors.
+//!
+//! Only the plans without side effects are retried, and the errors are
+//! classified by walking through the source chain, so the decision is
+//! deterministic for the same plan and error.
+
+use std::{
+    error::Error as StdError,
+    io::ErrorKind,
+    time::{Duration, Instant},
+};
+
+use datafusion::{error::DataFusionError, logical_expr::LogicalPlan};
+use object_store::ObjectStoreError;
+use query_frontend::plan::Plan;
+use serde::{Deserialize, Serialize};
+use time_ext::ReadableDuration;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    pub enable: bool,
+    /// Max retry times of a failed query
+    pub max_retries: usize,
+    /// The interval between two attempts
+    pub retry_interval: ReadableDuration,
+    /// No more retry is made once the query has taken longer than this
+    pub max_elapsed: ReadableDuration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enable: true,
+            max_retries: 2,
+            retry_interval: ReadableDuration::millis(100),
+            max_elapsed: ReadableDuration::secs(5),
+        }
+    }
+}
+
+impl Config {
+    /// Whether another attempt is allowed after `retries` retries have been
+    /// made and `elapsed` has been taken.
+    pub(crate) fn allow_retry(
+        &self,
+        retries: usize,
+        elapsed: Duration,
+        deadline: Option<Instant>,
+    ) -> bool {
+        if !self.enable || retries >= self.max_retries {
+            return false;
+        }
+
+        if elapsed + self.retry_interval.0 > self.max_elapsed.0 {
+            return false;
+        }
+
+        deadline.map_or(true, |deadline| {
+            Instant::now() + self.retry_interval.0 < deadline
+        })
+    }
+}
+
+/// Whether the plan is safe to be executed again, that is to say, it only reads
+/// data.
+pub(crate) fn is_side_effect_free(plan: &Plan) -> bool {
+    match plan {
+        Plan::Query(plan) => is_read_only(&plan.df_plan),
+        Plan::Insert(_)
+        | Plan::Delete(_)
+        | Plan::Create(_)
+        | Plan::CreateTables(_)
+        | Plan::Drop(_)
+        | Plan::Describe(_)
+        | Plan::AlterTable(_)
+        | Plan::Show(_)
+        | Plan::Exists(_)
+        | Plan::KillQuery(_) => false,
+    }
+}
+
+fn is_read_only(plan: &LogicalPlan) -> bool {
+    match plan {
+        LogicalPlan::Dml(_)
+        | LogicalPlan::Ddl(_)
+        | LogicalPlan::Copy(_)
+        | LogicalPlan::Statement(_) => false,
+        _ => plan.inputs().into_iter().all(is_read_only),
+    }
+}
+
+/// Whether the error is caused by a transient engine error, e.g. the sst has
+/// been purged by a concurrent compaction, or the object store is temporarily
+/// unavailable.
+pub(crate) fn is_retryable(err: &(dyn StdError + 'static)) -> bool {
+    let mut next = Some(err);
+    while let Some(err) = next {
+        if let Some(e) = err.downcast_ref::<ObjectStoreError>() {
+            return matches!(
+                e,
+                ObjectStoreError::NotFound { .. } | ObjectStoreError::Generic { .. }
+            );
+        }
+
+        if let Some(DataFusionError::ObjectStore(_)) = err.downcast_ref::<DataFusionError>() {
+            return true;
+        }
+
+        if let Some(e) = err.downcast_ref::<std::io::Error>() {
+            return matches!(
+                e.kind(),
+                ErrorKind::TimedOut
+                    | ErrorKind::Interrupted
+                    | ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+            );
+        }
+
+        next = err.source();
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use generic_error::BoxError;
+    use snafu::ResultExt;
+
+    use super::*;
+    use crate::error::{Error, Internal};
+
+    fn wrap<E: StdError + Send + Sync + 'static>(e: E) -> Error {
+        Err::<(), _>(e)
+            .box_err()
+            .context(Internal { msg: "test" })
+            .unwrap_err()
+    }
+
+    #[test]
+    fn test_is_retryable() {
+        let not_found = ObjectStoreError::NotFound {
+            path: "1.sst".to_string(),
+            source: "not found".into(),
+        };
+        assert!(is_retryable(&wrap(not_found)));
+
+        let generic = ObjectStoreError::Generic {
+            store: "s3",
+            source: "connection closed".into(),
+        };
+        assert!(is_retryable(&wrap(DataFusionError::External(Box::new(
+            generic
+        )))));
+
+        let timeout = std::io::Error::from(ErrorKind::TimedOut);
+        assert!(is_retryable(&wrap(timeout)));
+
+        let invalid = std::io::Error::from(ErrorKind::InvalidData);
+        assert!(!is_retryable(&wrap(invalid)));
+
+        let plan_err = DataFusionError::Plan("invalid plan".to_string());
+        assert!(!is_retryable(&wrap(plan_err)));
+    }
+
+    #[test]
+    fn test_allow_retry() {
+        let config = Config {
+            enable: true,
+            max_retries: 2,
+            retry_interval: ReadableDuration::millis(100),
+            max_elapsed: ReadableDuration::secs(1),
+        };
+
+        assert!(config.allow_retry(0, Duration::ZERO, None));
+        assert!(config.allow_retry(1, Duration::from_millis(500), None));
+        // Exceed max retries.
+        assert!(!config.allow_retry(2, Duration::ZERO, None));
+        // Exceed max elapsed.
+        assert!(!config.allow_retry(0, Duration::from_millis(950), None));
+        // Exceed deadline.
+        let deadline = Instant::now() + Duration::from_millis(50);
+        assert!(!config.allow_retry(0, Duration::ZERO, Some(deadline)));
+
+        let disabled = Config {
+            enable: false,
+            ..config
+        };
+        assert!(!disabled.allow_retry(0, Duration::ZERO, None));
+    }
+}
@@ -17,32 +17,48 @@
 
 //! Contains common methods used by the read process.
 
-use std::{sync::Arc, time::Duration};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use futures::FutureExt;
+use common_types::{record_batch::RecordBatch, request_id::RequestId};
+use futures::{
+    stream::{self, BoxStream},
+    FutureExt, StreamExt,
+};
 use generic_error::BoxError;
 use horaedbproto::storage::{
     storage_service_client::StorageServiceClient, RequestContext, SqlQueryRequest, SqlQueryResponse,
 };
 use http::StatusCode;
-use interpreters::interpreter::Output;
+use interpreters::interpreter::{Output, OutputStream};
 use logger::{error, info, warn, SlowTimer};
 use notifier::notifier::{ExecutionGuard, RequestNotifiers, RequestResult};
 use query_frontend::{
+    ast::Statement,
     frontend,
     frontend::{Context as SqlContext, Frontend},
-    plan::{Plan, PriorityContext},
+    hint::QueryHints,
+    plan::{CreateTablesPlan, Plan, PriorityContext},
     provider::CatalogMetaProvider,
 };
 use router::endpoint::Endpoint;
 use snafu::{ensure, ResultExt};
+use table_engine::table::{QueryStatsRef, ReadSnapshotRef};
+use time_ext::InstantExt;
 use tokio::sync::mpsc::{self, Sender};
 use tonic::{transport::Channel, IntoRequest};
 
 use crate::{
     error::{ErrNoCause, ErrWithCause, Error, Internal, InternalNoCause, Result},
     forward::{ForwardRequest, ForwardResult},
+    http::sql::convert_sql_response_to_output,
+    limiter,
     metrics::GRPC_HANDLER_COUNTER_VEC,
+    query_registry::QueryGuard,
+    query_retry,
+    trace::{STAGE_EXECUTE, STAGE_FORWARD, STAGE_PARSE, STAGE_PLAN},
     Context, Proxy,
 };
 
@@ -54,7 +70,63 @@ pub enum SqlResponse {
     Local(Output),
 }
 
+pub type RecordBatchStream = BoxStream<'static, Result<RecordBatch>>;
+
+/// Output of the sql query whose records are returned as a stream.
+pub enum SqlOutputStream {
+    AffectedRows(usize),
+    Records(RecordBatchStream),
+}
+
+impl From<Output> for SqlOutputStream {
+    fn from(output: Output) -> Self {
+        match output {
+            Output::AffectedRows(n) => SqlOutputStream::AffectedRows(n),
+            Output::Records(records) => {
+                SqlOutputStream::Records(stream::iter(records.into_iter().map(Ok)).boxed())
+            }
+        }
+    }
+}
+
+/// The plan of the sql to execute.
+struct PreparedSqlQuery<'a> {
+    plan: Plan,
+    query_hints: QueryHints,
+    deadline: Option<Instant>,
+    slow_timer: SlowTimer<'a>,
+    query_guard: QueryGuard,
+}
+
 impl Proxy {
+    /// Fill the options of the tables to create with the defaults configured
+    /// for the schema.
+    fn apply_default_table_options(&self, schema: &str, plan: &mut Plan) -> Result<()> {
+        let plans = match plan {
+            Plan::Create(plan) => std::slice::from_mut(plan),
+            Plan::CreateTables(plan) => plan.plans.as_mut_slice(),
+            _ => return Ok(()),
+        };
+
+        let schema_config = self
+            .schema_config_provider
+            .schema_config(schema)
+            .box_err()
+            .with_context(|| ErrWithCause {
+                code: StatusCode::INTERNAL_SERVER_ERROR,
+                msg: format!("Fail to fetch schema config, schema:{schema}"),
+            })?;
+        if let Some(schema_config) = schema_config {
+            for plan in plans {
+                schema_config
+                    .default_table_options
+                    .apply_to(&mut plan.options);
+            }
+        }
+
+        Ok(())
+    }
+
     pub(crate) async fn handle_sql(
         &self,
         ctx: &Context,
@@ -63,27 +135,61 @@ impl Proxy {
         enable_partition_table_access: bool,
         enable_block_query: bool, // true for grpc, false for http
     ) -> Result<SqlResponse> {
-        if let Some(resp) = self
-            .maybe_forward_sql_query(ctx.clone(), schema, sql)
-            .await?
-        {
-            match resp {
-                ForwardResult::Forwarded(resp) => return Ok(SqlResponse::Forwarded(resp?)),
-                ForwardResult::Local => (),
-            }
-        };
+        // Emit all the debug logs of the request if it's marked as verbose.
+        runtime::verbose::scope(ctx.verbose_request_id(), async {
+            let begin = Instant::now();
+            if let Some(resp) = self
+                .maybe_forward_sql_query(ctx.clone(), schema, sql)
+                .await?
+            {
+                match resp {
+                    ForwardResult::Forwarded(resp) => {
+                        ctx.record_stage(STAGE_FORWARD, begin.saturating_elapsed());
+                        return Ok(SqlResponse::Forwarded(resp?));
+                    }
+                    ForwardResult::Local => (),
+                }
+            };
 
-        let output = self
-            .fetch_sql_query_output(
-                ctx,
-                schema,
-                sql,
-                enable_partition_table_access,
-                enable_block_query,
-            )
-            .await?;
+            let output = self
+                .fetch_sql_query_output(
+                    ctx,
+                    schema,
+                    sql,
+                    enable_partition_table_access,
+                    enable_block_query,
+                )
+                .await?;
 
-        Ok(SqlResponse::Local(output))
+            Ok(SqlResponse::Local(output))
+        })
+        .await
+    }
+
+    /// Handle the sql like [Proxy::handle_sql], but the records of the local
+    /// query are returned as a stream.
+    pub(crate) async fn handle_sql_stream(
+        &self,
+        ctx: &Context,
+        schema: &str,
+        sql: &str,
+        enable_partition_table_access: bool,
+    ) -> Result<SqlOutputStream> {
+        runtime::verbose::scope(ctx.verbose_request_id(), async {
+            let begin = Instant::now();
+            if let Some(ForwardResult::Forwarded(resp)) = self
+                .maybe_forward_sql_query(ctx.clone(), schema, sql)
+                .await?
+            {
+                ctx.record_stage(STAGE_FORWARD, begin.saturating_elapsed());
+                let output = convert_sql_response_to_output(resp?)?;
+                return Ok(SqlOutputStream::from(output));
+            }
+
+            self.fetch_sql_query_stream(ctx, schema, sql, enable_partition_table_access)
+                .await
+        })
+        .await
     }
 
     pub(crate) async fn dedup_handle_sql(
@@ -112,6 +218,7 @@ impl Proxy {
             }
         };
 
+        let begin = Instant::now();
         if let Some(resp) = self
             .maybe_forward_sql_query(ctx.clone(), schema, sql)
             .await?
@@ -119,6 +226,7 @@ impl Proxy {
             match resp {
                 ForwardResult::Forwarded(resp) => {
                     let resp = resp?;
+                    ctx.record_stage(STAGE_FORWARD, begin.saturating_elapsed());
                     guard.cancel();
                     let notifiers = request_notifiers.take_notifiers(&sql.to_string()).unwrap();
                     for notifier in &notifiers {
@@ -173,6 +281,145 @@ impl Proxy {
         enable_partition_table_access: bool,
         enable_block_query: bool,
     ) -> Result<Output> {
+        let request_id = &ctx.request_id;
+        let catalog = self.instance.catalog_manager.default_catalog_name();
+        let PreparedSqlQuery {
+            plan,
+            query_hints,
+            deadline,
+            slow_timer,
+            query_guard,
+        } = self
+            .prepare_sql_query(ctx, schema, sql, enable_block_query)
+            .await?;
+
+        let stage_begin = Instant::now();
+        let execute = self.execute_plan_with_retry(
+            request_id,
+            catalog,
+            schema,
+            plan,
+            deadline,
+            enable_partition_table_access,
+            ctx.read_snapshot.clone(),
+            Some(query_guard.stats()),
+            query_hints,
+        );
+        // Dropping the execution cancels the query, including the remote reads.
+        let output = tokio::select! {
+            output = execute => output,
+            _ = query_guard.killed() => {
+                return ErrNoCause {
+                    code: StatusCode::BAD_REQUEST,
+                    msg: format!("Query is killed, request_id:{request_id}"),
+                }
+                .fail();
+            }
+        };
+        let output = output.box_err().with_context(|| ErrWithCause {
+            code: StatusCode::INTERNAL_SERVER_ERROR,
+            msg: "Failed to execute plan",
+        })?;
+        ctx.record_stage(STAGE_EXECUTE, stage_begin.saturating_elapsed());
+
+        let cost = slow_timer.elapsed();
+        info!(
+            "Handle sql query finished, sql:{sql}, elapsed:{cost:?}, catalog:{catalog}, schema:{schema}, ctx:{ctx:?}",
+        );
+
+        Ok(output)
+    }
+
+    /// Execute the sql on this node, and the records are returned as a stream
+    /// without being collected.
+    ///
+    /// The query is deregistered once the stream is dropped, and the stream
+    /// is terminated with an error if the query is killed.
+    pub(crate) async fn fetch_sql_query_stream(
+        &self,
+        ctx: &Context,
+        schema: &str,
+        sql: &str,
+        enable_partition_table_access: bool,
+    ) -> Result<SqlOutputStream> {
+        let request_id = ctx.request_id.clone();
+        let catalog = self.instance.catalog_manager.default_catalog_name();
+        let PreparedSqlQuery {
+            plan,
+            query_hints,
+            deadline,
+            slow_timer: _,
+            query_guard,
+        } = self.prepare_sql_query(ctx, schema, sql, false).await?;
+
+        let interpreter = self.build_interpreter(
+            request_id.clone(),
+            catalog,
+            schema,
+            plan,
+            deadline,
+            enable_partition_table_access,
+            ctx.read_snapshot.clone(),
+            Some(query_guard.stats()),
+            query_hints,
+        )?;
+        let output = tokio::select! {
+            output = interpreter.execute_to_stream() => output,
+            _ = query_guard.killed() => {
+                return ErrNoCause {
+                    code: StatusCode::BAD_REQUEST,
+                    msg: format!("Query is killed, request_id:{request_id}"),
+                }
+                .fail();
+            }
+        };
+        let output = output.box_err().with_context(|| ErrWithCause {
+            code: StatusCode::INTERNAL_SERVER_ERROR,
+            msg: "Failed to execute plan",
+        })?;
+
+        let records = match output {
+            OutputStream::AffectedRows(n) => return Ok(SqlOutputStream::AffectedRows(n)),
+            OutputStream::Records(records) => records,
+        };
+        // The guard is held by the stream, so the query can be killed until the
+        // stream is dropped.
+        let records = stream::unfold(Some((records, query_guard)), move |state| {
+            let request_id = request_id.clone();
+            async move {
+                let (mut records, query_guard) = state?;
+                tokio::select! {
+                    item = records.next() => {
+                        let item = item?.box_err().context(ErrWithCause {
+                            code: StatusCode::INTERNAL_SERVER_ERROR,
+                            msg: "Failed to poll records",
+                        });
+                        Some((item, Some((records, query_guard))))
+                    }
+                    _ = query_guard.killed() => {
+                        let e = ErrNoCause {
+                            code: StatusCode::BAD_REQUEST,
+                            msg: format!("Query is killed, request_id:{request_id}"),
+                        }
+                        .fail();
+                        Some((e, None))
+                    }
+                }
+            }
+        });
+
+        Ok(SqlOutputStream::Records(records.boxed()))
+    }
+
+    /// Parse and plan the sql, the query is registered until the returned
+    /// guard is dropped.
+    async fn prepare_sql_query<'a>(
+        &self,
+        ctx: &'a Context,
+        schema: &str,
+        sql: &'a str,
+        enable_block_query: bool,
+    ) -> Result<PreparedSqlQuery<'a>> {
         let request_id = &ctx.request_id;
         let slow_threshold_secs = self
             .instance()
@@ -186,6 +433,11 @@ impl Proxy {
 
         info!("Handle sql query begin, request_id:{request_id}, catalog:{catalog}, schema:{schema}, ctx:{ctx:?}, sql:{sql}");
 
+        let user = self.request_user(ctx);
+        let query_guard = self
+            .query_registry
+            .register(request_id.to_string(), sql, user.clone());
+
         let instance = &self.instance;
         // TODO(yingwen): Privilege check, cannot access data of other tenant
         // TODO(yingwen): Maybe move MetaProvider to instance
@@ -200,6 +452,7 @@ impl Proxy {
         let mut sql_ctx = SqlContext::new(request_id.clone(), deadline);
         // Parse sql, frontend error of invalid sql already contains sql
         // TODO(yingwen): Maybe move sql from frontend error to outer error
+        let stage_begin = Instant::now();
         let mut stmts = frontend
             .parse_sql(&mut sql_ctx, sql)
             .box_err()
@@ -207,14 +460,21 @@ impl Proxy {
                 code: StatusCode::BAD_REQUEST,
                 msg: "Failed to parse sql",
             })?;
+        let query_hints = QueryHints::parse(sql);
+        ctx.record_stage(STAGE_PARSE, stage_begin.saturating_elapsed());
 
-        // TODO: For simplicity, we only support executing one statement
+        // TODO: For simplicity, we only support executing one statement, except that
+        // multiple create table statements are executed as a batch.
         let stmts_len = stmts.len();
+        let is_batch_create = stmts_len > 1
+            && stmts
+                .iter()
+                .all(|stmt| matches!(stmt, Statement::Create(_)));
         ensure!(
-            stmts_len == 1,
+            stmts_len == 1 || is_batch_create,
             ErrNoCause {
                 code: StatusCode::BAD_REQUEST,
-                msg: format!("Only support execute one statement now, current num:{stmts_len}"),
+                msg: format!("Only support execute one statement or create tables in batch now, current num:{stmts_len}"),
             }
         );
 
@@ -227,15 +487,43 @@ impl Proxy {
 
         // Create logical plan
         // Note: Remember to store sql in error when creating logical plan
-        let plan = frontend
-            // TODO(yingwen): Check error, some error may indicate that the sql is invalid. Now we
-            // return internal server error in those cases
-            .statement_to_plan(&sql_ctx, stmts.remove(0))
-            .box_err()
-            .with_context(|| ErrWithCause {
-                code: StatusCode::INTERNAL_SERVER_ERROR,
-                msg: "Failed to create plan",
-            })?;
+        let stage_begin = Instant::now();
+        let mut plan = if is_batch_create {
+            let mut plans = Vec::with_capacity(stmts_len);
+            for stmt in stmts {
+                let plan = frontend
+                    .statement_to_plan(&sql_ctx, stmt)
+                    .box_err()
+                    .with_context(|| ErrWithCause {
+                        code: StatusCode::INTERNAL_SERVER_ERROR,
+                        msg: "Failed to create plan",
+                    })?;
+                let Plan::Create(plan) = plan else {
+                    return InternalNoCause {
+                        msg: format!("Unexpected plan to create table, plan:{plan:?}"),
+                    }
+                    .fail();
+                };
+                plans.push(plan);
+            }
+            Plan::CreateTables(CreateTablesPlan { plans })
+        } else {
+            frontend
+                // TODO(yingwen): Check error, some error may indicate that the sql is invalid. Now
+                // we return internal server error in those cases
+                .statement_to_plan(&sql_ctx, stmts.remove(0))
+                .box_err()
+                .with_context(|| ErrWithCause {
+                    code: StatusCode::INTERNAL_SERVER_ERROR,
+                    msg: "Failed to create plan",
+                })?
+        };
+        self.apply_default_table_options(schema, &mut plan)?;
+        ctx.record_stage(STAGE_PLAN, stage_begin.saturating_elapsed());
+
+        if let Plan::KillQuery(plan) = &plan {
+            self.check_kill_permission(&user, &plan.query_id)?;
+        }
 
         if enable_block_query {
             self.instance
@@ -248,6 +536,7 @@ impl Proxy {
         }
 
         if let Plan::Query(plan) = &plan {
+            slow_timer.fingerprint(limiter::query_fingerprint(plan));
             if let Some(priority) = plan
                 .decide_query_priority(PriorityContext {
                     time_range_threshold: self.expensive_query_threshold,
@@ -257,34 +546,138 @@ impl Proxy {
                     msg: format!("Decide query priority failed, table_name:{table_name:?}"),
                 })?
             {
-                slow_timer.priority(priority);
+                slow_timer.priority(query_hints.priority.unwrap_or(priority));
             }
         }
 
-        let output = if enable_partition_table_access {
-            self.execute_plan_involving_partition_table(
-                request_id.clone(),
+        Ok(PreparedSqlQuery {
+            plan,
+            query_hints,
+            deadline,
+            slow_timer,
+            query_guard,
+        })
+    }
+
+    /// Only the user issuing the query or the administrators can kill it.
+    fn check_kill_permission(&self, user: &str, query_id: &str) -> Result<()> {
+        // The query not found is reported by the interpreter.
+        let Some(owner) = self.query_registry.owner(query_id) else {
+            return Ok(());
+        };
+        ensure!(
+            owner == user || self.auth.is_admin(user),
+            ErrNoCause {
+                code: StatusCode::FORBIDDEN,
+                msg: format!(
+                    "Only the owner or administrators can kill the query, query_id:{query_id}, user:{user}"
+                ),
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Execute the plan, and retry it if it fails with transient engine errors
+    /// and has no side effects.
+    #[allow(clippy::too_many_arguments)]
+    async fn execute_plan_with_retry(
+        &self,
+        request_id: &RequestId,
+        catalog: &str,
+        schema: &str,
+        plan: Plan,
+        deadline: Option<Instant>,
+        enable_partition_table_access: bool,
+        read_snapshot: Option<ReadSnapshotRef>,
+        query_stats: Option<QueryStatsRef>,
+        query_hints: QueryHints,
+    ) -> Result<Output> {
+        let retry_plan = match &plan {
+            Plan::Query(query_plan)
+                if self.query_retry.enable && query_retry::is_side_effect_free(&plan) =>
+            {
+                Some(query_plan.clone())
+            }
+            _ => None,
+        };
+
+        let begin = Instant::now();
+        let mut result = self
+            .execute_plan_with_access(
+                request_id,
                 catalog,
                 schema,
                 plan,
                 deadline,
+                enable_partition_table_access,
+                read_snapshot.clone(),
+                query_stats.clone(),
+                query_hints.clone(),
             )
-            .await
-        } else {
-            self.execute_plan(request_id.clone(), catalog, schema, plan, deadline)
-                .await
+            .await;
+        let Some(retry_plan) = retry_plan else {
+            return result;
         };
-        let output = output.box_err().with_context(|| ErrWithCause {
-            code: StatusCode::INTERNAL_SERVER_ERROR,
-            msg: "Failed to execute plan",
-        })?;
 
-        let cost = slow_timer.elapsed();
-        info!(
-            "Handle sql query finished, sql:{sql}, elapsed:{cost:?}, catalog:{catalog}, schema:{schema}, ctx:{ctx:?}",
-        );
+        let mut retries = 0;
+        while let Err(e) = &result {
+            if !query_retry::is_retryable(e)
+                || !self
+                    .query_retry
+                    .allow_retry(retries, begin.saturating_elapsed(), deadline)
+            {
+                break;
+            }
 
-        Ok(output)
+            retries += 1;
+            warn!("Retry query failed by transient error, request_id:{request_id}, retries:{retries}, err:{e}");
+            GRPC_HANDLER_COUNTER_VEC.query_retried.inc();
+            tokio::time::sleep(self.query_retry.retry_interval.0).await;
+
+            result = self
+                .execute_plan_with_access(
+                    request_id,
+                    catalog,
+                    schema,
+                    Plan::Query(retry_plan.clone()),
+                    deadline,
+                    enable_partition_table_access,
+                    read_snapshot.clone(),
+                    query_stats.clone(),
+                    query_hints.clone(),
+                )
+                .await;
+        }
+
+        result
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn execute_plan_with_access(
+        &self,
+        request_id: &RequestId,
+        catalog: &str,
+        schema: &str,
+        plan: Plan,
+        deadline: Option<Instant>,
+        enable_partition_table_access: bool,
+        read_snapshot: Option<ReadSnapshotRef>,
+        query_stats: Option<QueryStatsRef>,
+        query_hints: QueryHints,
+    ) -> Result<Output> {
+        let interpreter = self.build_interpreter(
+            request_id.clone(),
+            catalog,
+            schema,
+            plan,
+            deadline,
+            enable_partition_table_access,
+            read_snapshot,
+            query_stats,
+            query_hints,
+        )?;
+        Self::interpreter_execute_plan(interpreter, deadline).await
     }
 
     async fn maybe_forward_sql_query(
@@ -37,9 +37,9 @@ impl ClusterBasedProvider {
 }
 
 impl SchemaConfigProvider for ClusterBasedProvider {
-    fn schema_config(&self, _schema_name: &str) -> Result<Option<&SchemaConfig>> {
+    fn schema_config(&self, _schema_name: &str) -> Result<Option<SchemaConfig>> {
         // FIXME: Fetch the schema config from the cluster rather than the hard-coded
         // default_schema_config.
-        Ok(Some(&self.default_schema_config))
+        Ok(Some(self.default_schema_config.clone()))
     }
 }
@@ -17,7 +17,7 @@
 
 // The schema config provider based on configs.
 
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::RwLock};
 
 use cluster::config::SchemaConfig;
 
@@ -28,25 +28,33 @@ pub type SchemaConfigs = HashMap<String, SchemaConfig>;
 /// Provide schema config according to the given config.
 #[derive(Debug)]
 pub struct ConfigBasedProvider {
-    schema_configs: SchemaConfigs,
+    schema_configs: RwLock<SchemaConfigs>,
     default: SchemaConfig,
 }
 
 impl ConfigBasedProvider {
     pub fn new(schema_configs: SchemaConfigs, default: SchemaConfig) -> Self {
         Self {
-            schema_configs,
+            schema_configs: RwLock::new(schema_configs),
             default,
         }
     }
+
+    /// Replace the schema configs, the default config is kept.
+    pub fn reload(&self, schema_configs: SchemaConfigs) {
+        *self.schema_configs.write().unwrap() = schema_configs;
+    }
 }
 
 impl SchemaConfigProvider for ConfigBasedProvider {
-    fn schema_config(&self, schema_name: &str) -> Result<Option<&SchemaConfig>> {
+    fn schema_config(&self, schema_name: &str) -> Result<Option<SchemaConfig>> {
         Ok(Some(
             self.schema_configs
+                .read()
+                .unwrap()
                 .get(schema_name)
-                .unwrap_or(&self.default),
+                .unwrap_or(&self.default)
+                .clone(),
         ))
     }
 }
@@ -35,5 +35,5 @@ define_result!(Error);
 pub type SchemaConfigProviderRef = Arc<dyn SchemaConfigProvider + Send + Sync>;
 
 pub trait SchemaConfigProvider {
-    fn schema_config(&self, schema_name: &str) -> Result<Option<&SchemaConfig>>;
+    fn schema_config(&self, schema_name: &str) -> Result<Option<SchemaConfig>>;
 }
@@ -0,0 +1,216 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Shard epochs attached to the responses, with which the clients are able to
+//! invalidate their cached routes precisely.
+
+use std::fmt::Write;
+
+use http::{HeaderMap, HeaderValue};
+use logger::warn;
+use router::ShardEpoch;
+
+use crate::metrics::SHARD_EPOCH_DROPPED_COUNTER_VEC_GLOBAL;
+
+/// Header carrying the shard epochs of the tables involved in the request, in
+/// the form of `table1=shard_id:version,table2=shard_id:version`.
+///
+/// The table names are percent-encoded, so neither `,` nor `=` appears in them.
+pub const SHARD_EPOCH_HEADER: &str = "x-horaedb-shard-epoch";
+/// Max size of the value of [SHARD_EPOCH_HEADER], the epochs beyond it are
+/// dropped, and the clients have to refresh the routes of those tables.
+const MAX_SHARD_EPOCH_HEADER_SIZE: usize = 4096;
+
+#[derive(Debug, Default)]
+pub struct ShardEpochs(Vec<(String, ShardEpoch)>);
+
+impl ShardEpochs {
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Build the header value no larger than `max_size`, returns the value and
+    /// the number of the epochs dropped for the size.
+    fn header_value(&self, max_size: usize) -> (String, usize) {
+        let mut value = String::new();
+        let mut entry = String::new();
+        for (idx, (table, epoch)) in self.0.iter().enumerate() {
+            entry.clear();
+            if !value.is_empty() {
+                entry.push(',');
+            }
+            encode_table_name(table, &mut entry);
+            write!(entry, "={epoch}").unwrap();
+
+            if value.len() + entry.len() > max_size {
+                return (value, self.0.len() - idx);
+            }
+            value.push_str(&entry);
+        }
+
+        (value, 0)
+    }
+
+    /// Fill the shard epochs into the response headers, nothing will be filled
+    /// if no epoch is known.
+    pub fn fill_headers(&self, headers: &mut HeaderMap) {
+        if self.is_empty() {
+            return;
+        }
+
+        let (value, num_dropped) = self.header_value(MAX_SHARD_EPOCH_HEADER_SIZE);
+        if num_dropped > 0 {
+            warn!(
+                "Shard epochs are dropped for the header size, dropped:{num_dropped}, total:{}",
+                self.0.len()
+            );
+            SHARD_EPOCH_DROPPED_COUNTER_VEC_GLOBAL
+                .with_label_values(&["too_large"])
+                .inc_by(num_dropped as u64);
+        }
+        if value.is_empty() {
+            return;
+        }
+
+        // The value is always valid after encoding, but the epochs are still
+        // dropped explicitly rather than failing the response.
+        match HeaderValue::from_str(&value) {
+            Ok(value) => {
+                headers.insert(SHARD_EPOCH_HEADER, value);
+            }
+            Err(e) => {
+                warn!("Shard epochs are dropped for the invalid header, value:{value}, err:{e}");
+                SHARD_EPOCH_DROPPED_COUNTER_VEC_GLOBAL
+                    .with_label_values(&["invalid"])
+                    .inc_by((self.0.len() - num_dropped) as u64);
+            }
+        }
+    }
+}
+
+/// Percent-encode the bytes of the table name other than the visible ascii,
+/// as well as the separators and `%` itself.
+fn encode_table_name(table: &str, buf: &mut String) {
+    for b in table.bytes() {
+        if b.is_ascii_graphic() && !matches!(b, b',' | b'=' | b'%') {
+            buf.push(b as char);
+        } else {
+            write!(buf, "%{b:02X}").unwrap();
+        }
+    }
+}
+
+impl FromIterator<(String, ShardEpoch)> for ShardEpochs {
+    fn from_iter<T: IntoIterator<Item = (String, ShardEpoch)>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill_headers() {
+        let mut headers = HeaderMap::new();
+        ShardEpochs::default().fill_headers(&mut headers);
+        assert!(headers.is_empty());
+
+        let epochs: ShardEpochs = vec![
+            (
+                "t1".to_string(),
+                ShardEpoch {
+                    shard_id: 1,
+                    version: 10,
+                },
+            ),
+            (
+                "t2".to_string(),
+                ShardEpoch {
+                    shard_id: 2,
+                    version: 3,
+                },
+            ),
+        ]
+        .into_iter()
+        .collect();
+        epochs.fill_headers(&mut headers);
+        assert_eq!(headers.get(SHARD_EPOCH_HEADER).unwrap(), "t1=1:10,t2=2:3");
+    }
+
+    #[test]
+    fn test_encode_table_name() {
+        let epochs: ShardEpochs = ["a,b=c", "50%", "表 1"]
+            .into_iter()
+            .map(|table| {
+                (
+                    table.to_string(),
+                    ShardEpoch {
+                        shard_id: 1,
+                        version: 2,
+                    },
+                )
+            })
+            .collect();
+        let mut headers = HeaderMap::new();
+        epochs.fill_headers(&mut headers);
+        assert_eq!(
+            headers.get(SHARD_EPOCH_HEADER).unwrap(),
+            "a%2Cb%3Dc=1:2,50%25=1:2,%E8%A1%A8%201=1:2"
+        );
+    }
+
+    #[test]
+    fn test_header_size_limit() {
+        let epochs: ShardEpochs = (0..3)
+            .map(|i| {
+                (
+                    format!("t{i}"),
+                    ShardEpoch {
+                        shard_id: i,
+                        version: 1,
+                    },
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            epochs.header_value(usize::MAX),
+            ("t0=0:1,t1=1:1,t2=2:1".to_string(), 0)
+        );
+        // The epoch exceeding the limit and all after it are dropped.
+        assert_eq!(epochs.header_value(13), ("t0=0:1,t1=1:1".to_string(), 1));
+        assert_eq!(epochs.header_value(5), (String::new(), 3));
+
+        let mut headers = HeaderMap::new();
+        let epochs: ShardEpochs = (0..MAX_SHARD_EPOCH_HEADER_SIZE)
+            .map(|i| {
+                (
+                    format!("t{i}"),
+                    ShardEpoch {
+                        shard_id: 1,
+                        version: 1,
+                    },
+                )
+            })
+            .collect();
+        epochs.fill_headers(&mut headers);
+        let value = headers.get(SHARD_EPOCH_HEADER).unwrap();
+        assert!(value.len() <= MAX_SHARD_EPOCH_HEADER_SIZE);
+    }
+}
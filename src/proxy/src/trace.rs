@@ -0,0 +1,209 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+//! Per-request trace sample echoed back to clients.
+//!
+//! When a client opts in by sending [TRACE_HEADER], the server returns the
+//! request id used in its logs as [TRACE_ID_HEADER], together with a compact
+//! breakdown of where the time was spent in [SERVER_TIMING_HEADER], following
+//! the format of the W3C `Server-Timing` header, e.g.
+//! `parse;dur=0.12, plan;dur=1.05, execute;dur=10.31, total;dur=11.60`.
+
+use std::{
+    fmt::Write,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use common_types::request_id::RequestId;
+use http::{HeaderMap, HeaderValue};
+use time_ext::InstantExt;
+
+/// Request header used by clients to opt in the trace sample.
+pub const TRACE_HEADER: &str = "x-horaedb-trace";
+/// Request header used by clients to mark the request as verbose, whose debug
+/// logs are emitted regardless of the global log level.
+pub const VERBOSE_HEADER: &str = "x-horaedb-verbose";
+/// Response header carrying the server-side request id.
+pub const TRACE_ID_HEADER: &str = "x-horaedb-trace-id";
+/// Response header carrying the timing breakdown.
+pub const SERVER_TIMING_HEADER: &str = "server-timing";
+
+pub const STAGE_FORWARD: &str = "forward";
+pub const STAGE_PARSE: &str = "parse";
+pub const STAGE_PLAN: &str = "plan";
+pub const STAGE_EXECUTE: &str = "execute";
+const STAGE_TOTAL: &str = "total";
+
+/// Whether the value of [TRACE_HEADER] enables the trace sample.
+pub fn is_trace_enabled(value: &str) -> bool {
+    matches!(
+        value.trim().to_ascii_lowercase().as_str(),
+        "1" | "true" | "on"
+    )
+}
+
+/// Whether the value of [VERBOSE_HEADER] marks the request as verbose, it
+/// accepts the same values as [TRACE_HEADER].
+pub fn is_verbose_enabled(value: Option<&str>) -> bool {
+    value.is_some_and(is_trace_enabled)
+}
+
+pub type RequestTraceRef = Arc<RequestTrace>;
+
+/// Create a trace sample if the client opts in by the value of [TRACE_HEADER].
+pub fn new_trace_if_enabled(value: Option<&str>) -> Option<RequestTraceRef> {
+    value
+        .filter(|v| is_trace_enabled(v))
+        .map(|_| Arc::new(RequestTrace::new()))
+}
+
+/// Trace sample collected for a single request.
+///
+/// Stages recorded more than once (e.g. the execution of multiple insert plans
+/// in one write request) are accumulated.
+#[derive(Debug)]
+pub struct RequestTrace {
+    begin: Instant,
+    inner: Mutex<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    trace_id: Option<RequestId>,
+    stages: Vec<(&'static str, Duration)>,
+}
+
+impl Default for RequestTrace {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RequestTrace {
+    pub fn new() -> Self {
+        Self {
+            begin: Instant::now(),
+            inner: Mutex::new(Inner::default()),
+        }
+    }
+
+    pub fn set_trace_id(&self, request_id: RequestId) {
+        self.inner.lock().unwrap().trace_id = Some(request_id);
+    }
+
+    pub fn trace_id(&self) -> Option<String> {
+        self.inner
+            .lock()
+            .unwrap()
+            .trace_id
+            .as_ref()
+            .map(|v| v.to_string())
+    }
+
+    pub fn record(&self, stage: &'static str, cost: Duration) {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.stages.iter_mut().find(|(name, _)| *name == stage) {
+            Some((_, total)) => *total += cost,
+            None => inner.stages.push((stage, cost)),
+        }
+    }
+
+    /// Render the recorded stages and the total elapsed time since the trace
+    /// is created.
+    pub fn server_timing(&self) -> String {
+        let inner = self.inner.lock().unwrap();
+        let mut timing = String::new();
+        let total = (STAGE_TOTAL, self.begin.saturating_elapsed());
+        for (name, cost) in inner.stages.iter().chain(std::iter::once(&total)) {
+            if !timing.is_empty() {
+                timing.push_str(", ");
+            }
+            let _ = write!(timing, "{name};dur={:.2}", cost.as_secs_f64() * 1000.0);
+        }
+
+        timing
+    }
+
+    /// Fill the trace id and the timing breakdown into the response headers.
+    pub fn fill_headers(&self, headers: &mut HeaderMap) {
+        if let Some(trace_id) = self.trace_id() {
+            if let Ok(value) = HeaderValue::from_str(&trace_id) {
+                headers.insert(TRACE_ID_HEADER, value);
+            }
+        }
+        if let Ok(value) = HeaderValue::from_str(&self.server_timing()) {
+            headers.insert(SERVER_TIMING_HEADER, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_trace_enabled() {
+        for value in ["1", "true", "TRUE", " on "] {
+            assert!(is_trace_enabled(value), "value:{value}");
+        }
+        for value in ["", "0", "false", "yes"] {
+            assert!(!is_trace_enabled(value), "value:{value}");
+        }
+    }
+
+    #[test]
+    fn test_new_trace_if_enabled() {
+        assert!(new_trace_if_enabled(None).is_none());
+        assert!(new_trace_if_enabled(Some("false")).is_none());
+        assert!(new_trace_if_enabled(Some("true")).is_some());
+    }
+
+    #[test]
+    fn test_server_timing() {
+        let trace = RequestTrace::new();
+        assert!(trace.trace_id().is_none());
+
+        let request_id = RequestId::next_id();
+        trace.set_trace_id(request_id.clone());
+        assert_eq!(trace.trace_id(), Some(request_id.to_string()));
+
+        trace.record(STAGE_PLAN, Duration::from_micros(1500));
+        trace.record(STAGE_EXECUTE, Duration::from_millis(2));
+        trace.record(STAGE_PLAN, Duration::from_micros(500));
+
+        let timing = trace.server_timing();
+        let stages: Vec<_> = timing.split(", ").collect();
+        assert_eq!(stages.len(), 3);
+        assert_eq!(stages[0], "plan;dur=2.00");
+        assert_eq!(stages[1], "execute;dur=2.00");
+        assert!(stages[2].starts_with("total;dur="));
+    }
+
+    #[test]
+    fn test_fill_headers() {
+        let trace = RequestTrace::new();
+        let mut headers = HeaderMap::new();
+        trace.fill_headers(&mut headers);
+        assert!(headers.get(TRACE_ID_HEADER).is_none());
+        assert!(headers.get(SERVER_TIMING_HEADER).is_some());
+
+        let request_id = RequestId::next_id();
+        trace.set_trace_id(request_id.clone());
+        trace.fill_headers(&mut headers);
+        assert_eq!(headers.get(TRACE_ID_HEADER).unwrap(), request_id.as_str());
+    }
+}
@@ -24,7 +24,7 @@ use std::{
 };
 
 use bytes::Bytes;
-use cluster::config::SchemaConfig;
+use cluster::{config::SchemaConfig, Cluster, TableStatus};
 use common_types::{
     column_schema::ColumnSchema,
     datum::{Datum, DatumKind},
@@ -40,7 +40,7 @@ use horaedbproto::storage::{
     WriteRequest, WriteResponse as WriteResponsePB, WriteSeriesEntry, WriteTableRequest,
 };
 use http::StatusCode;
-use interpreters::interpreter::Output;
+use interpreters::{insert, interpreter::Output};
 use logger::{debug, error, info, warn};
 use query_frontend::{
     frontend::{Context as FrontendContext, Frontend},
@@ -50,15 +50,34 @@ use query_frontend::{
 };
 use router::{endpoint::Endpoint, RouteRequest};
 use snafu::{ensure, OptionExt, ResultExt};
-use table_engine::table::TableRef;
+use table_engine::{
+    engine::{TableWriteRequest, WriteTablesRequest},
+    table::TableRef,
+};
+use time_ext::InstantExt;
 use tonic::transport::Channel;
 
 use crate::{
     error::{ErrNoCause, ErrWithCause, Internal, InternalNoCause, Result},
     forward::{ForwardResult, ForwarderRef},
+    trace::{STAGE_EXECUTE, STAGE_FORWARD, STAGE_PLAN},
     Context, Proxy,
 };
 
+/// Request header used by clients to decide whether the tables of the write
+/// request are written atomically, overriding the default of the server.
+pub const ATOMIC_WRITE_HEADER: &str = "x-horaedb-atomic-write";
+
+/// Parse the value of [ATOMIC_WRITE_HEADER], `None` if it's absent or
+/// invalid, so the default of the server is used.
+pub fn parse_atomic_write(value: Option<&str>) -> Option<bool> {
+    match value?.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "on" => Some(true),
+        "0" | "false" | "off" => Some(false),
+        _ => None,
+    }
+}
+
 type WriteResponseFutures<'a> = Vec<BoxFuture<'a, runtime::Result<Result<WriteResponse>>>>;
 
 struct PlanWithTable {
@@ -87,18 +106,22 @@ impl Proxy {
         ctx: Context,
         req: WriteRequest,
     ) -> Result<WriteResponse> {
-        let write_context = req.context.clone();
-        let resp = if self.cluster_with_meta {
-            self.handle_write_with_meta(ctx, req).await?
-        } else {
-            self.handle_write_without_meta(ctx, req).await?
-        };
+        // Emit all the debug logs of the request if it's marked as verbose.
+        runtime::verbose::scope(ctx.verbose_request_id(), async {
+            let write_context = req.context.clone();
+            let resp = if self.cluster_with_meta {
+                self.handle_write_with_meta(ctx, req).await?
+            } else {
+                self.handle_write_without_meta(ctx, req).await?
+            };
 
-        debug!(
-            "Handle write finished, write_context:{:?}, resp:{:?}",
-            write_context, resp
-        );
-        Ok(resp)
+            debug!(
+                "Handle write finished, write_context:{:?}, resp:{:?}",
+                write_context, resp
+            );
+            Ok(resp)
+        })
+        .await
     }
 
     // Handle write requests based on horaemeta.
@@ -119,8 +142,9 @@ impl Proxy {
         self.handle_auto_create_table_with_meta(request_id.clone(), &write_context.database, &req)
             .await?;
 
-        let (write_request_to_local, write_requests_to_forward) =
-            self.split_write_request(req).await?;
+        let (write_request_to_local, write_requests_to_forward) = self
+            .split_write_request(req, self.is_atomic_write(&ctx))
+            .await?;
 
         let mut futures = Vec::with_capacity(write_requests_to_forward.len() + 1);
 
@@ -149,8 +173,9 @@ impl Proxy {
             msg: "Missing context",
             code: StatusCode::BAD_REQUEST,
         })?;
-        let (write_request_to_local, write_requests_to_forward) =
-            self.split_write_request(req).await?;
+        let (write_request_to_local, write_requests_to_forward) = self
+            .split_write_request(req, self.is_atomic_write(&ctx))
+            .await?;
 
         let mut futures = Vec::with_capacity(write_requests_to_forward.len() + 1);
 
@@ -191,7 +216,6 @@ impl Proxy {
                 code: StatusCode::INTERNAL_SERVER_ERROR,
                 msg: format!("Fail to fetch schema config, schema_name:{schema}"),
             })?
-            .cloned()
             .unwrap_or_default();
 
         // TODO: Consider whether to build tables concurrently when there are too many
@@ -238,12 +262,13 @@ impl Proxy {
                 code: StatusCode::INTERNAL_SERVER_ERROR,
                 msg: format!("Fail to fetch schema config, schema:{schema}"),
             })?
-            .cloned()
             .unwrap_or_default();
 
         if self.auto_create_table {
             for (idx, table_name) in table_names.iter().enumerate() {
-                let table = self.try_get_table(catalog, schema, table_name)?;
+                let table = self
+                    .try_get_table_or_wait(catalog, schema, table_name)
+                    .await?;
                 if table.is_none() {
                     self.create_table(
                         request_id.clone(),
@@ -307,6 +332,7 @@ impl Proxy {
     async fn split_write_request(
         &self,
         req: WriteRequest,
+        atomic_write: bool,
     ) -> Result<(WriteRequest, HashMap<Endpoint, WriteRequest>)> {
         // Split write request into multiple requests, each request contains table
         // belong to one remote engine.
@@ -368,6 +394,22 @@ impl Proxy {
                 }
             }
         }
+
+        // The atomic write is done by one wal write, so all the tables must be written
+        // by the same node.
+        let num_nodes = table_requests_to_forward.len()
+            + usize::from(!table_requests_to_local.table_requests.is_empty());
+        ensure!(
+            !atomic_write || num_nodes <= 1,
+            ErrNoCause {
+                code: StatusCode::BAD_REQUEST,
+                msg: format!(
+                    "Tables of the atomic write are on different nodes, forwarded_tables:{:?}",
+                    forwarded_table_routes.keys().collect::<Vec<_>>()
+                ),
+            }
+        );
+
         Ok((table_requests_to_local, table_requests_to_forward))
     }
 
@@ -448,15 +490,26 @@ impl Proxy {
             Box::new(write) as _
         };
 
+        // The remote node should make the same decision on the atomic write.
+        let mut request = tonic::Request::new(table_write_request);
+        if let Some(atomic_write) = ctx.atomic_write {
+            request.metadata_mut().insert(
+                ATOMIC_WRITE_HEADER,
+                atomic_write.to_string().parse().unwrap(),
+            );
+        }
+
+        let begin = Instant::now();
         let forward_result = forwarder
             .forward_with_endpoint(
                 endpoint,
-                tonic::Request::new(table_write_request),
-                ctx.forwarded_from,
-                ctx.authorization,
+                request,
+                ctx.forwarded_from.clone(),
+                ctx.authorization.clone(),
                 do_write,
             )
             .await;
+        ctx.record_stage(STAGE_FORWARD, begin.saturating_elapsed());
         let forward_res = forward_result
             .map_err(|e| {
                 error!("Failed to forward sql req but the error is ignored, err:{e}");
@@ -481,7 +534,7 @@ impl Proxy {
     }
 
     async fn write_to_local(&self, ctx: Context, req: WriteRequest) -> Result<WriteResponse> {
-        let request_id = ctx.request_id;
+        let request_id = ctx.request_id.clone();
         let begin_instant = Instant::now();
         let deadline = ctx.timeout.map(|t| begin_instant + t);
         let catalog_name = self.instance.catalog_manager.default_catalog_name();
@@ -510,24 +563,32 @@ impl Proxy {
         let plans = self
             .write_request_to_insert_plan(req.table_requests, write_context)
             .await?;
+        ctx.record_stage(STAGE_PLAN, begin_instant.saturating_elapsed());
 
         let mut success = 0;
+        let execute_begin = Instant::now();
+
+        let write_pressure = self.instance.table_engine.write_pressure();
+        if self.is_atomic_write(&ctx) && plans.len() > 1 {
+            for PlanWithTable { plan, table } in &plans {
+                self.check_write_limit(table, plan, write_pressure)?;
+            }
+            success = self
+                .write_plans_atomically(catalog_name, &schema_name, plans)
+                .await?;
+            ctx.record_stage(STAGE_EXECUTE, execute_begin.saturating_elapsed());
+
+            return Ok(WriteResponse {
+                success: success as u32,
+                ..Default::default()
+            });
+        }
 
         // TODO: concurrently run the insert plan here
         for plan_with_table in plans {
             let PlanWithTable { plan, table } = plan_with_table;
 
-            // check limit first
-            // TODO: if one table is blocked, maybe should not lead to failure of whole
-            // batch?
-            self.instance
-                .limiter
-                .try_limit(&plan)
-                .box_err()
-                .context(ErrWithCause {
-                    code: StatusCode::INTERNAL_SERVER_ERROR,
-                    msg: "table is blocked",
-                })?;
+            self.check_write_limit(&table, &plan, write_pressure)?;
 
             match self
                 .execute_insert_plan(
@@ -555,12 +616,122 @@ impl Proxy {
             }
         }
 
+        ctx.record_stage(STAGE_EXECUTE, execute_begin.saturating_elapsed());
+
         Ok(WriteResponse {
             success: success as u32,
             ..Default::default()
         })
     }
 
+    fn check_write_limit(&self, table: &TableRef, plan: &Plan, write_pressure: f64) -> Result<()> {
+        self.instance
+            .limiter
+            .try_shed_write(table.name(), write_pressure)
+            .box_err()
+            .context(ErrWithCause {
+                code: StatusCode::TOO_MANY_REQUESTS,
+                msg: "write is shed under pressure",
+            })?;
+
+        // check limit first
+        // TODO: if one table is blocked, maybe should not lead to failure of whole
+        // batch?
+        self.instance
+            .limiter
+            .try_limit(plan)
+            .box_err()
+            .context(ErrWithCause {
+                code: StatusCode::INTERNAL_SERVER_ERROR,
+                msg: "table is blocked",
+            })
+    }
+
+    /// Whether to write the local tables of the request atomically, decided by
+    /// the request or the default of the server.
+    fn is_atomic_write(&self, ctx: &Context) -> bool {
+        ctx.atomic_write.unwrap_or(self.atomic_write)
+    }
+
+    /// Write the rows of all the plans by one write of the table engine, so
+    /// either all or none of them are persisted.
+    async fn write_plans_atomically(
+        &self,
+        catalog_name: &str,
+        schema_name: &str,
+        plans: Vec<PlanWithTable>,
+    ) -> Result<usize> {
+        let catalog = self.get_catalog(catalog_name)?;
+        let schema = self.get_schema(&catalog, schema_name)?;
+
+        let mut engine = None;
+        let mut requests = Vec::with_capacity(plans.len());
+        for PlanWithTable { plan, table } in plans {
+            let Plan::Insert(InsertPlan {
+                source: InsertSource::Values { row_group },
+                default_value_map,
+                ..
+            }) = plan
+            else {
+                return InternalNoCause {
+                    msg: format!("Invalid plan of atomic write, table:{}", table.name()),
+                }
+                .fail();
+            };
+            // The partitioned table is not a table of the engine, but a router to its
+            // sub tables, which may be on different nodes.
+            ensure!(
+                table.partition_info().is_none(),
+                ErrNoCause {
+                    code: StatusCode::BAD_REQUEST,
+                    msg: format!(
+                        "Partitioned table is not supported by the atomic write, table:{}",
+                        table.name()
+                    ),
+                }
+            );
+            let engine = engine.get_or_insert_with(|| table.engine_type().to_string());
+            ensure!(
+                engine == table.engine_type(),
+                ErrNoCause {
+                    code: StatusCode::BAD_REQUEST,
+                    msg: format!(
+                        "Tables of the atomic write are of different engines, table:{}",
+                        table.name()
+                    ),
+                }
+            );
+
+            let request = insert::build_write_request(&table, row_group, &default_value_map)
+                .box_err()
+                .with_context(|| Internal {
+                    msg: format!("Failed to build write request, table:{}", table.name()),
+                })?;
+            requests.push(TableWriteRequest {
+                schema_id: schema.id(),
+                table_id: table.id(),
+                request,
+            });
+        }
+
+        let request = WriteTablesRequest {
+            engine: engine.unwrap_or_default(),
+            requests,
+        };
+        let num_rows = self
+            .instance
+            .table_engine
+            .write_tables(request)
+            .await
+            .box_err()
+            .context(ErrWithCause {
+                code: StatusCode::INTERNAL_SERVER_ERROR,
+                msg: "Failed to write tables atomically",
+            })?;
+
+        Ok(num_rows.into_iter().sum())
+    }
+
     async fn write_request_to_insert_plan(
         &self,
         table_requests: Vec<WriteTableRequest>,
@@ -580,7 +751,8 @@ impl Proxy {
             self.maybe_open_partition_table_if_not_exist(&catalog, &schema, table_name)
                 .await?;
             let table = self
-                .try_get_table(&catalog, &schema, table_name)?
+                .try_get_table_or_wait(&catalog, &schema, table_name)
+                .await?
                 .with_context(|| ErrNoCause {
                     code: StatusCode::BAD_REQUEST,
                     msg: format!("Table not found, schema:{schema}, table:{table_name}"),
@@ -656,7 +828,7 @@ impl Proxy {
         })
     }
 
-    fn try_get_table(
+    pub(crate) fn try_get_table(
         &self,
         catalog: &str,
         schema: &str,
@@ -692,6 +864,24 @@ impl Proxy {
             })
     }
 
+    /// Like [Self::try_get_table], but waits for the table if its shard is
+    /// still opening, so the write can be applied after the recovery rather
+    /// than being rejected at once.
+    async fn try_get_table_or_wait(
+        &self,
+        catalog: &str,
+        schema: &str,
+        table_name: &str,
+    ) -> Result<Option<TableRef>> {
+        let get_table = || self.try_get_table(catalog, schema, table_name);
+        match &self.cluster {
+            Some(cluster) => {
+                get_table_or_wait(cluster.as_ref(), schema, table_name, get_table).await
+            }
+            None => get_table(),
+        }
+    }
+
     async fn execute_add_columns_plan(
         &self,
         request_id: RequestId,
@@ -1038,16 +1228,64 @@ fn need_evict_partition_table(msg: String) -> bool {
         || msg.contains("Can't find tag")
 }
 
+/// Get the table by `get_table`, and wait for the shard of the table to finish
+/// opening if the table is not found yet.
+///
+/// The tables of the opening shard may be missing (rather than failing to be
+/// found) in the catalog, so the write must wait for them instead of creating
+/// them or being rejected.
+async fn get_table_or_wait<T>(
+    cluster: &(dyn Cluster + Send + Sync),
+    schema: &str,
+    table_name: &str,
+    get_table: impl Fn() -> Result<Option<T>>,
+) -> Result<Option<T>> {
+    let res = get_table();
+    if matches!(res, Ok(Some(_)))
+        || !matches!(
+            cluster.get_table_status(schema, table_name),
+            Some(TableStatus::Recovering)
+        )
+    {
+        return res;
+    }
+
+    cluster
+        .wait_table_ready(schema, table_name)
+        .await
+        .box_err()
+        .with_context(|| ErrWithCause {
+            code: StatusCode::SERVICE_UNAVAILABLE,
+            msg: format!("Shard of the table is opening, schema:{schema}, table:{table_name}"),
+        })?;
+
+    get_table()
+}
+
 #[cfg(test)]
 mod test {
+    use std::{
+        sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use async_trait::async_trait;
+    use cluster::{
+        rebalance::{RebalanceProposal, ShardLoad},
+        shard_lock_manager::ShardLockManagerRef,
+        shard_set::ShardRef,
+        ClusterNodesResp,
+    };
     use common_types::{
         column_schema::{self},
         datum::{Datum, DatumKind},
         row::Row,
         schema::Builder,
+        table::ShardId,
         time::Timestamp,
     };
     use horaedbproto::storage::{value, Field, FieldGroup, Tag, Value, WriteSeriesEntry};
+    use meta_client::types::{RouteTablesRequest, RouteTablesResponse, ShardInfo};
     use system_catalog::sys_catalog_table::TIMESTAMP_COLUMN_NAME;
 
     use super::*;
@@ -1059,6 +1297,24 @@ mod test {
     const NAME_COL4: &str = "col4";
     const NAME_COL5: &str = "col5";
 
+    #[test]
+    fn test_parse_atomic_write() {
+        assert_eq!(parse_atomic_write(None), None);
+        for value in ["1", "true", " ON "] {
+            assert_eq!(parse_atomic_write(Some(value)), Some(true), "value:{value}");
+        }
+        for value in ["0", "false", "Off"] {
+            assert_eq!(
+                parse_atomic_write(Some(value)),
+                Some(false),
+                "value:{value}"
+            );
+        }
+        for value in ["", "yes"] {
+            assert_eq!(parse_atomic_write(Some(value)), None, "value:{value}");
+        }
+    }
+
     #[test]
     fn test_write_entry_to_row_group() {
         let (schema, tag_names, field_names, write_entry) = generate_write_entry();
@@ -1241,4 +1497,132 @@ mod test {
             entries: vec![write_entry],
         }
     }
+
+    /// Cluster whose shard is opening until the table is waited.
+    struct OpeningCluster {
+        recovering: AtomicBool,
+        num_waits: AtomicUsize,
+    }
+
+    impl OpeningCluster {
+        fn new(recovering: bool) -> Self {
+            Self {
+                recovering: AtomicBool::new(recovering),
+                num_waits: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Cluster for OpeningCluster {
+        async fn start(&self) -> cluster::Result<()> {
+            unimplemented!();
+        }
+
+        async fn stop(&self) -> cluster::Result<()> {
+            unimplemented!();
+        }
+
+        async fn open_shard(&self, _: &ShardInfo) -> cluster::Result<ShardRef> {
+            unimplemented!();
+        }
+
+        async fn open_shards(&self, _: &[ShardInfo]) -> cluster::Result<Vec<ShardRef>> {
+            unimplemented!();
+        }
+
+        fn shard(&self, _: ShardId) -> Option<ShardRef> {
+            unimplemented!();
+        }
+
+        fn get_table_status(&self, _: &str, _: &str) -> Option<TableStatus> {
+            if self.recovering.load(Ordering::Relaxed) {
+                Some(TableStatus::Recovering)
+            } else {
+                Some(TableStatus::Ready)
+            }
+        }
+
+        async fn wait_table_ready(&self, _: &str, _: &str) -> cluster::Result<()> {
+            self.num_waits.fetch_add(1, Ordering::Relaxed);
+            self.recovering.store(false, Ordering::Relaxed);
+            Ok(())
+        }
+
+        async fn close_shard(&self, _: ShardId) -> cluster::Result<ShardRef> {
+            unimplemented!();
+        }
+
+        fn list_shards(&self) -> Vec<ShardInfo> {
+            unimplemented!();
+        }
+
+        async fn route_tables(
+            &self,
+            _: &RouteTablesRequest,
+        ) -> cluster::Result<RouteTablesResponse> {
+            unimplemented!();
+        }
+
+        async fn fetch_nodes(&self) -> cluster::Result<ClusterNodesResp> {
+            unimplemented!();
+        }
+
+        fn shard_lock_manager(&self) -> ShardLockManagerRef {
+            unimplemented!();
+        }
+
+        async fn update_shard_lock_lease(&self, _: u64, _: Duration) -> cluster::Result<()> {
+            unimplemented!();
+        }
+
+        async fn propose_rebalance(
+            &self,
+            _: Vec<ShardLoad>,
+        ) -> cluster::Result<Vec<RebalanceProposal>> {
+            unimplemented!();
+        }
+    }
+
+    /// Get the table which is only found after the shard is opened.
+    async fn get_table_of_cluster(cluster: &OpeningCluster) -> Result<Option<u32>> {
+        get_table_or_wait(cluster, "public", "test_table", || {
+            if cluster.recovering.load(Ordering::Relaxed) {
+                Ok(None)
+            } else {
+                Ok(Some(1))
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_wait_table_of_opening_shard() {
+        // The table missing in the opening shard is waited.
+        let cluster = OpeningCluster::new(true);
+        assert_eq!(Some(1), get_table_of_cluster(&cluster).await.unwrap());
+        assert_eq!(1, cluster.num_waits.load(Ordering::Relaxed));
+
+        // The table failing to be found in the opening shard is waited too.
+        let cluster = OpeningCluster::new(true);
+        let res = get_table_or_wait(&cluster, "public", "test_table", || {
+            if cluster.recovering.load(Ordering::Relaxed) {
+                InternalNoCause {
+                    msg: "shard is opening",
+                }
+                .fail()
+            } else {
+                Ok(Some(1))
+            }
+        })
+        .await;
+        assert_eq!(Some(1), res.unwrap());
+        assert_eq!(1, cluster.num_waits.load(Ordering::Relaxed));
+
+        // Nothing is waited if the shard is ready.
+        let cluster = OpeningCluster::new(false);
+        let res = get_table_or_wait(&cluster, "public", "test_table", || Ok(None::<u32>)).await;
+        assert_eq!(None, res.unwrap());
+        assert_eq!(0, cluster.num_waits.load(Ordering::Relaxed));
+    }
 }
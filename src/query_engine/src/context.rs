@@ -21,6 +21,7 @@ use std::{sync::Arc, time::Instant};
 
 use common_types::request_id::RequestId;
 use runtime::Priority;
+use table_engine::table::{QueryStatsRef, ReadSnapshotRef};
 
 pub type ContextRef = Arc<Context>;
 
@@ -32,4 +33,13 @@ pub struct Context {
     pub default_catalog: String,
     pub default_schema: String,
     pub priority: Priority,
+    /// Snapshot shared with other queries, the tables are read at their
+    /// latest versions if not set.
+    pub read_snapshot: Option<ReadSnapshotRef>,
+    /// Statistics of the query updated by the reads.
+    pub query_stats: Option<QueryStatsRef>,
+    /// Parallelism to read the tables, the configured one is used if not set.
+    pub read_parallelism: Option<usize>,
+    /// The data read by the query won't be filled into the caches.
+    pub no_cache: bool,
 }
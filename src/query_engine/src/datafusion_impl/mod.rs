@@ -125,13 +125,17 @@ impl DfContextBuilder {
             default_catalog: ctx.default_catalog.clone(),
             default_schema: ctx.default_schema.clone(),
             priority: ctx.priority,
+            read_snapshot: ctx.read_snapshot.clone(),
+            query_stats: ctx.query_stats.clone(),
+            no_cache: ctx.no_cache,
+            time_ordered_limit: None,
         };
         let mut df_session_config = SessionConfig::new()
             .with_default_catalog_and_schema(
                 ctx.default_catalog.clone(),
                 ctx.default_schema.clone(),
             )
-            .with_target_partitions(self.config.read_parallelism);
+            .with_target_partitions(ctx.read_parallelism.unwrap_or(self.config.read_parallelism));
 
         df_session_config.options_mut().extensions.insert(options);
 
@@ -51,7 +51,7 @@ use table_engine::{
         RemoteEngineRef,
     },
     stream::ToDfStream,
-    table::{ReadOptions, ReadRequest, TableRef},
+    table::{ReadOptions, ReadOrder, ReadRequest, TableRef},
 };
 use trace_metric::MetricsCollector;
 
@@ -292,6 +292,11 @@ impl ExecutableScanBuilder for ExecutableScanBuilderImpl {
             batch_size: ctx.batch_size,
             read_parallelism: ctx.read_parallelism,
             deadline: self.deadline,
+            snapshot: None,
+            stats: None,
+            no_cache: false,
+            limit: None,
+            order: ReadOrder::None,
         };
 
         let read_request = ReadRequest {
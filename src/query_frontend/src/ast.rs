@@ -38,7 +38,11 @@ pub enum Statement {
     ShowCreate(ShowCreate),
     ShowDatabases,
     ShowTables(ShowTables),
+    /// SHOW TABLE STATS
+    ShowTableStats(ShowTableStats),
     Exists(ExistsTable),
+    /// KILL QUERY
+    KillQuery(KillQuery),
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -92,6 +96,7 @@ pub enum Partition {
     Random(RandomPartition),
     Hash(HashPartition),
     Key(KeyPartition),
+    Series(SeriesPartition),
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -117,6 +122,13 @@ pub struct KeyPartition {
     pub partition_key: Vec<String>,
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub struct SeriesPartition {
+    pub partition_num: u64,
+    /// All the tag columns in the order of definition.
+    pub series_key: Vec<String>,
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct DropTable {
     /// Table name
@@ -154,11 +166,22 @@ pub struct ShowCreate {
     pub table_name: TableName,
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub struct ShowTableStats {
+    pub table_name: TableName,
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct ExistsTable {
     pub table_name: TableName,
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub struct KillQuery {
+    /// Id of the query to kill, which is the request id of the query.
+    pub query_id: String,
+}
+
 #[cfg(test)]
 mod tests {
     use sqlparser::ast::Ident;
@@ -235,6 +235,15 @@ pub fn parse_table_name_with_standard(sql_statement: &SqlStatement) -> Option<St
         SqlStatement::Insert { table_name, .. } => {
             Some(TableName::from(table_name.clone()).to_string())
         }
+        SqlStatement::Delete { from, .. } => {
+            if from.len() != 1 {
+                None
+            } else if let TableFactor::Table { name, .. } = &from[0].relation {
+                Some(TableName::from(name.clone()).to_string())
+            } else {
+                None
+            }
+        }
         SqlStatement::Explain { statement, .. } => {
             if let SqlStatement::Query(q) = *statement {
                 match *q.body {
@@ -285,7 +294,9 @@ pub fn parse_table_name(statements: &StatementVec) -> Option<String> {
         Statement::ShowCreate(s) => Some(s.table_name.to_string()),
         Statement::ShowTables(_s) => None,
         Statement::ShowDatabases => None,
+        Statement::ShowTableStats(s) => Some(s.table_name.to_string()),
         Statement::Exists(s) => Some(s.table_name.to_string()),
+        Statement::KillQuery(_) => None,
     }
 }
 
@@ -323,6 +334,8 @@ mod tests {
                             format!("show create table `{table}`"),
                             format!("exists table {table}"),
                             format!("exists table `{table}`"),
+                            format!("delete from {table} where t < 1651737067000"),
+                            format!("delete from `{table}` where t < 1651737067000"),
         ];
         for sql in test_cases {
             assert_eq!(
@@ -0,0 +1,156 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Hints of the query given in the comments starting with `+`, e.g.
+//! `SELECT /*+ NO_CACHE, PARALLEL(8), PRIORITY(LOW) */ * FROM t`.
+//!
+//! Unknown or malformed hints are ignored like the other comments.
+
+use logger::warn;
+use runtime::Priority;
+use sqlparser::{
+    dialect::MySqlDialect,
+    tokenizer::{Token, Tokenizer, Whitespace},
+};
+
+const HINT_PREFIX: char = '+';
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueryHints {
+    /// The data read by the query won't be filled into the caches.
+    pub no_cache: bool,
+    /// Parallelism to read the tables.
+    pub parallelism: Option<usize>,
+    /// Priority overriding the one decided by the time range of the query.
+    pub priority: Option<Priority>,
+}
+
+impl QueryHints {
+    /// Parse the hints from the comments of the sql.
+    pub fn parse(sql: &str) -> Self {
+        let mut hints = Self::default();
+        // The invalid sql will be reported by the parser.
+        let Ok(tokens) = Tokenizer::new(&MySqlDialect {}, sql).tokenize() else {
+            return hints;
+        };
+
+        for token in tokens {
+            if let Token::Whitespace(Whitespace::MultiLineComment(comment)) = token {
+                if let Some(hint_list) = comment.strip_prefix(HINT_PREFIX) {
+                    hints.parse_hint_list(hint_list);
+                }
+            }
+        }
+
+        hints
+    }
+
+    /// Parse hints like `NO_CACHE, PARALLEL(8)`, the separating commas are
+    /// optional.
+    fn parse_hint_list(&mut self, hint_list: &str) {
+        let mut rest = hint_list.trim_start();
+        while !rest.is_empty() {
+            let name_end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            let name = &rest[..name_end];
+            if name.is_empty() {
+                warn!("Invalid query hints are ignored, hints:{hint_list}");
+                return;
+            }
+            rest = rest[name_end..].trim_start();
+
+            let arg = match rest.strip_prefix('(') {
+                Some(arg_start) => {
+                    let Some(arg_end) = arg_start.find(')') else {
+                        warn!("Invalid query hints are ignored, hints:{hint_list}");
+                        return;
+                    };
+                    rest = &arg_start[arg_end + 1..];
+                    Some(arg_start[..arg_end].trim())
+                }
+                None => None,
+            };
+            rest = rest.trim_start().trim_start_matches(',').trim_start();
+
+            self.apply_hint(name, arg);
+        }
+    }
+
+    fn apply_hint(&mut self, name: &str, arg: Option<&str>) {
+        match (name.to_ascii_uppercase().as_str(), arg) {
+            ("NO_CACHE", None) => self.no_cache = true,
+            ("PARALLEL", Some(arg)) => match arg.parse::<usize>() {
+                Ok(parallelism) if parallelism > 0 => self.parallelism = Some(parallelism),
+                _ => warn!("Invalid parallel hint is ignored, arg:{arg}"),
+            },
+            ("PRIORITY", Some(arg)) => match arg.to_ascii_uppercase().as_str() {
+                "HIGH" => self.priority = Some(Priority::High),
+                "LOW" => self.priority = Some(Priority::Low),
+                _ => warn!("Invalid priority hint is ignored, arg:{arg}"),
+            },
+            _ => warn!("Unknown query hint is ignored, name:{name}, arg:{arg:?}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_query_hints() {
+        let hints = QueryHints::parse(
+            "SELECT /*+ NO_CACHE, PARALLEL(8), PRIORITY(LOW) */ * FROM t WHERE a = 1",
+        );
+        assert_eq!(
+            QueryHints {
+                no_cache: true,
+                parallelism: Some(8),
+                priority: Some(Priority::Low),
+            },
+            hints
+        );
+
+        let hints = QueryHints::parse("select /*+ parallel( 2 ) priority(high) */ * from t");
+        assert_eq!(
+            QueryHints {
+                no_cache: false,
+                parallelism: Some(2),
+                priority: Some(Priority::High),
+            },
+            hints
+        );
+
+        let hints = QueryHints::parse("SELECT /*+ PARALLEL(0), UNKNOWN, PRIORITY */ * FROM t");
+        assert_eq!(QueryHints::default(), hints);
+    }
+
+    #[test]
+    fn test_parse_query_hints_ignore_non_hints() {
+        let sqls = [
+            "SELECT * FROM t",
+            "SELECT /* NO_CACHE */ * FROM t",
+            "SELECT * FROM t WHERE a = '/*+ NO_CACHE */'",
+            "SELECT /*+ PARALLEL(8 */ * FROM t",
+            "SELECT /*+ !NO_CACHE */ * FROM t",
+        ];
+        for sql in sqls {
+            assert_eq!(QueryHints::default(), QueryHints::parse(sql), "sql:{sql}");
+        }
+    }
+}
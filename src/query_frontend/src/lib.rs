@@ -26,6 +26,7 @@ pub mod config;
 pub mod container;
 mod datafusion_util;
 pub mod frontend;
+pub mod hint;
 pub mod influxql;
 
 mod logical_optimizer;
@@ -17,6 +17,7 @@
 
 //! Logical optimizer
 
+mod time_ordered_scan;
 mod type_conversion;
 use std::sync::Arc;
 
@@ -27,6 +28,7 @@ use datafusion::{
     optimizer::analyzer::Analyzer,
     prelude::SessionConfig,
 };
+use time_ordered_scan::TimeOrderedScan;
 use type_conversion::TypeConversion;
 
 pub fn optimize_plan(plan: &LogicalPlan) -> Result<LogicalPlan> {
@@ -35,6 +37,7 @@ pub fn optimize_plan(plan: &LogicalPlan) -> Result<LogicalPlan> {
     let state = register_analyzer_rules(state);
     // Register iox optimizers, used by influxql.
     let state = influxql_query::logical_optimizer::register_iox_logical_optimizers(state);
+    let state = state.add_optimizer_rule(Arc::new(TimeOrderedScan));
 
     let plan = state.optimize(plan)?;
 
@@ -0,0 +1,186 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Optimizer pushing down the order by the timestamp with a limit into the
+//! table scan.
+
+use std::sync::Arc;
+
+use common_types::schema::Schema;
+use datafusion::{
+    datasource::{provider_as_source, source_as_provider},
+    error::Result,
+    logical_expr::{expr::Sort as SortExpr, Expr, LogicalPlan, TableScan},
+    optimizer::{optimizer::ApplyOrder, OptimizerConfig, OptimizerRule},
+};
+use table_engine::{provider::TimeOrderedTableProvider, table::ReadOrder};
+
+/// Optimizer replacing the table scanned under the sort by the timestamp with
+/// a fetch by the [TimeOrderedTableProvider], so the table is read in the time
+/// order and the read stops once the rows are enough.
+///
+/// Only the projections keeping the timestamp column are allowed between the
+/// sort and the scan, as the other plans may change the rows read.
+///
+/// Example: `SELECT * FROM t ORDER BY ts DESC LIMIT 10`
+pub struct TimeOrderedScan;
+
+impl OptimizerRule for TimeOrderedScan {
+    fn try_optimize(
+        &self,
+        plan: &LogicalPlan,
+        _config: &dyn OptimizerConfig,
+    ) -> Result<Option<LogicalPlan>> {
+        let LogicalPlan::Sort(sort) = plan else {
+            return Ok(None);
+        };
+        let (Some(limit), [Expr::Sort(SortExpr { expr, asc, .. })]) =
+            (sort.fetch, sort.expr.as_slice())
+        else {
+            return Ok(None);
+        };
+        let Expr::Column(column) = expr.as_ref() else {
+            return Ok(None);
+        };
+        let order = if *asc {
+            ReadOrder::Asc
+        } else {
+            ReadOrder::Desc
+        };
+
+        match rewrite_scan(&sort.input, &column.name, order, limit)? {
+            Some(input) => plan.with_new_inputs(&[input]).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "time_ordered_scan"
+    }
+
+    fn apply_order(&self) -> Option<ApplyOrder> {
+        Some(ApplyOrder::TopDown)
+    }
+}
+
+fn rewrite_scan(
+    plan: &LogicalPlan,
+    column: &str,
+    order: ReadOrder,
+    limit: usize,
+) -> Result<Option<LogicalPlan>> {
+    match plan {
+        LogicalPlan::Projection(projection) => {
+            let keep_column = projection
+                .expr
+                .iter()
+                .any(|expr| matches!(expr, Expr::Column(c) if c.name == column));
+            if !keep_column {
+                return Ok(None);
+            }
+
+            match rewrite_scan(&projection.input, column, order, limit)? {
+                Some(input) => plan.with_new_inputs(&[input]).map(Some),
+                None => Ok(None),
+            }
+        }
+        LogicalPlan::TableScan(scan) => {
+            let Ok(provider) = source_as_provider(&scan.source) else {
+                return Ok(None);
+            };
+            if provider.as_any().is::<TimeOrderedTableProvider>() {
+                return Ok(None);
+            }
+            // Only the table sorted by its timestamp column is read in the time order.
+            let Ok(schema) = Schema::try_from(provider.schema()) else {
+                return Ok(None);
+            };
+            if schema.timestamp_name() != column {
+                return Ok(None);
+            }
+
+            let provider = TimeOrderedTableProvider::new(provider, order, limit);
+            Ok(Some(LogicalPlan::TableScan(TableScan {
+                source: provider_as_source(Arc::new(provider)),
+                ..scan.clone()
+            })))
+        }
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::common::tree_node::{TreeNode, VisitRecursion};
+
+    use super::*;
+    use crate::{plan::Plan, planner::tests::sql_to_logical_plan};
+
+    /// Returns the order and limit of the scanned table if it's read in the
+    /// time order.
+    fn time_ordered_scan(sql: &str) -> Option<(ReadOrder, usize)> {
+        let Plan::Query(plan) = sql_to_logical_plan(sql).unwrap() else {
+            panic!("It should be query plan");
+        };
+
+        let mut scans = Vec::new();
+        plan.df_plan
+            .apply(&mut |plan| {
+                if let LogicalPlan::TableScan(scan) = plan {
+                    let provider = source_as_provider(&scan.source).unwrap();
+                    scans.push(
+                        provider
+                            .as_any()
+                            .downcast_ref::<TimeOrderedTableProvider>()
+                            .map(|v| (v.order(), v.limit())),
+                    );
+                }
+                Ok(VisitRecursion::Continue)
+            })
+            .unwrap();
+
+        assert_eq!(1, scans.len());
+        scans.remove(0)
+    }
+
+    #[test]
+    fn test_time_ordered_scan() {
+        let cases = [
+            (
+                "select * from test_table order by key2 desc limit 10",
+                Some((ReadOrder::Desc, 10)),
+            ),
+            (
+                "select key1, key2 from test_table order by key2 limit 5 offset 5",
+                Some((ReadOrder::Asc, 10)),
+            ),
+            ("select * from test_table order by key2 desc", None),
+            (
+                "select * from test_table order by field1 desc limit 10",
+                None,
+            ),
+            (
+                "select * from test_table order by key2 desc, key1 limit 10",
+                None,
+            ),
+        ];
+
+        for (sql, expect) in cases {
+            assert_eq!(expect, time_ordered_scan(sql), "sql:{sql}");
+        }
+    }
+}
@@ -36,8 +36,8 @@ use table_engine::ANALYTIC_ENGINE_TYPE;
 use crate::{
     ast::{
         AlterAddColumn, AlterModifySetting, CreateTable, DescribeTable, DropTable, ExistsTable,
-        HashPartition, KeyPartition, Partition, RandomPartition, ShowCreate, ShowCreateObject,
-        ShowTables, Statement,
+        HashPartition, KeyPartition, KillQuery, Partition, RandomPartition, SeriesPartition,
+        ShowCreate, ShowCreateObject, ShowTableStats, ShowTables, Statement,
     },
     partition,
 };
@@ -204,6 +204,10 @@ impl<'a> Parser<'a> {
                         self.parser.next_token();
                         self.parse_exists()
                     }
+                    Keyword::KILL => {
+                        self.parser.next_token();
+                        self.parse_kill()
+                    }
                     _ => {
                         // use the native parser
                         let mut statement = self.parser.parse_statement()?;
@@ -255,8 +259,14 @@ impl<'a> Parser<'a> {
             Ok(Statement::ShowDatabases)
         } else if self.consume_token("CREATE") {
             Ok(self.parse_show_create()?)
+        } else if self.consume_tokens(&["TABLE", "STATS"]) {
+            let table_name = self.parser.parse_object_name()?.into();
+            Ok(Statement::ShowTableStats(ShowTableStats { table_name }))
         } else {
-            self.expected("create/tables/databases", self.parser.peek_token().token)
+            self.expected(
+                "create/tables/databases/table stats",
+                self.parser.peek_token().token,
+            )
         }
     }
 
@@ -390,6 +400,16 @@ impl<'a> Parser<'a> {
         Ok(Statement::Exists(ExistsTable { table_name }))
     }
 
+    // Parse "KILL [QUERY] 'query_id'"
+    pub fn parse_kill(&mut self) -> Result<Statement> {
+        if self.parser.parse_keyword(Keyword::CONNECTION) {
+            return parser_err!("KILL CONNECTION is not supported".to_string());
+        }
+        let _ = self.parser.parse_keyword(Keyword::QUERY);
+        let query_id = self.parser.parse_literal_string()?;
+        Ok(Statement::KillQuery(KillQuery { query_id }))
+    }
+
     // Copy from sqlparser
     fn parse_columns(&mut self) -> Result<(Vec<ColumnDef>, Vec<TableConstraint>)> {
         let mut columns = vec![];
@@ -591,6 +611,9 @@ impl<'a> Parser<'a> {
         if let Some(key) = self.maybe_parse_and_check_key_partition(columns)? {
             return Ok(Some(Partition::Key(key)));
         }
+        if let Some(series) = self.maybe_parse_and_check_series_partition(columns)? {
+            return Ok(Some(Partition::Series(series)));
+        }
         if let Some(hash) = self.maybe_parse_and_check_hash_partition(columns)? {
             return Ok(Some(Partition::Hash(hash)));
         }
@@ -694,6 +717,39 @@ impl<'a> Parser<'a> {
         }))
     }
 
+    // Parse "PARTITION BY SERIES [PARTITIONS num]".
+    //
+    // Rows are routed by the consistent hash of the whole series key, that is to
+    // say, all the tag columns in the order of definition, so the query of a
+    // single series is pruned to one partition.
+    fn maybe_parse_and_check_series_partition(
+        &mut self,
+        columns: &[ColumnDef],
+    ) -> Result<Option<SeriesPartition>> {
+        if !self.consume_token("SERIES") {
+            return Ok(None);
+        }
+
+        let series_key: Vec<_> = columns
+            .iter()
+            .filter(|col| col.options.iter().any(|opt| is_tag_column(&opt.option)))
+            .map(|col| col.name.value.clone())
+            .collect();
+        if series_key.is_empty() {
+            return parser_err!(
+                "series partition requires at least one tag column in the table".to_string()
+            );
+        }
+
+        let partition_num = self.parse_partition_num()?.unwrap_or(1);
+
+        // Parse successfully.
+        Ok(Some(SeriesPartition {
+            partition_num,
+            series_key,
+        }))
+    }
+
     // Parse second part: "PARTITIONS num".
     //
     // If not found, return `Ok(None)`.
@@ -1328,6 +1384,18 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_kill_query() {
+        for sql in ["KILL QUERY 'abc-123'", "KILL 'abc-123'"] {
+            let expected = Statement::KillQuery(KillQuery {
+                query_id: "abc-123".to_string(),
+            });
+            expect_parse_ok(sql, expected).unwrap();
+        }
+
+        assert!(Parser::parse_sql("KILL CONNECTION 'abc-123'").is_err());
+    }
+
     #[test]
     fn test_exists_table() {
         {
@@ -1386,6 +1454,29 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_show_table_stats() {
+        {
+            let sql = "show table stats t";
+            let statements = Parser::parse_sql(sql).unwrap();
+            assert_eq!(statements.len(), 1);
+            assert!(matches!(
+                &statements[0],
+                Statement::ShowTableStats(ShowTableStats { table_name }) if table_name.to_string() == "t"
+            ));
+        }
+
+        {
+            let sql = "show table stats";
+            assert!(Parser::parse_sql(sql).is_err());
+        }
+
+        {
+            let sql = "show table t";
+            assert!(Parser::parse_sql(sql).is_err());
+        }
+    }
+
     #[test]
     fn test_normalizing_table_name_in_select() {
         {
@@ -1554,6 +1645,33 @@ mod tests {
         KeyPartitionTableCases::invalid_column_type();
     }
 
+    #[test]
+    fn test_series_partition() {
+        let sql = r#"CREATE TABLE `demo` (`host` string TAG, `value` double NOT NULL, `region` string TAG, `t` timestamp NOT NULL, TIMESTAMP KEY(t)) PARTITION BY SERIES PARTITIONS 4 ENGINE=Analytic"#;
+        let stmt = Parser::parse_sql(sql).unwrap();
+        assert_eq!(stmt.len(), 1);
+        match &stmt[0] {
+            Statement::Create(v) => {
+                if let Some(Partition::Series(p)) = &v.partition {
+                    assert_eq!(p.series_key, vec!["host", "region"]);
+                    assert_eq!(p.partition_num, 4);
+                } else {
+                    panic!("failed");
+                };
+            }
+            _ => panic!("failed"),
+        }
+
+        let sql = r#"CREATE TABLE `demo` (`value` double NOT NULL, `t` timestamp NOT NULL, TIMESTAMP KEY(t)) PARTITION BY SERIES PARTITIONS 4 ENGINE=Analytic"#;
+        let stmt = Parser::parse_sql(sql);
+        assert_eq!(
+            stmt.err().unwrap(),
+            ParserError(
+                "series partition requires at least one tag column in the table".to_string()
+            )
+        );
+    }
+
     struct KeyPartitionTableCases;
 
     impl KeyPartitionTableCases {
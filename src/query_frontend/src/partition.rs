@@ -24,10 +24,11 @@ use snafu::ResultExt;
 use sqlparser::ast::Expr as SqlExpr;
 use table_engine::partition::{
     HashPartitionInfo, KeyPartitionInfo, PartitionDefinition, PartitionInfo, RandomPartitionInfo,
+    SERIES_PARTITION_VERSION,
 };
 
 use crate::{
-    ast::{HashPartition, KeyPartition, Partition, RandomPartition},
+    ast::{HashPartition, KeyPartition, Partition, RandomPartition, SeriesPartition},
     planner::{ParsePartitionWithCause, Result, UnsupportedPartition},
 };
 
@@ -42,6 +43,7 @@ impl PartitionParser {
             Partition::Random(stmt) => PartitionInfo::Random(Self::parse_random(stmt)),
             Partition::Hash(stmt) => PartitionInfo::Hash(Self::parse_hash(stmt)?),
             Partition::Key(stmt) => PartitionInfo::Key(Self::parse_key(stmt)?),
+            Partition::Series(stmt) => PartitionInfo::Key(Self::parse_series(stmt)),
         })
     }
 
@@ -95,6 +97,22 @@ impl PartitionParser {
             linear,
         })
     }
+
+    /// The series partition is persisted as a key partition of the series key,
+    /// whose version tells the rule to locate by the consistent hash.
+    fn parse_series(series_stmt: SeriesPartition) -> KeyPartitionInfo {
+        let SeriesPartition {
+            partition_num,
+            series_key,
+        } = series_stmt;
+
+        KeyPartitionInfo {
+            version: SERIES_PARTITION_VERSION,
+            definitions: make_partition_definitions(partition_num),
+            partition_key: series_key,
+            linear: false,
+        }
+    }
 }
 
 fn make_partition_definitions(partition_num: u64) -> Vec<PartitionDefinition> {
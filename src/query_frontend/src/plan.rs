@@ -25,7 +25,12 @@ use std::{
     sync::Arc,
 };
 
-use common_types::{column_schema::ColumnSchema, row::RowGroup, schema::Schema, time::TimeRange};
+use common_types::{
+    column_schema::ColumnSchema,
+    row::RowGroup,
+    schema::Schema,
+    time::{TimeRange, Timestamp},
+};
 use datafusion::{
     logical_expr::{
         expr::Expr as DfLogicalExpr, logical_plan::LogicalPlan as DataFusionLogicalPlan,
@@ -75,6 +80,8 @@ pub enum Plan {
     Insert(InsertPlan),
     /// Create table plan
     Create(CreateTablePlan),
+    /// Create tables in batch
+    CreateTables(CreateTablesPlan),
     /// Drop table plan
     Drop(DropTablePlan),
     /// Describe table plan
@@ -85,6 +92,10 @@ pub enum Plan {
     Show(ShowPlan),
     /// Exists table
     Exists(ExistsTablePlan),
+    /// Kill a running query
+    KillQuery(KillQueryPlan),
+    /// Delete the data before a timestamp
+    Delete(DeletePlan),
 }
 
 impl Plan {
@@ -92,12 +103,15 @@ impl Plan {
         match self {
             Self::Query(_) => "query",
             Self::Insert(_) => "insert",
+            Self::Delete(_) => "delete",
             Self::Create(_)
+            | Self::CreateTables(_)
             | Self::Drop(_)
             | Self::Describe(_)
             | Self::AlterTable(_)
             | Self::Show(_)
-            | Self::Exists(_) => "other",
+            | Self::Exists(_)
+            | Self::KillQuery(_) => "other",
         }
     }
 }
@@ -106,6 +120,7 @@ pub struct PriorityContext {
     pub time_range_threshold: u64,
 }
 
+#[derive(Clone)]
 pub struct QueryPlan {
     pub df_plan: DataFusionLogicalPlan,
     pub table_name: Option<String>,
@@ -142,6 +157,9 @@ impl QueryPlan {
     /// Note: When it timestamp filter evals to false(such as ts < 10 and ts >
     /// 100), it will return None, which means no valid time range for this
     /// query.
+    ///
+    /// The time range is in millis regardless of the timestamp precision of
+    /// the table, as the timestamps are read in millis.
     fn extract_time_range(&self) -> Result<Option<TimeRange>> {
         let ts_column = if let Some(v) = self.find_timestamp_column()? {
             v
@@ -297,6 +315,13 @@ impl Debug for CreateTablePlan {
     }
 }
 
+/// Plan to create many tables at once, either all of the tables are created or
+/// none of them.
+#[derive(Debug)]
+pub struct CreateTablesPlan {
+    pub plans: Vec<CreateTablePlan>,
+}
+
 #[derive(Debug)]
 pub struct DropTablePlan {
     /// Engine
@@ -374,6 +399,12 @@ pub struct ShowTablesPlan {
     pub query_type: QueryType,
 }
 
+#[derive(Debug)]
+pub struct ShowTableStatsPlan {
+    /// The table to show.
+    pub table: TableRef,
+}
+
 #[derive(Debug)]
 pub enum ShowPlan {
     /// show create table
@@ -382,6 +413,8 @@ pub enum ShowPlan {
     ShowTablesPlan(ShowTablesPlan),
     /// show database
     ShowDatabase,
+    /// show table stats
+    ShowTableStatsPlan(ShowTableStatsPlan),
 }
 
 #[derive(Debug)]
@@ -389,6 +422,24 @@ pub struct ExistsTablePlan {
     pub exists: bool,
 }
 
+#[derive(Debug)]
+pub struct KillQueryPlan {
+    pub query_id: String,
+}
+
+/// Delete logical plan
+///
+/// Only the deletion by a timestamp-only predicate is supported, that is to
+/// say, all the rows whose timestamp is before `before` are deleted.
+#[derive(Debug)]
+pub struct DeletePlan {
+    /// The table to delete from
+    pub table: TableRef,
+    /// The exclusive upper bound of the timestamps to delete, in the timestamp
+    /// precision of the table
+    pub before: Timestamp,
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -430,6 +481,12 @@ mod tests {
             ),
              // no timestamp filter
             ("select * from test_table", Some((i64::MIN, i64::MAX))),
+            // the time range of the table in us is in millis
+            (
+                "select * from test_table_us where key2 >= 1 and key2 < 10",
+                Some((1, 10)),
+            ),
+            ("select * from test_table_us", Some((i64::MIN, i64::MAX))),
             // aggr
             (
                 "select key2, sum(field1) from test_table where key2 > 1 and key2 < 10 group by key2",
@@ -27,7 +27,7 @@ use std::{
 };
 
 use arrow::{
-    compute::can_cast_types,
+    compute::{can_cast_types, kernels::cast_utils::string_to_timestamp_nanos},
     datatypes::{DataType as ArrowDataType, Field as ArrowField, Schema as ArrowSchema},
     error::ArrowError,
 };
@@ -39,6 +39,7 @@ use common_types::{
     request_id::RequestId,
     row::{RowBuilder, RowGroup},
     schema::{self, Builder as SchemaBuilder, Schema, TSID_COLUMN},
+    time::{Timestamp, TimestampPrecision},
 };
 use datafusion::{
     common::{DFField, DFSchema},
@@ -58,15 +59,16 @@ use macros::define_result;
 use prom_remote_api::types::Query as PromRemoteQuery;
 use snafu::{ensure, Backtrace, OptionExt, ResultExt, Snafu};
 use sqlparser::ast::{
-    visit_statements_mut, ColumnDef, ColumnOption, Expr, Expr as SqlExpr, Ident, Query, SelectItem,
-    SetExpr, SqlOption, Statement as SqlStatement, TableConstraint, UnaryOperator, Value, Values,
+    visit_statements_mut, BinaryOperator, ColumnDef, ColumnOption, Expr, Expr as SqlExpr, Ident,
+    Query, SelectItem, SetExpr, SqlOption, Statement as SqlStatement, TableConstraint, TableFactor,
+    TableWithJoins, UnaryOperator, Value, Values,
 };
 use table_engine::table::TableRef;
 
 use crate::{
     ast::{
         AlterAddColumn, AlterModifySetting, CreateTable, DescribeTable, DropTable, ExistsTable,
-        ShowCreate, ShowTables, Statement, TableName,
+        ShowCreate, ShowTableStats, ShowTables, Statement, TableName,
     },
     config::DynamicConfig,
     container::TableReference,
@@ -79,9 +81,9 @@ use crate::{
     parser,
     partition::PartitionParser,
     plan::{
-        AlterTableOperation, AlterTablePlan, CreateTablePlan, DescribeTablePlan, DropTablePlan,
-        ExistsTablePlan, InsertPlan, InsertSource, Plan, QueryPlan, QueryType, ShowCreatePlan,
-        ShowPlan, ShowTablesPlan,
+        AlterTableOperation, AlterTablePlan, CreateTablePlan, DeletePlan, DescribeTablePlan,
+        DropTablePlan, ExistsTablePlan, InsertPlan, InsertSource, KillQueryPlan, Plan, QueryPlan,
+        QueryType, ShowCreatePlan, ShowPlan, ShowTableStatsPlan, ShowTablesPlan,
     },
     promql::{remote_query_to_plan, ColumnNames, Expr as PromExpr, RemoteQueryPlan},
     provider::{ContextProviderAdapter, MetaProvider},
@@ -208,6 +210,9 @@ pub enum Error {
     #[snafu(display("Insert values not enough, len:{}, index:{}", len, index))]
     InsertValuesNotEnough { len: usize, index: usize },
 
+    #[snafu(display("Invalid delete stmt, msg:{}", msg))]
+    InvalidDeleteStmt { msg: String },
+
     #[snafu(display("Invalid insert stmt, contains duplicate columns"))]
     InsertDuplicateColumns,
 
@@ -355,7 +360,11 @@ impl<'a, P: MetaProvider> Planner<'a, P> {
             Statement::ShowCreate(s) => planner.show_create_to_plan(s),
             Statement::ShowTables(s) => planner.show_tables_to_plan(s),
             Statement::ShowDatabases => planner.show_databases_to_plan(),
+            Statement::ShowTableStats(s) => planner.show_table_stats_to_plan(s),
             Statement::Exists(s) => planner.exists_table_to_plan(s),
+            Statement::KillQuery(s) => Ok(Plan::KillQuery(KillQueryPlan {
+                query_id: s.query_id,
+            })),
         }
     }
 
@@ -399,12 +408,15 @@ impl<'a, P: MetaProvider> Planner<'a, P> {
         schema_config: &SchemaConfig,
         write_table: &WriteTableRequest,
     ) -> Result<Plan> {
+        let mut options = HashMap::default();
+        schema_config.default_table_options.apply_to(&mut options);
+
         Ok(Plan::Create(CreateTablePlan {
             engine: schema_config.default_engine_type.clone(),
             if_not_exists: true,
             table: write_table.table.clone(),
             table_schema: build_schema_from_write_table_request(schema_config, write_table)?,
-            options: HashMap::default(),
+            options,
             partition_info: None,
         }))
     }
@@ -625,6 +637,7 @@ impl<'a, P: MetaProvider> PlannerDelegate<'a, P> {
                 self.sql_statement_to_datafusion_plan(sql_stmt)
             }
             SqlStatement::Insert { .. } => self.insert_to_plan(sql_stmt),
+            SqlStatement::Delete { .. } => self.delete_to_plan(sql_stmt),
             _ => UnsupportedStatement.fail(),
         }
     }
@@ -1015,6 +1028,39 @@ impl<'a, P: MetaProvider> PlannerDelegate<'a, P> {
         }
     }
 
+    fn delete_to_plan(self, sql_stmt: SqlStatement) -> Result<Plan> {
+        let SqlStatement::Delete {
+            from, selection, ..
+        } = sql_stmt
+        else {
+            // We already known this stmt is a DELETE stmt
+            unreachable!()
+        };
+
+        let table_name = match from.as_slice() {
+            [TableWithJoins {
+                relation: TableFactor::Table { name, .. },
+                joins,
+            }] if joins.is_empty() => TableName::from(name.clone()).to_string(),
+            _ => {
+                return InvalidDeleteStmt {
+                    msg: "only deleting from a single table is supported",
+                }
+                .fail()
+            }
+        };
+        let table = self
+            .find_table(&table_name)?
+            .context(TableNotFound { name: table_name })?;
+        let selection = selection.context(InvalidDeleteStmt {
+            msg: "predicate on the timestamp column is required, use DROP TABLE to delete all",
+        })?;
+        let precision = table.timestamp_precision();
+        let before = parse_delete_before(&table.schema(), precision, &selection)?;
+
+        Ok(Plan::Delete(DeletePlan { table, before }))
+    }
+
     fn alter_modify_setting_to_plan(&self, stmt: AlterModifySetting) -> Result<Plan> {
         let table_name = stmt.table_name.to_string();
 
@@ -1072,6 +1118,15 @@ impl<'a, P: MetaProvider> PlannerDelegate<'a, P> {
         Ok(Plan::Show(ShowPlan::ShowDatabase))
     }
 
+    fn show_table_stats_to_plan(&self, show_table_stats: ShowTableStats) -> Result<Plan> {
+        let table_name = show_table_stats.table_name.to_string();
+        let table = self
+            .find_table(&table_name)?
+            .context(TableNotFound { name: table_name })?;
+        let plan = ShowTableStatsPlan { table };
+        Ok(Plan::Show(ShowPlan::ShowTableStatsPlan(plan)))
+    }
+
     pub(crate) fn find_table(&self, table_name: &str) -> Result<Option<TableRef>> {
         let table_ref = get_table_ref(table_name);
         let resolved_table = self
@@ -1271,6 +1326,80 @@ fn validate_insert_stmt(
     Ok(())
 }
 
+/// Parse the exclusive upper bound (in the timestamp `precision` of the table)
+/// of the timestamps to delete from the predicate of the delete stmt.
+///
+/// The predicate must be a comparison between the timestamp column and a
+/// literal, e.g. `ts < 1700000000000` or `ts <= '2023-11-15 00:00:00'`. The
+/// literal is in millis, in which the queries read the timestamps, and
+/// converted into the precision, so the deleted rows are exactly the ones
+/// selected by the same predicate.
+fn parse_delete_before(
+    schema: &Schema,
+    precision: TimestampPrecision,
+    predicate: &SqlExpr,
+) -> Result<Timestamp> {
+    let SqlExpr::BinaryOp { left, op, right } = predicate else {
+        return InvalidDeleteStmt {
+            msg: format!("unsupported predicate:{predicate}"),
+        }
+        .fail();
+    };
+
+    let timestamp_name = schema.timestamp_name();
+    let is_timestamp_column = |expr: &SqlExpr| match expr {
+        SqlExpr::Identifier(ident) => ident.value == timestamp_name,
+        _ => false,
+    };
+    // Normalize the predicate into the form of `ts op value`.
+    let (op, value) = if is_timestamp_column(left) {
+        (op.clone(), right.as_ref())
+    } else if is_timestamp_column(right) {
+        let op = match op {
+            BinaryOperator::Gt => BinaryOperator::Lt,
+            BinaryOperator::GtEq => BinaryOperator::LtEq,
+            BinaryOperator::Lt => BinaryOperator::Gt,
+            BinaryOperator::LtEq => BinaryOperator::GtEq,
+            op => op.clone(),
+        };
+        (op, left.as_ref())
+    } else {
+        return InvalidDeleteStmt {
+            msg: format!("only predicate on the timestamp column {timestamp_name} is supported"),
+        }
+        .fail();
+    };
+
+    let value = match value {
+        SqlExpr::Value(Value::Number(n, _)) => n.parse::<i64>().ok(),
+        SqlExpr::Value(Value::SingleQuotedString(s)) => string_to_timestamp_nanos(s)
+            .ok()
+            .map(|nanos| nanos.div_euclid(1_000_000)),
+        _ => None,
+    }
+    .with_context(|| InvalidDeleteStmt {
+        msg: format!("invalid timestamp literal:{value}"),
+    })?;
+
+    let before = match op {
+        BinaryOperator::Lt => precision.checked_from_millis_ceil(value),
+        BinaryOperator::LtEq => value
+            .checked_add(1)
+            .and_then(|v| precision.checked_from_millis_ceil(v)),
+        _ => {
+            return InvalidDeleteStmt {
+                msg: format!("only < and <= are supported, op:{op}"),
+            }
+            .fail()
+        }
+    }
+    .with_context(|| InvalidDeleteStmt {
+        msg: format!("timestamp overflow, value:{value}, precision:{precision}"),
+    })?;
+
+    Ok(Timestamp::new(before))
+}
+
 fn parse_options(options: Vec<SqlOption>) -> Result<HashMap<String, String>> {
     let mut parsed_options = HashMap::with_capacity(options.len());
 
@@ -1442,10 +1571,13 @@ pub fn get_table_ref(table_name: &str) -> TableReference {
 
 #[cfg(test)]
 pub mod tests {
+    use cluster::config::DefaultTableOptions;
+    use common_types::{COMPACTION_STRATEGY, TTL};
     use datafusion::{
         common::tree_node::{TreeNode, TreeNodeVisitor, VisitRecursion},
         datasource::source_as_provider,
-        logical_expr::LogicalPlan,
+        logical_expr::{BinaryExpr, Expr as DfExpr, LogicalPlan, Operator},
+        scalar::ScalarValue,
     };
     use horaedbproto::storage::{
         value, Field, FieldGroup, Tag, Value as PbValue, WriteSeriesEntry,
@@ -1746,6 +1878,126 @@ pub mod tests {
         df_plan.visit(&mut visitor).unwrap();
     }
 
+    #[test]
+    fn test_delete_statement_to_plan() {
+        let cases = [
+            (
+                "DELETE FROM test_table WHERE key2 < 1638428434000",
+                1638428434000,
+            ),
+            (
+                "DELETE FROM test_table WHERE key2 <= 1638428434000",
+                1638428434001,
+            ),
+            (
+                "DELETE FROM test_table WHERE 1638428434000 > key2",
+                1638428434000,
+            ),
+            (
+                "DELETE FROM test_table WHERE key2 < '2021-12-02T07:00:34Z'",
+                1638428434000,
+            ),
+        ];
+        for (sql, expected) in cases {
+            let Plan::Delete(plan) = sql_to_logical_plan(sql).unwrap() else {
+                panic!("expect delete plan, sql:{sql}");
+            };
+            assert_eq!(plan.table.name(), "test_table");
+            assert_eq!(plan.before.as_i64(), expected);
+        }
+
+        let invalid_sqls = [
+            "DELETE FROM test_table",
+            "DELETE FROM test_tablex WHERE key2 < 1638428434000",
+            "DELETE FROM test_table WHERE key2 > 1638428434000",
+            "DELETE FROM test_table WHERE key1 < 'a'",
+            "DELETE FROM test_table WHERE key2 < 1 AND key1 = 'a'",
+        ];
+        for sql in invalid_sqls {
+            assert!(sql_to_logical_plan(sql).is_err(), "sql:{sql}");
+        }
+    }
+
+    /// Returns whether the row with the timestamp `ts` (in the precision of the
+    /// table) is selected by the timestamp predicate of the query, which
+    /// compares the timestamp read in millis.
+    fn is_selected_by_query(sql: &str, precision: TimestampPrecision, ts: i64) -> bool {
+        let Plan::Query(plan) = sql_to_logical_plan(sql).unwrap() else {
+            panic!("expect query plan, sql:{sql}");
+        };
+
+        let mut predicate = None;
+        plan.df_plan
+            .apply(&mut |plan| {
+                for expr in plan.expressions() {
+                    if let DfExpr::BinaryExpr(BinaryExpr { left, op, right }) = expr {
+                        let (op, value) = match (left.as_ref(), right.as_ref()) {
+                            (DfExpr::Column(_), DfExpr::Literal(value)) => (op, value.clone()),
+                            (DfExpr::Literal(value), DfExpr::Column(_)) => {
+                                (op.swap().unwrap(), value.clone())
+                            }
+                            _ => continue,
+                        };
+                        if let ScalarValue::TimestampMillisecond(Some(v), _) = value {
+                            predicate = Some((op, v));
+                            return Ok(VisitRecursion::Stop);
+                        }
+                    }
+                }
+                Ok(VisitRecursion::Continue)
+            })
+            .unwrap();
+
+        let (op, value) = predicate.unwrap();
+        let millis = precision.checked_to_millis(ts).unwrap();
+        match op {
+            Operator::Lt => millis < value,
+            Operator::LtEq => millis <= value,
+            _ => panic!("unexpected op:{op}, sql:{sql}"),
+        }
+    }
+
+    #[test]
+    fn test_delete_matches_query_in_precision() {
+        let predicates = [
+            "key2 < 1638428434500",
+            "key2 <= 1638428434500",
+            "key2 < 1638428434000",
+            "key2 <= 1638428434000",
+            "1638428434500 > key2",
+            "key2 < '2021-12-02T07:00:34.500Z'",
+        ];
+        for (table, precision) in [
+            ("test_table", TimestampPrecision::Millisecond),
+            ("test_table_s", TimestampPrecision::Second),
+            ("test_table_us", TimestampPrecision::Microsecond),
+        ] {
+            for predicate in predicates {
+                let query = format!("SELECT * FROM {table} WHERE {predicate}");
+                let delete = format!("DELETE FROM {table} WHERE {predicate}");
+                let Plan::Delete(plan) = sql_to_logical_plan(&delete).unwrap() else {
+                    panic!("expect delete plan, sql:{delete}");
+                };
+
+                // Around the bounds of the millis 1638428434500 in the precision.
+                let start = precision.checked_from_millis(1638428434500).unwrap();
+                let end = precision.checked_from_millis(1638428434501).unwrap();
+                for ts in (start - 3..=start + 3).chain(end - 3..=end + 3) {
+                    assert_eq!(
+                        is_selected_by_query(&query, precision, ts),
+                        ts < plan.before.as_i64(),
+                        "ts:{ts}, sql:{delete}"
+                    );
+                }
+            }
+        }
+
+        // Overflow in the precision of the table.
+        assert!(
+            sql_to_logical_plan("DELETE FROM test_table_us WHERE key2 < 9223372036854776").is_err()
+        );
+    }
+
     #[test]
     fn test_insert_statement_to_plan() {
         let sql = "INSERT INTO test_tablex(key1, key2, field1, field2) VALUES('tagk', 1638428434000, 100, 'hello3');";
@@ -2674,6 +2926,44 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn test_default_table_options_precedence() {
+        let schema_config = SchemaConfig {
+            default_table_options: DefaultTableOptions {
+                ttl: Some("7d".to_string()),
+                compaction_strategy: Some("time_window".to_string()),
+                ..Default::default()
+            },
+            ..SchemaConfig::default()
+        };
+
+        // The options specified on the creation win over the defaults.
+        let sql = "CREATE TABLE IF NOT EXISTS t(c1 string tag not null,
+                                                      ts timestamp not null,
+                                                      timestamp key(ts),primary key(c1, ts)) \
+        ENGINE=Analytic WITH (ttl='1d')";
+        let Plan::Create(mut plan) = sql_to_logical_plan(sql).unwrap() else {
+            panic!("expect create table plan");
+        };
+        schema_config
+            .default_table_options
+            .apply_to(&mut plan.options);
+        assert_eq!("1d", plan.options[TTL]);
+        assert_eq!("time_window", plan.options[COMPACTION_STRATEGY]);
+
+        // The auto created tables take all the defaults.
+        let mock = MockMetaProvider::default();
+        let planner = build_planner(&mock, &DynamicConfig::default());
+        let Plan::Create(plan) = planner
+            .write_req_to_plan(&schema_config, &generate_write_table_request())
+            .unwrap()
+        else {
+            panic!("expect create table plan");
+        };
+        assert_eq!("7d", plan.options[TTL]);
+        assert_eq!("time_window", plan.options[COMPACTION_STRATEGY]);
+    }
+
     #[test]
     fn test_build_schema_from_write_table_request() {
         let schema_config = SchemaConfig {
@@ -15,11 +15,14 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use catalog::consts::{DEFAULT_CATALOG, DEFAULT_SCHEMA};
-use common_types::tests::{
-    build_default_value_schema, build_schema, build_schema_for_cpu, build_schema_for_metric,
+use common_types::{
+    tests::{
+        build_default_value_schema, build_schema, build_schema_for_cpu, build_schema_for_metric,
+    },
+    TIMESTAMP_PRECISION,
 };
 use datafusion::catalog::TableReference;
 use df_operator::{scalar::ScalarUdf, udaf::AggregateUdf};
@@ -95,6 +98,31 @@ impl Default for MockMetaProvider {
                     build_schema_for_metric(),
                     ANALYTIC_ENGINE_TYPE.to_string(),
                 )),
+                // Used in the tests of the timestamp precision
+                Arc::new(
+                    MemoryTable::new(
+                        "test_table_us".to_string(),
+                        TableId::from(107),
+                        build_schema(),
+                        ANALYTIC_ENGINE_TYPE.to_string(),
+                    )
+                    .with_options(HashMap::from([(
+                        TIMESTAMP_PRECISION.to_string(),
+                        "us".to_string(),
+                    )])),
+                ),
+                Arc::new(
+                    MemoryTable::new(
+                        "test_table_s".to_string(),
+                        TableId::from(108),
+                        build_schema(),
+                        ANALYTIC_ENGINE_TYPE.to_string(),
+                    )
+                    .with_options(HashMap::from([(
+                        TIMESTAMP_PRECISION.to_string(),
+                        "s".to_string(),
+                    )])),
+                ),
             ],
         }
     }
@@ -28,13 +28,14 @@ use snafu::ResultExt;
 
 use crate::{
     endpoint::Endpoint, OtherWithCause, ParseEndpoint, Result, RouteCacheConfig, RouteRequest,
-    Router, TableInfo,
+    RouteWithEpoch, Router, ShardEpoch, TableInfo,
 };
 
 #[derive(Clone, Debug)]
 struct RouteData {
     table_info: TableInfo,
     endpoint: Option<Endpoint>,
+    epoch: Option<ShardEpoch>,
 }
 
 pub struct ClusterBasedRouter {
@@ -103,14 +104,22 @@ impl ClusterBasedRouter {
         // Now we pick up the nodes who own the leader shard for the route response.
         for (table_name, route_entry) in route_resp.entries {
             let route = if route_entry.node_shards.is_empty() {
-                Some(make_route(route_entry.table_info, None)?)
+                Some(make_route(route_entry.table_info, None, None)?)
             } else {
                 route_entry
                     .node_shards
                     .into_iter()
                     .find(|node_shard| node_shard.shard_info.is_leader())
                     .map(|node_shard| {
-                        make_route(route_entry.table_info, Some(&node_shard.endpoint))
+                        let epoch = ShardEpoch {
+                            shard_id: node_shard.shard_info.id,
+                            version: node_shard.shard_info.version,
+                        };
+                        make_route(
+                            route_entry.table_info,
+                            Some(&node_shard.endpoint),
+                            Some(epoch),
+                        )
                     })
                     .transpose()?
             };
@@ -154,8 +163,13 @@ impl ClusterBasedRouter {
     }
 }
 
-/// Make a route according to the table_info and the raw endpoint.
-fn make_route(table_info: TableInfo, endpoint: Option<&str>) -> Result<RouteData> {
+/// Make a route according to the table_info, the raw endpoint and the epoch of
+/// the shard.
+fn make_route(
+    table_info: TableInfo,
+    endpoint: Option<&str>,
+    epoch: Option<ShardEpoch>,
+) -> Result<RouteData> {
     let endpoint = endpoint
         .map(|v| v.parse().context(ParseEndpoint { endpoint: v }))
         .transpose()?;
@@ -163,12 +177,18 @@ fn make_route(table_info: TableInfo, endpoint: Option<&str>) -> Result<RouteData
     Ok(RouteData {
         table_info,
         endpoint,
+        epoch,
     })
 }
 
 #[async_trait]
 impl Router for ClusterBasedRouter {
     async fn route(&self, req: RouteRequest) -> Result<Vec<Route>> {
+        let routes = self.route_with_epoch(req).await?;
+        Ok(routes.into_iter().map(|v| v.route).collect())
+    }
+
+    async fn route_with_epoch(&self, req: RouteRequest) -> Result<Vec<RouteWithEpoch>> {
         let req_ctx = req.inner.context.unwrap();
         let route_datas = self
             .route_internal(&req.inner.tables, req_ctx.database, req.route_with_cache)
@@ -176,9 +196,12 @@ impl Router for ClusterBasedRouter {
 
         Ok(route_datas
             .into_iter()
-            .map(|v| Route {
-                table: v.table_info.name,
-                endpoint: v.endpoint.map(Into::into),
+            .map(|v| RouteWithEpoch {
+                route: Route {
+                    table: v.table_info.name,
+                    endpoint: v.endpoint.map(Into::into),
+                },
+                epoch: v.epoch,
             })
             .collect())
     }
@@ -202,8 +225,10 @@ mod tests {
     use std::{collections::HashMap, sync::Arc, thread::sleep, time::Duration};
 
     use cluster::{
-        shard_lock_manager::ShardLockManagerRef, shard_set::ShardRef, Cluster, ClusterNodesResp,
-        TableStatus,
+        rebalance::{RebalanceProposal, ShardLoad},
+        shard_lock_manager::ShardLockManagerRef,
+        shard_set::ShardRef,
+        Cluster, ClusterNodesResp, TableStatus,
     };
     use common_types::table::ShardId;
     use horaedbproto::storage::{RequestContext, RouteRequest as RouteRequestPb};
@@ -230,6 +255,10 @@ mod tests {
             unimplemented!();
         }
 
+        async fn open_shards(&self, _: &[ShardInfo]) -> cluster::Result<Vec<ShardRef>> {
+            unimplemented!();
+        }
+
         fn shard(&self, _: ShardId) -> Option<ShardRef> {
             unimplemented!();
         }
@@ -238,6 +267,10 @@ mod tests {
             unimplemented!()
         }
 
+        async fn wait_table_ready(&self, _: &str, _: &str) -> cluster::Result<()> {
+            unimplemented!()
+        }
+
         async fn close_shard(&self, _: ShardId) -> cluster::Result<ShardRef> {
             unimplemented!();
         }
@@ -288,6 +321,17 @@ mod tests {
         fn shard_lock_manager(&self) -> ShardLockManagerRef {
             unimplemented!();
         }
+
+        async fn update_shard_lock_lease(&self, _: u64, _: Duration) -> cluster::Result<()> {
+            unimplemented!();
+        }
+
+        async fn propose_rebalance(
+            &self,
+            _: Vec<ShardLoad>,
+        ) -> cluster::Result<Vec<RebalanceProposal>> {
+            unimplemented!();
+        }
     }
 
     #[tokio::test]
@@ -342,4 +386,27 @@ mod tests {
         assert_eq!(miss.len(), 1);
         assert_eq!(miss[0], table2.to_string());
     }
+
+    #[tokio::test]
+    async fn test_route_with_epoch() {
+        let router = ClusterBasedRouter::new(Arc::new(MockClusterImpl {}), Default::default());
+        let request_pb = RouteRequestPb {
+            context: Some(RequestContext {
+                database: String::from("public"),
+            }),
+            tables: vec!["table1".to_string()],
+        };
+        let request = RouteRequest::new(request_pb, false);
+
+        let routes = router.route_with_epoch(request).await.unwrap();
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].route.table, "table1");
+        assert_eq!(
+            routes[0].epoch,
+            Some(ShardEpoch {
+                shard_id: 0,
+                version: 100
+            })
+        );
+    }
 }
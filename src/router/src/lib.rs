@@ -19,13 +19,13 @@ pub mod cluster_based;
 pub mod endpoint;
 mod hash;
 pub mod rule_based;
-use std::{sync::Arc, time::Duration};
+use std::{fmt, sync::Arc, time::Duration};
 
 use async_trait::async_trait;
 pub use cluster_based::ClusterBasedRouter;
 use horaedbproto::storage::{Route, RouteRequest as RouteRequestPb};
 use macros::define_result;
-use meta_client::types::TableInfo;
+use meta_client::types::{ShardId, ShardVersion, TableInfo};
 pub use rule_based::{RuleBasedRouter, RuleList};
 use serde::{Deserialize, Serialize};
 use snafu::{Backtrace, Snafu};
@@ -79,9 +79,44 @@ pub type RouterRef = Arc<dyn Router + Sync + Send>;
 #[async_trait]
 pub trait Router {
     async fn route(&self, req: RouteRequest) -> Result<Vec<Route>>;
+
+    /// Route the tables, along with the epochs of the shards they belong to.
+    ///
+    /// The epoch is left empty by the routers without the knowledge of shards.
+    async fn route_with_epoch(&self, req: RouteRequest) -> Result<Vec<RouteWithEpoch>> {
+        let routes = self.route(req).await?;
+        Ok(routes
+            .into_iter()
+            .map(|route| RouteWithEpoch { route, epoch: None })
+            .collect())
+    }
+
     async fn fetch_table_info(&self, schema: &str, table: &str) -> Result<Option<TableInfo>>;
 }
 
+/// Epoch of the shard which a table belongs to.
+///
+/// The shard version is bumped whenever the shard is moved or its tables are
+/// changed, so clients can compare the epoch with the cached one to tell
+/// whether their cached route is outdated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct ShardEpoch {
+    pub shard_id: ShardId,
+    pub version: ShardVersion,
+}
+
+impl fmt::Display for ShardEpoch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.shard_id, self.version)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RouteWithEpoch {
+    pub route: Route,
+    pub epoch: Option<ShardEpoch>,
+}
+
 pub struct RouteRequest {
     pub route_with_cache: bool,
     pub inner: RouteRequestPb,
@@ -17,7 +17,7 @@
 
 //! A router based on rules.
 
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::RwLock};
 
 use async_trait::async_trait;
 use cluster::config::SchemaConfig;
@@ -97,11 +97,15 @@ impl RuleList {
 // Schema -> Rule list of the schema.
 type SchemaRules = HashMap<String, RuleList>;
 
-pub struct RuleBasedRouter {
+struct RouteState {
     cluster_view: ClusterView,
     schema_rules: SchemaRules,
 }
 
+pub struct RuleBasedRouter {
+    state: RwLock<RouteState>,
+}
+
 impl RuleBasedRouter {
     pub fn new(cluster_view: ClusterView, rules: RuleList) -> Self {
         let schema_rules = rules.split_by_schema();
@@ -112,11 +116,29 @@ impl RuleBasedRouter {
         );
 
         Self {
-            schema_rules,
-            cluster_view,
+            state: RwLock::new(RouteState {
+                cluster_view,
+                schema_rules,
+            }),
         }
     }
 
+    /// Replace the cluster view and the rules, the routes in progress are not
+    /// affected.
+    pub fn reload(&self, cluster_view: ClusterView, rules: RuleList) {
+        let schema_rules = rules.split_by_schema();
+
+        info!(
+            "RuleBasedRouter reload rules, rules:{:?}, cluster_view:{:?}",
+            schema_rules, cluster_view
+        );
+
+        *self.state.write().unwrap() = RouteState {
+            cluster_view,
+            schema_rules,
+        };
+    }
+
     fn maybe_route_by_rule(table: &str, rule_list: &RuleList) -> Option<ShardId> {
         for prefix_rule in &rule_list.prefix_rules {
             if table.starts_with(&prefix_rule.prefix) {
@@ -158,11 +180,12 @@ impl Router for RuleBasedRouter {
     async fn route(&self, req: RouteRequest) -> Result<Vec<Route>> {
         let req_ctx = req.inner.context.unwrap();
         let schema = &req_ctx.database;
-        if let Some(shard_nodes) = self.cluster_view.schema_shards.get(schema) {
+        let state = self.state.read().unwrap();
+        if let Some(shard_nodes) = state.cluster_view.schema_shards.get(schema) {
             ensure!(!shard_nodes.is_empty(), RouteNotFound { schema });
 
             // Get rule list of this schema.
-            let rule_list_opt = self.schema_rules.get(schema);
+            let rule_list_opt = state.schema_rules.get(schema);
 
             // TODO(yingwen): Better way to get total shard number
             let total_shards = shard_nodes.len();
@@ -22,10 +22,10 @@ use std::{
     sync::{atomic::AtomicBool, Arc},
 };
 
-use cluster::config::SchemaConfig;
+use cluster::config::{DefaultTableOptions, SchemaConfig};
 use common_types::schema::TIMESTAMP_COLUMN;
 use meta_client::types::ShardId;
-use proxy::{auth, forward, hotspot, SubTableAccessPerm};
+use proxy::{auth, forward, hotspot, http::spill, query_retry, SubTableAccessPerm};
 use router::{
     endpoint::Endpoint,
     rule_based::{ClusterView, RuleList},
@@ -54,6 +54,7 @@ pub struct SchemaShardView {
     pub schema: String,
     pub default_engine_type: String,
     pub default_timestamp_column_name: String,
+    pub default_table_options: DefaultTableOptions,
     pub shard_views: Vec<ShardView>,
 }
 
@@ -63,6 +64,7 @@ impl Default for SchemaShardView {
             schema: "".to_string(),
             default_engine_type: ANALYTIC_ENGINE_TYPE.to_string(),
             default_timestamp_column_name: TIMESTAMP_COLUMN.to_string(),
+            default_table_options: DefaultTableOptions::default(),
             shard_views: Vec::default(),
         }
     }
@@ -73,6 +75,7 @@ impl From<SchemaShardView> for SchemaConfig {
         Self {
             default_engine_type: view.default_engine_type,
             default_timestamp_column_name: view.default_timestamp_column_name,
+            default_table_options: view.default_table_options,
         }
     }
 }
@@ -125,6 +128,24 @@ impl Default for QueryDedupConfig {
     }
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SstChangeNotifyConfig {
+    /// Whether to expose the sst changes at `/debug/sst_changes`
+    pub enable: bool,
+    /// Max number of the events buffered for the slow subscribers
+    pub capacity: usize,
+}
+
+impl Default for SstChangeNotifyConfig {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            capacity: 1024,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(default)]
 pub struct ServerConfig {
@@ -151,6 +172,14 @@ pub struct ServerConfig {
     /// used in gRPC
     pub auto_create_table: bool,
 
+    /// Whether to write the tables of one gRPC write request atomically, all
+    /// the tables should be on the same shard. The kafka and obkv wal don't
+    /// support it.
+    ///
+    /// It's the default of the requests, which can decide it by the
+    /// `x-horaedb-atomic-write` header.
+    pub atomic_write: bool,
+
     pub default_schema_config: SchemaConfig,
 
     // Config of route
@@ -167,6 +196,18 @@ pub struct ServerConfig {
 
     /// Whether enable to access partition table
     pub sub_table_access_perm: SubTableAccessPerm,
+
+    /// Config of retrying queries failed by transient engine errors
+    pub query_retry: query_retry::Config,
+
+    /// Config of spilling the large query results to the object store
+    pub result_spill: spill::Config,
+
+    /// Config of the quota of the local disk space
+    pub disk_quota: disk_quota::Config,
+
+    /// Config of notifying the sst changes of the flushes and compactions
+    pub sst_change_notify: SstChangeNotifyConfig,
 }
 
 impl Default for ServerConfig {
@@ -184,12 +225,17 @@ impl Default for ServerConfig {
             auth: auth::Config::default(),
             forward: forward::Config::default(),
             auto_create_table: true,
+            atomic_write: false,
             default_schema_config: Default::default(),
             route_cache: router::RouteCacheConfig::default(),
             hotspot: hotspot::Config::default(),
             remote_client: remote_engine_client::Config::default(),
             query_dedup: QueryDedupConfig::default(),
             sub_table_access_perm: SubTableAccessPerm::default(),
+            query_retry: query_retry::Config::default(),
+            result_spill: spill::Config::default(),
+            disk_quota: disk_quota::Config::default(),
+            sst_change_notify: SstChangeNotifyConfig::default(),
         }
     }
 }
@@ -25,5 +25,7 @@ pub const SCHEMA_HEADER: &str = "x-horaedb-schema";
 pub const TENANT_HEADER: &str = "x-horaedb-access-tenant";
 /// Header of content encoding type
 pub const CONTENT_ENCODING_HEADER: &str = "content-encoding";
+/// Header of accepted response content types
+pub const ACCEPT_HEADER: &str = "accept";
 
 pub const GZIP_ENCODING: &str = "gzip";
@@ -38,6 +38,7 @@ use common_types::{
     table::{ShardId, ShardVersion},
 };
 use future_ext::{BackoffConfig, RetryConfig};
+use futures::future;
 use generic_error::BoxError;
 use horaedbproto::meta_event::{
     meta_event_service_server::MetaEventService, ChangeShardRoleRequest, ChangeShardRoleResponse,
@@ -53,7 +54,10 @@ use paste::paste;
 use proxy::instance::InstanceRef;
 use runtime::Runtime;
 use snafu::{OptionExt, ResultExt};
-use table_engine::{engine::TableEngineRef, ANALYTIC_ENGINE_TYPE};
+use table_engine::{
+    engine::{ClosedTable, TableEngineRef},
+    ANALYTIC_ENGINE_TYPE,
+};
 use time_ext::InstantExt;
 use tonic::Response;
 use wal::manager::OpenedWals;
@@ -306,6 +310,9 @@ impl HandlerContext {
         let new_ctx = self.clone();
         let on_lock_expired = |shard_id| async move {
             warn!("Shard lock is released, try to close the tables and shard, shard_id:{shard_id}");
+            // The lock may be taken over by others, so refuse the writes as soon as
+            // possible.
+            new_ctx.table_engine.fence_shard_writes(shard_id);
             let res = do_close_shard(&new_ctx, shard_id).await;
             match res {
                 Ok(_) => info!("Close shard success, shard_id:{shard_id}"),
@@ -331,6 +338,12 @@ impl HandlerContext {
             warn!("Shard lock is already granted, shard_id:{}", shard_id);
         }
 
+        // Install the epoch of the lock to refuse the writes accepted before the lock
+        // is taken over by this node.
+        if let Some(epoch) = lock_mgr.lock_epoch(shard_id).await {
+            self.table_engine.install_shard_write_epoch(shard_id, epoch);
+        }
+
         Ok(())
     }
 
@@ -349,48 +362,61 @@ impl HandlerContext {
         if revoked_by_this_call {
             warn!("Shard lock is revoked already, shard_id:{}", shard_id);
         }
+        self.table_engine.fence_shard_writes(shard_id);
 
         Ok(())
     }
 }
 
-async fn do_open_shard(ctx: HandlerContext, shard_info: ShardInfo) -> Result<()> {
-    // Try to lock the shard in node level.
-    ctx.acquire_shard_lock(shard_info.id).await?;
+/// Open the shards concurrently, whose tables are fetched from the meta in one
+/// request.
+async fn do_open_shards(ctx: HandlerContext, shard_infos: Vec<ShardInfo>) -> Result<()> {
+    // Try to lock the shards in node level.
+    for shard_info in &shard_infos {
+        ctx.acquire_shard_lock(shard_info.id).await?;
+    }
 
-    // We need to ensure `open_shard` succeeds, otherwise it won't heartbeat to
+    // We need to ensure `open_shards` succeeds, otherwise it won't heartbeat to
     // meta.
-    let shard = future_ext::retry_async(
+    let shards = future_ext::retry_async(
         || async {
-            ctx.cluster.open_shard(&shard_info).await.map_err(|e| {
-                error!("Open shard failed, id:{}, err:{e}", shard_info.id);
+            ctx.cluster.open_shards(&shard_infos).await.map_err(|e| {
+                error!("Open shards failed, shard_infos:{shard_infos:?}, err:{e}");
                 e
             })
         },
         &RETRY,
     )
     .await;
-    let shard = match shard {
+    let shards = match shards {
         Ok(v) => v,
         Err(_) => panic!(
             "Open shard failed and we have to panic otherwise this shard wont be assigned again."
         ),
     };
 
-    let open_ctx = OpenContext {
-        catalog: ctx.default_catalog.clone(),
-        table_engine: ctx.table_engine.clone(),
-        table_operator: ctx.table_operator.clone(),
-        // FIXME: the engine type should not use the default one.
-        engine: ANALYTIC_ENGINE_TYPE.to_string(),
-    };
+    let open_shards = shards.into_iter().map(|shard| {
+        let open_ctx = OpenContext {
+            catalog: ctx.default_catalog.clone(),
+            table_engine: ctx.table_engine.clone(),
+            table_operator: ctx.table_operator.clone(),
+            // FIXME: the engine type should not use the default one.
+            engine: ANALYTIC_ENGINE_TYPE.to_string(),
+        };
 
-    // This `open` may only open part of tables in this shard, and this is
-    // allowed via shard status(PartialOpen) mechanism.
-    shard.open(open_ctx).await.box_err().context(ErrWithCause {
-        code: StatusCode::Internal,
-        msg: format!("fail to open shard, id:{}", shard_info.id),
-    })
+        // This `open` may only open part of tables in this shard, and this is
+        // allowed via shard status(PartialOpen) mechanism.
+        async move {
+            let shard_id = shard.shard_info().id;
+            shard.open(open_ctx).await.box_err().context(ErrWithCause {
+                code: StatusCode::Internal,
+                msg: format!("fail to open shard, id:{shard_id}"),
+            })
+        }
+    });
+
+    future::try_join_all(open_shards).await?;
+    Ok(())
 }
 
 // TODO: maybe we should encapsulate the logic of handling meta event into a
@@ -405,7 +431,8 @@ async fn handle_open_shard(ctx: HandlerContext, request: OpenShardRequest) -> Re
 
     let shard_id = shard_info.id;
     info!("Handle open shard begins, shard_id:{shard_id}");
-    match do_open_shard(ctx, shard_info).await {
+    // The meta sends one event per shard for now.
+    match do_open_shards(ctx, vec![shard_info]).await {
         Err(e) => {
             error!("Failed to open shard, shard_id:{shard_id}, err:{e}");
             Err(e)
@@ -432,7 +459,7 @@ async fn do_close_shard(ctx: &HandlerContext, shard_id: ShardId) -> Result<()> {
         engine: ANALYTIC_ENGINE_TYPE.to_string(),
     };
 
-    shard
+    let closed_tables = shard
         .close(close_ctx)
         .await
         .box_err()
@@ -440,6 +467,7 @@ async fn do_close_shard(ctx: &HandlerContext, shard_id: ShardId) -> Result<()> {
             code: StatusCode::Internal,
             msg: "fail to close shard",
         })?;
+    report_closed_tables(shard_id, &closed_tables);
 
     // Remove the shard from the cluster topology after the shard is closed indeed.
     let _ = ctx
@@ -462,6 +490,33 @@ async fn do_close_shard(ctx: &HandlerContext, shard_id: ShardId) -> Result<()> {
     Ok(())
 }
 
+/// Report the tables whose data is not flushed before the shard is closed, and
+/// these data has to be replayed from the wal by the next owner of the shard.
+fn report_closed_tables(shard_id: ShardId, closed_tables: &[ClosedTable]) {
+    let residual_tables: Vec<_> = closed_tables
+        .iter()
+        .filter(|table| table.has_unflushed_data())
+        .map(|table| {
+            format!(
+                "{}(flushed_sequence:{}, last_sequence:{})",
+                table.table_name, table.flushed_sequence, table.last_sequence
+            )
+        })
+        .collect();
+
+    if residual_tables.is_empty() {
+        info!(
+            "All tables are flushed before shard is closed, shard_id:{shard_id}, num_tables:{}",
+            closed_tables.len()
+        );
+    } else {
+        warn!(
+            "Some tables are closed with unflushed data left in wal, shard_id:{shard_id}, num_tables:{}, residual_tables:{residual_tables:?}",
+            closed_tables.len()
+        );
+    }
+}
+
 async fn handle_close_shard(ctx: HandlerContext, request: CloseShardRequest) -> Result<()> {
     info!("Receive close shard request, request:{request:?}");
 
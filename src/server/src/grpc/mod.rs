@@ -37,7 +37,7 @@ use logger::{info, warn};
 use macros::define_result;
 use notifier::notifier::RequestNotifiers;
 use proxy::{
-    auth::with_file::AuthWithFile,
+    auth::Authenticator,
     forward,
     hotspot::HotspotRecorder,
     instance::InstanceRef,
@@ -162,7 +162,7 @@ define_result!(Error);
 /// Rpc services manages all grpc services of the server.
 pub struct RpcServices {
     serve_addr: SocketAddr,
-    rpc_server: InterceptedService<StorageServiceServer<StorageServiceImpl>, AuthWithFile>,
+    rpc_server: InterceptedService<StorageServiceServer<StorageServiceImpl>, Authenticator>,
     meta_rpc_server: Option<MetaEventServiceServer<MetaServiceImpl>>,
     remote_engine_server: RemoteEngineServiceServer<RemoteEngineServiceImpl>,
     runtime: Arc<Runtime>,
@@ -216,7 +216,7 @@ impl RpcServices {
 }
 
 pub struct Builder {
-    auth: Option<AuthWithFile>,
+    auth: Option<Authenticator>,
     endpoint: String,
     timeout: Option<Duration>,
     runtimes: Option<Arc<EngineRuntimes>>,
@@ -244,7 +244,7 @@ impl Builder {
         }
     }
 
-    pub fn auth(mut self, auth: AuthWithFile) -> Self {
+    pub fn auth(mut self, auth: Authenticator) -> Self {
         self.auth = Some(auth);
         self
     }
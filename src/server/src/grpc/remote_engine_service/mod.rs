@@ -1258,6 +1258,10 @@ fn create_query_ctx(
         default_catalog,
         default_schema,
         priority,
+        read_snapshot: None,
+        query_stats: None,
+        read_parallelism: None,
+        no_cache: false,
     }
 }
 
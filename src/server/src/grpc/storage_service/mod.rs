@@ -34,10 +34,16 @@ use horaedbproto::{
         SqlQueryRequest, SqlQueryResponse, WriteRequest, WriteResponse,
     },
 };
-use http::StatusCode;
-use proxy::{auth::with_file::get_authorization, Context, Proxy, FORWARDED_FROM};
+use http::{HeaderMap, StatusCode};
+use proxy::{
+    auth::get_authorization,
+    shard_epoch::ShardEpochs,
+    trace::{self, RequestTraceRef},
+    write, Context, Proxy, FORWARDED_FROM,
+};
 use table_engine::engine::EngineRuntimes;
 use time_ext::InstantExt;
+use tonic::metadata::MetadataMap;
 
 use crate::grpc::metrics::GRPC_HANDLER_DURATION_HISTOGRAM_VEC;
 
@@ -170,6 +176,55 @@ fn get_forwarded_from<T>(req: &tonic::Request<T>) -> Option<String> {
         .map(|value| value.to_str().unwrap().to_string())
 }
 
+fn get_trace<T>(req: &tonic::Request<T>) -> Option<RequestTraceRef> {
+    let value = req
+        .metadata()
+        .get(trace::TRACE_HEADER)
+        .and_then(|value| value.to_str().ok());
+    trace::new_trace_if_enabled(value)
+}
+
+fn get_verbose<T>(req: &tonic::Request<T>) -> bool {
+    let value = req
+        .metadata()
+        .get(trace::VERBOSE_HEADER)
+        .and_then(|value| value.to_str().ok());
+    trace::is_verbose_enabled(value)
+}
+
+fn get_atomic_write<T>(req: &tonic::Request<T>) -> Option<bool> {
+    let value = req
+        .metadata()
+        .get(write::ATOMIC_WRITE_HEADER)
+        .and_then(|value| value.to_str().ok());
+    write::parse_atomic_write(value)
+}
+
+/// Build the response with the trace sample in its metadata if it's requested
+/// by the client, and the shard epochs if any.
+fn build_response<T>(
+    resp: T,
+    trace: Option<RequestTraceRef>,
+    shard_epochs: ShardEpochs,
+) -> tonic::Response<T> {
+    let mut headers = HeaderMap::new();
+    if let Some(trace) = trace {
+        trace.fill_headers(&mut headers);
+    }
+    shard_epochs.fill_headers(&mut headers);
+
+    let mut resp = tonic::Response::new(resp);
+    *resp.metadata_mut() = MetadataMap::from_headers(headers);
+    resp
+}
+
+#[inline]
+fn is_failed(header: &Option<ResponseHeader>) -> bool {
+    header
+        .as_ref()
+        .is_some_and(|header| header.code != StatusCode::OK.as_u16() as u32)
+}
+
 // TODO: Use macros to simplify duplicate code
 impl StorageServiceImpl {
     async fn route_internal(
@@ -189,89 +244,129 @@ impl StorageServiceImpl {
             .read_runtime
             .spawn(async move { proxy.handle_route(ctx, req).await });
 
-        let resp = match join_handle.await {
+        let (resp, shard_epochs) = match join_handle.await {
             Ok(v) => v,
-            Err(e) => RouteResponse {
-                header: Some(error::build_err_header(
-                    StatusCode::INTERNAL_SERVER_ERROR.as_u16() as u32,
-                    format!("fail to join the spawn task, err:{e:?}"),
-                )),
-                ..Default::default()
-            },
+            Err(e) => {
+                let resp = RouteResponse {
+                    header: Some(error::build_err_header(
+                        StatusCode::INTERNAL_SERVER_ERROR.as_u16() as u32,
+                        format!("fail to join the spawn task, err:{e:?}"),
+                    )),
+                    ..Default::default()
+                };
+                (resp, ShardEpochs::default())
+            }
         };
 
-        Ok(tonic::Response::new(resp))
+        Ok(build_response(resp, None, shard_epochs))
     }
 
     async fn write_internal(
         &self,
         req: tonic::Request<WriteRequest>,
     ) -> Result<tonic::Response<WriteResponse>, tonic::Status> {
+        let trace = get_trace(&req);
         let ctx = Context::new(
             self.timeout,
             get_forwarded_from(&req),
             get_authorization(&req),
-        );
+        )
+        .with_trace(trace.clone())
+        .with_verbose(get_verbose(&req))
+        .with_atomic_write(get_atomic_write(&req));
 
         let req = req.into_inner();
         let proxy = self.proxy.clone();
 
         let join_handle = self.runtimes.write_runtime.spawn(async move {
-            if req.context.is_none() {
-                return WriteResponse {
+            let schema = match &req.context {
+                Some(ctx) => ctx.database.clone(),
+                None => {
+                    let resp = WriteResponse {
+                        header: Some(error::build_err_header(
+                            StatusCode::BAD_REQUEST.as_u16() as u32,
+                            "database is not set".to_string(),
+                        )),
+                        ..Default::default()
+                    };
+                    return (resp, ShardEpochs::default());
+                }
+            };
+            let tables: Vec<_> = req.table_requests.iter().map(|v| v.table.clone()).collect();
+
+            let resp = proxy.handle_write(ctx, req).await;
+            // Tell the client the latest shard epochs of the tables on failure, with
+            // which it can decide whether to invalidate its cached routes.
+            let shard_epochs = if is_failed(&resp.header) {
+                proxy.fetch_shard_epochs(&schema, tables).await
+            } else {
+                ShardEpochs::default()
+            };
+            (resp, shard_epochs)
+        });
+
+        let (resp, shard_epochs) = match join_handle.await {
+            Ok(v) => v,
+            Err(e) => {
+                let resp = WriteResponse {
                     header: Some(error::build_err_header(
-                        StatusCode::BAD_REQUEST.as_u16() as u32,
-                        "database is not set".to_string(),
+                        StatusCode::INTERNAL_SERVER_ERROR.as_u16() as u32,
+                        format!("fail to join the spawn task, err:{e:?}"),
                     )),
                     ..Default::default()
                 };
+                (resp, ShardEpochs::default())
             }
-
-            proxy.handle_write(ctx, req).await
-        });
-
-        let resp = match join_handle.await {
-            Ok(v) => v,
-            Err(e) => WriteResponse {
-                header: Some(error::build_err_header(
-                    StatusCode::INTERNAL_SERVER_ERROR.as_u16() as u32,
-                    format!("fail to join the spawn task, err:{e:?}"),
-                )),
-                ..Default::default()
-            },
         };
 
-        Ok(tonic::Response::new(resp))
+        Ok(build_response(resp, trace, shard_epochs))
     }
 
     async fn sql_query_internal(
         &self,
         req: tonic::Request<SqlQueryRequest>,
     ) -> Result<tonic::Response<SqlQueryResponse>, tonic::Status> {
+        let trace = get_trace(&req);
         let ctx = Context::new(
             self.timeout,
             get_forwarded_from(&req),
             get_authorization(&req),
-        );
+        )
+        .with_trace(trace.clone())
+        .with_verbose(get_verbose(&req));
         let proxy = self.proxy.clone();
 
-        let join_handle = self
-            .runtimes
-            .read_runtime
-            .spawn(async move { proxy.handle_sql_query(ctx, req.into_inner()).await });
+        let req = req.into_inner();
 
-        let resp = match join_handle.await {
+        let join_handle = self.runtimes.read_runtime.spawn(async move {
+            let schema = req.context.as_ref().map(|ctx| ctx.database.clone());
+            let tables = req.tables.clone();
+
+            let resp = proxy.handle_sql_query(ctx, req).await;
+            let shard_epochs = match schema {
+                Some(schema) if is_failed(&resp.header) => {
+                    proxy.fetch_shard_epochs(&schema, tables).await
+                }
+                _ => ShardEpochs::default(),
+            };
+            (resp, shard_epochs)
+        });
+
+        let (resp, shard_epochs) = match join_handle.await {
             Ok(v) => v,
-            Err(e) => SqlQueryResponse {
-                header: Some(error::build_err_header(
-                    StatusCode::INTERNAL_SERVER_ERROR.as_u16() as u32,
-                    format!("fail to join the spawn task, err:{e:?}"),
-                )),
-                ..Default::default()
-            },
+            Err(e) => {
+                let resp = SqlQueryResponse {
+                    header: Some(error::build_err_header(
+                        StatusCode::INTERNAL_SERVER_ERROR.as_u16() as u32,
+                        format!("fail to join the spawn task, err:{e:?}"),
+                    )),
+                    ..Default::default()
+                };
+                (resp, ShardEpochs::default())
+            }
         };
 
-        Ok(tonic::Response::new(resp))
+        Ok(build_response(resp, trace, shard_epochs))
     }
 
     async fn prom_remote_query_internal(
@@ -361,7 +456,8 @@ impl StorageServiceImpl {
             self.timeout,
             get_forwarded_from(&req),
             get_authorization(&req),
-        );
+        )
+        .with_atomic_write(get_atomic_write(&req));
         let mut stream = req.into_inner();
         let proxy = self.proxy.clone();
 
@@ -27,44 +27,65 @@ use std::{
     time::Duration,
 };
 
+use analytic_engine::hook::{SstChangeEvent, SstChangeKind, SstChangeNotifier};
 use bytes_ext::Bytes;
-use cluster::ClusterRef;
+use cluster::{rebalance::ShardLoad, ClusterRef};
 use datafusion::parquet::data_type::AsBytes;
 use flate2::read::GzDecoder;
 use generic_error::{BoxError, GenericError};
-use logger::{error, info, RuntimeLevel};
+use logger::{error, info, warn, RuntimeLevel};
 use macros::define_result;
+use meta_client::types::ShardId;
 use profile::Profiler;
 use prom_remote_api::web;
 use proxy::{
     auth::AUTHORIZATION,
     context::RequestContext,
     handlers::{self},
-    http::sql::{convert_output, Request},
+    http::{
+        conformance,
+        exists::ExistsRequest,
+        spill::ResultSpiller,
+        sql::{BatchRequest, OutputEncoder, Request, ResponseFormat},
+    },
     influxdb::types::{InfluxqlParams, InfluxqlRequest, WriteParams, WriteRequest},
     instance::InstanceRef,
     opentsdb::types::{PutParams, PutRequest},
+    trace::{self, RequestTraceRef},
     Proxy,
 };
 use router::endpoint::Endpoint;
 use runtime::{PriorityRuntime, Runtime};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use snafu::{Backtrace, OptionExt, ResultExt, Snafu};
-use table_engine::{engine::EngineRuntimes, table::FlushRequest};
-use tokio::sync::oneshot::{self, Receiver, Sender};
+use table_engine::{
+    engine::EngineRuntimes,
+    table::{FlushRequest, TableId},
+};
+use time_ext::ReadableDuration;
+use tokio::sync::{
+    broadcast::error::RecvError,
+    oneshot::{self, Receiver, Sender},
+};
 use wal::manager::OpenedWals;
 use warp::{
     header,
-    http::StatusCode,
+    http::{
+        header::{ACCEPT_RANGES, CONTENT_RANGE, CONTENT_TYPE},
+        StatusCode,
+    },
+    hyper::Body,
     reject,
-    reply::{self, Reply},
-    Filter, Rejection,
+    reply::{self, Reply, Response},
+    sse, Filter, Rejection,
 };
 
 use crate::{
-    consts::{self, CONTENT_ENCODING_HEADER, GZIP_ENCODING},
+    config::StaticRouteConfig,
+    consts::{self, ACCEPT_HEADER, CONTENT_ENCODING_HEADER, GZIP_ENCODING},
     error_util,
     metrics::{self, HTTP_HANDLER_DURATION_HISTOGRAM_VEC},
+    static_route::StaticRouteReloader,
 };
 
 #[derive(Debug, Snafu)]
@@ -135,6 +156,9 @@ pub enum Error {
     #[snafu(display("Unsupported content encoding type, value:{}.", encoding_type))]
     UnspportedContentEncodingType { encoding_type: String },
 
+    #[snafu(display("Unsupported accept content type, value:{}.", accept))]
+    UnsupportedAcceptType { accept: String },
+
     #[snafu(display("Server already started.\nBacktrace:\n{}", backtrace))]
     AlreadyStarted { backtrace: Backtrace },
 
@@ -150,8 +174,35 @@ pub enum Error {
     #[snafu(display("Querying shards is only supported in cluster mode"))]
     QueryShards {},
 
+    #[snafu(display("Updating shard lock lease is only supported in cluster mode"))]
+    ShardLockLeaseNotSupported {},
+
+    #[snafu(display("Failed to update shard lock lease, err:{}", source))]
+    UpdateShardLockLease { source: cluster::Error },
+
+    #[snafu(display("Shard is not found on this node, shard_id:{shard_id}"))]
+    ShardNotFound { shard_id: ShardId },
+
+    #[snafu(display("Failed to propose rebalance, err:{}", source))]
+    ProposeRebalance { source: cluster::Error },
+
     #[snafu(display("unauthenticated.\nBacktrace:\n{}", backtrace))]
     UnAuthenticated { backtrace: Backtrace },
+
+    #[snafu(display("Spilling query result is not enabled.\nBacktrace:\n{}", backtrace))]
+    SpillNotEnabled { backtrace: Backtrace },
+
+    #[snafu(display("Failed to handle spilled result, err:{}", source))]
+    SpillResult { source: proxy::error::Error },
+
+    #[snafu(display("Reloading static route is only supported without meta"))]
+    StaticRouteNotSupported {},
+
+    #[snafu(display("Failed to reload static route, err:{}", source))]
+    ReloadStaticRoute { source: crate::static_route::Error },
+
+    #[snafu(display("Notifying sst changes is not enabled.\nBacktrace:\n{}", backtrace))]
+    SstChangeNotifyNotEnabled { backtrace: Backtrace },
 }
 
 define_result!(Error);
@@ -191,6 +242,9 @@ pub struct Service {
     config: HttpConfig,
     config_content: String,
     opened_wals: OpenedWals,
+    result_spiller: Option<Arc<ResultSpiller>>,
+    static_route_reloader: Option<Arc<StaticRouteReloader>>,
+    sst_change_notifier: Option<Arc<SstChangeNotifier>>,
 }
 
 impl Service {
@@ -239,12 +293,25 @@ impl Service {
             // public APIs
             .or(self.metrics())
             .or(self.sql())
+            .or(self.sql_batch())
+            .or(self.sql_spill())
             .or(self.influxdb_api())
             .or(self.opentsdb_api())
             .or(self.prom_api())
             .or(self.route())
+            .or(self.exists())
+            .or(self.conformance_vectors())
+            .or(self.conformance_verify())
             // admin APIs
             .or(self.admin_block())
+            .or(self.admin_blacklist())
+            .or(self.admin_table_criticality())
+            .or(self.admin_get_blacklist())
+            .or(self.admin_shard_lock_lease())
+            .or(self.admin_force_shard_ready())
+            .or(self.admin_check_open_shard())
+            .or(self.debug_scan_wal())
+            .or(self.admin_reload_static_route())
             // debug APIs
             .or(self.flush_memtable())
             .or(self.update_log_level())
@@ -252,9 +319,13 @@ impl Service {
             .or(self.profile_heap())
             .or(self.server_config())
             .or(self.shards())
+            .or(self.queries())
+            .or(self.rebalance_proposals())
             .or(self.wal_stats())
+            .or(self.wal_regions())
             .or(self.query_push_down())
             .or(self.slow_threshold())
+            .or(self.sst_changes())
             .with(warp::log::custom(|info| {
                 let path = info.path();
                 // Don't record /debug API
@@ -315,23 +386,34 @@ impl Service {
             .and(self.with_context())
             .and(self.with_proxy())
             .and(self.with_read_runtime())
+            .and(self.with_response_format())
             .and_then(
-                |req, mut ctx: RequestContext, proxy: Arc<Proxy>, runtime: PriorityRuntime| async move {
+                |req,
+                 mut ctx: RequestContext,
+                 proxy: Arc<Proxy>,
+                 runtime: PriorityRuntime,
+                 format: ResponseFormat| async move {
                     // We don't timeout http api since it's mainly used for debugging.
                     ctx.timeout = None;
+                    let trace = ctx.trace.clone();
 
                     let result = runtime
-                        .spawn(async move {
-                            proxy
-                                .handle_http_sql_query(&ctx, req)
-                                .await
-                                .map(convert_output)
-                        })
+                        .spawn(async move { proxy.handle_http_sql_query(&ctx, req).await })
                         .await
                         .box_err()
                         .context(HandleRequest);
                     match result {
-                        Ok(Ok(res)) => Ok(reply::json(&res)),
+                        Ok(Ok(output)) => {
+                            // Encode the output batch by batch while sending the response body.
+                            let encoder = OutputEncoder::new(output, format);
+                            let body = Body::wrap_stream(futures::stream::iter(encoder));
+                            let reply = reply::with_header(
+                                Response::new(body),
+                                CONTENT_TYPE,
+                                format.content_type(),
+                            );
+                            Ok(with_trace_headers(reply, trace))
+                        }
                         Ok(Err(e)) => {
                             if let proxy::error::Error::QueryMaybeExceedTTL { msg } = e {
                                 return Err(reject::custom(Error::QueryMaybeExceedTTL { msg }));
@@ -346,6 +428,159 @@ impl Service {
             )
     }
 
+    // POST /sql/batch
+    fn sql_batch(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("sql" / "batch")
+            .and(warp::post())
+            .and(warp::body::content_length_limit(self.config.max_body_size))
+            .and(warp::body::json())
+            .and(self.with_context())
+            .and(self.with_proxy())
+            .and(self.with_read_runtime())
+            .and_then(
+                |req: BatchRequest,
+                 mut ctx: RequestContext,
+                 proxy: Arc<Proxy>,
+                 runtime: PriorityRuntime| async move {
+                    // We don't timeout http api since it's mainly used for debugging.
+                    ctx.timeout = None;
+
+                    let result = runtime
+                        .spawn(async move { proxy.handle_http_sql_batch_query(&ctx, req).await })
+                        .await
+                        .box_err()
+                        .context(HandleRequest);
+                    match result {
+                        Ok(Ok(res)) => Ok(reply::json(&res)),
+                        Ok(Err(e)) => Err(reject::custom(Error::Internal {
+                            source: Box::new(e),
+                        })),
+                        Err(e) => Err(reject::custom(e)),
+                    }
+                },
+            )
+    }
+
+    // POST /sql/spill
+    // GET /sql/spill/{id}
+    // GET /sql/spill/{id}/{part}
+    // DELETE /sql/spill/{id}
+    fn sql_spill(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        // accept json or plain text
+        let extract_request = warp::body::json()
+            .or(warp::body::bytes().map(|v: Bytes| Request {
+                query: String::from_utf8_lossy(&v).to_string(),
+            }))
+            .unify();
+
+        let spill = warp::path!("sql" / "spill")
+            .and(warp::post())
+            .and(warp::body::content_length_limit(self.config.max_body_size))
+            .and(extract_request)
+            .and(self.with_context())
+            .and(self.with_proxy())
+            .and(self.with_read_runtime())
+            .and(self.with_response_format())
+            .and(self.with_result_spiller())
+            .and_then(
+                |req,
+                 mut ctx: RequestContext,
+                 proxy: Arc<Proxy>,
+                 runtime: PriorityRuntime,
+                 format: ResponseFormat,
+                 spiller: Arc<ResultSpiller>| async move {
+                    // Spilling is used to export large results, so don't timeout it.
+                    ctx.timeout = None;
+
+                    let result = runtime
+                        .spawn(async move {
+                            let output = proxy.handle_http_sql_query_stream(&ctx, req).await?;
+                            spiller
+                                .spill(&ctx.request_id, &ctx.user, output, format)
+                                .await
+                        })
+                        .await
+                        .box_err()
+                        .context(HandleRequest);
+                    match result {
+                        Ok(Ok(manifest)) => Ok(reply::json(&manifest)),
+                        Ok(Err(e)) => Err(reject::custom(Error::SpillResult { source: e })),
+                        Err(e) => Err(reject::custom(e)),
+                    }
+                },
+            );
+
+        let manifest = warp::path!("sql" / "spill" / String)
+            .and(warp::get())
+            .and(self.with_context())
+            .and(self.with_result_spiller())
+            .and_then(
+                |id: String, ctx: RequestContext, spiller: Arc<ResultSpiller>| async move {
+                    match spiller.manifest(&id, &ctx.user).await {
+                        Ok(manifest) => Ok(reply::json(&manifest)),
+                        Err(e) => Err(reject::custom(Error::SpillResult { source: e })),
+                    }
+                },
+            );
+
+        let fetch_part = warp::path!("sql" / "spill" / String / usize)
+            .and(warp::get())
+            .and(header::optional::<String>("range"))
+            .and(self.with_context())
+            .and(self.with_result_spiller())
+            .and_then(
+                |id: String,
+                 index: usize,
+                 range: Option<String>,
+                 ctx: RequestContext,
+                 spiller: Arc<ResultSpiller>| async move {
+                    let manifest = spiller
+                        .manifest(&id, &ctx.user)
+                        .await
+                        .map_err(|e| reject::custom(Error::SpillResult { source: e }))?;
+                    let (bytes, fetched, part_size) = spiller
+                        .fetch_part(&id, &ctx.user, index, range.as_deref())
+                        .await
+                        .map_err(|e| reject::custom(Error::SpillResult { source: e }))?;
+
+                    let mut resp = Response::new(Body::from(bytes));
+                    let headers = resp.headers_mut();
+                    headers.insert(CONTENT_TYPE, manifest.content_type.parse().unwrap());
+                    headers.insert(ACCEPT_RANGES, "bytes".parse().unwrap());
+                    if range.is_some() {
+                        let content_range = format!(
+                            "bytes {}-{}/{part_size}",
+                            fetched.start,
+                            fetched.end.saturating_sub(1)
+                        );
+                        headers.insert(CONTENT_RANGE, content_range.parse().unwrap());
+                        *resp.status_mut() = StatusCode::PARTIAL_CONTENT;
+                    }
+
+                    Ok::<_, Rejection>(resp)
+                },
+            );
+
+        let delete = warp::path!("sql" / "spill" / String)
+            .and(warp::delete())
+            .and(self.with_context())
+            .and(self.with_result_spiller())
+            .and_then(
+                |id: String, ctx: RequestContext, spiller: Arc<ResultSpiller>| async move {
+                    match spiller.delete(&id, &ctx.user).await {
+                        Ok(()) => Ok(reply::json(&id)),
+                        Err(e) => Err(reject::custom(Error::SpillResult { source: e })),
+                    }
+                },
+            );
+
+        spill.or(manifest).or(fetch_part).or(delete)
+    }
+
     // GET /route
     fn route(&self) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
         warp::path!("route" / String)
@@ -365,6 +600,65 @@ impl Service {
             })
     }
 
+    // POST /exists
+    //
+    // Check whether the series may exist in the time range by the meta data and
+    // filters only, without reading the data.
+    fn exists(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("exists")
+            .and(warp::post())
+            .and(warp::body::content_length_limit(self.config.max_body_size))
+            .and(warp::body::json())
+            .and(self.with_context())
+            .and(self.with_proxy())
+            .and(self.with_read_runtime())
+            .and_then(
+                |req: ExistsRequest,
+                 ctx: RequestContext,
+                 proxy: Arc<Proxy>,
+                 runtime: PriorityRuntime| async move {
+                    let result = runtime
+                        .spawn(async move { proxy.handle_http_exists(&ctx, req).await })
+                        .await
+                        .box_err()
+                        .and_then(|v| v.box_err())
+                        .context(HandleRequest);
+                    match result {
+                        Ok(res) => Ok(reply::json(&res)),
+                        Err(e) => Err(reject::custom(e)),
+                    }
+                },
+            )
+    }
+
+    // GET /conformance/vectors
+    fn conformance_vectors(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("conformance" / "vectors")
+            .and(warp::get())
+            .map(|| reply::json(&conformance::test_vectors()))
+    }
+
+    // POST /conformance/verify
+    fn conformance_verify(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("conformance" / "verify")
+            .and(warp::post())
+            .and(warp::body::content_length_limit(self.config.max_body_size))
+            .and(warp::body::json())
+            .and_then(|req| async move {
+                let result = conformance::verify(req).box_err().context(HandleRequest);
+                match result {
+                    Ok(res) => Ok(reply::json(&res)),
+                    Err(e) => Err(reject::custom(e)),
+                }
+            })
+    }
+
     /// for write api:
     ///     POST `/influxdb/v1/write`
     ///
@@ -385,14 +679,17 @@ impl Service {
             .and(warp::query::<WriteParams>())
             .and(warp::body::bytes())
             .and(self.with_proxy())
-            .and_then(|ctx, params, lines, proxy: Arc<Proxy>| async move {
-                let request = WriteRequest::new(lines, params);
-                let result = proxy.handle_influxdb_write(ctx, request).await;
-                match result {
-                    Ok(res) => Ok(reply::json(&res)),
-                    Err(e) => Err(reject::custom(e)),
-                }
-            });
+            .and_then(
+                |ctx: RequestContext, params, lines, proxy: Arc<Proxy>| async move {
+                    let trace = ctx.trace.clone();
+                    let request = WriteRequest::new(lines, params);
+                    let result = proxy.handle_influxdb_write(ctx, request).await;
+                    match result {
+                        Ok(res) => Ok(with_trace_headers(reply::json(&res), trace)),
+                        Err(e) => Err(reject::custom(e)),
+                    }
+                },
+            );
 
         // Query support both get and post method, so we can't add `body_limit` here.
         // Otherwise it will throw `Rejection(LengthRequired)`
@@ -435,29 +732,41 @@ impl Service {
             .and(warp::body::bytes())
             .and(self.with_proxy())
             .and(header::optional::<String>(CONTENT_ENCODING_HEADER))
-            .and_then(|ctx, params, points: Bytes, proxy: Arc<Proxy>, encoding: Option<String>| async move {
-                let points = match encoding {
-                    Some(encoding) => {
-                        let encode_type = ContentEncodingType::try_from(encoding.as_str())?;
-                        match encode_type {
-                            ContentEncodingType::Gzip => {
-                                let bytes = points.as_bytes();
-                                let mut decoder = GzDecoder::new(bytes);
-                                let mut decompressed_data = Vec::with_capacity(bytes.len()* 2);
-                                decoder.read_to_end(&mut decompressed_data).context(UnGzip)?;
-                                decompressed_data.into()
-                            },
+            .and_then(
+                |ctx: RequestContext,
+                 params,
+                 points: Bytes,
+                 proxy: Arc<Proxy>,
+                 encoding: Option<String>| async move {
+                    let trace = ctx.trace.clone();
+                    let points = match encoding {
+                        Some(encoding) => {
+                            let encode_type = ContentEncodingType::try_from(encoding.as_str())?;
+                            match encode_type {
+                                ContentEncodingType::Gzip => {
+                                    let bytes = points.as_bytes();
+                                    let mut decoder = GzDecoder::new(bytes);
+                                    let mut decompressed_data = Vec::with_capacity(bytes.len() * 2);
+                                    decoder
+                                        .read_to_end(&mut decompressed_data)
+                                        .context(UnGzip)?;
+                                    decompressed_data.into()
+                                }
+                            }
                         }
-                    },
-                    None => points,
-                };
-                let request = PutRequest::new(points, params);
-                let result = proxy.handle_opentsdb_put(ctx, request).await;
-                match result {
-                    Ok(_res) => Ok(reply::with_status(warp::reply(), StatusCode::NO_CONTENT)),
-                    Err(e) => Err(reject::custom(e)),
-                }
-            });
+                        None => points,
+                    };
+                    let request = PutRequest::new(points, params);
+                    let result = proxy.handle_opentsdb_put(ctx, request).await;
+                    match result {
+                        Ok(_res) => {
+                            let reply = reply::with_status(warp::reply(), StatusCode::NO_CONTENT);
+                            Ok(with_trace_headers(reply, trace))
+                        }
+                        Err(e) => Err(reject::custom(e)),
+                    }
+                },
+            );
 
         let query_api = warp::path!("query")
             .and(warp::post())
@@ -605,6 +914,45 @@ impl Service {
             })
     }
 
+    // GET /debug/queries
+    fn queries(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        let query_registry = self.proxy.query_registry();
+        warp::path!("debug" / "queries")
+            .and(warp::get())
+            .map(move || reply::json(&query_registry.list()))
+    }
+
+    // GET /debug/rebalance_proposals
+    fn rebalance_proposals(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("debug" / "rebalance_proposals")
+            .and(warp::get())
+            .and(self.with_cluster())
+            .and(self.with_instance())
+            .and_then(
+                |cluster: Option<ClusterRef>, instance: InstanceRef| async move {
+                    let cluster = match cluster {
+                        Some(cluster) => cluster,
+                        None => return Err(reject::custom(Error::QueryShards {})),
+                    };
+                    let shard_loads = collect_shard_loads(&cluster, &instance);
+                    let proposals = cluster
+                        .propose_rebalance(shard_loads.clone())
+                        .await
+                        .context(ProposeRebalance)
+                        .map_err(reject::custom)?;
+
+                    let mut result = HashMap::new();
+                    result.insert("shard_loads", serde_json::to_value(shard_loads).unwrap());
+                    result.insert("proposals", serde_json::to_value(proposals).unwrap());
+                    Ok(reply::json(&result))
+                },
+            )
+    }
+
     // GET /debug/stats
     fn wal_stats(
         &self,
@@ -635,6 +983,36 @@ impl Service {
             })
     }
 
+    // GET /debug/wal_regions
+    //
+    // Summarize the logs of the tables in the regions of the data wal and the
+    // manifest wal, the decoded logs of a table can be dumped by
+    // `/debug/scan_wal`.
+    fn wal_regions(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("debug" / "wal_regions")
+            .and(warp::get())
+            .and(self.with_opened_wals())
+            .and_then(|wals: OpenedWals| async move {
+                let result = async {
+                    let data_wal = wals.data_wal.summarize_regions().await.box_err()?;
+                    let manifest_wal = wals.manifest_wal.summarize_regions().await.box_err()?;
+                    Ok::<_, GenericError>(serde_json::json!({
+                        "data_wal": data_wal,
+                        "manifest_wal": manifest_wal,
+                    }))
+                }
+                .await
+                .context(HandleRequest);
+
+                match result {
+                    Ok(res) => Ok(reply::json(&res)),
+                    Err(e) => Err(reject::custom(e)),
+                }
+            })
+    }
+
     // PUT /debug/log_level/{level}
     fn update_log_level(
         &self,
@@ -677,6 +1055,207 @@ impl Service {
             })
     }
 
+    // POST /admin/table_criticality
+    fn admin_table_criticality(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("admin" / "table_criticality")
+            .and(warp::post())
+            .and(warp::body::json())
+            .and(self.with_context())
+            .and(self.with_instance())
+            .and_then(|req, ctx, instance| async {
+                let result = handlers::admin::handle_table_criticality(ctx, instance, req)
+                    .await
+                    .box_err()
+                    .context(HandleRequest);
+
+                match result {
+                    Ok(res) => Ok(reply::json(&res)),
+                    Err(e) => Err(reject::custom(e)),
+                }
+            })
+    }
+
+    // POST /admin/blacklist
+    fn admin_blacklist(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("admin" / "blacklist")
+            .and(warp::post())
+            .and(warp::body::json())
+            .and(self.with_context())
+            .and(self.with_instance())
+            .and_then(|req, ctx, instance| async {
+                let result = handlers::admin::handle_blacklist(ctx, instance, req)
+                    .await
+                    .box_err()
+                    .context(HandleRequest);
+
+                match result {
+                    Ok(res) => Ok(reply::json(&res)),
+                    Err(e) => Err(reject::custom(e)),
+                }
+            })
+    }
+
+    // GET /admin/blacklist
+    fn admin_get_blacklist(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("admin" / "blacklist")
+            .and(warp::get())
+            .and(self.with_context())
+            .and(self.with_instance())
+            .and_then(|ctx, instance| async {
+                let result = handlers::admin::handle_get_blacklist(ctx, instance)
+                    .await
+                    .box_err()
+                    .context(HandleRequest);
+
+                match result {
+                    Ok(res) => Ok(reply::json(&res)),
+                    Err(e) => Err(reject::custom(e)),
+                }
+            })
+    }
+
+    // PUT /admin/shard_lock_lease
+    fn admin_shard_lock_lease(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("admin" / "shard_lock_lease")
+            .and(warp::put())
+            .and(warp::body::json())
+            .and(self.with_cluster())
+            .and_then(
+                |req: ShardLockLeaseRequest, cluster: Option<ClusterRef>| async move {
+                    let cluster = match cluster {
+                        Some(cluster) => cluster,
+                        None => return Err(reject::custom(Error::ShardLockLeaseNotSupported {})),
+                    };
+                    let result = cluster
+                        .update_shard_lock_lease(req.ttl_sec, req.check_interval.0)
+                        .await
+                        .context(UpdateShardLockLease);
+
+                    match result {
+                        Ok(()) => Ok(reply::json(&req)),
+                        Err(e) => Err(reject::custom(e)),
+                    }
+                },
+            )
+    }
+
+    // POST /admin/shard_ready/{shard_id}
+    //
+    // Skip the warm-up of the opened shard and announce it as ready at once, for
+    // emergencies only.
+    fn admin_force_shard_ready(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("admin" / "shard_ready" / ShardId)
+            .and(warp::post())
+            .and(self.with_cluster())
+            .and_then(
+                |shard_id: ShardId, cluster: Option<ClusterRef>| async move {
+                    let cluster = match cluster {
+                        Some(cluster) => cluster,
+                        None => return Err(reject::custom(Error::QueryShards {})),
+                    };
+                    let shard = cluster
+                        .shard(shard_id)
+                        .context(ShardNotFound { shard_id })
+                        .map_err(reject::custom)?;
+                    warn!("Force shard to skip warm-up, shard_id:{shard_id}");
+                    shard.force_ready();
+
+                    Ok(reply::json(&shard.shard_info()))
+                },
+            )
+    }
+
+    // POST /admin/check_open_shard
+    //
+    // Dry run of opening the shard on this node, which horaemeta can consult
+    // before moving the shard here.
+    fn admin_check_open_shard(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("admin" / "check_open_shard")
+            .and(warp::post())
+            .and(warp::body::json())
+            .and(self.with_context())
+            .and(self.with_instance())
+            .and_then(|req, ctx, instance| async {
+                let result = handlers::admin::handle_check_open_shard(ctx, instance, req)
+                    .await
+                    .box_err()
+                    .context(HandleRequest);
+
+                match result {
+                    Ok(res) => Ok(reply::json(&res)),
+                    Err(e) => Err(reject::custom(e)),
+                }
+            })
+    }
+
+    // POST /debug/scan_wal
+    //
+    // Scan the decoded wal entries of a table opened by this node, the body
+    // has the `table`, the sequence range and the optional time range.
+    fn debug_scan_wal(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("debug" / "scan_wal")
+            .and(warp::post())
+            .and(warp::body::json())
+            .and(self.with_context())
+            .and(self.with_instance())
+            .and_then(|req, ctx, instance| async {
+                let result = handlers::admin::handle_scan_wal(ctx, instance, req)
+                    .await
+                    .box_err()
+                    .context(HandleRequest);
+
+                match result {
+                    Ok(res) => Ok(reply::json(&res)),
+                    Err(e) => Err(reject::custom(e)),
+                }
+            })
+    }
+
+    // POST /admin/static_route
+    //
+    // Replace the static routing rules and topology of the deployment without
+    // meta, the body has the `rules` and `topology` of the NoMeta deployment.
+    fn admin_reload_static_route(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        let static_route_reloader = self.static_route_reloader.clone();
+        warp::path!("admin" / "static_route")
+            .and(warp::post())
+            .and(warp::body::json())
+            .and(warp::any().map(move || static_route_reloader.clone()))
+            .and_then(
+                |config: StaticRouteConfig, reloader: Option<Arc<StaticRouteReloader>>| async move {
+                    let reloader = match reloader {
+                        Some(reloader) => reloader,
+                        None => return Err(reject::custom(Error::StaticRouteNotSupported {})),
+                    };
+                    let result = reloader
+                        .reload(config.clone())
+                        .await
+                        .context(ReloadStaticRoute);
+
+                    match result {
+                        Ok(()) => Ok(reply::json(&config)),
+                        Err(e) => Err(reject::custom(e)),
+                    }
+                },
+            )
+    }
+
     // POST /debug/query_push_down/{true/false}
     fn query_push_down(
         &self,
@@ -716,6 +1295,40 @@ impl Service {
             })
     }
 
+    // GET /debug/sst_changes?table_id={table_id}
+    //
+    // Stream the sst changes of the flushes and compactions as server-sent
+    // events, of all the tables if `table_id` is absent. A `lagged` event
+    // carrying the number of the missed changes is sent if the subscriber is
+    // too slow.
+    fn sst_changes(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("debug" / "sst_changes")
+            .and(warp::get())
+            .and(warp::query::<SstChangesParams>())
+            .and(self.with_sst_change_notifier())
+            .map(
+                |params: SstChangesParams, notifier: Arc<SstChangeNotifier>| {
+                    let subscriber = notifier.subscribe(params.table_id.map(TableId::new));
+                    let events = futures::stream::unfold(subscriber, |mut subscriber| async move {
+                        let event = match subscriber.recv().await {
+                            Ok(event) => sse::Event::default()
+                                .event("sst_change")
+                                .json_data(SstChangeView::from(event.as_ref())),
+                            Err(RecvError::Lagged(missed)) => Ok(sse::Event::default()
+                                .event("lagged")
+                                .data(missed.to_string())),
+                            Err(RecvError::Closed) => return None,
+                        };
+                        Some((event, subscriber))
+                    });
+
+                    sse::reply(sse::keep_alive().stream(events))
+                },
+            )
+    }
+
     fn with_context(
         &self,
     ) -> impl Filter<Extract = (RequestContext,), Error = warp::Rejection> + Clone {
@@ -738,26 +1351,33 @@ impl Service {
             .and(header::optional::<String>(consts::SCHEMA_HEADER))
             .and(header::optional::<String>(consts::TENANT_HEADER))
             .and(header::optional::<String>(AUTHORIZATION))
+            .and(header::optional::<String>(trace::TRACE_HEADER))
+            .and(header::optional::<String>(trace::VERBOSE_HEADER))
             .and_then(
                 move |catalog: Option<_>,
                       schema: Option<_>,
                       _tenant: Option<_>,
-                      authorization: Option<_>| {
+                      authorization: Option<_>,
+                      trace: Option<String>,
+                      verbose: Option<String>| {
                     // Clone the captured variables
                     let default_catalog = default_catalog.clone();
                     let schema = schema.unwrap_or_else(|| default_schema.clone());
                     let proxy = proxy.clone();
 
                     async move {
-                        if !proxy.check_auth(authorization.clone()) {
+                        let Some(user) = proxy.identify_user(authorization.as_deref()) else {
                             return UnAuthenticated.fail().map_err(reject::custom);
-                        }
+                        };
 
                         RequestContext::builder()
                             .catalog(catalog.unwrap_or(default_catalog))
                             .schema(schema)
                             .timeout(timeout)
                             .authorization(authorization)
+                            .user(user)
+                            .trace(trace::new_trace_if_enabled(trace.as_deref()))
+                            .verbose(trace::is_verbose_enabled(verbose.as_deref()))
                             .build()
                             .context(CreateContext)
                             .map_err(reject::custom)
@@ -766,6 +1386,19 @@ impl Service {
             )
     }
 
+    fn with_response_format(
+        &self,
+    ) -> impl Filter<Extract = (ResponseFormat,), Error = warp::Rejection> + Clone {
+        header::optional::<String>(ACCEPT_HEADER).and_then(|accept: Option<String>| async move {
+            match accept {
+                None => Ok(ResponseFormat::Json),
+                Some(accept) => ResponseFormat::from_accept(&accept)
+                    .context(UnsupportedAcceptType { accept })
+                    .map_err(reject::custom),
+            }
+        })
+    }
+
     fn with_profiler(&self) -> impl Filter<Extract = (Arc<Profiler>,), Error = Infallible> + Clone {
         let profiler = self.profiler.clone();
         warp::any().map(move || profiler.clone())
@@ -811,6 +1444,34 @@ impl Service {
         let runtime = self.engine_runtimes.read_runtime.clone();
         warp::any().map(move || runtime.clone())
     }
+
+    fn with_sst_change_notifier(
+        &self,
+    ) -> impl Filter<Extract = (Arc<SstChangeNotifier>,), Error = warp::Rejection> + Clone {
+        let notifier = self.sst_change_notifier.clone();
+        warp::any().and_then(move || {
+            let notifier = notifier.clone();
+            async move {
+                notifier
+                    .context(SstChangeNotifyNotEnabled)
+                    .map_err(reject::custom)
+            }
+        })
+    }
+
+    fn with_result_spiller(
+        &self,
+    ) -> impl Filter<Extract = (Arc<ResultSpiller>,), Error = warp::Rejection> + Clone {
+        let result_spiller = self.result_spiller.clone();
+        warp::any().and_then(move || {
+            let result_spiller = result_spiller.clone();
+            async move {
+                result_spiller
+                    .context(SpillNotEnabled)
+                    .map_err(reject::custom)
+            }
+        })
+    }
 }
 
 /// Service builder
@@ -822,6 +1483,9 @@ pub struct Builder {
     cluster: Option<ClusterRef>,
     proxy: Option<Arc<Proxy>>,
     opened_wals: Option<OpenedWals>,
+    result_spiller: Option<Arc<ResultSpiller>>,
+    static_route_reloader: Option<Arc<StaticRouteReloader>>,
+    sst_change_notifier: Option<Arc<SstChangeNotifier>>,
 }
 
 impl Builder {
@@ -834,6 +1498,9 @@ impl Builder {
             cluster: None,
             proxy: None,
             opened_wals: None,
+            result_spiller: None,
+            static_route_reloader: None,
+            sst_change_notifier: None,
         }
     }
 
@@ -866,6 +1533,27 @@ impl Builder {
         self.opened_wals = Some(opened_wals);
         self
     }
+
+    pub fn result_spiller(mut self, result_spiller: Option<Arc<ResultSpiller>>) -> Self {
+        self.result_spiller = result_spiller;
+        self
+    }
+
+    pub fn static_route_reloader(
+        mut self,
+        static_route_reloader: Option<Arc<StaticRouteReloader>>,
+    ) -> Self {
+        self.static_route_reloader = static_route_reloader;
+        self
+    }
+
+    pub fn sst_change_notifier(
+        mut self,
+        sst_change_notifier: Option<Arc<SstChangeNotifier>>,
+    ) -> Self {
+        self.sst_change_notifier = sst_change_notifier;
+        self
+    }
 }
 
 impl Builder {
@@ -891,12 +1579,85 @@ impl Builder {
             config: self.config,
             config_content,
             opened_wals,
+            result_spiller: self.result_spiller,
+            static_route_reloader: self.static_route_reloader,
+            sst_change_notifier: self.sst_change_notifier,
         };
 
         Ok(service)
     }
 }
 
+#[derive(Debug, Deserialize)]
+struct SstChangesParams {
+    table_id: Option<u64>,
+}
+
+/// The sst change exposed by `/debug/sst_changes`.
+#[derive(Debug, Serialize)]
+struct SstChangeView {
+    kind: &'static str,
+    table_id: u64,
+    table_name: String,
+    added: Vec<AddedSstView>,
+    deleted: Vec<DeletedSstView>,
+    max_sequence: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct AddedSstView {
+    file_id: u64,
+    level: u32,
+    path: String,
+    size: u64,
+    row_num: u64,
+    max_seq: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct DeletedSstView {
+    file_id: u64,
+    level: u32,
+}
+
+impl From<&SstChangeEvent> for SstChangeView {
+    fn from(event: &SstChangeEvent) -> Self {
+        let kind = match event.kind {
+            SstChangeKind::Flush => "flush",
+            SstChangeKind::Compaction => "compaction",
+        };
+        let added = event
+            .added
+            .iter()
+            .map(|add| AddedSstView {
+                file_id: add.file.id,
+                level: add.level.as_u32(),
+                path: event.sst_path(add.file.id).to_string(),
+                size: add.file.size,
+                row_num: add.file.row_num,
+                max_seq: add.file.max_seq,
+            })
+            .collect();
+        let deleted = event
+            .deleted
+            .iter()
+            .map(|delete| DeletedSstView {
+                file_id: delete.file_id,
+                level: delete.level.as_u32(),
+            })
+            .collect();
+
+        Self {
+            kind,
+            table_id: event.table_id.as_u64(),
+            table_name: event.table_name.clone(),
+            added,
+            deleted,
+            max_sequence: event.max_sequence,
+        }
+    }
+}
+
 /// Http service config
 #[derive(Debug, Clone)]
 pub struct HttpConfig {
@@ -905,17 +1666,71 @@ pub struct HttpConfig {
     pub timeout: Option<Duration>,
 }
 
+/// Request to adjust the shard lock lease at runtime.
+#[derive(Debug, Deserialize, Serialize)]
+struct ShardLockLeaseRequest {
+    ttl_sec: u64,
+    check_interval: ReadableDuration,
+}
+
 #[derive(Debug, Serialize)]
 struct ErrorResponse {
     code: u16,
     message: String,
 }
 
+/// Attach the trace sample to the reply if it's requested by the client.
+/// Collect the loads of the shards opened on this node from the stats of their
+/// tables.
+fn collect_shard_loads(cluster: &ClusterRef, instance: &InstanceRef) -> Vec<ShardLoad> {
+    let catalog = instance
+        .catalog_manager
+        .catalog_by_name(instance.catalog_manager.default_catalog_name())
+        .ok()
+        .flatten();
+
+    cluster
+        .list_shards()
+        .into_iter()
+        .filter_map(|shard_info| cluster.shard(shard_info.id))
+        .map(|shard| {
+            let tables = shard.tables();
+            let mut load = ShardLoad {
+                shard_id: shard.shard_info().id,
+                num_tables: tables.len(),
+                ..Default::default()
+            };
+            for table_info in &tables {
+                let table = catalog
+                    .as_ref()
+                    .and_then(|c| c.schema_by_name(&table_info.schema_name).ok().flatten())
+                    .and_then(|schema| schema.table_by_name(&table_info.name).ok().flatten());
+                if let Some(table) = table {
+                    let stats = table.stats();
+                    load.data_size += stats.data_size;
+                    load.num_write += stats.num_write;
+                    load.num_read += stats.num_read;
+                }
+            }
+            load
+        })
+        .collect()
+}
+
+fn with_trace_headers(reply: impl Reply, trace: Option<RequestTraceRef>) -> Response {
+    let mut resp = reply.into_response();
+    if let Some(trace) = trace {
+        trace.fill_headers(resp.headers_mut());
+    }
+    resp
+}
+
 fn error_to_status_code(err: &Error) -> StatusCode {
     match err {
         Error::UnGzip { .. }
         | Error::UnspportedContentEncodingType { .. }
         | Error::CreateContext { .. } => StatusCode::BAD_REQUEST,
+        Error::UnsupportedAcceptType { .. } => StatusCode::NOT_ACCEPTABLE,
         // TODO(yingwen): Map handle request error to more accurate status code
         Error::HandleRequest { .. }
         | Error::MissingEngineRuntimes { .. }
@@ -931,7 +1746,16 @@ fn error_to_status_code(err: &Error) -> StatusCode {
         | Error::AlreadyStarted { .. }
         | Error::MissingRouter { .. }
         | Error::MissingWal { .. }
-        | Error::QueryShards { .. } => StatusCode::BAD_REQUEST,
+        | Error::QueryShards { .. }
+        | Error::ShardLockLeaseNotSupported { .. }
+        | Error::UpdateShardLockLease { .. }
+        | Error::ShardNotFound { .. }
+        | Error::SpillNotEnabled { .. }
+        | Error::StaticRouteNotSupported { .. }
+        | Error::ReloadStaticRoute { .. }
+        | Error::SstChangeNotifyNotEnabled { .. } => StatusCode::BAD_REQUEST,
+        Error::SpillResult { source } => source.code(),
+        Error::ProposeRebalance { .. } => StatusCode::INTERNAL_SERVER_ERROR,
         Error::HandleUpdateLogLevel { .. } => StatusCode::INTERNAL_SERVER_ERROR,
         Error::QueryMaybeExceedTTL { .. } => StatusCode::OK,
         Error::UnAuthenticated { .. } => StatusCode::UNAUTHORIZED,
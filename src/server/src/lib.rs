@@ -32,3 +32,4 @@ mod mysql;
 mod postgresql;
 pub mod server;
 mod session;
+pub mod static_route;
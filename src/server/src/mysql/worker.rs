@@ -19,7 +19,7 @@ use std::{marker::PhantomData, net::SocketAddr, sync::Arc, time::Duration};
 
 use generic_error::BoxError;
 use interpreters::interpreter::Output;
-use logger::{error, info};
+use logger::{error, info, warn};
 use opensrv_mysql::{
     AsyncMysqlShim, ErrorKind, InitWriter, QueryResultWriter, StatementMetaWriter,
 };
@@ -35,6 +35,11 @@ use crate::{
     session::{parse_catalog_and_schema_from_db_string, Channel, Session, SessionRef},
 };
 
+/// The auth plugin sending the password in clear text, which is required to
+/// verify the password by the auth provider, e.g. ldap.
+const MYSQL_CLEAR_PASSWORD: &str = "mysql_clear_password";
+const MYSQL_NATIVE_PASSWORD: &str = "mysql_native_password";
+
 pub struct MysqlWorker<W: std::io::Write + Send + Sync> {
     generic_hold: PhantomData<W>,
     proxy: Arc<Proxy>,
@@ -63,6 +68,51 @@ where
 {
     type Error = crate::mysql::error::Error;
 
+    fn default_auth_plugin(&self) -> &str {
+        self.auth_plugin()
+    }
+
+    async fn auth_plugin_for_username<'a, 'user>(&'a self, _user: &'user [u8]) -> &'a str {
+        self.auth_plugin()
+    }
+
+    async fn authenticate(
+        &self,
+        auth_plugin: &str,
+        username: &[u8],
+        _salt: &[u8],
+        auth_data: &[u8],
+    ) -> bool {
+        let username = String::from_utf8_lossy(username);
+        if !self.proxy.auth_enabled() {
+            self.session.set_user(username.to_string());
+            return true;
+        }
+
+        if auth_plugin != MYSQL_CLEAR_PASSWORD {
+            warn!(
+                "Mysql client must authenticate with {MYSQL_CLEAR_PASSWORD}, plugin:{auth_plugin}"
+            );
+            return false;
+        }
+
+        // The clear password is terminated by a NUL byte.
+        let password = auth_data.strip_suffix(&[0]).unwrap_or(auth_data);
+        let user = match std::str::from_utf8(password) {
+            Ok(password) => self.proxy.identify_password(&username, password),
+            Err(_) => None,
+        };
+        // The session belongs to the user verified by the provider rather than
+        // the name claimed by the client.
+        match user {
+            Some(user) => {
+                self.session.set_user(user);
+                true
+            }
+            None => false,
+        }
+    }
+
     async fn on_prepare<'a>(
         &'a mut self,
         _query: &'a str,
@@ -125,6 +175,14 @@ impl<W> MysqlWorker<W>
 where
     W: std::io::Write + Send + Sync,
 {
+    fn auth_plugin(&self) -> &'static str {
+        if self.proxy.auth_enabled() {
+            MYSQL_CLEAR_PASSWORD
+        } else {
+            MYSQL_NATIVE_PASSWORD
+        }
+    }
+
     async fn do_query<'a>(&'a mut self, sql: &'a str) -> Result<Output> {
         if let Some(output) = federated::check(sql, self.session.clone()) {
             return Ok(output);
@@ -152,6 +210,7 @@ where
             .catalog(session.catalog().to_string())
             .schema(session.schema().to_string())
             .timeout(self.timeout)
+            .user(session.user())
             .build()
             .context(CreateContext)
     }
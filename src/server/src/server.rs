@@ -19,20 +19,25 @@
 
 use std::sync::Arc;
 
+use analytic_engine::hook::SstChangeNotifier;
 use catalog::manager::ManagerRef;
 use cluster::ClusterRef;
 use datafusion::execution::{runtime_env::RuntimeConfig, FunctionRegistry};
 use df_operator::registry::FunctionRegistryRef;
+use disk_quota::DiskGovernorRef;
 use interpreters::table_manipulator::TableManipulatorRef;
 use logger::{info, warn, RuntimeLevel};
 use macros::define_result;
 use notifier::notifier::RequestNotifiers;
+use object_store::ObjectStoreRef;
 use partition_table_engine::PartitionTableEngine;
 use proxy::{
-    auth::{with_file::AuthWithFile, AuthType},
+    auth::Authenticator,
     hotspot::HotspotRecorder,
+    http::spill::ResultSpiller,
     instance::{DynamicConfig, Instance, InstanceRef},
     limiter::Limiter,
+    query_registry::QueryRegistry,
     schema_config_provider::SchemaConfigProviderRef,
     Proxy,
 };
@@ -55,6 +60,7 @@ use crate::{
     mysql::error::Error as MysqlError,
     postgresql,
     postgresql::error::Error as PostgresqlError,
+    static_route::StaticRouteReloader,
 };
 
 #[derive(Debug, Snafu)]
@@ -151,11 +157,18 @@ pub struct Server {
     mysql_service: mysql::MysqlService,
     postgresql_service: postgresql::PostgresqlService,
     instance: InstanceRef,
+    proxy: Arc<Proxy>,
     cluster: Option<ClusterRef>,
     local_tables_recoverer: Option<LocalTablesRecoverer>,
+    disk_governor: Option<DiskGovernorRef>,
+    result_spiller: Option<Arc<ResultSpiller>>,
 }
 
 impl Server {
+    pub fn proxy(&self) -> Arc<Proxy> {
+        self.proxy.clone()
+    }
+
     pub async fn stop(mut self) {
         self.rpc_services.shutdown().await;
         self.http_service.stop();
@@ -165,27 +178,18 @@ impl Server {
         if let Some(cluster) = &self.cluster {
             cluster.stop().await.expect("fail to stop cluster");
         }
-    }
 
-    pub async fn start(&mut self) -> Result<()> {
-        // Run in standalone mode
-        if let Some(local_tables_recoverer) = &self.local_tables_recoverer {
-            info!("Server start, open local tables");
-            local_tables_recoverer
-                .recover()
-                .await
-                .context(OpenLocalTables)?;
+        if let Some(disk_governor) = &self.disk_governor {
+            disk_governor.stop().await;
         }
 
-        // Run in cluster mode
-        if let Some(cluster) = &self.cluster {
-            info!("Server start, start cluster");
-            cluster.start().await.context(StartCluster)?;
+        if let Some(result_spiller) = &self.result_spiller {
+            result_spiller.stop().await;
         }
+    }
 
-        // TODO: Is it necessary to create default schema in cluster mode?
-        info!("Server start, create default schema if not exist");
-        self.create_default_schema_if_not_exists().await;
+    pub async fn start(&mut self) -> Result<()> {
+        self.open_tables().await?;
 
         info!("Server start, start services");
 
@@ -210,6 +214,60 @@ impl Server {
         Ok(())
     }
 
+    /// Start the server in embedded mode, only the tables are opened and no
+    /// network services will be started, the requests are expected to be
+    /// served through the [Proxy] in process.
+    pub async fn start_embedded(&mut self) -> Result<()> {
+        self.open_tables().await?;
+
+        info!("Server start in embedded mode finished");
+
+        Ok(())
+    }
+
+    /// Stop the server started by [Server::start_embedded], and close the
+    /// table engine gracefully.
+    pub async fn stop_embedded(self) {
+        if let Some(cluster) = &self.cluster {
+            cluster.stop().await.expect("fail to stop cluster");
+        }
+
+        if let Some(disk_governor) = &self.disk_governor {
+            disk_governor.stop().await;
+        }
+
+        if let Some(result_spiller) = &self.result_spiller {
+            result_spiller.stop().await;
+        }
+
+        if let Err(e) = self.instance.table_engine.close().await {
+            warn!("Failed to close table engine in embedded mode, err:{e}");
+        }
+    }
+
+    async fn open_tables(&self) -> Result<()> {
+        // Run in standalone mode
+        if let Some(local_tables_recoverer) = &self.local_tables_recoverer {
+            info!("Server start, open local tables");
+            local_tables_recoverer
+                .recover()
+                .await
+                .context(OpenLocalTables)?;
+        }
+
+        // Run in cluster mode
+        if let Some(cluster) = &self.cluster {
+            info!("Server start, start cluster");
+            cluster.start().await.context(StartCluster)?;
+        }
+
+        // TODO: Is it necessary to create default schema in cluster mode?
+        info!("Server start, create default schema if not exist");
+        self.create_default_schema_if_not_exists().await;
+
+        Ok(())
+    }
+
     async fn create_default_schema_if_not_exists(&self) {
         let catalog_mgr = &self.instance.catalog_manager;
         let default_catalog = catalog_mgr
@@ -251,6 +309,12 @@ pub struct Builder {
     opened_wals: Option<OpenedWals>,
     remote_engine: Option<RemoteEngineRef>,
     datatfusion_context: Option<DatafusionContext>,
+    spill_store: Option<ObjectStoreRef>,
+    query_registry: Option<Arc<QueryRegistry>>,
+    disk_governor: Option<DiskGovernorRef>,
+    spill_on_local_disk: bool,
+    static_route_reloader: Option<Arc<StaticRouteReloader>>,
+    sst_change_notifier: Option<Arc<SstChangeNotifier>>,
 }
 
 impl Builder {
@@ -273,7 +337,13 @@ impl Builder {
             local_tables_recoverer: None,
             opened_wals: None,
             remote_engine: None,
+            spill_store: None,
+            query_registry: None,
+            disk_governor: None,
+            spill_on_local_disk: false,
             datatfusion_context: None,
+            static_route_reloader: None,
+            sst_change_notifier: None,
         }
     }
 
@@ -358,6 +428,45 @@ impl Builder {
         self
     }
 
+    /// The object store to spill the large query results.
+    pub fn spill_store(mut self, store: ObjectStoreRef) -> Self {
+        self.spill_store = Some(store);
+        self
+    }
+
+    /// The registry of the in-flight queries, which should be shared with the
+    /// `system.queries` table.
+    pub fn query_registry(mut self, query_registry: Arc<QueryRegistry>) -> Self {
+        self.query_registry = Some(query_registry);
+        self
+    }
+
+    /// The governor of the local disk space, the spilled results are tracked
+    /// by it if they are on the local disk.
+    pub fn disk_governor(mut self, disk_governor: Option<DiskGovernorRef>) -> Self {
+        self.disk_governor = disk_governor;
+        self
+    }
+
+    pub fn spill_on_local_disk(mut self, spill_on_local_disk: bool) -> Self {
+        self.spill_on_local_disk = spill_on_local_disk;
+        self
+    }
+
+    /// The reloader of the static route config, only available in the
+    /// deployment without meta.
+    pub fn static_route_reloader(mut self, reloader: Arc<StaticRouteReloader>) -> Self {
+        self.static_route_reloader = Some(reloader);
+        self
+    }
+
+    /// The notifier of the sst changes, which should be registered as an
+    /// engine hook.
+    pub fn sst_change_notifier(mut self, notifier: Option<Arc<SstChangeNotifier>>) -> Self {
+        self.sst_change_notifier = notifier;
+        self
+    }
+
     pub fn remote_engine(mut self, remote_engine: RemoteEngineRef) -> Self {
         self.remote_engine = Some(remote_engine);
         self
@@ -456,16 +565,7 @@ impl Builder {
             .then(|| Arc::new(RequestNotifiers::default()));
 
         // Build auth
-        let mut auth = if self.server_config.auth.enable {
-            match self.server_config.auth.auth_type {
-                AuthType::File => AuthWithFile::new(true, self.server_config.auth.source.clone()),
-            }
-        } else {
-            AuthWithFile::default()
-        };
-
-        // Load auth credential
-        auth.load_credential().context(LoadCredential)?;
+        let auth = Authenticator::try_new(&self.server_config.auth).context(LoadCredential)?;
 
         let proxy = Arc::new(Proxy::new(
             auth.clone(),
@@ -475,15 +575,37 @@ impl Builder {
             Endpoint::new(self.node_addr, self.server_config.grpc_port),
             self.server_config.resp_compress_min_length.as_byte() as usize,
             self.server_config.auto_create_table,
+            self.server_config.atomic_write,
             provider.clone(),
             hotspot_recorder.clone(),
             engine_runtimes.clone(),
-            self.cluster.is_some(),
+            self.cluster.clone(),
             self.server_config.sub_table_access_perm,
             request_notifiers,
             expensive_query_threshold,
+            self.server_config.query_retry,
+            self.query_registry.unwrap_or_default(),
         ));
 
+        let result_spiller = match self.spill_store {
+            Some(store) if self.server_config.result_spill.enable => Some(Arc::new(
+                ResultSpiller::new(store, self.server_config.result_spill.clone()),
+            )),
+            _ => None,
+        };
+
+        if let Some(disk_governor) = &self.disk_governor {
+            if let Some(result_spiller) =
+                result_spiller.as_ref().filter(|_| self.spill_on_local_disk)
+            {
+                disk_governor.register(result_spiller.clone());
+            }
+            disk_governor.start(&engine_runtimes.default_runtime);
+        }
+        if let Some(result_spiller) = &result_spiller {
+            result_spiller.start(&engine_runtimes.default_runtime);
+        }
+
         let http_service = http::Builder::new(http_config)
             .engine_runtimes(engine_runtimes.clone())
             .log_runtime(log_runtime)
@@ -491,6 +613,9 @@ impl Builder {
             .cluster(self.cluster.clone())
             .proxy(proxy.clone())
             .opened_wals(opened_wals.clone())
+            .result_spiller(result_spiller.clone())
+            .static_route_reloader(self.static_route_reloader)
+            .sst_change_notifier(self.sst_change_notifier)
             .build()
             .context(HttpService {
                 msg: "build failed",
@@ -524,7 +649,7 @@ impl Builder {
             .cluster(self.cluster.clone())
             .opened_wals(opened_wals)
             .timeout(self.server_config.timeout.map(|v| v.0))
-            .proxy(proxy)
+            .proxy(proxy.clone())
             .hotspot_recorder(hotspot_recorder)
             .query_dedup(self.server_config.query_dedup)
             .build()
@@ -536,8 +661,11 @@ impl Builder {
             mysql_service,
             postgresql_service,
             instance,
+            proxy,
             cluster: self.cluster,
             local_tables_recoverer: self.local_tables_recoverer,
+            disk_governor: self.disk_governor,
+            result_spiller,
         };
         Ok(server)
     }
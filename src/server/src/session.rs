@@ -29,6 +29,8 @@ use sqlparser::dialect::{Dialect, MySqlDialect, PostgreSqlDialect};
 pub struct Session {
     catalog: ArcSwap<String>,
     schema: ArcSwap<String>,
+    /// Name of the user authenticated on the connection
+    user: ArcSwap<String>,
     conn_info: ConnInfo,
 }
 
@@ -39,6 +41,7 @@ impl Session {
         Session {
             catalog: ArcSwap::new(Arc::new(DEFAULT_CATALOG.clone())),
             schema: ArcSwap::new(Arc::new(DEFAULT_SCHEMA.into())),
+            user: ArcSwap::new(Arc::new(String::new())),
             conn_info: ConnInfo::new(addr, channel),
         }
     }
@@ -68,6 +71,16 @@ impl Session {
     pub fn set_schema(&self, schema: String) {
         self.schema.store(Arc::new(schema));
     }
+
+    #[inline]
+    pub fn user(&self) -> String {
+        self.user.load().to_string()
+    }
+
+    #[inline]
+    pub fn set_user(&self, user: String) {
+        self.user.store(Arc::new(user));
+    }
 }
 
 #[derive(Debug)]
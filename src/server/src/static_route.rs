@@ -0,0 +1,139 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+//! Hot reload of the static route config in the deployment without meta
+
+use std::sync::Arc;
+
+use catalog::manager::ManagerRef;
+use generic_error::{BoxError, GenericError};
+use logger::info;
+use macros::define_result;
+use proxy::schema_config_provider::config_based::ConfigBasedProvider;
+use router::{rule_based::ClusterView, RuleBasedRouter};
+use snafu::{ensure, Backtrace, OptionExt, ResultExt, Snafu};
+
+use crate::config::StaticRouteConfig;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display(
+        "Hash rule has no shards, schema:{}.\nBacktrace:\n{}",
+        schema,
+        backtrace
+    ))]
+    EmptyHashRule {
+        schema: String,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display(
+        "Invalid default table options, schema:{}, msg:{}.\nBacktrace:\n{}",
+        schema,
+        msg,
+        backtrace
+    ))]
+    InvalidDefaultTableOptions {
+        schema: String,
+        msg: String,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Failed to fetch default catalog, err:{}", source))]
+    FetchCatalog { source: catalog::manager::Error },
+
+    #[snafu(display("Default catalog is not found.\nBacktrace:\n{}", backtrace))]
+    DefaultCatalogNotFound { backtrace: Backtrace },
+
+    #[snafu(display("Failed to create schema, schema:{}, err:{}", schema, source))]
+    CreateSchema {
+        schema: String,
+        source: GenericError,
+    },
+}
+
+define_result!(Error);
+
+/// Applies a new [StaticRouteConfig] to the router and the schema config
+/// provider without restarting the server.
+pub struct StaticRouteReloader {
+    router: Arc<RuleBasedRouter>,
+    schema_config_provider: Arc<ConfigBasedProvider>,
+    catalog_manager: ManagerRef,
+}
+
+impl StaticRouteReloader {
+    pub fn new(
+        router: Arc<RuleBasedRouter>,
+        schema_config_provider: Arc<ConfigBasedProvider>,
+        catalog_manager: ManagerRef,
+    ) -> Self {
+        Self {
+            router,
+            schema_config_provider,
+            catalog_manager,
+        }
+    }
+
+    /// Reload the rules and the topology.
+    ///
+    /// The schemas in the new topology are created first, and nothing is
+    /// changed if the config is invalid.
+    pub async fn reload(&self, config: StaticRouteConfig) -> Result<()> {
+        for rule in &config.rules.hash_rules {
+            ensure!(
+                !rule.shards.is_empty(),
+                EmptyHashRule {
+                    schema: &rule.schema
+                }
+            );
+        }
+
+        for schema_shard_view in &config.topology.schema_shards {
+            if let Err(msg) = schema_shard_view.default_table_options.validate() {
+                return InvalidDefaultTableOptions {
+                    schema: &schema_shard_view.schema,
+                    msg,
+                }
+                .fail();
+            }
+        }
+
+        let default_catalog = self
+            .catalog_manager
+            .catalog_by_name(self.catalog_manager.default_catalog_name())
+            .context(FetchCatalog)?
+            .context(DefaultCatalogNotFound)?;
+        for schema_shard_view in &config.topology.schema_shards {
+            default_catalog
+                .create_schema(&schema_shard_view.schema)
+                .await
+                .box_err()
+                .context(CreateSchema {
+                    schema: &schema_shard_view.schema,
+                })?;
+        }
+
+        let cluster_view = ClusterView::from(&config.topology);
+        self.schema_config_provider
+            .reload(cluster_view.schema_configs.clone());
+        self.router.reload(cluster_view, config.rules);
+
+        info!("Static route config is reloaded");
+
+        Ok(())
+    }
+}
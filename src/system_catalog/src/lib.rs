@@ -43,6 +43,7 @@ use table_engine::{
     },
 };
 
+pub mod queries;
 pub mod sys_catalog_table;
 pub mod tables;
 
@@ -64,11 +65,19 @@ pub const TABLES_TABLE_SEQ: TableSeq = TableSeq::from_u32(2);
 /// Table id of the `tables` table.
 pub const TABLES_TABLE_ID: TableId = TableId::with_seq(SYSTEM_SCHEMA_ID, TABLES_TABLE_SEQ).unwrap();
 
+/// Table name of the `queries` table.
+pub const QUERIES_TABLE_NAME: &str = "queries";
+/// Table sequence of the `queries` table.
+pub const QUERIES_TABLE_SEQ: TableSeq = TableSeq::from_u32(3);
+/// Table id of the `queries` table.
+pub const QUERIES_TABLE_ID: TableId =
+    TableId::with_seq(SYSTEM_SCHEMA_ID, QUERIES_TABLE_SEQ).unwrap();
+
 // NOTE: The MAX_SYSTEM_TABLE_ID should be updated if any new system table is
 // added.
 
 /// Max table id of all the system tables.
-pub const MAX_SYSTEM_TABLE_SEQ: TableSeq = TABLES_TABLE_SEQ;
+pub const MAX_SYSTEM_TABLE_SEQ: TableSeq = QUERIES_TABLE_SEQ;
 
 /// The minimal thing that a system table needs to implement
 #[async_trait]
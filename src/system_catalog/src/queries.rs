@@ -0,0 +1,213 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+/// implementation of system table: Queries
+/// For example `SELECT * FROM system.public.queries`
+use std::{
+    fmt::{Debug, Formatter},
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use common_types::{
+    column_schema,
+    datum::{Datum, DatumKind},
+    projected_schema::RowProjector,
+    record_batch::FetchedRecordBatchBuilder,
+    row::Row,
+    schema,
+    schema::Schema,
+    time::Timestamp,
+};
+use generic_error::BoxError;
+use serde::Serialize;
+use snafu::ResultExt;
+use table_engine::{
+    stream::SendableRecordBatchStream,
+    table::{ReadRequest, TableId},
+};
+
+use crate::{OneRecordBatchStream, SystemTable, QUERIES_TABLE_ID, QUERIES_TABLE_NAME};
+
+/// Snapshot of an in-flight query.
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryInfo {
+    pub query_id: String,
+    pub sql: String,
+    pub user: String,
+    pub start_time_ms: i64,
+    pub elapsed_ms: u64,
+    pub scanned_bytes: u64,
+    pub killed: bool,
+}
+
+/// Source of the in-flight queries.
+pub trait ActiveQueries: Send + Sync + Debug {
+    fn list(&self) -> Vec<QueryInfo>;
+}
+
+pub type ActiveQueriesRef = Arc<dyn ActiveQueries>;
+
+/// Build a new table schema for queries
+fn queries_schema() -> Schema {
+    schema::Builder::with_capacity(7)
+        .auto_increment_column_id(true)
+        .add_key_column(
+            column_schema::Builder::new("timestamp".to_string(), DatumKind::Timestamp)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_key_column(
+            column_schema::Builder::new("query_id".to_string(), DatumKind::String)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_normal_column(
+            column_schema::Builder::new("sql".to_string(), DatumKind::String)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_normal_column(
+            column_schema::Builder::new("user".to_string(), DatumKind::String)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_normal_column(
+            column_schema::Builder::new("elapsed_ms".to_string(), DatumKind::UInt64)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_normal_column(
+            column_schema::Builder::new("scanned_bytes".to_string(), DatumKind::UInt64)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_normal_column(
+            column_schema::Builder::new("killed".to_string(), DatumKind::Boolean)
+                .is_nullable(false)
+                .is_tag(false)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .primary_key_indexes(vec![0, 1])
+        .build()
+        .unwrap()
+}
+
+pub struct Queries {
+    schema: Schema,
+    active_queries: ActiveQueriesRef,
+}
+
+impl Debug for Queries {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SysQueries")
+            .field("schema", &self.schema)
+            .finish()
+    }
+}
+
+impl Queries {
+    pub fn new(active_queries: ActiveQueriesRef) -> Self {
+        Self {
+            schema: queries_schema(),
+            active_queries,
+        }
+    }
+
+    #[allow(clippy::wrong_self_convention)]
+    fn from_query(&self, query: QueryInfo) -> Row {
+        let mut datums = Vec::with_capacity(self.schema.num_columns());
+        datums.push(Datum::Timestamp(Timestamp::new(query.start_time_ms)));
+        datums.push(Datum::from(query.query_id.as_str()));
+        datums.push(Datum::from(query.sql.as_str()));
+        datums.push(Datum::from(query.user.as_str()));
+        datums.push(Datum::from(query.elapsed_ms));
+        datums.push(Datum::from(query.scanned_bytes));
+        datums.push(Datum::from(query.killed));
+        Row::from_datums(datums)
+    }
+}
+
+#[async_trait]
+impl SystemTable for Queries {
+    fn name(&self) -> &str {
+        QUERIES_TABLE_NAME
+    }
+
+    fn id(&self) -> TableId {
+        QUERIES_TABLE_ID
+    }
+
+    fn schema(&self) -> Schema {
+        self.schema.clone()
+    }
+
+    async fn read(
+        &self,
+        request: ReadRequest,
+    ) -> table_engine::table::Result<SendableRecordBatchStream> {
+        let fetched_schema = request.projected_schema.to_record_schema_with_key();
+        let primary_key_indexes = fetched_schema.primary_key_idx().to_vec();
+        let fetched_schema = fetched_schema.to_record_schema();
+        let mut builder = FetchedRecordBatchBuilder::new(
+            fetched_schema.clone(),
+            Some(primary_key_indexes.clone()),
+        );
+
+        let table_schema = request.projected_schema.table_schema();
+        let row_projector = RowProjector::new(
+            &fetched_schema,
+            Some(primary_key_indexes),
+            table_schema,
+            &self.schema,
+        )
+        .expect("Should succeed to try_project_key of sys_queries");
+        for query in self.active_queries.list() {
+            let row = self.from_query(query);
+            let projected_row = row_projector.project_row(&row, Vec::new());
+            builder
+                .append_row(projected_row)
+                .box_err()
+                .context(table_engine::table::Scan { table: self.name() })?;
+        }
+        let record_batch = builder.build().unwrap().into_record_batch();
+        Ok(Box::pin(OneRecordBatchStream {
+            schema: self.schema.clone().to_record_schema(),
+            record_batch: Some(record_batch),
+        }))
+    }
+}
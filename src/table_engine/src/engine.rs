@@ -24,17 +24,20 @@ use async_trait::async_trait;
 use common_types::{
     schema::Schema,
     table::{ShardId, DEFAULT_SHARD_ID},
+    time::TimeRange,
+    SequenceNumber,
 };
 use generic_error::{GenericError, GenericResult};
 use horaedbproto::sys_catalog as sys_catalog_pb;
 use itertools::Itertools;
 use macros::define_result;
 use runtime::{PriorityRuntime, RuntimeRef};
+use serde::Serialize;
 use snafu::{ensure, Backtrace, Snafu};
 
 use crate::{
     partition::PartitionInfo,
-    table::{SchemaId, TableId, TableInfo, TableRef},
+    table::{SchemaId, TableId, TableInfo, TableRef, WriteRequest},
 };
 
 #[derive(Debug, Snafu)]
@@ -332,6 +335,31 @@ pub struct TableDef {
 
 pub type CloseShardRequest = OpenShardRequest;
 
+/// Write request of one table in [WriteTablesRequest].
+#[derive(Clone, Debug)]
+pub struct TableWriteRequest {
+    pub schema_id: SchemaId,
+    pub table_id: TableId,
+    pub request: WriteRequest,
+}
+
+/// Request to write multiple tables on the same shard atomically.
+#[derive(Clone, Debug)]
+pub struct WriteTablesRequest {
+    /// Table engine type
+    pub engine: String,
+    pub requests: Vec<TableWriteRequest>,
+}
+
+/// Request to find an opened table by its name.
+#[derive(Clone, Debug)]
+pub struct FindTableRequest {
+    /// Table engine type
+    pub engine: String,
+    pub schema_id: SchemaId,
+    pub table_name: String,
+}
+
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct ShardStats {
     pub num_written_bytes: u64,
@@ -343,6 +371,74 @@ pub struct TableEngineStats {
     pub shard_stats: HashMap<ShardId, ShardStats>,
 }
 
+/// A single check of [TableEngine::check_open_shard].
+#[derive(Clone, Debug, Serialize)]
+pub struct OpenShardCheck {
+    /// What is checked, e.g. wal, object_store
+    pub item: String,
+    /// The table checked, None if the check is not specific to a table
+    pub table: Option<String>,
+    pub passed: bool,
+    pub msg: String,
+}
+
+/// Report of [TableEngine::check_open_shard].
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct OpenShardCheckReport {
+    pub shard_id: ShardId,
+    pub passed: bool,
+    pub checks: Vec<OpenShardCheck>,
+}
+
+impl OpenShardCheckReport {
+    pub fn new(shard_id: ShardId, checks: Vec<OpenShardCheck>) -> Self {
+        Self {
+            shard_id,
+            passed: checks.iter().all(|v| v.passed),
+            checks,
+        }
+    }
+}
+
+/// Request of [TableEngine::scan_wal].
+#[derive(Clone, Debug)]
+pub struct ScanWalRequest {
+    /// Table engine type
+    pub engine: String,
+    pub schema_id: SchemaId,
+    pub table_name: String,
+    /// The first sequence to scan (inclusive)
+    pub start_sequence: SequenceNumber,
+    /// The last sequence to scan (inclusive), None means the latest one
+    pub end_sequence: Option<SequenceNumber>,
+    /// Only the rows in the time range are returned if it's set
+    pub time_range: Option<TimeRange>,
+    /// Max number of entries to return
+    pub limit: usize,
+    /// Max number of rows to preview for every entry
+    pub max_preview_rows: usize,
+}
+
+/// A decoded entry of the wal returned by [TableEngine::scan_wal].
+#[derive(Clone, Debug, Serialize)]
+pub struct WalEntryView {
+    pub sequence: SequenceNumber,
+    /// Kind of the entry, e.g. write, alter_schema, alter_options
+    pub kind: String,
+    pub num_rows: usize,
+    /// Estimated size of the rows in bytes
+    pub size: usize,
+    pub preview: Vec<String>,
+}
+
+/// Result of [TableEngine::scan_wal].
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ScanWalResult {
+    pub entries: Vec<WalEntryView>,
+    /// The sequence to continue the scan from, None if no more entries left
+    pub next_sequence: Option<SequenceNumber>,
+}
+
 /// Table engine
 // TODO(yingwen): drop table support to release resource owned by the table
 #[async_trait]
@@ -375,16 +471,103 @@ pub trait TableEngine: Send + Sync {
     async fn open_shard(&self, request: OpenShardRequest) -> Result<OpenShardResult>;
 
     /// Close tables on same shard.
-    async fn close_shard(&self, request: CloseShardRequest) -> Vec<Result<String>>;
+    async fn close_shard(&self, request: CloseShardRequest) -> Vec<Result<ClosedTable>>;
+
+    /// Write to multiple tables on the same shard atomically, either all or
+    /// none of the writes will be persisted.
+    ///
+    /// Returns the number of rows written to each table.
+    async fn write_tables(&self, _request: WriteTablesRequest) -> Result<Vec<usize>> {
+        UnexpectedNoCause {
+            msg: format!(
+                "atomic write of tables is not supported by engine:{}",
+                self.engine_type()
+            ),
+        }
+        .fail()
+    }
+
+    /// Install the epoch of the shard's lock held by this node, and the writes
+    /// to the tables of the shard accepted with an older epoch will be
+    /// refused.
+    fn install_shard_write_epoch(&self, _shard_id: ShardId, _epoch: u64) {}
+
+    /// Fence the writes to the tables of the shard until a newer epoch is
+    /// installed, called when the lock of the shard is lost.
+    fn fence_shard_writes(&self, _shard_id: ShardId) {}
+
+    /// Pressure of the writes, that's the ratio of the memory used by the
+    /// writes to its limit, zero if it's unknown or unlimited.
+    fn write_pressure(&self) -> f64 {
+        0.0
+    }
+
+    /// Find the table opened by this engine, return None if the table is not
+    /// found or the engine doesn't support finding tables.
+    async fn find_table(&self, _request: FindTableRequest) -> Result<Option<TableRef>> {
+        Ok(None)
+    }
 
     /// Report the statistics of the table engine.
     async fn report_statistics(&self) -> Result<Option<TableEngineStats>> {
         Ok(None)
     }
+
+    /// Check whether the tables of the shard could be opened by this node,
+    /// without opening them.
+    async fn check_open_shard(&self, _request: OpenShardRequest) -> Result<OpenShardCheckReport> {
+        UnexpectedNoCause {
+            msg: format!(
+                "checking shard opening is not supported by engine:{}",
+                self.engine_type()
+            ),
+        }
+        .fail()
+    }
+
+    /// Scan and decode the wal entries of an opened table, which is used for
+    /// debugging and auditing.
+    async fn scan_wal(&self, _request: ScanWalRequest) -> Result<ScanWalResult> {
+        UnexpectedNoCause {
+            msg: format!(
+                "scanning wal is not supported by engine:{}",
+                self.engine_type()
+            ),
+        }
+        .fail()
+    }
 }
 
 pub type OpenShardResult = HashMap<TableId, GenericResult<Option<TableRef>>>;
 
+/// Table closed by [TableEngine::close_shard].
+#[derive(Clone, Debug, Default)]
+pub struct ClosedTable {
+    pub table_name: String,
+    /// The last sequence of the table when it is closed
+    pub last_sequence: SequenceNumber,
+    /// The entries after this sequence are not flushed and left in the wal
+    pub flushed_sequence: SequenceNumber,
+}
+
+impl ClosedTable {
+    /// Closed table without any sequence, e.g. the table of the engine without
+    /// wal.
+    pub fn new(table_name: String) -> Self {
+        Self {
+            table_name,
+            ..Default::default()
+        }
+    }
+
+    /// Whether some data of the table has to be replayed from the wal on next
+    /// opening.
+    #[inline]
+    pub fn has_unflushed_data(&self) -> bool {
+        self.last_sequence > self.flushed_sequence
+    }
+}
+
 /// A reference counted pointer to table engine
 pub type TableEngineRef = Arc<dyn TableEngine>;
 
@@ -30,3 +30,5 @@ pub mod table;
 pub const MEMORY_ENGINE_TYPE: &str = "Memory";
 pub const ANALYTIC_ENGINE_TYPE: &str = "Analytic";
 pub const PARTITION_TABLE_ENGINE_TYPE: &str = "PartitionTable";
+pub const EXTERNAL_ENGINE_TYPE: &str = "External";
+pub const UNION_ENGINE_TYPE: &str = "Union";
@@ -39,7 +39,7 @@ use snafu::{OptionExt, ResultExt};
 
 use crate::{
     engine::{
-        CloseShardRequest, CloseTableRequest, CreateTableParams, CreateTableRequest,
+        CloseShardRequest, CloseTableRequest, ClosedTable, CreateTableParams, CreateTableRequest,
         DropTableRequest, OpenShardRequest, OpenShardResult, OpenTableRequest, TableEngine,
     },
     remote::{
@@ -78,6 +78,8 @@ pub struct MemoryTable {
     row_groups: Arc<RwLock<RowGroupVec>>,
     /// Engine type
     engine_type: String,
+    /// Table options
+    options: HashMap<String, String>,
 }
 
 impl MemoryTable {
@@ -88,8 +90,14 @@ impl MemoryTable {
             schema,
             row_groups: Arc::new(RwLock::new(Vec::new())),
             engine_type,
+            options: HashMap::new(),
         }
     }
+
+    pub fn with_options(mut self, options: HashMap<String, String>) -> Self {
+        self.options = options;
+        self
+    }
 }
 
 impl fmt::Debug for MemoryTable {
@@ -114,7 +122,7 @@ impl Table for MemoryTable {
     }
 
     fn options(&self) -> HashMap<String, String> {
-        HashMap::new()
+        self.options.clone()
     }
 
     fn schema(&self) -> Schema {
@@ -338,8 +346,11 @@ impl TableEngine for MemoryTableEngine {
     }
 
     /// Close tables on same shard.
-    async fn close_shard(&self, _request: CloseShardRequest) -> Vec<crate::engine::Result<String>> {
-        vec![Ok("".to_string())]
+    async fn close_shard(
+        &self,
+        _request: CloseShardRequest,
+    ) -> Vec<crate::engine::Result<ClosedTable>> {
+        vec![Ok(ClosedTable::default())]
     }
 }
 
@@ -132,6 +132,11 @@ pub struct HashPartitionInfo {
     pub linear: bool,
 }
 
+/// Version of the key partition by the series key, whose rows are located by
+/// the jump consistent hash of the key rather than the modulo, so only about
+/// `1/n` of the series are moved once the partitions are increased to `n`.
+pub const SERIES_PARTITION_VERSION: i32 = 1;
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct KeyPartitionInfo {
     pub version: i32,
@@ -140,6 +145,15 @@ pub struct KeyPartitionInfo {
     pub linear: bool,
 }
 
+impl KeyPartitionInfo {
+    /// Whether the table is partitioned by the series key, see
+    /// [SERIES_PARTITION_VERSION].
+    #[inline]
+    pub fn is_series(&self) -> bool {
+        self.version == SERIES_PARTITION_VERSION
+    }
+}
+
 impl From<PartitionDefinition> for horaedbproto::cluster::PartitionDefinition {
     fn from(definition: PartitionDefinition) -> Self {
         Self {
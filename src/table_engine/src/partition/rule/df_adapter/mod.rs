@@ -91,8 +91,8 @@ mod tests {
 
     use super::*;
     use crate::partition::{
-        rule::key::{compute_partition, DEFAULT_PARTITION_VERSION},
-        KeyPartitionInfo, PartitionDefinition,
+        rule::key::{compute_consistent_partition, compute_partition, DEFAULT_PARTITION_VERSION},
+        KeyPartitionInfo, PartitionDefinition, SERIES_PARTITION_VERSION,
     };
 
     // TODO: this test maybe not reasonable to place here.
@@ -138,6 +138,44 @@ mod tests {
         assert!(partitions.is_empty());
     }
 
+    #[test]
+    fn test_locate_series_partitions_for_read() {
+        let schema = build_schema();
+        let partition_num = 16;
+        let series_partition = KeyPartitionInfo {
+            version: SERIES_PARTITION_VERSION,
+            definitions: vec![PartitionDefinition::default(); partition_num],
+            partition_key: vec!["col1".to_string(), "col2".to_string(), "col3".to_string()],
+            linear: false,
+        };
+        let series_rule_adapter =
+            DfPartitionRuleAdapter::new(PartitionInfo::Key(series_partition), &schema).unwrap();
+
+        // The query of a single series is pruned to one partition.
+        let filters = vec![
+            col("col1").eq(lit(1_i32)),
+            col("col2").eq(lit("test".to_string())),
+            col("col3").eq(lit(42_u64)),
+        ];
+        let partitions = series_rule_adapter
+            .locate_partitions_for_read(&filters)
+            .unwrap();
+        let partition_keys = [
+            Datum::Int32(1),
+            Datum::String(StringBytes::from("test")),
+            Datum::UInt64(42),
+        ];
+        let expected =
+            compute_consistent_partition(partition_keys.iter().map(Datum::as_view), partition_num);
+        assert_eq!(partitions, vec![expected]);
+
+        // The query without the whole series key reads all the partitions.
+        let partitions = series_rule_adapter
+            .locate_partitions_for_read(&filters[..2])
+            .unwrap();
+        assert_eq!(partitions, (0..partition_num).collect::<Vec<_>>());
+    }
+
     // TODO: this test maybe not reasonable to place here.
     #[test]
     fn test_locate_partitions_for_read_invalid() {
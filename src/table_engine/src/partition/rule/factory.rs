@@ -27,7 +27,7 @@ use crate::partition::{
         PartitionRulePtr,
     },
     BuildPartitionRule, InvalidPartitionKey, KeyPartitionInfo, PartitionInfo, RandomPartitionInfo,
-    Result,
+    Result, SERIES_PARTITION_VERSION,
 };
 
 pub struct PartitionRuleFactory;
@@ -45,12 +45,13 @@ impl PartitionRuleFactory {
     }
 
     fn create_key_rule(key_info: KeyPartitionInfo, schema: &Schema) -> Result<PartitionRulePtr> {
+        let supported_versions = [DEFAULT_PARTITION_VERSION, SERIES_PARTITION_VERSION];
         ensure!(
-            key_info.version == DEFAULT_PARTITION_VERSION,
+            supported_versions.contains(&key_info.version),
             BuildPartitionRule {
                 msg: format!(
                     "only support key partition info version:{:?}, input_version:{}",
-                    DEFAULT_PARTITION_VERSION, key_info.version
+                    supported_versions, key_info.version
                 )
             }
         );
@@ -60,10 +61,10 @@ impl PartitionRuleFactory {
             .all(|col| schema.column_with_name(col.as_str()).is_some());
         ensure!(valid_partition_key, InvalidPartitionKey);
 
-        Ok(Box::new(KeyRule::new(
-            key_info.definitions.len(),
-            key_info.partition_key,
-        )))
+        Ok(Box::new(
+            KeyRule::new(key_info.definitions.len(), key_info.partition_key)
+                .with_consistent_hash(key_info.is_series()),
+        ))
     }
 
     fn create_random_rule(random_info: RandomPartitionInfo) -> Result<PartitionRulePtr> {
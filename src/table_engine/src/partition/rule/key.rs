@@ -41,6 +41,7 @@ pub struct KeyRule {
     columns: Vec<String>,
     name_to_idx: HashMap<String, usize>,
     partition_num: usize,
+    consistent_hash: bool,
 }
 
 impl KeyRule {
@@ -54,8 +55,16 @@ impl KeyRule {
             columns,
             name_to_idx,
             partition_num,
+            consistent_hash: false,
         }
     }
+
+    /// Locate the partitions by the jump consistent hash of the key, see
+    /// [crate::partition::SERIES_PARTITION_VERSION].
+    pub fn with_consistent_hash(mut self, consistent_hash: bool) -> Self {
+        self.consistent_hash = consistent_hash;
+        self
+    }
 }
 
 impl KeyRule {
@@ -127,12 +136,17 @@ impl KeyRule {
     fn compute_partition_for_inserted_row(
         row: &Row,
         partition_num: usize,
+        consistent_hash: bool,
         target_column_idxs: &[usize],
     ) -> usize {
         let partition_keys = target_column_idxs
             .iter()
             .map(|col_idx| row[*col_idx].as_view());
-        compute_partition(partition_keys, partition_num)
+        if consistent_hash {
+            compute_consistent_partition(partition_keys, partition_num)
+        } else {
+            compute_partition(partition_keys, partition_num)
+        }
     }
 
     fn compute_partition_for_keys_group(
@@ -143,7 +157,11 @@ impl KeyRule {
         let mut partitions = BTreeSet::new();
         let expanded_group = expand_partition_keys_group(group, filters)?;
         for partition_keys in expanded_group {
-            let partition = compute_partition(partition_keys.into_iter(), self.partition_num);
+            let partition = if self.consistent_hash {
+                compute_consistent_partition(partition_keys.into_iter(), self.partition_num)
+            } else {
+                compute_partition(partition_keys.into_iter(), self.partition_num)
+            };
             partitions.insert(partition);
         }
 
@@ -177,10 +195,12 @@ impl PartitionRule for KeyRule {
 
         // Compute partitions.
         let partition_num = self.partition_num;
+        let consistent_hash = self.consistent_hash;
         let iter = row_group.into_iter().map(move |row| {
             let partition_id = Self::compute_partition_for_inserted_row(
                 &row,
                 partition_num,
+                consistent_hash,
                 &column_index_in_schema,
             );
             PartitionedRow { partition_id, row }
@@ -372,6 +392,29 @@ pub(crate) fn compute_partition<'a>(
     (hash64(reader) as usize) % partition_num
 }
 
+// Compute partition by the jump consistent hash.
+pub(crate) fn compute_consistent_partition<'a>(
+    partition_keys: impl Iterator<Item = DatumView<'a>>,
+    partition_num: usize,
+) -> usize {
+    let reader = PartitionKeysReadAdapter::new(partition_keys);
+    jump_consistent_hash(hash64(reader), partition_num)
+}
+
+/// The jump consistent hash from the paper "A Fast, Minimal Memory, Consistent
+/// Hash Algorithm" by Lamping and Veach.
+fn jump_consistent_hash(mut key: u64, num_buckets: usize) -> usize {
+    let mut bucket: i64 = -1;
+    let mut next: i64 = 0;
+    while next < num_buckets as i64 {
+        bucket = next;
+        key = key.wrapping_mul(2862933555777941757).wrapping_add(1);
+        next = ((bucket + 1) as f64 * ((1_u64 << 31) as f64 / ((key >> 33) + 1) as f64)) as i64;
+    }
+
+    bucket as usize
+}
+
 #[cfg(test)]
 mod tests {
     use std::{collections::BTreeSet, io::Read};
@@ -430,7 +473,7 @@ mod tests {
         let defined_idxs = vec![1, 2, 3, 4];
 
         let partition_id =
-            KeyRule::compute_partition_for_inserted_row(&row, partition_num, &defined_idxs);
+            KeyRule::compute_partition_for_inserted_row(&row, partition_num, false, &defined_idxs);
         assert_eq!(partition_id, 104979);
     }
 
@@ -450,10 +493,32 @@ mod tests {
 
         // Actual
         let partition_id =
-            KeyRule::compute_partition_for_inserted_row(&row, partition_num, &defined_idxs);
+            KeyRule::compute_partition_for_inserted_row(&row, partition_num, false, &defined_idxs);
         assert_eq!(partition_id, 6);
     }
 
+    #[test]
+    fn test_jump_consistent_hash() {
+        for key in [0, 1, 42, u64::MAX] {
+            assert_eq!(jump_consistent_hash(key, 1), 0);
+        }
+
+        // A key is either kept or moved to the new bucket once a bucket is added.
+        let mut moved = 0;
+        for key in 0..1000_u64 {
+            let key = key.wrapping_mul(0x9E3779B97F4A7C15);
+            let before = jump_consistent_hash(key, 8);
+            let after = jump_consistent_hash(key, 9);
+            assert!(before < 8);
+            if before != after {
+                assert_eq!(after, 8);
+                moved += 1;
+            }
+        }
+        // About 1/9 of the keys are moved.
+        assert!(moved > 50 && moved < 200, "moved:{moved}");
+    }
+
     #[test]
     fn test_get_candidate_partition_keys_groups() {
         // Key rule of keys:[col1, col2, col3]
@@ -22,7 +22,7 @@ use std::{fmt, sync::Arc};
 
 use common_types::{
     schema::Schema,
-    time::{TimeRange, Timestamp},
+    time::{TimeRange, Timestamp, TimestampPrecision},
 };
 use datafusion::{
     logical_expr::{
@@ -194,6 +194,15 @@ impl PredicateBuilder {
         self
     }
 
+    /// Convert the time range (in millis) into the range of the values in the
+    /// timestamp column stored in `precision`.
+    pub fn convert_time_range(mut self, precision: TimestampPrecision) -> Self {
+        self.time_range = self
+            .time_range
+            .map(|time_range| precision.range_from_millis(time_range));
+        self
+    }
+
     pub fn build(self) -> PredicateRef {
         Arc::new(Predicate {
             exprs: self.exprs,
@@ -25,10 +25,18 @@ use std::{
     time::{Duration, Instant},
 };
 
-use arrow::datatypes::SchemaRef;
+use arrow::{
+    array::{Array, TimestampMillisecondArray},
+    datatypes::{SchemaRef, TimestampMillisecondType},
+    record_batch::RecordBatch as ArrowRecordBatch,
+};
 use async_trait::async_trait;
-use common_types::{projected_schema::ProjectedSchema, request_id::RequestId, schema::Schema};
+use common_types::{
+    projected_schema::ProjectedSchema, request_id::RequestId, schema::Schema,
+    time::TimestampPrecision,
+};
 use datafusion::{
+    common::stats::Precision,
     config::{ConfigEntry, ConfigExtension, ExtensionOptions},
     datasource::TableProvider,
     error::{DataFusionError, Result},
@@ -39,11 +47,13 @@ use datafusion::{
         expressions,
         metrics::{Count, MetricValue, MetricsSet},
         projection::ProjectionExec,
+        stream::RecordBatchStreamAdapter,
         DisplayAs, DisplayFormatType, ExecutionPlan, Metric, Partitioning, PhysicalExpr,
         SendableRecordBatchStream as DfSendableRecordBatchStream, Statistics,
     },
 };
 use df_operator::visitor;
+use futures::StreamExt;
 use logger::debug;
 use runtime::Priority;
 use trace_metric::{collector::FormatCollectorVisitor, MetricsCollector};
@@ -51,7 +61,7 @@ use trace_metric::{collector::FormatCollectorVisitor, MetricsCollector};
 use crate::{
     predicate::{PredicateBuilder, PredicateRef},
     stream::{ScanStreamState, ToDfStream},
-    table::{ReadOptions, ReadRequest, TableRef},
+    table::{QueryStatsRef, ReadOptions, ReadOrder, ReadRequest, ReadSnapshotRef, TableRef},
 };
 
 pub const SCAN_TABLE_METRICS_COLLECTOR_NAME: &str = "scan_table";
@@ -63,6 +73,15 @@ pub struct HoraeDBOptions {
     pub default_schema: String,
     pub default_catalog: String,
     pub priority: Priority,
+    /// Snapshot shared by the reads of several queries.
+    pub read_snapshot: Option<ReadSnapshotRef>,
+    /// Statistics of the query.
+    pub query_stats: Option<QueryStatsRef>,
+    /// The data read by the query won't be filled into the caches.
+    pub no_cache: bool,
+    /// The first rows needed by the scan in the time order, only set for the
+    /// scan of [TimeOrderedTableProvider].
+    pub time_ordered_limit: Option<(ReadOrder, usize)>,
 }
 
 impl ConfigExtension for HoraeDBOptions {
@@ -185,6 +204,10 @@ pub struct TableProviderAdapter<B> {
     /// query
     current_table_schema: Schema,
 
+    /// Precision of the timestamp column, the filters of the queries compare
+    /// it in millis.
+    timestamp_precision: TimestampPrecision,
+
     /// Table scan builder
     builder: B,
 }
@@ -193,10 +216,12 @@ impl<B: TableScanBuilder> TableProviderAdapter<B> {
     pub fn new(table: TableRef, builder: B) -> Self {
         // Take a snapshot of the schema
         let current_table_schema = table.schema();
+        let timestamp_precision = table.timestamp_precision();
 
         Self {
             table,
             current_table_schema,
+            timestamp_precision,
             builder,
         }
     }
@@ -284,10 +309,19 @@ impl<B: TableScanBuilder> TableProviderAdapter<B> {
         } else {
             None
         };
+        let (order, limit) = match options.time_ordered_limit {
+            Some((order, limit)) => (order, Some(limit)),
+            None => (ReadOrder::None, limit),
+        };
         let opts = ReadOptions {
             deadline,
             read_parallelism,
             batch_size: state.config_options().execution.batch_size,
+            snapshot: options.read_snapshot.clone(),
+            stats: options.query_stats.clone(),
+            no_cache: options.no_cache,
+            limit,
+            order,
         };
 
         // TODO: metrics collector name should relate to detail scan impl?
@@ -312,36 +346,40 @@ impl<B: TableScanBuilder> TableProviderAdapter<B> {
     fn check_and_build_predicate_from_filters(&self, filters: &[Expr]) -> PredicateRef {
         let pushdown_filters = filters
             .iter()
-            .filter_map(|filter| {
-                let filter_cols = visitor::find_columns_by_expr(filter);
-
-                let support_pushdown = self
-                    .table
-                    .support_pushdown(&self.current_table_schema, &filter_cols);
-                if support_pushdown {
-                    Some(filter.clone())
-                } else {
-                    None
-                }
-            })
+            .filter(|filter| self.support_pushdown(filter))
+            .cloned()
             .collect::<Vec<_>>();
 
+        // The time range is exactly converted into the precision, while the filters
+        // on the timestamp column aren't pushed down.
         PredicateBuilder::default()
             .add_pushdown_exprs(&pushdown_filters)
             .extract_time_range(&self.current_table_schema, filters)
+            .convert_time_range(self.timestamp_precision)
             .build()
     }
 
+    fn support_pushdown(&self, filter: &Expr) -> bool {
+        let filter_cols = visitor::find_columns_by_expr(filter);
+
+        // The timestamps are compared in millis by the filters, but stored in the
+        // precision of the table.
+        if self.timestamp_precision != TimestampPrecision::Millisecond {
+            let timestamp_name = self.current_table_schema.timestamp_name();
+            if filter_cols.iter().any(|col| col.as_str() == timestamp_name) {
+                return false;
+            }
+        }
+
+        self.table
+            .support_pushdown(&self.current_table_schema, &filter_cols)
+    }
+
     fn pushdown_inner(&self, filters: &[&Expr]) -> Vec<TableProviderFilterPushDown> {
         filters
             .iter()
             .map(|filter| {
-                let filter_cols = visitor::find_columns_by_expr(filter);
-
-                let support_pushdown = self
-                    .table
-                    .support_pushdown(&self.current_table_schema, &filter_cols);
-                if support_pushdown {
+                if self.support_pushdown(filter) {
                     TableProviderFilterPushDown::Exact
                 } else {
                     TableProviderFilterPushDown::Inexact
@@ -410,11 +448,93 @@ impl<B: TableScanBuilder> TableSource for TableProviderAdapter<B> {
     }
 }
 
+/// [TableProvider] scanning the first `limit` rows of the inner table in the
+/// time `order`, see [ReadOptions::order].
+pub struct TimeOrderedTableProvider {
+    inner: Arc<dyn TableProvider>,
+    order: ReadOrder,
+    limit: usize,
+}
+
+impl TimeOrderedTableProvider {
+    pub fn new(inner: Arc<dyn TableProvider>, order: ReadOrder, limit: usize) -> Self {
+        Self {
+            inner,
+            order,
+            limit,
+        }
+    }
+
+    pub fn order(&self) -> ReadOrder {
+        self.order
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+}
+
+impl fmt::Debug for TimeOrderedTableProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TimeOrderedTableProvider")
+            .field("order", &self.order)
+            .field("limit", &self.limit)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl TableProvider for TimeOrderedTableProvider {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.inner.schema()
+    }
+
+    async fn scan(
+        &self,
+        state: &SessionState,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        // The order only applies to the scan of the inner table.
+        let mut state = state.clone();
+        if let Some(options) = state
+            .config_mut()
+            .options_mut()
+            .extensions
+            .get_mut::<HoraeDBOptions>()
+        {
+            options.time_ordered_limit = Some((self.order, self.limit));
+        }
+
+        self.inner.scan(&state, projection, filters, limit).await
+    }
+
+    fn supports_filters_pushdown(
+        &self,
+        filters: &[&Expr],
+    ) -> Result<Vec<TableProviderFilterPushDown>> {
+        self.inner.supports_filters_pushdown(filters)
+    }
+
+    fn table_type(&self) -> TableType {
+        self.inner.table_type()
+    }
+}
+
 /// Physical plan of scanning table.
+///
+/// The timestamps stored in the precision other than millis are converted into
+/// millis, which the queries and the time functions work in.
 pub struct ScanTable {
     table: TableRef,
     request: ReadRequest,
     stream_state: Mutex<ScanStreamState>,
+    timestamp_precision: TimestampPrecision,
 
     // FIXME: in origin partitioned table scan need to modify the parallelism when initializing
     // stream...
@@ -424,10 +544,12 @@ pub struct ScanTable {
 impl ScanTable {
     pub fn new(table: TableRef, request: ReadRequest) -> Self {
         let parallelism = request.opts.read_parallelism;
+        let timestamp_precision = table.timestamp_precision();
         Self {
             table,
             request,
             stream_state: Mutex::new(ScanStreamState::default()),
+            timestamp_precision,
             parallelism,
         }
     }
@@ -495,8 +617,30 @@ impl ExecutionPlan for ScanTable {
         }
 
         let stream = stream_state.take_stream(partition)?;
-
-        Ok(Box::pin(ToDfStream(stream)))
+        let stream = ToDfStream(stream);
+
+        let precision = self.timestamp_precision;
+        let timestamp_idx = self
+            .schema()
+            .index_of(
+                self.request
+                    .projected_schema
+                    .table_schema()
+                    .timestamp_name(),
+            )
+            .ok();
+        match timestamp_idx {
+            Some(idx) if precision != TimestampPrecision::Millisecond => {
+                let stream = stream.map(move |batch| {
+                    batch.and_then(|batch| normalize_timestamp_column(batch, idx, precision))
+                });
+                Ok(Box::pin(RecordBatchStreamAdapter::new(
+                    self.schema(),
+                    stream,
+                )))
+            }
+            _ => Ok(Box::pin(stream)),
+        }
     }
 
     fn metrics(&self) -> Option<MetricsSet> {
@@ -521,8 +665,13 @@ impl ExecutionPlan for ScanTable {
         &self,
     ) -> std::result::Result<datafusion::common::Statistics, datafusion::error::DataFusionError>
     {
-        // TODO(yingwen): Implement this
-        Ok(Statistics::new_unknown(&self.schema()))
+        let mut statistics = Statistics::new_unknown(&self.schema());
+        // The estimate of the table is stale anyway, so it's inexact.
+        if let Some(num_rows) = self.table.stats().num_rows {
+            statistics.num_rows = Precision::Inexact(num_rows as usize);
+        }
+
+        Ok(statistics)
     }
 }
 
@@ -550,6 +699,35 @@ impl fmt::Debug for ScanTable {
     }
 }
 
+/// Convert the values of the timestamp column at `idx` from the `precision`
+/// into millis, the sub-millisecond part is floored.
+fn normalize_timestamp_column(
+    batch: ArrowRecordBatch,
+    idx: usize,
+    precision: TimestampPrecision,
+) -> Result<ArrowRecordBatch> {
+    let column = batch
+        .column(idx)
+        .as_any()
+        .downcast_ref::<TimestampMillisecondArray>()
+        .ok_or_else(|| {
+            DataFusionError::Internal(format!(
+                "Invalid timestamp column, data_type:{}",
+                batch.column(idx).data_type()
+            ))
+        })?;
+    // The values out of the range of millis are rejected by the writes.
+    let normalized = column.unary::<_, TimestampMillisecondType>(|v| {
+        precision
+            .checked_to_millis(v)
+            .unwrap_or(if v < 0 { i64::MIN } else { i64::MAX })
+    });
+
+    let mut columns = batch.columns().to_vec();
+    columns[idx] = Arc::new(normalized);
+    Ok(ArrowRecordBatch::try_new(batch.schema(), columns)?)
+}
+
 fn collect_projection_from_expr(exprs: &[Expr], schema: &Schema) -> HashSet<usize> {
     let mut projections = HashSet::new();
     exprs.iter().for_each(|expr| {
@@ -562,3 +740,86 @@ fn collect_projection_from_expr(exprs: &[Expr], schema: &Schema) -> HashSet<usiz
 
     projections
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use arrow::{
+        array::Int32Array,
+        datatypes::{DataType, Field, Schema as ArrowSchema, TimeUnit},
+    };
+    use common_types::{tests::build_schema, time::TimeRange, TIMESTAMP_PRECISION};
+    use datafusion::{prelude::col, scalar::ScalarValue};
+
+    use super::*;
+    use crate::{memory::MemoryTable, table::TableId};
+
+    #[test]
+    fn test_predicate_in_timestamp_precision() {
+        let table: TableRef = Arc::new(
+            MemoryTable::new(
+                "test_table".to_string(),
+                TableId::from(100),
+                build_schema(),
+                "memory".to_string(),
+            )
+            .with_options(HashMap::from([(
+                TIMESTAMP_PRECISION.to_string(),
+                "us".to_string(),
+            )])),
+        );
+        let adapter = TableProviderAdapter::new(table.clone(), NormalTableScanBuilder::new(table));
+
+        let timestamp = |v| Expr::Literal(ScalarValue::TimestampMillisecond(Some(v), None));
+        let filters = [
+            col("key2").gt_eq(timestamp(1)),
+            col("key2").lt(timestamp(10)),
+        ];
+        let predicate = adapter.check_and_build_predicate_from_filters(&filters);
+        assert_eq!(
+            TimeRange::new_unchecked_for_test(1000, 10000),
+            predicate.time_range()
+        );
+        assert!(predicate.exprs().is_empty());
+
+        // The filters on the timestamp column are checked again on the values read in
+        // millis.
+        let filters = filters.iter().collect::<Vec<_>>();
+        assert!(adapter
+            .pushdown_inner(&filters)
+            .iter()
+            .all(|v| *v == TableProviderFilterPushDown::Inexact));
+    }
+
+    #[test]
+    fn test_normalize_timestamp_column() {
+        let schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("v", DataType::Int32, false),
+            Field::new("ts", DataType::Timestamp(TimeUnit::Millisecond, None), true),
+        ]));
+        let build_batch = |values: Vec<Option<i64>>| {
+            ArrowRecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Int32Array::from(vec![1; values.len()])),
+                    Arc::new(TimestampMillisecondArray::from(values)),
+                ],
+            )
+            .unwrap()
+        };
+
+        let batch = build_batch(vec![Some(1_500_999_999), None, Some(-1)]);
+        let normalized =
+            normalize_timestamp_column(batch, 1, TimestampPrecision::Nanosecond).unwrap();
+        assert_eq!(build_batch(vec![Some(1500), None, Some(-1)]), normalized);
+
+        let batch = build_batch(vec![Some(2), Some(-2)]);
+        let normalized = normalize_timestamp_column(batch, 1, TimestampPrecision::Second).unwrap();
+        assert_eq!(build_batch(vec![Some(2000), Some(-2000)]), normalized);
+
+        // Not a timestamp column.
+        let batch = build_batch(vec![Some(2)]);
+        assert!(normalize_timestamp_column(batch, 0, TimestampPrecision::Second).is_err());
+    }
+}
@@ -18,16 +18,19 @@
 //! Table engine proxy
 
 use async_trait::async_trait;
+use common_types::table::ShardId;
+use snafu::OptionExt;
 
 use crate::{
     engine::{
-        CloseShardRequest, CloseTableRequest, CreateTableParams, CreateTableRequest,
-        DropTableRequest, OpenShardRequest, OpenShardResult, OpenTableRequest, TableEngine,
-        TableEngineRef, UnknownEngineType,
+        CloseShardRequest, CloseTableRequest, ClosedTable, CreateTableParams, CreateTableRequest,
+        DropTableRequest, FindTableRequest, OpenShardCheckReport, OpenShardRequest,
+        OpenShardResult, OpenTableRequest, ScanWalRequest, ScanWalResult, TableEngine,
+        TableEngineRef, UnknownEngineType, WriteTablesRequest,
     },
     memory::MemoryTableEngine,
     table::TableRef,
-    ANALYTIC_ENGINE_TYPE, MEMORY_ENGINE_TYPE,
+    ANALYTIC_ENGINE_TYPE, EXTERNAL_ENGINE_TYPE, MEMORY_ENGINE_TYPE, UNION_ENGINE_TYPE,
 };
 
 /// Route [CreateTableRequest] to the correct engine by its engine type
@@ -36,6 +39,24 @@ pub struct TableEngineProxy {
     pub memory: MemoryTableEngine,
     /// Analytic table engine
     pub analytic: TableEngineRef,
+    /// External table engine, external tables are not supported if not set
+    pub external: Option<TableEngineRef>,
+    /// Union table engine, union tables are not supported if not set
+    pub union: Option<TableEngineRef>,
+}
+
+impl TableEngineProxy {
+    fn external(&self) -> crate::engine::Result<&TableEngineRef> {
+        self.external.as_ref().context(UnknownEngineType {
+            engine_type: EXTERNAL_ENGINE_TYPE,
+        })
+    }
+
+    fn union(&self) -> crate::engine::Result<&TableEngineRef> {
+        self.union.as_ref().context(UnknownEngineType {
+            engine_type: UNION_ENGINE_TYPE,
+        })
+    }
 }
 
 #[async_trait]
@@ -47,6 +68,12 @@ impl TableEngine for TableEngineProxy {
     async fn close(&self) -> crate::engine::Result<()> {
         self.memory.close().await?;
         self.analytic.close().await?;
+        if let Some(external) = &self.external {
+            external.close().await?;
+        }
+        if let Some(union) = &self.union {
+            union.close().await?;
+        }
 
         Ok(())
     }
@@ -55,6 +82,8 @@ impl TableEngine for TableEngineProxy {
         match params.engine.as_str() {
             MEMORY_ENGINE_TYPE => self.memory.validate_create_table(params).await,
             ANALYTIC_ENGINE_TYPE => self.analytic.validate_create_table(params).await,
+            EXTERNAL_ENGINE_TYPE => self.external()?.validate_create_table(params).await,
+            UNION_ENGINE_TYPE => self.union()?.validate_create_table(params).await,
             engine_type => UnknownEngineType { engine_type }.fail(),
         }
     }
@@ -63,6 +92,8 @@ impl TableEngine for TableEngineProxy {
         match request.params.engine.as_str() {
             MEMORY_ENGINE_TYPE => self.memory.create_table(request).await,
             ANALYTIC_ENGINE_TYPE => self.analytic.create_table(request).await,
+            EXTERNAL_ENGINE_TYPE => self.external()?.create_table(request).await,
+            UNION_ENGINE_TYPE => self.union()?.create_table(request).await,
             engine_type => UnknownEngineType { engine_type }.fail(),
         }
     }
@@ -71,6 +102,8 @@ impl TableEngine for TableEngineProxy {
         match request.engine.as_str() {
             MEMORY_ENGINE_TYPE => self.memory.drop_table(request).await,
             ANALYTIC_ENGINE_TYPE => self.analytic.drop_table(request).await,
+            EXTERNAL_ENGINE_TYPE => self.external()?.drop_table(request).await,
+            UNION_ENGINE_TYPE => self.union()?.drop_table(request).await,
             engine_type => UnknownEngineType { engine_type }.fail(),
         }
     }
@@ -83,6 +116,8 @@ impl TableEngine for TableEngineProxy {
         match request.engine.as_str() {
             MEMORY_ENGINE_TYPE => self.memory.open_table(request).await,
             ANALYTIC_ENGINE_TYPE => self.analytic.open_table(request).await,
+            EXTERNAL_ENGINE_TYPE => self.external()?.open_table(request).await,
+            UNION_ENGINE_TYPE => self.union()?.open_table(request).await,
             engine_type => UnknownEngineType { engine_type }.fail(),
         }
     }
@@ -92,6 +127,8 @@ impl TableEngine for TableEngineProxy {
         match request.engine.as_str() {
             MEMORY_ENGINE_TYPE => self.memory.close_table(request).await,
             ANALYTIC_ENGINE_TYPE => self.analytic.close_table(request).await,
+            EXTERNAL_ENGINE_TYPE => self.external()?.close_table(request).await,
+            UNION_ENGINE_TYPE => self.union()?.close_table(request).await,
             engine_type => UnknownEngineType { engine_type }.fail(),
         }
     }
@@ -103,16 +140,89 @@ impl TableEngine for TableEngineProxy {
         match request.engine.as_str() {
             MEMORY_ENGINE_TYPE => self.memory.open_shard(request).await,
             ANALYTIC_ENGINE_TYPE => self.analytic.open_shard(request).await,
+            EXTERNAL_ENGINE_TYPE => self.external()?.open_shard(request).await,
+            UNION_ENGINE_TYPE => self.union()?.open_shard(request).await,
             engine_type => UnknownEngineType { engine_type }.fail(),
         }
     }
 
     /// Close tables on same shard.
-    async fn close_shard(&self, request: CloseShardRequest) -> Vec<crate::engine::Result<String>> {
+    async fn close_shard(
+        &self,
+        request: CloseShardRequest,
+    ) -> Vec<crate::engine::Result<ClosedTable>> {
         match request.engine.as_str() {
             MEMORY_ENGINE_TYPE => self.memory.close_shard(request).await,
             ANALYTIC_ENGINE_TYPE => self.analytic.close_shard(request).await,
+            EXTERNAL_ENGINE_TYPE => match self.external() {
+                Ok(external) => external.close_shard(request).await,
+                Err(e) => vec![Err(e)],
+            },
+            UNION_ENGINE_TYPE => match self.union() {
+                Ok(union) => union.close_shard(request).await,
+                Err(e) => vec![Err(e)],
+            },
             engine_type => vec![UnknownEngineType { engine_type }.fail()],
         }
     }
+
+    async fn write_tables(&self, request: WriteTablesRequest) -> crate::engine::Result<Vec<usize>> {
+        match request.engine.as_str() {
+            MEMORY_ENGINE_TYPE => self.memory.write_tables(request).await,
+            ANALYTIC_ENGINE_TYPE => self.analytic.write_tables(request).await,
+            EXTERNAL_ENGINE_TYPE => self.external()?.write_tables(request).await,
+            UNION_ENGINE_TYPE => self.union()?.write_tables(request).await,
+            engine_type => UnknownEngineType { engine_type }.fail(),
+        }
+    }
+
+    /// Only the analytic engine persists writes of shards, so the epochs are
+    /// only forwarded to it.
+    fn install_shard_write_epoch(&self, shard_id: ShardId, epoch: u64) {
+        self.analytic.install_shard_write_epoch(shard_id, epoch)
+    }
+
+    fn fence_shard_writes(&self, shard_id: ShardId) {
+        self.analytic.fence_shard_writes(shard_id)
+    }
+
+    fn write_pressure(&self) -> f64 {
+        self.analytic.write_pressure()
+    }
+
+    async fn find_table(
+        &self,
+        request: FindTableRequest,
+    ) -> crate::engine::Result<Option<TableRef>> {
+        match request.engine.as_str() {
+            MEMORY_ENGINE_TYPE => self.memory.find_table(request).await,
+            ANALYTIC_ENGINE_TYPE => self.analytic.find_table(request).await,
+            EXTERNAL_ENGINE_TYPE => self.external()?.find_table(request).await,
+            UNION_ENGINE_TYPE => self.union()?.find_table(request).await,
+            engine_type => UnknownEngineType { engine_type }.fail(),
+        }
+    }
+
+    async fn check_open_shard(
+        &self,
+        request: OpenShardRequest,
+    ) -> crate::engine::Result<OpenShardCheckReport> {
+        match request.engine.as_str() {
+            MEMORY_ENGINE_TYPE => self.memory.check_open_shard(request).await,
+            ANALYTIC_ENGINE_TYPE => self.analytic.check_open_shard(request).await,
+            EXTERNAL_ENGINE_TYPE => self.external()?.check_open_shard(request).await,
+            UNION_ENGINE_TYPE => self.union()?.check_open_shard(request).await,
+            engine_type => UnknownEngineType { engine_type }.fail(),
+        }
+    }
+
+    async fn scan_wal(&self, request: ScanWalRequest) -> crate::engine::Result<ScanWalResult> {
+        match request.engine.as_str() {
+            MEMORY_ENGINE_TYPE => self.memory.scan_wal(request).await,
+            ANALYTIC_ENGINE_TYPE => self.analytic.scan_wal(request).await,
+            EXTERNAL_ENGINE_TYPE => self.external()?.scan_wal(request).await,
+            UNION_ENGINE_TYPE => self.union()?.scan_wal(request).await,
+            engine_type => UnknownEngineType { engine_type }.fail(),
+        }
+    }
 }
@@ -18,11 +18,12 @@
 //! Table abstraction
 
 use std::{
+    any::Any,
     collections::HashMap,
     fmt,
     sync::{
         atomic::{AtomicU32, AtomicU64, Ordering},
-        Arc,
+        Arc, Mutex,
     },
     time::{Duration, Instant},
 };
@@ -35,6 +36,8 @@ use common_types::{
     request_id::RequestId,
     row::{Row, RowGroup},
     schema::{RecordSchemaWithKey, Schema, Version},
+    time::{TimeRange, Timestamp, TimestampPrecision},
+    TIMESTAMP_PRECISION,
 };
 use generic_error::{BoxError, GenericError};
 use horaedbproto::sys_catalog as sys_catalog_pb;
@@ -122,6 +125,15 @@ pub enum Error {
     #[snafu(display("Failed to compact table, table:{}, err:{}", table, source))]
     Compact { table: String, source: GenericError },
 
+    #[snafu(display("Failed to delete data of table, table:{}, err:{}", table, source))]
+    DeleteData { table: String, source: GenericError },
+
+    #[snafu(display("Failed to check existence of rows, table:{}, err:{}", table, source))]
+    CheckExistence { table: String, source: GenericError },
+
+    #[snafu(display("Failed to reopen table, table:{}, err:{}", table, source))]
+    Reopen { table: String, source: GenericError },
+
     #[snafu(display("Failed to convert read request to pb, msg:{}, err:{}", msg, source))]
     ReadRequestToPb { msg: String, source: GenericError },
 
@@ -319,6 +331,79 @@ pub struct WriteRequest {
     pub row_group: RowGroup,
 }
 
+/// Snapshot shared by the reads of several queries.
+///
+/// The version of a table is pinned by its first read under the snapshot, and
+/// the later reads of the table see the same data. What is pinned is decided
+/// by the table engine.
+#[derive(Default)]
+pub struct ReadSnapshot {
+    pinned: Mutex<HashMap<TableId, Arc<dyn Any + Send + Sync>>>,
+}
+
+impl ReadSnapshot {
+    /// Returns the version of the table pinned by this snapshot, and `pin` is
+    /// called to pin the version if the table is not read yet.
+    ///
+    /// Returns None if the pinned version is not of type `T`.
+    pub fn get_or_pin<T, F>(&self, table_id: TableId, pin: F) -> Option<Arc<T>>
+    where
+        T: Any + Send + Sync,
+        F: FnOnce() -> T,
+    {
+        let pinned = {
+            let mut pinned = self.pinned.lock().unwrap();
+            pinned
+                .entry(table_id)
+                .or_insert_with(|| Arc::new(pin()))
+                .clone()
+        };
+
+        pinned.downcast::<T>().ok()
+    }
+}
+
+impl fmt::Debug for ReadSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pinned = self.pinned.lock().unwrap();
+        f.debug_struct("ReadSnapshot")
+            .field("pinned_tables", &pinned.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+pub type ReadSnapshotRef = Arc<ReadSnapshot>;
+
+/// Statistics of the reads of a query, which are updated during the reads.
+#[derive(Debug, Default)]
+pub struct QueryStats {
+    scanned_bytes: AtomicU64,
+}
+
+impl QueryStats {
+    #[inline]
+    pub fn add_scanned_bytes(&self, num_bytes: u64) {
+        self.scanned_bytes.fetch_add(num_bytes, Ordering::Relaxed);
+    }
+
+    /// Bytes fetched from the underlying storage.
+    #[inline]
+    pub fn scanned_bytes(&self) -> u64 {
+        self.scanned_bytes.load(Ordering::Relaxed)
+    }
+}
+
+pub type QueryStatsRef = Arc<QueryStats>;
+
+/// Order of the timestamp of the rows needed by the query.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReadOrder {
+    #[default]
+    None,
+    Asc,
+    Desc,
+}
+
 #[derive(Clone, Debug)]
 pub struct ReadOptions {
     pub batch_size: usize,
@@ -327,6 +412,21 @@ pub struct ReadOptions {
     pub read_parallelism: usize,
     /// Request deadline
     pub deadline: Option<Instant>,
+    /// Snapshot shared with the reads of other queries, the table is read at
+    /// its latest version if not set.
+    pub snapshot: Option<ReadSnapshotRef>,
+    /// Statistics of the query issuing the read.
+    pub stats: Option<QueryStatsRef>,
+    /// The data read won't be filled into the caches.
+    pub no_cache: bool,
+    /// Max number of rows needed by the query, the read can stop once this
+    /// number of rows are produced.
+    pub limit: Option<usize>,
+    /// The query needs the first `limit` rows in this order, so the data is
+    /// read by the time segments in this order, and the read can stop once
+    /// the rows read are enough. The rows of a segment are still not sorted by
+    /// the timestamp.
+    pub order: ReadOrder,
 }
 
 impl Default for ReadOptions {
@@ -335,6 +435,11 @@ impl Default for ReadOptions {
             batch_size: 10000,
             read_parallelism: DEFAULT_READ_PARALLELISM,
             deadline: None,
+            snapshot: None,
+            stats: None,
+            no_cache: false,
+            limit: None,
+            order: ReadOrder::None,
         }
     }
 }
@@ -349,6 +454,12 @@ impl From<horaedbproto::remote_engine::ReadOptions> for ReadOptions {
             } else {
                 Some(Instant::now() + Duration::from_millis(pb.timeout_ms as u64))
             },
+            // The snapshot is only shared by the local reads.
+            snapshot: None,
+            stats: None,
+            no_cache: false,
+            limit: None,
+            order: ReadOrder::None,
         }
     }
 }
@@ -378,6 +489,15 @@ pub struct GetRequest {
     pub primary_key: Vec<Datum>,
 }
 
+/// Request to check whether the rows matching the predicate exist.
+#[derive(Debug)]
+pub struct ExistsRequest {
+    /// Request id.
+    pub request_id: RequestId,
+    /// The time range and filters of the rows to check.
+    pub predicate: PredicateRef,
+}
+
 #[derive(Clone)]
 pub struct ReadRequest {
     /// Read request id.
@@ -522,6 +642,17 @@ pub trait Table: std::fmt::Debug {
     /// Options of this table.
     fn options(&self) -> HashMap<String, String>;
 
+    /// Precision of the values stored in the timestamp column, millis by
+    /// default.
+    ///
+    /// The values are converted into millis when read by the queries.
+    fn timestamp_precision(&self) -> TimestampPrecision {
+        self.options()
+            .get(TIMESTAMP_PRECISION)
+            .and_then(|v| TimestampPrecision::parse_from(v))
+            .unwrap_or_default()
+    }
+
     fn partition_info(&self) -> Option<PartitionInfo> {
         None
     }
@@ -566,10 +697,35 @@ pub trait Table: std::fmt::Debug {
 
     /// Compact this table and wait until compaction completes.
     async fn compact(&self) -> Result<()>;
+
+    /// Delete all the data whose timestamp is before `before` (in the
+    /// timestamp precision of the table) and wait until the deletion
+    /// completes.
+    ///
+    /// The rows before `before` written after the deletion are deleted too.
+    async fn delete_before(&self, _before: Timestamp) -> Result<()> {
+        UnsupportedMethod {
+            table: self.name(),
+            method: "delete_before",
+        }
+        .fail()
+    }
+
+    /// Check whether the rows matching the predicate may exist, by the meta
+    /// data and the filters only without reading the data of the ssts.
+    ///
+    /// False positives are possible but false negatives are not.
+    async fn maybe_exists(&self, _request: ExistsRequest) -> Result<bool> {
+        UnsupportedMethod {
+            table: self.name(),
+            method: "maybe_exists",
+        }
+        .fail()
+    }
 }
 
 /// Basic statistics of table.
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct TableStats {
     /// Total write request
     pub num_write: u64,
@@ -577,6 +733,30 @@ pub struct TableStats {
     pub num_read: u64,
     /// Total flush request
     pub num_flush: u64,
+    /// Approximate size of the data in bytes, including the memtables and ssts
+    pub data_size: u64,
+    /// Estimated number of rows, None if it's unknown or too stale to be
+    /// trusted
+    pub num_rows: Option<u64>,
+    /// Time elapsed since the estimated statistics are refreshed, None if
+    /// they are never refreshed
+    pub stats_staleness: Option<Duration>,
+    /// Approximate memory used by the memtables in bytes
+    pub memtable_size: u64,
+    /// Total size of the ssts in bytes
+    pub sst_size: u64,
+    /// Number of ssts in each level, indexed by the level
+    pub num_ssts_per_level: Vec<usize>,
+    /// Time range covering all the data of the table, None if the table is
+    /// empty
+    pub time_range: Option<TimeRange>,
+    /// Estimated number of series written since the table is opened, None if
+    /// unknown
+    pub num_series: Option<u64>,
+    /// Write requests per second in the recent window
+    pub write_rate: f64,
+    /// Read requests per second in the recent window
+    pub read_rate: f64,
 }
 
 /// A reference-counted pointer to Table
@@ -0,0 +1,151 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! A cli to replay captured queries against another cluster (or the same
+//! cluster at a new version), comparing the result checksums and latencies.
+
+use std::{sync::Arc, time::Instant};
+
+use anyhow::{ensure, Context, Result};
+use clap::Parser;
+use runtime::Runtime;
+use tokio::sync::{Mutex, Semaphore};
+use tools::query_replay::{is_read_only, parse_captured_queries, Client, ReplayReport};
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// Captured query log, either the slow query log or sql statements
+    /// terminated by `;`
+    #[clap(short, long, required(true))]
+    log: String,
+
+    /// Http endpoint of the cluster to replay against
+    #[clap(long, required(true))]
+    target: String,
+
+    /// Http endpoint of the cluster to compare with
+    #[clap(long)]
+    baseline: Option<String>,
+
+    /// Pacing speedup of the replay, 1.0 means original pacing, 0 means as
+    /// fast as possible
+    #[clap(short, long, default_value_t = 1.0)]
+    speed: f64,
+
+    /// Max number of queries in flight
+    #[clap(short, long, default_value_t = 8)]
+    concurrency: usize,
+
+    /// Thread num, 0 means cpu num
+    #[clap(short, long, default_value_t = 0)]
+    threads: usize,
+
+    /// Replay the statements modifying the data or the tables too, only the
+    /// read-only statements are replayed by default
+    #[clap(long)]
+    allow_writes: bool,
+}
+
+fn new_runtime(thread_num: usize) -> Runtime {
+    runtime::Builder::default()
+        .thread_name("query-replay")
+        .worker_threads(thread_num)
+        .enable_all()
+        .build()
+        .unwrap()
+}
+
+fn main() {
+    let args = Args::parse();
+    let thread_num = if args.threads == 0 {
+        num_cpus::get()
+    } else {
+        args.threads
+    };
+    let rt = Arc::new(new_runtime(thread_num));
+    rt.block_on(async move {
+        if let Err(e) = run(args).await {
+            eprintln!("Run failed, err:{e}");
+            std::process::exit(1);
+        }
+    });
+}
+
+async fn run(args: Args) -> Result<()> {
+    let content = tokio::fs::read_to_string(&args.log)
+        .await
+        .with_context(|| format!("read log {}", args.log))?;
+    let mut queries = parse_captured_queries(&content);
+    let num_captured = queries.len();
+    if !args.allow_writes {
+        queries.retain(|query| is_read_only(&query.sql));
+    }
+    println!(
+        "Replay {} queries from {}, skipped writes:{}",
+        queries.len(),
+        args.log,
+        num_captured - queries.len()
+    );
+
+    let target = Arc::new(Client::new(&args.target));
+    let baseline = args.baseline.as_deref().map(|v| Arc::new(Client::new(v)));
+    let report = Arc::new(Mutex::new(ReplayReport::default()));
+    let semaphore = Arc::new(Semaphore::new(args.concurrency.max(1)));
+
+    let begin = Instant::now();
+    let mut handles = Vec::with_capacity(queries.len());
+    for query in queries {
+        if args.speed > 0.0 {
+            if let Some(offset) = query.offset {
+                tokio::time::sleep_until((begin + offset.div_f64(args.speed)).into()).await;
+            }
+        }
+
+        let permit = semaphore.clone().acquire_owned().await?;
+        let (target, baseline, report) = (target.clone(), baseline.clone(), report.clone());
+        handles.push(tokio::spawn(async move {
+            let (target_res, baseline_res) = match &baseline {
+                Some(baseline) => {
+                    let (t, b) =
+                        tokio::join!(target.execute(&query.sql), baseline.execute(&query.sql));
+                    (t, Some(b))
+                }
+                None => (target.execute(&query.sql).await, None),
+            };
+            report
+                .lock()
+                .await
+                .record(&query.sql, target_res, baseline_res);
+            drop(permit);
+        }));
+    }
+
+    for handle in handles {
+        handle.await?;
+    }
+
+    println!("Replay finished, elapsed:{:?}", begin.elapsed());
+    let mut report = report.lock().await;
+    println!("{}", report.pretty());
+    ensure!(
+        report.is_success(),
+        "some queries failed or mismatched on the target"
+    );
+
+    Ok(())
+}
@@ -114,6 +114,7 @@ async fn run(args: Args, runtime: Arc<Runtime>) -> Result<()> {
         predicate: Arc::new(Predicate::empty()),
         meta_cache: None,
         scan_options,
+        query_stats: None,
         runtime,
         row_projector_builder,
     };
@@ -138,6 +139,7 @@ async fn run(args: Args, runtime: Arc<Runtime>) -> Result<()> {
             .with_context(|| format!("invalid compression:{}", args.compression))?,
         max_buffer_size: 10 * 1024 * 1024,
         column_stats: Default::default(),
+        field_groups: Vec::new(),
     };
     let output = Path::from(args.output);
     let mut writer = factory
@@ -0,0 +1,117 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! A cli to repair the sst whose custom meta data is lost or corrupted, by
+//! reconstructing it from the parquet footer and the data blocks.
+
+use std::sync::Arc;
+
+use analytic_engine::sst::parquet::encoding;
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use object_store::{config::LocalOptions, local_file, ObjectStoreRef, Path};
+use parquet::file::footer;
+use runtime::Runtime;
+use tools::sst_repair::{self, MetaLocation};
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// Root dir of storage
+    #[clap(short, long, required(true))]
+    store_path: String,
+
+    /// Sst file to repair(relative to store_path)
+    #[clap(short, long, required(true))]
+    input: String,
+
+    /// Max sequence of the sst, which isn't stored in the data blocks and
+    /// should be taken from the manifest
+    #[clap(short, long, required(true))]
+    max_sequence: u64,
+
+    /// Only print the rebuilt meta data without writing it
+    #[clap(short, long, required(false))]
+    dry_run: bool,
+}
+
+fn new_runtime(thread_num: usize) -> Runtime {
+    runtime::Builder::default()
+        .thread_name("sst-repair")
+        .worker_threads(thread_num)
+        .enable_all()
+        .build()
+        .unwrap()
+}
+
+fn main() {
+    let args = Args::parse();
+    let rt = Arc::new(new_runtime(2));
+    rt.block_on(async move {
+        if let Err(e) = run(args).await {
+            eprintln!("Repair failed, err:{e:?}");
+            std::process::exit(1);
+        }
+    });
+}
+
+async fn run(args: Args) -> Result<()> {
+    let local_opts = LocalOptions {
+        data_dir: args.store_path,
+        max_retries: 3,
+        timeout: Default::default(),
+    };
+    let storage = local_file::try_new(&local_opts).context("invalid store path")?;
+    let store: ObjectStoreRef = Arc::new(storage);
+    let input_path = Path::from(args.input);
+
+    let sst = store
+        .get(&input_path)
+        .await
+        .with_context(|| format!("failed to get sst:{input_path}"))?
+        .bytes()
+        .await?;
+    let file_meta = footer::parse_metadata(&sst).context("failed to parse parquet footer")?;
+    let (meta_path, meta_size) = match sst_repair::meta_location(&file_meta)? {
+        MetaLocation::Embedded => {
+            bail!("meta data is embedded in the parquet footer, repair is not supported")
+        }
+        MetaLocation::File { path, size } => (Path::from(path), size),
+    };
+
+    let meta = sst_repair::rebuild_meta_data(sst, args.max_sequence)?;
+    println!("Rebuilt meta data:{meta:?}");
+
+    let encoded = sst_repair::encode_meta_data(meta, meta_size)?;
+    // Ensure the repaired meta data can be read back.
+    encoding::decode_sst_meta_data_from_bytes(&encoded)
+        .context("failed to decode rebuilt meta data")?;
+    if args.dry_run {
+        return Ok(());
+    }
+
+    // Keep the corrupted meta data for investigation.
+    if store.head(&meta_path).await.is_ok() {
+        let backup_path = Path::from(format!("{meta_path}.corrupted"));
+        store.copy(&meta_path, &backup_path).await?;
+        println!("Backup corrupted meta data to {backup_path}");
+    }
+    store.put(&meta_path, encoded.into()).await?;
+    println!("Repair success, meta path:{meta_path}");
+
+    Ok(())
+}
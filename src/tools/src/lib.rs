@@ -15,4 +15,6 @@
 // specific language governing permissions and limitations
 // under the License.
 
+pub mod query_replay;
+pub mod sst_repair;
 pub mod sst_util;
@@ -0,0 +1,427 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Replay captured queries against the clusters, comparing the results and
+//! latencies.
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context, Result};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+/// Timestamp format used by the logger.
+const LOG_TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.3f";
+const LOG_TIMESTAMP_LEN: usize = 23;
+/// Marker of the sql in the slow query log.
+const SLOW_QUERY_MARKER: &str = "Normal query elapsed:";
+const SQL_MARKER: &str = ", query:";
+
+/// A query captured from logs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CapturedQuery {
+    /// The offset to the first captured query, `None` if the log carries no
+    /// timestamp.
+    pub offset: Option<Duration>,
+    pub sql: String,
+}
+
+/// Parse the captured queries from the content of the logs.
+///
+/// Two formats are supported:
+/// - The slow query log written by the server, and the pacing is restored from
+///   the timestamps. The lines following a log record without a timestamp are
+///   the rest of it, and are ignored unless the record is a slow query.
+/// - Plain text of sql statements (eg. audit logs exported by clients), which
+///   are terminated by `;` or separated by blank lines, so one statement may
+///   span multiple lines.
+pub fn parse_captured_queries(content: &str) -> Vec<CapturedQuery> {
+    let mut parser = QueryParser::default();
+    for line in content.lines() {
+        parser.parse_line(line);
+    }
+
+    parser.finish()
+}
+
+#[derive(Default)]
+struct QueryParser {
+    first_ts: Option<NaiveDateTime>,
+    queries: Vec<CapturedQuery>,
+    /// Whether a log record is being parsed, its following lines without a
+    /// timestamp are not plain text statements.
+    in_log_record: bool,
+    /// The query of the slow query log record being parsed.
+    log_query: Option<CapturedQuery>,
+    /// The plain text statement being parsed.
+    statement: String,
+    /// The quote of the statement not closed yet.
+    quote: Option<char>,
+}
+
+impl QueryParser {
+    fn parse_line(&mut self, line: &str) {
+        let ts = line
+            .trim_start()
+            .get(..LOG_TIMESTAMP_LEN)
+            .and_then(|v| NaiveDateTime::parse_from_str(v, LOG_TIMESTAMP_FORMAT).ok());
+        // A new log record begins.
+        if ts.is_some() || line.contains(SLOW_QUERY_MARKER) {
+            self.finish_statement();
+            self.finish_log_query();
+            self.start_log_query(line, ts);
+            return;
+        }
+
+        if !self.in_log_record {
+            self.parse_statement_line(line);
+            return;
+        }
+        if let Some(query) = &mut self.log_query {
+            if !line.trim().is_empty() {
+                query.sql.push('\n');
+                query.sql.push_str(line.trim_end());
+            }
+        }
+    }
+
+    fn start_log_query(&mut self, line: &str, ts: Option<NaiveDateTime>) {
+        self.in_log_record = true;
+        let Some(pos) = line.find(SLOW_QUERY_MARKER) else {
+            return;
+        };
+        let Some(sql_pos) = line[pos..].find(SQL_MARKER) else {
+            return;
+        };
+
+        let sql = line[pos + sql_pos + SQL_MARKER.len()..].trim().to_string();
+        let offset = ts.map(|ts| {
+            let first = *self.first_ts.get_or_insert(ts);
+            (ts - first).to_std().unwrap_or_default()
+        });
+        self.log_query = Some(CapturedQuery { offset, sql });
+    }
+
+    fn finish_log_query(&mut self) {
+        if let Some(query) = self.log_query.take() {
+            self.queries.push(query);
+        }
+    }
+
+    fn parse_statement_line(&mut self, line: &str) {
+        // The whitespaces in the quoted literals are kept.
+        let line = if self.quote.is_some() {
+            line
+        } else {
+            let line = line.trim();
+            if line.is_empty() {
+                self.finish_statement();
+                return;
+            }
+            if line.starts_with("--") {
+                return;
+            }
+            line
+        };
+
+        if !self.statement.is_empty() {
+            self.statement.push('\n');
+        }
+        for c in line.chars() {
+            match self.quote {
+                Some(quote) if c == quote => self.quote = None,
+                Some(_) => {}
+                None if c == ';' => {
+                    self.finish_statement();
+                    continue;
+                }
+                None if matches!(c, '\'' | '"' | '`') => self.quote = Some(c),
+                None => {}
+            }
+            self.statement.push(c);
+        }
+    }
+
+    fn finish_statement(&mut self) {
+        let statement = std::mem::take(&mut self.statement);
+        self.quote = None;
+        let sql = statement.trim();
+        if !sql.is_empty() {
+            self.queries.push(CapturedQuery {
+                offset: None,
+                sql: sql.to_string(),
+            });
+        }
+    }
+
+    fn finish(mut self) -> Vec<CapturedQuery> {
+        self.finish_statement();
+        self.finish_log_query();
+
+        self.queries
+    }
+}
+
+/// Keywords beginning the statements not modifying the data or the tables.
+const READ_ONLY_KEYWORDS: [&str; 6] = ["SELECT", "SHOW", "DESCRIBE", "DESC", "EXPLAIN", "WITH"];
+
+/// Whether the sql is a read-only statement, which is safe to replay.
+///
+/// The leading comments are skipped and only the first keyword is checked,
+/// so the unknown statements are treated as writes.
+pub fn is_read_only(sql: &str) -> bool {
+    let mut sql = sql.trim_start();
+    while let Some(rest) = sql.strip_prefix("--") {
+        sql = rest.split_once('\n').map_or("", |(_, v)| v).trim_start();
+    }
+    let keyword = sql
+        .split(|c: char| !c.is_ascii_alphabetic())
+        .next()
+        .unwrap_or_default();
+
+    READ_ONLY_KEYWORDS
+        .iter()
+        .any(|v| v.eq_ignore_ascii_case(keyword))
+}
+
+/// Result of executing a query against one endpoint.
+#[derive(Debug)]
+pub struct QueryResult {
+    pub latency: Duration,
+    /// Checksum of the response body, `None` if the query failed.
+    pub checksum: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct SqlRequest<'a> {
+    query: &'a str,
+}
+
+/// Client executing queries through the http sql api.
+pub struct Client {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl Client {
+    pub fn new(endpoint: &str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: format!("http://{endpoint}/sql"),
+        }
+    }
+
+    pub async fn execute(&self, sql: &str) -> QueryResult {
+        let begin = Instant::now();
+        let checksum = self.execute_inner(sql).await.ok();
+
+        QueryResult {
+            latency: begin.elapsed(),
+            checksum,
+        }
+    }
+
+    async fn execute_inner(&self, sql: &str) -> Result<u64> {
+        let resp = self
+            .client
+            .post(&self.url)
+            .json(&SqlRequest { query: sql })
+            .send()
+            .await
+            .context("send sql request")?;
+        let status = resp.status();
+        let body = resp.bytes().await.context("read sql response")?;
+        if !status.is_success() {
+            return Err(anyhow!(
+                "sql failed, status:{status}, body:{}",
+                String::from_utf8_lossy(&body)
+            ));
+        }
+
+        let mut hasher = DefaultHasher::new();
+        body.hash(&mut hasher);
+        Ok(hasher.finish())
+    }
+}
+
+/// Statistics of the replay.
+#[derive(Debug, Default)]
+pub struct ReplayReport {
+    pub total: usize,
+    pub target_failed: usize,
+    pub baseline_failed: usize,
+    /// Queries whose results differ between the target and the baseline.
+    pub mismatched: Vec<String>,
+    pub target_latencies: Vec<Duration>,
+    pub baseline_latencies: Vec<Duration>,
+}
+
+impl ReplayReport {
+    pub fn record(&mut self, sql: &str, target: QueryResult, baseline: Option<QueryResult>) {
+        self.total += 1;
+        if target.checksum.is_none() {
+            self.target_failed += 1;
+        }
+        self.target_latencies.push(target.latency);
+
+        if let Some(baseline) = baseline {
+            if baseline.checksum.is_none() {
+                self.baseline_failed += 1;
+            }
+            if baseline.checksum != target.checksum {
+                self.mismatched.push(sql.to_string());
+            }
+            self.baseline_latencies.push(baseline.latency);
+        }
+    }
+
+    /// Whether all the queries succeed on the target with the same results as
+    /// the baseline.
+    pub fn is_success(&self) -> bool {
+        self.target_failed == 0 && self.mismatched.is_empty()
+    }
+
+    pub fn pretty(&mut self) -> String {
+        let mut lines = vec![format!(
+            "total:{}, target_failed:{}, baseline_failed:{}, mismatched:{}",
+            self.total,
+            self.target_failed,
+            self.baseline_failed,
+            self.mismatched.len()
+        )];
+        for (name, latencies) in [
+            ("target", &mut self.target_latencies),
+            ("baseline", &mut self.baseline_latencies),
+        ] {
+            if latencies.is_empty() {
+                continue;
+            }
+            latencies.sort_unstable();
+            lines.push(format!(
+                "{name} latency, p50:{:?}, p90:{:?}, p99:{:?}, max:{:?}",
+                percentile(latencies, 0.5),
+                percentile(latencies, 0.9),
+                percentile(latencies, 0.99),
+                latencies[latencies.len() - 1],
+            ));
+        }
+        for sql in &self.mismatched {
+            lines.push(format!("mismatched query:{sql}"));
+        }
+
+        lines.join("\n")
+    }
+}
+
+/// Get the percentile from the sorted latencies.
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    let idx = ((sorted.len() as f64 * p).ceil() as usize).saturating_sub(1);
+    sorted[idx.min(sorted.len() - 1)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain(sql: &str) -> CapturedQuery {
+        CapturedQuery {
+            offset: None,
+            sql: sql.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_plain_statements() {
+        let content = "\
+-- captured by the client
+SELECT * FROM t1;
+SELECT a,
+    b
+FROM t2
+WHERE a > 1;
+
+SELECT 1; SELECT 2
+SELECT 3
+
+SELECT 'a;b
+  c' FROM t3";
+        let queries = parse_captured_queries(content);
+        let expect = vec![
+            plain("SELECT * FROM t1"),
+            plain("SELECT a,\nb\nFROM t2\nWHERE a > 1"),
+            plain("SELECT 1"),
+            plain("SELECT 2\nSELECT 3"),
+            plain("SELECT 'a;b\n  c' FROM t3"),
+        ];
+        assert_eq!(expect, queries);
+    }
+
+    #[test]
+    fn test_parse_slow_query_log() {
+        let content = "\
+2024-01-01 10:00:00.000 INFO [proxy/src/read.rs:100] Normal query elapsed:10ms, query:SELECT * FROM t1
+2024-01-01 10:00:01.500 INFO [server/src/http.rs:10] Other log
+DROP TABLE t1;
+2024-01-01 10:00:02.000 INFO [proxy/src/read.rs:100] Normal query elapsed:20ms, query:SELECT a
+FROM t2
+
+WHERE a > 1
+2024-01-01 10:00:03.000 INFO [proxy/src/read.rs:100] Normal query elapsed:30ms";
+        let queries = parse_captured_queries(content);
+        let expect = vec![
+            CapturedQuery {
+                offset: Some(Duration::ZERO),
+                sql: "SELECT * FROM t1".to_string(),
+            },
+            CapturedQuery {
+                offset: Some(Duration::from_secs(2)),
+                sql: "SELECT a\nFROM t2\nWHERE a > 1".to_string(),
+            },
+        ];
+        assert_eq!(expect, queries);
+    }
+
+    #[test]
+    fn test_is_read_only() {
+        for sql in [
+            "SELECT * FROM t1",
+            "select 1",
+            "  -- comment\nSELECT 1",
+            "SHOW CREATE TABLE t1",
+            "DESC t1",
+            "EXPLAIN SELECT 1",
+            "WITH t AS (SELECT 1) SELECT * FROM t",
+        ] {
+            assert!(is_read_only(sql), "{sql}");
+        }
+        for sql in [
+            "INSERT INTO t1 (a) VALUES (1)",
+            "DROP TABLE t1",
+            "ALTER TABLE t1 ADD COLUMN b string",
+            "DELETE FROM t1 WHERE ts < 1",
+            "CREATE TABLE t1 (a int)",
+            "-- SELECT\nDROP TABLE t1",
+            "",
+        ] {
+            assert!(!is_read_only(sql), "{sql}");
+        }
+    }
+}
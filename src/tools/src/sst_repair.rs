@@ -0,0 +1,334 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Utilities to reconstruct the custom meta data of a sst whose meta block is
+//! lost or corrupted, from the parquet footer and the data blocks.
+
+use analytic_engine::sst::parquet::{
+    encoding::{self, META_VALUE_HEADER},
+    meta_data::ParquetMetaData,
+};
+use anyhow::{bail, ensure, Context, Result};
+use arrow::array::{Array, TimestampMillisecondArray};
+use bytes::{BufMut, Bytes, BytesMut};
+use common_types::{
+    schema::Schema,
+    time::{TimeRange, Timestamp},
+    SequenceNumber,
+};
+use parquet::{
+    arrow::{arrow_reader::ParquetRecordBatchReaderBuilder, ProjectionMask},
+    file::{metadata::ParquetMetaData as FileMetaData, statistics::Statistics},
+};
+
+/// Field number used to pad the rebuilt meta data to the size recorded in the
+/// sst, it is unknown to the meta data message and skipped when decoding.
+const PADDING_FIELD_NUMBER: u64 = 1000;
+/// Wire type of the length delimited field.
+const LENGTH_DELIMITED_WIRE_TYPE: u64 = 2;
+
+/// Location of the custom meta data recorded in the parquet footer.
+#[derive(Debug)]
+pub enum MetaLocation {
+    /// The meta data is embedded in the footer (v1), and can't be rewritten
+    /// without rewriting the whole sst.
+    Embedded,
+    /// The meta data is stored in a separate file (v2).
+    File { path: String, size: Option<usize> },
+}
+
+/// Find where the custom meta data of the sst is stored.
+pub fn meta_location(file_meta: &FileMetaData) -> Result<MetaLocation> {
+    let kv_metas = file_meta
+        .file_metadata()
+        .key_value_metadata()
+        .context("key value meta data not found")?;
+    let find_kv = |key: &str| {
+        kv_metas
+            .iter()
+            .find(|kv| kv.key == key)
+            .and_then(|kv| kv.value.clone())
+    };
+
+    if find_kv(encoding::META_KEY).is_some() {
+        return Ok(MetaLocation::Embedded);
+    }
+    let path = find_kv(encoding::META_PATH_KEY).context("meta path not found")?;
+    let size = find_kv(encoding::META_SIZE_KEY)
+        .map(|v| v.parse::<usize>())
+        .transpose()
+        .context("invalid meta size")?;
+
+    Ok(MetaLocation::File { path, size })
+}
+
+/// Rebuild the custom meta data from the sst content.
+///
+/// The schema is recovered from the arrow schema stored in the footer and the
+/// time range from the statistics of the timestamp column, falling back to
+/// scanning the column if any row group lacks statistics. The max sequence
+/// isn't stored in the data, so it must be provided by the caller (e.g. from
+/// the manifest). The min/max keys and the filters are left empty as the
+/// readers don't rely on them.
+pub fn rebuild_meta_data(sst: Bytes, max_sequence: SequenceNumber) -> Result<ParquetMetaData> {
+    let builder = ParquetRecordBatchReaderBuilder::try_new(sst)?;
+    let schema = Schema::try_from(builder.schema().clone())
+        .context("failed to rebuild schema from the arrow schema in the footer")?;
+
+    let timestamp_index = schema.timestamp_index();
+    let time_range = match time_range_from_statistics(builder.metadata(), timestamp_index) {
+        Some(v) => v,
+        None => {
+            let mask = ProjectionMask::roots(builder.parquet_schema(), [timestamp_index]);
+            let reader = builder.with_projection(mask).build()?;
+            let mut min_max: Option<(i64, i64)> = None;
+            for batch in reader {
+                let batch = batch?;
+                let timestamps = batch
+                    .column(0)
+                    .as_any()
+                    .downcast_ref::<TimestampMillisecondArray>()
+                    .context("unexpected type of the timestamp column")?;
+                for ts in timestamps.iter().flatten() {
+                    min_max = Some(match min_max {
+                        Some((min, max)) => (min.min(ts), max.max(ts)),
+                        None => (ts, ts),
+                    });
+                }
+            }
+            let (min, max) = min_max.context("no timestamp found in the sst")?;
+            new_time_range(min, max)?
+        }
+    };
+
+    Ok(ParquetMetaData {
+        min_key: Bytes::new(),
+        max_key: Bytes::new(),
+        time_range,
+        max_sequence,
+        schema,
+        parquet_filter: None,
+        column_values: None,
+    })
+}
+
+fn time_range_from_statistics(
+    file_meta: &FileMetaData,
+    timestamp_index: usize,
+) -> Option<TimeRange> {
+    let mut min_max: Option<(i64, i64)> = None;
+    for row_group in file_meta.row_groups() {
+        match row_group.column(timestamp_index).statistics() {
+            Some(Statistics::Int64(stats)) if stats.has_min_max_set() => {
+                let (min, max) = (*stats.min(), *stats.max());
+                min_max = Some(match min_max {
+                    Some((cur_min, cur_max)) => (cur_min.min(min), cur_max.max(max)),
+                    None => (min, max),
+                });
+            }
+            _ => return None,
+        }
+    }
+
+    min_max.and_then(|(min, max)| new_time_range(min, max).ok())
+}
+
+fn new_time_range(min: i64, max: i64) -> Result<TimeRange> {
+    let max = Timestamp::new(max);
+    let end = max.checked_add_i64(1).unwrap_or(max);
+    TimeRange::new(Timestamp::new(min), end).context("invalid time range")
+}
+
+/// Encode the meta data, padding it to `target_size` if provided, which is
+/// necessary because the readers only fetch the recorded size of the meta
+/// file.
+pub fn encode_meta_data(meta: ParquetMetaData, target_size: Option<usize>) -> Result<Bytes> {
+    let encoded = encoding::encode_sst_meta_data(meta)?;
+    let Some(target_size) = target_size else {
+        return Ok(encoded);
+    };
+
+    ensure!(
+        encoded.len() <= target_size,
+        "rebuilt meta data is larger than the recorded size, rebuilt:{}, recorded:{target_size}",
+        encoded.len()
+    );
+    let padding_size = target_size - encoded.len();
+    if padding_size == 0 {
+        return Ok(encoded);
+    }
+
+    let tag = (PADDING_FIELD_NUMBER << 3) | LENGTH_DELIMITED_WIRE_TYPE;
+    let tag_len = varint_len(tag);
+    let mut buf = BytesMut::with_capacity(target_size);
+    buf.put_slice(&encoded);
+
+    // The padding size right after a boundary of the length varint can't be
+    // filled by one field, e.g. 131 bytes needs either a 128 bytes payload with
+    // a 1 byte length or a 127 bytes payload with a 2 bytes length, so an empty
+    // padding field is put first to skip the boundary.
+    let mut remaining = padding_size;
+    if padding_payload_len(remaining, tag_len).is_none() && remaining > tag_len + 1 {
+        put_varint(&mut buf, tag);
+        put_varint(&mut buf, 0);
+        remaining -= tag_len + 1;
+    }
+    let Some(payload_len) = padding_payload_len(remaining, tag_len) else {
+        bail!("unable to pad rebuilt meta data, padding size:{padding_size}");
+    };
+    put_varint(&mut buf, tag);
+    put_varint(&mut buf, payload_len as u64);
+    buf.put_bytes(0, payload_len);
+    assert_eq!(buf.len(), target_size);
+    assert_eq!(buf[0], META_VALUE_HEADER);
+
+    Ok(buf.freeze())
+}
+
+/// The length of the payload of the padding field to fill `padding_size`, the
+/// field consists of the tag, the length and the payload, and the length of
+/// the length varint depends on the payload length.
+fn padding_payload_len(padding_size: usize, tag_len: usize) -> Option<usize> {
+    (1..=4).find_map(|len_len| {
+        let payload_len = padding_size.checked_sub(tag_len + len_len)?;
+        (varint_len(payload_len as u64) == len_len).then_some(payload_len)
+    })
+}
+
+fn varint_len(mut v: u64) -> usize {
+    let mut len = 1;
+    while v >= 0x80 {
+        v >>= 7;
+        len += 1;
+    }
+    len
+}
+
+fn put_varint(buf: &mut BytesMut, mut v: u64) {
+    while v >= 0x80 {
+        buf.put_u8((v as u8) | 0x80);
+        v >>= 7;
+    }
+    buf.put_u8(v as u8);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::{
+        array::{ArrayRef, Float32Array, Int8Array, StringArray, UInt64Array},
+        record_batch::RecordBatch,
+    };
+    use common_types::tests::build_schema_for_cpu;
+    use parquet::{
+        arrow::ArrowWriter,
+        file::{
+            footer,
+            properties::{EnabledStatistics, WriterProperties},
+        },
+    };
+
+    use super::*;
+
+    fn new_meta_data() -> ParquetMetaData {
+        ParquetMetaData {
+            min_key: Bytes::new(),
+            max_key: Bytes::new(),
+            time_range: TimeRange::new(Timestamp::new(100), Timestamp::new(200)).unwrap(),
+            max_sequence: 10,
+            schema: build_schema_for_cpu(),
+            parquet_filter: None,
+            column_values: None,
+        }
+    }
+
+    #[test]
+    fn test_encode_meta_data_padding() {
+        let meta = new_meta_data();
+        let encoded_len = encoding::encode_sst_meta_data(meta.clone()).unwrap().len();
+
+        let encoded = encode_meta_data(meta.clone(), None).unwrap();
+        assert_eq!(encoded_len, encoded.len());
+
+        // The padding sizes around the boundaries of the length varint.
+        let padding_sizes = (0..=300).chain(16370..=16400).chain(2097140..=2097170);
+        for padding_size in padding_sizes {
+            let target_size = encoded_len + padding_size;
+            let encoded = match encode_meta_data(meta.clone(), Some(target_size)) {
+                Ok(v) => v,
+                Err(e) => {
+                    // The padding field takes at least 3 bytes.
+                    assert!(padding_size > 0 && padding_size < 3, "err:{e}");
+                    continue;
+                }
+            };
+            assert_eq!(target_size, encoded.len());
+
+            let decoded = encoding::decode_sst_meta_data_from_bytes(&encoded).unwrap();
+            assert_eq!(meta, decoded, "padding_size:{padding_size}");
+        }
+
+        // The rebuilt meta data can't be larger than the recorded size.
+        assert!(encode_meta_data(meta, Some(encoded_len - 1)).is_err());
+    }
+
+    fn build_sst(timestamps: &[i64], statistics: EnabledStatistics) -> Bytes {
+        let schema = build_schema_for_cpu();
+        let num_rows = timestamps.len();
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(UInt64Array::from_iter_values(0..num_rows as u64)),
+            Arc::new(TimestampMillisecondArray::from(timestamps.to_vec())),
+            Arc::new(StringArray::from(vec!["tag1"; num_rows])),
+            Arc::new(StringArray::from(vec!["tag2"; num_rows])),
+            Arc::new(Int8Array::from(vec![1; num_rows])),
+            Arc::new(Float32Array::from(vec![1.0; num_rows])),
+        ];
+        let batch = RecordBatch::try_new(schema.to_arrow_schema_ref(), columns).unwrap();
+
+        let props = WriterProperties::builder()
+            .set_max_row_group_size(2)
+            .set_statistics_enabled(statistics)
+            .build();
+        let mut writer =
+            ArrowWriter::try_new(Vec::new(), schema.to_arrow_schema_ref(), Some(props)).unwrap();
+        writer.write(&batch).unwrap();
+        Bytes::from(writer.into_inner().unwrap())
+    }
+
+    #[test]
+    fn test_rebuild_meta_data() {
+        let timestamps = [150, 120, 300, 199, 101];
+        let expect_time_range = TimeRange::new(Timestamp::new(101), Timestamp::new(301)).unwrap();
+
+        // The time range is taken from the statistics or by scanning the timestamp
+        // column without the statistics.
+        for statistics in [EnabledStatistics::Chunk, EnabledStatistics::None] {
+            let sst = build_sst(&timestamps, statistics);
+            let file_meta = footer::parse_metadata(&sst).unwrap();
+            assert_eq!(
+                statistics == EnabledStatistics::Chunk,
+                time_range_from_statistics(&file_meta, 1).is_some()
+            );
+
+            let meta = rebuild_meta_data(sst, 10).unwrap();
+            assert_eq!(expect_time_range, meta.time_range);
+            assert_eq!(10, meta.max_sequence);
+            assert_eq!(build_schema_for_cpu(), meta.schema);
+        }
+    }
+}
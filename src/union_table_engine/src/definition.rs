@@ -0,0 +1,235 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Definition of the union table, which is persisted in the object store.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use common_types::schema::Schema;
+use generic_error::BoxError;
+use logger::warn;
+use object_store::{ObjectStoreRef, Path};
+use serde::{Deserialize, Serialize};
+use snafu::{ensure, OptionExt, ResultExt};
+use table_engine::table::SchemaId;
+
+use crate::{
+    error::{
+        DeleteDefinition, DuplicateRoute, InvalidRoute, LoadDefinition, MissingOption, Result,
+        StoreDefinition,
+    },
+    schema,
+};
+
+pub const ROUTING_COLUMN: &str = "routing_column";
+/// The routes of the union table in the format `value1:table1,value2:table2`.
+pub const TABLES: &str = "tables";
+
+const DEFINITION_DIR: &str = "union_tables";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TableDefinition {
+    /// Name of the string column used to route the rows.
+    pub routing_column: String,
+    /// Mapping from the routing value to the name of physical table, and the
+    /// physical tables are in the same schema of the union table.
+    pub routes: BTreeMap<String, String>,
+}
+
+impl TableDefinition {
+    pub fn from_options(options: &HashMap<String, String>) -> Result<Self> {
+        let routing_column = options.get(ROUTING_COLUMN).context(MissingOption {
+            option: ROUTING_COLUMN,
+        })?;
+        let tables = options
+            .get(TABLES)
+            .context(MissingOption { option: TABLES })?;
+
+        let mut routes = BTreeMap::new();
+        for route in tables.split(',').map(str::trim).filter(|v| !v.is_empty()) {
+            let (value, table) = route.split_once(':').context(InvalidRoute { route })?;
+            let (value, table) = (value.trim(), table.trim());
+            ensure!(
+                !value.is_empty() && !table.is_empty(),
+                InvalidRoute { route }
+            );
+            ensure!(
+                routes
+                    .insert(value.to_string(), table.to_string())
+                    .is_none(),
+                DuplicateRoute { value }
+            );
+        }
+        ensure!(!routes.is_empty(), MissingOption { option: TABLES });
+
+        Ok(Self {
+            routing_column: routing_column.clone(),
+            routes,
+        })
+    }
+
+    pub fn to_options(&self) -> HashMap<String, String> {
+        let tables = self
+            .routes
+            .iter()
+            .map(|(value, table)| format!("{value}:{table}"))
+            .collect::<Vec<_>>()
+            .join(",");
+
+        HashMap::from([
+            (ROUTING_COLUMN.to_string(), self.routing_column.clone()),
+            (TABLES.to_string(), tables),
+        ])
+    }
+
+    /// Returns the distinct physical tables of the routes.
+    pub fn physical_tables(&self) -> BTreeSet<&str> {
+        self.routes.values().map(String::as_str).collect()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct PersistedDefinition {
+    definition: TableDefinition,
+    /// Schema of the union table encoded by [schema::encode_schema].
+    schema: String,
+}
+
+fn definition_path(schema_id: SchemaId, table_name: &str) -> Path {
+    Path::from(format!(
+        "{DEFINITION_DIR}/{}/{table_name}.json",
+        schema_id.as_u32()
+    ))
+}
+
+pub async fn store_definition(
+    store: &ObjectStoreRef,
+    schema_id: SchemaId,
+    table_name: &str,
+    definition: &TableDefinition,
+    table_schema: &Schema,
+) -> Result<()> {
+    let path = definition_path(schema_id, table_name);
+    let persisted = PersistedDefinition {
+        definition: definition.clone(),
+        schema: schema::encode_schema(table_schema),
+    };
+    let payload = serde_json::to_vec(&persisted)
+        .box_err()
+        .with_context(|| StoreDefinition {
+            path: path.to_string(),
+        })?;
+    store
+        .put(&path, payload.into())
+        .await
+        .box_err()
+        .with_context(|| StoreDefinition {
+            path: path.to_string(),
+        })?;
+
+    Ok(())
+}
+
+pub async fn load_definition(
+    store: &ObjectStoreRef,
+    schema_id: SchemaId,
+    table_name: &str,
+) -> Result<Option<(TableDefinition, Schema)>> {
+    let path = definition_path(schema_id, table_name);
+    let get_res = store.get(&path).await;
+    if let Err(object_store::ObjectStoreError::NotFound { .. }) = &get_res {
+        warn!("Union table definition doesn't exist, path:{}", path);
+        return Ok(None);
+    }
+
+    let payload = get_res
+        .box_err()
+        .with_context(|| LoadDefinition {
+            path: path.to_string(),
+        })?
+        .bytes()
+        .await
+        .box_err()
+        .with_context(|| LoadDefinition {
+            path: path.to_string(),
+        })?;
+    let persisted: PersistedDefinition =
+        serde_json::from_slice(&payload)
+            .box_err()
+            .with_context(|| LoadDefinition {
+                path: path.to_string(),
+            })?;
+    let table_schema = schema::decode_schema(&persisted.schema)
+        .box_err()
+        .with_context(|| LoadDefinition {
+            path: path.to_string(),
+        })?;
+
+    Ok(Some((persisted.definition, table_schema)))
+}
+
+pub async fn delete_definition(
+    store: &ObjectStoreRef,
+    schema_id: SchemaId,
+    table_name: &str,
+) -> Result<()> {
+    let path = definition_path(schema_id, table_name);
+    match store.delete(&path).await {
+        Ok(()) | Err(object_store::ObjectStoreError::NotFound { .. }) => Ok(()),
+        Err(source) => Err(source).context(DeleteDefinition {
+            path: path.to_string(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_options(routing_column: &str, tables: &str) -> HashMap<String, String> {
+        HashMap::from([
+            (ROUTING_COLUMN.to_string(), routing_column.to_string()),
+            (TABLES.to_string(), tables.to_string()),
+        ])
+    }
+
+    #[test]
+    fn test_definition_from_options() {
+        let options = build_options("tenant", "a:t_a, b:t_b,c:t_a,");
+        let definition = TableDefinition::from_options(&options).unwrap();
+        assert_eq!(definition.routing_column, "tenant");
+        assert_eq!(
+            definition.routes,
+            BTreeMap::from([
+                ("a".to_string(), "t_a".to_string()),
+                ("b".to_string(), "t_b".to_string()),
+                ("c".to_string(), "t_a".to_string()),
+            ])
+        );
+        assert_eq!(definition.physical_tables(), BTreeSet::from(["t_a", "t_b"]));
+
+        let options = definition.to_options();
+        assert_eq!(options[TABLES], "a:t_a,b:t_b,c:t_a");
+        assert_eq!(TableDefinition::from_options(&options).unwrap(), definition);
+
+        for tables in ["", "a", "a:", ":t_a", "a:t_a,a:t_b"] {
+            let options = build_options("tenant", tables);
+            assert!(TableDefinition::from_options(&options).is_err());
+        }
+        assert!(TableDefinition::from_options(&HashMap::new()).is_err());
+    }
+}
@@ -0,0 +1,103 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use generic_error::GenericError;
+use macros::define_result;
+use snafu::{Backtrace, Snafu};
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub))]
+pub enum Error {
+    #[snafu(display(
+        "Table option is missing, option:{}.\nBacktrace:\n{}",
+        option,
+        backtrace
+    ))]
+    MissingOption {
+        option: String,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display(
+        "Invalid route of union table, route:{}, expect value:table.\nBacktrace:\n{}",
+        route,
+        backtrace
+    ))]
+    InvalidRoute { route: String, backtrace: Backtrace },
+
+    #[snafu(display(
+        "Routing value is duplicated, value:{}.\nBacktrace:\n{}",
+        value,
+        backtrace
+    ))]
+    DuplicateRoute { value: String, backtrace: Backtrace },
+
+    #[snafu(display(
+        "Invalid routing column, column:{}, msg:{}.\nBacktrace:\n{}",
+        column,
+        msg,
+        backtrace
+    ))]
+    InvalidRoutingColumn {
+        column: String,
+        msg: String,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display(
+        "No route for the routing value, value:{}.\nBacktrace:\n{}",
+        value,
+        backtrace
+    ))]
+    NoRoute { value: String, backtrace: Backtrace },
+
+    #[snafu(display("Failed to find physical table, table:{}, err:{}", table, source))]
+    FindPhysicalTable { table: String, source: GenericError },
+
+    #[snafu(display(
+        "Physical table is not found, table:{}.\nBacktrace:\n{}",
+        table,
+        backtrace
+    ))]
+    PhysicalTableNotFound { table: String, backtrace: Backtrace },
+
+    #[snafu(display(
+        "Physical table is incompatible with the union table, table:{}, msg:{}.\nBacktrace:\n{}",
+        table,
+        msg,
+        backtrace
+    ))]
+    IncompatiblePhysicalTable {
+        table: String,
+        msg: String,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Failed to store table definition, path:{}, err:{}", path, source))]
+    StoreDefinition { path: String, source: GenericError },
+
+    #[snafu(display("Failed to load table definition, path:{}, err:{}", path, source))]
+    LoadDefinition { path: String, source: GenericError },
+
+    #[snafu(display("Failed to delete table definition, path:{}, err:{}", path, source))]
+    DeleteDefinition {
+        path: String,
+        source: object_store::ObjectStoreError,
+    },
+}
+
+define_result!(Error);
@@ -0,0 +1,211 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Union table engine implementations.
+//!
+//! A union table is a logical table routing the written rows to the physical
+//! tables by the value of its routing column, and the queries are answered by
+//! merging the physical tables. The physical tables are analytic tables in the
+//! same schema, which must be opened on the same node as the union table.
+
+pub mod definition;
+pub mod error;
+mod schema;
+mod table;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use common_types::schema::Schema;
+use generic_error::BoxError;
+use logger::info;
+use object_store::ObjectStoreRef;
+use snafu::ResultExt;
+use table_engine::{
+    engine::{
+        CloseShardRequest, CloseTableRequest, ClosedTable, CreateTableParams, CreateTableRequest,
+        DropTableRequest, InvalidArguments, OpenShardRequest, OpenShardResult, OpenTableRequest,
+        Result, TableEngine, TableEngineRef, Unexpected,
+    },
+    table::{SchemaId, TableId, TableRef},
+    UNION_ENGINE_TYPE,
+};
+
+use crate::{definition::TableDefinition, table::UnionTable};
+
+pub struct UnionTableEngine {
+    store: ObjectStoreRef,
+    /// Engine of the physical tables.
+    engine: TableEngineRef,
+}
+
+impl UnionTableEngine {
+    pub fn new(store: ObjectStoreRef, engine: TableEngineRef) -> Self {
+        Self { store, engine }
+    }
+
+    /// All the physical tables must exist and be compatible with the union
+    /// table.
+    async fn check_physical_tables(
+        &self,
+        schema_id: SchemaId,
+        table_schema: &Schema,
+        definition: &TableDefinition,
+    ) -> error::Result<()> {
+        schema::check_routing_column(table_schema, &definition.routing_column)?;
+        for table_name in definition.physical_tables() {
+            let table = table::find_physical_table(&self.engine, schema_id, table_name).await?;
+            schema::check_physical_schema(table_schema, table_name, &table.schema())?;
+        }
+
+        Ok(())
+    }
+
+    async fn open_union_table(
+        &self,
+        schema_id: SchemaId,
+        table_name: String,
+        table_id: TableId,
+    ) -> error::Result<Option<TableRef>> {
+        let Some((definition, table_schema)) =
+            definition::load_definition(&self.store, schema_id, &table_name).await?
+        else {
+            return Ok(None);
+        };
+
+        let table = UnionTable::new(
+            table_name,
+            table_id,
+            schema_id,
+            table_schema,
+            definition,
+            self.engine.clone(),
+        );
+        Ok(Some(Arc::new(table)))
+    }
+}
+
+#[async_trait]
+impl TableEngine for UnionTableEngine {
+    fn engine_type(&self) -> &str {
+        UNION_ENGINE_TYPE
+    }
+
+    async fn close(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn validate_create_table(&self, params: &CreateTableParams) -> Result<()> {
+        let definition = TableDefinition::from_options(&params.table_options)
+            .box_err()
+            .context(InvalidArguments {
+                table: &params.table_name,
+            })?;
+        schema::check_routing_column(&params.table_schema, &definition.routing_column)
+            .box_err()
+            .context(InvalidArguments {
+                table: &params.table_name,
+            })?;
+
+        Ok(())
+    }
+
+    async fn create_table(&self, request: CreateTableRequest) -> Result<TableRef> {
+        let params = request.params;
+        info!(
+            "Union table engine create table, table:{}, options:{:?}",
+            params.table_name, params.table_options
+        );
+
+        let definition = TableDefinition::from_options(&params.table_options)
+            .box_err()
+            .context(InvalidArguments {
+                table: &params.table_name,
+            })?;
+        self.check_physical_tables(request.schema_id, &params.table_schema, &definition)
+            .await
+            .box_err()
+            .context(InvalidArguments {
+                table: &params.table_name,
+            })?;
+
+        definition::store_definition(
+            &self.store,
+            request.schema_id,
+            &params.table_name,
+            &definition,
+            &params.table_schema,
+        )
+        .await
+        .box_err()
+        .context(Unexpected)?;
+
+        let table = UnionTable::new(
+            params.table_name,
+            request.table_id,
+            request.schema_id,
+            params.table_schema,
+            definition,
+            self.engine.clone(),
+        );
+        Ok(Arc::new(table))
+    }
+
+    /// Only the definition of the union table is dropped, and the physical
+    /// tables are kept.
+    async fn drop_table(&self, request: DropTableRequest) -> Result<bool> {
+        definition::delete_definition(&self.store, request.schema_id, &request.table_name)
+            .await
+            .box_err()
+            .context(Unexpected)?;
+
+        Ok(true)
+    }
+
+    async fn open_table(&self, request: OpenTableRequest) -> Result<Option<TableRef>> {
+        self.open_union_table(request.schema_id, request.table_name, request.table_id)
+            .await
+            .box_err()
+            .context(Unexpected)
+    }
+
+    async fn close_table(&self, _request: CloseTableRequest) -> Result<()> {
+        Ok(())
+    }
+
+    async fn open_shard(&self, request: OpenShardRequest) -> Result<OpenShardResult> {
+        let mut shard_result = OpenShardResult::with_capacity(request.table_defs.len());
+        for table_def in request.table_defs {
+            let table_id = table_def.id;
+            let open_res = self
+                .open_union_table(table_def.schema_id, table_def.name, table_id)
+                .await
+                .box_err();
+            shard_result.insert(table_id, open_res);
+        }
+
+        Ok(shard_result)
+    }
+
+    async fn close_shard(&self, request: CloseShardRequest) -> Vec<Result<ClosedTable>> {
+        request
+            .table_defs
+            .into_iter()
+            .map(|table_def| Ok(ClosedTable::new(table_def.name)))
+            .collect()
+    }
+}
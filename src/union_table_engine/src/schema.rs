@@ -0,0 +1,160 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Schema checks of the union tables.
+
+use common_types::{datum::DatumKind, schema::Schema};
+use generic_error::{BoxError, GenericResult};
+use horaedbproto::schema as schema_pb;
+use prost::Message;
+use snafu::{ensure, OptionExt};
+
+use crate::error::{IncompatiblePhysicalTable, InvalidRoutingColumn, Result};
+
+/// Returns the index of the routing column, which must be a string column.
+pub fn check_routing_column(schema: &Schema, routing_column: &str) -> Result<usize> {
+    let index = schema
+        .index_of(routing_column)
+        .with_context(|| InvalidRoutingColumn {
+            column: routing_column,
+            msg: "column doesn't exist",
+        })?;
+    let data_type = schema.column(index).data_type;
+    ensure!(
+        data_type == DatumKind::String,
+        InvalidRoutingColumn {
+            column: routing_column,
+            msg: format!("expect string column, given:{data_type}"),
+        }
+    );
+
+    Ok(index)
+}
+
+/// Check whether the physical table can be read and written through the
+/// union table.
+///
+/// The columns of the union table must be the prefix of the physical table's
+/// columns, so the projection of the union table is also valid for the
+/// physical table.
+pub fn check_physical_schema(
+    union_schema: &Schema,
+    table_name: &str,
+    table_schema: &Schema,
+) -> Result<()> {
+    ensure!(
+        union_schema.primary_key_indexes() == table_schema.primary_key_indexes(),
+        IncompatiblePhysicalTable {
+            table: table_name,
+            msg: "primary key is different",
+        }
+    );
+
+    for (index, column) in union_schema.columns().iter().enumerate() {
+        let matched = table_schema.columns().get(index).map_or(false, |v| {
+            v.id == column.id && v.name == column.name && v.data_type == column.data_type
+        });
+        ensure!(
+            matched,
+            IncompatiblePhysicalTable {
+                table: table_name,
+                msg: format!("column is different, column:{}", column.name),
+            }
+        );
+    }
+
+    Ok(())
+}
+
+/// Encode the schema in protobuf and then base64.
+pub fn encode_schema(schema: &Schema) -> String {
+    let schema_pb = schema_pb::TableSchema::from(schema);
+    base64::encode(schema_pb.encode_to_vec())
+}
+
+pub fn decode_schema(encoded: &str) -> GenericResult<Schema> {
+    let payload = base64::decode(encoded).box_err()?;
+    let schema_pb = schema_pb::TableSchema::decode(payload.as_slice()).box_err()?;
+    Schema::try_from(schema_pb).box_err()
+}
+
+#[cfg(test)]
+mod tests {
+    use common_types::{column_schema::Builder as ColumnSchemaBuilder, schema::Builder};
+
+    use super::*;
+
+    fn build_schema(columns: &[(&str, DatumKind)]) -> Schema {
+        let mut builder = Builder::new()
+            .auto_increment_column_id(true)
+            .primary_key_indexes(vec![0])
+            .add_key_column(
+                ColumnSchemaBuilder::new("ts".to_string(), DatumKind::Timestamp)
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap();
+        for (name, kind) in columns {
+            builder = builder
+                .add_normal_column(
+                    ColumnSchemaBuilder::new(name.to_string(), *kind)
+                        .build()
+                        .unwrap(),
+                )
+                .unwrap();
+        }
+
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn test_check_routing_column() {
+        let schema = build_schema(&[("tenant", DatumKind::String), ("value", DatumKind::Double)]);
+
+        assert_eq!(check_routing_column(&schema, "tenant").unwrap(), 1);
+        assert!(check_routing_column(&schema, "value").is_err());
+        assert!(check_routing_column(&schema, "not_exist").is_err());
+    }
+
+    #[test]
+    fn test_check_physical_schema() {
+        let union_schema =
+            build_schema(&[("tenant", DatumKind::String), ("value", DatumKind::Double)]);
+
+        check_physical_schema(&union_schema, "t", &union_schema).unwrap();
+        let appended = build_schema(&[
+            ("tenant", DatumKind::String),
+            ("value", DatumKind::Double),
+            ("extra", DatumKind::Int64),
+        ]);
+        check_physical_schema(&union_schema, "t", &appended).unwrap();
+
+        let different = build_schema(&[("tenant", DatumKind::String), ("value", DatumKind::Int64)]);
+        assert!(check_physical_schema(&union_schema, "t", &different).is_err());
+        let missing = build_schema(&[("tenant", DatumKind::String)]);
+        assert!(check_physical_schema(&union_schema, "t", &missing).is_err());
+    }
+
+    #[test]
+    fn test_encode_schema() {
+        let schema = build_schema(&[("tenant", DatumKind::String), ("value", DatumKind::Double)]);
+
+        let decoded = decode_schema(&encode_schema(&schema)).unwrap();
+        assert_eq!(decoded, schema);
+        assert!(decode_schema("invalid").is_err());
+    }
+}
@@ -0,0 +1,515 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::{collections::HashMap, fmt};
+
+use async_trait::async_trait;
+use common_types::{
+    projected_schema::ProjectedSchema,
+    row::{Row, RowGroup},
+    schema::Schema,
+};
+use futures::future;
+use generic_error::BoxError;
+use snafu::{OptionExt, ResultExt};
+use table_engine::{
+    engine::{FindTableRequest, TableEngineRef},
+    stream::{PartitionedStreams, SendableRecordBatchStream},
+    table::{
+        AlterSchemaRequest, FlushRequest, GetRequest, ReadRequest, Result, Scan, SchemaId, Table,
+        TableId, TableRef, TableStats, UnsupportedMethod, Write, WriteRequest,
+    },
+    ANALYTIC_ENGINE_TYPE, UNION_ENGINE_TYPE,
+};
+
+use crate::{
+    definition::TableDefinition,
+    error::{self, FindPhysicalTable, NoRoute, PhysicalTableNotFound},
+    schema,
+};
+
+/// Find the physical table in the schema, the physical tables are always
+/// created by the analytic engine.
+pub(crate) async fn find_physical_table(
+    engine: &TableEngineRef,
+    schema_id: SchemaId,
+    table_name: &str,
+) -> error::Result<TableRef> {
+    let request = FindTableRequest {
+        engine: ANALYTIC_ENGINE_TYPE.to_string(),
+        schema_id,
+        table_name: table_name.to_string(),
+    };
+
+    engine
+        .find_table(request)
+        .await
+        .box_err()
+        .context(FindPhysicalTable { table: table_name })?
+        .context(PhysicalTableNotFound { table: table_name })
+}
+
+/// The logical table whose rows are routed to the physical tables by the
+/// value of the routing column.
+pub struct UnionTable {
+    name: String,
+    id: TableId,
+    schema_id: SchemaId,
+    schema: Schema,
+    definition: TableDefinition,
+    /// Engine of the physical tables.
+    engine: TableEngineRef,
+}
+
+impl UnionTable {
+    pub fn new(
+        name: String,
+        id: TableId,
+        schema_id: SchemaId,
+        schema: Schema,
+        definition: TableDefinition,
+        engine: TableEngineRef,
+    ) -> Self {
+        Self {
+            name,
+            id,
+            schema_id,
+            schema,
+            definition,
+            engine,
+        }
+    }
+
+    fn unsupported<T>(&self, method: &str) -> Result<T> {
+        UnsupportedMethod {
+            table: &self.name,
+            method,
+        }
+        .fail()
+    }
+
+    /// Split the rows by the physical tables they are routed to.
+    fn split_rows(&self, mut row_group: RowGroup) -> error::Result<HashMap<&str, RowGroup>> {
+        let schema = row_group.schema().clone();
+        let routing_index = schema::check_routing_column(&schema, &self.definition.routing_column)?;
+
+        let mut split_rows: HashMap<_, Vec<Row>> = HashMap::new();
+        for row in row_group.take_rows() {
+            let datum = &row[routing_index];
+            let table = datum
+                .as_str()
+                .and_then(|value| self.definition.routes.get(value))
+                .with_context(|| NoRoute {
+                    value: format!("{datum:?}"),
+                })?;
+            split_rows.entry(table.as_str()).or_default().push(row);
+        }
+
+        let split_row_groups = split_rows
+            .into_iter()
+            // The rows have been checked by the union table's schema.
+            .map(|(table, rows)| (table, RowGroup::new_unchecked(schema.clone(), rows)))
+            .collect();
+
+        Ok(split_row_groups)
+    }
+}
+
+impl fmt::Debug for UnionTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UnionTable")
+            .field("name", &self.name)
+            .field("id", &self.id)
+            .field("schema", &self.schema)
+            .field("definition", &self.definition)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl Table for UnionTable {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn id(&self) -> TableId {
+        self.id
+    }
+
+    fn schema(&self) -> Schema {
+        self.schema.clone()
+    }
+
+    fn options(&self) -> HashMap<String, String> {
+        self.definition.to_options()
+    }
+
+    fn engine_type(&self) -> &str {
+        UNION_ENGINE_TYPE
+    }
+
+    fn stats(&self) -> TableStats {
+        TableStats::default()
+    }
+
+    // The physical tables may have different options deciding the pushdown.
+    fn support_pushdown(&self, _read_schema: &Schema, _col_names: &[String]) -> bool {
+        false
+    }
+
+    async fn write(&self, request: WriteRequest) -> Result<usize> {
+        let split_row_groups = self
+            .split_rows(request.row_group)
+            .box_err()
+            .context(Write { table: &self.name })?;
+
+        let writes = split_row_groups
+            .into_iter()
+            .map(|(table_name, row_group)| async move {
+                let table = find_physical_table(&self.engine, self.schema_id, table_name)
+                    .await
+                    .box_err()?;
+                table.write(WriteRequest { row_group }).await.box_err()
+            });
+        let num_rows = future::try_join_all(writes)
+            .await
+            .context(Write { table: &self.name })?;
+
+        Ok(num_rows.into_iter().sum())
+    }
+
+    async fn read(&self, _request: ReadRequest) -> Result<SendableRecordBatchStream> {
+        self.unsupported("read")
+    }
+
+    async fn get(&self, _request: GetRequest) -> Result<Option<Row>> {
+        self.unsupported("get")
+    }
+
+    /// The streams of all the physical tables are returned, and the read
+    /// parallelism is shared by the physical tables.
+    async fn partitioned_read(&self, request: ReadRequest) -> Result<PartitionedStreams> {
+        let table_names = self.definition.physical_tables();
+        let read_parallelism = (request.opts.read_parallelism / table_names.len()).max(1);
+        // The physical tables may have more columns than the union table, so the
+        // projection is always set to keep the output same as the union table.
+        let projection = request
+            .projected_schema
+            .projection()
+            .unwrap_or_else(|| (0..self.schema.num_columns()).collect());
+
+        let reads = table_names.into_iter().map(|table_name| {
+            let mut request = request.clone();
+            request.opts.read_parallelism = read_parallelism;
+            let projection = projection.clone();
+            async move {
+                let table = find_physical_table(&self.engine, self.schema_id, table_name)
+                    .await
+                    .box_err()?;
+                request.projected_schema =
+                    ProjectedSchema::new(table.schema(), Some(projection)).box_err()?;
+                table.partitioned_read(request).await.box_err()
+            }
+        });
+        let streams = future::try_join_all(reads)
+            .await
+            .context(Scan { table: &self.name })?
+            .into_iter()
+            .flat_map(|v| v.streams)
+            .collect();
+
+        Ok(PartitionedStreams { streams })
+    }
+
+    async fn alter_schema(&self, _request: AlterSchemaRequest) -> Result<usize> {
+        self.unsupported("alter_schema")
+    }
+
+    async fn alter_options(&self, _options: HashMap<String, String>) -> Result<usize> {
+        self.unsupported("alter_options")
+    }
+
+    // The physical tables are flushed and compacted by themselves.
+    async fn flush(&self, _request: FlushRequest) -> Result<()> {
+        Ok(())
+    }
+
+    async fn compact(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, sync::Arc};
+
+    use async_trait::async_trait;
+    use common_types::{
+        record_batch::RecordBatch,
+        request_id::RequestId,
+        tests::{build_row_for_cpu, build_schema_for_cpu},
+    };
+    use futures::TryStreamExt;
+    use table_engine::{
+        engine::{
+            CloseShardRequest, CloseTableRequest, ClosedTable, CreateTableParams,
+            CreateTableRequest, DropTableRequest, OpenShardRequest, OpenShardResult,
+            OpenTableRequest, TableEngine,
+        },
+        memory::MemoryTable,
+        predicate::Predicate,
+        table::ReadOptions,
+    };
+    use trace_metric::MetricsCollector;
+
+    use super::*;
+    use crate::error::Error;
+
+    /// Engine holding the physical tables, only finding tables is supported.
+    struct MockEngine {
+        tables: HashMap<String, TableRef>,
+    }
+
+    #[async_trait]
+    impl TableEngine for MockEngine {
+        fn engine_type(&self) -> &str {
+            ANALYTIC_ENGINE_TYPE
+        }
+
+        async fn close(&self) -> table_engine::engine::Result<()> {
+            Ok(())
+        }
+
+        async fn validate_create_table(
+            &self,
+            _request: &CreateTableParams,
+        ) -> table_engine::engine::Result<()> {
+            unimplemented!()
+        }
+
+        async fn create_table(
+            &self,
+            _request: CreateTableRequest,
+        ) -> table_engine::engine::Result<TableRef> {
+            unimplemented!()
+        }
+
+        async fn drop_table(
+            &self,
+            _request: DropTableRequest,
+        ) -> table_engine::engine::Result<bool> {
+            unimplemented!()
+        }
+
+        async fn open_table(
+            &self,
+            _request: OpenTableRequest,
+        ) -> table_engine::engine::Result<Option<TableRef>> {
+            unimplemented!()
+        }
+
+        async fn close_table(
+            &self,
+            _request: CloseTableRequest,
+        ) -> table_engine::engine::Result<()> {
+            unimplemented!()
+        }
+
+        async fn open_shard(
+            &self,
+            _request: OpenShardRequest,
+        ) -> table_engine::engine::Result<OpenShardResult> {
+            unimplemented!()
+        }
+
+        async fn close_shard(
+            &self,
+            _request: CloseShardRequest,
+        ) -> Vec<table_engine::engine::Result<ClosedTable>> {
+            unimplemented!()
+        }
+
+        async fn find_table(
+            &self,
+            request: FindTableRequest,
+        ) -> table_engine::engine::Result<Option<TableRef>> {
+            Ok(self.tables.get(&request.table_name).cloned())
+        }
+    }
+
+    const ROUTING_COLUMN: &str = "tag1";
+
+    /// Build a union table routing the rows of `a` to `t1` and the rows of `b`
+    /// to `t2`, and returns the physical tables too.
+    fn new_union_table() -> (UnionTable, TableRef, TableRef) {
+        let schema = build_schema_for_cpu();
+        let new_physical_table = |name: &str, id: u64| -> TableRef {
+            Arc::new(MemoryTable::new(
+                name.to_string(),
+                TableId::from(id),
+                schema.clone(),
+                ANALYTIC_ENGINE_TYPE.to_string(),
+            ))
+        };
+        let t1 = new_physical_table("t1", 1);
+        let t2 = new_physical_table("t2", 2);
+        let engine = Arc::new(MockEngine {
+            tables: HashMap::from([
+                ("t1".to_string(), t1.clone()),
+                ("t2".to_string(), t2.clone()),
+            ]),
+        });
+
+        let definition = TableDefinition {
+            routing_column: ROUTING_COLUMN.to_string(),
+            routes: BTreeMap::from([
+                ("a".to_string(), "t1".to_string()),
+                ("b".to_string(), "t2".to_string()),
+            ]),
+        };
+        let table = UnionTable::new(
+            "union_table".to_string(),
+            TableId::from(3),
+            SchemaId::from(0),
+            schema,
+            definition,
+            engine,
+        );
+
+        (table, t1, t2)
+    }
+
+    fn new_row_group(routing_values: &[&str]) -> RowGroup {
+        let rows = routing_values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| build_row_for_cpu(i as u64, 1000 + i as i64, value, "tag2", 1, 1.0))
+            .collect();
+        RowGroup::try_new(build_schema_for_cpu(), rows).unwrap()
+    }
+
+    fn routing_values_of_rows(row_group: &RowGroup, routing_index: usize) -> Vec<String> {
+        row_group
+            .iter()
+            .map(|row| row[routing_index].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    fn new_read_request(schema: Schema, read_parallelism: usize) -> ReadRequest {
+        ReadRequest {
+            request_id: RequestId::next_id(),
+            opts: ReadOptions {
+                read_parallelism,
+                ..Default::default()
+            },
+            projected_schema: ProjectedSchema::no_projection(schema),
+            predicate: Arc::new(Predicate::empty()),
+            metrics_collector: MetricsCollector::default(),
+            priority: Default::default(),
+        }
+    }
+
+    /// Read all the rows of the table, returns the routing values of the rows.
+    async fn read_routing_values(table: &dyn Table) -> Vec<String> {
+        let schema = table.schema();
+        let routing_index = schema.index_of(ROUTING_COLUMN).unwrap();
+        let request = new_read_request(schema, 4);
+        let streams = table.partitioned_read(request).await.unwrap().streams;
+
+        let mut values = Vec::new();
+        for stream in streams {
+            let batches: Vec<RecordBatch> = stream.try_collect().await.unwrap();
+            for batch in batches {
+                for i in 0..batch.num_rows() {
+                    let datum = batch.column(routing_index).datum(i);
+                    values.push(datum.as_str().unwrap().to_string());
+                }
+            }
+        }
+        values.sort_unstable();
+
+        values
+    }
+
+    #[test]
+    fn test_split_rows() {
+        let (table, _, _) = new_union_table();
+        let routing_index = table.schema.index_of(ROUTING_COLUMN).unwrap();
+
+        let split_row_groups = table.split_rows(new_row_group(&["a", "b", "a"])).unwrap();
+        assert_eq!(2, split_row_groups.len());
+        assert_eq!(
+            vec!["a", "a"],
+            routing_values_of_rows(&split_row_groups["t1"], routing_index)
+        );
+        assert_eq!(
+            vec!["b"],
+            routing_values_of_rows(&split_row_groups["t2"], routing_index)
+        );
+
+        let err = table
+            .split_rows(new_row_group(&["a", "unknown"]))
+            .unwrap_err();
+        assert!(matches!(err, Error::NoRoute { .. }), "err:{err}");
+    }
+
+    #[tokio::test]
+    async fn test_write_to_routed_tables() {
+        let (table, t1, t2) = new_union_table();
+
+        let num_rows = table
+            .write(WriteRequest {
+                row_group: new_row_group(&["a", "b", "a", "b", "b"]),
+            })
+            .await
+            .unwrap();
+        assert_eq!(5, num_rows);
+        assert_eq!(vec!["a", "a"], read_routing_values(t1.as_ref()).await);
+        assert_eq!(vec!["b", "b", "b"], read_routing_values(t2.as_ref()).await);
+
+        // Nothing is written if any row can't be routed.
+        assert!(table
+            .write(WriteRequest {
+                row_group: new_row_group(&["a", "unknown"]),
+            })
+            .await
+            .is_err());
+        assert_eq!(vec!["a", "a"], read_routing_values(t1.as_ref()).await);
+    }
+
+    #[tokio::test]
+    async fn test_partitioned_read_all_tables() {
+        let (table, t1, t2) = new_union_table();
+        t1.write(WriteRequest {
+            row_group: new_row_group(&["a", "a"]),
+        })
+        .await
+        .unwrap();
+        t2.write(WriteRequest {
+            row_group: new_row_group(&["b"]),
+        })
+        .await
+        .unwrap();
+
+        let request = new_read_request(table.schema(), 4);
+        let streams = table.partitioned_read(request).await.unwrap().streams;
+        // One stream of each memory table.
+        assert_eq!(2, streams.len());
+
+        assert_eq!(vec!["a", "a", "b"], read_routing_values(&table).await);
+    }
+}
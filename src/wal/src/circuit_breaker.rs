@@ -0,0 +1,159 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Wal manager guarded by a circuit breaker.
+
+use std::{future::Future, sync::Arc};
+
+use async_trait::async_trait;
+use circuit_breaker::CircuitBreaker;
+use common_types::SequenceNumber;
+use snafu::ResultExt;
+
+use crate::{
+    log_batch::LogWriteBatch,
+    manager::{
+        BatchLogIteratorAdapter, CircuitBreakerOpen, Error, OpenedWals, ReadContext, ReadRequest,
+        RegionId, RegionLogsSummary, Result, ScanContext, ScanRequest, WalLocation, WalManager,
+        WalManagerRef, WriteContext,
+    },
+};
+
+/// A wal manager wrapper failing fast when the wal backend keeps failing.
+///
+/// Only the operations accessing the backend are guarded, and closing the wal
+/// is always allowed.
+#[derive(Debug)]
+pub struct WalManagerWithCircuitBreaker {
+    wal: WalManagerRef,
+    breaker: CircuitBreaker,
+}
+
+impl WalManagerWithCircuitBreaker {
+    pub fn new(
+        wal: WalManagerRef,
+        dependency: impl Into<String>,
+        config: circuit_breaker::Config,
+    ) -> Self {
+        Self {
+            wal,
+            breaker: CircuitBreaker::new(dependency, config),
+        }
+    }
+
+    async fn call<T>(&self, f: impl Future<Output = Result<T>>) -> Result<T> {
+        self.breaker.acquire().context(CircuitBreakerOpen)?;
+        let res = f.await;
+        let success = match &res {
+            Ok(_) => true,
+            Err(e) => !is_backend_failure(e),
+        };
+        self.breaker.on_result(success);
+        res
+    }
+}
+
+/// Only the errors from the backend should trip the breaker.
+fn is_backend_failure(e: &Error) -> bool {
+    matches!(
+        e,
+        Error::Write { .. }
+            | Error::Read { .. }
+            | Error::Delete { .. }
+            | Error::RuntimeExec { .. }
+            | Error::Unknown { .. }
+    )
+}
+
+#[async_trait]
+impl WalManager for WalManagerWithCircuitBreaker {
+    async fn sequence_num(&self, location: WalLocation) -> Result<SequenceNumber> {
+        self.call(self.wal.sequence_num(location)).await
+    }
+
+    async fn mark_delete_entries_up_to(
+        &self,
+        location: WalLocation,
+        sequence_num: SequenceNumber,
+    ) -> Result<()> {
+        self.call(self.wal.mark_delete_entries_up_to(location, sequence_num))
+            .await
+    }
+
+    async fn close_region(&self, region: RegionId) -> Result<()> {
+        self.wal.close_region(region).await
+    }
+
+    async fn close_gracefully(&self) -> Result<()> {
+        self.wal.close_gracefully().await
+    }
+
+    async fn read_batch(
+        &self,
+        ctx: &ReadContext,
+        req: &ReadRequest,
+    ) -> Result<BatchLogIteratorAdapter> {
+        self.call(self.wal.read_batch(ctx, req)).await
+    }
+
+    async fn write(&self, ctx: &WriteContext, batch: &LogWriteBatch) -> Result<SequenceNumber> {
+        self.call(self.wal.write(ctx, batch)).await
+    }
+
+    async fn write_atomically(
+        &self,
+        ctx: &WriteContext,
+        batches: &[LogWriteBatch],
+    ) -> Result<Vec<SequenceNumber>> {
+        self.call(self.wal.write_atomically(ctx, batches)).await
+    }
+
+    async fn scan(&self, ctx: &ScanContext, req: &ScanRequest) -> Result<BatchLogIteratorAdapter> {
+        self.call(self.wal.scan(ctx, req)).await
+    }
+
+    async fn get_statistics(&self) -> Option<String> {
+        self.wal.get_statistics().await
+    }
+
+    async fn summarize_regions(&self) -> Result<Option<Vec<RegionLogsSummary>>> {
+        self.call(self.wal.summarize_regions()).await
+    }
+}
+
+impl OpenedWals {
+    /// Guard both the data wal and the manifest wal by their own circuit
+    /// breakers if enabled.
+    pub fn with_circuit_breaker(self, config: &circuit_breaker::Config) -> Self {
+        if !config.enable {
+            return self;
+        }
+
+        Self {
+            data_wal: Arc::new(WalManagerWithCircuitBreaker::new(
+                self.data_wal,
+                "data_wal",
+                config.clone(),
+            )),
+            manifest_wal: Arc::new(WalManagerWithCircuitBreaker::new(
+                self.manifest_wal,
+                "manifest_wal",
+                config.clone(),
+            )),
+        }
+    }
+}
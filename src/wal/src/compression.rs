@@ -0,0 +1,306 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Compression of the wal logs.
+//!
+//! The compressed log values are marked by their value header, so the logs
+//! written before the compression is enabled remain readable.
+
+use std::{borrow::Cow, sync::Arc};
+
+use async_trait::async_trait;
+use common_types::SequenceNumber;
+use generic_error::{BoxError, GenericError};
+use macros::define_result;
+use serde::{Deserialize, Serialize};
+use snafu::{Backtrace, OptionExt, ResultExt, Snafu};
+
+use crate::{
+    config::{Config as WalConfig, StorageConfig},
+    kv_encoder::{LOG_VALUE_ENCODING_COMPRESSED, NEWEST_LOG_VALUE_ENCODING_VERSION},
+    log_batch::{LogWriteBatch, LogWriteEntry},
+    manager::{
+        self, BatchLogIteratorAdapter, Encoding, OpenedWals, ReadContext, ReadRequest, RegionId,
+        RegionLogsSummary, ScanContext, ScanRequest, WalLocation, WalManager, WalManagerRef,
+        WriteContext,
+    },
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Compression codec of log value is missing.\nBacktrace:\n{backtrace}"))]
+    MissingCodec { backtrace: Backtrace },
+
+    #[snafu(display("Unknown compression codec, codec:{codec}.\nBacktrace:\n{backtrace}"))]
+    UnknownCodec { codec: u8, backtrace: Backtrace },
+
+    #[snafu(display("Failed to compress log value, codec:{codec:?}, err:{source}"))]
+    Compress { codec: Codec, source: GenericError },
+
+    #[snafu(display("Failed to decompress log value, codec:{codec:?}, err:{source}"))]
+    Decompress { codec: Codec, source: GenericError },
+}
+
+define_result!(Error);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Codec {
+    #[default]
+    None,
+    Lz4,
+    Zstd,
+}
+
+impl Codec {
+    fn to_u8(self) -> u8 {
+        match self {
+            Codec::None => 0,
+            Codec::Lz4 => 1,
+            Codec::Zstd => 2,
+        }
+    }
+
+    fn from_u8(v: u8) -> Option<Self> {
+        match v {
+            0 => Some(Codec::None),
+            1 => Some(Codec::Lz4),
+            2 => Some(Codec::Zstd),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    /// Codec to compress the log batches, no compression by default
+    pub codec: Codec,
+    /// Compression level, only used by zstd
+    pub level: i32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            codec: Codec::None,
+            level: 3,
+        }
+    }
+}
+
+/// Compress the encoded log `value`, returns None if the compressed one isn't
+/// smaller.
+///
+/// Compressed value format:
+/// +--------------------+-----------+--------------------+
+/// | version_header(u8) | codec(u8) | compressed payload |
+/// +--------------------+-----------+--------------------+
+pub fn compress_value(config: &Config, value: &[u8]) -> Result<Option<Vec<u8>>> {
+    // Only the values in the newest encoding are compressed.
+    let Some((&NEWEST_LOG_VALUE_ENCODING_VERSION, payload)) = value.split_first() else {
+        return Ok(None);
+    };
+
+    let codec = config.codec;
+    let compressed = match codec {
+        Codec::None => return Ok(None),
+        Codec::Lz4 => lz4_flex::block::compress_prepend_size(payload),
+        Codec::Zstd => zstd::bulk::compress(payload, config.level)
+            .box_err()
+            .context(Compress { codec })?,
+    };
+    if compressed.len() + 2 >= value.len() {
+        return Ok(None);
+    }
+
+    let mut buf = Vec::with_capacity(compressed.len() + 2);
+    buf.push(LOG_VALUE_ENCODING_COMPRESSED);
+    buf.push(codec.to_u8());
+    buf.extend_from_slice(&compressed);
+    Ok(Some(buf))
+}
+
+/// Decompress the payload of the compressed log value, `buf` is the value
+/// without the version header.
+pub fn decompress_payload(buf: &[u8]) -> Result<Cow<'_, [u8]>> {
+    let (&codec, payload) = buf.split_first().context(MissingCodec)?;
+    let codec = Codec::from_u8(codec).context(UnknownCodec { codec })?;
+
+    let decompressed = match codec {
+        Codec::None => return Ok(Cow::Borrowed(payload)),
+        Codec::Lz4 => lz4_flex::block::decompress_size_prepended(payload)
+            .box_err()
+            .context(Decompress { codec })?,
+        Codec::Zstd => zstd::stream::decode_all(payload)
+            .box_err()
+            .context(Decompress { codec })?,
+    };
+    Ok(Cow::Owned(decompressed))
+}
+
+/// A wal manager wrapper compressing the log batches before writing them into
+/// the backend.
+///
+/// The logs are decompressed by the backends when they are read.
+#[derive(Debug)]
+pub struct WalManagerWithCompression {
+    wal: WalManagerRef,
+    config: Config,
+}
+
+impl WalManagerWithCompression {
+    pub fn new(wal: WalManagerRef, config: Config) -> Self {
+        Self { wal, config }
+    }
+
+    fn compress_batch(&self, batch: &LogWriteBatch) -> manager::Result<LogWriteBatch> {
+        let mut compressed = LogWriteBatch::with_capacity(batch.location, batch.len());
+        for entry in &batch.entries {
+            let payload = compress_value(&self.config, &entry.payload)
+                .box_err()
+                .context(Encoding)?
+                .unwrap_or_else(|| entry.payload.clone());
+            compressed.push(LogWriteEntry { payload });
+        }
+
+        Ok(compressed)
+    }
+}
+
+#[async_trait]
+impl WalManager for WalManagerWithCompression {
+    async fn sequence_num(&self, location: WalLocation) -> manager::Result<SequenceNumber> {
+        self.wal.sequence_num(location).await
+    }
+
+    async fn mark_delete_entries_up_to(
+        &self,
+        location: WalLocation,
+        sequence_num: SequenceNumber,
+    ) -> manager::Result<()> {
+        self.wal
+            .mark_delete_entries_up_to(location, sequence_num)
+            .await
+    }
+
+    async fn close_region(&self, region: RegionId) -> manager::Result<()> {
+        self.wal.close_region(region).await
+    }
+
+    async fn close_gracefully(&self) -> manager::Result<()> {
+        self.wal.close_gracefully().await
+    }
+
+    async fn read_batch(
+        &self,
+        ctx: &ReadContext,
+        req: &ReadRequest,
+    ) -> manager::Result<BatchLogIteratorAdapter> {
+        self.wal.read_batch(ctx, req).await
+    }
+
+    async fn write(
+        &self,
+        ctx: &WriteContext,
+        batch: &LogWriteBatch,
+    ) -> manager::Result<SequenceNumber> {
+        let batch = self.compress_batch(batch)?;
+        self.wal.write(ctx, &batch).await
+    }
+
+    async fn write_atomically(
+        &self,
+        ctx: &WriteContext,
+        batches: &[LogWriteBatch],
+    ) -> manager::Result<Vec<SequenceNumber>> {
+        let batches = batches
+            .iter()
+            .map(|batch| self.compress_batch(batch))
+            .collect::<manager::Result<Vec<_>>>()?;
+        self.wal.write_atomically(ctx, &batches).await
+    }
+
+    async fn scan(
+        &self,
+        ctx: &ScanContext,
+        req: &ScanRequest,
+    ) -> manager::Result<BatchLogIteratorAdapter> {
+        self.wal.scan(ctx, req).await
+    }
+
+    async fn get_statistics(&self) -> Option<String> {
+        self.wal.get_statistics().await
+    }
+
+    async fn summarize_regions(&self) -> manager::Result<Option<Vec<RegionLogsSummary>>> {
+        self.wal.summarize_regions().await
+    }
+}
+
+impl OpenedWals {
+    /// Compress the logs of both the data wal and the manifest wal if enabled.
+    ///
+    /// The memory wal keeps the logs without decoding them, so it's never
+    /// compressed.
+    pub fn with_compression(self, config: &WalConfig) -> Self {
+        if config.compression.codec == Codec::None
+            || matches!(config.storage, StorageConfig::Memory(_))
+        {
+            return self;
+        }
+
+        Self {
+            data_wal: Arc::new(WalManagerWithCompression::new(
+                self.data_wal,
+                config.compression.clone(),
+            )),
+            manifest_wal: Arc::new(WalManagerWithCompression::new(
+                self.manifest_wal,
+                config.compression.clone(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compress_value() {
+        let mut value = vec![NEWEST_LOG_VALUE_ENCODING_VERSION];
+        value.extend(std::iter::repeat(b"horaedb").take(100).flatten());
+
+        for codec in [Codec::Lz4, Codec::Zstd] {
+            let config = Config { codec, level: 3 };
+            let compressed = compress_value(&config, &value).unwrap().unwrap();
+            assert!(compressed.len() < value.len());
+            assert_eq!(compressed[0], LOG_VALUE_ENCODING_COMPRESSED);
+
+            let decompressed = decompress_payload(&compressed[1..]).unwrap();
+            assert_eq!(&decompressed[..], &value[1..]);
+        }
+
+        // Incompressible values are kept as is.
+        let config = Config {
+            codec: Codec::Zstd,
+            level: 3,
+        };
+        let value = vec![NEWEST_LOG_VALUE_ENCODING_VERSION, 1, 2, 3];
+        assert!(compress_value(&config, &value).unwrap().is_none());
+    }
+}
@@ -15,8 +15,12 @@
 // specific language governing permissions and limitations
 // under the License.
 
+use std::path::{Path, PathBuf};
+
 use serde::{Deserialize, Serialize};
 
+use crate::manager::{MANIFEST_DIR_NAME, WAL_DIR_NAME};
+
 #[cfg(feature = "wal-rocksdb")]
 pub type RocksDBStorageConfig = crate::rocksdb_impl::config::RocksDBStorageConfig;
 #[cfg(not(feature = "wal-rocksdb"))]
@@ -41,6 +45,14 @@ pub type LocalStorageConfig = crate::local_storage_impl::config::LocalStorageCon
 #[derive(Debug, Default, Clone, Deserialize, Serialize)]
 pub struct LocalStorageConfig;
 
+#[cfg(feature = "wal-object-store")]
+pub type ObjectStoreStorageConfig = crate::object_store_impl::config::ObjectStoreStorageConfig;
+#[cfg(not(feature = "wal-object-store"))]
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct ObjectStoreStorageConfig;
+
+pub type MemoryStorageConfig = crate::memory_impl::config::MemoryStorageConfig;
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Config {
     // The flatten attribute inlines keys from a field into the parent struct.
@@ -51,6 +63,15 @@ pub struct Config {
     // Note: this is only used for test, we shouldn't enable this in production.
     #[serde(default)]
     pub disable_data: bool,
+    /// Circuit breaker of the wal backend.
+    #[serde(default)]
+    pub circuit_breaker: circuit_breaker::Config,
+    /// Compression of the log batches.
+    #[serde(default)]
+    pub compression: crate::compression::Config,
+    /// Group commit of the writes to the same region.
+    #[serde(default)]
+    pub group_commit: crate::group_commit::Config,
 }
 
 impl Default for Config {
@@ -58,10 +79,33 @@ impl Default for Config {
         Self {
             storage: StorageConfig::RocksDB(Box::default()),
             disable_data: false,
+            circuit_breaker: Default::default(),
+            compression: Default::default(),
+            group_commit: Default::default(),
         }
     }
 }
 
+impl Config {
+    /// The local directories holding the wal and manifest logs, empty if the
+    /// logs are not stored on the local disk.
+    pub fn local_dirs(&self) -> Vec<PathBuf> {
+        let data_dir: &str = match &self.storage {
+            #[cfg(feature = "wal-rocksdb")]
+            StorageConfig::RocksDB(config) => &config.data_dir,
+            #[cfg(feature = "wal-local-storage")]
+            StorageConfig::Local(config) => &config.data_dir,
+            _ => return Vec::new(),
+        };
+
+        let data_dir = Path::new(data_dir);
+        vec![
+            data_dir.join(WAL_DIR_NAME),
+            data_dir.join(MANIFEST_DIR_NAME),
+        ]
+    }
+}
+
 /// Options for wal storage backend
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(tag = "type")]
@@ -70,4 +114,21 @@ pub enum StorageConfig {
     Obkv(Box<ObkvStorageConfig>),
     Kafka(Box<KafkaStorageConfig>),
     Local(Box<LocalStorageConfig>),
+    ObjectStore(Box<ObjectStoreStorageConfig>),
+    /// Keep all the logs in memory, only for tests and ephemeral tables.
+    Memory(MemoryStorageConfig),
+}
+
+impl StorageConfig {
+    /// Whether the wal supports writing the log batches of multiple tables on
+    /// the same shard atomically.
+    pub fn support_atomic_write(&self) -> bool {
+        match self {
+            StorageConfig::RocksDB(_)
+            | StorageConfig::Local(_)
+            | StorageConfig::ObjectStore(_)
+            | StorageConfig::Memory(_) => true,
+            StorageConfig::Obkv(_) | StorageConfig::Kafka(_) => false,
+        }
+    }
 }
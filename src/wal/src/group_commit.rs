@@ -0,0 +1,662 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Group commit of the writes to the same region.
+//!
+//! Writes to different tables of the same region are issued to the backend
+//! separately, which is expensive for a shard with many small tables.
+//! [GroupBatcher] accumulates the items submitted with the same key in a small
+//! time window, and commits them together by a [GroupCommitter].
+//! [WalManagerWithGroupCommit] groups the log batches of the writes to the same
+//! region, and persists them by one atomic write.
+
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    hash::Hash,
+    mem,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use common_types::SequenceNumber;
+use generic_error::{BoxError, GenericResult};
+use logger::debug;
+use macros::define_result;
+use runtime::Runtime;
+use serde::{Deserialize, Serialize};
+use size_ext::ReadableSize;
+use snafu::{ResultExt, Snafu};
+use time_ext::ReadableDuration;
+use tokio::sync::oneshot;
+
+use crate::{
+    config::{Config as WalConfig, StorageConfig},
+    log_batch::LogWriteBatch,
+    manager::{
+        self, BatchLogIteratorAdapter, OpenedWals, ReadContext, ReadRequest, RegionId,
+        RegionLogsSummary, ScanContext, ScanRequest, WalLocation, WalManager, WalManagerRef, Write,
+        WriteContext,
+    },
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Failed to commit the group, key:{key}, msg:{msg}"))]
+    Commit { key: String, msg: String },
+
+    #[snafu(display("Group is dropped, key:{key}, err:{source}"))]
+    Dropped {
+        key: String,
+        source: oneshot::error::RecvError,
+    },
+}
+
+define_result!(Error);
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    /// Time window to group the writes to the same region, zero disables the
+    /// group commit
+    pub window: ReadableDuration,
+    /// Max number of log batches in a group, the group is committed at once if
+    /// it's full
+    pub max_batches: usize,
+    /// Max payload size of a group, the group is committed at once if it's
+    /// full
+    pub max_bytes: ReadableSize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            window: ReadableDuration::millis(0),
+            max_batches: 128,
+            max_bytes: ReadableSize::mb(4),
+        }
+    }
+}
+
+impl Config {
+    fn is_enabled(&self) -> bool {
+        !self.window.is_zero() && self.max_batches > 1
+    }
+
+    fn limits(&self) -> GroupLimits {
+        GroupLimits {
+            window: self.window.0,
+            max_entries: self.max_batches,
+            max_bytes: self.max_bytes.as_byte() as usize,
+        }
+    }
+}
+
+/// Limits of the groups of a [GroupBatcher].
+#[derive(Debug, Clone, Copy)]
+pub struct GroupLimits {
+    /// Time window to accumulate the items of a group.
+    pub window: Duration,
+    /// Max number of entries of a group.
+    pub max_entries: usize,
+    /// Max bytes of a group.
+    pub max_bytes: usize,
+}
+
+/// Commit the items accumulated in a group.
+#[async_trait]
+pub trait GroupCommitter: Send + Sync + 'static {
+    type Key: Clone + Debug + Eq + Hash + Send + Sync + 'static;
+    type Item: Send + 'static;
+    type Output: Send + 'static;
+
+    /// Returns the number of entries and bytes of the item, which are counted
+    /// against the [GroupLimits].
+    fn size_of(item: &Self::Item) -> (usize, usize);
+
+    /// Commit the items of a group, either all of them or none of them should
+    /// be committed.
+    ///
+    /// Returns the output of every item in the order of `items`.
+    async fn commit(
+        &self,
+        key: &Self::Key,
+        items: Vec<Self::Item>,
+    ) -> GenericResult<Vec<Self::Output>>;
+}
+
+/// Accumulate the items submitted with the same key into groups.
+///
+/// The first item arriving at a key without pending group schedules the commit
+/// of a new group after the window, and the items arriving before the commit
+/// join the group. A group is committed at once if it reaches the limits, and
+/// an item which can't fit into the pending group is put into a new group, so
+/// a group exceeds the limits only if it holds a single item.
+pub struct GroupBatcher<C: GroupCommitter> {
+    inner: Arc<BatcherInner<C>>,
+    limits: GroupLimits,
+    runtime: Arc<Runtime>,
+}
+
+struct BatcherInner<C: GroupCommitter> {
+    committer: C,
+    /// Pending groups of the keys.
+    groups: Mutex<HashMap<C::Key, PendingGroup<C>>>,
+    /// Id allocated to the next group.
+    next_group_id: AtomicU64,
+}
+
+struct PendingGroup<C: GroupCommitter> {
+    id: u64,
+    items: Vec<C::Item>,
+    num_entries: usize,
+    num_bytes: usize,
+    waiters: Vec<oneshot::Sender<Result<C::Output>>>,
+}
+
+impl<C: GroupCommitter> PendingGroup<C> {
+    fn new(id: u64) -> Self {
+        Self {
+            id,
+            items: Vec::new(),
+            num_entries: 0,
+            num_bytes: 0,
+            waiters: Vec::new(),
+        }
+    }
+
+    fn can_hold(&self, num_entries: usize, num_bytes: usize, limits: &GroupLimits) -> bool {
+        self.items.is_empty()
+            || (self.num_entries + num_entries <= limits.max_entries
+                && self.num_bytes + num_bytes <= limits.max_bytes)
+    }
+
+    fn is_full(&self, limits: &GroupLimits) -> bool {
+        self.num_entries >= limits.max_entries || self.num_bytes >= limits.max_bytes
+    }
+}
+
+impl<C: GroupCommitter> GroupBatcher<C> {
+    pub fn new(committer: C, limits: GroupLimits, runtime: Arc<Runtime>) -> Self {
+        let inner = BatcherInner {
+            committer,
+            groups: Mutex::new(HashMap::new()),
+            next_group_id: AtomicU64::new(0),
+        };
+
+        Self {
+            inner: Arc::new(inner),
+            limits,
+            runtime,
+        }
+    }
+
+    #[inline]
+    pub fn committer(&self) -> &C {
+        &self.inner.committer
+    }
+
+    /// Commit the item with the ones of the same key in the window.
+    pub async fn submit(&self, key: C::Key, item: C::Item) -> Result<C::Output> {
+        let (num_entries, num_bytes) = C::size_of(&item);
+        let (tx, rx) = oneshot::channel();
+        let mut full_groups = Vec::with_capacity(2);
+        let to_schedule = {
+            let mut groups = self.inner.groups.lock().unwrap();
+            if let Some(group) = groups.get(&key) {
+                if !group.can_hold(num_entries, num_bytes, &self.limits) {
+                    full_groups.extend(groups.remove(&key));
+                }
+            }
+
+            let group = groups
+                .entry(key.clone())
+                .or_insert_with(|| PendingGroup::new(self.inner.alloc_group_id()));
+            let to_schedule = group.waiters.is_empty().then_some(group.id);
+            group.items.push(item);
+            group.num_entries += num_entries;
+            group.num_bytes += num_bytes;
+            group.waiters.push(tx);
+
+            if group.is_full(&self.limits) {
+                full_groups.extend(groups.remove(&key));
+                None
+            } else {
+                to_schedule
+            }
+        };
+
+        // Commit in background tasks so the group is still committed even if the
+        // submitter scheduling it is cancelled.
+        for group in full_groups {
+            let inner = self.inner.clone();
+            let key = key.clone();
+            self.runtime.spawn(async move {
+                inner.commit(&key, group).await;
+            });
+        }
+        if let Some(group_id) = to_schedule {
+            let inner = self.inner.clone();
+            let key = key.clone();
+            let window = self.limits.window;
+            self.runtime.spawn(async move {
+                tokio::time::sleep(window).await;
+                // The group may be already committed as it's full.
+                let group = {
+                    let mut groups = inner.groups.lock().unwrap();
+                    match groups.get(&key) {
+                        Some(group) if group.id == group_id => groups.remove(&key),
+                        _ => None,
+                    }
+                };
+                if let Some(group) = group {
+                    inner.commit(&key, group).await;
+                }
+            });
+        }
+
+        rx.await.context(Dropped {
+            key: format!("{key:?}"),
+        })?
+    }
+}
+
+impl<C: GroupCommitter> BatcherInner<C> {
+    fn alloc_group_id(&self) -> u64 {
+        self.next_group_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    async fn commit(&self, key: &C::Key, group: PendingGroup<C>) {
+        let PendingGroup {
+            items,
+            num_entries,
+            num_bytes,
+            waiters,
+            ..
+        } = group;
+
+        debug!(
+            "Commit group, key:{key:?}, items:{}, entries:{num_entries}, bytes:{num_bytes}",
+            items.len()
+        );
+
+        // The items are released before notifying the submitters.
+        let msg = match self.committer.commit(key, items).await {
+            Ok(outputs) if outputs.len() == waiters.len() => {
+                for (tx, output) in waiters.into_iter().zip(outputs) {
+                    // The submitter may be cancelled, just ignore it.
+                    let _ = tx.send(Ok(output));
+                }
+                return;
+            }
+            Ok(outputs) => format!(
+                "unexpected number of outputs, expect:{}, given:{}",
+                waiters.len(),
+                outputs.len()
+            ),
+            Err(e) => e.to_string(),
+        };
+
+        for tx in waiters {
+            let _ = tx.send(
+                Commit {
+                    key: format!("{key:?}"),
+                    msg: &msg,
+                }
+                .fail(),
+            );
+        }
+    }
+}
+
+/// Commit the log batches of a region by one atomic write.
+struct WalCommitter {
+    wal: WalManagerRef,
+}
+
+#[async_trait]
+impl GroupCommitter for WalCommitter {
+    type Item = (WriteContext, LogWriteBatch);
+    type Key = RegionId;
+    type Output = SequenceNumber;
+
+    fn size_of((_, batch): &Self::Item) -> (usize, usize) {
+        let num_bytes = batch.entries.iter().map(|entry| entry.payload.len()).sum();
+        (1, num_bytes)
+    }
+
+    async fn commit(
+        &self,
+        _region_id: &RegionId,
+        items: Vec<Self::Item>,
+    ) -> GenericResult<Vec<SequenceNumber>> {
+        // The context of the first write is used for the group.
+        let ctx = items[0].0.clone();
+        let mut batches: Vec<_> = items.into_iter().map(|(_, batch)| batch).collect();
+        let res = if batches.len() == 1 {
+            self.wal.write(&ctx, &batches[0]).await.map(|seq| vec![seq])
+        } else {
+            self.wal.write_atomically(&ctx, &batches).await
+        };
+        // Release the payloads before notifying the writers.
+        mem::take(&mut batches);
+
+        res.box_err()
+    }
+}
+
+/// A wal manager wrapper committing the concurrent writes to the same region
+/// in groups.
+///
+/// The batches of a group are persisted by [WalManager::write_atomically], so
+/// the wrapped wal must support the atomic write of the batches in the same
+/// region.
+pub struct WalManagerWithGroupCommit {
+    batcher: GroupBatcher<WalCommitter>,
+    config: Config,
+}
+
+impl std::fmt::Debug for WalManagerWithGroupCommit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WalManagerWithGroupCommit")
+            .field("wal", &self.wal())
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+impl WalManagerWithGroupCommit {
+    pub fn new(wal: WalManagerRef, config: Config, runtime: Arc<Runtime>) -> Self {
+        let batcher = GroupBatcher::new(WalCommitter { wal }, config.limits(), runtime);
+
+        Self { batcher, config }
+    }
+
+    #[inline]
+    fn wal(&self) -> &WalManagerRef {
+        &self.batcher.committer().wal
+    }
+}
+
+#[async_trait]
+impl WalManager for WalManagerWithGroupCommit {
+    async fn sequence_num(&self, location: WalLocation) -> manager::Result<SequenceNumber> {
+        self.wal().sequence_num(location).await
+    }
+
+    async fn mark_delete_entries_up_to(
+        &self,
+        location: WalLocation,
+        sequence_num: SequenceNumber,
+    ) -> manager::Result<()> {
+        self.wal()
+            .mark_delete_entries_up_to(location, sequence_num)
+            .await
+    }
+
+    async fn close_region(&self, region: RegionId) -> manager::Result<()> {
+        self.wal().close_region(region).await
+    }
+
+    async fn close_gracefully(&self) -> manager::Result<()> {
+        self.wal().close_gracefully().await
+    }
+
+    async fn read_batch(
+        &self,
+        ctx: &ReadContext,
+        req: &ReadRequest,
+    ) -> manager::Result<BatchLogIteratorAdapter> {
+        self.wal().read_batch(ctx, req).await
+    }
+
+    async fn write(
+        &self,
+        ctx: &WriteContext,
+        batch: &LogWriteBatch,
+    ) -> manager::Result<SequenceNumber> {
+        self.batcher
+            .submit(batch.location.region_id, (ctx.clone(), batch.clone()))
+            .await
+            .box_err()
+            .context(Write)
+    }
+
+    async fn write_atomically(
+        &self,
+        ctx: &WriteContext,
+        batches: &[LogWriteBatch],
+    ) -> manager::Result<Vec<SequenceNumber>> {
+        self.wal().write_atomically(ctx, batches).await
+    }
+
+    async fn scan(
+        &self,
+        ctx: &ScanContext,
+        req: &ScanRequest,
+    ) -> manager::Result<BatchLogIteratorAdapter> {
+        self.wal().scan(ctx, req).await
+    }
+
+    async fn get_statistics(&self) -> Option<String> {
+        self.wal().get_statistics().await
+    }
+
+    async fn summarize_regions(&self) -> manager::Result<Option<Vec<RegionLogsSummary>>> {
+        self.wal().summarize_regions().await
+    }
+}
+
+impl OpenedWals {
+    /// Commit the writes to the data wal in groups if enabled.
+    ///
+    /// Only the backends supporting the atomic write of the batches in the same
+    /// region are wrapped.
+    pub fn with_group_commit(self, config: &WalConfig, runtime: Arc<Runtime>) -> Self {
+        let supported = matches!(
+            config.storage,
+            StorageConfig::RocksDB(_) | StorageConfig::Local(_) | StorageConfig::ObjectStore(_)
+        );
+        if !supported || !config.group_commit.is_enabled() {
+            return self;
+        }
+
+        Self {
+            data_wal: Arc::new(WalManagerWithGroupCommit::new(
+                self.data_wal,
+                config.group_commit.clone(),
+                runtime,
+            )),
+            manifest_wal: self.manifest_wal,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        log_batch::{LogWriteEntry, MemoryPayloadDecoder},
+        memory_impl::wal_manager::MemoryImpl,
+    };
+
+    fn build_batch(location: WalLocation, vals: &[u32]) -> LogWriteBatch {
+        let mut batch = LogWriteBatch::new(location);
+        for val in vals {
+            batch.push(LogWriteEntry {
+                payload: val.to_be_bytes().to_vec(),
+            });
+        }
+        batch
+    }
+
+    async fn read_all(wal: &dyn WalManager, location: WalLocation) -> Vec<(SequenceNumber, u32)> {
+        let req = ReadRequest {
+            location,
+            start: manager::ReadBoundary::Min,
+            end: manager::ReadBoundary::Max,
+        };
+        let mut iter = wal.read_batch(&ReadContext::default(), &req).await.unwrap();
+        let entries = iter
+            .next_log_entries(MemoryPayloadDecoder, |_| true, Default::default())
+            .await
+            .unwrap();
+        entries
+            .into_iter()
+            .map(|entry| (entry.sequence, entry.payload.val))
+            .collect()
+    }
+
+    fn build_runtime() -> Arc<Runtime> {
+        Arc::new(
+            runtime::Builder::default()
+                .worker_threads(2)
+                .enable_all()
+                .build()
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_group_commit() {
+        let runtime = build_runtime();
+        let config = Config {
+            window: ReadableDuration::millis(10),
+            max_batches: 128,
+            ..Default::default()
+        };
+        let wal = Arc::new(WalManagerWithGroupCommit::new(
+            Arc::new(MemoryImpl::default()),
+            config,
+            runtime.clone(),
+        ));
+
+        runtime.block_on(async {
+            let locations = [
+                WalLocation::new(1, 1),
+                WalLocation::new(1, 2),
+                WalLocation::new(2, 1),
+            ];
+            let writes = locations.iter().enumerate().map(|(i, location)| {
+                let wal = wal.clone();
+                let batch = build_batch(*location, &[i as u32, i as u32 + 10]);
+                async move { wal.write(&WriteContext::default(), &batch).await }
+            });
+            let seqs = futures::future::try_join_all(writes).await.unwrap();
+            assert_eq!(seqs, vec![2, 2, 2]);
+
+            for (i, location) in locations.iter().enumerate() {
+                let i = i as u32;
+                assert_eq!(
+                    read_all(wal.as_ref(), *location).await,
+                    vec![(1, i), (2, i + 10)]
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn test_commit_full_group() {
+        let runtime = build_runtime();
+        // The window is long enough so the writes return only if the full group
+        // is committed at once.
+        let config = Config {
+            window: ReadableDuration::secs(3600),
+            max_batches: 2,
+            ..Default::default()
+        };
+        let wal = Arc::new(WalManagerWithGroupCommit::new(
+            Arc::new(MemoryImpl::default()),
+            config,
+            runtime.clone(),
+        ));
+
+        runtime.block_on(async {
+            let ctx = WriteContext::default();
+            let batch1 = build_batch(WalLocation::new(1, 1), &[1]);
+            let batch2 = build_batch(WalLocation::new(1, 2), &[2]);
+            let writes =
+                futures::future::try_join(wal.write(&ctx, &batch1), wal.write(&ctx, &batch2));
+            let seqs = tokio::time::timeout(Duration::from_secs(10), writes)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(seqs, (1, 1));
+        });
+    }
+
+    /// Record the byte sizes of the items of every committed group.
+    #[derive(Default)]
+    struct RecordCommitter {
+        groups: Mutex<Vec<Vec<usize>>>,
+    }
+
+    #[async_trait]
+    impl GroupCommitter for Arc<RecordCommitter> {
+        type Item = Vec<u8>;
+        type Key = u64;
+        type Output = ();
+
+        fn size_of(item: &Vec<u8>) -> (usize, usize) {
+            (1, item.len())
+        }
+
+        async fn commit(&self, _key: &u64, items: Vec<Vec<u8>>) -> GenericResult<Vec<()>> {
+            let sizes = items.iter().map(|item| item.len()).collect();
+            self.groups.lock().unwrap().push(sizes);
+            Ok(vec![(); items.len()])
+        }
+    }
+
+    #[test]
+    fn test_group_limits() {
+        let runtime = build_runtime();
+        let committer = Arc::new(RecordCommitter::default());
+        // The window is long enough so the submits return only if the groups
+        // are committed as they reach the limits.
+        let limits = GroupLimits {
+            window: Duration::from_secs(3600),
+            max_entries: 3,
+            max_bytes: 10,
+        };
+        let batcher = GroupBatcher::new(committer.clone(), limits, runtime.clone());
+
+        runtime.block_on(async {
+            // The third item can't fit into the first group, the item exceeding the
+            // bytes limit is committed alone, and the last group is full of
+            // entries.
+            let sizes = [4, 4, 4, 6, 20, 1, 1, 1];
+            let submits = sizes.iter().map(|size| batcher.submit(1, vec![0; *size]));
+            let submits = futures::future::try_join_all(submits);
+            tokio::time::timeout(Duration::from_secs(10), submits)
+                .await
+                .unwrap()
+                .unwrap();
+        });
+
+        let mut groups = committer.groups.lock().unwrap().clone();
+        groups.sort();
+        assert_eq!(
+            groups,
+            vec![vec![1, 1, 1], vec![4, 4], vec![4, 6], vec![20]]
+        );
+    }
+}
@@ -17,6 +17,8 @@
 
 //! Common Encoding for Wal logs
 
+use std::borrow::Cow;
+
 use bytes_ext::{self, Buf, BufMut, BytesMut, SafeBuf, SafeBufMut};
 use codec::{Decoder, Encoder};
 use common_types::{table::TableId, SequenceNumber};
@@ -25,6 +27,7 @@ use macros::define_result;
 use snafu::{ensure, Backtrace, ResultExt, Snafu};
 
 use crate::{
+    compression,
     log_batch::{LogWriteBatch, LogWriteEntry, Payload},
     manager::{self, Encoding, WalLocation},
 };
@@ -34,6 +37,9 @@ pub const NEWEST_LOG_KEY_ENCODING_VERSION: u8 = LOG_KEY_ENCODING_V0;
 
 pub const LOG_VALUE_ENCODING_V0: u8 = 0;
 pub const NEWEST_LOG_VALUE_ENCODING_VERSION: u8 = LOG_VALUE_ENCODING_V0;
+/// Version header of the log values compressed by the
+/// [WalManagerWithCompression](crate::compression::WalManagerWithCompression).
+pub const LOG_VALUE_ENCODING_COMPRESSED: u8 = 1;
 
 pub const META_KEY_ENCODING_V0: u8 = 0;
 pub const NEWEST_META_KEY_ENCODING_VERSION: u8 = META_KEY_ENCODING_V0;
@@ -64,6 +70,9 @@ pub enum Error {
     #[snafu(display("Failed to decode log value payload, err:{}", source))]
     DecodeLogValuePayload { source: GenericError },
 
+    #[snafu(display("Failed to decompress log value, err:{}", source))]
+    DecompressLogValue { source: compression::Error },
+
     #[snafu(display("Failed to encode meta key, err:{}", source))]
     EncodeMetaKey {
         source: bytes_ext::Error,
@@ -250,8 +259,13 @@ pub struct LogValueDecoder {
 }
 
 impl LogValueDecoder {
-    pub fn decode<'a>(&self, mut buf: &'a [u8]) -> Result<&'a [u8]> {
+    /// The compressed values are decompressed, and the others are returned
+    /// without copy.
+    pub fn decode<'a>(&self, mut buf: &'a [u8]) -> Result<Cow<'a, [u8]>> {
         let version = buf.try_get_u8().context(DecodeLogValueHeader)?;
+        if version == LOG_VALUE_ENCODING_COMPRESSED {
+            return compression::decompress_payload(buf).context(DecompressLogValue);
+        }
         ensure!(
             version == self.version,
             InvalidVersion {
@@ -260,7 +274,7 @@ impl LogValueDecoder {
             }
         );
 
-        Ok(buf)
+        Ok(Cow::Borrowed(buf))
     }
 }
 
@@ -521,7 +535,7 @@ impl LogEncoding {
         self.key_enc.decode(&mut buf)
     }
 
-    pub fn decode_value<'a>(&self, buf: &'a [u8]) -> Result<&'a [u8]> {
+    pub fn decode_value<'a>(&self, buf: &'a [u8]) -> Result<Cow<'a, [u8]>> {
         let value_dec = LogValueDecoder {
             version: self.value_enc_version,
         };
@@ -735,7 +749,7 @@ impl CommonLogEncoding {
         self.key_enc.decode(&mut buf)
     }
 
-    pub fn decode_value<'a>(&self, buf: &'a [u8]) -> Result<&'a [u8]> {
+    pub fn decode_value<'a>(&self, buf: &'a [u8]) -> Result<Cow<'a, [u8]>> {
         let value_dec = LogValueDecoder {
             version: self.value_enc_version,
         };
@@ -777,7 +791,8 @@ mod tests {
 
             encoding.encode_value(&mut buf, &payload).unwrap();
 
-            let mut value = encoding.decode_value(&buf).unwrap();
+            let value = encoding.decode_value(&buf).unwrap();
+            let mut value = value.as_ref();
             let decoded_value = decoder
                 .decode(&PayloadDecodeContext::default(), &mut value)
                 .unwrap();
@@ -786,6 +801,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_decode_compressed_log_value() {
+        let mut value = vec![NEWEST_LOG_VALUE_ENCODING_VERSION];
+        value.extend(std::iter::repeat(b"log").take(100).flatten());
+        let config = compression::Config {
+            codec: compression::Codec::Lz4,
+            level: 0,
+        };
+        let compressed = compression::compress_value(&config, &value)
+            .unwrap()
+            .unwrap();
+
+        let encoding = CommonLogEncoding::newest();
+        // Both the compressed and the uncompressed values are readable.
+        for v in [&value, &compressed] {
+            let decoded = encoding.decode_value(v).unwrap();
+            assert_eq!(&decoded[..], &value[1..]);
+        }
+    }
+
     #[test]
     fn test_common_log_key_encoding() {
         let region_id = 1234;
@@ -19,16 +19,22 @@
 
 #![feature(trait_alias)]
 
+pub mod circuit_breaker;
+pub mod compression;
 pub mod config;
 mod dummy;
+pub mod group_commit;
 pub mod kv_encoder;
 #[cfg(feature = "wal-local-storage")]
 pub mod local_storage_impl;
 pub mod log_batch;
 pub mod manager;
+pub mod memory_impl;
 #[cfg(feature = "wal-message-queue")]
 pub mod message_queue_impl;
 pub(crate) mod metrics;
+#[cfg(feature = "wal-object-store")]
+pub mod object_store_impl;
 #[cfg(feature = "wal-rocksdb")]
 pub mod rocksdb_impl;
 #[cfg(feature = "wal-table-kv")]
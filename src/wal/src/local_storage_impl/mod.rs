@@ -16,6 +16,6 @@
 // under the License.
 
 pub mod config;
-mod record_encoding;
+pub(crate) mod record_encoding;
 mod segment;
 pub mod wal_manager;
@@ -41,7 +41,7 @@ use crate::{
     local_storage_impl::record_encoding::{Record, RecordEncoding},
     log_batch::{LogEntry, LogWriteBatch},
     manager::{
-        BatchLogIteratorAdapter, Read, ReadContext, ReadRequest, RegionId, ScanContext,
+        self, BatchLogIteratorAdapter, Read, ReadContext, ReadRequest, RegionId, ScanContext,
         ScanRequest, SyncLogIterator, WalLocation, WriteContext,
     },
 };
@@ -106,10 +106,16 @@ pub enum Error {
     #[snafu(display("{}", source))]
     Encoding { source: GenericError },
 
-    #[snafu(display("Invalid record: {}, backtrace:\n{}", source, backtrace))]
-    InvalidRecord {
+    #[snafu(display(
+        "Corrupted record, table_id:{}, sequence:{}, err:{}",
+        table_id,
+        sequence,
+        source
+    ))]
+    CorruptedRecord {
+        table_id: TableId,
+        sequence: SequenceNumber,
         source: GenericError,
-        backtrace: Backtrace,
     },
 
     #[snafu(display("Length mismatch: expected {} but found {}", expected, actual))]
@@ -198,6 +204,11 @@ pub struct Segment {
 pub struct Position {
     start: usize,
     end: usize,
+    /// Table id of the record, kept to locate the record even if its content
+    /// is corrupted later.
+    table_id: TableId,
+    /// Sequence number of the record.
+    sequence: SequenceNumber,
 }
 
 impl Segment {
@@ -263,6 +274,8 @@ impl Segment {
                     record_position.push(Position {
                         start: pos,
                         end: pos + record.len(),
+                        table_id: record.table_id,
+                        sequence: record.sequence_num,
                     });
 
                     // Update max sequence number
@@ -413,9 +426,7 @@ impl Segment {
         &mut self,
         data: &[u8],
         positions: &mut Vec<Position>,
-        table_id: TableId,
-        prev_sequence_num: SequenceNumber,
-        next_sequence_num: SequenceNumber,
+        table_ranges: &[(TableId, SequenceNumber, SequenceNumber)],
     ) -> Result<()> {
         // Append logs to segment file
         self.append(data)?;
@@ -423,18 +434,20 @@ impl Segment {
         // Update record position
         self.record_position.append(positions);
 
-        // Update min and max sequence number
-        self.min_seq = min(self.min_seq, prev_sequence_num);
-        self.max_seq = max(self.max_seq, next_sequence_num - 1);
-
-        // Update sequence range
-        self.table_ranges
-            .entry(table_id)
-            .and_modify(|seq_range| {
-                seq_range.0 = min(seq_range.0, prev_sequence_num);
-                seq_range.1 = max(seq_range.1, next_sequence_num - 1);
-            })
-            .or_insert((prev_sequence_num, next_sequence_num - 1));
+        for &(table_id, prev_sequence_num, next_sequence_num) in table_ranges {
+            // Update min and max sequence number
+            self.min_seq = min(self.min_seq, prev_sequence_num);
+            self.max_seq = max(self.max_seq, next_sequence_num - 1);
+
+            // Update sequence range
+            self.table_ranges
+                .entry(table_id)
+                .and_modify(|seq_range| {
+                    seq_range.0 = min(seq_range.0, prev_sequence_num);
+                    seq_range.1 = max(seq_range.1, next_sequence_num - 1);
+                })
+                .or_insert((prev_sequence_num, next_sequence_num - 1));
+        }
 
         Ok(())
     }
@@ -740,7 +753,18 @@ impl Region {
         Ok(new_segment)
     }
 
-    pub fn write(&self, _ctx: &WriteContext, batch: &LogWriteBatch) -> Result<SequenceNumber> {
+    pub fn write(&self, ctx: &WriteContext, batch: &LogWriteBatch) -> Result<SequenceNumber> {
+        let sequences = self.write_batches(ctx, std::slice::from_ref(batch))?;
+        Ok(sequences[0])
+    }
+
+    /// Write the batches by a single append to the current segment, returns
+    /// the max sequence number of every batch.
+    pub fn write_batches(
+        &self,
+        _ctx: &WriteContext,
+        batches: &[LogWriteBatch],
+    ) -> Result<Vec<SequenceNumber>> {
         // In the WAL based on local storage, we need to ensure the sequence number in
         // segment is monotonically increasing. So we need to acquire a lock here.
         // Perhaps we could avoid acquiring the lock here and instead allocate the
@@ -748,32 +772,39 @@ impl Region {
         // spawn_blocking. However, I’m not sure about the correctness of this approach.
         let mut current_segment = self.segment_manager.current_segment.lock().unwrap();
 
-        let entries_num = batch.len() as u64;
-        let table_id = batch.location.table_id;
-
-        // Allocate sequence number
-        let prev_sequence_num = self.alloc_sequence_num(entries_num);
-        let mut next_sequence_num = prev_sequence_num;
-
         let mut data = Vec::new();
         let mut record_position = Vec::new();
+        let mut table_ranges = Vec::with_capacity(batches.len());
+
+        for batch in batches {
+            let entries_num = batch.len() as u64;
+            let table_id = batch.location.table_id;
+
+            // Allocate sequence number
+            let prev_sequence_num = self.alloc_sequence_num(entries_num);
+            let mut next_sequence_num = prev_sequence_num;
+
+            for entry in &batch.entries {
+                // Encode the record
+                let record = Record::new(table_id, next_sequence_num, &entry.payload)
+                    .box_err()
+                    .context(Encoding)?;
+                self.record_encoding
+                    .encode(&mut data, &record)
+                    .box_err()
+                    .context(Encoding)?;
+
+                record_position.push(Position {
+                    start: data.len() - record.len(),
+                    end: data.len(),
+                    table_id,
+                    sequence: next_sequence_num,
+                });
 
-        for entry in &batch.entries {
-            // Encode the record
-            let record = Record::new(table_id, next_sequence_num, &entry.payload)
-                .box_err()
-                .context(Encoding)?;
-            self.record_encoding
-                .encode(&mut data, &record)
-                .box_err()
-                .context(Encoding)?;
-
-            record_position.push(Position {
-                start: data.len() - record.len(),
-                end: data.len(),
-            });
+                next_sequence_num += 1;
+            }
 
-            next_sequence_num += 1;
+            table_ranges.push((table_id, prev_sequence_num, next_sequence_num));
         }
 
         // Check if the current segment has enough space for the new data
@@ -799,14 +830,11 @@ impl Region {
         }
 
         // Append logs to segment file
-        guard.append_records(
-            &data,
-            &mut record_position,
-            table_id,
-            prev_sequence_num,
-            next_sequence_num,
-        )?;
-        Ok(next_sequence_num - 1)
+        guard.append_records(&data, &mut record_position, &table_ranges)?;
+        Ok(table_ranges
+            .into_iter()
+            .map(|(_, _, next_sequence_num)| next_sequence_num - 1)
+            .collect())
     }
 
     pub fn read(&self, ctx: &ReadContext, req: &ReadRequest) -> Result<BatchLogIteratorAdapter> {
@@ -969,6 +997,16 @@ impl RegionManager {
         region.write(ctx, batch)
     }
 
+    pub fn write_batches(
+        &self,
+        ctx: &WriteContext,
+        region_id: RegionId,
+        batches: &[LogWriteBatch],
+    ) -> Result<Vec<SequenceNumber>> {
+        let region = self.get_region(region_id)?;
+        region.write_batches(ctx, batches)
+    }
+
     pub fn read(&self, ctx: &ReadContext, req: &ReadRequest) -> Result<BatchLogIteratorAdapter> {
         let region = self.get_region(req.location.region_id)?;
         region.read(ctx, req)
@@ -1084,52 +1122,56 @@ impl SegmentLogIterator {
 
             self.current_record_idx += 1;
 
-            // Extract the record data from the segment content
-            let record_data = &self.segment_content[pos.start..pos.end];
-
-            // Decode the record
-            let record = self
-                .record_encoding
-                .decode(record_data)
-                .box_err()
-                .context(InvalidRecord)?;
+            // Filter by the position before decoding, so the corrupted records
+            // out of the read range won't fail the read.
+            let (table_id, sequence) = (pos.table_id, pos.sequence);
 
             // Filter by sequence number
-            if record.sequence_num < self.start {
+            if sequence < self.start {
                 continue;
             }
-            if record.sequence_num > self.end {
+            if sequence > self.end {
                 self.no_more_data = true;
                 return Ok(None);
             }
 
             // Filter by table_id
-            if let Some(table_id) = self.table_id {
-                if record.table_id != table_id {
+            if let Some(expect_table_id) = self.table_id {
+                if table_id != expect_table_id {
                     continue;
                 }
             }
 
             // Filter by sequence range
-            if let Some((start, end)) = &self.table_ranges.get(&record.table_id) {
-                if record.sequence_num < *start || record.sequence_num > *end {
+            if let Some((start, end)) = &self.table_ranges.get(&table_id) {
+                if sequence < *start || sequence > *end {
                     continue;
                 }
             } else {
                 continue;
             }
 
+            // Extract the record data from the segment content
+            let record_data = &self.segment_content[pos.start..pos.end];
+
+            // Decode the record
+            let record = self
+                .record_encoding
+                .decode(record_data)
+                .box_err()
+                .context(CorruptedRecord { table_id, sequence })?;
+
             // Decode the value
             let value = self
                 .log_encoding
                 .decode_value(record.value)
                 .box_err()
-                .context(InvalidRecord)?;
+                .context(CorruptedRecord { table_id, sequence })?;
 
             return Ok(Some(LogEntry {
                 table_id: record.table_id,
                 sequence: record.sequence_num,
-                payload: value.to_owned(),
+                payload: value.into_owned(),
             }));
         }
     }
@@ -1243,7 +1285,15 @@ impl MultiSegmentLogIterator {
 
 impl SyncLogIterator for MultiSegmentLogIterator {
     fn next_log_entry(&mut self) -> crate::manager::Result<Option<LogEntry<&'_ [u8]>>> {
-        self.next().box_err().context(Read)
+        match self.next() {
+            Ok(v) => Ok(v),
+            Err(Error::CorruptedRecord {
+                table_id,
+                sequence,
+                source,
+            }) => Err(source).context(manager::CorruptedRecord { table_id, sequence }),
+            Err(e) => Err(e).box_err().context(Read),
+        }
     }
 }
 
@@ -1475,6 +1525,103 @@ mod tests {
         runtime.block_on(test_multi_segment_write_and_read_inner(runtime.clone()));
     }
 
+    #[test]
+    fn test_region_write_batches() {
+        let dir = tempdir().unwrap();
+        let runtime = Arc::new(Builder::default().build().unwrap());
+
+        let region = Region::new(
+            1,
+            2,
+            4096,
+            dir.path().to_str().unwrap().to_string(),
+            runtime.clone(),
+        )
+        .unwrap();
+
+        let batches: Vec<_> = [(1, 0..3), (2, 3..5)]
+            .into_iter()
+            .map(|(table_id, vals)| {
+                LogBatchEncoder::create(WalLocation::new(1, table_id))
+                    .encode_batch(vals.map(|v| MemoryPayload { val: v }))
+                    .expect("should succeed to encode payloads")
+            })
+            .collect();
+        let sequences = region
+            .write_batches(&WriteContext::default(), &batches)
+            .unwrap();
+        let first = MIN_SEQUENCE_NUMBER + 1;
+        assert_eq!(sequences, vec![first + 2, first + 4]);
+
+        {
+            let current_segment = region.segment_manager.current_segment.lock().unwrap();
+            let segment = current_segment.lock().unwrap();
+            assert_eq!(segment.table_ranges[&1], (first, first + 2));
+            assert_eq!(segment.table_ranges[&2], (first + 3, first + 4));
+            assert_eq!(segment.record_position.len(), 5);
+        }
+
+        region.close().unwrap()
+    }
+
+    #[test]
+    fn test_iterator_corrupted_record() {
+        let dir = tempdir().unwrap();
+        let runtime = Arc::new(Builder::default().build().unwrap());
+
+        let region = Region::new(
+            1,
+            2,
+            4096,
+            dir.path().to_str().unwrap().to_string(),
+            runtime.clone(),
+        )
+        .unwrap();
+
+        let location = WalLocation::new(1, 1);
+        let batch = LogBatchEncoder::create(location)
+            .encode_batch((0..3).map(|v| MemoryPayload { val: v }))
+            .expect("should succeed to encode payloads");
+        region.write(&WriteContext::default(), &batch).unwrap();
+
+        // Flip the last byte of the value of the second record.
+        {
+            let current_segment = region.segment_manager.current_segment.lock().unwrap();
+            let mut segment = current_segment.lock().unwrap();
+            let end = segment.record_position[1].end;
+            segment.mmap.as_mut().unwrap()[end - 1] ^= 0xff;
+        }
+
+        let mut iter = MultiSegmentLogIterator::new(
+            region.segment_manager.clone(),
+            region.log_encoding.clone(),
+            region.record_encoding.clone(),
+            Some(location.table_id),
+            MIN_SEQUENCE_NUMBER,
+            MAX_SEQUENCE_NUMBER,
+        )
+        .unwrap();
+
+        let first = MIN_SEQUENCE_NUMBER + 1;
+        let entry = iter.next_log_entry().unwrap().unwrap();
+        assert_eq!(entry.sequence, first);
+        match iter.next_log_entry() {
+            Err(manager::Error::CorruptedRecord {
+                table_id, sequence, ..
+            }) => {
+                assert_eq!(table_id, location.table_id);
+                assert_eq!(sequence, first + 1);
+            }
+            v => panic!("unexpected result:{v:?}"),
+        }
+        // The corrupted record is skipped by the next call.
+        let entry = iter.next_log_entry().unwrap().unwrap();
+        assert_eq!(entry.sequence, first + 2);
+        assert!(iter.next_log_entry().unwrap().is_none());
+
+        region.close().unwrap()
+    }
+
     #[test]
     fn test_region_mark_delete_entries_up_to() {
         const SEGMENT_SIZE: usize = 4096;
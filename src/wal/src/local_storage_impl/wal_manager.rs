@@ -27,7 +27,7 @@ use common_types::SequenceNumber;
 use generic_error::BoxError;
 use logger::{debug, info};
 use runtime::Runtime;
-use snafu::ResultExt;
+use snafu::{ensure, ResultExt};
 
 use crate::{
     config::{Config, StorageConfig},
@@ -149,6 +149,35 @@ impl WalManager for LocalStorageImpl {
             .context(Write)
     }
 
+    async fn write_atomically(
+        &self,
+        ctx: &WriteContext,
+        batches: &[LogWriteBatch],
+    ) -> Result<Vec<SequenceNumber>> {
+        let Some(first) = batches.first() else {
+            return Ok(Vec::new());
+        };
+        // The batches are appended to the segment at once only if they are in
+        // the same region.
+        let region_id = first.location.region_id;
+        ensure!(
+            batches
+                .iter()
+                .all(|batch| batch.location.region_id == region_id),
+            AtomicWriteNotSupported
+        );
+
+        debug!(
+            "Write batches atomically to LocalStorage based WAL, ctx:{:?}, batches_num:{}",
+            ctx,
+            batches.len()
+        );
+        self.region_manager
+            .write_batches(ctx, region_id, batches)
+            .box_err()
+            .context(Write)
+    }
+
     async fn scan(&self, ctx: &ScanContext, req: &ScanRequest) -> Result<BatchLogIteratorAdapter> {
         debug!(
             "Scan from LocalStorage based WAL, ctx:{:?}, req:{:?}",
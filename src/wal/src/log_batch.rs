@@ -114,6 +114,8 @@ impl LogWriteBatch {
 #[derive(Debug, Default, Clone)]
 pub struct PayloadDecodeContext {
     pub table_id: TableId,
+    /// Sequence of the log entry of the payload.
+    pub sequence: SequenceNumber,
 }
 
 pub trait PayloadDecoder: Send + Sync {
@@ -125,6 +127,12 @@ pub trait PayloadDecoder: Send + Sync {
         ctx: &PayloadDecodeContext,
         buf: &mut B,
     ) -> Result<Self::Target, Self::Error>;
+
+    /// Decode `Target` for the log whose record is corrupted in the storage,
+    /// the read fails if `None` is returned.
+    fn decode_corrupted(&self, _ctx: &PayloadDecodeContext) -> Option<Self::Target> {
+        None
+    }
 }
 
 pub struct MemoryPayloadDecoder;
@@ -28,7 +28,8 @@ use common_types::{
 pub use error::*;
 use generic_error::BoxError;
 use runtime::Runtime;
-use snafu::ResultExt;
+use serde::Serialize;
+use snafu::{ensure, ResultExt};
 
 use crate::{
     config::Config,
@@ -43,7 +44,7 @@ pub mod error {
     use macros::define_result;
     use snafu::{Backtrace, Snafu};
 
-    use crate::manager::{RegionId, WalLocation};
+    use crate::manager::{RegionId, SequenceNumber, TableId, WalLocation};
 
     // Now most error from manage implementation don't have backtrace, so we add
     // backtrace here.
@@ -105,6 +106,22 @@ pub mod error {
             backtrace: Backtrace,
         },
 
+        #[snafu(display(
+            "Atomic write of multiple log batches is not supported.\nBacktrace:\n{}",
+            backtrace
+        ))]
+        AtomicWriteNotSupported { backtrace: Backtrace },
+
+        #[snafu(display(
+            "Wal region is poisoned by a failed write, region:{}.\nBacktrace:\n{}",
+            region,
+            backtrace
+        ))]
+        RegionPoisoned {
+            region: String,
+            backtrace: Backtrace,
+        },
+
         #[snafu(display(
             "Failed to read log entries, err:{}.\nBacktrace:\n{}",
             source,
@@ -137,6 +154,20 @@ pub mod error {
             backtrace: Backtrace,
         },
 
+        #[snafu(display(
+            "Log record is corrupted, table_id:{}, sequence:{}, err:{}.\nBacktrace:\n{}",
+            table_id,
+            sequence,
+            source,
+            backtrace
+        ))]
+        CorruptedRecord {
+            table_id: TableId,
+            sequence: SequenceNumber,
+            source: GenericError,
+            backtrace: Backtrace,
+        },
+
         #[snafu(display(
             "Failed to close wal region, region_id:{}, err:{}.\nBacktrace:\n{}",
             source,
@@ -168,11 +199,25 @@ pub mod error {
         #[snafu(display("Failed to execute in runtime, err:{}", source))]
         RuntimeExec { source: runtime::Error },
 
+        #[snafu(display("Wal is unavailable, err:{}", source))]
+        CircuitBreakerOpen { source: circuit_breaker::Error },
+
         #[snafu(display("Encountered unknown error, msg:{}.\nBacktrace:\n{}", msg, backtrace))]
         Unknown { msg: String, backtrace: Backtrace },
     }
 
     define_result!(Error);
+
+    impl Error {
+        /// Whether the failed write may succeed if retried, e.g. the transient
+        /// io errors of the underlying storage.
+        ///
+        /// The poisoned region refuses all the writes until it's reopened, so
+        /// retrying them is useless.
+        pub fn is_retryable(&self) -> bool {
+            matches!(self, Error::Write { .. } | Error::RuntimeExec { .. })
+        }
+    }
 }
 
 pub type RegionId = u64;
@@ -302,6 +347,25 @@ pub struct ScanRequest {
 
 pub type ScanContext = ReadContext;
 
+/// Summary of the logs of a table in a region.
+#[derive(Debug, Clone, Serialize)]
+pub struct TableLogsSummary {
+    pub table_id: TableId,
+    /// The min sequence of the undeleted logs, None if there is no log.
+    pub min_sequence: Option<SequenceNumber>,
+    /// The last sequence allocated to the table.
+    pub max_sequence: SequenceNumber,
+    /// Approximate size of the undeleted logs in bytes.
+    pub approximate_size: u64,
+}
+
+/// Summary of the logs in a region.
+#[derive(Debug, Clone, Serialize)]
+pub struct RegionLogsSummary {
+    pub region_id: RegionId,
+    pub tables: Vec<TableLogsSummary>,
+}
+
 /// Sync Iterator abstraction for log entry.
 pub trait SyncLogIterator: Send + fmt::Debug {
     /// Fetch next log entry from the iterator.
@@ -352,11 +416,39 @@ pub trait WalManager: Send + Sync + fmt::Debug + 'static {
     /// Returns the max sequence number for the batch of log entries.
     async fn write(&self, ctx: &WriteContext, batch: &LogWriteBatch) -> Result<SequenceNumber>;
 
+    /// Write multiple batches of log entries, either all of them or none of
+    /// them will be persisted.
+    ///
+    /// Returns the max sequence number of each batch in the order of
+    /// `batches`. The default implementation only accepts a single batch.
+    async fn write_atomically(
+        &self,
+        ctx: &WriteContext,
+        batches: &[LogWriteBatch],
+    ) -> Result<Vec<SequenceNumber>> {
+        ensure!(batches.len() <= 1, AtomicWriteNotSupported);
+
+        let mut sequences = Vec::with_capacity(batches.len());
+        for batch in batches {
+            sequences.push(self.write(ctx, batch).await?);
+        }
+
+        Ok(sequences)
+    }
+
     /// Scan all logs from a `Region`.
     async fn scan(&self, ctx: &ScanContext, req: &ScanRequest) -> Result<BatchLogIteratorAdapter>;
 
     /// Get statistics
     async fn get_statistics(&self) -> Option<String>;
+
+    /// Summarize the logs of the regions in the wal, which are only the opened
+    /// regions for some implementations.
+    ///
+    /// Returns None if the implementation doesn't support it.
+    async fn summarize_regions(&self) -> Result<Option<Vec<RegionLogsSummary>>> {
+        Ok(None)
+    }
 }
 
 /// Used to collect the metrics about the write logs.
@@ -429,28 +521,36 @@ impl BatchLogIteratorAdapter {
         let (log_entries, iter_opt) = runtime
             .spawn_blocking(move || {
                 while buffer.len() < batch_size {
-                    if let Some(raw_log_entry) = iter.next_log_entry()? {
-                        if !filter(raw_log_entry.table_id) {
+                    let raw_log_entry = match iter.next_log_entry() {
+                        Ok(Some(v)) => v,
+                        Ok(None) => return Ok((buffer, None)),
+                        Err(e) => {
+                            if let Some(log_entry) = decode_corrupted_record(&decoder, &filter, e)?
+                            {
+                                buffer.push_back(log_entry);
+                            }
                             continue;
                         }
-
-                        let mut raw_payload = raw_log_entry.payload;
-                        let ctx = PayloadDecodeContext {
-                            table_id: raw_log_entry.table_id,
-                        };
-                        let payload = decoder
-                            .decode(&ctx, &mut raw_payload)
-                            .box_err()
-                            .context(error::Decoding)?;
-                        let log_entry = LogEntry {
-                            table_id: raw_log_entry.table_id,
-                            sequence: raw_log_entry.sequence,
-                            payload,
-                        };
-                        buffer.push_back(log_entry);
-                    } else {
-                        return Ok((buffer, None));
+                    };
+                    if !filter(raw_log_entry.table_id) {
+                        continue;
                     }
+
+                    let mut raw_payload = raw_log_entry.payload;
+                    let ctx = PayloadDecodeContext {
+                        table_id: raw_log_entry.table_id,
+                        sequence: raw_log_entry.sequence,
+                    };
+                    let payload = decoder
+                        .decode(&ctx, &mut raw_payload)
+                        .box_err()
+                        .context(error::Decoding)?;
+                    let log_entry = LogEntry {
+                        table_id: raw_log_entry.table_id,
+                        sequence: raw_log_entry.sequence,
+                        payload,
+                    };
+                    buffer.push_back(log_entry);
                 }
 
                 Ok((buffer, Some(iter)))
@@ -479,27 +579,34 @@ impl BatchLogIteratorAdapter {
 
         let mut async_iter = async_iter;
         while buffer.len() < self.batch_size {
-            if let Some(raw_log_entry) = async_iter.next_log_entry().await? {
-                if !filter(raw_log_entry.table_id) {
+            let raw_log_entry = match async_iter.next_log_entry().await {
+                Ok(Some(v)) => v,
+                Ok(None) => return Ok((buffer, None)),
+                Err(e) => {
+                    if let Some(log_entry) = decode_corrupted_record(&decoder, &filter, e)? {
+                        buffer.push_back(log_entry);
+                    }
                     continue;
                 }
-                let mut raw_payload = raw_log_entry.payload;
-                let ctx = PayloadDecodeContext {
-                    table_id: raw_log_entry.table_id,
-                };
-                let payload = decoder
-                    .decode(&ctx, &mut raw_payload)
-                    .box_err()
-                    .context(error::Decoding)?;
-                let log_entry = LogEntry {
-                    table_id: raw_log_entry.table_id,
-                    sequence: raw_log_entry.sequence,
-                    payload,
-                };
-                buffer.push_back(log_entry);
-            } else {
-                return Ok((buffer, None));
+            };
+            if !filter(raw_log_entry.table_id) {
+                continue;
             }
+            let mut raw_payload = raw_log_entry.payload;
+            let ctx = PayloadDecodeContext {
+                table_id: raw_log_entry.table_id,
+                sequence: raw_log_entry.sequence,
+            };
+            let payload = decoder
+                .decode(&ctx, &mut raw_payload)
+                .box_err()
+                .context(error::Decoding)?;
+            let log_entry = LogEntry {
+                table_id: raw_log_entry.table_id,
+                sequence: raw_log_entry.sequence,
+                payload,
+            };
+            buffer.push_back(log_entry);
         }
 
         Ok((buffer, Some(LogIterator::Async(async_iter))))
@@ -538,6 +645,41 @@ impl BatchLogIteratorAdapter {
     }
 }
 
+/// Handle the error returned by the log iterator.
+///
+/// The [Error::CorruptedRecord] of a needed table is handed to the `decoder`,
+/// which decides whether to skip the record or to fail the read, and the
+/// record of an unneeded table is just skipped. Other errors are returned.
+fn decode_corrupted_record<D, F>(
+    decoder: &D,
+    filter: &F,
+    err: Error,
+) -> Result<Option<LogEntry<D::Target>>>
+where
+    D: PayloadDecoder,
+    F: TableFilter,
+{
+    let (table_id, sequence) = match &err {
+        Error::CorruptedRecord {
+            table_id, sequence, ..
+        } => (*table_id, *sequence),
+        _ => return Err(err),
+    };
+    if !filter(table_id) {
+        return Ok(None);
+    }
+
+    let ctx = PayloadDecodeContext { table_id, sequence };
+    match decoder.decode_corrupted(&ctx) {
+        Some(payload) => Ok(Some(LogEntry {
+            table_id,
+            sequence,
+            payload,
+        })),
+        None => Err(err),
+    }
+}
+
 pub type WalManagerRef = Arc<dyn WalManager>;
 
 #[derive(Debug, Clone)]
@@ -0,0 +1,447 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, RwLock},
+};
+
+use async_trait::async_trait;
+use common_types::{table::TableId, SequenceNumber, MIN_SEQUENCE_NUMBER};
+use logger::{debug, info};
+
+use crate::{
+    config::{Config, StorageConfig},
+    log_batch::{LogEntry, LogWriteBatch},
+    manager::{
+        self, error::*, AsyncLogIterator, BatchLogIteratorAdapter, OpenedWals, ReadContext,
+        ReadRequest, RegionId, RegionLogsSummary, ScanContext, ScanRequest, TableLogsSummary,
+        WalLocation, WalManager, WalManagerRef, WalRuntimes, WalsOpener, WriteContext,
+    },
+};
+
+/// Logs of a table.
+#[derive(Debug)]
+struct TableLogs {
+    /// The next sequence to allocate, it keeps increasing even if the logs are
+    /// deleted.
+    next_sequence: SequenceNumber,
+    logs: BTreeMap<SequenceNumber, Vec<u8>>,
+}
+
+impl Default for TableLogs {
+    fn default() -> Self {
+        Self {
+            next_sequence: MIN_SEQUENCE_NUMBER + 1,
+            logs: BTreeMap::new(),
+        }
+    }
+}
+
+impl TableLogs {
+    #[inline]
+    fn last_sequence(&self) -> SequenceNumber {
+        self.next_sequence - 1
+    }
+}
+
+type RegionLogs = HashMap<TableId, TableLogs>;
+
+/// The storage of the logs, which can be shared by multiple [MemoryImpl]s to
+/// simulate the reopening of the wal.
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    regions: RwLock<HashMap<RegionId, RegionLogs>>,
+}
+
+pub type MemoryStorageRef = Arc<MemoryStorage>;
+
+/// Wal keeping all the logs in memory.
+#[derive(Debug)]
+pub struct MemoryImpl {
+    storage: MemoryStorageRef,
+}
+
+impl MemoryImpl {
+    pub fn new(storage: MemoryStorageRef) -> Self {
+        Self { storage }
+    }
+}
+
+impl Default for MemoryImpl {
+    fn default() -> Self {
+        Self::new(Arc::new(MemoryStorage::default()))
+    }
+}
+
+/// Append the entries of `batch` to the logs, returns the last sequence of the
+/// table.
+fn append_batch(
+    regions: &mut HashMap<RegionId, RegionLogs>,
+    batch: &LogWriteBatch,
+) -> SequenceNumber {
+    manager::collect_write_log_metrics(batch);
+
+    let location = batch.location;
+    let table = regions
+        .entry(location.region_id)
+        .or_default()
+        .entry(location.table_id)
+        .or_default();
+
+    for entry in &batch.entries {
+        table
+            .logs
+            .insert(table.next_sequence, entry.payload.clone());
+        table.next_sequence += 1;
+    }
+
+    table.last_sequence()
+}
+
+#[async_trait]
+impl WalManager for MemoryImpl {
+    async fn sequence_num(&self, location: WalLocation) -> Result<SequenceNumber> {
+        let regions = self.storage.regions.read().unwrap();
+        let sequence = regions
+            .get(&location.region_id)
+            .and_then(|region| region.get(&location.table_id))
+            .map(|table| table.last_sequence())
+            .unwrap_or(MIN_SEQUENCE_NUMBER);
+
+        Ok(sequence)
+    }
+
+    async fn mark_delete_entries_up_to(
+        &self,
+        location: WalLocation,
+        sequence_num: SequenceNumber,
+    ) -> Result<()> {
+        debug!(
+            "Mark delete entries up to {} for location:{:?}",
+            sequence_num, location
+        );
+
+        let mut regions = self.storage.regions.write().unwrap();
+        if let Some(table) = regions
+            .get_mut(&location.region_id)
+            .and_then(|region| region.get_mut(&location.table_id))
+        {
+            // Keep the logs whose sequence is greater than `sequence_num`.
+            table.logs = table.logs.split_off(&(sequence_num.saturating_add(1)));
+            if sequence_num == SequenceNumber::MAX {
+                table.logs.clear();
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn close_region(&self, region_id: RegionId) -> Result<()> {
+        debug!(
+            "Close region for memory based WAL is noop operation, region_id:{}",
+            region_id
+        );
+
+        Ok(())
+    }
+
+    async fn close_gracefully(&self) -> Result<()> {
+        info!("Close memory wal gracefully");
+
+        Ok(())
+    }
+
+    async fn read_batch(
+        &self,
+        ctx: &ReadContext,
+        req: &ReadRequest,
+    ) -> Result<BatchLogIteratorAdapter> {
+        let (Some(start), Some(end)) = (
+            req.start.as_start_sequence_number(),
+            req.end.as_end_sequence_number(),
+        ) else {
+            return Ok(BatchLogIteratorAdapter::empty());
+        };
+        if start > end {
+            return Ok(BatchLogIteratorAdapter::empty());
+        }
+
+        let location = req.location;
+        let regions = self.storage.regions.read().unwrap();
+        let entries = regions
+            .get(&location.region_id)
+            .and_then(|region| region.get(&location.table_id))
+            .map(|table| {
+                table
+                    .logs
+                    .range(start..=end)
+                    .map(|(sequence, payload)| (location.table_id, *sequence, payload.clone()))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let iter = MemoryLogIterator::new(entries);
+        Ok(BatchLogIteratorAdapter::new_with_async(
+            Box::new(iter),
+            ctx.batch_size,
+        ))
+    }
+
+    async fn write(&self, _ctx: &WriteContext, batch: &LogWriteBatch) -> Result<SequenceNumber> {
+        let mut regions = self.storage.regions.write().unwrap();
+
+        Ok(append_batch(&mut regions, batch))
+    }
+
+    async fn write_atomically(
+        &self,
+        _ctx: &WriteContext,
+        batches: &[LogWriteBatch],
+    ) -> Result<Vec<SequenceNumber>> {
+        // Hold the lock during the whole write so the batches are visible all at
+        // once.
+        let mut regions = self.storage.regions.write().unwrap();
+        let sequences = batches
+            .iter()
+            .map(|batch| append_batch(&mut regions, batch))
+            .collect();
+
+        Ok(sequences)
+    }
+
+    async fn scan(&self, ctx: &ScanContext, req: &ScanRequest) -> Result<BatchLogIteratorAdapter> {
+        let regions = self.storage.regions.read().unwrap();
+        let Some(region) = regions.get(&req.region_id) else {
+            return Ok(BatchLogIteratorAdapter::empty());
+        };
+
+        let mut table_ids: Vec<_> = region.keys().copied().collect();
+        table_ids.sort_unstable();
+        let entries = table_ids
+            .into_iter()
+            .flat_map(|table_id| {
+                region[&table_id]
+                    .logs
+                    .iter()
+                    .map(move |(sequence, payload)| (table_id, *sequence, payload.clone()))
+            })
+            .collect();
+
+        let iter = MemoryLogIterator::new(entries);
+        Ok(BatchLogIteratorAdapter::new_with_async(
+            Box::new(iter),
+            ctx.batch_size,
+        ))
+    }
+
+    async fn get_statistics(&self) -> Option<String> {
+        let regions = self.storage.regions.read().unwrap();
+        let stats = regions
+            .iter()
+            .map(|(region_id, region)| {
+                let num_logs: usize = region.values().map(|table| table.logs.len()).sum();
+                format!(
+                    "region_id:{region_id}, num_tables:{}, num_logs:{num_logs}",
+                    region.len()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        Some(stats)
+    }
+
+    async fn summarize_regions(&self) -> Result<Option<Vec<RegionLogsSummary>>> {
+        let regions = self.storage.regions.read().unwrap();
+        let summaries = regions
+            .iter()
+            .map(|(region_id, region)| RegionLogsSummary {
+                region_id: *region_id,
+                tables: region
+                    .iter()
+                    .map(|(table_id, table)| TableLogsSummary {
+                        table_id: *table_id,
+                        min_sequence: table.logs.keys().next().copied(),
+                        max_sequence: table.last_sequence(),
+                        approximate_size: table.logs.values().map(|v| v.len() as u64).sum(),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Ok(Some(summaries))
+    }
+}
+
+/// Iterator over the logs copied from the [MemoryStorage].
+#[derive(Debug)]
+struct MemoryLogIterator {
+    entries: Vec<(TableId, SequenceNumber, Vec<u8>)>,
+    cursor: usize,
+}
+
+impl MemoryLogIterator {
+    fn new(entries: Vec<(TableId, SequenceNumber, Vec<u8>)>) -> Self {
+        Self { entries, cursor: 0 }
+    }
+}
+
+#[async_trait]
+impl AsyncLogIterator for MemoryLogIterator {
+    async fn next_log_entry(&mut self) -> Result<Option<LogEntry<&'_ [u8]>>> {
+        let Some((table_id, sequence, payload)) = self.entries.get(self.cursor) else {
+            return Ok(None);
+        };
+        self.cursor += 1;
+
+        Ok(Some(LogEntry {
+            table_id: *table_id,
+            sequence: *sequence,
+            payload: payload.as_slice(),
+        }))
+    }
+}
+
+#[derive(Default)]
+pub struct MemoryWalsOpener;
+
+#[async_trait]
+impl WalsOpener for MemoryWalsOpener {
+    async fn open_wals(&self, config: &Config, _runtimes: WalRuntimes) -> Result<OpenedWals> {
+        if !matches!(&config.storage, StorageConfig::Memory(_)) {
+            return InvalidWalConfig {
+                msg: format!(
+                    "invalid wal storage config while opening memory wal, config:{config:?}"
+                ),
+            }
+            .fail();
+        }
+
+        let data_wal: WalManagerRef = if config.disable_data {
+            Arc::new(crate::dummy::DoNothing)
+        } else {
+            Arc::new(MemoryImpl::default())
+        };
+        let manifest_wal = Arc::new(MemoryImpl::default());
+
+        Ok(OpenedWals {
+            data_wal,
+            manifest_wal,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log_batch::{LogWriteEntry, MemoryPayloadDecoder};
+
+    fn build_batch(location: WalLocation, vals: &[u32]) -> LogWriteBatch {
+        let mut batch = LogWriteBatch::new(location);
+        for val in vals {
+            batch.push(LogWriteEntry {
+                payload: val.to_be_bytes().to_vec(),
+            });
+        }
+        batch
+    }
+
+    async fn read_all(wal: &MemoryImpl, location: WalLocation) -> Vec<(SequenceNumber, u32)> {
+        let req = ReadRequest {
+            location,
+            start: manager::ReadBoundary::Min,
+            end: manager::ReadBoundary::Max,
+        };
+        let mut iter = wal.read_batch(&ReadContext::default(), &req).await.unwrap();
+        let entries = iter
+            .next_log_entries(MemoryPayloadDecoder, |_| true, Default::default())
+            .await
+            .unwrap();
+        entries
+            .into_iter()
+            .map(|entry| (entry.sequence, entry.payload.val))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_write_read_delete() {
+        let wal = MemoryImpl::default();
+        let location = WalLocation::new(1, 1);
+
+        let seq = wal
+            .write(&WriteContext::default(), &build_batch(location, &[1, 2, 3]))
+            .await
+            .unwrap();
+        assert_eq!(seq, 3);
+        assert_eq!(wal.sequence_num(location).await.unwrap(), 3);
+        assert_eq!(read_all(&wal, location).await, vec![(1, 1), (2, 2), (3, 3)]);
+
+        wal.mark_delete_entries_up_to(location, 2).await.unwrap();
+        assert_eq!(read_all(&wal, location).await, vec![(3, 3)]);
+
+        // Sequence keeps increasing after deletion.
+        let seq = wal
+            .write(&WriteContext::default(), &build_batch(location, &[4]))
+            .await
+            .unwrap();
+        assert_eq!(seq, 4);
+        assert_eq!(read_all(&wal, location).await, vec![(3, 3), (4, 4)]);
+    }
+    #[tokio::test]
+    async fn test_summarize_regions() {
+        let wal = MemoryImpl::default();
+        let location = WalLocation::new(1, 1);
+        wal.write(&WriteContext::default(), &build_batch(location, &[1, 2, 3]))
+            .await
+            .unwrap();
+        wal.mark_delete_entries_up_to(location, 1).await.unwrap();
+
+        let summaries = wal.summarize_regions().await.unwrap().unwrap();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].region_id, 1);
+        let table = &summaries[0].tables[0];
+        assert_eq!(table.table_id, 1);
+        assert_eq!(table.min_sequence, Some(2));
+        assert_eq!(table.max_sequence, 3);
+        assert_eq!(table.approximate_size, 8);
+    }
+
+    #[tokio::test]
+    async fn test_write_atomically() {
+        let wal = MemoryImpl::default();
+        let (location1, location2) = (WalLocation::new(1, 1), WalLocation::new(1, 2));
+        wal.write(&WriteContext::default(), &build_batch(location1, &[1]))
+            .await
+            .unwrap();
+
+        let batches = [
+            build_batch(location1, &[2, 3]),
+            build_batch(location2, &[4]),
+        ];
+        let seqs = wal
+            .write_atomically(&WriteContext::default(), &batches)
+            .await
+            .unwrap();
+        assert_eq!(seqs, vec![3, 1]);
+        assert_eq!(
+            read_all(&wal, location1).await,
+            vec![(1, 1), (2, 2), (3, 3)]
+        );
+        assert_eq!(read_all(&wal, location2).await, vec![(1, 4)]);
+    }
+}
@@ -19,8 +19,11 @@
 
 use message_queue::kafka::config::Config as KafkaConfig;
 use serde::{Deserialize, Serialize};
+use size_ext::ReadableSize;
 use time_ext::ReadableDuration;
 
+use crate::group_commit::GroupLimits;
+
 #[derive(Debug, Default, Clone, Deserialize, Serialize)]
 #[serde(default)]
 pub struct KafkaStorageConfig {
@@ -36,12 +39,34 @@ pub struct KafkaStorageConfig {
 #[serde(default)]
 pub struct KafkaWalConfig {
     pub clean_period: ReadableDuration,
+    /// Time window to batch the writes of different tables in the same region
+    /// into one produce request, 0 means disable batching.
+    pub write_batch_window: ReadableDuration,
+    /// Max number of messages of a batched produce request.
+    pub write_batch_max_messages: usize,
+    /// Max bytes of a batched produce request, should be less than the max
+    /// request size of the message queue.
+    pub write_batch_max_bytes: ReadableSize,
 }
 
 impl Default for KafkaWalConfig {
     fn default() -> Self {
         Self {
             clean_period: ReadableDuration::millis(3600 * 1000),
+            write_batch_window: ReadableDuration::millis(0),
+            write_batch_max_messages: 1024,
+            write_batch_max_bytes: ReadableSize::kb(512),
         }
     }
 }
+
+impl KafkaWalConfig {
+    /// Limits to batch the writes, returns `None` if batching is disabled.
+    pub fn write_batch_limits(&self) -> Option<GroupLimits> {
+        (!self.write_batch_window.is_zero()).then(|| GroupLimits {
+            window: self.write_batch_window.0,
+            max_entries: self.write_batch_max_messages,
+            max_bytes: self.write_batch_max_bytes.as_byte() as usize,
+        })
+    }
+}
@@ -30,6 +30,7 @@ mod region;
 mod region_context;
 mod snapshot_synchronizer;
 pub mod wal;
+mod write_batcher;
 
 #[cfg(test)]
 mod test_util;
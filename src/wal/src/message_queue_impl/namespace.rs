@@ -38,6 +38,7 @@ use crate::{
         config::KafkaWalConfig,
         encoding::MetaEncoding,
         region::{self, MessageQueueLogIterator, Region},
+        write_batcher::{MessageProducer, WriteBatcher, WriteBatcherRef},
     },
 };
 
@@ -145,7 +146,14 @@ impl<M: MessageQueue> Namespace<M> {
         default_runtime: Arc<Runtime>,
         config: KafkaWalConfig,
     ) -> Self {
-        let inner = Arc::new(NamespaceInner::new(namespace, message_queue));
+        let write_batcher = config.write_batch_limits().map(|limits| {
+            Arc::new(WriteBatcher::new(
+                MessageProducer::new(message_queue.clone()),
+                limits,
+                default_runtime.clone(),
+            ))
+        });
+        let inner = Arc::new(NamespaceInner::new(namespace, message_queue, write_batcher));
         let cleaner_handle = start_log_cleaner(
             default_runtime.as_ref(),
             config.clean_period.0,
@@ -256,16 +264,23 @@ struct NamespaceInner<M: MessageQueue> {
     message_queue: Arc<M>,
     meta_encoding: MetaEncoding,
     log_encoding: LogEncoding,
+    /// Batch the writes of different tables in a region if set.
+    write_batcher: Option<WriteBatcherRef<M>>,
 }
 
 impl<M: MessageQueue> NamespaceInner<M> {
-    pub fn new(namespace: String, message_queue: Arc<M>) -> Self {
+    pub fn new(
+        namespace: String,
+        message_queue: Arc<M>,
+        write_batcher: Option<WriteBatcherRef<M>>,
+    ) -> Self {
         Self {
             namespace,
             regions: Default::default(),
             message_queue,
             meta_encoding: MetaEncoding::newest(),
             log_encoding: LogEncoding::newest(),
+            write_batcher,
         }
     }
 
@@ -301,8 +316,14 @@ impl<M: MessageQueue> NamespaceInner<M> {
             return Ok(region.clone());
         }
 
-        let region =
-            Arc::new(Region::open(&self.namespace, region_id, self.message_queue.clone()).await?);
+        let region = Region::open(
+            &self.namespace,
+            region_id,
+            self.message_queue.clone(),
+            self.write_batcher.clone(),
+        )
+        .await?;
+        let region = Arc::new(region);
         regions.insert(region_id, region.clone());
 
         info!(
@@ -40,6 +40,7 @@ use crate::{
             TableMetaData, TableWriteContext,
         },
         snapshot_synchronizer::{self, SnapshotSynchronizer},
+        write_batcher::WriteBatcherRef,
     },
 };
 
@@ -140,7 +141,14 @@ pub struct Region<M: MessageQueue> {
 
 impl<M: MessageQueue> Region<M> {
     /// Init the region.
-    pub async fn open(namespace: &str, region_id: u64, message_queue: Arc<M>) -> Result<Self> {
+    ///
+    /// The writes of different tables are batched if `write_batcher` is set.
+    pub async fn open(
+        namespace: &str,
+        region_id: u64,
+        message_queue: Arc<M>,
+        write_batcher: Option<WriteBatcherRef<M>>,
+    ) -> Result<Self> {
         info!(
             "Begin to open region in namespace, namespace:{}, region id:{}",
             namespace, region_id
@@ -201,6 +209,7 @@ impl<M: MessageQueue> Region<M> {
             log_encoding,
             message_queue.clone(),
             log_topic.clone(),
+            write_batcher,
         ));
 
         // Init others.
@@ -716,6 +725,9 @@ struct RegionInner<M> {
 
     /// Topic storing logs in message queue
     log_topic: String,
+
+    /// Batch the writes of different tables if set
+    write_batcher: Option<WriteBatcherRef<M>>,
 }
 
 impl<M: MessageQueue> RegionInner<M> {
@@ -724,12 +736,14 @@ impl<M: MessageQueue> RegionInner<M> {
         log_encoding: CommonLogEncoding,
         message_queue: Arc<M>,
         log_topic: String,
+        write_batcher: Option<WriteBatcherRef<M>>,
     ) -> Self {
         Self {
             region_context,
             log_encoding,
             message_queue,
             log_topic,
+            write_batcher,
         }
     }
 
@@ -742,6 +756,7 @@ impl<M: MessageQueue> RegionInner<M> {
             log_encoding: self.log_encoding.clone(),
             log_topic: self.log_topic.clone(),
             message_queue: self.message_queue.clone(),
+            write_batcher: self.write_batcher.clone(),
         };
 
         self.region_context
@@ -920,7 +935,7 @@ impl<C: ConsumeIterator> MessageQueueLogIterator<C> {
                 msg: "failed while polling log",
             })?;
 
-        self.previous_value = payload.to_owned();
+        self.previous_value = payload.into_owned();
 
         Ok(Some(LogEntry {
             table_id: log_key.table_id,
@@ -1038,6 +1053,7 @@ mod tests {
             let mut payload = log_entry.payload;
             let ctx = PayloadDecodeContext {
                 table_id: log_entry.table_id,
+                sequence: log_entry.sequence,
             };
             let decoded_payload = test_context
                 .test_payload_encoder
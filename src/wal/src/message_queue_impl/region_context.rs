@@ -36,7 +36,7 @@ use crate::{
     kv_encoder::{CommonLogEncoding, CommonLogKey},
     log_batch::LogWriteBatch,
     manager::{self},
-    message_queue_impl,
+    message_queue_impl::{self, write_batcher::WriteBatcherRef},
 };
 
 #[derive(Debug, Snafu)]
@@ -646,17 +646,23 @@ impl TableWriter {
             next_sequence_num += 1;
         }
 
-        // Write.
-        let offsets = table_write_ctx
-            .message_queue
-            .produce(&table_write_ctx.log_topic, messages)
-            .await
-            .box_err()
-            .context(WriteWithCause {
-                region_id,
-                table_id,
-                msg: "produce messages failed",
-            })?;
+        // Write, the messages may be batched with the ones of other tables.
+        let offsets = match &table_write_ctx.write_batcher {
+            Some(write_batcher) => write_batcher
+                .submit(table_write_ctx.log_topic.clone(), messages)
+                .await
+                .box_err(),
+            None => table_write_ctx
+                .message_queue
+                .produce(&table_write_ctx.log_topic, messages)
+                .await
+                .box_err(),
+        }
+        .context(WriteWithCause {
+            region_id,
+            table_id,
+            msg: "produce messages failed",
+        })?;
 
         ensure!(
             !offsets.is_empty(),
@@ -711,4 +717,5 @@ pub struct TableWriteContext<M: MessageQueue> {
     pub log_encoding: CommonLogEncoding,
     pub log_topic: String,
     pub message_queue: Arc<M>,
+    pub write_batcher: Option<WriteBatcherRef<M>>,
 }
@@ -81,7 +81,7 @@ impl<Mq: MessageQueue> TestContext<Mq> {
             })
             .collect();
 
-        let region = Region::open(&namespace, region_id, message_queue.clone())
+        let region = Region::open(&namespace, region_id, message_queue.clone(), None)
             .await
             .unwrap();
 
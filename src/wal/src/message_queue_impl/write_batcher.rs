@@ -0,0 +1,264 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Batch the writes of different tables in the same region.
+//!
+//! Every write to the region produces its messages to the log topic in a
+//! separate request, which is expensive for a shard with many small tables.
+//! [WriteBatcher] groups the messages of the writes to the same log topic
+//! arriving in a small time window, and produces them to the message queue in
+//! one request.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use generic_error::{BoxError, GenericResult};
+use message_queue::{Message, MessageQueue, Offset};
+
+use crate::group_commit::{self, GroupBatcher, GroupCommitter};
+
+pub type WriteBatcher<M> = GroupBatcher<MessageProducer<M>>;
+pub type WriteBatcherRef<M> = Arc<WriteBatcher<M>>;
+
+/// Produce the messages of the writes to a log topic in one request.
+///
+/// The messages of one write are kept contiguous in the request, so their
+/// offsets are still contiguous.
+pub struct MessageProducer<M> {
+    message_queue: Arc<M>,
+}
+
+impl<M: MessageQueue> MessageProducer<M> {
+    pub fn new(message_queue: Arc<M>) -> Self {
+        Self { message_queue }
+    }
+}
+
+#[async_trait]
+impl<M: MessageQueue> GroupCommitter for MessageProducer<M> {
+    type Item = Vec<Message>;
+    type Key = String;
+    type Output = Vec<Offset>;
+
+    fn size_of(messages: &Vec<Message>) -> (usize, usize) {
+        let num_bytes = messages
+            .iter()
+            .map(|msg| {
+                msg.key.as_ref().map_or(0, Vec::len) + msg.value.as_ref().map_or(0, Vec::len)
+            })
+            .sum();
+        (messages.len(), num_bytes)
+    }
+
+    async fn commit(
+        &self,
+        log_topic: &String,
+        writes: Vec<Vec<Message>>,
+    ) -> GenericResult<Vec<Vec<Offset>>> {
+        let write_lens: Vec<_> = writes.iter().map(Vec::len).collect();
+        let messages: Vec<_> = writes.into_iter().flatten().collect();
+        let num_messages = messages.len();
+        let offsets = self
+            .message_queue
+            .produce(log_topic, messages)
+            .await
+            .box_err()?;
+        if offsets.len() != num_messages {
+            return group_commit::Commit {
+                key: log_topic,
+                msg: format!(
+                    "unexpected number of offsets, expect:{num_messages}, given:{}",
+                    offsets.len()
+                ),
+            }
+            .fail()
+            .box_err();
+        }
+
+        let mut offsets = offsets.into_iter();
+        Ok(write_lens
+            .into_iter()
+            .map(|len| offsets.by_ref().take(len).collect())
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+    use message_queue::{ConsumeIterator, MessageAndOffset, OffsetType, StartOffset};
+
+    use super::*;
+    use crate::{group_commit::GroupLimits, message_queue_impl};
+
+    /// Message queue recording the number of messages of every produce request.
+    #[derive(Debug, Clone, Default)]
+    struct MockMessageQueue {
+        requests: Arc<Mutex<HashMap<String, Vec<usize>>>>,
+    }
+
+    #[derive(Debug)]
+    struct MockConsumeIterator;
+
+    #[async_trait]
+    impl ConsumeIterator for MockConsumeIterator {
+        type Error = std::io::Error;
+
+        async fn next_message(&mut self) -> Result<(MessageAndOffset, Offset), Self::Error> {
+            unimplemented!()
+        }
+    }
+
+    #[async_trait]
+    impl MessageQueue for MockMessageQueue {
+        type ConsumeIterator = MockConsumeIterator;
+        type Error = std::io::Error;
+
+        async fn create_topic_if_not_exist(&self, _topic_name: &str) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        async fn fetch_offset(
+            &self,
+            _topic_name: &str,
+            _offset_type: OffsetType,
+        ) -> Result<Offset, Self::Error> {
+            unimplemented!()
+        }
+
+        async fn produce(
+            &self,
+            topic_name: &str,
+            messages: Vec<Message>,
+        ) -> Result<Vec<Offset>, Self::Error> {
+            let mut requests = self.requests.lock().unwrap();
+            let requests = requests.entry(topic_name.to_string()).or_default();
+            let start = requests.iter().sum::<usize>() as Offset;
+            requests.push(messages.len());
+            Ok((start..start + messages.len() as Offset).collect())
+        }
+
+        async fn consume(
+            &self,
+            _topic_name: &str,
+            _start_offset: StartOffset,
+        ) -> Result<Self::ConsumeIterator, Self::Error> {
+            unimplemented!()
+        }
+
+        async fn delete_to(&self, _topic_name: &str, _offset: Offset) -> Result<(), Self::Error> {
+            unimplemented!()
+        }
+    }
+
+    fn build_messages(num: usize, value_len: usize) -> Vec<Message> {
+        (0..num)
+            .map(|_| message_queue_impl::to_message(vec![0; 4], vec![0; value_len]))
+            .collect()
+    }
+
+    fn build_runtime() -> Arc<runtime::Runtime> {
+        Arc::new(
+            runtime::Builder::default()
+                .worker_threads(2)
+                .enable_all()
+                .build()
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_batch_writes() {
+        let runtime = build_runtime();
+        let message_queue = Arc::new(MockMessageQueue::default());
+        let limits = GroupLimits {
+            window: Duration::from_millis(10),
+            max_entries: 4,
+            max_bytes: 1024,
+        };
+        let batcher = WriteBatcher::new(
+            MessageProducer::new(message_queue.clone()),
+            limits,
+            runtime.clone(),
+        );
+
+        runtime.block_on(async {
+            // The writes to topic `a` fill a request, and the write to topic `b` is
+            // produced after the window.
+            let writes = [("a", 2), ("b", 1), ("a", 2)];
+            let writes = writes
+                .iter()
+                .map(|(topic, num)| batcher.submit(topic.to_string(), build_messages(*num, 8)));
+            let offsets = futures::future::try_join_all(writes).await.unwrap();
+            assert_eq!(offsets, vec![vec![0, 1], vec![0], vec![2, 3]]);
+
+            let offsets = batcher
+                .submit("a".to_string(), build_messages(1, 8))
+                .await
+                .unwrap();
+            assert_eq!(offsets, vec![4]);
+        });
+
+        let requests = message_queue.requests.lock().unwrap().clone();
+        assert_eq!(requests["a"], vec![4, 1]);
+        assert_eq!(requests["b"], vec![1]);
+    }
+
+    #[test]
+    fn test_batch_bytes_limit() {
+        let runtime = build_runtime();
+        let message_queue = Arc::new(MockMessageQueue::default());
+        // The window is long enough so the writes return only if the requests are
+        // produced as they reach the limits.
+        let limits = GroupLimits {
+            window: Duration::from_secs(3600),
+            max_entries: 1024,
+            max_bytes: 100,
+        };
+        let batcher = WriteBatcher::new(
+            MessageProducer::new(message_queue.clone()),
+            limits,
+            runtime.clone(),
+        );
+
+        runtime.block_on(async {
+            // Every message has 4 bytes key and 46 bytes value, so the first two
+            // writes fill a request.
+            let writes = [1, 1]
+                .iter()
+                .map(|num| batcher.submit("a".to_string(), build_messages(*num, 46)));
+            let writes = futures::future::try_join_all(writes);
+            let offsets = tokio::time::timeout(Duration::from_secs(10), writes)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(offsets, vec![vec![0], vec![1]]);
+
+            // The write exceeding the limit is produced alone.
+            let write = batcher.submit("a".to_string(), build_messages(3, 46));
+            let offsets = tokio::time::timeout(Duration::from_secs(10), write)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(offsets, vec![2, 3, 4]);
+        });
+
+        let requests = message_queue.requests.lock().unwrap().clone();
+        assert_eq!(requests["a"], vec![2, 3]);
+    }
+}
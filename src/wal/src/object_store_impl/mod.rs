@@ -0,0 +1,27 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Wal implementation appending the logs to the segment files in the object
+//! store, so no local disk is required.
+//!
+//! The object stores don't support appending, so the active segment of a
+//! region is buffered in memory and uploaded as a whole on every write, and a
+//! new segment is rolled once it exceeds the segment size. The concurrent
+//! writes are persisted by a single upload.
+
+pub mod config;
+pub mod wal_manager;
@@ -0,0 +1,1100 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use bytes_ext::Bytes;
+use codec::Encoder;
+use common_types::{table::TableId, SequenceNumber, MIN_SEQUENCE_NUMBER};
+use futures::TryStreamExt;
+use generic_error::BoxError;
+use logger::{debug, error, info};
+use object_store::{
+    aliyun, config::ObjectStoreOptions, local_file, prefix::StoreWithPrefix, s3, ObjectStoreRef,
+    Path,
+};
+use serde::{Deserialize, Serialize};
+use snafu::{ensure, ResultExt};
+use tokio::sync::{Mutex as AsyncMutex, RwLock};
+
+use crate::{
+    config::{Config, StorageConfig},
+    kv_encoder::CommonLogEncoding,
+    local_storage_impl::record_encoding::{Record, RecordEncoding},
+    log_batch::{LogEntry, LogWriteBatch},
+    manager::{
+        self, error::*, AsyncLogIterator, BatchLogIteratorAdapter, OpenedWals, ReadContext,
+        ReadRequest, RegionId, RegionLogsSummary, ScanContext, ScanRequest, TableLogsSummary,
+        WalLocation, WalManager, WalManagerRef, WalRuntimes, WalsOpener, WriteContext,
+        MANIFEST_DIR_NAME, WAL_DIR_NAME,
+    },
+    object_store_impl::config::ObjectStoreStorageConfig,
+};
+
+type SegmentId = u64;
+
+const SEGMENT_FILE_SUFFIX: &str = ".seg";
+const REGION_META_FILE: &str = "META";
+
+/// Sequence range and size of the logs of a table in a segment.
+#[derive(Debug, Clone, Copy)]
+struct SequenceRange {
+    min: SequenceNumber,
+    max: SequenceNumber,
+    size: u64,
+}
+
+/// Tables whose logs are in the segment.
+type SegmentTables = HashMap<TableId, SequenceRange>;
+
+fn add_to_segment_tables(
+    tables: &mut SegmentTables,
+    table_id: TableId,
+    sequence: SequenceNumber,
+    size: usize,
+) {
+    let size = size as u64;
+    tables
+        .entry(table_id)
+        .and_modify(|range| {
+            range.min = range.min.min(sequence);
+            range.max = range.max.max(sequence);
+            range.size += size;
+        })
+        .or_insert(SequenceRange {
+            min: sequence,
+            max: sequence,
+            size,
+        });
+}
+
+/// Segment still in memory, which is pending to upload or being uploaded.
+#[derive(Debug)]
+struct BufferedSegment {
+    id: SegmentId,
+    data: Vec<u8>,
+    tables: SegmentTables,
+}
+
+impl BufferedSegment {
+    fn new(id: SegmentId) -> Self {
+        Self {
+            id,
+            data: Vec::new(),
+            tables: HashMap::new(),
+        }
+    }
+}
+
+/// Persisted states of the tables, which are stored in the meta file of the
+/// region, so the sequences are kept increasing after their segments are
+/// deleted.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+struct TableState {
+    next_sequence: SequenceNumber,
+    deleted_up_to: SequenceNumber,
+}
+
+impl TableState {
+    fn new() -> Self {
+        Self {
+            next_sequence: MIN_SEQUENCE_NUMBER + 1,
+            deleted_up_to: MIN_SEQUENCE_NUMBER,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct RegionMeta {
+    tables: HashMap<TableId, TableState>,
+}
+
+/// Every upload persists the logs appended since the last upload as a new
+/// segment, so the uploaded segments are immutable and never re-uploaded.
+#[derive(Debug)]
+struct RegionState {
+    /// Segments uploaded, ordered by id
+    segments: BTreeMap<SegmentId, SegmentTables>,
+    /// Segment being uploaded
+    uploading: Option<BufferedSegment>,
+    /// Logs appended but not uploaded yet
+    pending: BufferedSegment,
+    /// Segments before this id are persisted
+    persisted: SegmentId,
+    tables: HashMap<TableId, TableState>,
+    /// Set if an upload fails, and the region must be reopened as the
+    /// sequences of the failed logs are already allocated.
+    poisoned: bool,
+}
+
+/// Source of the logs to read.
+#[derive(Debug)]
+enum SegmentSource {
+    Remote(Path),
+    Buffered(Bytes),
+}
+
+/// Logs of a region, whose segments are stored under `dir`.
+#[derive(Debug)]
+struct Region {
+    store: ObjectStoreRef,
+    dir: String,
+    state: Mutex<RegionState>,
+    /// Serialize the modifications to the object store
+    upload_lock: AsyncMutex<()>,
+}
+
+impl Region {
+    async fn open(store: ObjectStoreRef, dir: String) -> Result<Self> {
+        let prefix = Path::from(dir.as_str());
+        let objects: Vec<_> = store
+            .list(Some(&prefix))
+            .try_collect()
+            .await
+            .box_err()
+            .context(Open {
+                wal_path: dir.clone(),
+            })?;
+        let mut segment_ids: Vec<_> = objects
+            .iter()
+            .filter_map(|object| {
+                object
+                    .location
+                    .filename()?
+                    .strip_suffix(SEGMENT_FILE_SUFFIX)?
+                    .parse::<SegmentId>()
+                    .ok()
+            })
+            .collect();
+        segment_ids.sort_unstable();
+
+        let mut tables = match store.get(&Self::meta_path(&dir)).await {
+            Ok(v) => {
+                let bytes = v.bytes().await.box_err().context(Open {
+                    wal_path: dir.clone(),
+                })?;
+                let meta: RegionMeta = serde_json::from_slice(&bytes).box_err().context(Open {
+                    wal_path: dir.clone(),
+                })?;
+                meta.tables
+            }
+            Err(object_store::ObjectStoreError::NotFound { .. }) => HashMap::new(),
+            Err(e) => {
+                return Err(e).box_err().context(Open {
+                    wal_path: dir.clone(),
+                })
+            }
+        };
+
+        // Recover the tables in the segments and the sequences.
+        let mut segments = BTreeMap::new();
+        for segment_id in &segment_ids {
+            let data = store
+                .get(&Self::segment_path(&dir, *segment_id))
+                .await
+                .box_err()
+                .context(Open {
+                    wal_path: dir.clone(),
+                })?
+                .bytes()
+                .await
+                .box_err()
+                .context(Open {
+                    wal_path: dir.clone(),
+                })?;
+            let mut segment_tables = SegmentTables::new();
+            for (table_id, sequence, payload) in decode_records(&data).context(Open {
+                wal_path: dir.clone(),
+            })? {
+                add_to_segment_tables(&mut segment_tables, table_id, sequence, payload.len());
+                let table = tables.entry(table_id).or_insert_with(TableState::new);
+                table.next_sequence = table.next_sequence.max(sequence + 1);
+            }
+            segments.insert(*segment_id, segment_tables);
+        }
+
+        // Never overwrite the recovered segments.
+        let pending_id = segment_ids.last().map(|id| id + 1).unwrap_or_default();
+        info!(
+            "Open object store wal region, dir:{dir}, segments:{}, tables:{}",
+            segments.len(),
+            tables.len()
+        );
+
+        Ok(Self {
+            store,
+            dir,
+            state: Mutex::new(RegionState {
+                segments,
+                uploading: None,
+                pending: BufferedSegment::new(pending_id),
+                persisted: pending_id,
+                tables,
+                poisoned: false,
+            }),
+            upload_lock: AsyncMutex::new(()),
+        })
+    }
+
+    fn segment_path(dir: &str, segment_id: SegmentId) -> Path {
+        Path::from(format!("{dir}/{segment_id:020}{SEGMENT_FILE_SUFFIX}"))
+    }
+
+    fn meta_path(dir: &str) -> Path {
+        Path::from(format!("{dir}/{REGION_META_FILE}"))
+    }
+
+    fn sequence_num(&self, table_id: TableId) -> SequenceNumber {
+        let state = self.state.lock().unwrap();
+        state
+            .tables
+            .get(&table_id)
+            .map(|table| table.next_sequence - 1)
+            .unwrap_or(MIN_SEQUENCE_NUMBER)
+    }
+
+    fn is_poisoned(&self) -> bool {
+        self.state.lock().unwrap().poisoned
+    }
+
+    /// Append the batches to the pending segment and wait for them to be
+    /// persisted, returns the last sequence of every batch.
+    async fn write(&self, batches: &[LogWriteBatch]) -> Result<Vec<SequenceNumber>> {
+        let mut sequences = Vec::with_capacity(batches.len());
+        let segment_id = {
+            let mut state = self.state.lock().unwrap();
+            ensure!(
+                !state.poisoned,
+                RegionPoisoned {
+                    region: self.dir.clone(),
+                }
+            );
+
+            // Encode all the batches before allocating the sequences, so nothing is
+            // appended if any of them fails.
+            let state = &mut *state;
+            let encoding = RecordEncoding::newest();
+            let mut next_sequences = HashMap::new();
+            let mut data = Vec::new();
+            let mut records = Vec::new();
+            for batch in batches {
+                manager::collect_write_log_metrics(batch);
+
+                let table_id = batch.location.table_id;
+                let next_sequence = next_sequences.entry(table_id).or_insert_with(|| {
+                    state
+                        .tables
+                        .get(&table_id)
+                        .map(|table| table.next_sequence)
+                        .unwrap_or(MIN_SEQUENCE_NUMBER + 1)
+                });
+                for entry in &batch.entries {
+                    let sequence = *next_sequence;
+                    let record = Record::new(table_id, sequence, &entry.payload)
+                        .box_err()
+                        .context(Encoding)?;
+                    encoding
+                        .encode(&mut data, &record)
+                        .box_err()
+                        .context(Encoding)?;
+                    records.push((table_id, sequence, entry.payload.len()));
+                    *next_sequence += 1;
+                }
+                sequences.push(*next_sequence - 1);
+            }
+
+            state.pending.data.extend_from_slice(&data);
+            for (table_id, sequence, size) in records {
+                add_to_segment_tables(&mut state.pending.tables, table_id, sequence, size);
+            }
+            for (table_id, next_sequence) in next_sequences {
+                let table = state.tables.entry(table_id).or_insert_with(TableState::new);
+                table.next_sequence = next_sequence;
+            }
+
+            state.pending.id
+        };
+
+        self.persist(segment_id).await?;
+
+        Ok(sequences)
+    }
+
+    /// Upload the pending logs until the segment `segment_id` is persisted.
+    ///
+    /// The logs appended by others before the upload are persisted together,
+    /// and the ones appended during the upload are persisted by the next
+    /// upload. The region is poisoned if the upload fails, so all the writes
+    /// waiting for the upload fail.
+    async fn persist(&self, segment_id: SegmentId) -> Result<()> {
+        let _guard = self.upload_lock.lock().await;
+        loop {
+            let (upload_id, data) = {
+                let mut state = self.state.lock().unwrap();
+                ensure!(
+                    !state.poisoned,
+                    RegionPoisoned {
+                        region: self.dir.clone(),
+                    }
+                );
+                if state.persisted > segment_id {
+                    return Ok(());
+                }
+
+                let next = BufferedSegment::new(state.pending.id + 1);
+                let segment = std::mem::replace(&mut state.pending, next);
+                let upload = (segment.id, Bytes::from(segment.data.clone()));
+                state.uploading = Some(segment);
+                upload
+            };
+
+            let res = self
+                .store
+                .put(&Self::segment_path(&self.dir, upload_id), data.into())
+                .await;
+
+            let mut state = self.state.lock().unwrap();
+            let segment = state.uploading.take().unwrap();
+            if let Err(e) = res {
+                state.poisoned = true;
+                error!(
+                    "Failed to upload object store wal segment, the region is poisoned, dir:{}, segment_id:{upload_id}, err:{e}",
+                    self.dir
+                );
+                return Err(e).box_err().context(Write);
+            }
+            state.segments.insert(segment.id, segment.tables);
+            state.persisted = upload_id + 1;
+        }
+    }
+
+    /// Mark the logs of the table up to `sequence` deleted, and delete the
+    /// segments whose logs are all deleted.
+    async fn mark_delete_up_to(&self, table_id: TableId, sequence: SequenceNumber) -> Result<()> {
+        let _guard = self.upload_lock.lock().await;
+        let (meta, deletable) = {
+            let mut state = self.state.lock().unwrap();
+            let table = state.tables.entry(table_id).or_insert_with(TableState::new);
+            table.deleted_up_to = table.deleted_up_to.max(sequence);
+
+            let deletable: Vec<_> = state
+                .segments
+                .iter()
+                .filter(|(_, tables)| {
+                    tables.iter().all(|(table_id, range)| {
+                        state
+                            .tables
+                            .get(table_id)
+                            .map(|table| range.max <= table.deleted_up_to)
+                            .unwrap_or(false)
+                    })
+                })
+                .map(|(segment_id, _)| *segment_id)
+                .collect();
+            let meta = RegionMeta {
+                tables: state.tables.clone(),
+            };
+            (meta, deletable)
+        };
+
+        // Persist the sequences before deleting the segments holding them, and
+        // the deleted logs won't be read again after recovery.
+        let meta = serde_json::to_vec(&meta).box_err().context(Encoding)?;
+        self.store
+            .put(&Self::meta_path(&self.dir), meta.into())
+            .await
+            .box_err()
+            .context(Delete)?;
+        for segment_id in deletable {
+            self.store
+                .delete(&Self::segment_path(&self.dir, segment_id))
+                .await
+                .box_err()
+                .context(Delete)?;
+            self.state.lock().unwrap().segments.remove(&segment_id);
+            debug!(
+                "Delete object store wal segment, dir:{}, segment_id:{segment_id}",
+                self.dir
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Collect the segments holding the logs of `table_id` (all tables if it
+    /// is None) in `[start, end]`.
+    fn segments_to_read(
+        &self,
+        table_id: Option<TableId>,
+        start: SequenceNumber,
+        end: SequenceNumber,
+    ) -> (Vec<SegmentSource>, HashMap<TableId, SequenceNumber>) {
+        let state = self.state.lock().unwrap();
+        let overlaps = |tables: &SegmentTables| match table_id {
+            Some(table_id) => tables
+                .get(&table_id)
+                .map(|range| range.min <= end && range.max >= start)
+                .unwrap_or(false),
+            None => !tables.is_empty(),
+        };
+
+        let mut sources = Vec::new();
+        for (segment_id, tables) in &state.segments {
+            if overlaps(tables) {
+                sources.push(SegmentSource::Remote(Self::segment_path(
+                    &self.dir,
+                    *segment_id,
+                )));
+            }
+        }
+        for segment in state
+            .uploading
+            .iter()
+            .chain(std::iter::once(&state.pending))
+        {
+            if overlaps(&segment.tables) {
+                sources.push(SegmentSource::Buffered(Bytes::from(segment.data.clone())));
+            }
+        }
+
+        let deleted = state
+            .tables
+            .iter()
+            .map(|(table_id, table)| (*table_id, table.deleted_up_to))
+            .collect();
+        (sources, deleted)
+    }
+
+    /// Summarize the logs of the tables, the sizes include the deleted logs
+    /// in the segments not deleted yet.
+    fn summarize(&self, region_id: RegionId) -> RegionLogsSummary {
+        let state = self.state.lock().unwrap();
+        let segment_tables = state
+            .segments
+            .values()
+            .chain(state.uploading.iter().map(|segment| &segment.tables))
+            .chain(std::iter::once(&state.pending.tables));
+        let mut ranges: HashMap<TableId, (Option<SequenceNumber>, u64)> = HashMap::new();
+        for tables in segment_tables {
+            for (table_id, range) in tables {
+                let deleted_up_to = state
+                    .tables
+                    .get(table_id)
+                    .map(|table| table.deleted_up_to)
+                    .unwrap_or_default();
+                if range.max <= deleted_up_to {
+                    continue;
+                }
+
+                let (min_sequence, size) = ranges.entry(*table_id).or_default();
+                let min = range.min.max(deleted_up_to + 1);
+                *min_sequence = Some(min_sequence.map_or(min, |v| v.min(min)));
+                *size += range.size;
+            }
+        }
+
+        let tables = state
+            .tables
+            .iter()
+            .map(|(table_id, table)| {
+                let (min_sequence, approximate_size) =
+                    ranges.get(table_id).copied().unwrap_or_default();
+                TableLogsSummary {
+                    table_id: *table_id,
+                    min_sequence,
+                    max_sequence: table.next_sequence - 1,
+                    approximate_size,
+                }
+            })
+            .collect();
+
+        RegionLogsSummary { region_id, tables }
+    }
+
+    fn statistics(&self) -> String {
+        let state = self.state.lock().unwrap();
+        format!(
+            "dir:{}, segments:{}, uploading:{}, pending_id:{}, pending_size:{}, tables:{}, poisoned:{}",
+            self.dir,
+            state.segments.len(),
+            state.uploading.is_some(),
+            state.pending.id,
+            state.pending.data.len(),
+            state.tables.len(),
+            state.poisoned
+        )
+    }
+}
+
+fn decode_records(
+    data: &[u8],
+) -> std::result::Result<Vec<(TableId, SequenceNumber, Vec<u8>)>, generic_error::GenericError> {
+    let encoding = RecordEncoding::newest();
+    let mut records = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        let record = encoding.decode(&data[pos..]).box_err()?;
+        pos += record.len();
+        records.push((record.table_id, record.sequence_num, record.value.to_vec()));
+    }
+
+    Ok(records)
+}
+
+/// Iterator loading the segments one by one.
+#[derive(Debug)]
+struct ObjectStoreLogIterator {
+    store: ObjectStoreRef,
+    sources: VecDeque<SegmentSource>,
+    table_id: Option<TableId>,
+    start: SequenceNumber,
+    end: SequenceNumber,
+    /// Logs up to the sequence of each table are deleted
+    deleted: HashMap<TableId, SequenceNumber>,
+    /// Decoder of the log values, which strips the version header and
+    /// decompresses the compressed ones.
+    log_encoding: CommonLogEncoding,
+    entries: Vec<(TableId, SequenceNumber, Vec<u8>)>,
+    cursor: usize,
+}
+
+impl ObjectStoreLogIterator {
+    fn new(
+        store: ObjectStoreRef,
+        sources: Vec<SegmentSource>,
+        table_id: Option<TableId>,
+        start: SequenceNumber,
+        end: SequenceNumber,
+        deleted: HashMap<TableId, SequenceNumber>,
+    ) -> Self {
+        Self {
+            store,
+            sources: sources.into(),
+            table_id,
+            start,
+            end,
+            deleted,
+            log_encoding: CommonLogEncoding::newest(),
+            entries: Vec::new(),
+            cursor: 0,
+        }
+    }
+
+    fn is_needed(&self, table_id: TableId, sequence: SequenceNumber) -> bool {
+        if let Some(expect) = self.table_id {
+            if table_id != expect {
+                return false;
+            }
+        }
+        let deleted_up_to = self.deleted.get(&table_id).copied().unwrap_or_default();
+
+        sequence > deleted_up_to && sequence >= self.start && sequence <= self.end
+    }
+
+    async fn load_next_segment(&mut self) -> Result<bool> {
+        let Some(source) = self.sources.pop_front() else {
+            return Ok(false);
+        };
+        let data = match source {
+            SegmentSource::Remote(path) => self
+                .store
+                .get(&path)
+                .await
+                .box_err()
+                .context(Read)?
+                .bytes()
+                .await
+                .box_err()
+                .context(Read)?,
+            SegmentSource::Buffered(data) => data,
+        };
+
+        let mut entries = decode_records(&data).context(Decoding)?;
+        entries.retain(|(table_id, sequence, _)| self.is_needed(*table_id, *sequence));
+        for (_, _, value) in &mut entries {
+            let payload = self
+                .log_encoding
+                .decode_value(value)
+                .box_err()
+                .context(Decoding)?
+                .into_owned();
+            *value = payload;
+        }
+        self.entries = entries;
+        self.cursor = 0;
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl AsyncLogIterator for ObjectStoreLogIterator {
+    async fn next_log_entry(&mut self) -> Result<Option<LogEntry<&'_ [u8]>>> {
+        while self.cursor >= self.entries.len() {
+            if !self.load_next_segment().await? {
+                return Ok(None);
+            }
+        }
+
+        let (table_id, sequence, payload) = &self.entries[self.cursor];
+        self.cursor += 1;
+        Ok(Some(LogEntry {
+            table_id: *table_id,
+            sequence: *sequence,
+            payload: payload.as_slice(),
+        }))
+    }
+}
+
+/// Wal storing the logs of every region in its own directory of the object
+/// store.
+#[derive(Debug)]
+pub struct ObjectStoreImpl {
+    store: ObjectStoreRef,
+    root: String,
+    regions: RwLock<HashMap<RegionId, Arc<Region>>>,
+}
+
+impl ObjectStoreImpl {
+    pub fn new(store: ObjectStoreRef, root: String) -> Self {
+        Self {
+            store,
+            root,
+            regions: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Get the region, which is recovered from the object store if it isn't
+    /// opened yet or it is poisoned.
+    async fn region(&self, region_id: RegionId) -> Result<Arc<Region>> {
+        if let Some(region) = self.regions.read().await.get(&region_id) {
+            if !region.is_poisoned() {
+                return Ok(region.clone());
+            }
+        }
+
+        let mut regions = self.regions.write().await;
+        if let Some(region) = regions.get(&region_id) {
+            if !region.is_poisoned() {
+                return Ok(region.clone());
+            }
+            info!("Reopen poisoned object store wal region, region_id:{region_id}");
+        }
+        let dir = format!("{}/{region_id}", self.root);
+        let region = Arc::new(Region::open(self.store.clone(), dir).await?);
+        regions.insert(region_id, region.clone());
+
+        Ok(region)
+    }
+}
+
+#[async_trait]
+impl WalManager for ObjectStoreImpl {
+    async fn sequence_num(&self, location: WalLocation) -> Result<SequenceNumber> {
+        let region = self.region(location.region_id).await?;
+        Ok(region.sequence_num(location.table_id))
+    }
+
+    async fn mark_delete_entries_up_to(
+        &self,
+        location: WalLocation,
+        sequence_num: SequenceNumber,
+    ) -> Result<()> {
+        let region = self.region(location.region_id).await?;
+        region
+            .mark_delete_up_to(location.table_id, sequence_num)
+            .await
+    }
+
+    async fn close_region(&self, region_id: RegionId) -> Result<()> {
+        // The logs are persisted before the writes return, so nothing to flush.
+        self.regions.write().await.remove(&region_id);
+        debug!("Close object store wal region, region_id:{region_id}");
+
+        Ok(())
+    }
+
+    async fn close_gracefully(&self) -> Result<()> {
+        self.regions.write().await.clear();
+        info!("Close object store wal gracefully, root:{}", self.root);
+
+        Ok(())
+    }
+
+    async fn read_batch(
+        &self,
+        ctx: &ReadContext,
+        req: &ReadRequest,
+    ) -> Result<BatchLogIteratorAdapter> {
+        let (Some(start), Some(end)) = (
+            req.start.as_start_sequence_number(),
+            req.end.as_end_sequence_number(),
+        ) else {
+            return Ok(BatchLogIteratorAdapter::empty());
+        };
+        if start > end {
+            return Ok(BatchLogIteratorAdapter::empty());
+        }
+
+        let location = req.location;
+        let region = self.region(location.region_id).await?;
+        let (sources, deleted) = region.segments_to_read(Some(location.table_id), start, end);
+        let iter = ObjectStoreLogIterator::new(
+            self.store.clone(),
+            sources,
+            Some(location.table_id),
+            start,
+            end,
+            deleted,
+        );
+
+        Ok(BatchLogIteratorAdapter::new_with_async(
+            Box::new(iter),
+            ctx.batch_size,
+        ))
+    }
+
+    async fn write(&self, _ctx: &WriteContext, batch: &LogWriteBatch) -> Result<SequenceNumber> {
+        let region = self.region(batch.location.region_id).await?;
+        let sequences = region.write(std::slice::from_ref(batch)).await?;
+
+        Ok(sequences[0])
+    }
+
+    async fn write_atomically(
+        &self,
+        _ctx: &WriteContext,
+        batches: &[LogWriteBatch],
+    ) -> Result<Vec<SequenceNumber>> {
+        let Some(first) = batches.first() else {
+            return Ok(Vec::new());
+        };
+        // The batches are persisted by a single upload only if they are in the
+        // same region.
+        let region_id = first.location.region_id;
+        ensure!(
+            batches
+                .iter()
+                .all(|batch| batch.location.region_id == region_id),
+            AtomicWriteNotSupported
+        );
+
+        let region = self.region(region_id).await?;
+        region.write(batches).await
+    }
+
+    async fn scan(&self, ctx: &ScanContext, req: &ScanRequest) -> Result<BatchLogIteratorAdapter> {
+        let region = self.region(req.region_id).await?;
+        let (sources, deleted) =
+            region.segments_to_read(None, MIN_SEQUENCE_NUMBER, SequenceNumber::MAX);
+        let iter = ObjectStoreLogIterator::new(
+            self.store.clone(),
+            sources,
+            None,
+            MIN_SEQUENCE_NUMBER,
+            SequenceNumber::MAX,
+            deleted,
+        );
+
+        Ok(BatchLogIteratorAdapter::new_with_async(
+            Box::new(iter),
+            ctx.batch_size,
+        ))
+    }
+
+    async fn get_statistics(&self) -> Option<String> {
+        let regions = self.regions.read().await;
+        let stats = regions
+            .values()
+            .map(|region| region.statistics())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        Some(stats)
+    }
+
+    async fn summarize_regions(&self) -> Result<Option<Vec<RegionLogsSummary>>> {
+        let regions = self.regions.read().await;
+        let summaries = regions
+            .iter()
+            .map(|(region_id, region)| region.summarize(*region_id))
+            .collect();
+
+        Ok(Some(summaries))
+    }
+}
+
+#[derive(Default)]
+pub struct ObjectStoreWalsOpener;
+
+impl ObjectStoreWalsOpener {
+    fn open_store(options: &ObjectStoreOptions) -> Result<ObjectStoreRef> {
+        let store: ObjectStoreRef = match options {
+            ObjectStoreOptions::Local(local_opts) => {
+                Arc::new(local_file::try_new(local_opts).box_err().context(Open {
+                    wal_path: local_opts.data_dir.clone(),
+                })?)
+            }
+            ObjectStoreOptions::Aliyun(aliyun_opts) => {
+                let store: ObjectStoreRef =
+                    Arc::new(aliyun::try_new(aliyun_opts).box_err().context(Open {
+                        wal_path: aliyun_opts.prefix.clone(),
+                    })?);
+                Arc::new(
+                    StoreWithPrefix::new(aliyun_opts.prefix.clone(), store)
+                        .box_err()
+                        .context(Open {
+                            wal_path: aliyun_opts.prefix.clone(),
+                        })?,
+                )
+            }
+            ObjectStoreOptions::S3(s3_opts) => {
+                let store: ObjectStoreRef =
+                    Arc::new(s3::try_new(s3_opts).box_err().context(Open {
+                        wal_path: s3_opts.prefix.clone(),
+                    })?);
+                Arc::new(
+                    StoreWithPrefix::new(s3_opts.prefix.clone(), store)
+                        .box_err()
+                        .context(Open {
+                            wal_path: s3_opts.prefix.clone(),
+                        })?,
+                )
+            }
+        };
+
+        Ok(store)
+    }
+}
+
+#[async_trait]
+impl WalsOpener for ObjectStoreWalsOpener {
+    async fn open_wals(&self, config: &Config, _runtimes: WalRuntimes) -> Result<OpenedWals> {
+        let StorageConfig::ObjectStore(storage_config) = &config.storage else {
+            return InvalidWalConfig {
+                msg: format!(
+                    "invalid wal storage config while opening object store wal, config:{config:?}"
+                ),
+            }
+            .fail();
+        };
+        let ObjectStoreStorageConfig { object_store } = storage_config.as_ref();
+        let store = Self::open_store(object_store)?;
+
+        let data_wal: WalManagerRef = if config.disable_data {
+            Arc::new(crate::dummy::DoNothing)
+        } else {
+            Arc::new(ObjectStoreImpl::new(
+                store.clone(),
+                WAL_DIR_NAME.to_string(),
+            ))
+        };
+        let manifest_wal = Arc::new(ObjectStoreImpl::new(store, MANIFEST_DIR_NAME.to_string()));
+
+        Ok(OpenedWals {
+            data_wal,
+            manifest_wal,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fmt::Display,
+        ops::Range,
+        sync::atomic::{AtomicBool, Ordering},
+    };
+
+    use futures::stream::BoxStream;
+    use object_store::{
+        config::LocalOptions, GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta,
+        ObjectStore, ObjectStoreError, PutMultipartOpts, PutOptions, PutPayload, PutResult,
+    };
+
+    use super::*;
+    use crate::{
+        kv_encoder::LogBatchEncoder,
+        log_batch::{MemoryPayload, MemoryPayloadDecoder},
+    };
+
+    type StoreResult<T> = std::result::Result<T, ObjectStoreError>;
+
+    /// Store failing all the puts once `fail_put` is set.
+    #[derive(Debug)]
+    struct FailPutStore {
+        store: ObjectStoreRef,
+        fail_put: AtomicBool,
+    }
+
+    impl Display for FailPutStore {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "FailPutStore({})", self.store)
+        }
+    }
+
+    #[async_trait]
+    impl ObjectStore for FailPutStore {
+        async fn put_opts(
+            &self,
+            location: &Path,
+            payload: PutPayload,
+            opts: PutOptions,
+        ) -> StoreResult<PutResult> {
+            if self.fail_put.load(Ordering::Relaxed) {
+                return Err(ObjectStoreError::Generic {
+                    store: "FailPutStore",
+                    source: "injected put failure".into(),
+                });
+            }
+            self.store.put_opts(location, payload, opts).await
+        }
+
+        async fn put_multipart_opts(
+            &self,
+            location: &Path,
+            opts: PutMultipartOpts,
+        ) -> StoreResult<Box<dyn MultipartUpload>> {
+            self.store.put_multipart_opts(location, opts).await
+        }
+
+        async fn get_opts(&self, location: &Path, options: GetOptions) -> StoreResult<GetResult> {
+            self.store.get_opts(location, options).await
+        }
+
+        async fn get_range(&self, location: &Path, range: Range<usize>) -> StoreResult<Bytes> {
+            self.store.get_range(location, range).await
+        }
+
+        async fn delete(&self, location: &Path) -> StoreResult<()> {
+            self.store.delete(location).await
+        }
+
+        fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, StoreResult<ObjectMeta>> {
+            self.store.list(prefix)
+        }
+
+        async fn list_with_delimiter(&self, prefix: Option<&Path>) -> StoreResult<ListResult> {
+            self.store.list_with_delimiter(prefix).await
+        }
+
+        async fn copy(&self, from: &Path, to: &Path) -> StoreResult<()> {
+            self.store.copy(from, to).await
+        }
+
+        async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> StoreResult<()> {
+            self.store.copy_if_not_exists(from, to).await
+        }
+    }
+
+    fn build_batch(location: WalLocation, vals: &[u32]) -> LogWriteBatch {
+        LogBatchEncoder::create(location)
+            .encode_batch(vals.iter().map(|val| MemoryPayload { val: *val }))
+            .expect("should succeed to encode payloads")
+    }
+
+    async fn read_all(wal: &ObjectStoreImpl, location: WalLocation) -> Vec<(SequenceNumber, u32)> {
+        let req = ReadRequest {
+            location,
+            start: manager::ReadBoundary::Min,
+            end: manager::ReadBoundary::Max,
+        };
+        let mut iter = wal.read_batch(&ReadContext::default(), &req).await.unwrap();
+        let entries = iter
+            .next_log_entries(MemoryPayloadDecoder, |_| true, Default::default())
+            .await
+            .unwrap();
+        entries
+            .into_iter()
+            .map(|entry| (entry.sequence, entry.payload.val))
+            .collect()
+    }
+
+    fn open_store(dir: &tempfile::TempDir) -> ObjectStoreRef {
+        let options = LocalOptions::new_with_default(dir.path().to_str().unwrap().to_string());
+        Arc::new(local_file::try_new(&options).unwrap())
+    }
+
+    fn open_wal(dir: &tempfile::TempDir) -> ObjectStoreImpl {
+        ObjectStoreImpl::new(open_store(dir), WAL_DIR_NAME.to_string())
+    }
+
+    #[tokio::test]
+    async fn test_write_read_recover() {
+        let dir = tempfile::tempdir().unwrap();
+        let location = WalLocation::new(1, 1);
+        {
+            let wal = open_wal(&dir);
+            for vals in [&[1, 2][..], &[3], &[4, 5]] {
+                wal.write(&WriteContext::default(), &build_batch(location, vals))
+                    .await
+                    .unwrap();
+            }
+            assert_eq!(wal.sequence_num(location).await.unwrap(), 5);
+
+            wal.mark_delete_entries_up_to(location, 3).await.unwrap();
+            assert_eq!(read_all(&wal, location).await, vec![(4, 4), (5, 5)]);
+        }
+
+        // Logs and sequences are recovered from the object store.
+        let wal = open_wal(&dir);
+        assert_eq!(wal.sequence_num(location).await.unwrap(), 5);
+        assert_eq!(read_all(&wal, location).await, vec![(4, 4), (5, 5)]);
+        let seq = wal
+            .write(&WriteContext::default(), &build_batch(location, &[6]))
+            .await
+            .unwrap();
+        assert_eq!(seq, 6);
+    }
+    #[tokio::test]
+    async fn test_write_put_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = Arc::new(FailPutStore {
+            store: open_store(&dir),
+            fail_put: AtomicBool::new(false),
+        });
+        let wal = ObjectStoreImpl::new(store.clone(), WAL_DIR_NAME.to_string());
+        let location = WalLocation::new(1, 1);
+        wal.write(&WriteContext::default(), &build_batch(location, &[1, 2]))
+            .await
+            .unwrap();
+
+        store.fail_put.store(true, Ordering::Relaxed);
+        assert!(wal
+            .write(&WriteContext::default(), &build_batch(location, &[3]))
+            .await
+            .is_err());
+
+        // The failed logs are neither readable nor persisted, and the region is
+        // reopened from the object store by the next access.
+        store.fail_put.store(false, Ordering::Relaxed);
+        assert_eq!(wal.sequence_num(location).await.unwrap(), 2);
+        assert_eq!(read_all(&wal, location).await, vec![(1, 1), (2, 2)]);
+        let seq = wal
+            .write(&WriteContext::default(), &build_batch(location, &[4]))
+            .await
+            .unwrap();
+        assert_eq!(seq, 3);
+        assert_eq!(read_all(&wal, location).await, vec![(1, 1), (2, 2), (3, 4)]);
+
+        let wal = open_wal(&dir);
+        assert_eq!(read_all(&wal, location).await, vec![(1, 1), (2, 2), (3, 4)]);
+    }
+}
@@ -18,6 +18,7 @@
 //! WalManager implementation based on RocksDB
 
 use std::{
+    borrow::Cow,
     collections::HashMap,
     fmt,
     fmt::Formatter,
@@ -203,6 +204,35 @@ impl TableUnit {
         Ok(log_iter)
     }
 
+    /// Put the log entries of `batch` into the rocksdb write batch `wb`.
+    ///
+    /// Returns the max sequence number allocated for the entries.
+    fn put_batch(&self, wb: &WriteBatch, batch: &LogWriteBatch) -> Result<u64> {
+        manager::collect_write_log_metrics(batch);
+
+        let entries_num = batch.len() as u64;
+        let mut next_sequence_num = self.alloc_sequence_num(entries_num);
+        let mut key_buf = BytesMut::new();
+
+        for entry in &batch.entries {
+            let region_id = batch.location.region_id;
+            self.log_encoding
+                .encode_key(
+                    &mut key_buf,
+                    &CommonLogKey::new(region_id, batch.location.table_id, next_sequence_num),
+                )
+                .box_err()
+                .context(Encoding)?;
+            wb.put(&key_buf, &entry.payload)
+                .map_err(|e| e.into())
+                .context(Write)?;
+
+            next_sequence_num += 1;
+        }
+
+        Ok(next_sequence_num - 1)
+    }
+
     async fn write(&self, ctx: &WriteContext, batch: &LogWriteBatch) -> Result<u64> {
         debug!(
             "Wal table unit begin writing, ctx:{:?}, log_entries_num:{}",
@@ -210,32 +240,8 @@ impl TableUnit {
             batch.entries.len()
         );
 
-        manager::collect_write_log_metrics(batch);
-
-        let entries_num = batch.len() as u64;
-        let (wb, max_sequence_num) = {
-            let wb = WriteBatch::default();
-            let mut next_sequence_num = self.alloc_sequence_num(entries_num);
-            let mut key_buf = BytesMut::new();
-
-            for entry in &batch.entries {
-                let region_id = batch.location.region_id;
-                self.log_encoding
-                    .encode_key(
-                        &mut key_buf,
-                        &CommonLogKey::new(region_id, batch.location.table_id, next_sequence_num),
-                    )
-                    .box_err()
-                    .context(Encoding)?;
-                wb.put(&key_buf, &entry.payload)
-                    .map_err(|e| e.into())
-                    .context(Write)?;
-
-                next_sequence_num += 1;
-            }
-
-            (wb, next_sequence_num - 1)
-        };
+        let wb = WriteBatch::default();
+        let max_sequence_num = self.put_batch(&wb, batch)?;
 
         let db = self.db.clone();
         self.runtime
@@ -729,6 +735,8 @@ pub struct RocksLogIterator {
     seeked: bool,
     /// RocksDB iterator
     iter: DBIterator<Arc<DB>>,
+    /// Buffer holding the current log value if it is decompressed
+    decompressed_value: Vec<u8>,
 }
 
 impl fmt::Debug for RocksLogIterator {
@@ -758,6 +766,7 @@ impl RocksLogIterator {
             max_log_key,
             seeked: false,
             iter,
+            decompressed_value: Vec::new(),
         }
     }
 
@@ -770,6 +779,7 @@ impl RocksLogIterator {
             max_log_key: CommonLogKey::new(0, 0, 0),
             seeked: false,
             iter,
+            decompressed_value: Vec::new(),
         }
     }
 
@@ -835,11 +845,20 @@ impl SyncLogIterator for RocksLogIterator {
         self.no_more_data = self.is_end_reached(&curr_log_key);
 
         if self.is_valid_log_key(&curr_log_key) {
-            let payload = self
+            let payload = match self
                 .log_encoding
                 .decode_value(self.iter.value())
                 .box_err()
-                .context(Decoding)?;
+                .context(CorruptedRecord {
+                    table_id: curr_log_key.table_id,
+                    sequence: curr_log_key.sequence_num,
+                })? {
+                Cow::Borrowed(v) => v,
+                Cow::Owned(v) => {
+                    self.decompressed_value = v;
+                    self.decompressed_value.as_slice()
+                }
+            };
             let log_entry = LogEntry {
                 table_id: curr_log_key.table_id,
                 sequence: curr_log_key.sequence_num,
@@ -917,6 +936,39 @@ impl WalManager for RocksImpl {
         table_unit.write(ctx, batch).await
     }
 
+    async fn write_atomically(
+        &self,
+        ctx: &WriteContext,
+        batches: &[LogWriteBatch],
+    ) -> Result<Vec<SequenceNumber>> {
+        debug!(
+            "Wal begin writing batches atomically, ctx:{:?}, batches_num:{}",
+            ctx,
+            batches.len()
+        );
+
+        // All the entries are put into one rocksdb write batch, which is applied
+        // atomically.
+        let wb = WriteBatch::default();
+        let mut max_sequence_nums = Vec::with_capacity(batches.len());
+        for batch in batches {
+            let table_unit = self.get_or_create_table_unit(batch.location);
+            max_sequence_nums.push(table_unit.put_batch(&wb, batch)?);
+        }
+
+        let db = self.db.clone();
+        self.runtime
+            .spawn_blocking(move || {
+                db.write(&wb)
+                    .map(|_| max_sequence_nums)
+                    .map_err(|e| e.into())
+                    .context(Write)
+            })
+            .await
+            .box_err()
+            .context(Write)?
+    }
+
     async fn scan(&self, ctx: &ScanContext, req: &ScanRequest) -> Result<BatchLogIteratorAdapter> {
         debug!("Wal region begin scanning, ctx:{:?}, req:{:?}", ctx, req);
 
@@ -16,6 +16,7 @@
 // under the License.
 
 use serde::{Deserialize, Serialize};
+use size_ext::ReadableSize;
 use table_kv::config::ObkvConfig;
 use time_ext::ReadableDuration;
 
@@ -81,6 +82,7 @@ impl From<ManifestNamespaceConfig> for NamespaceConfig {
             clean_scan_timeout: manifest_config.clean_scan_timeout,
             clean_scan_batch_size: manifest_config.clean_scan_batch_size,
             bucket_create_parallelism: manifest_config.bucket_create_parallelism,
+            ..Default::default()
         }
     }
 }
@@ -103,6 +105,14 @@ pub struct WalNamespaceConfig {
     pub init_scan_timeout: ReadableDuration,
     pub init_scan_batch_size: i32,
     pub bucket_create_parallelism: usize,
+
+    /// Time window to batch the writes of different tables in the same region
+    /// into one write, 0 means disable batching.
+    pub write_batch_window: ReadableDuration,
+    /// Max number of key-value pairs of a batched write.
+    pub write_batch_max_entries: usize,
+    /// Max bytes of a batched write.
+    pub write_batch_max_bytes: ReadableSize,
 }
 
 impl Default for WalNamespaceConfig {
@@ -116,6 +126,9 @@ impl Default for WalNamespaceConfig {
             init_scan_timeout: namespace_config.init_scan_timeout,
             init_scan_batch_size: namespace_config.init_scan_batch_size,
             bucket_create_parallelism: namespace_config.bucket_create_parallelism,
+            write_batch_window: namespace_config.write_batch_window,
+            write_batch_max_entries: namespace_config.write_batch_max_entries,
+            write_batch_max_bytes: namespace_config.write_batch_max_bytes,
         }
     }
 }
@@ -129,6 +142,9 @@ impl From<WalNamespaceConfig> for NamespaceConfig {
             init_scan_timeout: wal_config.init_scan_timeout,
             init_scan_batch_size: wal_config.init_scan_batch_size,
             bucket_create_parallelism: wal_config.bucket_create_parallelism,
+            write_batch_window: wal_config.write_batch_window,
+            write_batch_max_entries: wal_config.write_batch_max_entries,
+            write_batch_max_bytes: wal_config.write_batch_max_bytes,
             ..Default::default()
         }
     }
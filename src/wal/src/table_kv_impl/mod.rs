@@ -25,6 +25,7 @@ mod namespace;
 mod table_unit;
 
 pub mod wal;
+mod write_batcher;
 
 mod consts {
     /// Table name of the meta table.
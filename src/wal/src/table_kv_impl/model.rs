@@ -22,11 +22,13 @@ use std::{str, time::Duration};
 use common_types::{table::TableId, time::Timestamp};
 use macros::define_result;
 use serde::{Deserialize, Serialize};
+use size_ext::ReadableSize;
 use snafu::{ensure, Backtrace, ResultExt, Snafu};
 use table_kv::ScanContext;
 use time_ext::ReadableDuration;
 
 use crate::{
+    group_commit::GroupLimits,
     manager::SequenceNumber,
     table_kv_impl::{consts, table_unit::CleanContext},
 };
@@ -281,6 +283,14 @@ pub struct NamespaceConfig {
     pub clean_scan_timeout: ReadableDuration,
     pub clean_scan_batch_size: usize,
     pub bucket_create_parallelism: usize,
+
+    /// Time window to batch the writes of different tables in the same region
+    /// into one write, 0 means disable batching.
+    pub write_batch_window: ReadableDuration,
+    /// Max number of key-value pairs of a batched write.
+    pub write_batch_max_entries: usize,
+    /// Max bytes of a batched write.
+    pub write_batch_max_bytes: ReadableSize,
 }
 
 impl NamespaceConfig {
@@ -320,6 +330,15 @@ impl NamespaceConfig {
         self.new_init_scan_ctx()
     }
 
+    /// Limits to batch the writes, returns `None` if batching is disabled.
+    pub fn write_batch_limits(&self) -> Option<GroupLimits> {
+        (!self.write_batch_window.is_zero()).then(|| GroupLimits {
+            window: self.write_batch_window.0,
+            max_entries: self.write_batch_max_entries,
+            max_bytes: self.write_batch_max_bytes.as_byte() as usize,
+        })
+    }
+
     pub fn new_clean_ctx(&self) -> CleanContext {
         CleanContext {
             scan_timeout: self.clean_scan_timeout.0,
@@ -342,6 +361,10 @@ impl Default for NamespaceConfig {
             clean_scan_timeout: default_clean_ctx.scan_timeout.into(),
             clean_scan_batch_size: default_clean_ctx.batch_size,
             bucket_create_parallelism: 32,
+
+            write_batch_window: ReadableDuration::millis(0),
+            write_batch_max_entries: 1024,
+            write_batch_max_bytes: ReadableSize::mb(4),
         }
     }
 }
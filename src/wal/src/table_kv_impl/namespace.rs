@@ -51,6 +51,7 @@ use crate::{
         consts, encoding,
         model::{BucketEntry, NamespaceConfig, NamespaceEntry},
         table_unit::{TableLogIterator, TableUnit, TableUnitRef},
+        write_batcher::{KvWriter, WriteBatcher, WriteBatcherRef},
     },
 };
 
@@ -253,6 +254,8 @@ struct NamespaceInner<T> {
     operator: Mutex<TableOperator>,
     // Only one thread can persist and create a new bucket.
     bucket_creator: Mutex<BucketCreator>,
+    /// Batch the writes of different tables in a region if set.
+    write_batcher: Option<WriteBatcherRef<T>>,
     config: NamespaceConfig,
 }
 
@@ -675,7 +678,13 @@ impl<T: TableKv> NamespaceInner<T> {
         let table_unit = self.get_or_create_table_unit(batch.location).await?;
 
         let sequence = table_unit
-            .write_log(&self.table_kv, &bucket, ctx, batch)
+            .write_log(
+                &self.table_kv,
+                self.write_batcher.as_deref(),
+                &bucket,
+                ctx,
+                batch,
+            )
             .await
             .context(WriteTableUnit {
                 namespace: self.name(),
@@ -1064,6 +1073,13 @@ impl<T: TableKv> Namespace<T> {
         }
 
         let bucket_set = BucketSet::new(config.ttl.is_some());
+        let write_batcher = config.write_batch_limits().map(|limits| {
+            Arc::new(WriteBatcher::new(
+                KvWriter::new(table_kv.clone(), runtimes.write_runtime.clone()),
+                limits,
+                runtimes.default_runtime.clone(),
+            ))
+        });
 
         let inner = Arc::new(NamespaceInner {
             runtimes: runtimes.clone(),
@@ -1077,6 +1093,7 @@ impl<T: TableKv> Namespace<T> {
                 bucket_create_parallelism: config.bucket_create_parallelism,
             }),
             bucket_creator: Mutex::new(BucketCreator),
+            write_batcher,
             config,
         });
 
@@ -1644,6 +1661,7 @@ mod tests {
     use crate::{
         kv_encoder::{LogBatchEncoder, LogEncoding},
         log_batch::{MemoryPayload, MemoryPayloadDecoder, PayloadDecodeContext, PayloadDecoder},
+        manager::SyncLogIterator,
         table_kv_impl::consts,
     };
 
@@ -1670,6 +1688,7 @@ mod tests {
         table_kv: T,
         runtime: Arc<Runtime>,
         ttl: Option<Duration>,
+        write_batch_window: Duration,
     }
 
     impl<T: TableKv> NamespaceMocker<T> {
@@ -1679,6 +1698,7 @@ mod tests {
                 table_kv,
                 runtime,
                 ttl: None,
+                write_batch_window: Duration::ZERO,
             }
         }
 
@@ -1687,11 +1707,17 @@ mod tests {
             self
         }
 
+        fn write_batch_window(mut self, window: Duration) -> Self {
+            self.write_batch_window = window;
+            self
+        }
+
         fn build(self) -> Namespace<T> {
             let config = NamespaceConfig {
                 wal_shard_num: 4,
                 table_unit_meta_shard_num: 4,
                 ttl: self.ttl.map(Into::into),
+                write_batch_window: self.write_batch_window.into(),
                 ..Default::default()
             };
             let wal_runtimes = new_wal_runtimes(self.runtime);
@@ -1968,6 +1994,47 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_batch_writes_of_region() {
+        let runtime = new_runtime();
+        let table_kv = MemoryImpl::default();
+
+        runtime.block_on(async {
+            let namespace = NamespaceMocker::new(table_kv, runtime.clone())
+                .write_batch_window(Duration::from_millis(10))
+                .build();
+            // The logs of the tables in the same region are written in one batch.
+            let locations: Vec<_> = (1..=3)
+                .map(|table_id| WalLocation::new(DEFAULT_SHARD_ID as u64, table_id))
+                .collect();
+            let writes = locations
+                .iter()
+                .map(|location| write_test_payloads(&namespace, *location, 1000, 1004));
+            let max_seqs = futures::future::join_all(writes).await;
+
+            for (location, max_seq) in locations.into_iter().zip(max_seqs) {
+                let req = ReadRequest {
+                    location,
+                    start: manager::ReadBoundary::Min,
+                    end: manager::ReadBoundary::Max,
+                };
+                let mut iter = namespace
+                    .read_log(&ReadContext::default(), &req)
+                    .await
+                    .unwrap();
+                let mut seqs = Vec::new();
+                while let Some(entry) = iter.next_log_entry().unwrap() {
+                    assert_eq!(location.table_id, entry.table_id);
+                    seqs.push(entry.sequence);
+                }
+                assert_eq!(4, seqs.len());
+                assert_eq!(Some(&max_seq), seqs.last());
+            }
+
+            namespace.close().await.unwrap();
+        });
+    }
+
     async fn direct_read_logs_from_table<T: TableKv>(
         table_kv: &T,
         table_name: &str,
@@ -2003,9 +2070,11 @@ mod tests {
         let mut key_values = Vec::new();
         while iter.valid() {
             let decoded_key = log_encoding.decode_key(iter.key()).unwrap();
-            let mut raw_value = log_encoding.decode_value(iter.value()).unwrap();
+            let raw_value = log_encoding.decode_value(iter.value()).unwrap();
+            let mut raw_value = raw_value.as_ref();
             let ctx = PayloadDecodeContext {
                 table_id: region_id,
+                sequence: decoded_key.1,
             };
             let decoded_value = decoder.decode(&ctx, &mut raw_value).unwrap();
             key_values.push((decoded_key.1, decoded_value));
@@ -44,7 +44,9 @@ use crate::{
     kv_encoder::{CommonLogEncoding, CommonLogKey},
     log_batch::{LogEntry, LogWriteBatch},
     manager::{self, ReadContext, ReadRequest, SequenceNumber, SyncLogIterator, WalRuntimes},
-    table_kv_impl::{encoding, model::TableUnitEntry, namespace::BucketRef},
+    table_kv_impl::{
+        encoding, model::TableUnitEntry, namespace::BucketRef, write_batcher::WriteBatcher,
+    },
 };
 
 #[derive(Debug, Snafu)]
@@ -332,9 +334,12 @@ impl TableUnit {
         .context(RuntimeExec)?
     }
 
+    /// Write the log batch, the write may be batched with the ones of other
+    /// table units by the `write_batcher`.
     pub async fn write_log<T: TableKv>(
         &self,
         table_kv: &T,
+        write_batcher: Option<&WriteBatcher<T>>,
         bucket: &BucketRef,
         ctx: &manager::WriteContext,
         log_batch: &LogWriteBatch,
@@ -344,6 +349,7 @@ impl TableUnit {
             .write_log(
                 &self.runtimes.write_runtime,
                 table_kv,
+                write_batcher,
                 &self.state,
                 bucket,
                 ctx,
@@ -813,20 +819,23 @@ impl<T: TableKv> SyncLogIterator for TableLogIterator<T> {
             .decode_key(current_iter.key())
             .box_err()
             .context(manager::Decoding)?;
+        // To unblock pr#119, we use the following to simple resolve borrow-check error.
+        // detail info: https://github.com/apache/incubator-horaedb/issues/120
         let payload = self
             .log_encoding
             .decode_value(current_iter.value())
-            .box_err()
-            .context(manager::Encoding)?;
-
-        // To unblock pr#119, we use the following to simple resolve borrow-check error.
-        // detail info: https://github.com/apache/incubator-horaedb/issues/120
-        self.previous_value = payload.to_owned();
+            .map(|v| v.into_owned());
 
         // Step current iterator, if it becomes invalid, reset `current_iter` to None
-        // and advance `current_bucket_index`.
+        // and advance `current_bucket_index`. The iterator is stepped before the
+        // corrupted record is reported, so it can be skipped by the next call.
         self.step_current_iter().box_err().context(manager::Read)?;
 
+        self.previous_value = payload.box_err().context(manager::CorruptedRecord {
+            table_id: self.current_log_key.table_id,
+            sequence: self.current_log_key.sequence_num,
+        })?;
+
         let log_entry = LogEntry {
             table_id: self.current_log_key.table_id,
             sequence: self.current_log_key.sequence_num,
@@ -931,6 +940,7 @@ impl TableUnitWriter {
         &mut self,
         runtime: &Runtime,
         table_kv: &T,
+        write_batcher: Option<&WriteBatcher<T>>,
         table_unit_state: &TableUnitState,
         bucket: &BucketRef,
         ctx: &manager::WriteContext,
@@ -947,8 +957,16 @@ impl TableUnitWriter {
         let entries_num = log_batch.len() as u64;
         let region_id = log_batch.location.region_id;
         let table_id = log_batch.location.table_id;
-        let (wb, max_sequence_num) = {
-            let mut wb = T::WriteBatch::with_capacity(log_batch.len());
+        // Key-value pairs are collected instead of the write batch if the write
+        // is batched with others.
+        let (mut wb, mut pairs) = match write_batcher {
+            Some(_) => (
+                T::WriteBatch::default(),
+                Vec::with_capacity(log_batch.len()),
+            ),
+            None => (T::WriteBatch::with_capacity(log_batch.len()), Vec::new()),
+        };
+        let max_sequence_num = {
             let mut next_sequence_num = self.alloc_sequence_num(table_unit_state, entries_num)?;
             let mut key_buf = BytesMut::new();
 
@@ -959,14 +977,29 @@ impl TableUnitWriter {
                         &CommonLogKey::new(region_id, table_id, next_sequence_num),
                     )
                     .context(LogCodec)?;
-                wb.insert(&key_buf, &entry.payload);
+                if write_batcher.is_some() {
+                    pairs.push((key_buf.to_vec(), entry.payload.clone()));
+                } else {
+                    wb.insert(&key_buf, &entry.payload);
+                }
 
                 next_sequence_num += 1;
             }
 
-            (wb, next_sequence_num - 1)
+            next_sequence_num - 1
         };
 
+        if let Some(write_batcher) = write_batcher {
+            let table_name = bucket.wal_shard_table(region_id).to_string();
+            write_batcher
+                .submit(table_name, pairs)
+                .await
+                .box_err()
+                .context(WriteLog { region_id })?;
+
+            return Ok(max_sequence_num);
+        }
+
         let table_kv = table_kv.clone();
         let bucket = bucket.clone();
         runtime
@@ -0,0 +1,163 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Batch the writes of different tables in the same region.
+//!
+//! The logs of the tables in the same region are stored in the same wal shard
+//! table, but every write to the region is issued to the table kv separately.
+//! [WriteBatcher] groups the key-value pairs of the writes to the same wal
+//! shard table arriving in a small time window, and writes them in one batch.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use generic_error::{BoxError, GenericResult};
+use runtime::Runtime;
+use table_kv::{TableKv, WriteBatch, WriteContext};
+
+use crate::group_commit::{GroupBatcher, GroupCommitter};
+
+pub type WriteBatcher<T> = GroupBatcher<KvWriter<T>>;
+pub type WriteBatcherRef<T> = Arc<WriteBatcher<T>>;
+
+/// Write the key-value pairs of the writes to a wal shard table in one batch.
+pub struct KvWriter<T> {
+    table_kv: T,
+    runtime: Arc<Runtime>,
+}
+
+impl<T: TableKv> KvWriter<T> {
+    pub fn new(table_kv: T, runtime: Arc<Runtime>) -> Self {
+        Self { table_kv, runtime }
+    }
+}
+
+#[async_trait]
+impl<T: TableKv> GroupCommitter for KvWriter<T> {
+    type Item = Vec<(Vec<u8>, Vec<u8>)>;
+    type Key = String;
+    type Output = ();
+
+    fn size_of(pairs: &Self::Item) -> (usize, usize) {
+        let num_bytes = pairs.iter().map(|(k, v)| k.len() + v.len()).sum();
+        (pairs.len(), num_bytes)
+    }
+
+    async fn commit(&self, table_name: &String, writes: Vec<Self::Item>) -> GenericResult<Vec<()>> {
+        let num_writes = writes.len();
+        let num_pairs = writes.iter().map(Vec::len).sum();
+        let table_kv = self.table_kv.clone();
+        let table_name = table_name.clone();
+        self.runtime
+            .spawn_blocking(move || {
+                let mut wb = T::WriteBatch::with_capacity(num_pairs);
+                for (key, value) in writes.iter().flatten() {
+                    wb.insert(key, value);
+                }
+                table_kv.write(WriteContext::default(), &table_name, wb)
+            })
+            .await
+            .box_err()?
+            .box_err()?;
+
+        Ok(vec![(); num_writes])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use table_kv::{memory::MemoryImpl, KeyBoundary, ScanContext, ScanIter, ScanRequest};
+
+    use super::*;
+    use crate::group_commit::GroupLimits;
+
+    fn build_runtime() -> Arc<Runtime> {
+        Arc::new(
+            runtime::Builder::default()
+                .worker_threads(2)
+                .enable_all()
+                .build()
+                .unwrap(),
+        )
+    }
+
+    fn scan_all(table_kv: &MemoryImpl, table_name: &str) -> Vec<Vec<u8>> {
+        let req = ScanRequest {
+            start: KeyBoundary::MinIncluded,
+            end: KeyBoundary::MaxIncluded,
+            reverse: false,
+        };
+        let mut iter = table_kv
+            .scan(ScanContext::default(), table_name, req)
+            .unwrap();
+        let mut keys = Vec::new();
+        while iter.valid() {
+            keys.push(iter.key().to_vec());
+            iter.next().unwrap();
+        }
+        keys
+    }
+
+    #[test]
+    fn test_batch_writes() {
+        let runtime = build_runtime();
+        let table_kv = MemoryImpl::default();
+        for table_name in ["shard_0", "shard_1"] {
+            table_kv.create_table(table_name).unwrap();
+        }
+        // The window is long enough so the writes return only if the batch is
+        // written as it's full.
+        let limits = GroupLimits {
+            window: Duration::from_secs(3600),
+            max_entries: 3,
+            max_bytes: 1024,
+        };
+        let batcher = WriteBatcher::new(
+            KvWriter::new(table_kv.clone(), runtime.clone()),
+            limits,
+            runtime.clone(),
+        );
+
+        runtime.block_on(async {
+            let writes = [
+                ("shard_0", vec![1, 2]),
+                ("shard_1", vec![3, 4, 5]),
+                ("shard_0", vec![6]),
+            ];
+            let writes = writes.into_iter().map(|(table_name, keys)| {
+                let pairs = keys.into_iter().map(|k| (vec![k], vec![k])).collect();
+                batcher.submit(table_name.to_string(), pairs)
+            });
+            let writes = futures::future::try_join_all(writes);
+            tokio::time::timeout(Duration::from_secs(10), writes)
+                .await
+                .unwrap()
+                .unwrap();
+        });
+
+        assert_eq!(
+            scan_all(&table_kv, "shard_0"),
+            vec![vec![1], vec![2], vec![6]]
+        );
+        assert_eq!(
+            scan_all(&table_kv, "shard_1"),
+            vec![vec![3], vec![4], vec![5]]
+        );
+    }
+}
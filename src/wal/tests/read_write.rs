@@ -41,6 +41,7 @@ use wal::{
         BatchLogIteratorAdapter, ReadBoundary, ReadContext, ReadRequest, ScanRequest, WalLocation,
         WalManager, WalManagerRef, WalRuntimes, WriteContext,
     },
+    memory_impl::wal_manager::{MemoryImpl, MemoryStorage, MemoryStorageRef},
     message_queue_impl::{config::KafkaWalConfig, wal::MessageQueueImpl},
     rocksdb_impl::manager::RocksImpl,
     table_kv_impl::{model::NamespaceConfig, wal::WalNamespaceImpl},
@@ -77,6 +78,12 @@ fn test_local_storage_wal() {
     test_all(builder, false);
 }
 
+#[test]
+fn test_memory_wal() {
+    let builder = MemoryWalBuilder::default();
+    test_all(builder, false);
+}
+
 fn test_all<B: WalBuilder>(builder: B, is_distributed: bool) {
     test_simple_read_write_default_batch(builder.clone());
     test_simple_read_write_different_batch_size(builder.clone());
@@ -1003,6 +1010,30 @@ impl WalBuilder for LocalStorageWalBuilder {
     }
 }
 
+/// Builder sharing the storage between the built wals, so the logs can be
+/// read after reopening.
+#[derive(Default)]
+pub struct MemoryWalBuilder {
+    storage: MemoryStorageRef,
+}
+
+#[async_trait]
+impl WalBuilder for MemoryWalBuilder {
+    type Wal = MemoryImpl;
+
+    async fn build(&self, _data_path: &Path, _runtime: Arc<Runtime>) -> Arc<Self::Wal> {
+        Arc::new(MemoryImpl::new(self.storage.clone()))
+    }
+}
+
+impl Clone for MemoryWalBuilder {
+    fn clone(&self) -> Self {
+        Self {
+            storage: Arc::new(MemoryStorage::default()),
+        }
+    }
+}
+
 /// The environment for testing wal.
 pub struct TestEnv<B> {
     pub dir: TempDir,